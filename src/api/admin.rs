@@ -0,0 +1,194 @@
+//! Proxy administration endpoints under `/api/proxy/`, outside Ollama's API.
+
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+use crate::api::RequestContext;
+use crate::error::ProxyError;
+use crate::http::json_response;
+use crate::lmstudio::download::{cancel_lmstudio_download, fetch_lmstudio_download_status};
+use crate::model::{ModelResolver, clean_model_name};
+use crate::storage::{BlobStore, ModelPinStore, VirtualModelStore};
+
+/// `GET /api/proxy/cache/models`: the resolver's cached name → id mappings.
+pub async fn handle_model_cache_list(
+    model_resolver: Arc<ModelResolver>,
+) -> Result<axum::response::Response, ProxyError> {
+    let entries: Vec<_> = model_resolver
+        .cached_resolutions()
+        .into_iter()
+        .map(|(name, model_id)| json!({"name": name, "model_id": model_id}))
+        .collect();
+    Ok(json_response(&json!({
+        "count": entries.len(),
+        "entries": entries,
+    })))
+}
+
+/// `DELETE /api/proxy/cache/models`: forget every cached resolution and the
+/// cached model list, so renamed or re-downloaded models resolve afresh.
+pub async fn handle_model_cache_clear(
+    model_resolver: Arc<ModelResolver>,
+) -> Result<axum::response::Response, ProxyError> {
+    let cleared = model_resolver.cached_resolutions().len();
+    model_resolver.invalidate_all().await;
+    log::info!("model cache: cleared {} resolution(s)", cleared);
+    Ok(json_response(&json!({ "cleared": cleared })))
+}
+
+/// `GET /api/proxy/aliases`: every stored alias as written, sorted by name.
+/// `?name=` narrows the list to that alias (`:latest` optional).
+pub async fn handle_alias_list(
+    virtual_models: Arc<VirtualModelStore>,
+    name: Option<&str>,
+) -> Result<axum::response::Response, ProxyError> {
+    let wanted = name.map(clean_model_name);
+    let mut aliases: Vec<_> = virtual_models
+        .list()
+        .await
+        .into_iter()
+        .filter(|entry| wanted.is_none_or(|wanted| clean_model_name(&entry.name) == wanted))
+        .collect();
+    aliases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(json_response(&json!({
+        "count": aliases.len(),
+        "aliases": aliases,
+    })))
+}
+
+/// `GET /api/proxy/blobs`: every stored blob with its size and last use,
+/// sorted by digest, plus the store's total and `--blob-max-total-mb` limit
+/// in bytes. `referenced` blobs are never evicted.
+pub async fn handle_blob_list(
+    blob_store: Arc<BlobStore>,
+    virtual_models: Arc<VirtualModelStore>,
+) -> Result<axum::response::Response, ProxyError> {
+    let referenced = virtual_models.referenced_blobs().await;
+    let blobs = blob_store.list().await;
+    let total_bytes: u64 = blobs.iter().map(|blob| blob.size).sum();
+    let entries: Vec<_> = blobs
+        .into_iter()
+        .map(|blob| {
+            json!({
+                "digest": blob.digest,
+                "size": blob.size,
+                "last_access": blob.last_access,
+                "referenced": referenced.contains(&blob.digest),
+            })
+        })
+        .collect();
+    Ok(json_response(&json!({
+        "count": entries.len(),
+        "total_bytes": total_bytes,
+        "max_total_bytes": blob_store.max_total_bytes(),
+        "blobs": entries,
+    })))
+}
+
+/// `POST /api/proxy/pull/cancel`: stop the download `job_id`. A download this
+/// proxy is following is cancelled through its pull (which ends its stream
+/// and asks LM Studio to stop); any other job id is cancelled in LM Studio
+/// directly, answering with the status LM Studio reports afterwards.
+pub async fn handle_pull_cancel(
+    context: RequestContext<'_>,
+    body: Value,
+) -> Result<axum::response::Response, ProxyError> {
+    let job_id = body
+        .get("job_id")
+        .and_then(Value::as_str)
+        .filter(|job_id| !job_id.trim().is_empty())
+        .ok_or_else(|| ProxyError::bad_request("missing 'job_id' field"))?;
+
+    if let Some(cancelled) = context.pull_registry.cancel_job(job_id) {
+        return Ok(json_response(&cancelled));
+    }
+
+    cancel_lmstudio_download(context.client, context.lmstudio_url, job_id)
+        .await
+        .map_err(|e| match e.status_code {
+            404 => ProxyError::not_found(&format!("no download in progress for '{}'", job_id)),
+            _ => e,
+        })?;
+    log::info!("pull: cancelled untracked download {}", job_id);
+    let last_status = fetch_lmstudio_download_status(
+        context.client,
+        context.lmstudio_url,
+        job_id,
+        CancellationToken::new(),
+    )
+    .await
+    .ok()
+    .map(|status| status.to_chunk(""));
+    Ok(json_response(&json!({
+        "status": "cancelled",
+        "job_id": job_id,
+        "model": Value::Null,
+        "last_status": last_status,
+    })))
+}
+
+/// `GET /api/proxy/pins`: every pin, sorted by name.
+pub async fn handle_pin_list(
+    model_pins: Arc<ModelPinStore>,
+) -> Result<axum::response::Response, ProxyError> {
+    let pins = model_pins.list().await;
+    Ok(json_response(&json!({
+        "count": pins.len(),
+        "pins": pins,
+    })))
+}
+
+/// `POST /api/proxy/pins`: make `name` resolve to the LM Studio model
+/// `target`. The target must be a listed, visible model id (matched without
+/// case). An exact id match on `name` still wins over its pin.
+pub async fn handle_pin_set(
+    context: RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
+    body: Value,
+    cancellation_token: CancellationToken,
+) -> Result<axum::response::Response, ProxyError> {
+    let name = required_str(&body, "name")?;
+    let target = required_str(&body, "target")?;
+
+    let models = model_resolver
+        .get_all_models(context.client, cancellation_token)
+        .await?;
+    let Some(model) = models
+        .iter()
+        .filter(|m| context.model_filter.is_visible(&m.id))
+        .find(|m| m.id.eq_ignore_ascii_case(target))
+    else {
+        return Err(ProxyError::not_found(&format!(
+            "pin target '{}' is not an LM Studio model id",
+            target
+        )));
+    };
+
+    let pin = context.model_pins.set(name, &model.id).await?;
+    model_resolver.invalidate_all().await;
+    log::info!("pin: '{}' -> '{}'", pin.name, pin.target);
+    Ok(json_response(&pin))
+}
+
+/// `DELETE /api/proxy/pins`: drop the pin for `name`; 404 when there is none.
+pub async fn handle_pin_delete(
+    model_pins: Arc<ModelPinStore>,
+    model_resolver: Arc<ModelResolver>,
+    body: Value,
+) -> Result<axum::response::Response, ProxyError> {
+    let name = required_str(&body, "name")?;
+    let removed = model_pins.remove(name).await?;
+    model_resolver.invalidate_all().await;
+    log::info!("pin: removed '{}'", removed.name);
+    Ok(json_response(&removed))
+}
+
+fn required_str<'a>(body: &'a Value, field: &str) -> Result<&'a str, ProxyError> {
+    body.get(field)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ProxyError::bad_request(&format!("missing '{}' field", field)))
+}
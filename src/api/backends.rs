@@ -0,0 +1,108 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::model::ModelResolver;
+
+/// How long a backend that could not be reached is skipped before requests
+/// try it again.
+pub const BACKEND_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// One LM Studio server. Each has its own resolver, and so its own caches:
+/// model ids and name resolutions differ between machines.
+pub struct Backend {
+    pub url: String,
+    pub model_resolver: Arc<ModelResolver>,
+    down_until: Mutex<Option<Instant>>,
+}
+
+impl Backend {
+    pub fn new(url: String, model_resolver: Arc<ModelResolver>) -> Self {
+        Self {
+            url,
+            model_resolver,
+            down_until: Mutex::new(None),
+        }
+    }
+
+    /// Whether the breaker is open: the backend refused or timed out a
+    /// request less than a cooldown ago.
+    pub fn is_tripped(&self) -> bool {
+        self.down_until
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|until| Instant::now() < until)
+    }
+}
+
+/// `--lmstudio-url`, then `--lmstudio-fallback-url` when set, with a small
+/// circuit breaker per server so a primary that is down doesn't cost every
+/// request its connect timeout.
+///
+/// Exposed behind `Arc` so handlers share one instance across requests.
+pub struct BackendSelector {
+    backends: Vec<Backend>,
+    cooldown: Duration,
+}
+
+impl BackendSelector {
+    /// `backends` in preference order; the first is the primary.
+    pub fn new(backends: Vec<Backend>, cooldown: Duration) -> Arc<Self> {
+        assert!(!backends.is_empty(), "at least one LM Studio backend");
+        Arc::new(Self { backends, cooldown })
+    }
+
+    pub fn primary(&self) -> &Backend {
+        &self.backends[0]
+    }
+
+    pub fn get(&self, index: usize) -> &Backend {
+        &self.backends[index]
+    }
+
+    pub fn all(&self) -> &[Backend] {
+        &self.backends
+    }
+
+    pub fn has_fallback(&self) -> bool {
+        self.backends.len() > 1
+    }
+
+    /// Indexes in the order a request should try them: reachable backends
+    /// in preference order, then tripped ones, so a request still goes
+    /// somewhere when every breaker is open.
+    pub fn try_order(&self) -> Vec<usize> {
+        let (closed, open): (Vec<usize>, Vec<usize>) =
+            (0..self.backends.len()).partition(|&i| !self.backends[i].is_tripped());
+        closed.into_iter().chain(open).collect()
+    }
+
+    /// The backend a request starts on.
+    pub fn preferred(&self) -> usize {
+        self.try_order()[0]
+    }
+
+    /// Open `index`'s breaker for the cooldown.
+    pub fn trip(&self, index: usize) {
+        let backend = &self.backends[index];
+        *backend.down_until.lock().unwrap_or_else(|e| e.into_inner()) =
+            Some(Instant::now() + self.cooldown);
+    }
+
+    /// Drop every backend's cached resolutions and model list; the proxy's
+    /// own state (pins, aliases) changed for all of them.
+    pub async fn invalidate_resolutions(&self) {
+        for backend in &self.backends {
+            backend.model_resolver.invalidate_all().await;
+        }
+    }
+
+    /// Close `index`'s breaker after a request reached it.
+    pub fn reset(&self, index: usize) {
+        let backend = &self.backends[index];
+        *backend.down_until.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/handlers_backends.rs"]
+mod tests;
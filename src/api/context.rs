@@ -1,15 +1,54 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::model::LoadTracker;
-use crate::storage::{BlobStore, VirtualModelStore};
+use crate::api::{BackendSelector, LoadCoordinator, PullRegistry};
+use crate::model::{LoadTracker, ModelConcurrency, ModelFilter, RuntimeInfoCache};
+use crate::storage::{
+    BlobStore, EmbeddingCache, GenerateContextStore, ModelPinStore, VirtualModelStore,
+};
+use crate::streaming::StreamTimeouts;
 
 #[derive(Clone)]
 pub struct RequestContext<'a> {
     pub client: &'a reqwest::Client,
+    /// The LM Studio server this request is sent to.
     pub lmstudio_url: &'a str,
+    /// Every configured server, for failover and per-backend health.
+    pub backends: Arc<BackendSelector>,
     pub virtual_models: Arc<VirtualModelStore>,
+    /// `/api/proxy/pins`, written by the pin admin handlers.
+    pub model_pins: Arc<ModelPinStore>,
+    /// `--model-name-map`, keyed like pins; checked before any other lookup.
+    pub model_name_map: Arc<HashMap<String, String>>,
     pub blob_store: Arc<BlobStore>,
     pub load_tracker: Arc<LoadTracker>,
+    /// LM Studio's `model_info`/`runtime` blocks from served responses, for
+    /// `/api/show`.
+    pub runtime_info: Arc<RuntimeInfoCache>,
+    pub model_concurrency: Arc<ModelConcurrency>,
+    /// Merges concurrent load triggers for the same model.
+    pub load_coordinator: Arc<LoadCoordinator>,
+    /// Downloads started by `/api/pull` that are still running.
+    pub pull_registry: Arc<PullRegistry>,
+    /// `--model-allowlist`/`--model-blocklist`, applied to listings and resolution.
+    pub model_filter: Arc<ModelFilter>,
+    /// First-token and inter-chunk budgets for streamed responses.
+    pub stream_timeouts: StreamTimeouts,
+    /// Client headers allowlisted by `--forward-header`, sent on with the
+    /// chat/generate/embed request to LM Studio. Empty for other routes.
+    pub forward_headers: reqwest::header::HeaderMap,
+    /// Set when `--emulate-generate-context` is on.
+    pub generate_context: Option<Arc<GenerateContextStore>>,
+    /// Set when `--embedding-cache-size` is non-zero.
+    pub embedding_cache: Option<Arc<EmbeddingCache>>,
+    /// `--default-system-prompt`, used when neither the request nor a
+    /// virtual model supplies one.
+    pub default_system_prompt: Option<&'a str>,
+    /// `--expose-stats` or `?stats=1`: non-streaming chat/generate responses
+    /// carry LM Studio's `stats` block as `proxy_stats`.
+    pub expose_stats: bool,
+    /// `--ollama-model-ids`: passthrough `/v1/models` lists Ollama names.
+    pub ollama_model_ids: bool,
 }
 
 impl<'a> RequestContext<'a> {
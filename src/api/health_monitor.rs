@@ -0,0 +1,221 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::constants::LM_STUDIO_NATIVE_MODELS;
+use crate::error::ProxyError;
+use crate::model::ModelResolver;
+
+/// Upper bound on one probe, so a hung LM Studio can't stall the monitor.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// LM Studio's state as last seen by the monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendStatus {
+    /// No probe has finished yet.
+    Unknown,
+    Healthy,
+    /// Reachable, but the model list answered with a non-2xx status.
+    Unhealthy,
+    /// The probe could not connect or timed out.
+    Unreachable,
+}
+
+impl BackendStatus {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Healthy,
+            2 => Self::Unhealthy,
+            3 => Self::Unreachable,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::Unknown => 0,
+            Self::Healthy => 1,
+            Self::Unhealthy => 2,
+            Self::Unreachable => 3,
+        }
+    }
+
+    /// The `/health` `status` string for this state.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Healthy => "healthy",
+            Self::Unhealthy => "unhealthy",
+            Self::Unreachable => "unreachable",
+        }
+    }
+}
+
+/// Result of one probe of LM Studio's model list.
+#[derive(Debug, Clone, Default)]
+pub struct ProbeResult {
+    pub checked_at: Option<DateTime<Utc>>,
+    pub last_healthy_at: Option<DateTime<Utc>>,
+    pub http_status: Option<u16>,
+    pub model_count: usize,
+    pub response_time_ms: u128,
+    pub error: Option<String>,
+}
+
+/// Background view of LM Studio's availability (`--health-check-interval-seconds`).
+///
+/// A task probes `/api/v1/models` on a fixed interval and records the outcome
+/// here. While LM Studio is known unreachable, inference handlers fail fast
+/// with a 503 instead of each paying the connect timeout, and `/health`
+/// answers from the last probe. The status is an atomic so the fast path
+/// never takes a lock; the details behind it are only read by `/health` and
+/// error messages.
+pub struct HealthMonitor {
+    interval: Duration,
+    status: AtomicU8,
+    last: Mutex<ProbeResult>,
+}
+
+impl HealthMonitor {
+    pub fn new(interval: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            interval,
+            status: AtomicU8::new(BackendStatus::Unknown.as_u8()),
+            last: Mutex::new(ProbeResult::default()),
+        })
+    }
+
+    pub fn status(&self) -> BackendStatus {
+        BackendStatus::from_u8(self.status.load(Ordering::Acquire))
+    }
+
+    /// The last probe's details, or `None` before the first one finishes.
+    pub fn snapshot(&self) -> Option<(BackendStatus, ProbeResult)> {
+        let last = self.last.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        match self.status() {
+            BackendStatus::Unknown => None,
+            status => Some((status, last)),
+        }
+    }
+
+    /// Store a probe outcome. Returns `true` when it marks LM Studio coming
+    /// back after being seen down, so the caller can drop stale caches.
+    pub fn record(&self, status: BackendStatus, mut result: ProbeResult) -> bool {
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        result.last_healthy_at = match status {
+            BackendStatus::Healthy => result.checked_at,
+            _ => last.last_healthy_at,
+        };
+        *last = result;
+        let previous = BackendStatus::from_u8(self.status.swap(status.as_u8(), Ordering::AcqRel));
+        drop(last);
+
+        if previous != status {
+            match status {
+                BackendStatus::Healthy => log::info!("health monitor: LM Studio is reachable"),
+                _ => log::warn!("health monitor: LM Studio is {}", status.as_str()),
+            }
+        }
+        status == BackendStatus::Healthy
+            && matches!(
+                previous,
+                BackendStatus::Unhealthy | BackendStatus::Unreachable
+            )
+    }
+
+    /// `Err(503)` while the last probe could not reach LM Studio.
+    pub fn ensure_reachable(&self) -> Result<(), ProxyError> {
+        if self.status() != BackendStatus::Unreachable {
+            return Ok(());
+        }
+        let last_healthy = self
+            .last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_healthy_at;
+        let seen = match last_healthy {
+            Some(at) => format!("last seen healthy at {}", at.to_rfc3339()),
+            None => "not seen healthy since the proxy started".to_string(),
+        };
+        Err(ProxyError::lm_studio_unavailable(&format!(
+            "LM Studio is unreachable according to the health monitor ({})",
+            seen
+        )))
+    }
+
+    /// Probe LM Studio every interval until `shutdown` fires. When it comes
+    /// back after being down, the resolver's caches are dropped so models
+    /// loaded or downloaded meanwhile resolve right away.
+    pub async fn run(
+        self: Arc<Self>,
+        client: reqwest::Client,
+        lmstudio_url: String,
+        model_resolver: Arc<ModelResolver>,
+        shutdown: CancellationToken,
+    ) {
+        let url = format!("{}{}", lmstudio_url, LM_STUDIO_NATIVE_MODELS);
+        let timeout = self.interval.min(PROBE_TIMEOUT);
+        loop {
+            let (status, result) = probe(&client, &url, timeout).await;
+            if self.record(status, result) {
+                log::info!("health monitor: dropping model caches after LM Studio came back");
+                model_resolver.invalidate_all().await;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = shutdown.cancelled() => return,
+            }
+        }
+    }
+}
+
+async fn probe(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Duration,
+) -> (BackendStatus, ProbeResult) {
+    let start = Instant::now();
+    let outcome = client.get(url).timeout(timeout).send().await;
+    let mut result = ProbeResult {
+        checked_at: Some(Utc::now()),
+        ..ProbeResult::default()
+    };
+    let status = match outcome {
+        Ok(response) => {
+            let http_status = response.status();
+            result.http_status = Some(http_status.as_u16());
+            if http_status.is_success() {
+                if let Ok(models) = response.json::<Value>().await {
+                    result.model_count = count_listed_models(&models);
+                }
+                BackendStatus::Healthy
+            } else {
+                BackendStatus::Unhealthy
+            }
+        }
+        Err(e) => {
+            result.error = Some(e.to_string());
+            BackendStatus::Unreachable
+        }
+    };
+    result.response_time_ms = start.elapsed().as_millis();
+    (status, result)
+}
+
+/// Number of models in an LM Studio model-list response (native `models` or
+/// OpenAI-style `data`).
+pub fn count_listed_models(models_response: &Value) -> usize {
+    models_response
+        .get("models")
+        .or_else(|| models_response.get("data"))
+        .and_then(|d| d.as_array())
+        .map_or(0, Vec::len)
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/handlers_health_monitor.rs"]
+mod tests;
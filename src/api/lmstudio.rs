@@ -1,23 +1,37 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 
 use axum::body::Body;
 use axum::response::Response;
 use bytes::Bytes;
+use futures_util::{StreamExt, TryStreamExt};
 use http::{HeaderName, HeaderValue};
-use serde_json::Value;
+use serde_json::{Value, json};
 use tokio_util::sync::CancellationToken;
 
 use crate::api::RequestContext;
+use crate::api::ollama::resolution::resolve_model_target;
 use crate::api::retry::with_retry_and_cancellation;
-use crate::constants::{LOG_PREFIX_INFO, LOG_PREFIX_SUCCESS};
+use crate::constants::{
+    FAN_OUT_CONCURRENCY, LOG_PREFIX_INFO, LOG_PREFIX_SUCCESS, MAX_FAN_OUT_CANDIDATES,
+    MAX_JSON_BODY_SIZE_BYTES,
+};
 use crate::error::ProxyError;
 use crate::http::body::{parse_json_body_template, prepare_request_body};
-use crate::http::{build_forward_headers, client::CancellableRequest};
+use crate::http::client::{CancellableRequest, handle_json_response};
+use crate::http::{build_forward_headers, json_response};
+use crate::lmstudio::request::{lift_ollama_options, normalize_stop_field};
 use crate::logging::{LogConfig, format_duration, log_request, log_timed};
-use crate::model::ModelResolver;
-use crate::streaming::{handle_passthrough_streaming_response, is_streaming_request};
+use crate::model::naming::ollama_model_name;
+use crate::model::{ModelFilter, ModelResolver};
+use crate::storage::embedding_cache::EmbeddingLookup;
+use crate::storage::{EmbeddingCache, VirtualModelEntry};
+use crate::streaming::{
+    StreamTimeouts, handle_passthrough_streaming_response, is_streaming_request,
+};
 
+#[derive(Clone)]
 pub struct LmStudioPassthroughRequest {
     pub method: http::Method,
     pub endpoint: String,
@@ -26,6 +40,63 @@ pub struct LmStudioPassthroughRequest {
     pub query: Option<String>,
 }
 
+/// A probe request answered by the proxy itself instead of LM Studio.
+struct LocalShim {
+    method: http::Method,
+    /// Exact endpoint path, or `"*"` for any path.
+    path: &'static str,
+    respond: fn() -> Response,
+}
+
+/// Probes some OpenAI clients send through `/v1`; LM Studio rejects them as
+/// "Unexpected endpoint or method", so they are answered locally. Add a row
+/// to shim another path — anything not listed still passes through.
+const LOCAL_SHIMS: &[LocalShim] = &[
+    LocalShim {
+        method: http::Method::GET,
+        path: "/v1/api/version",
+        respond: shim_version,
+    },
+    LocalShim {
+        method: http::Method::GET,
+        path: "/v1/health",
+        respond: shim_health,
+    },
+    LocalShim {
+        method: http::Method::OPTIONS,
+        path: "*",
+        respond: shim_options,
+    },
+];
+
+fn find_local_shim(method: &http::Method, endpoint: &str) -> Option<&'static LocalShim> {
+    LOCAL_SHIMS
+        .iter()
+        .find(|shim| shim.method == *method && (shim.path == "*" || shim.path == endpoint))
+}
+
+/// Whether a passthrough request is answered by the proxy without asking
+/// LM Studio, so it works while LM Studio is down.
+pub fn is_answered_locally(method: &http::Method, endpoint: &str) -> bool {
+    find_local_shim(method, endpoint).is_some()
+}
+
+fn shim_version() -> Response {
+    json_response(&json!({ "version": crate::VERSION }))
+}
+
+fn shim_health() -> Response {
+    json_response(&json!({ "status": "ok" }))
+}
+
+fn shim_options() -> Response {
+    Response::builder()
+        .status(http::StatusCode::NO_CONTENT)
+        .header(http::header::ALLOW, "GET, POST, PUT, DELETE, HEAD, OPTIONS")
+        .body(Body::empty())
+        .unwrap_or_default()
+}
+
 pub async fn handle_lmstudio_passthrough(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
@@ -42,6 +113,11 @@ pub async fn handle_lmstudio_passthrough(
         query,
     } = request;
 
+    if let Some(shim) = find_local_shim(&method, &endpoint) {
+        log::debug!("passthrough shim: {} {} answered locally", method, endpoint);
+        return Ok((shim.respond)());
+    }
+
     if LogConfig::get().debug_enabled {
         log::debug!("passthrough request: {} {}", method, endpoint);
         if let Ok(body_str) = std::str::from_utf8(&body) {
@@ -49,13 +125,50 @@ pub async fn handle_lmstudio_passthrough(
         }
     }
 
-    let json_body_template = parse_json_body_template(&headers, &body)?;
+    // Exotic payloads that merely look like JSON are forwarded untouched;
+    // LM Studio is the authority on whether the body is acceptable.
+    let mut json_body_template = parse_json_body_template(&headers, &body).unwrap_or_else(|e| {
+        log::debug!(
+            "passthrough body not rewritable, forwarding raw: {}",
+            e.message
+        );
+        None
+    });
+    if is_choices_endpoint(&endpoint)
+        && let Some(body_json) = json_body_template.as_mut().and_then(Value::as_object_mut)
+    {
+        lift_ollama_options(body_json);
+        normalize_stop_field(body_json);
+    }
     let original_model_name = json_body_template
         .as_ref()
         .and_then(|value: &Value| value.get("model"))
         .and_then(|value: &Value| value.as_str())
         .map(|s| s.to_string());
 
+    // Virtual aliases first, then the resolver — the same lookup the Ollama
+    // handlers use, so `/v1/embeddings` and `/v1/completions` accept the
+    // names `/api/tags` advertises. Resolved once, so the load retry below
+    // acts on the model the request runs on.
+    let resolved_model_name = match original_model_name.as_deref() {
+        Some(model_name) => Some(
+            resolve_model_target(
+                &context,
+                &model_resolver,
+                model_name,
+                cancellation_token.clone(),
+            )
+            .await?
+            .0,
+        ),
+        None => None,
+    };
+    if let Some(resolved_model) = &resolved_model_name
+        && let Some(body_json) = json_body_template.as_mut().and_then(Value::as_object_mut)
+    {
+        body_json.insert("model".to_string(), Value::String(resolved_model.clone()));
+    }
+
     let operation = {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
@@ -67,6 +180,7 @@ pub async fn handle_lmstudio_passthrough(
         let json_template_clone = json_body_template.clone();
         let cancellation_clone = cancellation_token.clone();
         let original_model_name_clone = original_model_name.clone();
+        let resolved_model_name_clone = resolved_model_name.clone();
 
         move || {
             let context = context.clone();
@@ -79,23 +193,15 @@ pub async fn handle_lmstudio_passthrough(
             let json_template = json_template_clone.clone();
             let cancellation_token = cancellation_clone.clone();
             let original_model_name = original_model_name_clone.clone();
+            let resolved_model_name = resolved_model_name_clone.clone();
 
             async move {
-                let mut current_body = json_template.clone();
-                let mut resolved_model_name: Option<String> = None;
-
-                if let Some(ref mut body_json) = current_body
-                    && let Some(model_name) =
-                        body_json.get("model").and_then(|m: &Value| m.as_str())
-                {
-                    let resolved_model = model_resolver
-                        .resolve_model_name(model_name, context.client, cancellation_token.clone())
-                        .await?;
-                    resolved_model_name = Some(resolved_model.clone());
-                    if let Some(obj) = body_json.as_object_mut() {
-                        obj.insert("model".to_string(), Value::String(resolved_model));
+                let permit = match &resolved_model_name {
+                    Some(resolved_model) => {
+                        Some(context.model_concurrency.acquire(resolved_model).await?)
                     }
-                }
+                    None => None,
+                };
 
                 let final_endpoint_url = context.append_query_params(
                     determine_passthrough_endpoint_url(
@@ -110,8 +216,11 @@ pub async fn handle_lmstudio_passthrough(
                     .as_deref()
                     .or(original_model_name.as_deref());
                 log_request(method.as_str(), &final_endpoint_url, log_model);
+                if let Some(model) = original_model_name.as_deref() {
+                    crate::telemetry::record_model(model);
+                }
 
-                if let Some(body_json) = current_body {
+                let result = if let Some(body_json) = json_template {
                     forward_json_body_request(ForwardJsonRequest {
                         client: context.client,
                         method,
@@ -122,6 +231,8 @@ pub async fn handle_lmstudio_passthrough(
                         endpoint: &endpoint,
                         original_model_name: original_model_name.as_deref(),
                         cancellation_token,
+                        stream_timeouts: context.stream_timeouts,
+                        embedding_cache: context.embedding_cache.clone(),
                     })
                     .await
                 } else {
@@ -134,18 +245,24 @@ pub async fn handle_lmstudio_passthrough(
                         cancellation_token,
                     )
                     .await
+                };
+                match permit {
+                    Some(permit) => result.map(|r| permit.attach(r)),
+                    None => result,
                 }
             }
         }
     };
 
-    let result = match original_model_name.as_deref() {
+    let is_listing = method == http::Method::GET && is_model_listing_endpoint(&endpoint);
+
+    let result = match resolved_model_name.as_deref() {
         Some(model) => {
             with_retry_and_cancellation(
                 &context,
                 model,
                 load_timeout_seconds,
-                operation,
+                |_| operation(),
                 cancellation_token,
             )
             .await?
@@ -156,10 +273,134 @@ pub async fn handle_lmstudio_passthrough(
         }
     };
 
+    let openai_listing = is_listing && is_openai_model_listing(&endpoint);
+    let aliases = if openai_listing {
+        context.virtual_models.list_resolved().await
+    } else {
+        Vec::new()
+    };
+    let adds_catalog = openai_listing && (context.ollama_model_ids || !aliases.is_empty());
+    let result = if adds_catalog || (is_listing && context.model_filter.is_active()) {
+        edit_model_listing(result, |listing| {
+            filter_model_listing(listing, &context.model_filter);
+            if adds_catalog {
+                add_ollama_catalog(listing, &aliases, context.ollama_model_ids);
+            }
+        })
+        .await?
+    } else {
+        result
+    };
+
     log_timed(LOG_PREFIX_SUCCESS, "LM Studio passthrough", start_time);
     Ok(result)
 }
 
+/// LM Studio's model-list endpoints, which `--model-allowlist`/`--model-blocklist`
+/// trim so OpenAI and native clients see the same models as `/api/tags`.
+fn is_model_listing_endpoint(endpoint: &str) -> bool {
+    matches!(
+        endpoint.trim_end_matches('/'),
+        "/v1/models" | "/api/v0/models" | "/api/v1/models"
+    )
+}
+
+/// The OpenAI-style listing, which gets the proxy's catalog on top of
+/// LM Studio's (see [`add_ollama_catalog`]).
+fn is_openai_model_listing(endpoint: &str) -> bool {
+    endpoint.trim_end_matches('/') == "/v1/models"
+}
+
+/// Apply `edit` to a successful JSON model listing. Non-JSON or error bodies
+/// are returned untouched.
+async fn edit_model_listing(
+    response: Response,
+    edit: impl FnOnce(&mut Value),
+) -> Result<Response, ProxyError> {
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_JSON_BODY_SIZE_BYTES as usize)
+        .await
+        .map_err(|e| {
+            ProxyError::bad_gateway(&format!("failed to read LM Studio model list: {}", e))
+        })?;
+    let Ok(mut listing) = serde_json::from_slice::<Value>(&bytes) else {
+        return Ok(Response::from_parts(parts, Body::from(bytes)));
+    };
+
+    edit(&mut listing);
+
+    let edited = serde_json::to_vec(&listing).map_err(|e| {
+        ProxyError::internal_server_error(&format!("failed to encode model list: {}", e))
+    })?;
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(parts, Body::from(edited)))
+}
+
+/// Drop hidden models from a listing: OpenAI-style `data[].id` (`/v1/models`,
+/// `/api/v0/models`) or native `models[].key` (`/api/v1/models`).
+fn filter_model_listing(listing: &mut Value, filter: &ModelFilter) {
+    for (field, id_key) in [("data", "id"), ("models", "key")] {
+        if let Some(entries) = listing.get_mut(field).and_then(Value::as_array_mut) {
+            entries.retain(|entry| {
+                entry
+                    .get(id_key)
+                    .and_then(Value::as_str)
+                    .is_none_or(|id| filter.is_visible(id))
+            });
+        }
+    }
+}
+
+/// `/v1/models` as `/api/tags` lists it: with `--ollama-model-ids` each id is
+/// renamed to its Ollama name, and every virtual alias is appended by name
+/// (after the models, sorted), copying its target's entry when that is
+/// listed. A name already in the listing is not repeated.
+fn add_ollama_catalog(listing: &mut Value, aliases: &[VirtualModelEntry], ollama_ids: bool) {
+    let Some(entries) = listing.get_mut("data").and_then(Value::as_array_mut) else {
+        return;
+    };
+    let targets: HashMap<String, Value> = entries
+        .iter()
+        .filter_map(|entry| Some((entry.get("id")?.as_str()?.to_string(), entry.clone())))
+        .collect();
+
+    if ollama_ids {
+        for entry in entries.iter_mut() {
+            let renamed = entry
+                .get("id")
+                .and_then(Value::as_str)
+                .map(ollama_model_name);
+            if let (Some(renamed), Some(obj)) = (renamed, entry.as_object_mut()) {
+                obj.insert("id".to_string(), json!(renamed));
+            }
+        }
+    }
+
+    let mut listed: HashSet<String> = entries
+        .iter()
+        .filter_map(|entry| entry.get("id").and_then(Value::as_str).map(str::to_string))
+        .collect();
+    let mut aliases: Vec<&VirtualModelEntry> = aliases.iter().collect();
+    aliases.sort_by(|a, b| a.name.cmp(&b.name));
+    for alias in aliases {
+        if !listed.insert(alias.name.clone()) {
+            continue;
+        }
+        let mut entry = targets
+            .get(&alias.target_model_id)
+            .filter(|target| target.is_object())
+            .cloned()
+            .unwrap_or_else(|| json!({ "object": "model", "owned_by": "organization_owner" }));
+        if let Some(obj) = entry.as_object_mut() {
+            obj.insert("id".to_string(), json!(alias.name));
+        }
+        entries.push(entry);
+    }
+}
+
 struct ForwardJsonRequest<'a> {
     client: &'a reqwest::Client,
     method: http::Method,
@@ -170,6 +411,8 @@ struct ForwardJsonRequest<'a> {
     endpoint: &'a str,
     original_model_name: Option<&'a str>,
     cancellation_token: CancellationToken,
+    stream_timeouts: StreamTimeouts,
+    embedding_cache: Option<Arc<EmbeddingCache>>,
 }
 
 async fn forward_json_body_request(
@@ -185,8 +428,50 @@ async fn forward_json_body_request(
         endpoint,
         original_model_name,
         cancellation_token,
+        stream_timeouts,
+        embedding_cache,
     } = req;
     let is_streaming = is_streaming_request(&body_json);
+
+    // LM Studio returns a single choice whatever `n` says; run one request
+    // per candidate instead.
+    if method == http::Method::POST
+        && is_choices_endpoint(endpoint)
+        && let Some(candidates) = requested_candidates(&body_json)?
+    {
+        if is_streaming {
+            return Err(ProxyError::bad_request(
+                "n > 1 cannot be combined with stream: true; request the candidates without streaming",
+            ));
+        }
+        return fan_out_candidates(FanOutRequest {
+            client,
+            endpoint_url,
+            headers,
+            body_json,
+            candidates,
+            cancellation_token,
+        })
+        .await;
+    }
+
+    if method == http::Method::POST
+        && is_embeddings_endpoint(endpoint)
+        && let Some(cache) = embedding_cache
+        && let Some(lookup) = lookup_cached_embeddings(&cache, &body_json).await
+    {
+        return forward_uncached_embeddings(
+            client,
+            endpoint_url,
+            headers,
+            body_json,
+            &cache,
+            lookup,
+            cancellation_token,
+        )
+        .await;
+    }
+
     let prepared_body = prepare_request_body(Some(body_json), body_bytes)?;
 
     let forward_headers = build_forward_headers(headers, prepared_body.is_json);
@@ -210,7 +495,191 @@ async fn forward_json_body_request(
         );
     }
 
-    route_response(response, is_streaming, cancellation_token).await
+    route_response(response, is_streaming, cancellation_token, stream_timeouts).await
+}
+
+/// OpenAI endpoints whose responses carry a `choices` array.
+fn is_choices_endpoint(endpoint: &str) -> bool {
+    matches!(
+        endpoint.trim_end_matches('/'),
+        "/v1/chat/completions"
+            | "/api/v0/chat/completions"
+            | "/v1/completions"
+            | "/api/v0/completions"
+    )
+}
+
+/// The `n` of an OpenAI body when it asks for more than one candidate.
+/// Anything that is not a positive integer is left for LM Studio to judge.
+fn requested_candidates(body: &Value) -> Result<Option<u64>, ProxyError> {
+    match body.get("n").and_then(Value::as_u64) {
+        Some(n) if n > MAX_FAN_OUT_CANDIDATES => Err(ProxyError::bad_request(&format!(
+            "n = {} exceeds the proxy's limit of {} candidates per request",
+            n, MAX_FAN_OUT_CANDIDATES
+        ))),
+        Some(n) if n > 1 => Ok(Some(n)),
+        _ => Ok(None),
+    }
+}
+
+struct FanOutRequest<'a> {
+    client: &'a reqwest::Client,
+    endpoint_url: &'a str,
+    headers: &'a http::HeaderMap,
+    body_json: Value,
+    candidates: u64,
+    cancellation_token: CancellationToken,
+}
+
+/// Send `candidates` single-choice copies of the request, at most
+/// `FAN_OUT_CONCURRENCY` at a time, and merge the answers. `best_of` is
+/// dropped: LM Studio cannot rank candidates, so `n` of them are returned.
+/// The first failing candidate fails the whole request.
+async fn fan_out_candidates(req: FanOutRequest<'_>) -> Result<Response, ProxyError> {
+    let FanOutRequest {
+        client,
+        endpoint_url,
+        headers,
+        mut body_json,
+        candidates,
+        cancellation_token,
+    } = req;
+    if let Some(obj) = body_json.as_object_mut() {
+        obj.remove("n");
+        obj.remove("best_of");
+    }
+    let prepared_body = prepare_request_body(Some(body_json), &[])?;
+    let forward_headers = build_forward_headers(headers, prepared_body.is_json);
+    log::debug!("passthrough: fanning out {} candidates", candidates);
+
+    let responses: Vec<Value> = futures_util::stream::iter(0..candidates)
+        .map(|_| {
+            let request = CancellableRequest::new(client, cancellation_token.clone());
+            let forward_headers = forward_headers.clone();
+            let body = prepared_body.bytes.clone();
+            let token = cancellation_token.clone();
+            async move {
+                let response = request
+                    .make_raw_request(http::Method::POST, endpoint_url, forward_headers, body)
+                    .await?;
+                handle_json_response(response, token).await
+            }
+        })
+        .buffered(FAN_OUT_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    Ok(json_response(&merge_candidates(responses)))
+}
+
+/// One response holding every candidate's choices, re-indexed in order, with
+/// `usage` summed. Other fields come from the first candidate.
+fn merge_candidates(responses: Vec<Value>) -> Value {
+    let mut responses = responses.into_iter();
+    let Some(mut merged) = responses.next() else {
+        return Value::Null;
+    };
+    let mut choices = take_choices(&mut merged);
+    let mut usage = merged.get("usage").cloned();
+    for mut response in responses {
+        choices.extend(take_choices(&mut response));
+        match (&mut usage, response.get("usage")) {
+            (Some(total), Some(more)) => add_usage(total, more),
+            (None, Some(more)) => usage = Some(more.clone()),
+            _ => {}
+        }
+    }
+    for (index, choice) in choices.iter_mut().enumerate() {
+        if let Some(obj) = choice.as_object_mut() {
+            obj.insert("index".to_string(), json!(index));
+        }
+    }
+    if let Some(obj) = merged.as_object_mut() {
+        obj.insert("choices".to_string(), Value::Array(choices));
+        if let Some(usage) = usage {
+            obj.insert("usage".to_string(), usage);
+        }
+    }
+    merged
+}
+
+fn take_choices(response: &mut Value) -> Vec<Value> {
+    match response.get_mut("choices").map(Value::take) {
+        Some(Value::Array(choices)) => choices,
+        _ => Vec::new(),
+    }
+}
+
+/// Add every numeric field of `more` into `total`, descending into nested
+/// objects such as `completion_tokens_details`.
+fn add_usage(total: &mut Value, more: &Value) {
+    let (Some(total), Some(more)) = (total.as_object_mut(), more.as_object()) else {
+        return;
+    };
+    for (key, value) in more {
+        let Some(current) = total.get_mut(key) else {
+            total.insert(key.clone(), value.clone());
+            continue;
+        };
+        match value {
+            Value::Object(_) => add_usage(current, value),
+            Value::Number(n) => {
+                *current = match (current.as_u64(), n.as_u64()) {
+                    (Some(a), Some(b)) => json!(a + b),
+                    _ => json!(current.as_f64().unwrap_or(0.0) + n.as_f64().unwrap_or(0.0)),
+                };
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_embeddings_endpoint(endpoint: &str) -> bool {
+    matches!(
+        endpoint.trim_end_matches('/'),
+        "/v1/embeddings" | "/api/v0/embeddings"
+    )
+}
+
+/// Cache lookup for an OpenAI embeddings body whose `model` is already the
+/// resolved id. Base64 `encoding_format` responses are never cached.
+async fn lookup_cached_embeddings(cache: &EmbeddingCache, body: &Value) -> Option<EmbeddingLookup> {
+    let format = body.get("encoding_format").and_then(Value::as_str);
+    if format.is_some_and(|f| f != "float") {
+        return None;
+    }
+    let model = body.get("model").and_then(Value::as_str)?;
+    let dimensions = body.get("dimensions").and_then(Value::as_u64);
+    cache.lookup(model, dimensions, body.get("input")?).await
+}
+
+/// Send LM Studio only the inputs the embedding cache has no vector for,
+/// then answer with cached and fresh vectors merged in input order.
+async fn forward_uncached_embeddings(
+    client: &reqwest::Client,
+    endpoint_url: &str,
+    headers: &http::HeaderMap,
+    mut body_json: Value,
+    cache: &EmbeddingCache,
+    lookup: EmbeddingLookup,
+    cancellation_token: CancellationToken,
+) -> Result<Response, ProxyError> {
+    let mut upstream = None;
+    if let Some(input) = lookup.missing_input() {
+        body_json["input"] = input;
+        let prepared_body = prepare_request_body(Some(body_json), &[])?;
+        let forward_headers = build_forward_headers(headers, prepared_body.is_json);
+        let response = CancellableRequest::new(client, cancellation_token.clone())
+            .make_raw_request(
+                http::Method::POST,
+                endpoint_url,
+                forward_headers,
+                prepared_body.bytes,
+            )
+            .await?;
+        upstream = Some(handle_json_response(response, cancellation_token).await?);
+    }
+    Ok(json_response(&cache.complete(lookup, upstream).await?))
 }
 
 async fn forward_raw_body_request(
@@ -236,12 +705,13 @@ async fn route_response(
     response: reqwest::Response,
     is_streaming: bool,
     cancellation_token: CancellationToken,
+    stream_timeouts: StreamTimeouts,
 ) -> Result<axum::response::Response, ProxyError> {
     if is_streaming {
         if LogConfig::get().debug_enabled {
             log::debug!("passthrough response: (streaming)");
         }
-        handle_passthrough_streaming_response(response, cancellation_token, 60).await
+        handle_passthrough_streaming_response(response, cancellation_token, stream_timeouts).await
     } else {
         route_non_streaming_response(response, cancellation_token).await
     }
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::ProxyError;
+
+type LoadOutcome = Option<Result<bool, ProxyError>>;
+
+/// Merges concurrent load triggers for the same model into one.
+///
+/// The first request for a cold model runs the trigger; requests arriving
+/// while it is in flight wait for its outcome instead of sending their own
+/// load to LM Studio, and all of them see the same result or error. Waiting
+/// is bounded by `--load-timeout-seconds` (zero waits without a bound).
+///
+/// Exposed behind `Arc` so handlers share one instance across requests.
+pub struct LoadCoordinator {
+    max_wait: Option<Duration>,
+    in_flight: Mutex<HashMap<String, watch::Receiver<LoadOutcome>>>,
+}
+
+/// Removes the leader's entry however its load ends, including when the
+/// leading request is dropped mid-load; waiters then see a closed channel.
+struct InFlightGuard<'a> {
+    in_flight: &'a Mutex<HashMap<String, watch::Receiver<LoadOutcome>>>,
+    key: &'a str,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(self.key);
+    }
+}
+
+enum Role {
+    Leader(watch::Sender<LoadOutcome>),
+    Follower(watch::Receiver<LoadOutcome>),
+}
+
+impl LoadCoordinator {
+    pub fn new(max_wait: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            max_wait: (!max_wait.is_zero()).then_some(max_wait),
+            in_flight: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Number of models with a load currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Run `load` for `key`, or join the load already running for it.
+    ///
+    /// A leader whose own request was cancelled does not hand that
+    /// cancellation to its waiters: one of them takes over and loads instead.
+    pub async fn run<F, Fut>(
+        &self,
+        key: &str,
+        cancellation_token: &CancellationToken,
+        load: F,
+    ) -> Result<bool, ProxyError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<bool, ProxyError>>,
+    {
+        let mut load = Some(load);
+        loop {
+            let role = {
+                let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+                match in_flight.get(key) {
+                    Some(rx) => Role::Follower(rx.clone()),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        in_flight.insert(key.to_string(), rx);
+                        Role::Leader(tx)
+                    }
+                }
+            };
+
+            match role {
+                Role::Leader(tx) => {
+                    let _guard = InFlightGuard {
+                        in_flight: &self.in_flight,
+                        key,
+                    };
+                    // A follower only loops back here when the leader left
+                    // without an outcome, so it still holds its own `load`.
+                    let Some(load) = load.take() else {
+                        return Err(ProxyError::internal_server_error(&format!(
+                            "model load for '{}' was led twice by one request",
+                            key
+                        )));
+                    };
+                    let result = load().await;
+                    if !matches!(&result, Err(e) if e.is_cancelled()) {
+                        tx.send_replace(Some(result.clone()));
+                    }
+                    return result;
+                }
+                Role::Follower(mut rx) => {
+                    log::debug!("model load: joining in-flight load for '{}'", key);
+                    let outcome = async {
+                        let waited = rx.wait_for(Option::is_some).await;
+                        waited.ok().and_then(|outcome| outcome.clone())
+                    };
+                    let outcome = tokio::select! {
+                        outcome = self.bounded(outcome) => outcome,
+                        _ = cancellation_token.cancelled() => {
+                            return Err(ProxyError::request_cancelled());
+                        }
+                    };
+                    match outcome {
+                        Some(Some(result)) => return result,
+                        // The leader went away without an outcome; take over.
+                        Some(None) => continue,
+                        None => {
+                            return Err(ProxyError::new(
+                                format!(
+                                    "timed out after {}s waiting for '{}' to load (--load-timeout-seconds)",
+                                    self.max_wait.map_or(0, |d| d.as_secs()),
+                                    key
+                                ),
+                                504,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// `None` when `max_wait` elapses first.
+    async fn bounded<T>(&self, fut: impl Future<Output = T>) -> Option<T> {
+        match self.max_wait {
+            Some(max_wait) => tokio::time::timeout(max_wait, fut).await.ok(),
+            None => Some(fut.await),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/handlers_load_coordinator.rs"]
+mod tests;
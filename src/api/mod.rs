@@ -1,9 +1,20 @@
+pub mod admin;
+pub mod backends;
 pub mod context;
+pub mod health_monitor;
 pub mod lmstudio;
+pub mod load_coordinator;
 pub mod ollama;
 pub mod pipeline;
+pub mod pull_registry;
 pub mod response;
 pub mod retry;
 pub mod web;
 
+pub use backends::{Backend, BackendSelector};
 pub use context::RequestContext;
+pub use health_monitor::HealthMonitor;
+pub use load_coordinator::LoadCoordinator;
+pub use pull_registry::{
+    PullAttachment, PullFollower, PullJob, PullLead, PullRegistry, PullSlot, SharedPullStatus,
+};
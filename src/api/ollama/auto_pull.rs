@@ -0,0 +1,213 @@
+//! `--auto-pull-missing`: download a model LM Studio does not have when a
+//! chat or generate request names it, then serve the request.
+//!
+//! Only names that look like catalog identifiers are pulled (`owner/repo`, or
+//! a `--auto-pull-pattern` glob), so a typo of a local model still 404s.
+//! Streaming requests get Ollama pull progress chunks ahead of the response;
+//! non-streaming ones wait up to `--auto-pull-wait-seconds`.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::api::{PullRegistry, RequestContext};
+use crate::error::ProxyError;
+use crate::lmstudio::download::{
+    LmStudioDownloadStatus, initiate_lmstudio_download, stream_download_status_updates,
+    wait_for_download_completion,
+};
+use crate::logging::spawn_with_request_id;
+use crate::model::ModelResolver;
+use crate::model::filter::glob_match;
+use crate::streaming::create_ndjson_stream_response;
+
+use super::resolution::resolve_model_target;
+use super::status_stream::send_status_error_chunk;
+
+/// Whether a name LM Studio does not know should be downloaded: it names a
+/// catalog repo (`owner/repo`) or matches one of `patterns`.
+pub fn is_auto_pull_candidate(model: &str, patterns: &[String]) -> bool {
+    let lowered = model.to_lowercase();
+    model.contains('/')
+        || patterns
+            .iter()
+            .any(|pattern| glob_match(&pattern.trim().to_lowercase(), &lowered))
+}
+
+/// Start downloading `requested_model` when it does not resolve and is an
+/// auto-pull candidate. `None` means serve the request as usual: the model
+/// is present, finished downloading at once, or is not eligible (the
+/// handler then reports the resolution error itself).
+pub async fn start_missing_model_pull(
+    context: &RequestContext<'_>,
+    model_resolver: &Arc<ModelResolver>,
+    requested_model: &str,
+    patterns: &[String],
+    cancellation_token: CancellationToken,
+) -> Result<Option<LmStudioDownloadStatus>, ProxyError> {
+    match resolve_model_target(
+        context,
+        model_resolver,
+        requested_model,
+        cancellation_token.clone(),
+    )
+    .await
+    {
+        Err(e) if e.status_code == 404 && is_auto_pull_candidate(requested_model, patterns) => {}
+        _ => return Ok(None),
+    }
+
+    log::info!(
+        "auto-pull: '{}' is not in LM Studio, downloading it",
+        requested_model
+    );
+    let status = initiate_lmstudio_download(
+        context.client,
+        context.lmstudio_url,
+        requested_model,
+        None,
+        cancellation_token,
+    )
+    .await?;
+    if status.is_terminal() {
+        status.into_final_response(requested_model)?;
+        model_resolver.invalidate_all().await;
+        return Ok(None);
+    }
+    Ok(Some(status))
+}
+
+/// A download started by [`start_missing_model_pull`] and what is needed to
+/// follow it to the end.
+pub struct AutoPull {
+    pub client: reqwest::Client,
+    pub base_url: String,
+    pub pull_registry: Arc<PullRegistry>,
+    pub model_resolver: Arc<ModelResolver>,
+    pub model: String,
+    pub status: LmStudioDownloadStatus,
+    pub stream: bool,
+    /// How long a non-streaming request waits for the download.
+    pub wait: Duration,
+    pub cancellation_token: CancellationToken,
+}
+
+/// Follow `pull` to completion, then run `proceed` (the chat or generate
+/// handler).
+///
+/// Streaming: pull progress chunks, then `proceed`'s NDJSON body, on one
+/// stream; closing it cancels the download like aborting `/api/pull`.
+/// Non-streaming: waits up to `pull.wait`, else a 503 naming the job id.
+/// The download keeps going in that case and can be cancelled with
+/// `DELETE /api/pull`.
+pub async fn serve_after_pull<F, Fut>(
+    pull: AutoPull,
+    proceed: F,
+) -> Result<axum::response::Response, ProxyError>
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<axum::response::Response, ProxyError>> + Send + 'static,
+{
+    let AutoPull {
+        client,
+        base_url,
+        pull_registry,
+        model_resolver,
+        model,
+        status,
+        stream,
+        wait,
+        cancellation_token,
+    } = pull;
+    let job_id = status.job_id()?.to_string();
+    let job = pull_registry.track(
+        Some(&job_id),
+        &model,
+        cancellation_token.child_token(),
+        client.clone(),
+        base_url.clone(),
+    );
+
+    if !stream {
+        let (done_tx, done_rx) = oneshot::channel();
+        let model_for_wait = model.clone();
+        spawn_with_request_id(async move {
+            let result = match wait_for_download_completion(&client, &base_url, status, job).await {
+                Ok(status) => status.into_final_response(&model_for_wait).map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if result.is_ok() {
+                model_resolver.invalidate_all().await;
+            }
+            let _ = done_tx.send(result);
+        });
+        return match tokio::time::timeout(wait, done_rx).await {
+            Ok(Ok(Ok(()))) => proceed().await,
+            Ok(Ok(Err(e))) => Err(e),
+            Ok(Err(_)) => Err(ProxyError::internal_server_error(
+                "auto-pull: download watcher stopped",
+            )),
+            Err(_) => Err(ProxyError::new(
+                format!(
+                    "model '{}' is still downloading (job_id {}); retry once the pull completes",
+                    model, job_id
+                ),
+                503,
+            )),
+        };
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    spawn_with_request_id(async move {
+        match stream_download_status_updates(
+            client,
+            base_url,
+            status,
+            model.clone(),
+            job,
+            None,
+            tx.clone(),
+        )
+        .await
+        {
+            Ok(()) => {
+                model_resolver.invalidate_all().await;
+                match proceed().await {
+                    Ok(response) => {
+                        let mut body = response.into_body().into_data_stream();
+                        while let Some(chunk) = body.next().await {
+                            let sent = match chunk {
+                                Ok(bytes) => tx.send(Ok(bytes)).is_ok(),
+                                Err(e) => {
+                                    send_status_error_chunk(&tx, &e.to_string());
+                                    false
+                                }
+                            };
+                            if !sent {
+                                break;
+                            }
+                        }
+                    }
+                    Err(e) => send_status_error_chunk(&tx, &e.message),
+                }
+            }
+            Err(e) if e.is_cancelled() => {
+                log::info!("auto-pull: download of '{}' cancelled", model);
+                send_status_error_chunk(&tx, "download cancelled");
+            }
+            Err(e) => {
+                log::error!("auto-pull: {}", e.message);
+                send_status_error_chunk(&tx, &e.message);
+            }
+        }
+    });
+    create_ndjson_stream_response(rx, "failed to create auto-pull streaming response")
+}
+
+#[cfg(test)]
+#[path = "../../../tests/unit/handlers_ollama_auto_pull.rs"]
+mod tests;
@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Instant;
 
 use axum::body::Body;
@@ -9,6 +10,7 @@ use http::StatusCode;
 use crate::api::RequestContext;
 use crate::constants::LOG_PREFIX_SUCCESS;
 use crate::error::ProxyError;
+use crate::http::body::{blob_over_store_limit, blob_too_large};
 use crate::logging::{LogConfig, log_request, log_timed};
 
 pub async fn handle_blob_head(
@@ -18,8 +20,10 @@ pub async fn handle_blob_head(
     if LogConfig::get().debug_enabled {
         log::debug!("blob head request: {}", digest);
     }
-    let exists = context.blob_store.exists(&digest).await?;
-    let status = if exists {
+    let size = context.blob_store.size(&digest).await?;
+    let status = if size.is_some() {
+        // Clients check for a blob before `/api/create` uses it.
+        context.blob_store.touch(&digest).await;
         StatusCode::OK
     } else {
         StatusCode::NOT_FOUND
@@ -29,16 +33,26 @@ pub async fn handle_blob_head(
         log::debug!("blob head response: {}", status);
     }
 
-    Response::builder()
-        .status(status)
+    let mut builder = Response::builder().status(status);
+    if let Some(size) = size {
+        builder = builder.header(http::header::CONTENT_LENGTH, size);
+    }
+    builder
         .body(Body::empty())
         .map_err(|_| ProxyError::internal_server_error("failed to build blob response"))
 }
 
+/// Store an uploaded blob. `max_size` is `--max-blob-size` (0 = unlimited):
+/// a declared `content_length` over it is refused up front, and a body that
+/// grows past it is cut off mid-stream. A blob bigger than the whole store's
+/// `--blob-max-total-mb` is refused the same way, instead of evicting every
+/// other blob and still not fitting.
 pub async fn handle_blob_upload<S, B>(
     context: RequestContext<'_>,
     digest: String,
     stream: S,
+    content_length: Option<u64>,
+    max_size: u64,
 ) -> Result<Response, ProxyError>
 where
     S: Stream<Item = Result<B, axum::Error>> + Unpin,
@@ -50,9 +64,46 @@ where
         log::debug!("blob upload request: {}", digest);
     }
 
-    let byte_stream = stream.map_ok(|mut buf| buf.copy_to_bytes(buf.remaining()));
+    let store_limit = context.blob_store.max_total_bytes();
+    let (limit, too_large): (u64, fn(u64, Option<u64>) -> ProxyError) =
+        if store_limit > 0 && (max_size == 0 || store_limit < max_size) {
+            (store_limit, blob_over_store_limit)
+        } else {
+            (max_size, blob_too_large)
+        };
 
-    context.blob_store.save_stream(&digest, byte_stream).await?;
+    if limit > 0
+        && let Some(length) = content_length
+        && length > limit
+    {
+        return Err(too_large(limit, Some(length)));
+    }
+
+    let exceeded = AtomicBool::new(false);
+    let mut received = 0u64;
+    let byte_stream = stream.and_then(|mut buf| {
+        let chunk = buf.copy_to_bytes(buf.remaining());
+        received = received.saturating_add(chunk.len() as u64);
+        let result = if limit > 0 && received > limit {
+            exceeded.store(true, Ordering::Relaxed);
+            Err(axum::Error::new("blob exceeds the upload size limit"))
+        } else {
+            Ok(chunk)
+        };
+        std::future::ready(result)
+    });
+
+    let saved = context.blob_store.save_stream(&digest, byte_stream).await;
+    if exceeded.load(Ordering::Relaxed) {
+        return Err(too_large(limit, content_length));
+    }
+    saved?;
+
+    let referenced = context.virtual_models.referenced_blobs().await;
+    context
+        .blob_store
+        .evict_over_limit(&digest, &referenced)
+        .await;
 
     log_timed(
         LOG_PREFIX_SUCCESS,
@@ -69,3 +120,27 @@ where
         .body(Body::empty())
         .map_err(|_| ProxyError::internal_server_error("failed to build blob upload response"))
 }
+
+/// `DELETE /api/blobs/{digest}`: remove one stored blob. A blob an alias is
+/// built from is refused with a 409; an unknown one is a 404.
+pub async fn handle_blob_delete(
+    context: RequestContext<'_>,
+    digest: String,
+) -> Result<Response, ProxyError> {
+    log_request("DELETE", "/api/blobs", Some(&digest));
+    let referenced = context.virtual_models.referenced_blobs().await;
+    if referenced.contains(&digest.to_ascii_lowercase()) {
+        return Err(ProxyError::new(
+            format!("blob {} is used by a model and cannot be deleted", digest),
+            409,
+        ));
+    }
+
+    let size = context.blob_store.remove(&digest).await?;
+    log::info!("deleted blob {} ({} bytes)", digest, size);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::empty())
+        .map_err(|_| ProxyError::internal_server_error("failed to build blob delete response"))
+}
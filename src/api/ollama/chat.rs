@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
@@ -7,11 +7,8 @@ use tokio_util::sync::CancellationToken;
 use crate::api::RequestContext;
 use crate::api::pipeline::ChatLikeCall;
 use crate::api::response::{ResponseContext, ResponseParams, handle_response};
-use crate::config::get_runtime_config;
-use crate::constants::{
-    DEFAULT_STREAM_TIMEOUT_SECONDS, ERROR_MISSING_MESSAGES, LM_STUDIO_NATIVE_CHAT,
-    LM_STUDIO_V1_CHAT,
-};
+use crate::config::{ReasoningMode, get_runtime_config};
+use crate::constants::{ERROR_MISSING_MESSAGES, LM_STUDIO_NATIVE_CHAT, LM_STUDIO_V1_CHAT};
 use crate::error::ProxyError;
 use crate::http::client::{CancellableRequest, handle_json_response};
 use crate::http::json_response;
@@ -22,13 +19,24 @@ use crate::lmstudio::native_chat::{
     NativeChatRequestParams, build_native_chat_request, convert_native_to_ollama_chat,
 };
 use crate::lmstudio::request::{LMStudioRequestType, build_lm_studio_request};
-use crate::lmstudio::response::normalize_chat_messages;
+use crate::lmstudio::response::{
+    ResponseTransformer, apply_measured_load, normalize_chat_messages,
+};
+use crate::lmstudio::tokens::count_tokens;
 use crate::logging::LogConfig;
 use crate::model::ModelResolver;
 use crate::model::naming::extract_required_model_name;
-use crate::streaming::handle_native_streaming_response;
+use crate::streaming::{NativeStreamingParams, handle_native_streaming_response};
 
-use super::resolution::{make_top_level_params, resolve_model_with_context};
+use super::context_guard::{
+    MESSAGE_OVERHEAD_TOKENS, context_limit, estimate_chat_tokens, estimate_message_tokens,
+    fit_messages, mark_truncated, reserved_output_tokens,
+};
+use super::resolution::{
+    ModelResolutionContext, fetch_model_info_for_id, make_top_level_params,
+    resolve_model_with_context, resolve_reasoning_mode, resolve_request_target,
+};
+use super::transform::UpstreamRequest;
 use super::unload_only::{UnloadOnlyCall, is_chat_unload_only, respond_unload_only};
 
 /// Server-config knobs the chat handler reads, bundled so the entry point
@@ -38,6 +46,27 @@ pub struct ChatOptions {
     pub use_native_chat: bool,
     pub native_chat_streaming: bool,
     pub auto_evict: bool,
+    pub reasoning_mode: ReasoningMode,
+    /// `--context-guard`: check the history fits the model's context first.
+    pub context_guard: bool,
+}
+
+/// An Ollama chat response carries one `message`, so there is nowhere to put
+/// a second candidate: `n` above 1 (top level or in `options`) is a 400 that
+/// points at the OpenAI surface, which fans such requests out.
+fn reject_multiple_candidates(body: &Value) -> Result<(), ProxyError> {
+    let n = body
+        .get("n")
+        .or_else(|| body.get("options").and_then(|options| options.get("n")))
+        .and_then(Value::as_u64);
+    match n {
+        Some(n) if n > 1 => Err(ProxyError::bad_request(&format!(
+            "n = {} is not supported on /api/chat, which returns a single message; \
+             use /v1/chat/completions for several candidates",
+            n
+        ))),
+        _ => Ok(()),
+    }
 }
 
 pub async fn handle_ollama_chat(
@@ -52,10 +81,13 @@ pub async fn handle_ollama_chat(
         use_native_chat,
         native_chat_streaming,
         auto_evict,
+        reasoning_mode,
+        context_guard,
     } = options;
     let start_time = Instant::now();
     let ollama_model_name = extract_required_model_name(&body)?.to_string();
     let keep_alive_seconds = parse_keep_alive_seconds(body.get("keep_alive"))?;
+    reject_multiple_candidates(&body)?;
 
     // Spec: `{"model":"x","keep_alive":0}` (no/empty `messages`) is an
     // unload-only call. Short-circuit before the inference path so we don't
@@ -75,18 +107,31 @@ pub async fn handle_ollama_chat(
         .await;
     }
 
+    if !body.get("messages").is_some_and(Value::is_array) {
+        return Err(ProxyError::bad_request(ERROR_MISSING_MESSAGES));
+    }
+    let target = resolve_request_target(
+        &context,
+        &model_resolver,
+        &ollama_model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let lm_studio_model_id = target.lm_studio_model_id.clone();
+
     let operation = {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
         let body = body.clone();
         let cancellation_token = cancellation_token.clone();
         let ollama_model_name = ollama_model_name.clone();
-        move || {
+        move |load_duration: Duration| {
             let context = context.clone();
             let model_resolver = model_resolver.clone();
-            let body = body.clone();
+            let mut body = body.clone();
             let cancellation_token = cancellation_token.clone();
             let ollama_model_name = ollama_model_name.clone();
+            let target = target.clone();
             async move {
                 if LogConfig::get().debug_enabled {
                     log::debug!(
@@ -95,31 +140,28 @@ pub async fn handle_ollama_chat(
                     );
                 }
 
-                let messages = body
-                    .get("messages")
-                    .and_then(|m| m.as_array())
-                    .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_MESSAGES))?;
-
                 let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(true);
 
-                // Route to the native /api/v1/chat path when explicitly opted in
-                // (`--use-native-chat`) or when `--native-chat-streaming` is set
-                // and this is a streaming request. Non-streaming stays on the v0
-                // path under `--native-chat-streaming`, matching the flag's help.
-                let use_native = use_native_chat || (native_chat_streaming && stream);
-
-                let ollama_tools = body.get("tools");
-                let ollama_images = body.get("images");
+                let use_native =
+                    routes_to_native_chat(use_native_chat, native_chat_streaming, stream);
 
                 let resolution_ctx = resolve_model_with_context(
                     &context,
                     &model_resolver,
-                    &ollama_model_name,
+                    &target,
                     &body,
                     true,
                     cancellation_token.clone(),
                 )
                 .await?;
+                let reasoning_mode = resolve_reasoning_mode(
+                    resolution_ctx.effective_options.as_ref(),
+                    reasoning_mode,
+                )?;
+                let permit = context
+                    .model_concurrency
+                    .acquire(&resolution_ctx.lm_studio_model_id)
+                    .await?;
 
                 // Honor Ollama `num_ctx`: reload the model at the requested
                 // context window before inference. No-op when unset or already
@@ -133,137 +175,109 @@ pub async fn handle_ollama_chat(
                 )
                 .await;
 
-                let message_count = messages.len();
-
-                // Native /api/v1/chat path: build the request from the raw Ollama
-                // messages (the native builder owns its own `input`/image shaping)
-                // and dispatch to the native converter / streaming driver.
-                if use_native {
-                    // Only forward `integrations` on the native path; the default
-                    // OpenAI-compat path never reads this field.
-                    let integrations = body.get("integrations").filter(|v| v.is_array());
-                    // Mirror the OpenAI-compat default: absent `think` on a
-                    // thinking-capable model enables reasoning. Explicit `think`
-                    // always wins. The local `default_think` outlives the borrow.
-                    let default_think = Value::String("on".to_string());
-                    let native_think = make_top_level_params(&body).think.or_else(|| {
-                        resolution_ctx
-                            .model_supports_thinking
-                            .then_some(&default_think)
-                    });
-                    let mut native_request = build_native_chat_request(NativeChatRequestParams {
-                        model_lm_studio_id: &resolution_ctx.lm_studio_model_id,
-                        messages: body.get("messages").unwrap_or(&Value::Null),
-                        system_prompt: resolution_ctx.system_prompt.as_deref(),
-                        ollama_options: resolution_ctx.effective_options.as_ref(),
-                        think: native_think,
-                        stream,
-                        integrations,
-                    });
-                    apply_keep_alive_ttl(&mut native_request, keep_alive_seconds);
+                let dropped = if context_guard {
+                    guard_context(
+                        &context,
+                        &model_resolver,
+                        &resolution_ctx,
+                        &mut body,
+                        cancellation_token.clone(),
+                    )
+                    .await?
+                } else {
+                    0
+                };
 
-                    let response =
-                        CancellableRequest::new(context.client, cancellation_token.clone())
-                            .make_request(
-                                reqwest::Method::POST,
-                                &context.endpoint_url(LM_STUDIO_V1_CHAT),
-                                Some(native_request),
-                            )
-                            .await?;
+                let upstream =
+                    build_chat_upstream(&body, &resolution_ctx, keep_alive_seconds, use_native)?;
+                // Counted on what is sent: the injected system prompt and
+                // flattened image-message text included.
+                let prompt_tokens_estimate = estimate_upstream_prompt_tokens(&upstream.body);
+                let response = CancellableRequest::new(context.client, cancellation_token.clone())
+                    .with_headers(context.forward_headers.clone())
+                    .make_request(
+                        reqwest::Method::POST,
+                        &context.endpoint_url(upstream.endpoint),
+                        Some(upstream.body),
+                    )
+                    .await?;
 
-                    return if stream {
+                // Native /api/v1/chat path: dispatch to the native converter /
+                // streaming driver.
+                let result = if use_native {
+                    if stream {
                         handle_native_streaming_response(
                             response,
-                            &ollama_model_name,
-                            start_time,
-                            cancellation_token,
-                            DEFAULT_STREAM_TIMEOUT_SECONDS,
+                            NativeStreamingParams {
+                                model_name: &ollama_model_name,
+                                start_time,
+                                load_duration,
+                                cancellation_token,
+                                timeouts: context.stream_timeouts,
+                                reasoning_mode,
+                                prompt_tokens_estimate,
+                            },
                         )
                         .await
                     } else {
                         let native_value =
                             handle_json_response(response, cancellation_token).await?;
-                        let ollama_response = convert_native_to_ollama_chat(
+                        let mut ollama_response = convert_native_to_ollama_chat(
                             &native_value,
                             &ollama_model_name,
+                            prompt_tokens_estimate,
                             start_time,
                         );
+                        ResponseTransformer::apply_reasoning_mode(
+                            &mut ollama_response,
+                            reasoning_mode,
+                        );
+                        if context.expose_stats {
+                            ResponseTransformer::attach_proxy_stats(
+                                &mut ollama_response,
+                                &native_value,
+                            );
+                        }
+                        apply_measured_load(&mut ollama_response, load_duration, start_time);
                         Ok(json_response(&ollama_response))
-                    };
-                }
-
-                let normalized_messages =
-                    normalize_chat_messages(messages, resolution_ctx.system_prompt.as_deref());
-                // /api/chat: each message may carry its own `images` array — pull those
-                // into OpenAI content parts on the same message first.
-                let with_per_message_images = convert_per_message_images(normalized_messages);
-                // Top-level `images` (rare on /api/chat, common on vision /api/generate
-                // bridged through chat) attaches to the LAST user message.
-                let messages_with_images = if let Some(images) = ollama_images {
-                    inject_images_into_messages(with_per_message_images, images)
+                    }
                 } else {
-                    with_per_message_images
-                };
-
-                let mut top_level_params = make_top_level_params(&body);
-                top_level_params.model_is_thinking = resolution_ctx.model_supports_thinking;
-                let mut lm_request = build_lm_studio_request(
-                    &resolution_ctx.lm_studio_model_id,
-                    LMStudioRequestType::Chat {
-                        messages: &messages_with_images,
+                    handle_response(ResponseParams {
+                        response,
                         stream,
-                    },
-                    resolution_ctx.effective_options.as_ref(),
-                    ollama_tools,
-                    resolution_ctx.effective_format.as_ref(),
-                    Some(&top_level_params),
-                );
+                        is_chat: true,
+                        model_name: &ollama_model_name,
+                        start_time,
+                        load_duration,
+                        context: ResponseContext::Chat {
+                            prompt_tokens_estimate,
+                        },
+                        cancellation_token,
+                        reasoning_mode,
+                        stream_timeouts: context.stream_timeouts,
+                        expose_stats: context.expose_stats,
+                        runtime_info: context.runtime_info.clone(),
+                        lm_studio_model_id: &resolution_ctx.lm_studio_model_id,
+                    })
+                    .await
+                };
+                let response = permit.attach(result?);
 
-                // Forward OpenAI-compat `tool_choice`, but only alongside a
-                // non-empty `tools` array (matching how `tools` itself is
-                // gated) — `tool_choice` without tools is meaningless and some
-                // backends reject it. LM Studio accepts it on /api/v0 otherwise.
-                let tools_present = ollama_tools
-                    .and_then(|t| t.as_array())
-                    .is_some_and(|arr| !arr.is_empty());
-                if tools_present
-                    && let Some(tool_choice) = body.get("tool_choice")
-                    && let Some(obj) = lm_request.as_object_mut()
-                {
-                    obj.insert("tool_choice".to_string(), tool_choice.clone());
+                if dropped > 0 {
+                    mark_truncated(response, stream).await
+                } else {
+                    Ok(response)
                 }
-
-                apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
-
-                let response = CancellableRequest::new(context.client, cancellation_token.clone())
-                    .make_request(
-                        reqwest::Method::POST,
-                        &context.endpoint_url(LM_STUDIO_NATIVE_CHAT),
-                        Some(lm_request),
-                    )
-                    .await?;
-
-                handle_response(ResponseParams {
-                    response,
-                    stream,
-                    is_chat: true,
-                    model_name: &ollama_model_name,
-                    start_time,
-                    context: ResponseContext::Chat { message_count },
-                    cancellation_token,
-                })
-                .await
             }
         }
     };
 
     ChatLikeCall {
         context,
-        resolver: model_resolver,
         body,
         cancellation: cancellation_token,
         load_timeout_seconds,
-        ollama_model_name,
+        lm_studio_model_id,
         keep_alive_seconds,
         start_time,
         op_label: "Ollama chat",
@@ -273,3 +287,177 @@ pub async fn handle_ollama_chat(
     .run(operation)
     .await
 }
+
+/// Estimated prompt tokens of the chat body sent upstream: its `messages` on
+/// the OpenAI-compatible path, or on the native path the `text` items of
+/// `input` plus `system_prompt`.
+fn estimate_upstream_prompt_tokens(body: &Value) -> u64 {
+    if let Some(messages) = body.get("messages").and_then(Value::as_array) {
+        return estimate_chat_tokens(messages);
+    }
+    let input: u64 = body
+        .get("input")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("text"))
+        .map(estimate_message_tokens)
+        .sum();
+    let system = body
+        .get("system_prompt")
+        .and_then(Value::as_str)
+        .map_or(0, |text| count_tokens(text) + MESSAGE_OVERHEAD_TOKENS);
+    input + system
+}
+
+/// `--context-guard` for one request: fit `body.messages` into the resolved
+/// model's context, dropping old messages when `options.truncate_history`
+/// is set. Returns how many were dropped. Skipped when LM Studio does not
+/// list the model.
+async fn guard_context(
+    context: &RequestContext<'_>,
+    model_resolver: &Arc<ModelResolver>,
+    resolution_ctx: &ModelResolutionContext,
+    body: &mut Value,
+    cancellation_token: CancellationToken,
+) -> Result<usize, ProxyError> {
+    let Some(info) = fetch_model_info_for_id(
+        context,
+        model_resolver,
+        &resolution_ctx.lm_studio_model_id,
+        cancellation_token,
+    )
+    .await?
+    else {
+        return Ok(0);
+    };
+    let options = resolution_ctx.effective_options.as_ref();
+    let truncate = options
+        .and_then(|o| o.get("truncate_history"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let Some(messages) = body.get_mut("messages").and_then(Value::as_array_mut) else {
+        return Ok(0);
+    };
+    let dropped = fit_messages(
+        messages,
+        resolution_ctx.system_prompt.as_deref(),
+        context_limit(&info, options),
+        reserved_output_tokens(options),
+        truncate,
+    )?;
+    if dropped > 0 {
+        log::info!(
+            "context guard: dropped {} oldest messages to fit '{}'",
+            dropped,
+            resolution_ctx.lm_studio_model_id
+        );
+    }
+    Ok(dropped)
+}
+
+/// Route to the native /api/v1/chat path when explicitly opted in
+/// (`--use-native-chat`) or when `--native-chat-streaming` is set and this is
+/// a streaming request. Non-streaming stays on the v0 path under
+/// `--native-chat-streaming`, matching the flag's help.
+pub fn routes_to_native_chat(
+    use_native_chat: bool,
+    native_chat_streaming: bool,
+    stream: bool,
+) -> bool {
+    use_native_chat || (native_chat_streaming && stream)
+}
+
+/// The LM Studio request for a chat body against an already-resolved model,
+/// with `ttl` applied. Sends nothing.
+pub fn build_chat_upstream(
+    body: &Value,
+    resolution_ctx: &ModelResolutionContext,
+    keep_alive_seconds: Option<i64>,
+    use_native: bool,
+) -> Result<UpstreamRequest, ProxyError> {
+    let messages = body
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_MESSAGES))?;
+    let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(true);
+
+    // Native /api/v1/chat: build the request from the raw Ollama messages (the
+    // native builder owns its own `input`/image shaping).
+    if use_native {
+        // Only forward `integrations` on the native path; the default
+        // OpenAI-compat path never reads this field.
+        let integrations = body.get("integrations").filter(|v| v.is_array());
+        // Mirror the OpenAI-compat default: absent `think` on a
+        // thinking-capable model enables reasoning. Explicit `think` always
+        // wins. The local `default_think` outlives the borrow.
+        let default_think = Value::String("on".to_string());
+        let native_think = make_top_level_params(body).think.or_else(|| {
+            resolution_ctx
+                .model_supports_thinking
+                .then_some(&default_think)
+        });
+        let mut native_request = build_native_chat_request(NativeChatRequestParams {
+            model_lm_studio_id: resolution_ctx.upstream_model_id(),
+            messages: body.get("messages").unwrap_or(&Value::Null),
+            system_prompt: resolution_ctx.system_prompt.as_deref(),
+            ollama_options: resolution_ctx.effective_options.as_ref(),
+            think: native_think,
+            stream,
+            integrations,
+        });
+        apply_keep_alive_ttl(&mut native_request, keep_alive_seconds);
+        return Ok(UpstreamRequest {
+            endpoint: LM_STUDIO_V1_CHAT,
+            body: native_request,
+        });
+    }
+
+    let ollama_tools = body.get("tools");
+    let normalized_messages =
+        normalize_chat_messages(messages, resolution_ctx.system_prompt.as_deref());
+    // /api/chat: each message may carry its own `images` array — pull those
+    // into OpenAI content parts on the same message first.
+    let with_per_message_images = convert_per_message_images(normalized_messages);
+    // Top-level `images` (rare on /api/chat, common on vision /api/generate
+    // bridged through chat) attaches to the LAST user message.
+    let messages_with_images = if let Some(images) = body.get("images") {
+        inject_images_into_messages(with_per_message_images, images)
+    } else {
+        with_per_message_images
+    };
+
+    let mut top_level_params = make_top_level_params(body);
+    top_level_params.model_is_thinking = resolution_ctx.model_supports_thinking;
+    let mut lm_request = build_lm_studio_request(
+        resolution_ctx.upstream_model_id(),
+        LMStudioRequestType::Chat {
+            messages: &messages_with_images,
+            stream,
+        },
+        resolution_ctx.effective_options.as_ref(),
+        ollama_tools,
+        resolution_ctx.effective_format.as_ref(),
+        Some(&top_level_params),
+    );
+
+    // Forward OpenAI-compat `tool_choice`, but only alongside a non-empty
+    // `tools` array (matching how `tools` itself is gated) — `tool_choice`
+    // without tools is meaningless and some backends reject it. LM Studio
+    // accepts it on /api/v0 otherwise.
+    let tools_present = ollama_tools
+        .and_then(|t| t.as_array())
+        .is_some_and(|arr| !arr.is_empty());
+    if tools_present
+        && let Some(tool_choice) = body.get("tool_choice")
+        && let Some(obj) = lm_request.as_object_mut()
+    {
+        obj.insert("tool_choice".to_string(), tool_choice.clone());
+    }
+
+    apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
+    Ok(UpstreamRequest {
+        endpoint: LM_STUDIO_NATIVE_CHAT,
+        body: lm_request,
+    })
+}
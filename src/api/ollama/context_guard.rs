@@ -0,0 +1,176 @@
+use axum::body::Body;
+use axum::response::Response;
+use bytes::Bytes;
+use futures_util::StreamExt;
+use serde_json::Value;
+
+use crate::error::ProxyError;
+use crate::lmstudio::tokens::count_tokens;
+use crate::model::ModelInfo;
+
+/// Tokens charged per message on top of its text: the role and the chat
+/// template's separators.
+pub const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+/// Estimated prompt tokens for one chat message: its text (string content or
+/// the `text` parts of a content array), any `tool_calls`, plus
+/// [`MESSAGE_OVERHEAD_TOKENS`]. Images are not counted.
+pub fn estimate_message_tokens(message: &Value) -> u64 {
+    let content = match message.get("content") {
+        Some(Value::String(text)) => count_tokens(text),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .map(count_tokens)
+            .sum(),
+        _ => 0,
+    };
+    let tool_calls = message
+        .get("tool_calls")
+        .filter(|calls| !calls.is_null())
+        .map(|calls| count_tokens(&calls.to_string()))
+        .unwrap_or(0);
+    content + tool_calls + MESSAGE_OVERHEAD_TOKENS
+}
+
+pub fn estimate_chat_tokens(messages: &[Value]) -> u64 {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+/// The window a request runs in: `num_ctx` when the request sets it (the
+/// model is reloaded to it), otherwise the loaded context length, which falls
+/// back to `max_context_length`. Zero means unknown.
+pub fn context_limit(info: &ModelInfo, options: Option<&Value>) -> u64 {
+    options
+        .and_then(|o| o.get("num_ctx"))
+        .and_then(Value::as_u64)
+        .filter(|&n| n > 0)
+        .unwrap_or(if info.context_length > 0 {
+            info.context_length
+        } else {
+            info.max_context_length
+        })
+}
+
+/// Tokens kept free for the reply: `max_tokens` / `num_predict` when
+/// positive. `-1` and `-2` (no limit, fill the context) reserve nothing.
+pub fn reserved_output_tokens(options: Option<&Value>) -> u64 {
+    options
+        .and_then(|o| o.get("max_tokens").or_else(|| o.get("num_predict")))
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+fn is_system(message: &Value) -> bool {
+    message
+        .get("role")
+        .and_then(Value::as_str)
+        .is_some_and(|role| role.eq_ignore_ascii_case("system"))
+}
+
+fn is_tool_result(message: &Value) -> bool {
+    message
+        .get("role")
+        .and_then(Value::as_str)
+        .is_some_and(|role| role.eq_ignore_ascii_case("tool"))
+}
+
+/// `--context-guard`: make `messages` fit `limit` tokens with `reserved` left
+/// for the reply. When they don't, either fail with a 400 or, with
+/// `truncate`, drop the oldest non-system messages (and the tool results
+/// that answer them) until they do. The last message is never dropped.
+///
+/// `system_prompt` is the prompt the proxy will add when the history has no
+/// system message. Returns how many messages were dropped.
+pub fn fit_messages(
+    messages: &mut Vec<Value>,
+    system_prompt: Option<&str>,
+    limit: u64,
+    reserved: u64,
+    truncate: bool,
+) -> Result<usize, ProxyError> {
+    if limit == 0 {
+        return Ok(0);
+    }
+    let injected_system = match system_prompt {
+        Some(prompt) if !messages.iter().any(is_system) => {
+            count_tokens(prompt) + MESSAGE_OVERHEAD_TOKENS
+        }
+        _ => 0,
+    };
+    let mut estimate = injected_system + estimate_chat_tokens(messages);
+    if estimate.saturating_add(reserved) <= limit {
+        return Ok(0);
+    }
+    if !truncate {
+        return Err(ProxyError::bad_request(&format!(
+            "chat history exceeds the model's context: ~{} prompt tokens + {} reserved for the reply > {} tokens; shorten it or set options.truncate_history",
+            estimate, reserved, limit
+        )));
+    }
+
+    let mut dropped = 0;
+    while estimate.saturating_add(reserved) > limit {
+        let last = messages.len().saturating_sub(1);
+        let Some(index) = (0..last).find(|&i| !is_system(&messages[i])) else {
+            return Err(ProxyError::bad_request(&format!(
+                "chat history exceeds the model's context even with older messages dropped: ~{} prompt tokens + {} reserved for the reply > {} tokens",
+                estimate, reserved, limit
+            )));
+        };
+        let removed = messages.remove(index);
+        estimate -= estimate_message_tokens(&removed);
+        dropped += 1;
+        // Tool results are meaningless without the call they answer.
+        while index < messages.len().saturating_sub(1) && is_tool_result(&messages[index]) {
+            estimate -= estimate_message_tokens(&messages.remove(index));
+            dropped += 1;
+        }
+    }
+    Ok(dropped)
+}
+
+/// Add `"truncated": true` to a JSON line that finishes a reply
+/// (`"done": true`); anything else passes through untouched.
+fn mark_final_line(bytes: Bytes) -> Bytes {
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return bytes;
+    };
+    if value.get("done").and_then(Value::as_bool) != Some(true) {
+        return bytes;
+    }
+    let Some(obj) = value.as_object_mut() else {
+        return bytes;
+    };
+    obj.insert("truncated".to_string(), Value::Bool(true));
+    let mut out = serde_json::to_vec(&value).unwrap_or_default();
+    if bytes.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+    Bytes::from(out)
+}
+
+/// Tell the client its history was shortened: `"truncated": true` on the
+/// response object, or on the final chunk of an NDJSON stream.
+pub async fn mark_truncated(response: Response, stream: bool) -> Result<Response, ProxyError> {
+    let (mut parts, body) = response.into_parts();
+    if stream {
+        // NDJSON responses are framed one line per chunk.
+        let frames = body
+            .into_data_stream()
+            .map(|frame| frame.map(mark_final_line));
+        return Ok(Response::from_parts(parts, Body::from_stream(frames)));
+    }
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| ProxyError::internal_server_error(&format!("read response: {}", e)))?;
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    Ok(Response::from_parts(
+        parts,
+        Body::from(mark_final_line(bytes)),
+    ))
+}
+
+#[cfg(test)]
+#[path = "../../../tests/unit/handlers_ollama_context_guard.rs"]
+mod tests;
@@ -1,11 +1,12 @@
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use serde_json::Value;
+use serde_json::{Value, json};
 use tokio_util::sync::CancellationToken;
 
 use crate::api::RequestContext;
 use crate::api::pipeline::ChatLikeCall;
+use crate::api::retry::trigger_model_loading_for_ollama;
 use crate::config::get_runtime_config;
 use crate::constants::{
     ERROR_EMBED_INPUT_EMPTY, ERROR_EMBED_INPUT_REQUIRED, ERROR_EMBEDDINGS_PROMPT_EMPTY,
@@ -15,14 +16,21 @@ use crate::error::ProxyError;
 use crate::http::client::{CancellableRequest, handle_json_response};
 use crate::http::json_response;
 use crate::lmstudio::ensure_context_length;
-use crate::lmstudio::keep_alive::{apply_keep_alive_ttl, parse_keep_alive_seconds};
+use crate::lmstudio::keep_alive::{
+    apply_keep_alive_ttl, keep_alive_requests_unload, parse_keep_alive_seconds,
+    spawn_model_unload_if_needed,
+};
 use crate::lmstudio::request::{LMStudioRequestType, build_lm_studio_request};
-use crate::lmstudio::response::ResponseTransformer;
+use crate::lmstudio::response::{ResponseTransformer, apply_measured_load};
+use crate::lmstudio::tokens::count_tokens;
 use crate::logging::LogConfig;
 use crate::model::ModelResolver;
 use crate::model::naming::extract_required_model_name;
 
-use super::resolution::resolve_model_with_context;
+use super::resolution::{
+    fetch_model_info_for_id, resolve_model_target, resolve_model_target_and_fetch,
+    resolve_model_with_context, resolve_request_target,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum EmbeddingResponseMode {
@@ -45,18 +53,55 @@ pub async fn handle_ollama_embeddings(
     let ollama_model_name = extract_required_model_name(&body)?.to_string();
     let keep_alive_seconds = parse_keep_alive_seconds(body.get("keep_alive"))?;
 
+    // Ollama: `/api/embed` with no input only loads the model, so a RAG
+    // client can warm its embedder before the first query. With
+    // `keep_alive: 0` it is the unload hint chat and generate take.
+    if matches!(response_mode, EmbeddingResponseMode::Embed) && is_embed_load_only(&body) {
+        if keep_alive_requests_unload(keep_alive_seconds) {
+            return respond_embed_unload_only(
+                &context,
+                model_resolver,
+                &ollama_model_name,
+                keep_alive_seconds,
+                start_time,
+                cancellation_token,
+            )
+            .await;
+        }
+        return respond_embed_load_only(
+            &context,
+            &model_resolver,
+            &ollama_model_name,
+            start_time,
+            cancellation_token,
+        )
+        .await;
+    }
+
+    let input_value = extract_embedding_input(&body, response_mode)?;
+    let target = resolve_request_target(
+        &context,
+        &model_resolver,
+        &ollama_model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let lm_studio_model_id = target.lm_studio_model_id.clone();
+
     let operation = {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
         let body = body.clone();
         let cancellation_token = cancellation_token.clone();
         let ollama_model_name = ollama_model_name.clone();
-        move || {
+        move |load_duration: Duration| {
             let context = context.clone();
             let model_resolver = model_resolver.clone();
             let body = body.clone();
             let cancellation_token = cancellation_token.clone();
             let ollama_model_name = ollama_model_name.clone();
+            let target = target.clone();
+            let input_value = input_value.clone();
             async move {
                 if LogConfig::get().debug_enabled {
                     log::debug!(
@@ -65,17 +110,19 @@ pub async fn handle_ollama_embeddings(
                     );
                 }
 
-                let input_value = extract_embedding_input(&body, response_mode)?;
-
                 let resolution_ctx = resolve_model_with_context(
                     &context,
                     &model_resolver,
-                    &ollama_model_name,
+                    &target,
                     &body,
                     false,
                     cancellation_token.clone(),
                 )
                 .await?;
+                let _permit = context
+                    .model_concurrency
+                    .acquire(&resolution_ctx.lm_studio_model_id)
+                    .await?;
 
                 // Honor Ollama `num_ctx`: reload the model at the requested
                 // context window before inference. No-op when unset or already
@@ -89,34 +136,89 @@ pub async fn handle_ollama_embeddings(
                 )
                 .await;
 
-                let mut lm_request = build_lm_studio_request(
-                    &resolution_ctx.lm_studio_model_id,
-                    LMStudioRequestType::Embeddings {
-                        input: &input_value,
-                    },
-                    resolution_ctx.effective_options.as_ref(),
-                    None,
-                    None,
-                    None,
-                );
+                // `truncate: false` asks for an error instead of a silently
+                // shortened input; LM Studio only knows how to truncate.
+                if !truncation_allowed(resolution_ctx.effective_options.as_ref())
+                    && let Some(info) = fetch_model_info_for_id(
+                        &context,
+                        &model_resolver,
+                        &resolution_ctx.lm_studio_model_id,
+                        cancellation_token.clone(),
+                    )
+                    .await?
+                {
+                    check_inputs_fit_context(&input_value, info.max_context_length)?;
+                }
 
-                apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
+                // With the cache on, only inputs it has no vector for go
+                // upstream; nothing is sent when all of them hit.
+                let cache_lookup = match &context.embedding_cache {
+                    Some(cache) => {
+                        cache
+                            .lookup(
+                                &resolution_ctx.lm_studio_model_id,
+                                requested_dimensions(resolution_ctx.effective_options.as_ref()),
+                                &input_value,
+                            )
+                            .await
+                    }
+                    None => None,
+                };
+                let upstream_input = match &cache_lookup {
+                    Some(lookup) => lookup.missing_input(),
+                    None => Some(input_value.clone()),
+                };
 
-                let response = CancellableRequest::new(context.client, cancellation_token.clone())
-                    .make_request(
-                        reqwest::Method::POST,
-                        &context.endpoint_url(LM_STUDIO_NATIVE_EMBEDDINGS),
-                        Some(lm_request),
-                    )
-                    .await?;
-                let lm_response_value = handle_json_response(response, cancellation_token).await?;
+                let mut upstream_response = None;
+                if let Some(upstream_input) = upstream_input {
+                    let mut lm_request = build_lm_studio_request(
+                        resolution_ctx.upstream_model_id(),
+                        LMStudioRequestType::Embeddings {
+                            input: &upstream_input,
+                        },
+                        resolution_ctx.effective_options.as_ref(),
+                        None,
+                        None,
+                        None,
+                    );
 
-                let ollama_response = ResponseTransformer::convert_to_ollama_embeddings(
+                    apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
+
+                    let response =
+                        CancellableRequest::new(context.client, cancellation_token.clone())
+                            .with_headers(context.forward_headers.clone())
+                            .make_request(
+                                reqwest::Method::POST,
+                                &context.endpoint_url(LM_STUDIO_NATIVE_EMBEDDINGS),
+                                Some(lm_request),
+                            )
+                            .await?;
+                    upstream_response =
+                        Some(handle_json_response(response, cancellation_token).await?);
+                }
+                let lm_response_value = match (&context.embedding_cache, cache_lookup) {
+                    (Some(cache), Some(lookup)) => {
+                        cache.complete(lookup, upstream_response).await?
+                    }
+                    _ => upstream_response.unwrap_or_default(),
+                };
+
+                let mut ollama_response = ResponseTransformer::convert_to_ollama_embeddings(
                     &lm_response_value,
                     &ollama_model_name,
                     &input_value,
                     start_time,
+                    normalize_requested(resolution_ctx.effective_options.as_ref()),
                 );
+                apply_measured_load(&mut ollama_response, load_duration, start_time);
+                check_embedding_count(&ollama_response, &input_value)?;
+                if LogConfig::get().debug_enabled {
+                    log::debug!(
+                        "embeddings response: {} vector(s) of dimension {}",
+                        embedding_input_count(&input_value),
+                        embedding_dimension(&ollama_response)
+                    );
+                }
                 let final_payload = finalize_embedding_response(ollama_response, response_mode);
                 if LogConfig::get().debug_enabled {
                     log::debug!(
@@ -131,11 +233,10 @@ pub async fn handle_ollama_embeddings(
 
     ChatLikeCall {
         context,
-        resolver: model_resolver,
         body,
         cancellation: cancellation_token,
         load_timeout_seconds,
-        ollama_model_name,
+        lm_studio_model_id,
         keep_alive_seconds,
         start_time,
         op_label: "Ollama embeddings",
@@ -146,12 +247,95 @@ pub async fn handle_ollama_embeddings(
     .await
 }
 
+/// `/api/embed` load-only request (unload-only with `keep_alive: 0`):
+/// `input` missing, `null`, `""` or `[]`.
+/// A body carrying the legacy `prompt` instead still gets the wrong-field
+/// 400, and an array of only empty strings is still rejected.
+pub fn is_embed_load_only(body: &Value) -> bool {
+    if body.get("prompt").is_some() {
+        return false;
+    }
+    match body.get("input") {
+        None | Some(Value::Null) => true,
+        Some(Value::String(s)) => s.is_empty(),
+        Some(Value::Array(items)) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Load the model the way a cold request would (an explicit load for an
+/// embedding model), then answer with no vectors once it is up.
+async fn respond_embed_load_only(
+    context: &RequestContext<'_>,
+    model_resolver: &Arc<ModelResolver>,
+    ollama_model_name: &str,
+    start_time: Instant,
+    cancellation_token: CancellationToken,
+) -> Result<axum::response::Response, ProxyError> {
+    // Aliases and `--model-name-map` resolve as they do for an embed
+    // request, and an unknown name 404s here as it would there.
+    let (model_id, _, model_info) = resolve_model_target_and_fetch(
+        context,
+        model_resolver,
+        ollama_model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let load_start = Instant::now();
+    trigger_model_loading_for_ollama(context, &model_id, model_info.as_ref(), cancellation_token)
+        .await?;
+    Ok(json_response(&json!({
+        "model": ollama_model_name,
+        "embeddings": [],
+        "total_duration": start_time.elapsed().as_nanos() as u64,
+        "load_duration": load_start.elapsed().as_nanos() as u64,
+        "prompt_eval_count": 0u64,
+    })))
+}
+
+/// `keep_alive: 0` with no input: unload the model instead of loading it,
+/// and answer with no vectors straight away.
+async fn respond_embed_unload_only(
+    context: &RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
+    ollama_model_name: &str,
+    keep_alive_seconds: Option<i64>,
+    start_time: Instant,
+    cancellation_token: CancellationToken,
+) -> Result<axum::response::Response, ProxyError> {
+    // Aliases and `--model-name-map` resolve as they do for an embed
+    // request; an unknown name 404s here rather than "unloading" nothing.
+    let (model_id, _) = resolve_model_target(
+        context,
+        &model_resolver,
+        ollama_model_name,
+        cancellation_token,
+    )
+    .await?;
+    spawn_model_unload_if_needed(
+        context.client.clone(),
+        context.lmstudio_url.to_string(),
+        model_id,
+        keep_alive_seconds,
+        0,
+    );
+    Ok(json_response(&json!({
+        "model": ollama_model_name,
+        "embeddings": [],
+        "total_duration": start_time.elapsed().as_nanos() as u64,
+        "load_duration": 0u64,
+        "prompt_eval_count": 0u64,
+    })))
+}
+
 /// Extract the embedding input value from the request body, gated by endpoint mode.
 ///
 /// `/api/embed` (Embed) requires `input` (string or string[]) and rejects the
 /// legacy `prompt` field. `/api/embeddings` (LegacyEmbeddings) requires `prompt`
 /// (string) and rejects the new `input` field. Empty values — empty string,
-/// empty array, or an array of only empty strings — are rejected with 400.
+/// empty array, or an array of only empty strings — are rejected with 400;
+/// on `/api/embed` only the last reaches this, since an absent or empty
+/// `input` is a load-only request ([`is_embed_load_only`]).
 fn extract_embedding_input(body: &Value, mode: EmbeddingResponseMode) -> Result<Value, ProxyError> {
     match mode {
         EmbeddingResponseMode::Embed => {
@@ -199,6 +383,89 @@ fn is_empty_embedding_input(value: &Value) -> bool {
     }
 }
 
+/// `dimensions` (lifted into `options`), part of the embedding cache key.
+fn requested_dimensions(options: Option<&Value>) -> Option<u64> {
+    options
+        .and_then(|o| o.get("dimensions"))
+        .and_then(Value::as_u64)
+}
+
+/// Ollama's `truncate` (lifted into `options`); defaults to true.
+fn truncation_allowed(options: Option<&Value>) -> bool {
+    options
+        .and_then(|o| o.get("truncate"))
+        .and_then(Value::as_bool)
+        .unwrap_or(true)
+}
+
+/// 400 when any input is longer than `max_context_length` tokens. Counts are
+/// estimates unless the `accurate-tokens` tokenizer is enabled; an unknown
+/// (zero) context length skips the check.
+fn check_inputs_fit_context(input: &Value, max_context_length: u64) -> Result<(), ProxyError> {
+    if max_context_length == 0 {
+        return Ok(());
+    }
+    let texts: Vec<(usize, &str)> = match input {
+        Value::String(s) => vec![(0, s.as_str())],
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| item.as_str().map(|text| (index, text)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for (index, text) in texts {
+        let tokens = count_tokens(text);
+        if tokens > max_context_length {
+            let which = if matches!(input, Value::Array(_)) {
+                format!("input {}", index)
+            } else {
+                "input".to_string()
+            };
+            return Err(ProxyError::bad_request(&format!(
+                "{} exceeds the model's context length (~{} > {} tokens) and truncate is false",
+                which, tokens, max_context_length
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Number of vectors an input should produce: one for a string, one per
+/// element for an array.
+fn embedding_input_count(input: &Value) -> usize {
+    match input {
+        Value::Array(items) => items.len(),
+        _ => 1,
+    }
+}
+
+/// Reject a response whose vectors cannot be lined up with the inputs, rather
+/// than hand back embeddings shifted onto the wrong strings.
+fn check_embedding_count(response: &Value, input: &Value) -> Result<(), ProxyError> {
+    let returned = response
+        .get("embeddings")
+        .and_then(|e| e.as_array())
+        .map_or(0, Vec::len);
+    let expected = embedding_input_count(input);
+    if returned != expected {
+        return Err(ProxyError::bad_gateway(&format!(
+            "LM Studio returned {} embeddings for {} inputs",
+            returned, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Length of the first returned vector, for `--debug` output.
+fn embedding_dimension(response: &Value) -> usize {
+    response
+        .get("embeddings")
+        .and_then(|e| e.get(0))
+        .and_then(|v| v.as_array())
+        .map_or(0, Vec::len)
+}
+
 fn finalize_embedding_response(mut response: Value, mode: EmbeddingResponseMode) -> Value {
     if matches!(mode, EmbeddingResponseMode::LegacyEmbeddings) {
         let fallback = Value::Array(Vec::new());
@@ -217,8 +484,12 @@ fn finalize_embedding_response(mut response: Value, mode: EmbeddingResponseMode)
     response
 }
 
+/// Top-level `/api/embed` parameters that are moved into `options`.
+const LIFTED_EMBED_PARAMS: [&str; 3] = ["truncate", "dimensions", "normalize"];
+
 /// Lift Ollama's top-level `/api/embed` advanced parameters (`truncate`, `dimensions`)
-/// into the `options` map so the shared option-mapper picks them up.
+/// and the proxy's own `normalize` flag into the `options` map so the shared
+/// option-mapper and the handler read them from one place.
 ///
 /// Per Ollama spec (api-docs/ollama.md §"Generate Embeddings"), `truncate` and
 /// `dimensions` sit at the top level of the request body, peers of `model` and
@@ -228,9 +499,11 @@ pub fn lift_embed_top_level_params(body: &mut Value) {
         return;
     };
 
-    let truncate = obj.remove("truncate");
-    let dimensions = obj.remove("dimensions");
-    if truncate.is_none() && dimensions.is_none() {
+    let lifted: Vec<(&str, Value)> = LIFTED_EMBED_PARAMS
+        .iter()
+        .filter_map(|key| obj.remove(*key).map(|value| (*key, value)))
+        .collect();
+    if lifted.is_empty() {
         return;
     }
 
@@ -239,23 +512,26 @@ pub fn lift_embed_top_level_params(body: &mut Value) {
         .or_insert_with(|| serde_json::json!({}));
     let Some(options) = options_entry.as_object_mut() else {
         // `options` is set to a non-object value; restore top-level fields and bail.
-        if let Some(t) = truncate {
-            obj.insert("truncate".to_string(), t);
-        }
-        if let Some(d) = dimensions {
-            obj.insert("dimensions".to_string(), d);
+        for (key, value) in lifted {
+            obj.insert(key.to_string(), value);
         }
         return;
     };
 
-    if let Some(t) = truncate {
-        options.entry("truncate".to_string()).or_insert(t);
-    }
-    if let Some(d) = dimensions {
-        options.entry("dimensions".to_string()).or_insert(d);
+    for (key, value) in lifted {
+        options.entry(key.to_string()).or_insert(value);
     }
 }
 
+/// Proxy-side `normalize` option: L2-normalize each returned vector.
+/// LM Studio has no such parameter, so it is never forwarded.
+pub fn normalize_requested(options: Option<&Value>) -> bool {
+    options
+        .and_then(|o| o.get("normalize"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 #[path = "../../../tests/unit/handlers_ollama_embed_params.rs"]
 mod tests_embed_params;
@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
@@ -8,13 +8,14 @@ use tokio_util::sync::CancellationToken;
 use crate::api::RequestContext;
 use crate::api::pipeline::ChatLikeCall;
 use crate::api::response::{ResponseContext, ResponseParams, handle_response};
-use crate::config::get_runtime_config;
+use crate::config::{ReasoningMode, get_runtime_config};
 use crate::constants::{
     ERROR_MISSING_PROMPT, ERROR_RAW_WITH_IMAGES, LM_STUDIO_NATIVE_CHAT,
     LM_STUDIO_NATIVE_COMPLETIONS,
 };
 use crate::error::ProxyError;
 use crate::http::client::CancellableRequest;
+use crate::http::error::upstream_error;
 use crate::lmstudio::ensure_context_length;
 use crate::lmstudio::images::build_vision_chat_messages;
 use crate::lmstudio::keep_alive::{apply_keep_alive_ttl, parse_keep_alive_seconds};
@@ -22,8 +23,14 @@ use crate::lmstudio::request::{LMStudioRequestType, build_lm_studio_request};
 use crate::logging::LogConfig;
 use crate::model::ModelResolver;
 use crate::model::naming::extract_required_model_name;
+use crate::storage::GenerateContextTurn;
+use crate::storage::generate_context::{GenerateExchange, history_messages, prompt_with_history};
 
-use super::resolution::{make_top_level_params, resolve_model_with_context};
+use super::resolution::{
+    ModelResolutionContext, fetch_model_info_for_id, make_top_level_params,
+    resolve_model_with_context, resolve_reasoning_mode, resolve_request_target,
+};
+use super::transform::UpstreamRequest;
 use super::unload_only::{UnloadOnlyCall, is_generate_unload_only, respond_unload_only};
 
 pub async fn handle_ollama_generate(
@@ -33,6 +40,7 @@ pub async fn handle_ollama_generate(
     cancellation_token: CancellationToken,
     load_timeout_seconds: u64,
     auto_evict: bool,
+    reasoning_mode: ReasoningMode,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
     let ollama_model_name = extract_required_model_name(&body)?.to_string();
@@ -56,18 +64,30 @@ pub async fn handle_ollama_generate(
         .await;
     }
 
+    // A malformed body is a 400 whether or not the model resolves.
+    GenerateInput::from_body(&body)?;
+    let target = resolve_request_target(
+        &context,
+        &model_resolver,
+        &ollama_model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let lm_studio_model_id = target.lm_studio_model_id.clone();
+
     let operation = {
         let context = context.clone();
         let model_resolver = model_resolver.clone();
         let body = body.clone();
         let cancellation_token = cancellation_token.clone();
         let ollama_model_name = ollama_model_name.clone();
-        move || {
+        move |load_duration: Duration| {
             let context = context.clone();
             let model_resolver = model_resolver.clone();
             let body = body.clone();
             let cancellation_token = cancellation_token.clone();
             let ollama_model_name = ollama_model_name.clone();
+            let target = target.clone();
             async move {
                 if LogConfig::get().debug_enabled {
                     log::debug!(
@@ -76,36 +96,25 @@ pub async fn handle_ollama_generate(
                     );
                 }
 
-                let current_prompt = body
-                    .get("prompt")
-                    .and_then(|p| p.as_str())
-                    .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_PROMPT))?;
-
-                let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(true);
-
-                let current_images = body.get("images");
-                let raw = body.get("raw").and_then(|v| v.as_bool()).unwrap_or(false);
-
-                // LM Studio's /api/v0/completions does not accept multimodal
-                // input and /api/v0/chat/completions always applies a chat
-                // template — so `raw + images` is unsatisfiable. Treat an
-                // empty `images` array as absent.
-                let has_images = current_images
-                    .and_then(|v| v.as_array())
-                    .is_some_and(|arr| !arr.is_empty());
-                if raw && has_images {
-                    return Err(ProxyError::bad_request(ERROR_RAW_WITH_IMAGES));
-                }
+                let input = GenerateInput::from_body(&body)?;
 
                 let resolution_ctx = resolve_model_with_context(
                     &context,
                     &model_resolver,
-                    &ollama_model_name,
+                    &target,
                     &body,
                     true,
                     cancellation_token.clone(),
                 )
                 .await?;
+                let reasoning_mode = resolve_reasoning_mode(
+                    resolution_ctx.effective_options.as_ref(),
+                    reasoning_mode,
+                )?;
+                let permit = context
+                    .model_concurrency
+                    .acquire(&resolution_ctx.lm_studio_model_id)
+                    .await?;
 
                 // Honor Ollama `num_ctx`: reload the model at the requested
                 // context window before inference. No-op when unset or already
@@ -119,140 +128,94 @@ pub async fn handle_ollama_generate(
                 )
                 .await;
 
-                let prompt_for_estimation = current_prompt;
-                let chat_messages_payload: Option<Value>;
-
-                // A non-empty `system` on a non-raw text request must frame a real
-                // system turn — so route through /api/v0/chat/completions where the
-                // model's chat template applies, mirroring the vision path. Splicing
-                // `system\n\nprompt` into a /completions body never frames system.
-                // `raw` always stays on the raw /completions path (no template).
-                let system_for_chat = if has_images || raw {
-                    None
-                } else {
-                    resolution_ctx
-                        .system_prompt
-                        .as_deref()
-                        .map(str::trim)
-                        .filter(|s| !s.is_empty())
-                };
-
-                let (lm_studio_endpoint, lm_request_type) = if has_images {
-                    let system_for_vision = if raw {
-                        None
-                    } else {
-                        resolution_ctx.system_prompt.as_deref()
-                    };
-                    chat_messages_payload = Some(build_vision_chat_messages(
-                        system_for_vision,
-                        current_prompt,
-                        current_images,
-                    ));
-                    let messages_ref = chat_messages_payload.as_ref().unwrap();
-
-                    (
-                        LM_STUDIO_NATIVE_CHAT,
-                        LMStudioRequestType::Chat {
-                            messages: messages_ref,
-                            stream,
-                        },
-                    )
-                } else if let Some(system_text) = system_for_chat {
-                    // images=None → plain string user content; the [system, user]
-                    // turn lets LM Studio's chat template frame the system prompt.
-                    chat_messages_payload = Some(build_vision_chat_messages(
-                        Some(system_text),
-                        current_prompt,
-                        None,
-                    ));
-                    let messages_ref = chat_messages_payload.as_ref().unwrap();
-
-                    (
-                        LM_STUDIO_NATIVE_CHAT,
-                        LMStudioRequestType::Chat {
-                            messages: messages_ref,
-                            stream,
-                        },
-                    )
-                } else {
-                    // No chat payload on the raw / no-system text path.
-                    (
-                        LM_STUDIO_NATIVE_COMPLETIONS,
-                        LMStudioRequestType::Completion {
-                            prompt: Cow::Borrowed(current_prompt),
-                            stream,
-                        },
-                    )
+                // `--emulate-generate-context`: exchanges behind the request's
+                // `context`, replayed ahead of this prompt.
+                let history = match &context.generate_context {
+                    Some(store) => store.history(body.get("context")).await,
+                    None => Arc::default(),
                 };
 
-                // Invariant: `raw` requests must never reach the chat template.
-                debug_assert!(
-                    !(raw && lm_studio_endpoint == LM_STUDIO_NATIVE_CHAT),
-                    "raw generate must stay on /api/v0/completions, never chat-templated"
-                );
+                let GenerateUpstream {
+                    request: upstream,
+                    prompt_for_estimation,
+                } = build_generate_upstream(
+                    &context,
+                    &model_resolver,
+                    &input,
+                    &resolution_ctx,
+                    &history,
+                    keep_alive_seconds,
+                    cancellation_token.clone(),
+                )
+                .await?;
+                let mut lm_request = upstream.body;
 
-                let routed_to_chat = lm_studio_endpoint == LM_STUDIO_NATIVE_CHAT;
-                let mut top_level_params = make_top_level_params(&body);
-                top_level_params.model_is_thinking = resolution_ctx.model_supports_thinking;
-                let suffix_val = body.get("suffix");
+                let sent_suffix = lm_request.get("suffix").is_some();
+                let request = CancellableRequest::new(context.client, cancellation_token.clone())
+                    .with_headers(context.forward_headers.clone());
+                let url = context.endpoint_url(upstream.endpoint);
+                let mut response = request
+                    .make_request(reqwest::Method::POST, &url, Some(&lm_request))
+                    .await?;
 
-                // `suffix` (fill-in-the-middle) is a /completions-only feature; drop
-                // it with a warning on any chat-routed path (vision or system turn).
-                if routed_to_chat && suffix_val.is_some() {
+                // Models without FIM support make LM Studio reject `suffix` with a
+                // 400 naming it. Fall back to a plain completion of the prefix
+                // rather than failing the editor's request outright; any other
+                // rejection is reported as it came.
+                if sent_suffix && response.status() == reqwest::StatusCode::BAD_REQUEST {
+                    let error = upstream_error(response).await;
+                    if !is_suffix_rejection(&error.message) {
+                        return Err(error);
+                    }
                     log::warn!(
-                        "Ollama options ignored (LM Studio does not support them on the chat path): suffix"
+                        "LM Studio rejected suffix for '{}' ({}); retrying without fill-in-the-middle",
+                        resolution_ctx.lm_studio_model_id,
+                        error.message
                     );
+                    if let Some(obj) = lm_request.as_object_mut() {
+                        obj.remove("suffix");
+                    }
+                    response = request
+                        .make_request(reqwest::Method::POST, &url, Some(&lm_request))
+                        .await?;
                 }
 
-                let mut lm_request = build_lm_studio_request(
-                    &resolution_ctx.lm_studio_model_id,
-                    lm_request_type,
-                    resolution_ctx.effective_options.as_ref(),
-                    None,
-                    resolution_ctx.effective_format.as_ref(),
-                    Some(&top_level_params),
-                );
-
-                if !routed_to_chat
-                    && let Some(s) = suffix_val
-                    && let Some(obj) = lm_request.as_object_mut()
-                {
-                    obj.insert("suffix".to_string(), s.clone());
-                }
-
-                apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
-
-                let response = CancellableRequest::new(context.client, cancellation_token.clone())
-                    .make_request(
-                        reqwest::Method::POST,
-                        &context.endpoint_url(lm_studio_endpoint),
-                        Some(lm_request),
-                    )
-                    .await?;
-
                 handle_response(ResponseParams {
                     response,
-                    stream,
+                    stream: input.stream,
                     is_chat: false,
                     model_name: &ollama_model_name,
                     start_time,
+                    load_duration,
                     context: ResponseContext::Generate {
-                        prompt: prompt_for_estimation.to_string(),
+                        prompt: prompt_for_estimation,
+                        generate_context: context.generate_context.clone().map(|store| {
+                            GenerateContextTurn {
+                                store,
+                                history,
+                                prompt: input.prompt.to_string(),
+                            }
+                        }),
                     },
                     cancellation_token,
+                    reasoning_mode,
+                    stream_timeouts: context.stream_timeouts,
+                    expose_stats: context.expose_stats,
+                    runtime_info: context.runtime_info.clone(),
+                    lm_studio_model_id: &resolution_ctx.lm_studio_model_id,
                 })
                 .await
+                .map(|r| permit.attach(r))
             }
         }
     };
 
     ChatLikeCall {
         context,
-        resolver: model_resolver,
         body,
         cancellation: cancellation_token,
         load_timeout_seconds,
-        ollama_model_name,
+        lm_studio_model_id,
         keep_alive_seconds,
         start_time,
         op_label: "Ollama generate",
@@ -262,3 +225,240 @@ pub async fn handle_ollama_generate(
     .run(operation)
     .await
 }
+
+/// Whether an LM Studio 400 is the model refusing `suffix` (no
+/// fill-in-the-middle support) rather than some other problem with the
+/// request.
+fn is_suffix_rejection(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    [
+        "suffix",
+        "fim",
+        "fill-in-the-middle",
+        "fill in the middle",
+        "infill",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+/// Splice earlier `--emulate-generate-context` exchanges in as chat turns
+/// just before the new user turn.
+fn insert_history(messages: &mut Value, history: &[GenerateExchange]) {
+    if history.is_empty() {
+        return;
+    }
+    if let Some(list) = messages.as_array_mut() {
+        let at = list.len().saturating_sub(1);
+        list.splice(at..at, history_messages(history));
+    }
+}
+
+/// The prompt-side fields of a generate body.
+pub struct GenerateInput<'a> {
+    pub body: &'a Value,
+    pub prompt: &'a str,
+    pub stream: bool,
+    pub images: Option<&'a Value>,
+    /// `images` is present and non-empty.
+    pub has_images: bool,
+    pub raw: bool,
+    pub suffix: Option<&'a str>,
+}
+
+impl<'a> GenerateInput<'a> {
+    pub fn from_body(body: &'a Value) -> Result<Self, ProxyError> {
+        let prompt = body
+            .get("prompt")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_PROMPT))?;
+        let images = body.get("images");
+        let raw = body.get("raw").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        // LM Studio's /api/v0/completions does not accept multimodal input
+        // and /api/v0/chat/completions always applies a chat template — so
+        // `raw + images` is unsatisfiable. Treat an empty `images` array as
+        // absent.
+        let has_images = images
+            .and_then(|v| v.as_array())
+            .is_some_and(|arr| !arr.is_empty());
+        if raw && has_images {
+            return Err(ProxyError::bad_request(ERROR_RAW_WITH_IMAGES));
+        }
+
+        Ok(Self {
+            body,
+            prompt,
+            stream: body.get("stream").and_then(|s| s.as_bool()).unwrap_or(true),
+            images,
+            has_images,
+            raw,
+            suffix: body.get("suffix").and_then(|s| s.as_str()),
+        })
+    }
+}
+
+/// A built generate request plus the prompt text used for token estimates.
+pub struct GenerateUpstream {
+    pub request: UpstreamRequest,
+    pub prompt_for_estimation: String,
+}
+
+/// The LM Studio request for a generate body against an already-resolved
+/// model, with `ttl` applied. Picking between the chat and completions
+/// endpoints may look up the model's info; nothing is sent for inference.
+pub async fn build_generate_upstream(
+    context: &RequestContext<'_>,
+    model_resolver: &Arc<ModelResolver>,
+    input: &GenerateInput<'_>,
+    resolution_ctx: &ModelResolutionContext,
+    history: &[GenerateExchange],
+    keep_alive_seconds: Option<i64>,
+    cancellation_token: CancellationToken,
+) -> Result<GenerateUpstream, ProxyError> {
+    let GenerateInput {
+        body,
+        prompt: current_prompt,
+        stream,
+        images: current_images,
+        has_images,
+        raw,
+        suffix: suffix_text,
+    } = *input;
+
+    let mut prompt_for_estimation = Cow::Borrowed(current_prompt);
+    let chat_messages_payload: Option<Value>;
+
+    // A non-empty `system` on a non-raw text request must frame a real system
+    // turn — so route through /api/v0/chat/completions where the model's chat
+    // template applies, mirroring the vision path. Splicing `system\n\nprompt`
+    // into a /completions body never frames system. `raw` always stays on the
+    // raw /completions path (no template).
+    if raw && resolution_ctx.system_prompt.is_some() {
+        log::debug!(
+            "generate: raw mode, system prompt for '{}' not applied",
+            resolution_ctx.lm_studio_model_id
+        );
+    }
+    // Fill-in-the-middle needs /completions; the global default system prompt
+    // must not push an editor's FIM request onto the chat path (where `suffix`
+    // is dropped).
+    let wants_fim = suffix_text.is_some_and(|s| !s.is_empty());
+    let system_for_chat =
+        if has_images || raw || (wants_fim && resolution_ctx.system_prompt_is_default) {
+            None
+        } else {
+            resolution_ctx
+                .system_prompt
+                .as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+        };
+
+    // Without `raw`, Ollama applies the model's template. For a
+    // chat/instruct-tuned model that means the chat endpoint, with the prompt
+    // as a single user turn. Base models, `raw`, and `suffix`
+    // (fill-in-the-middle needs /completions) keep the bare prompt.
+    let apply_chat_template = if has_images || raw {
+        false
+    } else if system_for_chat.is_some() {
+        true
+    } else if wants_fim {
+        false
+    } else {
+        fetch_model_info_for_id(
+            context,
+            model_resolver,
+            &resolution_ctx.lm_studio_model_id,
+            cancellation_token,
+        )
+        .await?
+        .is_some_and(|info| info.is_chat_tuned())
+    };
+
+    let (lm_studio_endpoint, lm_request_type) = if has_images {
+        let system_for_vision = if raw {
+            None
+        } else {
+            resolution_ctx.system_prompt.as_deref()
+        };
+        let mut messages =
+            build_vision_chat_messages(system_for_vision, current_prompt, current_images);
+        insert_history(&mut messages, history);
+        chat_messages_payload = Some(messages);
+        let messages_ref = chat_messages_payload.as_ref().unwrap();
+
+        (
+            LM_STUDIO_NATIVE_CHAT,
+            LMStudioRequestType::Chat {
+                messages: messages_ref,
+                stream,
+            },
+        )
+    } else if apply_chat_template {
+        // images=None → plain string user content; the optional [system,
+        // user] turn lets LM Studio's chat template frame the system prompt.
+        let mut messages = build_vision_chat_messages(system_for_chat, current_prompt, None);
+        insert_history(&mut messages, history);
+        chat_messages_payload = Some(messages);
+        let messages_ref = chat_messages_payload.as_ref().unwrap();
+
+        (
+            LM_STUDIO_NATIVE_CHAT,
+            LMStudioRequestType::Chat {
+                messages: messages_ref,
+                stream,
+            },
+        )
+    } else {
+        // No chat payload on the raw / base-model / FIM text path.
+        if !history.is_empty() {
+            prompt_for_estimation = Cow::Owned(prompt_with_history(history, current_prompt));
+        }
+        (
+            LM_STUDIO_NATIVE_COMPLETIONS,
+            LMStudioRequestType::Completion {
+                prompt: Cow::Borrowed(prompt_for_estimation.as_ref()),
+                stream,
+                suffix: suffix_text,
+            },
+        )
+    };
+
+    // Invariant: `raw` requests must never reach the chat template.
+    debug_assert!(
+        !(raw && lm_studio_endpoint == LM_STUDIO_NATIVE_CHAT),
+        "raw generate must stay on /api/v0/completions, never chat-templated"
+    );
+
+    let routed_to_chat = lm_studio_endpoint == LM_STUDIO_NATIVE_CHAT;
+    let mut top_level_params = make_top_level_params(body);
+    top_level_params.model_is_thinking = resolution_ctx.model_supports_thinking;
+
+    // `suffix` (fill-in-the-middle) is a /completions-only feature; drop it
+    // with a warning on any chat-routed path (vision or system turn).
+    if routed_to_chat && suffix_text.is_some() {
+        log::warn!(
+            "Ollama options ignored (LM Studio does not support them on the chat path): suffix"
+        );
+    }
+
+    let mut lm_request = build_lm_studio_request(
+        resolution_ctx.upstream_model_id(),
+        lm_request_type,
+        resolution_ctx.effective_options.as_ref(),
+        None,
+        resolution_ctx.effective_format.as_ref(),
+        Some(&top_level_params),
+    );
+    apply_keep_alive_ttl(&mut lm_request, keep_alive_seconds);
+
+    let prompt_for_estimation = prompt_for_estimation.into_owned();
+    Ok(GenerateUpstream {
+        request: UpstreamRequest {
+            endpoint: lm_studio_endpoint,
+            body: lm_request,
+        },
+        prompt_for_estimation,
+    })
+}
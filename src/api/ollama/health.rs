@@ -1,10 +1,13 @@
-use std::time::Instant;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use reqwest::Method;
 use serde_json::{Value, json};
 use tokio_util::sync::CancellationToken;
 
 use crate::api::RequestContext;
+use crate::api::health_monitor::{HealthMonitor, count_listed_models};
 use crate::constants::{
     ERROR_LM_STUDIO_UNAVAILABLE, LM_STUDIO_NATIVE_MODELS, LOG_PREFIX_ERROR, LOG_PREFIX_SUCCESS,
 };
@@ -26,6 +29,16 @@ pub async fn handle_ollama_root() -> Result<axum::response::Response, ProxyError
         }))
 }
 
+/// A bare `OPTIONS` probe of `/` or `/api` (no CORS preflight headers, which
+/// the CORS layer answers first): 204 listing what the banner accepts.
+pub async fn handle_ollama_root_options() -> axum::response::Response {
+    axum::response::Response::builder()
+        .status(http::StatusCode::NO_CONTENT)
+        .header(http::header::ALLOW, "GET, HEAD, OPTIONS")
+        .body(axum::body::Body::empty())
+        .unwrap_or_default()
+}
+
 pub async fn handle_ollama_version(version: &str) -> Result<axum::response::Response, ProxyError> {
     if LogConfig::get().debug_enabled {
         log::debug!("version request");
@@ -42,16 +55,154 @@ pub async fn handle_ollama_version(version: &str) -> Result<axum::response::Resp
     Ok(crate::http::json_response(&response))
 }
 
+/// The last direct `/health` probe of each LM Studio (everything but the
+/// live `concurrency` snapshot), reused for `--health-cache-seconds` so
+/// frequent probes don't each make a round-trip. Unreachable and unhealthy
+/// results are reused too, with the `response_time_ms` of the probe that
+/// found them. The background monitor replaces it for the primary; the
+/// fallbacks it doesn't watch are always cached here.
+pub struct HealthProbeCache {
+    ttl: Duration,
+    last: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+impl HealthProbeCache {
+    /// A zero `ttl` probes LM Studio on every request.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            last: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, lmstudio_url: &str) -> Option<Value> {
+        let last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        let (_, probe) = last
+            .get(lmstudio_url)
+            .filter(|(at, _)| at.elapsed() < self.ttl)?;
+        let mut probe = probe.clone();
+        probe["from_cache"] = json!(true);
+        Some(probe)
+    }
+
+    fn store(&self, lmstudio_url: &str, probe: &Value) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(lmstudio_url.to_string(), (Instant::now(), probe.clone()));
+    }
+
+    /// `context`'s LM Studio as the `/health` body: the cached probe while
+    /// it is fresh, otherwise a new one.
+    async fn probe(
+        &self,
+        context: &RequestContext<'_>,
+        cancellation_token: CancellationToken,
+    ) -> Result<Value, ProxyError> {
+        if let Some(probe) = self.get(context.lmstudio_url) {
+            return Ok(probe);
+        }
+        let probe = probe_lmstudio(context, cancellation_token).await?;
+        self.store(context.lmstudio_url, &probe);
+        Ok(probe)
+    }
+}
+
+/// `GET /health/live`: the process is up and serving. Never contacts LM
+/// Studio, so it stays cheap enough for a liveness probe.
+pub async fn handle_liveness() -> Result<axum::response::Response, ProxyError> {
+    Ok(crate::http::json_response(&json!({
+        "status": "alive",
+        "proxy_version": crate::VERSION,
+    })))
+}
+
+/// Whether a `/health` body reports LM Studio as usable (`/health/ready`):
+/// the primary, or with `--lmstudio-fallback-url` any listed backend.
+pub fn is_ready(health: &Value) -> bool {
+    health["status"] == "healthy"
+        || health["backends"]
+            .as_array()
+            .is_some_and(|backends| backends.iter().any(|b| b["status"] == "healthy"))
+}
+
 pub async fn handle_health_check(
     context: RequestContext<'_>,
+    monitor: Option<&HealthMonitor>,
+    probe_cache: &HealthProbeCache,
     cancellation_token: CancellationToken,
 ) -> Result<Value, ProxyError> {
-    let start_time = Instant::now();
     if LogConfig::get().debug_enabled {
         log::debug!("health check request");
     }
+    let mut response = match monitor.and_then(|m| cached_health(&context, m)) {
+        Some(response) => response,
+        None => {
+            let mut response = probe_cache
+                .probe(&context, cancellation_token.clone())
+                .await?;
+            response["concurrency"] = json!(context.model_concurrency.snapshot());
+            response
+        }
+    };
+    if context.backends.has_fallback() {
+        response["backends"] =
+            backend_health(&context, &response, probe_cache, cancellation_token).await?;
+    }
+    if LogConfig::get().debug_enabled {
+        log::debug!(
+            "health check response: {}",
+            serde_json::to_string_pretty(&response).unwrap_or_default()
+        );
+    }
+    Ok(response)
+}
+
+/// `/health`'s `backends` list, in preference order. The backend `current`
+/// was built for keeps its status; one whose breaker is open is unreachable
+/// without asking it again, and any other comes from `probe_cache`.
+async fn backend_health(
+    context: &RequestContext<'_>,
+    current: &Value,
+    probe_cache: &HealthProbeCache,
+    cancellation_token: CancellationToken,
+) -> Result<Value, ProxyError> {
+    let mut backends = Vec::new();
+    for backend in context.backends.all() {
+        let status = if backend.url == context.lmstudio_url {
+            current["status"].clone()
+        } else if backend.is_tripped() {
+            json!("unreachable")
+        } else {
+            let backend_context = RequestContext {
+                lmstudio_url: &backend.url,
+                ..context.clone()
+            };
+            probe_cache
+                .probe(&backend_context, cancellation_token.clone())
+                .await?["status"]
+                .clone()
+        };
+        backends.push(json!({
+            "url": backend.url,
+            "status": status,
+            "circuit_open": backend.is_tripped(),
+        }));
+    }
+    Ok(json!(backends))
+}
+
+/// One round-trip to LM Studio's model list, as the `/health` body.
+async fn probe_lmstudio(
+    context: &RequestContext<'_>,
+    cancellation_token: CancellationToken,
+) -> Result<Value, ProxyError> {
+    let start_time = Instant::now();
     let url = context.endpoint_url(LM_STUDIO_NATIVE_MODELS);
-    let request = CancellableRequest::new(context.client, cancellation_token.clone());
+    let request = CancellableRequest::new(context.client, cancellation_token);
 
     match request.make_request(Method::GET, &url, None::<Value>).await {
         Ok(response) => {
@@ -60,12 +211,7 @@ pub async fn handle_health_check(
             let mut model_count = 0;
 
             if is_healthy && let Ok(models_response) = response.json::<Value>().await {
-                model_count = models_response
-                    .get("models")
-                    .or_else(|| models_response.get("data"))
-                    .and_then(|d| d.as_array())
-                    .map(|arr| arr.len())
-                    .unwrap_or(0);
+                model_count = count_listed_models(&models_response);
             }
 
             log_timed(
@@ -78,22 +224,15 @@ pub async fn handle_health_check(
                 start_time,
             );
 
-            let response = json!({
+            Ok(json!({
                 "status": if is_healthy { "healthy" } else { "unhealthy" },
                 "lmstudio_url": context.lmstudio_url,
                 "http_status": status.as_u16(),
                 "models_known_to_lmstudio": model_count,
                 "response_time_ms": start_time.elapsed().as_millis(),
                 "timestamp": chrono::Utc::now().to_rfc3339(),
-                "proxy_version": crate::VERSION
-            });
-            if LogConfig::get().debug_enabled {
-                log::debug!(
-                    "health check response: {}",
-                    serde_json::to_string_pretty(&response).unwrap_or_default()
-                );
-            }
-            Ok(response)
+                "proxy_version": crate::VERSION,
+            }))
         }
         Err(e) if e.is_cancelled() => Err(ProxyError::request_cancelled()),
         Err(e) => {
@@ -102,26 +241,44 @@ pub async fn handle_health_check(
                 &format!("health check failed: {}", e.message),
                 start_time,
             );
-            let response = json!({
+            Ok(json!({
                 "status": "unreachable",
                 "lmstudio_url": context.lmstudio_url,
                 "error_message": e.message,
                 "error_details": ERROR_LM_STUDIO_UNAVAILABLE,
                 "response_time_ms": start_time.elapsed().as_millis(),
                 "timestamp": chrono::Utc::now().to_rfc3339(),
-                "proxy_version": crate::VERSION
-            });
-            if LogConfig::get().debug_enabled {
-                log::debug!(
-                    "health check response (error): {}",
-                    serde_json::to_string_pretty(&response).unwrap_or_default()
-                );
-            }
-            Ok(response)
+                "proxy_version": crate::VERSION,
+            }))
         }
     }
 }
 
+/// `/health` from the background monitor's last probe, flagged with
+/// `"from_monitor": true`; `None` until the first probe has finished.
+fn cached_health(context: &RequestContext<'_>, monitor: &HealthMonitor) -> Option<Value> {
+    let (status, probe) = monitor.snapshot()?;
+    let mut response = json!({
+        "status": status.as_str(),
+        "lmstudio_url": context.lmstudio_url,
+        "models_known_to_lmstudio": probe.model_count,
+        "response_time_ms": probe.response_time_ms,
+        "timestamp": probe.checked_at.map(|t| t.to_rfc3339()),
+        "last_healthy_at": probe.last_healthy_at.map(|t| t.to_rfc3339()),
+        "from_monitor": true,
+        "proxy_version": crate::VERSION,
+        "concurrency": context.model_concurrency.snapshot()
+    });
+    if let Some(http_status) = probe.http_status {
+        response["http_status"] = json!(http_status);
+    }
+    if let Some(error) = probe.error {
+        response["error_message"] = json!(error);
+        response["error_details"] = json!(ERROR_LM_STUDIO_UNAVAILABLE);
+    }
+    Some(response)
+}
+
 #[cfg(test)]
 #[path = "../../../tests/unit/handlers_ollama_health.rs"]
 mod tests;
@@ -2,10 +2,10 @@ use std::time::Instant;
 
 use http::StatusCode;
 use serde_json::{Value, json};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot, watch};
 use tokio_util::sync::CancellationToken;
 
-use crate::api::RequestContext;
+use crate::api::{PullFollower, PullSlot, RequestContext, SharedPullStatus};
 use crate::constants::LOG_PREFIX_SUCCESS;
 use crate::error::ProxyError;
 use crate::http::json_response;
@@ -13,15 +13,17 @@ use crate::logging::{log_request, log_timed};
 use crate::model::ModelResolver;
 use crate::model::naming::extract_required_model_name;
 use crate::storage::VirtualModelStore;
-use crate::storage::virtual_models::VirtualModelMetadata;
 use std::sync::Arc;
 
-use super::resolution::resolve_model_target;
-use super::status_stream::{send_status_error_chunk, stream_status_messages};
-use crate::lmstudio::download::{determine_download_identifier, looks_like_remote_identifier};
+use super::resolution::{resolve_model_target, resolve_model_target_and_fetch};
+use super::status_stream::{send_status_chunk, send_status_error_chunk, stream_status_messages};
 use crate::lmstudio::download::{
-    initiate_lmstudio_download, stream_download_status_updates, wait_for_download_completion,
+    LmStudioDownloadStatus, initiate_lmstudio_download, normalize_download_identifier,
+    stream_download_status_updates, wait_for_download_completion,
 };
+use crate::lmstudio::download::{determine_download_identifier, looks_like_remote_identifier};
+use crate::lmstudio::keep_alive::unload_model_instances;
+use crate::lmstudio::request::collect_unknown_option_keys;
 use crate::logging::log_handler_io;
 use crate::streaming::create_ndjson_stream_response;
 
@@ -53,7 +55,7 @@ pub async fn handle_ollama_pull(
     let resolved_model_context =
         if source_override.is_none() && !looks_like_remote_identifier(requested_model) {
             Some(
-                resolve_model_target(
+                resolve_model_target_and_fetch(
                     &context,
                     &model_resolver,
                     requested_model,
@@ -65,39 +67,85 @@ pub async fn handle_ollama_pull(
             None
         };
 
-    let download_identifier = determine_download_identifier(
-        &context,
-        &model_resolver,
-        requested_model,
-        source_override,
-        resolved_model_context,
-        cancellation_token.clone(),
-    )
-    .await?;
+    let download_identifier =
+        determine_download_identifier(requested_model, source_override, resolved_model_context);
+
+    // A pull of something already being pulled follows that download
+    // rather than asking LM Studio for a second one.
+    let pull_key = format!(
+        "{}#{}",
+        normalize_download_identifier(&download_identifier)?,
+        quantization.as_deref().unwrap_or_default()
+    );
+    let lead = match context.pull_registry.claim(&pull_key) {
+        PullSlot::Lead(lead) => lead,
+        PullSlot::Follow(follower) => {
+            log::info!(
+                "pull: '{}' attached to the running download",
+                requested_model
+            );
+            return follow_shared_pull(requested_model, stream, follower).await;
+        }
+    };
 
-    let initial_status = initiate_lmstudio_download(
+    let initial_status = match initiate_lmstudio_download(
         &client,
         &base_url,
         &download_identifier,
         quantization.as_deref(),
         cancellation_token.clone(),
     )
-    .await?;
+    .await
+    {
+        Ok(status) => status,
+        Err(e) => {
+            lead.publish(Err(e.clone()));
+            return Err(e);
+        }
+    };
+    lead.publish(Ok(initial_status.clone()));
+    let attachment = lead.attach();
+
+    // Dropping the job before the download finishes (every attached client
+    // gone, pull cancelled) cancels it in LM Studio too, as aborting an
+    // Ollama pull does.
+    let running_job_id = initial_status
+        .job_id
+        .as_deref()
+        .filter(|_| !initial_status.is_terminal());
+    let job = context
+        .pull_registry
+        .track(
+            running_job_id,
+            requested_model,
+            cancellation_token.child_token(),
+            client.clone(),
+            base_url.clone(),
+        )
+        .share_with(lead);
 
     if !stream {
         let final_status = if initial_status.is_terminal() {
             initial_status
         } else {
-            wait_for_download_completion(
-                &client,
-                &base_url,
-                initial_status,
-                cancellation_token.clone(),
-            )
-            .await?
+            // Poll from a task of its own so the download outlives this
+            // request while a follower still waits on it; `attachment` marks
+            // this client as attached until the handler is dropped.
+            let (done_tx, done_rx) = oneshot::channel();
+            crate::logging::spawn_with_request_id(async move {
+                let result =
+                    wait_for_download_completion(&client, &base_url, initial_status, job).await;
+                let _ = done_tx.send(result);
+            });
+            let result = done_rx
+                .await
+                .map_err(|_| ProxyError::internal_server_error("pull: download watcher stopped"))?;
+            result?
         };
 
         let response_body = final_status.into_final_response(requested_model)?;
+        // A finished download can change which id a name resolves to.
+        model_resolver.invalidate_all().await;
         log_timed(LOG_PREFIX_SUCCESS, "Ollama pull", start_time);
         log_handler_io("pull", None, Some(&response_body));
         return Ok(json_response(&response_body));
@@ -107,21 +155,29 @@ pub async fn handle_ollama_pull(
     let stream_client = client.clone();
     let stream_base_url = base_url.clone();
     let model_for_stream = requested_model.to_string();
-    let token_for_stream = cancellation_token.clone();
+    let resolver_for_stream = model_resolver.clone();
 
-    tokio::spawn(async move {
-        if let Err(e) = stream_download_status_updates(
+    crate::logging::spawn_with_request_id(async move {
+        match stream_download_status_updates(
             stream_client,
             stream_base_url,
             initial_status,
             model_for_stream.clone(),
-            token_for_stream,
+            job,
+            Some(attachment),
             tx.clone(),
         )
         .await
         {
-            log::error!("Ollama pull stream: {}", e.message);
-            send_status_error_chunk(&tx, &e.message);
+            Ok(()) => resolver_for_stream.invalidate_all().await,
+            Err(e) if e.is_cancelled() => {
+                log::info!("Ollama pull stream: {} cancelled", model_for_stream);
+                send_status_error_chunk(&tx, "download cancelled");
+            }
+            Err(e) => {
+                log::error!("Ollama pull stream: {}", e.message);
+                send_status_error_chunk(&tx, &e.message);
+            }
         }
     });
 
@@ -131,10 +187,109 @@ pub async fn handle_ollama_pull(
     Ok(response)
 }
 
+/// The next status the leading pull publishes, or `None` once it has gone
+/// without LM Studio reporting an end (cancelled, client gone, polling failed).
+async fn next_shared_status(
+    shared: &mut watch::Receiver<SharedPullStatus>,
+) -> Option<Result<LmStudioDownloadStatus, ProxyError>> {
+    loop {
+        shared.changed().await.ok()?;
+        if let Some(status) = shared.borrow_and_update().clone() {
+            return Some(status);
+        }
+    }
+}
+
+/// Serve a pull attached to one already running for the same identifier,
+/// relaying the leader's progress. Only the leader polls LM Studio; the
+/// download carries on while any attached pull still waits on it and is
+/// cancelled once the last one leaves or on `DELETE /api/pull`.
+async fn follow_shared_pull(
+    requested_model: &str,
+    stream: bool,
+    follower: PullFollower,
+) -> Result<axum::response::Response, ProxyError> {
+    const ENDED_EARLY: &str = "download ended before completing";
+
+    let PullFollower {
+        status: mut shared,
+        attachment,
+    } = follower;
+
+    if !stream {
+        loop {
+            match next_shared_status(&mut shared).await {
+                Some(Ok(status)) if status.is_terminal() => {
+                    return Ok(json_response(&status.into_final_response(requested_model)?));
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Err(ProxyError::internal_server_error(ENDED_EARLY)),
+            }
+        }
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let model_for_stream = requested_model.to_string();
+    crate::logging::spawn_with_request_id(async move {
+        let _attachment = attachment;
+        loop {
+            match next_shared_status(&mut shared).await {
+                Some(Ok(status)) => {
+                    if !send_status_chunk(&tx, &status.to_chunk(&model_for_stream)) {
+                        return;
+                    }
+                    if status.is_failure() {
+                        let message = status
+                            .error
+                            .unwrap_or_else(|| "LM Studio download failed".to_string());
+                        send_status_error_chunk(&tx, &message);
+                        return;
+                    }
+                    if status.is_terminal() {
+                        return;
+                    }
+                }
+                Some(Err(e)) => {
+                    send_status_error_chunk(&tx, &e.message);
+                    return;
+                }
+                None => {
+                    send_status_error_chunk(&tx, ENDED_EARLY);
+                    return;
+                }
+            }
+        }
+    });
+    create_ndjson_stream_response(rx, "failed to create pull streaming response")
+}
+
+/// `DELETE /api/pull`: stop an in-flight download named by `model` or
+/// `job_id`, cancelling it in LM Studio. Answers with the last
+/// status the pull reported; 404 when no such download is running.
+pub async fn handle_ollama_pull_cancel(
+    context: RequestContext<'_>,
+    body: Value,
+) -> Result<axum::response::Response, ProxyError> {
+    log_handler_io("pull cancel", Some(&body), None);
+    let target = match body.get("job_id").and_then(|v| v.as_str()) {
+        Some(job_id) => job_id,
+        None => extract_required_model_name(&body)?,
+    };
+    log_request("DELETE", "/api/pull", Some(target));
+
+    let cancelled = context.pull_registry.cancel(target).ok_or_else(|| {
+        ProxyError::not_found(&format!("no download in progress for '{}'", target))
+    })?;
+    log_handler_io("pull cancel", None, Some(&cancelled));
+    Ok(json_response(&cancelled))
+}
+
 pub async fn handle_ollama_create(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
     body: Value,
+    strict_params: bool,
     cancellation_token: CancellationToken,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
@@ -167,6 +322,8 @@ pub async fn handle_ollama_create(
         ));
     }
 
+    let warnings = check_alias_parameters(&body, strict_params)?;
+
     // `from` is required unless a system prompt or template is the only
     // customization (both still need a base model to alias). Silently
     // defaulting to `model` produces a self-referential alias that resolves
@@ -176,49 +333,114 @@ pub async fn handle_ollama_create(
         .and_then(|v| v.as_str())
         .ok_or_else(|| ProxyError::bad_request("'from' is required"))?;
 
+    // Aliases may target models hidden by the allow/blocklist.
+    let alias_resolver = Arc::new(model_resolver.without_filter());
     let (resolved_id, source_virtual_entry) = resolve_model_target(
         &context,
-        &model_resolver,
+        &alias_resolver,
         source_model_name,
         cancellation_token,
     )
     .await?;
 
+    // Each status is recorded only once its step has actually happened, so a
+    // client gating on the stream never sees `success` for an alias that was
+    // not saved.
+    let mut statuses = vec![json!({"status": "reading model metadata"})];
+
+    // An alias onto a model no cached name resolves to suggests LM Studio's
+    // catalog has moved on since those entries were cached.
+    if !model_resolver.has_cached_target(&resolved_id) {
+        model_resolver.invalidate_all().await;
+    }
+
+    let is_child = source_virtual_entry.is_some();
     let base_metadata = source_virtual_entry.map(|entry| entry.metadata);
     let metadata = VirtualModelStore::build_metadata_from_request(&body, base_metadata);
 
-    context
-        .virtual_models
-        .upsert_alias(
-            new_model_name,
-            source_model_name.to_string(),
-            resolved_id,
-            metadata,
-        )
-        .await?;
-
-    log_timed(LOG_PREFIX_SUCCESS, "Ollama create", start_time);
+    let created = if is_child {
+        context
+            .virtual_models
+            .upsert_child_alias(
+                new_model_name,
+                source_model_name.to_string(),
+                resolved_id,
+                metadata,
+            )
+            .await
+    } else {
+        context
+            .virtual_models
+            .upsert_alias(
+                new_model_name,
+                source_model_name.to_string(),
+                resolved_id,
+                metadata,
+            )
+            .await
+    };
+
+    match created {
+        Ok(_) => {
+            statuses.push(json!({"status": "creating alias"}));
+            statuses.push(with_warnings(json!({"status": "success"}), &warnings));
+            log_timed(LOG_PREFIX_SUCCESS, "Ollama create", start_time);
+        }
+        Err(e) if stream => {
+            log::error!("Ollama create: {}", e.message);
+            statuses.push(json!({"status": "failed", "error": e.message}));
+        }
+        Err(e) => return Err(e),
+    }
 
     if stream {
-        let statuses = vec![
-            json!({"status": "reading model metadata"}),
-            json!({"status": "creating alias"}),
-            json!({"status": "writing manifest"}),
-            json!({"status": "success"}),
-        ];
         log_handler_io("create", None, None);
         return stream_status_messages(statuses, "failed to create model alias stream");
     }
 
-    let response = json!({"status": "success"});
+    let response = with_warnings(json!({"status": "success"}), &warnings);
     log_handler_io("create", None, Some(&response));
     Ok(json_response(&response))
 }
 
+/// Flag `parameters` keys the request mapper would ignore. Each becomes a
+/// warning (also logged), or a 400 with `--strict-params`.
+fn check_alias_parameters(body: &Value, strict_params: bool) -> Result<Vec<String>, ProxyError> {
+    let unknown = body
+        .get("parameters")
+        .map(collect_unknown_option_keys)
+        .unwrap_or_default();
+    if unknown.is_empty() {
+        return Ok(Vec::new());
+    }
+    if strict_params {
+        return Err(ProxyError::bad_request(&format!(
+            "unknown parameters: {}",
+            unknown.join(", ")
+        )));
+    }
+    let warnings: Vec<String> = unknown
+        .iter()
+        .map(|key| format!("unknown parameter '{}' has no effect", key))
+        .collect();
+    for warning in &warnings {
+        log::warn!("alias {}", warning);
+    }
+    Ok(warnings)
+}
+
+fn with_warnings(mut response: Value, warnings: &[String]) -> Value {
+    if !warnings.is_empty() {
+        response["warnings"] = json!(warnings);
+    }
+    response
+}
+
 pub async fn handle_ollama_copy(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
     body: Value,
+    strict_params: bool,
     cancellation_token: CancellationToken,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
@@ -237,19 +459,40 @@ pub async fn handle_ollama_copy(
 
     log_request("POST", "/api/copy", Some(destination));
 
+    // Copy answers with an empty body, so warnings only reach the log.
+    check_alias_parameters(&body, strict_params)?;
+
+    // Optional `system` / `template` / `parameters` / ... in the body are
+    // layered over the source's metadata, exactly as `create` does, so a copy
+    // can tweak the alias while inheriting everything it doesn't mention.
     if let Some(existing) = context.virtual_models.get(source).await {
-        context
-            .virtual_models
-            .upsert_alias(
-                destination,
-                existing.source_model.clone(),
-                existing.target_model_id.clone(),
-                existing.metadata.clone(),
-            )
-            .await?;
+        let metadata =
+            VirtualModelStore::build_metadata_from_request(&body, Some(existing.metadata));
+        // A copy of a child alias is a sibling: it follows the same parent.
+        match existing.parent_alias {
+            Some(parent) => {
+                context
+                    .virtual_models
+                    .upsert_child_alias(destination, parent, existing.target_model_id, metadata)
+                    .await?
+            }
+            None => {
+                context
+                    .virtual_models
+                    .upsert_alias(
+                        destination,
+                        existing.source_model,
+                        existing.target_model_id,
+                        metadata,
+                    )
+                    .await?
+            }
+        };
     } else {
+        // Aliases may target models hidden by the allow/blocklist.
+        let alias_resolver = Arc::new(model_resolver.without_filter());
         let (resolved_id, _) =
-            resolve_model_target(&context, &model_resolver, source, cancellation_token).await?;
+            resolve_model_target(&context, &alias_resolver, source, cancellation_token).await?;
 
         context
             .virtual_models
@@ -257,7 +500,7 @@ pub async fn handle_ollama_copy(
                 destination,
                 source.to_string(),
                 resolved_id,
-                VirtualModelMetadata::default(),
+                VirtualModelStore::build_metadata_from_request(&body, None),
             )
             .await?;
     }
@@ -274,21 +517,37 @@ pub async fn handle_ollama_copy(
 
 pub async fn handle_ollama_delete(
     context: RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
     body: Value,
+    delete_unloads: bool,
+    cancellation_token: CancellationToken,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
     log_handler_io("delete", Some(&body), None);
     let model_name = extract_required_model_name(&body)?;
     log_request("DELETE", "/api/delete", Some(model_name));
 
-    // Only proxy-managed virtual aliases can be deleted. Native LM Studio models
-    // are not writable through this proxy, so any unknown name returns 404.
+    // Proxy-managed virtual aliases are deleted outright. Native LM Studio
+    // models are not writable through this proxy: with `--delete-unloads` the
+    // closest equivalent is evicting them from memory, otherwise 404.
     if context.virtual_models.get(model_name).await.is_none() {
-        return Err(ProxyError::not_found(&format!(
-            "model '{}' cannot be deleted: only proxy-managed virtual aliases are deletable; \
-             LM Studio's REST API exposes no model-file delete",
-            model_name
-        )));
+        if !delete_unloads {
+            return Err(ProxyError::not_found(&format!(
+                "model '{}' cannot be deleted: only proxy-managed virtual aliases are deletable; \
+                 LM Studio's REST API exposes no model-file delete",
+                model_name
+            )));
+        }
+
+        // Resolve first so an unknown name is a 404, not a silent no-op.
+        let (model_id, _) =
+            resolve_model_target(&context, &model_resolver, model_name, cancellation_token).await?;
+        unload_model_instances(context.client, context.lmstudio_url, &model_id).await?;
+
+        let response = json!({ "status": "success" });
+        log_timed(LOG_PREFIX_SUCCESS, "Ollama delete (unload)", start_time);
+        log_handler_io("delete", None, Some(&response));
+        return Ok(json_response(&response));
     }
 
     context.virtual_models.delete(model_name).await?;
@@ -1,20 +1,31 @@
+pub mod auto_pull;
 pub mod blobs;
 pub mod chat;
+pub mod context_guard;
 pub mod embeddings;
 pub mod generate;
 pub mod health;
 pub mod lifecycle;
 pub mod models;
+pub mod registry;
 pub mod resolution;
 pub mod status_stream;
+pub mod transform;
 pub mod unload_only;
 
-pub use blobs::{handle_blob_head, handle_blob_upload};
+pub use auto_pull::{AutoPull, serve_after_pull, start_missing_model_pull};
+pub use blobs::{handle_blob_delete, handle_blob_head, handle_blob_upload};
 pub use chat::{ChatOptions, handle_ollama_chat};
 pub use embeddings::{EmbeddingResponseMode, handle_ollama_embeddings};
 pub use generate::handle_ollama_generate;
-pub use health::{handle_health_check, handle_ollama_root, handle_ollama_version};
+pub use health::{
+    HealthProbeCache, handle_health_check, handle_liveness, handle_ollama_root,
+    handle_ollama_root_options, handle_ollama_version, is_ready,
+};
 pub use lifecycle::{
     handle_ollama_copy, handle_ollama_create, handle_ollama_delete, handle_ollama_pull,
+    handle_ollama_pull_cancel,
 };
 pub use models::{handle_ollama_ps, handle_ollama_show, handle_ollama_tags};
+pub use registry::{RegistryPath, handle_registry_blob, handle_registry_manifest};
+pub use transform::{TransformOptions, handle_transform_debug};
@@ -13,15 +13,17 @@ use crate::logging::{LogConfig, log_request, log_timed};
 use crate::model::ModelResolver;
 use std::sync::Arc;
 
-use super::resolution::resolve_model_target;
+use super::resolution::resolve_model_target_and_list;
 use crate::logging::log_handler_io;
 use crate::model::naming::extract_required_model_name;
 use crate::model::types::ModelInfo;
+use crate::storage::ModelTimestampStore;
 
 pub async fn handle_ollama_show(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
     body: Value,
+    match_debug: bool,
     cancellation_token: CancellationToken,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
@@ -36,10 +38,9 @@ pub async fn handle_ollama_show(
 
     log_request("POST", "/api/show", Some(ollama_model_name));
 
-    trigger_model_loading_for_ollama(&context, ollama_model_name, cancellation_token.clone())
-        .await?;
-
-    let (resolved_id, virtual_entry) = resolve_model_target(
+    // One model-list retrieval serves resolution, the warm-up, the lookup
+    // and the match debug below.
+    let (resolved_id, virtual_entry, models) = resolve_model_target_and_list(
         &context,
         &model_resolver,
         ollama_model_name,
@@ -47,10 +48,6 @@ pub async fn handle_ollama_show(
     )
     .await?;
 
-    let models = model_resolver
-        .get_all_models(context.client, cancellation_token)
-        .await?;
-
     let base_model = models.iter().find(|m| m.id == resolved_id);
 
     let Some(model) = base_model else {
@@ -60,6 +57,9 @@ pub async fn handle_ollama_show(
         )));
     };
 
+    trigger_model_loading_for_ollama(&context, &resolved_id, Some(model), cancellation_token)
+        .await?;
+
     let verbose = body
         .get("verbose")
         .and_then(|v| v.as_bool())
@@ -67,6 +67,23 @@ pub async fn handle_ollama_show(
 
     let alias_metadata = virtual_entry.as_ref().map(|entry| &entry.metadata);
     let mut response = model.to_show_response(alias_metadata, verbose);
+    if let Some(runtime) = context.runtime_info.get(&model.id) {
+        runtime.apply_to_show(&mut response, verbose);
+    }
+
+    if match_debug {
+        let debug = build_match_debug(
+            &model_resolver,
+            ollama_model_name,
+            &resolved_id,
+            virtual_entry.is_some(),
+            &models,
+        )
+        .await;
+        if let Some(obj) = response.as_object_mut() {
+            obj.insert("proxy_match_debug".to_string(), debug);
+        }
+    }
 
     if let Some(entry) = virtual_entry
         && let Some(obj) = response.as_object_mut()
@@ -74,7 +91,10 @@ pub async fn handle_ollama_show(
         obj.insert("virtual".to_string(), json!(true));
         obj.insert("alias_name".to_string(), json!(entry.name));
         obj.insert("source_model".to_string(), json!(entry.source_model));
-        obj.insert("target_model_id".to_string(), json!(entry.target_model_id));
+        if let Some(parent) = &entry.parent_alias {
+            obj.insert("parent_alias".to_string(), json!(parent));
+        }
+        obj.insert("target_model_id".to_string(), json!(resolved_id));
         // Virtual aliases carry a real updated_at — surface it as modified_at
         // since the proxy persists it on every alias edit.
         obj.insert(
@@ -95,6 +115,42 @@ pub async fn handle_ollama_show(
     Ok(json_response(&response))
 }
 
+/// Resolver diagnostics for `POST /api/show?debug=true`: every LM Studio
+/// model's match score for the requested name, highest first. A virtual alias
+/// bypasses the matcher entirely, so its candidate list is empty.
+async fn build_match_debug(
+    model_resolver: &ModelResolver,
+    requested: &str,
+    resolved_id: &str,
+    via_alias: bool,
+    models: &[ModelInfo],
+) -> Value {
+    let candidates: Vec<Value> = if via_alias {
+        Vec::new()
+    } else {
+        model_resolver
+            .explain_match(requested, models)
+            .await
+            .into_iter()
+            .map(|c| {
+                json!({
+                    "id": c.id,
+                    "score": c.score,
+                    "stage": c.stage.as_str(),
+                    "loaded": c.is_loaded,
+                    "selected": c.id == resolved_id,
+                })
+            })
+            .collect()
+    };
+    json!({
+        "query": requested,
+        "resolved_id": resolved_id,
+        "resolved_via": if via_alias { "virtual_alias" } else { "matcher" },
+        "candidates": candidates,
+    })
+}
+
 pub async fn handle_ollama_ps(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
@@ -102,23 +158,32 @@ pub async fn handle_ollama_ps(
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
 
-    let loaded_models = model_resolver
-        .get_loaded_models(context.client, cancellation_token)
-        .await?;
-
-    let virtual_entries = context.virtual_models.list().await;
+    let (loaded_models, virtual_entries) = tokio::join!(
+        model_resolver.get_loaded_models(context.client, cancellation_token),
+        context.virtual_models.list_resolved()
+    );
+    // A model LM Studio has loaded several times is listed once per
+    // instance, so clients can see (and target) each copy.
+    let loaded_models: Vec<ModelInfo> = loaded_models?
+        .iter()
+        .flat_map(ModelInfo::instance_views)
+        .collect();
     let loaded_virtuals: Vec<_> = virtual_entries
         .into_iter()
         .filter(|entry| loaded_models.iter().any(|m| m.id == entry.target_model_id))
         .collect();
 
-    // Best-effort real `expires_at`: only models the proxy itself loaded/kept
-    // alive are tracked; others fall back to the placeholder inside
-    // `to_ollama_ps_model`. Virtual aliases resolve to their target's deadline.
+    // Real `expires_at`: only models the proxy itself loaded/kept alive with a
+    // known keep_alive are tracked; others omit the field. Virtual aliases
+    // resolve to their target's deadline.
     let load_tracker = context.load_tracker.clone();
-    let ollama_models = ModelInfo::merge_with_virtuals(&loaded_models, &loaded_virtuals, |m| {
-        m.to_ollama_ps_model(load_tracker.expires_at_unix(&m.id))
-    });
+    let ollama_models = ModelInfo::merge_with_virtuals_or_orphans(
+        &loaded_models,
+        &loaded_virtuals,
+        &context.model_filter,
+        |m| m.to_ollama_ps_model(load_tracker.expires_at_unix(&m.id)),
+        |_| None,
+    );
 
     let response = json!({ "models": ollama_models });
     log_timed(LOG_PREFIX_SUCCESS, "Ollama ps", start_time);
@@ -129,24 +194,28 @@ pub async fn handle_ollama_ps(
 pub async fn handle_ollama_tags(
     context: RequestContext<'_>,
     model_resolver: Arc<ModelResolver>,
+    model_timestamps: Arc<ModelTimestampStore>,
     cancellation_token: CancellationToken,
 ) -> Result<axum::response::Response, ProxyError> {
     let start_time = Instant::now();
 
-    let models = model_resolver
-        .get_all_models(context.client, cancellation_token)
-        .await?;
-
-    let virtual_entries = context.virtual_models.list().await;
-    let mut ollama_models =
-        ModelInfo::merge_with_virtuals(&models, &virtual_entries, |m| m.to_ollama_tags_model());
-
-    for entry in &virtual_entries {
-        if models.iter().all(|m| m.id != entry.target_model_id) {
+    let (models, virtual_entries) = tokio::join!(
+        model_resolver.get_all_models(context.client, cancellation_token),
+        context.virtual_models.list_resolved()
+    );
+    let models = models?;
+    let first_seen = model_timestamps.observe(&models).await;
+
+    let ollama_models = ModelInfo::merge_with_virtuals_or_orphans(
+        &models,
+        &virtual_entries,
+        &context.model_filter,
+        |m| m.to_ollama_tags_model(first_seen.get(&m.id).copied()),
+        |entry| {
             // Orphan alias — its target was removed from LM Studio so we have
             // no real metadata. Emit the same shape as a real entry with zeros
             // for size/context so clients don't fall back to their own defaults.
-            ollama_models.push(json!({
+            Some(json!({
                 "name": entry.name,
                 "model": entry.name,
                 "modified_at": entry.updated_at.to_rfc3339(),
@@ -163,9 +232,9 @@ pub async fn handle_ollama_tags(
                     "context_length": 0,
                     "max_context_length": 0
                 }
-            }));
-        }
-    }
+            }))
+        },
+    );
 
     let response = json!({ "models": ollama_models });
     log_timed(LOG_PREFIX_SUCCESS, "Ollama tags", start_time);
@@ -0,0 +1,207 @@
+//! Read-only shim of the registry endpoints some tools call on an Ollama
+//! server to introspect models: `GET /v2/{name}/manifests/{tag}` and
+//! `GET /v2/{name}/blobs/{digest}`.
+//!
+//! LM Studio has no layers or manifests, so the manifest is fabricated from
+//! [`ModelInfo`]: a small config blob carrying the digest and estimated size
+//! `/api/tags` reports, and no layers, since the weights cannot be fetched
+//! through the proxy. Every digest a manifest names can be fetched: the
+//! config blob is served from the model it describes, and anything else only
+//! when it was uploaded through `/api/blobs`.
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::response::Response;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::RequestContext;
+use crate::error::ProxyError;
+use crate::logging::{LogConfig, log_request};
+use crate::model::ModelResolver;
+use crate::model::types::ModelInfo;
+
+use super::resolution::resolve_model_target;
+
+pub const MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const CONFIG_MEDIA_TYPE: &str = "application/vnd.docker.container.image.v1+json";
+/// Namespace real Ollama puts unqualified model names in (`library/llama3`).
+const DEFAULT_NAMESPACE: &str = "library/";
+const BLOB_READ_CHUNK_BYTES: usize = 64 * 1024;
+
+/// A `/v2/...` path split into what it asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RegistryPath {
+    Manifest { name: String, reference: String },
+    Blob { name: String, digest: String },
+}
+
+impl RegistryPath {
+    /// Parse the part after `/v2/`. Names may contain slashes
+    /// (`library/llama3`, `owner/repo`), so the kind is found from the end.
+    pub fn parse(path: &str) -> Option<Self> {
+        let path = path.trim_matches('/');
+        if let Some((name, reference)) = path.rsplit_once("/manifests/") {
+            return (!name.is_empty() && !reference.is_empty() && !reference.contains('/')).then(
+                || Self::Manifest {
+                    name: name.to_string(),
+                    reference: reference.to_string(),
+                },
+            );
+        }
+        let (name, digest) = path.rsplit_once("/blobs/")?;
+        (!name.is_empty() && !digest.is_empty() && !digest.contains('/')).then(|| Self::Blob {
+            name: name.to_string(),
+            digest: digest.to_string(),
+        })
+    }
+}
+
+/// The model name a registry reference stands for: the `library/` namespace
+/// dropped and `:tag` appended unless it is `latest`.
+pub fn model_name_for_reference(name: &str, reference: &str) -> String {
+    let name = name.strip_prefix(DEFAULT_NAMESPACE).unwrap_or(name);
+    if reference == "latest" {
+        name.to_string()
+    } else {
+        format!("{}:{}", name, reference)
+    }
+}
+
+/// The fabricated config blob for `model` and its `sha256:` digest.
+fn manifest_config(model: &ModelInfo) -> (Vec<u8>, String) {
+    let params = model.parse_parameters();
+    let config = json!({
+        "model_format": model.compatibility_type,
+        "model_family": model.arch,
+        "model_families": [model.arch],
+        "model_type": params.size_string,
+        "file_type": model.quantization,
+        "architecture": "amd64",
+        "os": "linux",
+        "model_digest": format!("sha256:{}", model.digest()),
+        "model_size": model.calculate_estimated_size(),
+    });
+    let bytes = serde_json::to_vec(&config).unwrap_or_default();
+    let digest = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+    (bytes, digest)
+}
+
+/// OCI-style manifest for `model`: its config blob and no layers. LM Studio's
+/// weights cannot be fetched through `/v2/.../blobs`, so no layer digest is
+/// advertised; the config carries the `/api/tags` digest and size instead.
+pub fn build_manifest(model: &ModelInfo) -> Value {
+    let (config, config_digest) = manifest_config(model);
+    json!({
+        "schemaVersion": 2,
+        "mediaType": MANIFEST_MEDIA_TYPE,
+        "config": {
+            "mediaType": CONFIG_MEDIA_TYPE,
+            "digest": config_digest,
+            "size": config.len(),
+        },
+        "layers": [],
+    })
+}
+
+pub async fn handle_registry_manifest(
+    context: RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
+    name: &str,
+    reference: &str,
+    cancellation_token: CancellationToken,
+) -> Result<Response, ProxyError> {
+    let model_name = model_name_for_reference(name, reference);
+    log_request("GET", "/v2/manifests", Some(&model_name));
+
+    let (resolved_id, _) = resolve_model_target(
+        &context,
+        &model_resolver,
+        &model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let models = model_resolver
+        .get_all_models(context.client, cancellation_token)
+        .await?;
+    let Some(model) = models.iter().find(|m| m.id == resolved_id) else {
+        return Err(ProxyError::not_found(&format!(
+            "manifest for '{}' not found",
+            model_name
+        )));
+    };
+
+    let manifest = serde_json::to_vec(&build_manifest(model)).map_err(|e| {
+        ProxyError::internal_server_error(&format!("failed to encode manifest: {}", e))
+    })?;
+    let manifest_digest = format!("sha256:{}", hex::encode(Sha256::digest(&manifest)));
+    if LogConfig::get().debug_enabled {
+        log::debug!("manifest for {}: {}", model_name, manifest_digest);
+    }
+
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, MANIFEST_MEDIA_TYPE)
+        .header("Docker-Content-Digest", manifest_digest)
+        .body(Body::from(manifest))
+        .map_err(|_| ProxyError::internal_server_error("failed to build manifest response"))
+}
+
+/// Stream a blob stored through `/api/blobs`, or a manifest's config blob.
+/// `name` is not checked: the proxy's blob store is shared by every model,
+/// as Ollama's is, and a config digest names its model on its own.
+pub async fn handle_registry_blob(
+    context: RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
+    digest: &str,
+    cancellation_token: CancellationToken,
+) -> Result<Response, ProxyError> {
+    if LogConfig::get().debug_enabled {
+        log::debug!("registry blob request: {}", digest);
+    }
+    let Some((file, size)) = context.blob_store.open(digest).await? else {
+        let models = model_resolver
+            .get_all_models(context.client, cancellation_token)
+            .await?;
+        let Some(config) = models
+            .iter()
+            .map(manifest_config)
+            .find_map(|(config, config_digest)| (config_digest == digest).then_some(config))
+        else {
+            return Err(ProxyError::not_found(&format!("blob {} not found", digest)));
+        };
+        return Response::builder()
+            .header(http::header::CONTENT_TYPE, "application/octet-stream")
+            .header(http::header::CONTENT_LENGTH, config.len())
+            .header("Docker-Content-Digest", digest)
+            .body(Body::from(config))
+            .map_err(|_| ProxyError::internal_server_error("failed to build blob response"));
+    };
+
+    // A read error ends the stream after reporting it.
+    let chunks = futures_util::stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0u8; BLOB_READ_CHUNK_BYTES];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(bytes::Bytes::from(buffer)), Some(file)))
+            }
+            Err(e) => Some((Err(e), None)),
+        }
+    });
+
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/octet-stream")
+        .header(http::header::CONTENT_LENGTH, size)
+        .header("Docker-Content-Digest", digest)
+        .body(Body::from_stream(chunks))
+        .map_err(|_| ProxyError::internal_server_error("failed to build blob response"))
+}
+
+#[cfg(test)]
+#[path = "../../../tests/unit/handlers_ollama_registry.rs"]
+mod tests;
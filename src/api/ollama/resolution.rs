@@ -4,10 +4,12 @@ use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
 use crate::api::RequestContext;
+use crate::config::ReasoningMode;
 use crate::error::ProxyError;
 use crate::lmstudio::request::TopLevelParams;
 use crate::model::ModelInfo;
 use crate::model::ModelResolver;
+use crate::model::clean_model_name;
 use crate::storage::VirtualModelEntry;
 
 /// Pull the three Ollama top-level forwarded keys (`think`, `logprobs`,
@@ -39,24 +41,100 @@ pub fn extract_system_prompt(body: &Value) -> Option<String> {
         })
 }
 
+/// Whether a chat body's `messages` already include a system turn.
+fn has_system_message(body: &Value) -> bool {
+    body.get("messages")
+        .and_then(|m| m.as_array())
+        .is_some_and(|messages| {
+            messages.iter().any(|message| {
+                message
+                    .get("role")
+                    .and_then(|role| role.as_str())
+                    .is_some_and(|role| role.eq_ignore_ascii_case("system"))
+            })
+        })
+}
+
+/// Pick the reasoning mode for one request: `options.reasoning_mode` (from the
+/// request or a virtual model's parameters) wins over the `--reasoning-mode`
+/// server default. An unrecognised value is a 400 rather than a silent fallback.
+pub fn resolve_reasoning_mode(
+    options: Option<&Value>,
+    server_default: ReasoningMode,
+) -> Result<ReasoningMode, ProxyError> {
+    let Some(raw) = options.and_then(|opts| opts.get("reasoning_mode")) else {
+        return Ok(server_default);
+    };
+    raw.as_str().and_then(ReasoningMode::parse).ok_or_else(|| {
+        ProxyError::bad_request(&format!(
+            "invalid options.reasoning_mode {}: expected \"merge\", \"separate\" or \"strip\"",
+            raw
+        ))
+    })
+}
+
 pub struct ModelResolutionContext {
     pub lm_studio_model_id: String,
     pub effective_options: Option<Value>,
     pub effective_format: Option<Value>,
     pub system_prompt: Option<String>,
+    /// `system_prompt` came from `--default-system-prompt`.
+    pub system_prompt_is_default: bool,
     /// Whether the resolved model is reasoning-capable (`ModelInfo::is_thinking_model`).
     /// Drives the default-`reasoning:on` behavior when the caller omits `think`.
     pub model_supports_thinking: bool,
+    /// A specific loaded instance of the model to run on, from
+    /// `options.instance_id` or a `model@instance` name.
+    pub instance_id: Option<String>,
+}
+
+impl ModelResolutionContext {
+    /// What goes in the upstream request's `model` field: the chosen
+    /// instance when there is one, otherwise the model key (LM Studio then
+    /// picks an instance itself).
+    pub fn upstream_model_id(&self) -> &str {
+        self.instance_id
+            .as_deref()
+            .unwrap_or(&self.lm_studio_model_id)
+    }
 }
 
+/// `model@instance`, as `/api/ps` lists extra instances: the model key and
+/// instance id when the text after some `@` names a loaded instance. LM
+/// Studio keys can contain `@` themselves (`model@q4_k_m`), so any other
+/// name is left to normal resolution.
+async fn resolve_instance_suffix(
+    context: &RequestContext<'_>,
+    model_resolver: &Arc<ModelResolver>,
+    requested_model: &str,
+    cancellation_token: CancellationToken,
+) -> Result<Option<(String, String)>, ProxyError> {
+    let name = clean_model_name(requested_model);
+    if !name.contains('@') {
+        return Ok(None);
+    }
+    let loaded = model_resolver
+        .get_loaded_models(context.client, cancellation_token)
+        .await?;
+    for (pos, _) in name.match_indices('@') {
+        let instance = &name[pos + 1..];
+        if let Some(model) = loaded.iter().find(|m| m.has_loaded_instance(instance)) {
+            return Ok(Some((model.id.clone(), instance.to_string())));
+        }
+    }
+    Ok(None)
+}
+
+/// The LM Studio id a requested name runs on: a `--model-name-map` entry,
+/// then a virtual alias (returned alongside), then the resolver.
 pub async fn resolve_model_target<'a>(
     context: &RequestContext<'a>,
     model_resolver: &Arc<ModelResolver>,
     requested_model: &str,
     cancellation_token: CancellationToken,
 ) -> Result<(String, Option<VirtualModelEntry>), ProxyError> {
-    if let Some(entry) = context.virtual_models.get(requested_model).await {
-        return Ok((entry.target_model_id.clone(), Some(entry)));
+    if let Some(target) = mapped_model_target(context, requested_model).await? {
+        return Ok(target);
     }
 
     model_resolver
@@ -65,21 +143,122 @@ pub async fn resolve_model_target<'a>(
         .map(|id| (id, None))
 }
 
-pub async fn resolve_model_with_context<'a>(
+/// [`resolve_model_target`] plus the model list, from one retrieval (see
+/// [`ModelResolver::resolve_and_list`]).
+pub async fn resolve_model_target_and_list<'a>(
     context: &RequestContext<'a>,
     model_resolver: &Arc<ModelResolver>,
     requested_model: &str,
-    request_body: &Value,
-    wants_thinking_default: bool,
     cancellation_token: CancellationToken,
-) -> Result<ModelResolutionContext, ProxyError> {
-    let (lm_studio_model_id, virtual_entry) = resolve_model_target(
+) -> Result<(String, Option<VirtualModelEntry>, Vec<ModelInfo>), ProxyError> {
+    if let Some((id, virtual_entry)) = mapped_model_target(context, requested_model).await? {
+        let models = model_resolver
+            .get_all_models(context.client, cancellation_token)
+            .await?;
+        return Ok((id, virtual_entry, models));
+    }
+
+    model_resolver
+        .resolve_and_list(requested_model, context.client, cancellation_token)
+        .await
+        .map(|(id, models)| (id, None, models))
+}
+
+/// [`resolve_model_target`] plus the target's catalog entry (see
+/// [`ModelResolver::resolve_and_fetch`]).
+pub async fn resolve_model_target_and_fetch<'a>(
+    context: &RequestContext<'a>,
+    model_resolver: &Arc<ModelResolver>,
+    requested_model: &str,
+    cancellation_token: CancellationToken,
+) -> Result<(String, Option<VirtualModelEntry>, Option<ModelInfo>), ProxyError> {
+    if let Some((id, virtual_entry)) = mapped_model_target(context, requested_model).await? {
+        let model_info =
+            fetch_model_info_for_id(context, model_resolver, &id, cancellation_token).await?;
+        return Ok((id, virtual_entry, model_info));
+    }
+
+    model_resolver
+        .resolve_and_fetch(requested_model, context.client, cancellation_token)
+        .await
+        .map(|(id, model_info)| (id, None, model_info))
+}
+
+/// A `--model-name-map` entry or virtual alias for `requested_model`; these
+/// name their target without consulting LM Studio.
+async fn mapped_model_target(
+    context: &RequestContext<'_>,
+    requested_model: &str,
+) -> Result<Option<(String, Option<VirtualModelEntry>)>, ProxyError> {
+    crate::telemetry::record_model(requested_model);
+    if let Some(id) = context
+        .model_name_map
+        .get(&clean_model_name(requested_model).to_lowercase())
+    {
+        return Ok(Some((id.clone(), None)));
+    }
+    Ok(context
+        .virtual_models
+        .resolve(requested_model)
+        .await?
+        .map(|(entry, target)| (target, Some(entry))))
+}
+
+/// What a chat/generate/embed request's `model` names, resolved once per
+/// request: the handler hands `lm_studio_model_id` to the load, keep-alive
+/// and unload paths, and [`resolve_model_with_context`] builds on the rest.
+#[derive(Debug, Clone)]
+pub struct RequestTarget {
+    pub lm_studio_model_id: String,
+    /// The virtual alias the name resolved through, if any.
+    pub virtual_entry: Option<VirtualModelEntry>,
+    /// The instance a `model@instance` name picked.
+    pub suffix_instance: Option<String>,
+}
+
+/// A `model@instance` name, then [`resolve_model_target`].
+pub async fn resolve_request_target<'a>(
+    context: &RequestContext<'a>,
+    model_resolver: &Arc<ModelResolver>,
+    requested_model: &str,
+    cancellation_token: CancellationToken,
+) -> Result<RequestTarget, ProxyError> {
+    if let Some((lm_studio_model_id, instance)) = resolve_instance_suffix(
         context,
         model_resolver,
         requested_model,
         cancellation_token.clone(),
     )
-    .await?;
+    .await?
+    {
+        return Ok(RequestTarget {
+            lm_studio_model_id,
+            virtual_entry: None,
+            suffix_instance: Some(instance),
+        });
+    }
+    let (lm_studio_model_id, virtual_entry) =
+        resolve_model_target(context, model_resolver, requested_model, cancellation_token).await?;
+    Ok(RequestTarget {
+        lm_studio_model_id,
+        virtual_entry,
+        suffix_instance: None,
+    })
+}
+
+pub async fn resolve_model_with_context<'a>(
+    context: &RequestContext<'a>,
+    model_resolver: &Arc<ModelResolver>,
+    target: &RequestTarget,
+    request_body: &Value,
+    wants_thinking_default: bool,
+    cancellation_token: CancellationToken,
+) -> Result<ModelResolutionContext, ProxyError> {
+    let RequestTarget {
+        lm_studio_model_id,
+        virtual_entry,
+        suffix_instance,
+    } = target.clone();
 
     let request_options = request_body.get("options");
     let request_format = request_body.get("format");
@@ -91,6 +270,32 @@ pub async fn resolve_model_with_context<'a>(
         request_options,
     );
 
+    // An explicit `options.instance_id` beats the name suffix, but must be
+    // an instance of the model the name resolved to.
+    let instance_id = match effective_options
+        .as_ref()
+        .and_then(|options| options.get("instance_id"))
+        .and_then(Value::as_str)
+    {
+        Some(requested_instance) => {
+            let model_info = fetch_model_info_for_id(
+                context,
+                model_resolver,
+                &lm_studio_model_id,
+                cancellation_token.clone(),
+            )
+            .await?;
+            if !model_info.is_some_and(|info| info.has_loaded_instance(requested_instance)) {
+                return Err(ProxyError::not_found(&format!(
+                    "model '{}' has no loaded instance '{}'",
+                    lm_studio_model_id, requested_instance
+                )));
+            }
+            Some(requested_instance.to_string())
+        }
+        None => suffix_instance,
+    };
+
     let effective_format = virtual_entry
         .as_ref()
         .and_then(|entry| entry.metadata.parameters.as_ref())
@@ -102,7 +307,20 @@ pub async fn resolve_model_with_context<'a>(
     let system_from_virtual = virtual_entry
         .as_ref()
         .and_then(|entry| entry.metadata.system_prompt.clone());
-    let system_prompt = system_from_body.or(system_from_virtual);
+    // The global default only fills in when the request carries no system
+    // turn of any kind, including a `system` message in a chat history.
+    let default_system_prompt = if system_from_body.is_none()
+        && system_from_virtual.is_none()
+        && !has_system_message(request_body)
+    {
+        context.default_system_prompt.map(str::to_string)
+    } else {
+        None
+    };
+    let system_prompt_is_default = default_system_prompt.is_some();
+    let system_prompt = system_from_body
+        .or(system_from_virtual)
+        .or(default_system_prompt);
 
     // Resolve the model's reasoning capability so the inference path can default
     // `reasoning:on` for thinking models when the caller omitted `think`
@@ -135,7 +353,9 @@ pub async fn resolve_model_with_context<'a>(
         effective_options,
         effective_format,
         system_prompt,
+        system_prompt_is_default,
         model_supports_thinking,
+        instance_id,
     })
 }
 
@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+use tokio_util::sync::CancellationToken;
+
+use crate::api::RequestContext;
+use crate::error::ProxyError;
+use crate::http::json_response;
+use crate::lmstudio::keep_alive::parse_keep_alive_seconds;
+use crate::model::ModelResolver;
+use crate::model::naming::extract_required_model_name;
+
+use super::chat::{build_chat_upstream, routes_to_native_chat};
+use super::generate::{GenerateInput, GenerateUpstream, build_generate_upstream};
+use super::resolution::{resolve_model_with_context, resolve_request_target};
+
+/// An LM Studio inference call, built but not yet sent.
+pub struct UpstreamRequest {
+    /// Path under the LM Studio base URL (one of the `LM_STUDIO_*` constants).
+    pub endpoint: &'static str,
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformKind {
+    Chat,
+    Generate,
+}
+
+impl TransformKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransformKind::Chat => "chat",
+            TransformKind::Generate => "generate",
+        }
+    }
+
+    /// An explicit `kind` wins; otherwise `messages` means chat and `prompt`
+    /// means generate.
+    pub fn from_body(body: &Value) -> Result<Self, ProxyError> {
+        match body.get("kind") {
+            Some(Value::String(kind)) if kind == "chat" => Ok(TransformKind::Chat),
+            Some(Value::String(kind)) if kind == "generate" => Ok(TransformKind::Generate),
+            Some(other) => Err(ProxyError::bad_request(&format!(
+                "invalid kind {}: expected \"chat\" or \"generate\"",
+                other
+            ))),
+            None if body.get("messages").is_some() => Ok(TransformKind::Chat),
+            None if body.get("prompt").is_some() => Ok(TransformKind::Generate),
+            None => Err(ProxyError::bad_request(
+                "cannot tell a chat body from a generate body: set \"kind\", \"messages\" or \"prompt\"",
+            )),
+        }
+    }
+}
+
+/// Server-config knobs that change which request the chat path builds.
+pub struct TransformOptions {
+    pub use_native_chat: bool,
+    pub native_chat_streaming: bool,
+}
+
+/// `POST /api/proxy/debug/transform`: run an `/api/chat` or `/api/generate`
+/// body through model resolution, alias metadata, option mapping and
+/// `keep_alive` handling, and return the request the proxy would send.
+///
+/// No inference call is made. Resolving the model (and, for generate,
+/// choosing between the chat and completions endpoints) still reads LM
+/// Studio's model list through the resolver cache.
+pub async fn handle_transform_debug(
+    context: RequestContext<'_>,
+    model_resolver: Arc<ModelResolver>,
+    body: Value,
+    cancellation_token: CancellationToken,
+    options: TransformOptions,
+) -> Result<axum::response::Response, ProxyError> {
+    let kind = TransformKind::from_body(&body)?;
+    let ollama_model_name = extract_required_model_name(&body)?;
+    let keep_alive_seconds = parse_keep_alive_seconds(body.get("keep_alive"))?;
+    let generate_input = match kind {
+        TransformKind::Generate => Some(GenerateInput::from_body(&body)?),
+        TransformKind::Chat => None,
+    };
+
+    let target = resolve_request_target(
+        &context,
+        &model_resolver,
+        ollama_model_name,
+        cancellation_token.clone(),
+    )
+    .await?;
+    let resolution_ctx = resolve_model_with_context(
+        &context,
+        &model_resolver,
+        &target,
+        &body,
+        true,
+        cancellation_token.clone(),
+    )
+    .await?;
+
+    let upstream = match generate_input {
+        Some(input) => {
+            let history = match &context.generate_context {
+                Some(store) => store.history(body.get("context")).await,
+                None => Arc::default(),
+            };
+            let GenerateUpstream { request, .. } = build_generate_upstream(
+                &context,
+                &model_resolver,
+                &input,
+                &resolution_ctx,
+                &history,
+                keep_alive_seconds,
+                cancellation_token,
+            )
+            .await?;
+            request
+        }
+        None => {
+            let stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(true);
+            let use_native = routes_to_native_chat(
+                options.use_native_chat,
+                options.native_chat_streaming,
+                stream,
+            );
+            build_chat_upstream(&body, &resolution_ctx, keep_alive_seconds, use_native)?
+        }
+    };
+
+    Ok(json_response(&json!({
+        "kind": kind.as_str(),
+        "model": ollama_model_name,
+        "lm_studio_model_id": resolution_ctx.lm_studio_model_id,
+        "method": "POST",
+        "endpoint": upstream.endpoint,
+        "url": context.endpoint_url(upstream.endpoint),
+        "body": upstream.body,
+    })))
+}
+
+#[cfg(test)]
+#[path = "../../../tests/unit/handlers_ollama_transform.rs"]
+mod tests;
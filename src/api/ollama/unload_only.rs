@@ -29,6 +29,7 @@ use serde_json::{Value, json};
 use tokio_util::sync::CancellationToken;
 
 use crate::api::RequestContext;
+use crate::api::ollama::resolution::resolve_model_target;
 use crate::api::ollama::status_stream::stream_status_messages;
 use crate::error::ProxyError;
 use crate::http::json_response;
@@ -46,8 +47,9 @@ pub struct UnloadOnlyCall<'a> {
     pub cancellation_token: CancellationToken,
 }
 
-/// Resolves the model (cheaply — no load triggered), spawns the unload, and
-/// returns a `done:true` response in the requested wire format.
+/// Resolves the model (cheaply — no load triggered) as an inference request
+/// would, spawns the unload, and returns a `done:true` response in the
+/// requested wire format.
 pub async fn respond_unload_only(
     call: UnloadOnlyCall<'_>,
 ) -> Result<axum::response::Response, ProxyError> {
@@ -62,17 +64,21 @@ pub async fn respond_unload_only(
         cancellation_token,
     } = call;
 
-    // Verify the model resolves — a 404 here matches what a normal request
-    // would return, rather than silently "unloading" an unknown name.
-    model_resolver
-        .resolve_model_name(ollama_model_name, context.client, cancellation_token)
-        .await?;
+    // Aliases and `--model-name-map` pick the model a normal request would
+    // run on, and an unknown name 404s as it would there rather than
+    // silently "unloading" nothing.
+    let (model_id, _) = resolve_model_target(
+        context,
+        &model_resolver,
+        ollama_model_name,
+        cancellation_token,
+    )
+    .await?;
 
     spawn_model_unload_if_needed(
         context.client.clone(),
         context.lmstudio_url.to_string(),
-        model_resolver,
-        ollama_model_name.to_string(),
+        model_id,
         keep_alive_seconds,
         // No streaming response to wait for — unload immediately.
         0,
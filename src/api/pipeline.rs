@@ -8,21 +8,16 @@
 //! everything around it lives here.
 
 use std::future::Future;
-use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use axum::response::Response;
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
-use crate::constants::{
-    DEFAULT_STREAM_TIMEOUT_SECONDS, LM_STUDIO_NATIVE_MODELS, LM_STUDIO_NATIVE_UNLOAD,
-    LOG_PREFIX_SUCCESS,
-};
+use crate::constants::{LM_STUDIO_NATIVE_MODELS, LM_STUDIO_NATIVE_UNLOAD, LOG_PREFIX_SUCCESS};
 use crate::error::ProxyError;
 use crate::lmstudio::keep_alive::{proactive_evict_if_unloaded, spawn_model_unload_if_needed};
 use crate::logging::log_timed;
-use crate::model::ModelResolver;
 use crate::model::types::NativeModelsResponse;
 use crate::streaming::is_streaming_request;
 
@@ -31,11 +26,16 @@ use super::retry::with_retry_and_cancellation;
 
 pub struct ChatLikeCall<'a> {
     pub context: RequestContext<'a>,
-    pub resolver: Arc<ModelResolver>,
     pub body: Value,
     pub cancellation: CancellationToken,
     pub load_timeout_seconds: u64,
-    pub ollama_model_name: String,
+    /// The LM Studio key the request's name resolved to (see
+    /// [`resolve_request_target`]). Auto-evict, the load retry, the load
+    /// tracker and the `keep_alive: 0` unload all act on it, so a mapped or
+    /// aliased name never reaches a model the request did not run on.
+    ///
+    /// [`resolve_request_target`]: super::ollama::resolution::resolve_request_target
+    pub lm_studio_model_id: String,
     pub keep_alive_seconds: Option<i64>,
     pub start_time: Instant,
     pub op_label: &'static str,
@@ -49,16 +49,15 @@ pub struct ChatLikeCall<'a> {
 impl ChatLikeCall<'_> {
     pub async fn run<F, Fut>(self, attempt: F) -> Result<Response, ProxyError>
     where
-        F: Fn() -> Fut,
+        F: Fn(Duration) -> Fut,
         Fut: Future<Output = Result<Response, ProxyError>>,
     {
         let ChatLikeCall {
             context,
-            resolver,
             body,
             cancellation,
             load_timeout_seconds,
-            ollama_model_name,
+            lm_studio_model_id,
             keep_alive_seconds,
             start_time,
             op_label,
@@ -78,29 +77,13 @@ impl ChatLikeCall<'_> {
             match context.client.get(&models_url).send().await {
                 Ok(resp) => match resp.json::<NativeModelsResponse>().await {
                     Ok(models) => {
-                        match resolver
-                            .resolve_model_name(
-                                &ollama_model_name,
-                                context.client,
-                                cancellation.clone(),
-                            )
-                            .await
-                        {
-                            Ok(lm_key) => {
-                                proactive_evict_if_unloaded(
-                                    context.client,
-                                    &models,
-                                    &lm_key,
-                                    &unload_url,
-                                )
-                                .await;
-                            }
-                            Err(e) => log::debug!(
-                                "auto-evict: could not resolve key for '{}', skipping: {}",
-                                ollama_model_name,
-                                e.message
-                            ),
-                        }
+                        proactive_evict_if_unloaded(
+                            context.client,
+                            &models,
+                            &lm_studio_model_id,
+                            &unload_url,
+                        )
+                        .await;
                     }
                     Err(e) => {
                         log::warn!("auto-evict: parse models response failed, skipping: {}", e)
@@ -112,7 +95,7 @@ impl ChatLikeCall<'_> {
 
         let result = with_retry_and_cancellation(
             &context,
-            &ollama_model_name,
+            &lm_studio_model_id,
             load_timeout_seconds,
             attempt,
             cancellation.clone(),
@@ -120,9 +103,8 @@ impl ChatLikeCall<'_> {
         .await?;
 
         // Refresh the load tracker with this request's resolved keep_alive so
-        // /api/ps can report an accurate expires_at. Best-effort: resolution is
-        // cached on the shared resolver and any failure is ignored. Skipped for
-        // keep_alive:0 (the model is torn down by spawn_unload below).
+        // /api/ps can report an accurate expires_at. Skipped for keep_alive:0
+        // (the model is torn down by spawn_unload below).
         if keep_alive_seconds != Some(0) {
             let intent = match keep_alive_seconds {
                 Some(s) if s < 0 => crate::model::load_tracker::KeepAlive::Forever,
@@ -131,30 +113,19 @@ impl ChatLikeCall<'_> {
                 ),
                 _ => crate::model::load_tracker::KeepAlive::Unknown,
             };
-            match resolver
-                .resolve_model_name(&ollama_model_name, context.client, cancellation.clone())
-                .await
-            {
-                Ok(lm_key) => context.load_tracker.record(&lm_key, intent),
-                Err(e) => log::debug!(
-                    "load-tracker: could not resolve key for '{}', skipping: {}",
-                    ollama_model_name,
-                    e.message
-                ),
-            }
+            context.load_tracker.record(&lm_studio_model_id, intent);
         }
 
         if spawn_unload {
             let unload_delay = if is_streaming_request(&body) {
-                DEFAULT_STREAM_TIMEOUT_SECONDS
+                context.stream_timeouts.longest().as_secs()
             } else {
                 0
             };
             spawn_model_unload_if_needed(
                 context.client.clone(),
                 context.lmstudio_url.to_string(),
-                resolver,
-                ollama_model_name,
+                lm_studio_model_id,
                 keep_alive_seconds,
                 unload_delay,
             );
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::error::ProxyError;
+use crate::lmstudio::download::{LmStudioDownloadStatus, cancel_lmstudio_download};
+use crate::model::clean_model_name;
+
+/// LM Studio downloads started by `/api/pull` that have not finished yet.
+///
+/// Each entry holds the pull's cancellation token and the last status chunk
+/// sent to its client, so `DELETE /api/pull` can stop a download by model
+/// name or `job_id` and report where it got to.
+///
+/// It also knows which download identifiers are being pulled right now, so a
+/// second pull of the same model follows the first one's progress instead of
+/// starting another LM Studio download (see [`PullRegistry::claim`]).
+///
+/// Exposed behind `Arc` so handlers share one instance across requests.
+pub struct PullRegistry {
+    jobs: Mutex<HashMap<String, TrackedPull>>,
+    downloads: Mutex<HashMap<String, SharedDownload>>,
+}
+
+/// A claimed download: where its progress is published, and how many
+/// clients are attached to it.
+struct SharedDownload {
+    status: watch::Receiver<SharedPullStatus>,
+    attached: Arc<AtomicUsize>,
+}
+
+/// Latest state of a shared pull: `None` until LM Studio has answered the
+/// download request, then each status it reports (or why starting failed).
+pub type SharedPullStatus = Option<Result<LmStudioDownloadStatus, ProxyError>>;
+
+/// What [`PullRegistry::claim`] hands a pull.
+pub enum PullSlot {
+    /// Nobody is pulling this identifier: start the download and publish
+    /// its progress through the lead.
+    Lead(PullLead),
+    /// Another request already is: follow its published progress.
+    Follow(PullFollower),
+}
+
+/// The right to start one download, held by the request that claimed it.
+/// Dropping it lets the next pull of the identifier start afresh and closes
+/// every follower's channel.
+pub struct PullLead {
+    registry: Arc<PullRegistry>,
+    key: String,
+    tx: watch::Sender<SharedPullStatus>,
+    attached: Arc<AtomicUsize>,
+}
+
+/// A pull attached to a download another request leads.
+/// Holding it keeps the download running after its lead's client leaves.
+pub struct PullFollower {
+    pub status: watch::Receiver<SharedPullStatus>,
+    pub attachment: PullAttachment,
+}
+
+/// One client attached to a shared download, the lead's own included.
+/// The download is cancelled only once every attachment is dropped.
+pub struct PullAttachment {
+    attached: Arc<AtomicUsize>,
+}
+
+impl PullAttachment {
+    fn new(attached: &Arc<AtomicUsize>) -> Self {
+        attached.fetch_add(1, Ordering::SeqCst);
+        Self {
+            attached: attached.clone(),
+        }
+    }
+}
+
+impl Drop for PullAttachment {
+    fn drop(&mut self) {
+        self.attached.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct TrackedPull {
+    model: String,
+    token: CancellationToken,
+    last_status: Option<Value>,
+}
+
+/// One in-flight download, owned by the task polling it.
+///
+/// Dropping it before [`PullJob::finish`] (every attached client went away,
+/// the pull was cancelled, or polling failed) asks LM Studio to cancel the
+/// download, so an abandoned pull does not keep fetching gigabytes in the
+/// background.
+pub struct PullJob {
+    registry: Arc<PullRegistry>,
+    job_id: Option<String>,
+    token: CancellationToken,
+    client: reqwest::Client,
+    base_url: String,
+    finished: bool,
+    lead: Option<PullLead>,
+}
+
+impl PullRegistry {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            jobs: Mutex::new(HashMap::new()),
+            downloads: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Start tracking the download `job_id` for `model`. Without a job id
+    /// (LM Studio already had the model) the returned job is inert.
+    pub fn track(
+        self: &Arc<Self>,
+        job_id: Option<&str>,
+        model: &str,
+        token: CancellationToken,
+        client: reqwest::Client,
+        base_url: String,
+    ) -> PullJob {
+        if let Some(job_id) = job_id {
+            self.lock().insert(
+                job_id.to_string(),
+                TrackedPull {
+                    model: model.to_string(),
+                    token: token.clone(),
+                    last_status: None,
+                },
+            );
+        }
+        PullJob {
+            registry: self.clone(),
+            job_id: job_id.map(str::to_string),
+            token,
+            client,
+            base_url,
+            finished: false,
+            lead: None,
+        }
+    }
+
+    /// Lead the pull of `key` (a download identifier plus quantization) or,
+    /// when one is already running, follow it. Claiming and checking happen
+    /// under one lock, so of two simultaneous pulls exactly one leads.
+    pub fn claim(self: &Arc<Self>, key: &str) -> PullSlot {
+        let mut downloads = self.lock_downloads();
+        if let Some(download) = downloads.get(key) {
+            return PullSlot::Follow(PullFollower {
+                status: download.status.clone(),
+                attachment: PullAttachment::new(&download.attached),
+            });
+        }
+        let (tx, rx) = watch::channel(None);
+        let attached = Arc::new(AtomicUsize::new(0));
+        downloads.insert(
+            key.to_string(),
+            SharedDownload {
+                status: rx,
+                attached: attached.clone(),
+            },
+        );
+        PullSlot::Lead(PullLead {
+            registry: self.clone(),
+            key: key.to_string(),
+            tx,
+            attached,
+        })
+    }
+
+    /// Number of downloads currently in flight.
+    pub fn in_flight(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Cancel the in-flight download whose `job_id` or model name is
+    /// `target` (`:latest` optional). Returns what was cancelled and its last
+    /// known status, or `None` when nothing matches.
+    pub fn cancel(&self, target: &str) -> Option<Value> {
+        let jobs = self.lock();
+        let wanted = clean_model_name(target);
+        let found = jobs
+            .iter()
+            .find(|(job_id, _)| job_id.as_str() == target)
+            .or_else(|| {
+                jobs.iter()
+                    .find(|(_, pull)| clean_model_name(&pull.model) == wanted)
+            })?;
+        Some(Self::cancel_entry(found))
+    }
+
+    /// Cancel the in-flight download `job_id`, ignoring model names.
+    pub fn cancel_job(&self, job_id: &str) -> Option<Value> {
+        let jobs = self.lock();
+        jobs.get_key_value(job_id).map(Self::cancel_entry)
+    }
+
+    fn cancel_entry((job_id, pull): (&String, &TrackedPull)) -> Value {
+        pull.token.cancel();
+        log::info!("pull: cancelling download {} ({})", job_id, pull.model);
+        json!({
+            "status": "cancelled",
+            "job_id": job_id,
+            "model": pull.model,
+            "last_status": pull.last_status,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, TrackedPull>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_downloads(&self) -> std::sync::MutexGuard<'_, HashMap<String, SharedDownload>> {
+        self.downloads.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl PullLead {
+    /// Hand `status` to every pull following this one.
+    pub fn publish(&self, status: Result<LmStudioDownloadStatus, ProxyError>) {
+        self.tx.send_replace(Some(status));
+    }
+
+    /// Attach the leading request's own client to the download.
+    pub fn attach(&self) -> PullAttachment {
+        PullAttachment::new(&self.attached)
+    }
+
+    /// Whether every client attached to the download has gone.
+    fn is_abandoned(&self) -> bool {
+        self.attached.load(Ordering::SeqCst) == 0
+    }
+}
+
+impl Drop for PullLead {
+    fn drop(&mut self) {
+        self.registry.lock_downloads().remove(&self.key);
+    }
+}
+
+impl PullJob {
+    /// Fires when the pull is cancelled explicitly or the proxy shuts down.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Remember the chunk just sent to the client as the last known status.
+    pub fn record(&self, chunk: &Value) {
+        if let Some(job_id) = &self.job_id
+            && let Some(pull) = self.registry.lock().get_mut(job_id)
+        {
+            pull.last_status = Some(chunk.clone());
+        }
+    }
+
+    /// Publish each status this job polls to the pulls following `lead`.
+    pub fn share_with(mut self, lead: PullLead) -> Self {
+        self.lead = Some(lead);
+        self
+    }
+
+    /// Pass a freshly polled status on to followers, if any.
+    pub fn publish(&self, status: &LmStudioDownloadStatus) {
+        if let Some(lead) = &self.lead {
+            lead.publish(Ok(status.clone()));
+        }
+    }
+
+    /// Whether other pulls can attach to this download.
+    pub fn is_shared(&self) -> bool {
+        self.lead.is_some()
+    }
+
+    /// Whether a shared download has lost every attached client, its lead's
+    /// own and each follower's. An unshared job never is; its owner's
+    /// lifetime decides.
+    pub fn is_abandoned(&self) -> bool {
+        self.lead.as_ref().is_some_and(PullLead::is_abandoned)
+    }
+
+    /// LM Studio reported a terminal status; nothing is left to cancel.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for PullJob {
+    fn drop(&mut self) {
+        let Some(job_id) = self.job_id.take() else {
+            return;
+        };
+        self.registry.lock().remove(&job_id);
+        if self.finished {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let client = self.client.clone();
+        let base_url = std::mem::take(&mut self.base_url);
+        runtime.spawn(async move {
+            if let Err(e) = cancel_lmstudio_download(&client, &base_url, &job_id).await {
+                log::warn!(
+                    "pull: LM Studio did not cancel download {}: {}; it may still be downloading",
+                    job_id,
+                    e.message
+                );
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/handlers_pull_registry.rs"]
+mod tests;
@@ -1,17 +1,30 @@
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::constants::DEFAULT_STREAM_TIMEOUT_SECONDS;
+use crate::config::ReasoningMode;
 use crate::error::ProxyError;
 use crate::http::client::handle_json_response;
 use crate::http::json_response;
-use crate::lmstudio::response::ResponseTransformer;
+use crate::lmstudio::response::{ResponseTransformer, apply_measured_load};
+use crate::lmstudio::tokens::count_tokens;
 use crate::logging::log_handler_io;
-use crate::streaming::handle_streaming_response;
+use crate::model::RuntimeInfoCache;
+use crate::storage::GenerateContextTurn;
+use crate::streaming::{StreamTimeouts, StreamingParams, handle_streaming_response};
 use tokio_util::sync::CancellationToken;
 
 pub enum ResponseContext {
-    Chat { message_count: usize },
-    Generate { prompt: String },
+    Chat {
+        /// `prompt_eval_count` when LM Studio reports no usage: the
+        /// estimated size of the messages sent.
+        prompt_tokens_estimate: u64,
+    },
+    Generate {
+        prompt: String,
+        /// With `--emulate-generate-context`, records the exchange and adds
+        /// `context` to the final response.
+        generate_context: Option<GenerateContextTurn>,
+    },
 }
 
 pub struct ResponseParams<'a> {
@@ -20,8 +33,19 @@ pub struct ResponseParams<'a> {
     pub is_chat: bool,
     pub model_name: &'a str,
     pub start_time: Instant,
+    /// Time spent loading the model before this attempt (zero when warm).
+    pub load_duration: Duration,
     pub context: ResponseContext,
     pub cancellation_token: CancellationToken,
+    pub reasoning_mode: ReasoningMode,
+    pub stream_timeouts: StreamTimeouts,
+    /// Non-streaming only: add LM Studio's `stats` as `proxy_stats`.
+    pub expose_stats: bool,
+    /// Keeps the response's `model_info`/`runtime` blocks for `/api/show`.
+    pub runtime_info: Arc<RuntimeInfoCache>,
+    /// The resolved LM Studio model key the blocks are filed under; the
+    /// response's own `model` can name an instance instead.
+    pub lm_studio_model_id: &'a str,
 }
 
 pub async fn handle_response(
@@ -33,39 +57,89 @@ pub async fn handle_response(
         is_chat,
         model_name,
         start_time,
+        load_duration,
         context,
         cancellation_token,
+        reasoning_mode,
+        stream_timeouts,
+        expose_stats,
+        runtime_info,
+        lm_studio_model_id,
     } = params;
 
     if stream {
+        let (generate_context, prompt_tokens_estimate) = match context {
+            ResponseContext::Generate {
+                prompt,
+                generate_context,
+            } => (generate_context, count_tokens(&prompt)),
+            ResponseContext::Chat {
+                prompt_tokens_estimate,
+            } => (None, prompt_tokens_estimate),
+        };
         handle_streaming_response(
             response,
-            is_chat,
-            model_name,
-            start_time,
-            cancellation_token,
-            DEFAULT_STREAM_TIMEOUT_SECONDS,
+            StreamingParams {
+                is_chat,
+                model_name,
+                start_time,
+                load_duration,
+                cancellation_token,
+                timeouts: stream_timeouts,
+                reasoning_mode,
+                generate_context,
+                prompt_tokens_estimate,
+                runtime_info,
+                lm_studio_model_id,
+            },
         )
         .await
     } else {
         let lm_response_value = handle_json_response(response, cancellation_token).await?;
+        runtime_info.observe(lm_studio_model_id, &lm_response_value);
 
-        let ollama_response = match context {
-            ResponseContext::Chat { message_count } => ResponseTransformer::convert_to_ollama_chat(
-                &lm_response_value,
-                model_name,
-                message_count,
-                start_time,
+        let (mut ollama_response, generate_context) = match context {
+            ResponseContext::Chat {
+                prompt_tokens_estimate,
+            } => (
+                ResponseTransformer::convert_to_ollama_chat(
+                    &lm_response_value,
+                    model_name,
+                    prompt_tokens_estimate,
+                    start_time,
+                ),
+                None,
             ),
-            ResponseContext::Generate { prompt } => {
+            ResponseContext::Generate {
+                prompt,
+                generate_context,
+            } => (
                 ResponseTransformer::convert_to_ollama_generate(
                     &lm_response_value,
                     model_name,
                     &prompt,
                     start_time,
-                )
-            }
+                ),
+                generate_context,
+            ),
         };
+        ResponseTransformer::apply_reasoning_mode(&mut ollama_response, reasoning_mode);
+        if expose_stats {
+            ResponseTransformer::attach_proxy_stats(&mut ollama_response, &lm_response_value);
+        }
+        apply_measured_load(&mut ollama_response, load_duration, start_time);
+
+        if let Some(turn) = generate_context {
+            let text = ollama_response
+                .get("response")
+                .and_then(|r| r.as_str())
+                .unwrap_or_default()
+                .to_string();
+            let context = turn.finish(&text).await;
+            if let Some(obj) = ollama_response.as_object_mut() {
+                obj.insert("context".to_string(), context);
+            }
+        }
 
         log_handler_io(
             if is_chat { "chat" } else { "generate" },
@@ -17,7 +17,7 @@ use crate::http::CancellableRequest;
 use crate::lmstudio::keep_alive::unload_other_models;
 use crate::lmstudio::{build_load_config_body, is_model_loading_error};
 use crate::logging::log_timed;
-use crate::model::ModelResolver;
+use crate::model::ModelInfo;
 
 #[derive(Serialize)]
 struct MinimalChatMessage<'a> {
@@ -33,33 +33,44 @@ struct MinimalChatRequestPayload<'a> {
     stream: bool,
 }
 
+/// Warm or load `model_key`, the LM Studio key the request's own name
+/// already resolved to (through `--model-name-map`, aliases, pins and the
+/// model filter). `/api/v1/models/load` and the chat-ping address LM Studio by
+/// that bare key; re-resolving the Ollama name here could pick another model.
 pub async fn trigger_model_loading(
     context: &RequestContext<'_>,
-    ollama_model_name: &str,
+    model_key: &str,
     do_explicit_load: bool,
     cancellation_token: CancellationToken,
 ) -> Result<bool, ProxyError> {
-    // `/api/v1/models/load` and the v0 chat-ping below address LM Studio by its
-    // bare model key (e.g. "qwen3.5-0.8b"). The Ollama-facing name usually carries
-    // a ":latest" tag that LM Studio rejects (`model_not_found` / "no models
-    // loaded"), which silently broke every explicit load whenever LM Studio's own
-    // JIT was off (and embedders, which only load via this path). Resolve to the
-    // key first; best-effort, falling back to the raw name (a truly missing model
-    // 404s either way).
-    let resolved_key = {
-        let cache = moka::future::Cache::builder().max_capacity(64).build();
-        let resolver = ModelResolver::new(context.lmstudio_url.to_string(), cache);
-        resolver
-            .resolve_model_name(
-                ollama_model_name,
-                context.client,
+    // Concurrent requests for one cold model share a single trigger. A warm
+    // ping and an explicit load are different operations, so they don't merge.
+    let trigger = if do_explicit_load { "load" } else { "warm" };
+    crate::telemetry::event(
+        "model.load_trigger",
+        &[("model", model_key), ("trigger", trigger)],
+    );
+    let load_key = format!("{}#{}", model_key, trigger);
+    context
+        .load_coordinator
+        .run(&load_key, &cancellation_token, || {
+            run_model_trigger(
+                context,
+                model_key,
+                do_explicit_load,
                 cancellation_token.clone(),
             )
-            .await
-            .unwrap_or_else(|_| ollama_model_name.to_string())
-    };
-    let model_for_lm_studio_trigger = resolved_key.as_str();
+        })
+        .await
+}
 
+/// The load itself, once per in-flight model (see [`trigger_model_loading`]).
+async fn run_model_trigger(
+    context: &RequestContext<'_>,
+    model_key: &str,
+    do_explicit_load: bool,
+    cancellation_token: CancellationToken,
+) -> Result<bool, ProxyError> {
     // Issue a model-only explicit load ONLY when the caller knows the model is
     // not resident (the JIT-on-error path). `build_load_config_body` returns None
     // with no tuning flags, but a bare `{"model": ...}` load still brings up BOTH
@@ -72,39 +83,17 @@ pub async fn trigger_model_loading(
     if do_explicit_load {
         if get_runtime_config().auto_evict {
             // Best-effort: evict every other model's loaded instances before
-            // bringing up the target. Resolution uses a transient resolver (no
-            // shared cache) — fine here since the explicit-load path is cold and
-            // any failure logs and continues, never aborting the load below.
-            let cache = moka::future::Cache::builder().max_capacity(64).build();
-            let resolver = ModelResolver::new(context.lmstudio_url.to_string(), cache);
-            match resolver
-                .resolve_model_name(
-                    model_for_lm_studio_trigger,
-                    context.client,
-                    cancellation_token.clone(),
-                )
-                .await
+            // bringing up the target. Any failure logs and continues, never
+            // aborting the load below.
+            if let Err(e) =
+                unload_other_models(context.client, context.lmstudio_url, model_key).await
             {
-                Ok(keep_key) => {
-                    if let Err(e) =
-                        unload_other_models(context.client, context.lmstudio_url, &keep_key).await
-                    {
-                        log::warn!("auto-evict: unload failed, continuing: {}", e.message);
-                    }
-                }
-                Err(e) => {
-                    log::warn!(
-                        "auto-evict: could not resolve keep key for '{}', skipping: {}",
-                        model_for_lm_studio_trigger,
-                        e.message
-                    );
-                }
+                log::warn!("auto-evict: unload failed, continuing: {}", e.message);
             }
         }
 
-        let load_body =
-            build_load_config_body(model_for_lm_studio_trigger, get_runtime_config(), None)
-                .unwrap_or_else(|| serde_json::json!({ "model": model_for_lm_studio_trigger }));
+        let load_body = build_load_config_body(model_key, get_runtime_config(), None)
+            .unwrap_or_else(|| serde_json::json!({ "model": model_key }));
         let load_url = context.endpoint_url(LM_STUDIO_MODELS_LOAD);
         let load_request = CancellableRequest::new(context.client, cancellation_token.clone());
         match load_request
@@ -113,16 +102,11 @@ pub async fn trigger_model_loading(
         {
             Ok(_) => {
                 // Record the load so /api/ps can report a best-effort expires_at.
-                // Keyed on the LM Studio model key (= ModelInfo.id); resolve the
-                // Ollama name first so the ps lookup matches. ttl is unknown on
-                // the load path (keep_alive lives on the inference request, not
-                // here), so None = loaded forever until a keep-alive refresh.
-                record_loaded_key(
-                    context,
-                    model_for_lm_studio_trigger,
-                    cancellation_token.clone(),
-                )
-                .await;
+                // Keyed on the LM Studio model key (= ModelInfo.id) so the ps
+                // lookup matches. ttl is unknown on the load path (keep_alive
+                // lives on the inference request, not here), so None = loaded
+                // forever until a keep-alive refresh.
+                record_loaded_key(context, model_key);
             }
             Err(e) if e.is_cancelled() => return Err(ProxyError::request_cancelled()),
             Err(e) => {
@@ -133,7 +117,7 @@ pub async fn trigger_model_loading(
 
     let url = context.endpoint_url(LM_STUDIO_NATIVE_CHAT);
     let minimal_request_body = MinimalChatRequestPayload {
-        model: model_for_lm_studio_trigger,
+        model: model_key,
         messages: vec![MinimalChatMessage {
             role: "user",
             content: "ping",
@@ -156,7 +140,7 @@ pub async fn trigger_model_loading(
             // explicit-load branch above only runs on the JIT-on-error path).
             // Record it so /api/ps has a real expires_at to report.
             if trigger_considered_successful {
-                record_loaded_key(context, ollama_model_name, cancellation_token.clone()).await;
+                record_loaded_key(context, model_key);
             }
             if !trigger_considered_successful {
                 log::warn!("model trigger: status: {}", status);
@@ -164,9 +148,10 @@ pub async fn trigger_model_loading(
             Ok(trigger_considered_successful)
         }
         Err(e) if e.is_cancelled() => Err(ProxyError::request_cancelled()),
-        Err(e) if e.is_lm_studio_unavailable() => Err(ProxyError::lm_studio_unavailable(
-            ERROR_LM_STUDIO_UNAVAILABLE,
-        )),
+        Err(e) if e.is_lm_studio_unavailable() => Err(ProxyError {
+            message: ERROR_LM_STUDIO_UNAVAILABLE.to_string(),
+            ..e
+        }),
         Err(e) => {
             log::error!("model trigger: {}", e.message);
             Ok(false)
@@ -174,26 +159,35 @@ pub async fn trigger_model_loading(
     }
 }
 
+/// Warm a model a handler has already resolved to `model_id`, with its
+/// catalog entry when the caller has one, so no further model list is read.
 pub async fn trigger_model_loading_for_ollama(
     context: &RequestContext<'_>,
-    ollama_model_name: &str,
+    model_id: &str,
+    model: Option<&ModelInfo>,
     cancellation_token: CancellationToken,
 ) -> Result<(), ProxyError> {
     // Unconditional warm (e.g. /api/show): no explicit load — see the duplicate-
-    // instance note on `trigger_model_loading`. The chat-ping loads chat models.
-    match trigger_model_loading(context, ollama_model_name, false, cancellation_token).await {
+    // instance note on `trigger_model_loading`. The chat-ping loads chat models;
+    // an embedding model with no instance can only come up through an explicit
+    // load, and having none there is no duplicate to stack.
+    let explicit_load = model.is_some_and(is_unloaded_embedding_model);
+    match trigger_model_loading(context, model_id, explicit_load, cancellation_token).await {
         Ok(true) => Ok(()),
         Ok(false) => {
-            log::warn!(
-                "load hint: trigger for '{}' failed, proceeding",
-                ollama_model_name
-            );
+            log::warn!("load hint: trigger for '{}' failed, proceeding", model_id);
             Ok(())
         }
         Err(e) => Err(e),
     }
 }
 
+/// Whether `model` is an embedding model with no loaded instance.
+fn is_unloaded_embedding_model(model: &ModelInfo) -> bool {
+    matches!(model.model_type.as_str(), "embeddings" | "embedding")
+        && model.loaded_instances.is_empty()
+}
+
 /// LM Studio's literal "no models loaded" 400 — a model that exists in the
 /// catalog but has no resident instance yet. Matched narrowly (rather than via
 /// the loose `is_model_loading_error` classifier) so proxy-side validation 400s
@@ -203,37 +197,14 @@ fn is_no_models_loaded(message: &str) -> bool {
     message.to_lowercase().contains("no models loaded")
 }
 
-/// Best-effort: resolve `ollama_model_name` to its LM Studio key (= `ModelInfo.id`)
-/// and record the load in the proxy's tracker so `/api/ps` can report a real
-/// `expires_at`. Resolution uses a transient resolver (no shared cache) — the
-/// recorded deadline is approximate anyway and any failure logs and continues,
-/// never affecting the load itself. ttl is None (loaded forever) because the
-/// load path carries no keep_alive; a subsequent inference request's keep_alive
-/// would refresh it if threaded, but that refresh is out of scope here.
-async fn record_loaded_key(
-    context: &RequestContext<'_>,
-    ollama_model_name: &str,
-    cancellation_token: CancellationToken,
-) {
-    let cache = moka::future::Cache::builder().max_capacity(64).build();
-    let resolver = ModelResolver::new(context.lmstudio_url.to_string(), cache);
-    match resolver
-        .resolve_model_name(
-            ollama_model_name,
-            context.client,
-            cancellation_token.clone(),
-        )
-        .await
-    {
-        Ok(lm_key) => context
-            .load_tracker
-            .record(&lm_key, crate::model::load_tracker::KeepAlive::Unknown),
-        Err(e) => log::debug!(
-            "load-tracker: could not resolve key for '{}', skipping record: {}",
-            ollama_model_name,
-            e.message
-        ),
-    }
+/// Record the load in the proxy's tracker under the LM Studio key (=
+/// `ModelInfo.id`) so `/api/ps` can report a real `expires_at`. The keep-alive
+/// is unknown because the load path carries none; the inference request that
+/// follows refreshes it with its own.
+fn record_loaded_key(context: &RequestContext<'_>, model_key: &str) {
+    context
+        .load_tracker
+        .record(model_key, crate::model::load_tracker::KeepAlive::Unknown);
 }
 
 /// Whether an upstream failure should trigger a best-effort model load and retry.
@@ -253,20 +224,26 @@ pub(crate) fn should_trigger_load(status: u16, message: &str) -> bool {
     is_model_loading_error(message)
 }
 
+/// Run `operation`, and when it fails because the model is not resident,
+/// trigger a load of `model_key` (the resolved LM Studio key, see
+/// [`trigger_model_loading`]) and run it once more.
+///
+/// `operation` receives the time spent loading before that attempt: zero on
+/// the first try, the trigger plus `load_timeout_seconds` wait on the retry.
 pub async fn with_retry_and_cancellation<F, Fut, T>(
     context: &RequestContext<'_>,
-    ollama_model_name: &str,
+    model_key: &str,
     load_timeout_seconds: u64,
     operation: F,
     cancellation_token: CancellationToken,
 ) -> Result<T, ProxyError>
 where
-    F: Fn() -> Fut,
+    F: Fn(Duration) -> Fut,
     Fut: Future<Output = Result<T, ProxyError>>,
 {
     check_cancelled!(cancellation_token);
 
-    match operation().await {
+    match operation(Duration::ZERO).await {
         Ok(result) => Ok(result),
         Err(e) if e.is_cancelled() => Err(ProxyError::request_cancelled()),
         Err(e) if e.is_lm_studio_unavailable() => {
@@ -289,19 +266,14 @@ where
                 let model_loading_start = Instant::now();
                 log_timed(
                     LOG_PREFIX_INFO,
-                    &format!("{} not loaded, triggering", ollama_model_name),
+                    &format!("{} not loaded, triggering", model_key),
                     model_loading_start,
                 );
 
                 // JIT-on-error: the model is genuinely not resident, so force an
                 // explicit load (required to bring up embedding models).
-                match trigger_model_loading(
-                    context,
-                    ollama_model_name,
-                    true,
-                    cancellation_token.clone(),
-                )
-                .await
+                match trigger_model_loading(context, model_key, true, cancellation_token.clone())
+                    .await
                 {
                     Ok(true) => {
                         tokio::select! {
@@ -312,11 +284,12 @@ where
                         }
                         check_cancelled!(cancellation_token);
 
-                        match operation().await {
+                        crate::telemetry::event("retry", &[("model", model_key)]);
+                        match operation(model_loading_start.elapsed()).await {
                             Ok(result) => {
                                 log_timed(
                                     LOG_PREFIX_SUCCESS,
-                                    &format!("{} loaded", ollama_model_name),
+                                    &format!("{} loaded", model_key),
                                     model_loading_start,
                                 );
                                 Ok(result)
@@ -324,7 +297,7 @@ where
                             Err(retry_error) => {
                                 log::error!(
                                     "retry failed for {}: {}",
-                                    ollama_model_name,
+                                    model_key,
                                     retry_error.message
                                 );
                                 Err(e)
@@ -334,7 +307,7 @@ where
                     Ok(false) => {
                         log::error!(
                             "model trigger: failed for {} - model may not exist. Original: {}",
-                            ollama_model_name,
+                            model_key,
                             e.message
                         );
                         Err(e)
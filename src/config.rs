@@ -1,15 +1,26 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::OnceLock;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
-use crate::constants::OLLAMA_SERVER_VERSION;
+use crate::constants::{
+    DEFAULT_INDEFINITE_TTL_SECONDS, MAX_JSON_BODY_SIZE_BYTES, OLLAMA_SERVER_VERSION,
+};
+use crate::model::clean_model_name;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "ollama-lmstudio-proxy")]
 #[command(about = "high-performance proxy server bridging ollama API and lm studio")]
 pub struct Config {
-    #[arg(long, default_value = "0.0.0.0:11434", help = "server listen address")]
-    pub listen: String,
+    #[arg(
+        long,
+        default_value = "0.0.0.0:11434",
+        help = "server listen address; repeat to bind several (e.g. --listen 0.0.0.0:11434 --listen [::]:11434). `unix:/path/to.sock` listens on a Unix domain socket instead"
+    )]
+    pub listen: Vec<String>,
 
     #[arg(
         long,
@@ -18,6 +29,12 @@ pub struct Config {
     )]
     pub lmstudio_url: String,
 
+    #[arg(
+        long,
+        help = "second LM Studio server to retry a request on when --lmstudio-url refuses the connection or times out"
+    )]
+    pub lmstudio_fallback_url: Option<String>,
+
     #[arg(
         long,
         env = "RUST_LOG",
@@ -26,6 +43,58 @@ pub struct Config {
     )]
     pub log_level: String,
 
+    #[arg(
+        long,
+        help = "also write logs (without color codes) to this file, rotating it by size"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value = "10",
+        help = "rotate --log-file once it reaches this many megabytes"
+    )]
+    pub log_max_size_mb: u64,
+
+    #[arg(
+        long,
+        default_value = "5",
+        help = "rotated --log-file copies to keep (file.1 is the newest); 0 = truncate instead"
+    )]
+    pub log_max_files: usize,
+
+    #[arg(
+        long,
+        help = "write one line per request (time, request id, method, path, status, duration) to this file, rotated like --log-file by --log-max-size-mb / --log-max-files"
+    )]
+    pub access_log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "leave per-request lines out of the console and --log-file; --access-log-file still gets them"
+    )]
+    pub no_console_access_log: bool,
+
+    #[arg(
+        long,
+        env = "OTEL_EXPORTER_OTLP_ENDPOINT",
+        help = "export request traces to this OTLP/HTTP collector (e.g. http://otel-collector:4318; /v1/traces is appended); needs the `otel` build feature"
+    )]
+    pub otlp_endpoint: Option<String>,
+
+    #[arg(
+        long,
+        requires = "log_file",
+        help = "run in the background with no console: logs go to --log-file only. On Windows the proxy runs as a Windows service and must be started by the Service Control Manager"
+    )]
+    pub service: bool,
+
+    #[arg(
+        long,
+        help = "write the process id to this file once the listeners are bound; removed on shutdown"
+    )]
+    pub pid_file: Option<PathBuf>,
+
     #[arg(
         long,
         default_value = "15",
@@ -33,6 +102,33 @@ pub struct Config {
     )]
     pub load_timeout_seconds: u64,
 
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "model to load into LM Studio at startup so the first request skips the cold start; repeat or comma-separate. Failures are logged, never fatal"
+    )]
+    pub preload: Vec<String>,
+
+    #[arg(
+        long,
+        help = "when /api/chat or /api/generate names a model LM Studio does not have and the name looks like a catalog id (owner/repo, or an --auto-pull-pattern match), download it first and then answer"
+    )]
+    pub auto_pull_missing: bool,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "extra globs (e.g. \"qwen*\") naming models --auto-pull-missing may download; repeat or comma-separate"
+    )]
+    pub auto_pull_pattern: Vec<String>,
+
+    #[arg(
+        long,
+        default_value = "300",
+        help = "how long a non-streaming request waits for an --auto-pull-missing download before failing with its job id"
+    )]
+    pub auto_pull_wait_seconds: u64,
+
     #[arg(
         long,
         default_value = "262144",
@@ -53,10 +149,23 @@ pub struct Config {
     #[arg(
         long,
         env = "LMSTUDIO_TOKEN",
-        help = "bearer token for LM Studio authentication (sets Authorization header on all outbound requests)"
+        alias = "lmstudio-api-key",
+        help = "bearer token for LM Studio authentication (sets Authorization header on all outbound requests); also accepted as --lmstudio-api-key"
     )]
     pub lmstudio_token: Option<String>,
 
+    #[arg(
+        long,
+        help = "extra header sent on every request to LM Studio, as \"Name: value\" (e.g. a key for a gateway in front of it); repeat for several. Overrides --lmstudio-token when it names Authorization"
+    )]
+    pub lmstudio_header: Vec<String>,
+
+    #[arg(
+        long,
+        help = "local IP address to send LM Studio requests from on multi-homed hosts; on Linux a network interface name (e.g. eth1) also works"
+    )]
+    pub upstream_bind_interface: Option<String>,
+
     #[arg(
         long,
         env = "OLLAMA_API_KEY",
@@ -134,6 +243,306 @@ pub struct Config {
         help = "unload all other models' loaded instances before loading a model (mirrors Ollama single-model default + LM Studio JIT auto-evict)"
     )]
     pub auto_evict: bool,
+
+    #[arg(
+        long,
+        help = "DELETE /api/delete on a real (non-alias) model unloads it from LM Studio instead of returning 404; model files are never removed"
+    )]
+    pub delete_unloads: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "separate",
+        help = "where model reasoning goes in /api/chat and /api/generate output: merge (prepend to content), separate (thinking field), strip (drop); a request's options.reasoning_mode overrides it"
+    )]
+    pub reasoning_mode: ReasoningMode,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "fuzzy",
+        help = "model name resolution: fuzzy (exact, then substring, then token-scored match) or exact (case-insensitive id match only; anything else is not found)"
+    )]
+    pub resolution: ResolutionMode,
+
+    #[arg(
+        long,
+        help = "max simultaneous inference requests per LM Studio model (chat, generate, embeddings, /v1 passthrough); unset = unlimited"
+    )]
+    pub max_concurrent_per_model: Option<usize>,
+
+    #[arg(
+        long,
+        default_value = "30",
+        help = "how long a request over --max-concurrent-per-model waits for a slot before a 429; 0 = reject immediately"
+    )]
+    pub concurrency_wait_seconds: u64,
+
+    #[arg(
+        long,
+        help = "count tokens with a BPE tokenizer when LM Studio reports no usage (needs the `accurate-tokens` build feature); otherwise a length/4 estimate is used"
+    )]
+    pub accurate_tokens: bool,
+
+    #[arg(
+        long,
+        default_value = "5",
+        help = "seconds to reuse LM Studio's model list across /api/tags, /api/ps and /api/show; 0 = always refetch"
+    )]
+    pub models_cache_ttl_seconds: u64,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "only expose LM Studio models matching these globs (e.g. \"qwen*\"); repeat or comma-separate. Applies to /api/tags, /api/ps, /v1/models and name resolution"
+    )]
+    pub model_allowlist: Vec<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "hide LM Studio models matching these globs (e.g. \"*embed*\"); virtual aliases stay usable"
+    )]
+    pub model_blocklist: Vec<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "map an Ollama name straight to an LM Studio id, skipping fuzzy resolution (e.g. llama3=lmstudio-community/Meta-Llama-3.1-8B-Instruct-GGUF); repeat or comma-separate"
+    )]
+    pub model_name_map: Vec<String>,
+
+    #[arg(
+        long,
+        help = "estimate each /api/chat history's tokens and reject (400) one that would not fit the model's context with num_predict left for the reply; options.truncate_history drops the oldest messages instead"
+    )]
+    pub context_guard: bool,
+
+    #[arg(
+        long,
+        help = "check LM Studio, the native API, the model list, a one-token completion, the data directory and the listen ports, print a pass/fail report and exit (non-zero on failure) instead of serving"
+    )]
+    pub self_test: bool,
+
+    #[arg(
+        long,
+        requires = "self_test",
+        help = "skip --self-test's test completion"
+    )]
+    pub self_test_no_inference: bool,
+
+    #[arg(
+        long,
+        help = "gzip/deflate non-streaming responses over 1 KiB when the client sends Accept-Encoding; NDJSON/SSE streams are never compressed"
+    )]
+    pub enable_compression: bool,
+
+    #[arg(
+        long,
+        default_value = "60",
+        help = "how long a streamed response may wait for its first chunk (prompt eval, model warm-up) before failing"
+    )]
+    pub first_token_timeout_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "60",
+        help = "max gap between chunks once a streamed response has started before it is failed"
+    )]
+    pub stream_idle_timeout_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "15",
+        help = "seconds of silence on a /v1 SSE passthrough stream before a keep-alive comment is sent, so proxies in between keep it open; 0 = off"
+    )]
+    pub sse_keepalive_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "10",
+        help = "seconds to wait for a TCP connection to LM Studio; 0 = no limit"
+    )]
+    pub connect_timeout_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "300",
+        help = "overall limit in seconds for one LM Studio request, including a streamed body; 0 = unlimited (for very long generations)"
+    )]
+    pub request_timeout_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "32",
+        help = "idle keep-alive connections kept open to LM Studio"
+    )]
+    pub pool_max_idle_per_host: usize,
+
+    #[arg(
+        long,
+        requires = "tls_key",
+        help = "PEM certificate chain; with --tls-key, serve HTTPS on every TCP --listen address"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "tls_cert",
+        help = "PEM private key (PKCS#8, PKCS#1 or SEC1) for --tls-cert"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "browser origin allowed to call the proxy cross-origin (e.g. http://localhost:3000), or * for any; repeat or comma-separate. No CORS headers are sent when unset"
+    )]
+    pub cors_origin: Vec<String>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "client request header to pass on to LM Studio from /api/chat, /api/generate and /api/embed (e.g. X-Request-Id); repeat or comma-separate. A forwarded Authorization replaces --lmstudio-token for that request"
+    )]
+    pub forward_header: Vec<String>,
+
+    #[arg(
+        long,
+        help = "serve only the Ollama API: /v1/* and /api/v0|v1/* are not passed through to LM Studio and answer 404"
+    )]
+    pub disable_passthrough: bool,
+
+    #[arg(
+        long,
+        help = "list passthrough GET /v1/models ids under the Ollama names /api/tags uses (e.g. qwen3-8b:latest); virtual aliases are appended either way"
+    )]
+    pub ollama_model_ids: bool,
+
+    #[arg(
+        long,
+        help = "answer /api/generate with a proxy-issued `context` array and replay the earlier exchanges when a client sends it back (LM Studio has no token context of its own)"
+    )]
+    pub emulate_generate_context: bool,
+
+    #[arg(
+        long,
+        default_value = "1800",
+        help = "seconds a `context` issued by --emulate-generate-context stays usable"
+    )]
+    pub generate_context_ttl_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "cache up to this many embedding vectors per input string and model, so repeated inputs skip LM Studio; 0 = off"
+    )]
+    pub embedding_cache_size: u64,
+
+    #[arg(
+        long,
+        default_value = "3600",
+        help = "seconds a vector stays in the --embedding-cache-size cache"
+    )]
+    pub embedding_cache_ttl_seconds: u64,
+
+    #[arg(
+        long,
+        default_value_t = MAX_JSON_BODY_SIZE_BYTES,
+        help = "largest request body in bytes accepted from clients; bigger bodies get a 413"
+    )]
+    pub max_body_size: u64,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "largest /api/blobs upload in bytes; bigger uploads get a 413. Blobs are streamed to disk, so --max-body-size does not apply to them. 0 = unlimited"
+    )]
+    pub max_blob_size: u64,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "total size in MiB of stored /api/blobs uploads; an upload that goes over it evicts the least recently used blobs no model refers to. 0 = unlimited"
+    )]
+    pub blob_max_total_mb: u64,
+
+    #[arg(
+        long,
+        value_parser = parse_text_or_file,
+        help = "system prompt added to /api/chat and /api/generate requests that bring none of their own (a system message, `system`, or a virtual model's); @path reads it from a file"
+    )]
+    pub default_system_prompt: Option<String>,
+
+    #[arg(
+        long,
+        default_value = "0",
+        help = "seconds between background LM Studio health probes; while it is known unreachable, inference requests fail fast with a 503. 0 = off (health checked per request)"
+    )]
+    pub health_check_interval_seconds: u64,
+
+    #[arg(
+        long,
+        default_value = "2",
+        help = "seconds a direct /health probe of LM Studio is reused by later /health and /health/ready calls, including an unreachable result. 0 = probe every time"
+    )]
+    pub health_cache_seconds: u64,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_INDEFINITE_TTL_SECONDS,
+        help = "LM Studio ttl sent for a negative keep_alive (Ollama's \"stay loaded\"); 0 = send no ttl and leave it to LM Studio's idle timeout"
+    )]
+    pub indefinite_ttl_seconds: u64,
+
+    #[arg(
+        long,
+        help = "reject Ollama API request bodies not sent as `Content-Type: application/json` with a 415 (by default a missing or non-JSON content type is accepted when the body is JSON)"
+    )]
+    pub strict_json: bool,
+
+    #[arg(
+        long,
+        help = "reject /api/create and /api/copy when `parameters` has an unknown option key (by default the key is kept and a warning returned)"
+    )]
+    pub strict_params: bool,
+
+    #[arg(
+        long,
+        help = "add LM Studio's raw `stats` block (tokens_per_second, time_to_first_token, draft-model counts, ...) to non-streaming /api/chat and /api/generate responses as `proxy_stats`; a request can ask for it alone with ?stats=1"
+    )]
+    pub expose_stats: bool,
+}
+
+/// How an Ollama model name is matched against LM Studio model ids.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionMode {
+    /// Exact id, then substring, then token-overlap scoring.
+    #[default]
+    Fuzzy,
+    /// Case-insensitive exact id match only.
+    Exact,
+}
+
+/// How reasoning (chain-of-thought) text from the backend is surfaced to
+/// Ollama clients, on both streaming chunks and final responses.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReasoningMode {
+    /// Prepend reasoning to the visible `content` / `response` text.
+    Merge,
+    /// Emit reasoning in the separate `thinking` field (Ollama's shape).
+    #[default]
+    Separate,
+    /// Drop reasoning entirely.
+    Strip,
+}
+
+impl ReasoningMode {
+    /// Parse a request-level `reasoning_mode` value (case-insensitive).
+    pub fn parse(value: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(value.trim(), true).ok()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -145,6 +554,9 @@ pub struct RuntimeConfig {
     pub eval_batch_size: Option<u32>,
     pub default_context_length: Option<u64>,
     pub auto_evict: bool,
+    pub accurate_tokens: bool,
+    /// `--indefinite-ttl-seconds`; 0 omits `ttl` for a negative keep_alive.
+    pub indefinite_ttl_seconds: u64,
 }
 
 impl Default for RuntimeConfig {
@@ -157,6 +569,8 @@ impl Default for RuntimeConfig {
             eval_batch_size: None,
             default_context_length: None,
             auto_evict: false,
+            accurate_tokens: false,
+            indefinite_ttl_seconds: DEFAULT_INDEFINITE_TTL_SECONDS,
         }
     }
 }
@@ -174,18 +588,238 @@ pub fn get_runtime_config() -> &'static RuntimeConfig {
     })
 }
 
+/// One `--listen` target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// `unix:/path/to.sock` — a Unix domain socket (Unix platforms only).
+    Unix(PathBuf),
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl ListenAddr {
+    fn parse(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim();
+        if let Some(path) = raw.strip_prefix("unix:") {
+            if !cfg!(unix) {
+                return Err(format!(
+                    "unix socket listen addresses are not supported on this platform: {}",
+                    raw
+                ));
+            }
+            if path.is_empty() {
+                return Err(format!(
+                    "invalid listen address (empty socket path): {}",
+                    raw
+                ));
+            }
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        raw.parse::<SocketAddr>()
+            .map(Self::Tcp)
+            .map_err(|_| format!("invalid listen address: {}", raw))
+    }
+}
+
+/// Value as given, or the contents of the file when it starts with `@`.
+pub fn parse_text_or_file(value: &str) -> Result<String, String> {
+    let text = match value.strip_prefix('@') {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?
+        }
+        None => value.to_string(),
+    };
+    if text.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    Ok(text.trim().to_string())
+}
+
+/// Parse every `--listen` value into a socket address. IPv6 addresses use the
+/// bracketed form (`[::]:11434`, `[::1]:11434`) and `unix:<path>` selects a
+/// Unix domain socket; duplicates are dropped so the same address is never
+/// bound twice.
+pub fn parse_listen_addrs(listen: &[String]) -> Result<Vec<ListenAddr>, String> {
+    if listen.is_empty() {
+        return Err("at least one listen address is required".to_string());
+    }
+    let mut addrs = Vec::with_capacity(listen.len());
+    for raw in listen {
+        let addr = ListenAddr::parse(raw)?;
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+    Ok(addrs)
+}
+
+/// Connection-level and body-framing headers. They describe the client's hop
+/// to the proxy, or are set by the proxy itself, so `--forward-header` refuses them.
+const UNFORWARDABLE_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "content-length",
+    "content-type",
+    "transfer-encoding",
+    "keep-alive",
+    "upgrade",
+    "te",
+    "trailer",
+    "proxy-authorization",
+];
+
 pub fn validate_config(config: &Config) -> Result<(), String> {
-    if config.listen.parse::<std::net::SocketAddr>().is_err() {
-        return Err(format!("invalid listen address: {}", config.listen));
+    parse_listen_addrs(&config.listen)?;
+    if config.tls_cert.is_some() != config.tls_key.is_some() {
+        return Err("--tls-cert and --tls-key must be given together".to_string());
+    }
+    parse_model_name_map(&config.model_name_map)?;
+    for name in &config.forward_header {
+        let parsed = http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| format!("invalid --forward-header name: {}", name))?;
+        if UNFORWARDABLE_HEADERS.contains(&parsed.as_str()) {
+            return Err(format!(
+                "--forward-header cannot forward {}: the proxy sets it per hop",
+                name
+            ));
+        }
+    }
+    parse_lmstudio_headers(&config.lmstudio_header)?;
+    if let Some(bind) = &config.upstream_bind_interface {
+        parse_upstream_bind(bind)?;
+    }
+    if (config.log_file.is_some() || config.access_log_file.is_some())
+        && config.log_max_size_mb == 0
+    {
+        return Err("--log-max-size-mb must be at least 1".to_string());
     }
-    if !config.lmstudio_url.starts_with("http://") && !config.lmstudio_url.starts_with("https://") {
+    if config.max_body_size == 0 {
+        return Err("--max-body-size must be at least 1".to_string());
+    }
+    if config.embedding_cache_size > 0 && config.embedding_cache_ttl_seconds == 0 {
+        return Err("--embedding-cache-ttl-seconds must be at least 1".to_string());
+    }
+    if config.emulate_generate_context && config.generate_context_ttl_seconds == 0 {
+        return Err("--generate-context-ttl-seconds must be at least 1".to_string());
+    }
+    for origin in &config.cors_origin {
+        let has_host = url::Url::parse(origin).is_ok_and(|u| u.host().is_some());
+        if origin != "*" && !has_host {
+            return Err(format!(
+                "invalid --cors-origin (expected * or scheme://host[:port]): {}",
+                origin
+            ));
+        }
+    }
+    if let Some(endpoint) = &config.otlp_endpoint {
+        crate::telemetry::traces_url(endpoint)?;
+    }
+    validate_lmstudio_url(&config.lmstudio_url)?;
+    if let Some(fallback) = &config.lmstudio_fallback_url {
+        validate_lmstudio_url(fallback)?;
+    }
+    Ok(())
+}
+
+/// `--lmstudio-header` entries as a header map. Values are marked sensitive
+/// so they stay out of debug output.
+pub fn parse_lmstudio_headers(entries: &[String]) -> Result<http::HeaderMap, String> {
+    let mut headers = http::HeaderMap::with_capacity(entries.len());
+    for entry in entries {
+        let invalid = || {
+            format!(
+                "invalid --lmstudio-header (expected \"Name: value\"): {}",
+                entry
+            )
+        };
+        let (name, value) = entry
+            .split_once(':')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or_else(invalid)?;
+        let name = http::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+        if UNFORWARDABLE_HEADERS.contains(&name.as_str()) {
+            return Err(format!(
+                "--lmstudio-header cannot set {}: the proxy sets it per hop",
+                name
+            ));
+        }
+        let mut value = http::HeaderValue::from_str(value).map_err(|_| invalid())?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    Ok(headers)
+}
+
+/// Where `--upstream-bind-interface` binds LM Studio connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpstreamBind {
+    Address(IpAddr),
+    /// A network interface by name (`SO_BINDTODEVICE`); Linux only.
+    Interface(String),
+}
+
+pub fn parse_upstream_bind(value: &str) -> Result<UpstreamBind, String> {
+    let value = value.trim();
+    if let Ok(addr) = value.parse::<IpAddr>() {
+        return Ok(UpstreamBind::Address(addr));
+    }
+    if value.is_empty()
+        || !cfg!(any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux"
+        ))
+    {
+        return Err(format!(
+            "invalid --upstream-bind-interface {:?}: expected a local IP address{}",
+            value,
+            if cfg!(target_os = "linux") {
+                " or interface name"
+            } else {
+                ""
+            }
+        ));
+    }
+    Ok(UpstreamBind::Interface(value.to_string()))
+}
+
+/// `--model-name-map` entries as `name → LM Studio id`, keyed by the
+/// lowercased name without `:latest` so lookups ignore both, like pins.
+pub fn parse_model_name_map(entries: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut map = HashMap::with_capacity(entries.len());
+    for entry in entries {
+        let (name, id) = entry
+            .split_once('=')
+            .map(|(name, id)| (name.trim(), id.trim()))
+            .filter(|(name, id)| !name.is_empty() && !id.is_empty())
+            .ok_or_else(|| format!("invalid --model-name-map (expected name=id): {}", entry))?;
+        map.insert(clean_model_name(name).to_lowercase(), id.to_string());
+    }
+    Ok(map)
+}
+
+fn validate_lmstudio_url(lmstudio_url: &str) -> Result<(), String> {
+    if !lmstudio_url.starts_with("http://") && !lmstudio_url.starts_with("https://") {
         return Err(format!(
             "invalid LM Studio URL (must start with http:// or https://): {}",
-            config.lmstudio_url
+            lmstudio_url
         ));
     }
-    if let Err(e) = url::Url::parse(&config.lmstudio_url) {
+    if let Err(e) = url::Url::parse(lmstudio_url) {
         return Err(format!("invalid LM Studio URL format: {}", e));
     }
     Ok(())
 }
+
+#[cfg(test)]
+#[path = "../tests/unit/config.rs"]
+mod tests;
@@ -10,6 +10,7 @@ pub const LM_STUDIO_NATIVE_COMPLETIONS: &str = "/api/v0/completions";
 pub const LM_STUDIO_NATIVE_EMBEDDINGS: &str = "/v1/embeddings";
 pub const LM_STUDIO_NATIVE_DOWNLOAD: &str = "/api/v1/models/download";
 pub const LM_STUDIO_NATIVE_DOWNLOAD_STATUS: &str = "/api/v1/models/download/status";
+pub const LM_STUDIO_NATIVE_DOWNLOAD_CANCEL: &str = "/api/v1/models/download/cancel";
 pub const LM_STUDIO_NATIVE_UNLOAD: &str = "/api/v1/models/unload";
 pub const LM_STUDIO_MODELS_LOAD: &str = "/api/v1/models/load";
 
@@ -18,22 +19,31 @@ pub const OLLAMA_SERVER_VERSION: &str = "0.30.0";
 
 /// Timing and performance constants
 pub const TOKEN_TO_CHAR_RATIO: f64 = 0.25;
+/// `load_duration` for a model that was already resident: near zero, as a
+/// warm Ollama reports. Measured loads replace it.
 pub const DEFAULT_LOAD_DURATION_NS: u64 = 1_000_000;
 pub const TIMING_EVAL_RATIO: u64 = 2;
 pub const TIMING_PROMPT_RATIO: u64 = 4;
 pub const DEFAULT_STREAM_TIMEOUT_SECONDS: u64 = 60;
+/// Gap after which an idle SSE passthrough stream gets a comment frame.
+pub const DEFAULT_SSE_KEEPALIVE_SECONDS: u64 = 15;
+
+/// OpenAI `n > 1` fan-out: most candidates one request may ask for, and how
+/// many of them run against LM Studio at once.
+pub const MAX_FAN_OUT_CANDIDATES: u64 = 16;
+pub const FAN_OUT_CONCURRENCY: usize = 4;
 
 /// Response headers
 pub const CONTENT_TYPE_JSON: &str = "application/json; charset=utf-8";
-pub const CONTENT_TYPE_SSE: &str = "text/event-stream";
+pub const CONTENT_TYPE_SSE: &str = "text/event-stream; charset=utf-8";
+pub const CONTENT_TYPE_NDJSON: &str = "application/x-ndjson; charset=utf-8";
 pub const HEADER_CACHE_CONTROL: &str = "no-cache";
+/// `X-Accel-Buffering: no` stops nginx-style reverse proxies from holding
+/// stream chunks back.
+pub const HEADER_ACCEL_BUFFERING: &str = "no";
 pub const HEADER_CONNECTION: &str = "keep-alive";
-pub const HEADER_ACCESS_CONTROL_ALLOW_ORIGIN: &str = "*";
-pub const HEADER_ACCESS_CONTROL_ALLOW_METHODS: &str = "GET, POST, PUT, DELETE, OPTIONS";
-pub const HEADER_ACCESS_CONTROL_ALLOW_HEADERS: &str = "Content-Type, Authorization";
-
-/// Default parameter values
-pub const DEFAULT_KEEP_ALIVE_MINUTES: i64 = 5;
+/// Correlation id echoed to the client and forwarded to LM Studio.
+pub const HEADER_REQUEST_ID: &str = "x-request-id";
 
 /// Error messages
 pub const ERROR_MISSING_MODEL: &str = "Missing 'model' field";
@@ -42,7 +52,7 @@ pub const ERROR_MISSING_PROMPT: &str = "Missing 'prompt' field";
 pub const ERROR_EMBED_INPUT_REQUIRED: &str =
     "`input` field required (string or string[]). Use `/api/embeddings` for legacy `prompt`.";
 pub const ERROR_EMBED_INPUT_EMPTY: &str =
-    "`input` must not be empty (empty string, empty array, or array of only empty strings)";
+    "`input` must not be an array of only empty strings; omit it to just load the model";
 pub const ERROR_EMBEDDINGS_PROMPT_REQUIRED: &str =
     "`prompt` field required. Use `/api/embed` for batch `input`.";
 pub const ERROR_EMBEDDINGS_PROMPT_EMPTY: &str = "`prompt` must not be empty";
@@ -64,5 +74,8 @@ pub const LOG_PREFIX_WARNING: &str = "⚠️";
 pub const LOG_PREFIX_INFO: &str = "ℹ️";
 pub const LOG_PREFIX_CONN: &str = "↔️";
 
-/// Maximum accepted JSON body size (bytes)
+/// Default `--max-body-size` (bytes); also caps buffered LM Studio model listings.
 pub const MAX_JSON_BODY_SIZE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// `ttl` sent for a negative (`"stay loaded"`) `keep_alive`: one year.
+pub const DEFAULT_INDEFINITE_TTL_SECONDS: u64 = 365 * 24 * 60 * 60;
@@ -8,11 +8,27 @@ use serde_json::json;
 
 use crate::constants::ERROR_CANCELLED;
 
+/// What went wrong, where the status code alone can't tell: every upstream
+/// 503 looks the same, but only a backend that couldn't be reached at all
+/// should trip its breaker and fail over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorKind {
+    #[default]
+    Other,
+    /// The connection to LM Studio was refused or timed out.
+    Unreachable,
+}
+
 /// Error type for the proxy server
 #[derive(Debug, Clone)]
 pub struct ProxyError {
     pub message: String,
     pub status_code: u16,
+    pub kind: ErrorKind,
+    /// Seconds a client should wait before retrying; sent as `Retry-After`.
+    pub retry_after_seconds: Option<u64>,
+    /// Close model names for a failed lookup; sent as a `suggestions` array.
+    pub suggestions: Vec<String>,
 }
 
 impl ProxyError {
@@ -20,63 +36,62 @@ impl ProxyError {
         Self {
             message,
             status_code,
+            kind: ErrorKind::Other,
+            retry_after_seconds: None,
+            suggestions: Vec::new(),
         }
     }
 
     pub fn internal_server_error(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 500,
-        }
+        Self::new(message.to_string(), 500)
     }
 
     pub fn bad_request(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 400,
-        }
+        Self::new(message.to_string(), 400)
     }
 
     pub fn not_found(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 404,
-        }
+        Self::new(message.to_string(), 404)
     }
 
     pub fn not_implemented(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 501,
-        }
+        Self::new(message.to_string(), 501)
     }
 
     pub fn request_cancelled() -> Self {
-        Self {
-            message: ERROR_CANCELLED.to_string(),
-            status_code: 499,
-        }
+        Self::new(ERROR_CANCELLED.to_string(), 499)
     }
 
     pub fn lm_studio_unavailable(message: &str) -> Self {
+        Self::new(message.to_string(), 503)
+    }
+
+    /// A 503 for a connection to LM Studio that was refused or timed out.
+    pub fn unreachable(message: &str) -> Self {
         Self {
-            message: message.to_string(),
-            status_code: 503,
+            kind: ErrorKind::Unreachable,
+            ..Self::lm_studio_unavailable(message)
         }
     }
 
     pub fn too_many_requests(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 429,
-        }
+        Self::new(message.to_string(), 429)
     }
 
     pub fn bad_gateway(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-            status_code: 502,
-        }
+        Self::new(message.to_string(), 502)
+    }
+
+    /// Attach a `Retry-After` hint (seconds) to the error response.
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after_seconds = Some(seconds);
+        self
+    }
+
+    /// Attach model-name suggestions to a not-found error.
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
     }
 
     pub fn is_cancelled(&self) -> bool {
@@ -86,6 +101,10 @@ impl ProxyError {
     pub fn is_lm_studio_unavailable(&self) -> bool {
         self.status_code == 503
     }
+
+    pub fn is_unreachable(&self) -> bool {
+        self.kind == ErrorKind::Unreachable
+    }
 }
 
 impl fmt::Display for ProxyError {
@@ -100,10 +119,20 @@ impl IntoResponse for ProxyError {
     fn into_response(self) -> Response {
         let status =
             StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-        let body = Json(json!({
+        let mut body = json!({
             "error": self.message,
-        }));
-        (status, body).into_response()
+        });
+        if !self.suggestions.is_empty() {
+            body["suggestions"] = json!(self.suggestions);
+        }
+        let body = Json(body);
+        let mut response = (status, body).into_response();
+        if let Some(seconds) = self.retry_after_seconds {
+            response
+                .headers_mut()
+                .insert(http::header::RETRY_AFTER, seconds.into());
+        }
+        response
     }
 }
 
@@ -1,4 +1,5 @@
 use http::HeaderMap;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 
 use crate::error::ProxyError;
@@ -38,9 +39,7 @@ pub fn parse_json_body_template(
     body: &[u8],
 ) -> Result<Option<Value>, ProxyError> {
     if should_parse_as_json(headers, body)? {
-        serde_json::from_slice(body)
-            .map(Some)
-            .map_err(|e| ProxyError::bad_request(&format!("invalid JSON body: {}", e)))
+        parse_json_request(body).map(Some)
     } else {
         Ok(None)
     }
@@ -70,13 +69,92 @@ pub fn contains_json_content_type(headers: &HeaderMap) -> bool {
 }
 
 pub fn body_looks_like_json(body: &[u8]) -> bool {
-    let Ok(text) = std::str::from_utf8(body) else {
+    let Ok(text) = std::str::from_utf8(strip_utf8_bom(body)) else {
         return false;
     };
     let trimmed = text.trim();
     trimmed.starts_with('{') || trimmed.starts_with('[')
 }
 
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Bytes of context shown on each side of a JSON parse failure.
+const JSON_ERROR_SNIPPET_RADIUS: usize = 40;
+
+/// Drop a leading UTF-8 byte order mark, which some Windows clients send
+/// ahead of an otherwise valid JSON body.
+pub fn strip_utf8_bom(body: &[u8]) -> &[u8] {
+    body.strip_prefix(UTF8_BOM).unwrap_or(body)
+}
+
+/// Deserialize a client request body. A parse failure names the line and
+/// column (via serde's message) and quotes the text around it, so a bad byte
+/// deep inside a large payload can be found.
+pub fn parse_json_request<T: DeserializeOwned>(body: &[u8]) -> Result<T, ProxyError> {
+    let body = strip_utf8_bom(body);
+    serde_json::from_slice(body).map_err(|e| {
+        let snippet = json_error_snippet(body, e.line(), e.column());
+        let message = if snippet.is_empty() {
+            format!("invalid JSON body: {}", e)
+        } else {
+            format!("invalid JSON body: {} near `{}`", e, snippet)
+        };
+        ProxyError::bad_request(&message)
+    })
+}
+
+/// The 413 for a body over `--max-body-size`, with the size the client
+/// declared when it sent a `Content-Length`.
+pub fn body_too_large(limit: u64, content_length: Option<u64>) -> ProxyError {
+    too_large("request body", "--max-body-size", limit, content_length)
+}
+
+/// The 413 for a `/api/blobs` upload over `--max-blob-size`.
+pub fn blob_too_large(limit: u64, content_length: Option<u64>) -> ProxyError {
+    too_large("blob", "--max-blob-size", limit, content_length)
+}
+
+/// The 413 for a `/api/blobs` upload that alone is over
+/// `--blob-max-total-mb`, so storing it could never fit.
+pub fn blob_over_store_limit(limit: u64, content_length: Option<u64>) -> ProxyError {
+    too_large("blob", "--blob-max-total-mb", limit, content_length)
+}
+
+fn too_large(what: &str, flag: &str, limit: u64, content_length: Option<u64>) -> ProxyError {
+    let message = match content_length {
+        Some(length) => format!(
+            "{} too large: {} bytes exceeds the {}-byte limit ({})",
+            what, length, limit, flag
+        ),
+        None => format!(
+            "{} too large: exceeds the {}-byte limit ({})",
+            what, limit, flag
+        ),
+    };
+    ProxyError::new(message, 413)
+}
+
+/// Text around a 1-based `line`/`column` position, on one line.
+fn json_error_snippet(body: &[u8], line: usize, column: usize) -> String {
+    if line == 0 {
+        return String::new();
+    }
+    let line_start: usize = body
+        .split(|&b| b == b'\n')
+        .take(line - 1)
+        .map(|l| l.len() + 1)
+        .sum();
+    let at = (line_start + column.saturating_sub(1)).min(body.len());
+    let start = at.saturating_sub(JSON_ERROR_SNIPPET_RADIUS);
+    let end = (at + JSON_ERROR_SNIPPET_RADIUS).min(body.len());
+    String::from_utf8_lossy(&body[start..end])
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/http_body.rs"]
 mod tests;
@@ -1,19 +1,66 @@
+use std::time::Duration;
+
 use serde::Serialize;
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
 use crate::check_cancelled;
-use crate::constants::CONTENT_TYPE_JSON;
+use crate::config::Config;
+use crate::constants::{CONTENT_TYPE_JSON, HEADER_REQUEST_ID};
 use crate::error::ProxyError;
+use crate::http::error::upstream_error;
+use crate::telemetry::ChildSpan;
+
+/// Pool and timeout settings for the shared LM Studio client. A zero in the
+/// matching `Config` field leaves that timeout unset (unlimited).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientSettings {
+    pub connect_timeout: Option<Duration>,
+    pub request_timeout: Option<Duration>,
+    pub pool_max_idle_per_host: usize,
+}
+
+impl ClientSettings {
+    pub fn from_config(config: &Config) -> Self {
+        let non_zero = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        Self {
+            connect_timeout: non_zero(config.connect_timeout_seconds),
+            request_timeout: non_zero(config.request_timeout_seconds),
+            pool_max_idle_per_host: config.pool_max_idle_per_host,
+        }
+    }
+
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(request_timeout) = self.request_timeout {
+            builder = builder.timeout(request_timeout);
+        }
+        builder.pool_max_idle_per_host(self.pool_max_idle_per_host)
+    }
+}
 
 pub struct CancellableRequest<'a> {
     client: &'a reqwest::Client,
     token: CancellationToken,
+    headers: reqwest::header::HeaderMap,
 }
 
 impl<'a> CancellableRequest<'a> {
     pub fn new(client: &'a reqwest::Client, token: CancellationToken) -> Self {
-        Self { client, token }
+        Self {
+            client,
+            token,
+            headers: reqwest::header::HeaderMap::new(),
+        }
+    }
+
+    /// Extra headers sent with every `make_request`, e.g. the client headers
+    /// allowlisted by `--forward-header`.
+    pub fn with_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+        self.headers = headers;
+        self
     }
 
     pub fn token(&self) -> &CancellationToken {
@@ -28,22 +75,30 @@ impl<'a> CancellableRequest<'a> {
     ) -> Result<reqwest::Response, ProxyError> {
         check_cancelled!(self.token);
 
+        let mut span = upstream_span(&method, url);
         let mut request_builder = self.client.request(method, url);
 
+        let headers = with_request_id_header(self.headers.clone());
+        if !headers.is_empty() {
+            request_builder = request_builder.headers(headers);
+        }
+
         if let Some(body_content) = body {
             request_builder = request_builder
                 .header("Content-Type", CONTENT_TYPE_JSON)
                 .json(&body_content);
         }
 
-        tokio::select! {
+        let result = tokio::select! {
             result = request_builder.send() => {
                 result.map_err(crate::http::error::map_reqwest_error)
             }
             _ = self.token.cancelled() => {
                 Err(ProxyError::request_cancelled())
             }
-        }
+        };
+        record_upstream(&mut span, &result);
+        result
     }
 
     /// Make a raw HTTP request with custom headers and optional body
@@ -56,8 +111,10 @@ impl<'a> CancellableRequest<'a> {
     ) -> Result<reqwest::Response, ProxyError> {
         check_cancelled!(self.token);
 
+        let mut span = upstream_span(&method, url);
         let mut builder = self.client.request(method, url);
 
+        let headers = with_request_id_header(headers);
         if !headers.is_empty() {
             builder = builder.headers(headers);
         }
@@ -66,51 +123,73 @@ impl<'a> CancellableRequest<'a> {
             builder = builder.body(payload);
         }
 
-        tokio::select! {
+        let result = tokio::select! {
             result = builder.send() => {
                 result.map_err(crate::http::error::map_reqwest_error)
             }
             _ = self.token.cancelled() => {
                 Err(ProxyError::request_cancelled())
             }
+        };
+        record_upstream(&mut span, &result);
+        result
+    }
+}
+
+/// The `lmstudio.request` trace span for one LM Studio call; it ends when the
+/// response headers arrive, a streamed body being covered by the request's
+/// `response.stream` span.
+fn upstream_span(method: &reqwest::Method, url: &str) -> ChildSpan {
+    let mut span = crate::telemetry::child_span("lmstudio.request");
+    span.set_str("http.request.method", method.as_str());
+    span.set_str("url.full", url);
+    span
+}
+
+fn record_upstream(span: &mut ChildSpan, result: &Result<reqwest::Response, ProxyError>) {
+    match result {
+        Ok(response) => {
+            span.set_i64(
+                "http.response.status_code",
+                i64::from(response.status().as_u16()),
+            );
+            if response.status().is_server_error() {
+                span.set_error(response.status().as_str());
+            }
         }
+        Err(e) => span.set_error(&e.message),
     }
 }
 
+/// Tag an LM Studio request with the current correlation id, replacing any
+/// `X-Request-Id` the client headers carried.
+fn with_request_id_header(mut headers: reqwest::header::HeaderMap) -> reqwest::header::HeaderMap {
+    if let Some(id) = crate::logging::current_request_id()
+        && let Ok(value) = reqwest::header::HeaderValue::from_str(&id)
+    {
+        headers.insert(HEADER_REQUEST_ID, value);
+    }
+    headers
+}
+
 pub async fn handle_json_response(
     response: reqwest::Response,
     cancellation_token: CancellationToken,
 ) -> Result<Value, ProxyError> {
     check_cancelled!(cancellation_token);
 
-    let status = response.status();
-    let is_error = !status.is_success();
+    if !response.status().is_success() {
+        return tokio::select! {
+            error = upstream_error(response) => Err(error),
+            _ = cancellation_token.cancelled() => Err(ProxyError::request_cancelled()),
+        };
+    }
 
     tokio::select! {
         result = response.json::<Value>() => {
-            match result {
-                Ok(json_value) => {
-                    if is_error {
-                        let error_message = match json_value.get("error") {
-                            Some(Value::Object(obj)) => obj
-                                .get("message")
-                                .and_then(|m| m.as_str())
-                                .map(|s| s.to_string()),
-                            Some(Value::String(message)) => Some(message.clone()),
-                            _ => None,
-                        }
-                        .unwrap_or_else(|| format!("LM Studio error: {}", status));
-                        Err(ProxyError::new(error_message, status.as_u16()))
-                    } else {
-                        Ok(json_value)
-                    }
-                }
-                Err(e) => {
-                    Err(ProxyError::internal_server_error(&format!(
-                        "invalid JSON from LM Studio: {}", e
-                    )))
-                }
-            }
+            result.map_err(|e| {
+                ProxyError::internal_server_error(&format!("invalid JSON from LM Studio: {}", e))
+            })
         }
         _ = cancellation_token.cancelled() => {
             Err(ProxyError::request_cancelled())
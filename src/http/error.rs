@@ -1,17 +1,54 @@
+use serde_json::Value;
+
 use crate::constants::{ERROR_LM_STUDIO_UNAVAILABLE, ERROR_TIMEOUT};
 use crate::error::ProxyError;
 
 pub fn map_reqwest_error(err: reqwest::Error) -> ProxyError {
     if err.is_connect() {
-        ProxyError::lm_studio_unavailable(ERROR_LM_STUDIO_UNAVAILABLE)
+        ProxyError::unreachable(ERROR_LM_STUDIO_UNAVAILABLE)
     } else if err.is_timeout() {
-        ProxyError::lm_studio_unavailable(ERROR_TIMEOUT)
+        ProxyError::unreachable(ERROR_TIMEOUT)
     } else {
         log::error!("HTTP request failed: {}", err);
         ProxyError::internal_server_error(&format!("LM Studio request failed: {}", err))
     }
 }
 
+/// The message of an LM Studio error body, for Ollama's flat
+/// `{"error": "<string>"}`: OpenAI's nested `{"error": {"message": ...}}`,
+/// a flat `{"error": "..."}`, a bare `{"message": ...}`, or a short plain-text
+/// body. Falls back to naming the status.
+pub fn upstream_error_message(status: reqwest::StatusCode, body: &str) -> String {
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        let message = match value.get("error") {
+            Some(Value::Object(obj)) => obj.get("message").and_then(Value::as_str),
+            Some(Value::String(message)) => Some(message.as_str()),
+            _ => value.get("message").and_then(Value::as_str),
+        };
+        if let Some(message) = message.filter(|m| !m.is_empty()) {
+            return message.to_string();
+        }
+    } else {
+        let text = body.trim();
+        if !text.is_empty() && text.len() <= MAX_PLAIN_ERROR_LEN {
+            return text.to_string();
+        }
+    }
+    format!("LM Studio error: {}", status)
+}
+
+/// Plain-text error bodies longer than this (an HTML error page, say) are
+/// replaced by the status line.
+const MAX_PLAIN_ERROR_LEN: usize = 512;
+
+/// A `ProxyError` carrying an unsuccessful LM Studio response's status and
+/// message.
+pub async fn upstream_error(response: reqwest::Response) -> ProxyError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    ProxyError::new(upstream_error_message(status, &body), status.as_u16())
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/http_error.rs"]
 mod tests;
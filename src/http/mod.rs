@@ -3,6 +3,6 @@ pub mod client;
 pub mod error;
 pub mod response;
 
-pub use response::{build_forward_headers, json_response};
+pub use response::{build_forward_headers, json_response, select_forward_headers};
 
-pub use client::CancellableRequest;
+pub use client::{CancellableRequest, ClientSettings};
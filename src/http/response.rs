@@ -3,10 +3,7 @@ use axum::response::Response;
 use http::{HeaderMap, StatusCode, header};
 use serde_json::Value;
 
-use crate::constants::{
-    CONTENT_TYPE_JSON, HEADER_ACCESS_CONTROL_ALLOW_HEADERS, HEADER_ACCESS_CONTROL_ALLOW_METHODS,
-    HEADER_ACCESS_CONTROL_ALLOW_ORIGIN, HEADER_CACHE_CONTROL,
-};
+use crate::constants::{CONTENT_TYPE_JSON, HEADER_CACHE_CONTROL};
 
 pub fn is_json_response(response: &reqwest::Response) -> bool {
     response
@@ -26,18 +23,6 @@ pub fn json_response(value: &Value) -> Response {
         .header("Content-Type", CONTENT_TYPE_JSON)
         .header("Content-Length", content_length.to_string())
         .header("Cache-Control", HEADER_CACHE_CONTROL)
-        .header(
-            "Access-Control-Allow-Origin",
-            HEADER_ACCESS_CONTROL_ALLOW_ORIGIN,
-        )
-        .header(
-            "Access-Control-Allow-Methods",
-            HEADER_ACCESS_CONTROL_ALLOW_METHODS,
-        )
-        .header(
-            "Access-Control-Allow-Headers",
-            HEADER_ACCESS_CONTROL_ALLOW_HEADERS,
-        )
         .body(Body::from(json_string))
         .unwrap_or_else(|_| {
             Response::builder()
@@ -83,6 +68,27 @@ pub fn build_forward_headers(original: &HeaderMap, force_json: bool) -> reqwest:
     filtered
 }
 
+/// The client headers named in `allowlist` (`--forward-header`, matched
+/// case-insensitively), for the Ollama-native handlers' LM Studio requests.
+pub fn select_forward_headers(
+    original: &HeaderMap,
+    allowlist: &[String],
+) -> reqwest::header::HeaderMap {
+    let mut selected = reqwest::header::HeaderMap::new();
+    if allowlist.is_empty() {
+        return selected;
+    }
+    for (name, value) in original.iter() {
+        if allowlist
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(name.as_str()))
+        {
+            selected.append(name.clone(), value.clone());
+        }
+    }
+    selected
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/http_response.rs"]
 mod tests;
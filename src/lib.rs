@@ -9,6 +9,7 @@ pub mod model;
 pub mod proxy;
 pub mod storage;
 pub mod streaming;
+pub mod telemetry;
 pub mod update;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -19,12 +19,14 @@ use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 
-use crate::api::RequestContext;
-use crate::constants::{LM_STUDIO_NATIVE_DOWNLOAD, LM_STUDIO_NATIVE_DOWNLOAD_STATUS};
+use crate::api::{PullAttachment, PullJob};
+use crate::constants::{
+    LM_STUDIO_NATIVE_DOWNLOAD, LM_STUDIO_NATIVE_DOWNLOAD_CANCEL, LM_STUDIO_NATIVE_DOWNLOAD_STATUS,
+};
 use crate::error::ProxyError;
 use crate::http::client::{CancellableRequest, handle_json_response};
 use crate::logging::log_request;
-use crate::model::ModelResolver;
+use crate::model::ModelInfo;
 use crate::model::clean_model_name;
 use crate::storage::VirtualModelEntry;
 
@@ -130,6 +132,9 @@ pub fn looks_like_remote_identifier(identifier: &str) -> bool {
         || lowered.starts_with("hf://")
         || lowered.starts_with("s3://")
         || lowered.starts_with("gs://")
+        || HF_HOSTS
+            .iter()
+            .any(|host| lowered.starts_with(&format!("{}/", host)))
 }
 
 pub fn extract_virtual_download_source(entry: &VirtualModelEntry) -> Option<String> {
@@ -157,6 +162,79 @@ pub fn build_hf_download_url(publisher: &str, model_id: &str) -> String {
     )
 }
 
+/// Hosts whose web URLs name a Hugging Face model repo.
+const HF_HOSTS: &[&str] = &["huggingface.co", "www.huggingface.co", "hf.co"];
+
+/// The `owner/repo` a Hugging Face web URL points at, e.g.
+/// `https://huggingface.co/owner/repo/blob/main/model-Q4_K_M.gguf`. Also
+/// takes `hf://owner/repo` and scheme-less `huggingface.co/owner/repo`.
+/// `Ok(None)` when `identifier` is not a Hugging Face URL at all; an error
+/// when it is one that names no model repo (a dataset, a space, or a bare
+/// owner).
+pub fn hf_repo_from_url(identifier: &str) -> Result<Option<String>, ProxyError> {
+    let trimmed = identifier.trim();
+    let lowered = trimmed.to_ascii_lowercase();
+    let path = if lowered.starts_with("hf://") {
+        &trimmed["hf://".len()..]
+    } else {
+        let without_scheme = ["https://", "http://"]
+            .iter()
+            .find(|scheme| lowered.starts_with(*scheme))
+            .map_or(trimmed, |scheme| &trimmed[scheme.len()..]);
+        let (host, rest) = without_scheme
+            .split_once('/')
+            .unwrap_or((without_scheme, ""));
+        if !HF_HOSTS.contains(&host.to_ascii_lowercase().as_str()) {
+            return Ok(None);
+        }
+        rest
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let repo = match (segments.next(), segments.next()) {
+        (Some(owner), Some(repo))
+            if !matches!(owner, "datasets" | "spaces")
+                && [owner, repo].iter().all(|part| is_hf_name(part)) =>
+        {
+            Some(format!("{}/{}", owner, repo))
+        }
+        _ => None,
+    };
+    repo.map(Some).ok_or_else(|| {
+        ProxyError::bad_request(&format!(
+            "'{}' is not a Hugging Face model URL; expected https://huggingface.co/<owner>/<repo>",
+            identifier
+        ))
+    })
+}
+
+/// Hugging Face owner and repo names: letters, digits, `-`, `_` and `.`,
+/// not starting with a dot.
+fn is_hf_name(part: &str) -> bool {
+    !part.starts_with('.')
+        && part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// The identifier to post to LM Studio's download endpoint. It accepts
+/// catalog identifiers and exact repo links, so any Hugging Face web URL
+/// (a file, a branch, `hf.co`, `hf://`) becomes
+/// `https://huggingface.co/<owner>/<repo>`; everything else is unchanged.
+pub fn normalize_download_identifier(identifier: &str) -> Result<String, ProxyError> {
+    match hf_repo_from_url(identifier)? {
+        Some(repo) => {
+            let link = format!("https://huggingface.co/{}", repo);
+            if link != identifier {
+                log::debug!("download: '{}' normalized to '{}'", identifier, link);
+            }
+            Ok(link)
+        }
+        None => Ok(identifier.to_string()),
+    }
+}
+
 pub fn build_catalog_identifier(publisher: &str, model_id: &str) -> Option<String> {
     let trimmed = publisher.trim();
     if trimmed.is_empty() {
@@ -169,60 +247,53 @@ pub fn build_catalog_identifier(publisher: &str, model_id: &str) -> Option<Strin
     ))
 }
 
-pub async fn determine_download_identifier(
-    context: &RequestContext<'_>,
-    model_resolver: &std::sync::Arc<ModelResolver>,
+/// `resolved_model` is the requested name's target, its virtual alias and
+/// catalog entry, as resolved together by
+/// [`crate::api::ollama::resolution::resolve_model_target_and_fetch`].
+pub fn determine_download_identifier(
     requested_model: &str,
     source_override: Option<&str>,
-    resolved_model: Option<(String, Option<VirtualModelEntry>)>,
-    cancellation_token: CancellationToken,
-) -> Result<String, ProxyError> {
+    resolved_model: Option<(String, Option<VirtualModelEntry>, Option<ModelInfo>)>,
+) -> String {
     if let Some(source) = source_override {
-        return Ok(source.to_string());
+        return source.to_string();
     }
 
     if looks_like_remote_identifier(requested_model) {
-        return Ok(requested_model.to_string());
+        return requested_model.to_string();
     }
 
-    if let Some((resolved_model_id, virtual_entry)) = resolved_model {
+    if let Some((resolved_model_id, virtual_entry, model_info)) = resolved_model {
         if let Some(source) = virtual_entry
             .as_ref()
             .and_then(extract_virtual_download_source)
         {
-            return Ok(source);
+            return source;
         }
 
         if looks_like_remote_identifier(&resolved_model_id) {
-            return Ok(resolved_model_id);
+            return resolved_model_id;
         }
 
         if resolved_model_id.contains('/') && !resolved_model_id.contains(' ') {
-            return Ok(resolved_model_id);
+            return resolved_model_id;
         }
 
-        if let Some(model_info) = crate::api::ollama::resolution::fetch_model_info_for_id(
-            context,
-            model_resolver,
-            &resolved_model_id,
-            cancellation_token,
-        )
-        .await?
-        {
+        if let Some(model_info) = model_info {
             let cleaned_id = clean_model_name(&model_info.id).to_string();
             if publisher_prefers_hf_link(&model_info.publisher) {
-                return Ok(build_hf_download_url(&model_info.publisher, &cleaned_id));
+                return build_hf_download_url(&model_info.publisher, &cleaned_id);
             }
 
             if let Some(identifier) = build_catalog_identifier(&model_info.publisher, &cleaned_id) {
-                return Ok(identifier);
+                return identifier;
             }
         }
 
-        return Ok(resolved_model_id);
+        return resolved_model_id;
     }
 
-    Ok(requested_model.to_string())
+    requested_model.to_string()
 }
 
 // ---------------------------------------------------------------------------
@@ -236,24 +307,36 @@ pub async fn initiate_lmstudio_download(
     quantization: Option<&str>,
     cancellation_token: CancellationToken,
 ) -> Result<LmStudioDownloadStatus, ProxyError> {
+    let model_identifier = normalize_download_identifier(model_identifier)?;
     let mut payload = serde_json::Map::new();
-    payload.insert(
-        "model".to_string(),
-        Value::String(model_identifier.to_string()),
-    );
+    payload.insert("model".to_string(), Value::String(model_identifier.clone()));
     if let Some(q) = quantization {
         payload.insert("quantization".to_string(), Value::String(q.to_string()));
     }
 
     let url = format!("{}{}", base_url, LM_STUDIO_NATIVE_DOWNLOAD);
-    log_request("POST", &url, Some(model_identifier));
+    log_request("POST", &url, Some(&model_identifier));
 
     let request = CancellableRequest::new(client, cancellation_token);
     let response = request
         .make_request(Method::POST, &url, Some(Value::Object(payload)))
         .await?;
 
-    let response_value = handle_json_response(response, request.token().clone()).await?;
+    let response_value = handle_json_response(response, request.token().clone())
+        .await
+        .map_err(|e| {
+            if (400..500).contains(&e.status_code) && !e.is_cancelled() {
+                ProxyError::new(
+                    format!(
+                        "LM Studio rejected download of '{}': {}",
+                        model_identifier, e.message
+                    ),
+                    e.status_code,
+                )
+            } else {
+                e
+            }
+        })?;
 
     serde_json::from_value(response_value).map_err(|e| {
         ProxyError::internal_server_error(&format!("invalid download response: {}", e))
@@ -264,34 +347,61 @@ pub async fn wait_for_download_completion(
     client: &reqwest::Client,
     base_url: &str,
     mut status: LmStudioDownloadStatus,
-    cancellation_token: CancellationToken,
+    mut job: PullJob,
 ) -> Result<LmStudioDownloadStatus, ProxyError> {
     while !status.is_terminal() {
         let job_id = status.job_id()?.to_string();
         tokio::select! {
-            _ = cancellation_token.cancelled() => {
+            _ = job.token().cancelled() => {
                 return Err(ProxyError::request_cancelled());
             }
             _ = sleep(Duration::from_millis(DOWNLOAD_STATUS_POLL_INTERVAL_MS)) => {}
         }
+        if job.is_abandoned() {
+            return Err(ProxyError::request_cancelled());
+        }
         status =
-            fetch_lmstudio_download_status(client, base_url, &job_id, cancellation_token.clone())
-                .await?;
+            fetch_lmstudio_download_status(client, base_url, &job_id, job.token().clone()).await?;
+        job.record(&status.to_chunk(""));
+        job.publish(&status);
     }
+    job.finish();
     Ok(status)
 }
 
+/// Relay LM Studio's progress for one download as NDJSON chunks on `tx`.
+///
+/// Stops when the download ends, the client stops reading (`tx` closed), or
+/// `job`'s token fires (`DELETE /api/pull`, shutdown). In the last two cases
+/// `job` is dropped unfinished, which cancels the download in LM Studio.
+/// A shared job outlives its own client: it drops `attachment` and keeps
+/// polling for as long as another pull follows it.
 pub async fn stream_download_status_updates(
     client: reqwest::Client,
     base_url: String,
     mut status: LmStudioDownloadStatus,
     model_name: String,
-    cancellation_token: CancellationToken,
+    mut job: PullJob,
+    mut attachment: Option<PullAttachment>,
     tx: mpsc::UnboundedSender<Result<Bytes, std::io::Error>>,
 ) -> Result<(), ProxyError> {
+    let mut client_gone = false;
+    let abandoned =
+        |job: &PullJob, client_gone: bool| client_gone && (!job.is_shared() || job.is_abandoned());
     loop {
-        if !send_status_chunk(&tx, &status.to_chunk(&model_name)) {
-            return Ok(());
+        let chunk = status.to_chunk(&model_name);
+        job.record(&chunk);
+        job.publish(&status);
+        if !client_gone && !send_status_chunk(&tx, &chunk) {
+            client_gone = true;
+            attachment.take();
+        }
+        if abandoned(&job, client_gone) {
+            return Err(ProxyError::request_cancelled());
+        }
+
+        if status.is_terminal() {
+            job.finish();
         }
 
         if status.is_failure() {
@@ -309,19 +419,51 @@ pub async fn stream_download_status_updates(
 
         let job_id = status.job_id()?.to_string();
         tokio::select! {
-            _ = cancellation_token.cancelled() => {
+            _ = job.token().cancelled() => {
                 return Err(ProxyError::request_cancelled());
             }
             _ = sleep(Duration::from_millis(DOWNLOAD_STATUS_POLL_INTERVAL_MS)) => {}
         }
+        if abandoned(&job, client_gone) {
+            return Err(ProxyError::request_cancelled());
+        }
 
-        status =
-            fetch_lmstudio_download_status(&client, &base_url, &job_id, cancellation_token.clone())
-                .await?;
+        status = fetch_lmstudio_download_status(&client, &base_url, &job_id, job.token().clone())
+            .await?;
+    }
+}
+
+/// Ask LM Studio to stop the download `job_id`. LM Studio's REST docs list
+/// no cancel endpoint yet, so this is best effort: a build without one
+/// answers with an error and the download carries on there.
+pub async fn cancel_lmstudio_download(
+    client: &reqwest::Client,
+    base_url: &str,
+    job_id: &str,
+) -> Result<(), ProxyError> {
+    let url = format!("{}{}", base_url, LM_STUDIO_NATIVE_DOWNLOAD_CANCEL);
+    log_request("POST", &url, Some(job_id));
+
+    let request = CancellableRequest::new(client, CancellationToken::new());
+    let response = request
+        .make_request(
+            Method::POST,
+            &url,
+            Some(serde_json::json!({ "job_id": job_id })),
+        )
+        .await?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(ProxyError::new(
+            format!("LM Studio answered {} to download cancel", status),
+            status.as_u16(),
+        ))
     }
 }
 
-async fn fetch_lmstudio_download_status(
+pub(crate) async fn fetch_lmstudio_download_status(
     client: &reqwest::Client,
     base_url: &str,
     job_id: &str,
@@ -2,23 +2,28 @@ use std::borrow::Cow;
 
 use serde_json::{Value, json};
 
+/// Build the `data:<mime>;base64,<payload>` URL LM Studio expects for an
+/// Ollama `images` entry.
+///
+/// Ollama clients send bare base64, but some send a full data URL, which is
+/// returned unchanged. Line-wrapped base64 (MIME-style, 76 columns) has its
+/// whitespace removed so the payload survives strict decoders.
 fn image_data_url(base64_data: &str) -> String {
-    format!(
-        "data:{};base64,{}",
-        detect_image_mime(base64_data),
-        base64_data
-    )
+    let trimmed = base64_data.trim();
+    if trimmed.starts_with("data:") {
+        return trimmed.to_string();
+    }
+    let payload: Cow<'_, str> = if trimmed.contains(char::is_whitespace) {
+        Cow::Owned(trimmed.split_whitespace().collect())
+    } else {
+        Cow::Borrowed(trimmed)
+    };
+    format!("data:{};base64,{}", detect_image_mime(&payload), payload)
 }
 
-/// Build a `data:<mime>;base64,<payload>` URL for LM Studio's native
-/// `/api/v1/chat` `{type:"image", data_url}` input entries.
-///
-/// If the caller already passed a full data URL, it is returned unchanged;
-/// otherwise the MIME type is sniffed from the base64 magic prefix.
+/// Data URL for LM Studio's native `/api/v1/chat` `{type:"image", data_url}`
+/// input entries; same rules as the OpenAI-compat `image_url` parts.
 pub fn native_image_data_url(base64_data: &str) -> String {
-    if base64_data.starts_with("data:") {
-        return base64_data.to_string();
-    }
     image_data_url(base64_data)
 }
 
@@ -1,23 +1,24 @@
 //! Keep-alive translation and explicit model-unload spawning.
 //!
 //! Ollama keep_alive accepts:
-//!   - integer N: seconds (0 = unload now; negative = stay forever)
+//!   - number N: seconds (0 = unload now; negative = stay forever); a
+//!     fractional value is truncated to whole seconds (`5.5` → 5), except
+//!     that a positive one under a second becomes 1 rather than an unload
 //!   - string: Go-style duration ("5m", "1h30m", "500ms", optionally negative)
 //!
 //! LM Studio's `ttl` field is a non-negative seconds count; we normalize
-//! negative values to a `-1` sentinel and omit `ttl` from the request,
-//! letting LM Studio's default ("loaded indefinitely") apply.
+//! negative values to a `-1` sentinel and send `--indefinite-ttl-seconds`
+//! for it. Omitting `ttl` is not enough: a JIT-loaded model then falls back
+//! to LM Studio's idle TTL and is unloaded after all.
 
-use std::sync::Arc;
 use std::time::Duration;
 
 use humantime::parse_duration;
 use serde_json::{Value, json};
-use tokio_util::sync::CancellationToken;
 
+use crate::config::get_runtime_config;
 use crate::constants::{LM_STUDIO_NATIVE_MODELS, LM_STUDIO_NATIVE_UNLOAD};
 use crate::error::ProxyError;
-use crate::model::ModelResolver;
 use crate::model::types::NativeModelsResponse;
 
 const FOREVER_SENTINEL: i64 = -1;
@@ -49,7 +50,35 @@ fn parse_numeric(num: &serde_json::Number) -> Result<Option<i64>, ProxyError> {
             "keep_alive value exceeds supported range",
         ));
     }
-    Err(ProxyError::bad_request("keep_alive must be integral"))
+    match num.as_f64() {
+        Some(float) => parse_float(float),
+        None => Err(ProxyError::bad_request(
+            "keep_alive must be a finite number",
+        )),
+    }
+}
+
+/// Float seconds, truncated like a duration string's `as_secs`. Positive
+/// fractions below one second keep the model loaded for 1 s instead of
+/// unloading it.
+fn parse_float(secs: f64) -> Result<Option<i64>, ProxyError> {
+    if !secs.is_finite() {
+        return Err(ProxyError::bad_request(
+            "keep_alive must be a finite number",
+        ));
+    }
+    if secs < 0.0 {
+        return Ok(Some(FOREVER_SENTINEL));
+    }
+    if secs > 0.0 && secs < 1.0 {
+        return Ok(Some(1));
+    }
+    if secs >= i64::MAX as f64 {
+        return Err(ProxyError::bad_request(
+            "keep_alive value exceeds supported range",
+        ));
+    }
+    Ok(Some(secs.trunc() as i64))
 }
 
 fn parse_string(text: &str) -> Result<Option<i64>, ProxyError> {
@@ -100,32 +129,38 @@ fn normalize(secs: i64) -> i64 {
 }
 
 pub fn apply_keep_alive_ttl(target: &mut Value, keep_alive_seconds: Option<i64>) {
-    let Some(ttl) = keep_alive_seconds else {
-        return;
-    };
-    if ttl < 0 {
-        // "stay loaded forever" → omit ttl (LM Studio default applies)
-        return;
-    }
-    if let Some(obj) = target.as_object_mut() {
+    let indefinite = get_runtime_config().indefinite_ttl_seconds;
+    if let Some(ttl) = keep_alive_ttl(keep_alive_seconds, indefinite)
+        && let Some(obj) = target.as_object_mut()
+    {
         obj.insert("ttl".to_string(), Value::from(ttl));
     }
 }
 
+/// LM Studio `ttl` for a parsed keep_alive: positive and `0` pass through,
+/// "stay loaded forever" becomes `indefinite_ttl_seconds` (omitted when that
+/// is 0), and an absent keep_alive sends nothing.
+pub fn keep_alive_ttl(keep_alive_seconds: Option<i64>, indefinite_ttl_seconds: u64) -> Option<u64> {
+    match keep_alive_seconds? {
+        ttl if ttl < 0 => (indefinite_ttl_seconds > 0).then_some(indefinite_ttl_seconds),
+        ttl => Some(ttl as u64),
+    }
+}
+
 pub fn keep_alive_requests_unload(ttl: Option<i64>) -> bool {
     matches!(ttl, Some(value) if value == 0)
 }
 
 /// Spawns a background task to explicitly unload the model via LMStudio's native
-/// unload endpoint when `keep_alive: 0` is requested.
+/// unload endpoint when `keep_alive: 0` is requested. `model_key` is the LM
+/// Studio key the request's name already resolved to.
 ///
 /// The `delay_seconds` parameter allows callers to defer the unload (e.g. to let
 /// a streaming response finish before tearing down the model instance).
 pub fn spawn_model_unload_if_needed(
     client: reqwest::Client,
     base_url: String,
-    model_resolver: Arc<ModelResolver>,
-    ollama_model_name: String,
+    model_key: String,
     keep_alive_seconds: Option<i64>,
     delay_seconds: u64,
 ) {
@@ -133,32 +168,23 @@ pub fn spawn_model_unload_if_needed(
         return;
     }
 
-    tokio::spawn(async move {
+    crate::logging::spawn_with_request_id(async move {
         if delay_seconds > 0 {
             tokio::time::sleep(Duration::from_secs(delay_seconds)).await;
         }
-        if let Err(e) =
-            unload_model_instances(&client, &base_url, &model_resolver, &ollama_model_name).await
-        {
-            log::warn!(
-                "model unload failed for '{}': {}",
-                ollama_model_name,
-                e.message
-            );
+        if let Err(e) = unload_model_instances(&client, &base_url, &model_key).await {
+            log::warn!("model unload failed for '{}': {}", model_key, e.message);
         }
     });
 }
 
-async fn unload_model_instances(
+/// Unload every loaded instance of the LM Studio model `model_key`.
+/// Per-instance failures are logged and skipped.
+pub async fn unload_model_instances(
     client: &reqwest::Client,
     base_url: &str,
-    model_resolver: &Arc<ModelResolver>,
-    ollama_model_name: &str,
+    model_key: &str,
 ) -> Result<(), ProxyError> {
-    let lm_studio_id = model_resolver
-        .resolve_model_name(ollama_model_name, client, CancellationToken::new())
-        .await?;
-
     let models_url = format!("{}{}", base_url, LM_STUDIO_NATIVE_MODELS);
     let native: NativeModelsResponse = client
         .get(&models_url)
@@ -174,7 +200,7 @@ async fn unload_model_instances(
         })?;
 
     let unload_url = format!("{}{}", base_url, LM_STUDIO_NATIVE_UNLOAD);
-    for model in native.models.iter().filter(|m| m.key == lm_studio_id) {
+    for model in native.models.iter().filter(|m| m.key == model_key) {
         for instance in &model.loaded_instances {
             match client
                 .post(&unload_url)
@@ -6,6 +6,7 @@ pub mod loading_error;
 pub mod native_chat;
 pub mod request;
 pub mod response;
+pub mod tokens;
 
 pub use load_config::{build_load_config_body, ensure_context_length};
 pub use loading_error::is_model_loading_error;
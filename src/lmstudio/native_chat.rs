@@ -13,8 +13,8 @@
 //! tools), so client-supplied tools/tool_choice/format are intentionally NOT
 //! forwarded on this path. Clients needing those must use the default
 //! OpenAI-compat `/api/v0/chat/completions` path; MCP tools go via
-//! `integrations`. The native API likewise exposes no finish-reason, so
-//! `done_reason` is always `"stop"`.
+//! `integrations`. The native API documents no finish-reason, so `done_reason`
+//! is `"stop"` unless the `stats` block carries a `stop_reason`.
 //!
 //! Source of truth:
 //! - `api-docs/future/lmstudio/1_developer/2_rest/chat.md` (request + response)
@@ -24,7 +24,7 @@ use std::time::Instant;
 use serde_json::{Map, Value, json};
 
 use crate::lmstudio::request::normalize_reasoning;
-use crate::lmstudio::response::{TimingInfo, convert_tool_calls_to_ollama};
+use crate::lmstudio::response::{TimingInfo, convert_tool_calls_to_ollama, extract_stop_reason};
 use crate::streaming::chunks::map_done_reason;
 
 /// Parameters for building a native `/api/v1/chat` request body.
@@ -165,11 +165,13 @@ fn build_native_input(messages: &Value) -> Value {
 /// `message.content`; `{type:"reasoning"}` entries concatenate into
 /// `message.thinking` (omitted when empty); `{type:"tool_call"}` entries are
 /// collected and shaped via `convert_tool_calls_to_ollama`. Timing comes from
-/// the native `stats` block via the shared `TimingInfo::from_native_stats`.
+/// the native `stats` block via the shared `TimingInfo::from_native_stats`,
+/// with `prompt_tokens_estimate` standing in when it reports no input tokens.
 /// Output shape matches `convert_to_ollama_chat`.
 pub fn convert_native_to_ollama_chat(
     native_response: &Value,
     model_ollama_name: &str,
+    prompt_tokens_estimate: u64,
     start_time: Instant,
 ) -> Value {
     let NativeOutput {
@@ -181,8 +183,8 @@ pub fn convert_native_to_ollama_chat(
     let timing = TimingInfo::from_native_stats(
         native_response,
         start_time,
-        10,
-        crate::lmstudio::response::estimate_token_count(&content),
+        prompt_tokens_estimate.max(1),
+        crate::lmstudio::tokens::count_tokens(&content),
     );
 
     let mut ollama_message = json!({
@@ -202,10 +204,9 @@ pub fn convert_native_to_ollama_chat(
         }
     }
 
-    // The native API exposes no finish-reason anywhere (the non-stream stats
-    // block carries only token/timing data), so `done_reason` is always `"stop"`
-    // by backend limitation.
-    let done_reason = "stop";
+    // The native API documents no finish-reason; honour a `stats.stop_reason`
+    // when LM Studio sends one and fall back to `"stop"` otherwise.
+    let done_reason = native_done_reason_from(native_response);
 
     json!({
         "model": model_ollama_name,
@@ -296,6 +297,14 @@ pub fn native_done_reason() -> &'static str {
     map_done_reason("stop").unwrap_or("stop")
 }
 
+/// `done_reason` for a native body carrying a `stats` block, falling back to
+/// [`native_done_reason`] when it has no recognised `stop_reason`.
+pub fn native_done_reason_from(body: &Value) -> &'static str {
+    extract_stop_reason(body)
+        .and_then(map_done_reason)
+        .unwrap_or_else(native_done_reason)
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/lmstudio_native_chat.rs"]
 mod tests;
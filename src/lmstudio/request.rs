@@ -3,9 +3,20 @@ use std::borrow::Cow;
 use serde_json::{Value, json};
 
 pub enum LMStudioRequestType<'a> {
-    Chat { messages: &'a Value, stream: bool },
-    Completion { prompt: Cow<'a, str>, stream: bool },
-    Embeddings { input: &'a Value },
+    Chat {
+        messages: &'a Value,
+        stream: bool,
+    },
+    /// `suffix` is Ollama's fill-in-the-middle tail; LM Studio's completions
+    /// endpoint accepts it verbatim.
+    Completion {
+        prompt: Cow<'a, str>,
+        stream: bool,
+        suffix: Option<&'a str>,
+    },
+    Embeddings {
+        input: &'a Value,
+    },
 }
 
 pub struct TopLevelParams<'a> {
@@ -58,6 +69,89 @@ pub fn normalize_reasoning(think_val: &Value) -> Value {
     }
 }
 
+/// Most stop sequences OpenAI-compatible backends accept per request.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Normalise a `stop` value to the array of strings LM Studio expects.
+///
+/// Ollama (and OpenAI) accept a single string or an array, but some LM Studio
+/// builds reject the string form with a 400. A string becomes a one-element
+/// array; non-string entries and empty strings are dropped; the list is cut
+/// to [`MAX_STOP_SEQUENCES`] with a warning. `None` means nothing usable is
+/// left and `stop` should be omitted.
+pub fn normalize_stop(stop: &Value) -> Option<Value> {
+    let candidates: Vec<&Value> = match stop {
+        Value::String(_) => vec![stop],
+        Value::Array(entries) => entries.iter().collect(),
+        Value::Null => return None,
+        other => {
+            log::warn!("stop: expected a string or an array, ignoring {}", other);
+            return None;
+        }
+    };
+    let mut sequences: Vec<&str> = Vec::with_capacity(candidates.len());
+    for entry in candidates {
+        match entry.as_str() {
+            Some("") => {}
+            Some(sequence) => sequences.push(sequence),
+            None => log::warn!("stop: ignoring non-string entry {}", entry),
+        }
+    }
+    if sequences.len() > MAX_STOP_SEQUENCES {
+        log::warn!(
+            "stop: {} sequences given, keeping the first {}",
+            sequences.len(),
+            MAX_STOP_SEQUENCES
+        );
+        sequences.truncate(MAX_STOP_SEQUENCES);
+    }
+    (!sequences.is_empty()).then(|| json!(sequences))
+}
+
+/// Apply [`normalize_stop`] to the `stop` field of a request body in place.
+pub fn normalize_stop_field(body: &mut serde_json::Map<String, Value>) {
+    if let Some(stop) = body.get("stop") {
+        match normalize_stop(stop) {
+            Some(normalized) => {
+                body.insert("stop".to_string(), normalized);
+            }
+            None => {
+                body.remove("stop");
+            }
+        }
+    }
+}
+
+/// Lift an Ollama-style `options` object out of an otherwise OpenAI-shaped
+/// body, for half-migrated clients on the `/v1` passthrough. Known keys go
+/// through the same mapping as the Ollama handlers (`num_predict` →
+/// `max_tokens`, `stop` normalized, `format` → `response_format`); keys LM
+/// Studio has no equivalent for are dropped. A key already set at the top
+/// level wins. Bodies without an `options` object are left alone.
+pub fn lift_ollama_options(body: &mut serde_json::Map<String, Value>) {
+    if !body.get("options").is_some_and(Value::is_object) {
+        return;
+    }
+    let Some(options) = body.remove("options") else {
+        return;
+    };
+    let unsupported = collect_unsupported_keys(&options);
+    if !unsupported.is_empty() {
+        log::debug!(
+            "passthrough: dropped Ollama options LM Studio does not support: {}",
+            unsupported.join(", ")
+        );
+    }
+
+    let mut params = serde_json::Map::new();
+    map_direct_params(Some(&options), &mut params);
+    map_token_limits(Some(&options), &mut params);
+    map_format_params(Some(&options), None, &mut params);
+    for (key, value) in params {
+        body.entry(key).or_insert(value);
+    }
+}
+
 fn apply_top_level_params(
     top: &TopLevelParams<'_>,
     request_obj: &mut serde_json::Map<String, Value>,
@@ -99,9 +193,16 @@ pub fn build_lm_studio_request(
                 body.insert("tools".to_string(), tools_val.clone());
             }
         }
-        LMStudioRequestType::Completion { prompt, stream } => {
+        LMStudioRequestType::Completion {
+            prompt,
+            stream,
+            suffix,
+        } => {
             body.insert("prompt".to_string(), json!(prompt.as_ref()));
             body.insert("stream".to_string(), json!(stream));
+            if let Some(suffix) = suffix.filter(|s| !s.is_empty()) {
+                body.insert("suffix".to_string(), json!(suffix));
+            }
         }
         LMStudioRequestType::Embeddings { input } => {
             body.insert("input".to_string(), input.clone());
@@ -120,23 +221,41 @@ pub fn build_lm_studio_request(
     Value::Object(body)
 }
 
-fn map_direct_params(ollama_options: Option<&Value>, params: &mut serde_json::Map<String, Value>) {
-    // Listed in LM Studio's chat-completions doc
-    // (api-docs/lmstudio/1_developer/3_openai-compat/chat-completions.md).
-    // LM Studio v0 chat accepts `min_p` (verified live), so it is forwarded as a
-    // direct sampling key alongside `top_k`.
-    const DIRECT_MAPPINGS: &[&str] = &[
-        "temperature",
-        "top_p",
-        "top_k",
-        "min_p",
-        "seed",
-        "stop",
-        "presence_penalty",
-        "frequency_penalty",
-        "repeat_penalty",
-    ];
+// Listed in LM Studio's chat-completions doc
+// (api-docs/lmstudio/1_developer/3_openai-compat/chat-completions.md).
+// LM Studio v0 chat accepts `min_p` (verified live), so it is forwarded as a
+// direct sampling key alongside `top_k`.
+const DIRECT_MAPPINGS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "top_k",
+    "min_p",
+    "seed",
+    "stop",
+    "presence_penalty",
+    "frequency_penalty",
+    "repeat_penalty",
+];
+
+const EMBEDDINGS_ONLY: &[&str] = &["truncate", "dimensions"];
 
+/// Option keys read by name outside the mapping tables: token limits,
+/// `num_ctx` (a load-time reload), `format`, and the proxy's own extensions.
+const HANDLED_OPTION_KEYS: &[&str] = &[
+    "logit_bias",
+    "logprobs",
+    "top_logprobs",
+    "max_tokens",
+    "num_predict",
+    "num_ctx",
+    "format",
+    "system",
+    "reasoning_mode",
+    "instance_id",
+    "truncate_history",
+];
+
+fn map_direct_params(ollama_options: Option<&Value>, params: &mut serde_json::Map<String, Value>) {
     let Some(options) = ollama_options else {
         return;
     };
@@ -146,10 +265,18 @@ fn map_direct_params(ollama_options: Option<&Value>, params: &mut serde_json::Ma
             params.insert((*param).to_string(), value.clone());
         }
     }
+    normalize_stop_field(params);
 
     if let Some(logit_bias) = options.get("logit_bias") {
         params.insert("logit_bias".to_string(), logit_bias.clone());
     }
+
+    // Also accepted at the top level, which is applied later and wins.
+    for key in ["logprobs", "top_logprobs"] {
+        if let Some(value) = options.get(key) {
+            params.insert(key.to_string(), value.clone());
+        }
+    }
 }
 
 // Ollama spec (api-docs/ollama/api/embed.md) defines `truncate` and
@@ -159,8 +286,6 @@ fn forward_embeddings_only_params(
     ollama_options: Option<&Value>,
     body: &mut serde_json::Map<String, Value>,
 ) {
-    const EMBEDDINGS_ONLY: &[&str] = &["truncate", "dimensions"];
-
     if let Some(options) = ollama_options {
         for key in EMBEDDINGS_ONLY {
             if let Some(value) = options.get(key) {
@@ -239,6 +364,28 @@ pub(crate) fn collect_unsupported_keys(options: &Value) -> Vec<&'static str> {
         .collect()
 }
 
+/// Keys of an `options` object the request mapper does not recognize at
+/// all, neither mapped nor known-unsupported; usually a typo such as
+/// `temperatur`. Sorted for stable messages.
+pub fn collect_unknown_option_keys(options: &Value) -> Vec<String> {
+    let Some(map) = options.as_object() else {
+        return Vec::new();
+    };
+    let mut unknown: Vec<String> = map
+        .keys()
+        .filter(|key| {
+            let key = key.as_str();
+            !DIRECT_MAPPINGS.contains(&key)
+                && !EMBEDDINGS_ONLY.contains(&key)
+                && !HANDLED_OPTION_KEYS.contains(&key)
+                && !UNSUPPORTED_OPTION_KEYS.contains(&key)
+        })
+        .cloned()
+        .collect();
+    unknown.sort();
+    unknown
+}
+
 pub(crate) fn log_unsupported_options(options: &Value) {
     let keys = collect_unsupported_keys(options);
     if !keys.is_empty() {
@@ -2,9 +2,11 @@ use std::time::{Duration, Instant};
 
 use serde_json::{Value, json};
 
+use crate::config::ReasoningMode;
 use crate::constants::{
     DEFAULT_LOAD_DURATION_NS, TIMING_EVAL_RATIO, TIMING_PROMPT_RATIO, TOKEN_TO_CHAR_RATIO,
 };
+use crate::lmstudio::tokens::count_tokens;
 use crate::streaming::chunks::resolve_done_reason;
 
 /// Timing information for Ollama responses
@@ -138,17 +140,21 @@ impl TimingInfo {
         )
     }
 
-    pub fn from_stream_chunks(
+    /// Timings for a finished stream: the wall-clock `duration` split by
+    /// token counts, with LM Studio's `usage` counts (when a chunk carried
+    /// them) winning over the estimates.
+    pub fn from_stream(
         duration: Duration,
-        chunk_count_estimate: u64,
+        input_tokens_estimate: u64,
+        output_tokens_estimate: u64,
+        actual_prompt_tokens: Option<u64>,
         actual_completion_tokens: Option<u64>,
     ) -> Self {
-        let total_duration_ns = duration.as_nanos() as u64;
         Self::from_duration_and_tokens(
-            total_duration_ns,
-            10,
-            chunk_count_estimate.max(1),
-            None,
+            duration.as_nanos() as u64,
+            input_tokens_estimate,
+            output_tokens_estimate,
+            actual_prompt_tokens,
             actual_completion_tokens,
         )
     }
@@ -192,13 +198,61 @@ impl TimingInfo {
     }
 }
 
+/// Fold a load the proxy waited for (the JIT-on-error trigger before a
+/// retry) into an Ollama response's timings.
+///
+/// `load_duration` becomes the measured load and `total_duration` is raised
+/// to the request's wall-clock time, so it covers the load even when the
+/// upstream `stats` only timed generation. Wall-clock phase estimates that
+/// already absorbed the load are scaled down to what is left. No-op for a
+/// zero load.
+pub fn apply_measured_load(response: &mut Value, load: Duration, start_time: Instant) {
+    if load.is_zero() {
+        return;
+    }
+    let Some(obj) = response.as_object_mut() else {
+        return;
+    };
+    let load_ns = load.as_nanos() as u64;
+    let field = |obj: &serde_json::Map<String, Value>, key: &str| {
+        obj.get(key).and_then(|v| v.as_u64()).unwrap_or(0)
+    };
+
+    let total = field(obj, "total_duration")
+        .max(start_time.elapsed().as_nanos() as u64)
+        .max(load_ns);
+    let prompt_eval = field(obj, "prompt_eval_duration");
+    let eval = field(obj, "eval_duration");
+    let phases = prompt_eval + eval;
+    let remaining = total - load_ns;
+    if phases > remaining {
+        let scale = remaining as f64 / phases as f64;
+        if obj.contains_key("prompt_eval_duration") {
+            obj.insert(
+                "prompt_eval_duration".to_string(),
+                json!(((prompt_eval as f64 * scale) as u64).max(1)),
+            );
+        }
+        if obj.contains_key("eval_duration") {
+            obj.insert(
+                "eval_duration".to_string(),
+                json!(((eval as f64 * scale) as u64).max(1)),
+            );
+        }
+    }
+    obj.insert("total_duration".to_string(), json!(total));
+    obj.insert("load_duration".to_string(), json!(load_ns));
+}
+
 pub struct ResponseTransformer;
 
 impl ResponseTransformer {
+    /// `estimated_prompt_tokens` stands in for `prompt_eval_count` when LM
+    /// Studio reports no usage; see `context_guard::estimate_chat_tokens`.
     pub fn convert_to_ollama_chat(
         lm_response: &Value,
         model_ollama_name: &str,
-        message_count_for_estimation: usize,
+        estimated_prompt_tokens: u64,
         start_time: Instant,
     ) -> Value {
         let content = extract_chat_content(lm_response);
@@ -207,8 +261,8 @@ impl ResponseTransformer {
         let timing = TimingInfo::from_native_stats(
             lm_response,
             start_time,
-            (message_count_for_estimation * 10).max(1) as u64,
-            estimate_token_count(&content),
+            estimated_prompt_tokens.max(1),
+            count_tokens(&content),
         );
 
         let done_reason = extract_finish_reason(lm_response)
@@ -291,15 +345,16 @@ impl ResponseTransformer {
         let timing = TimingInfo::from_native_stats(
             lm_response,
             start_time,
-            estimate_token_count(prompt_for_estimation),
-            estimate_token_count(&content),
+            count_tokens(prompt_for_estimation),
+            count_tokens(&content),
         );
 
         let done_reason = extract_finish_reason(lm_response)
             .filter(|reason| !reason.is_empty())
             .map(resolve_done_reason);
-        // `context` is absent from the Ollama OpenAPI schema for GenerateResponse.
-        // LM Studio does not expose token IDs anyway, so the field is omitted.
+        // LM Studio exposes no token ids, so there is no real `context` to
+        // return; with --emulate-generate-context the response handler adds a
+        // proxy-issued one.
         let mut response_obj = json!({
             "model": model_ollama_name,
             "created_at": chrono::Utc::now().to_rfc3339(),
@@ -327,13 +382,60 @@ impl ResponseTransformer {
         response_obj
     }
 
+    /// `--expose-stats`: copy LM Studio's `stats` block onto `response` as
+    /// `proxy_stats`, untouched. No-op when LM Studio sent none.
+    pub fn attach_proxy_stats(response: &mut Value, lm_response: &Value) {
+        let Some(stats) = lm_response.get("stats").filter(|s| s.is_object()) else {
+            return;
+        };
+        if let Some(obj) = response.as_object_mut() {
+            obj.insert("proxy_stats".to_string(), stats.clone());
+        }
+    }
+
+    /// Reshape an Ollama chat or generate response's reasoning for `mode`,
+    /// matching what the streaming path does per chunk.
+    ///
+    /// Chat keeps reasoning in `message.thinking` with text in
+    /// `message.content`; generate uses top-level `thinking` and `response`.
+    /// `Merge` prepends the reasoning to the text field, `Strip` removes it,
+    /// `Separate` is a no-op.
+    pub fn apply_reasoning_mode(response: &mut Value, mode: ReasoningMode) {
+        if mode == ReasoningMode::Separate {
+            return;
+        }
+        let (target, text_key) = if response.get("message").is_some() {
+            (response.get_mut("message"), "content")
+        } else {
+            (Some(response), "response")
+        };
+        let Some(obj) = target.and_then(|v| v.as_object_mut()) else {
+            return;
+        };
+        let Some(thinking) = obj.remove("thinking") else {
+            return;
+        };
+        if mode == ReasoningMode::Merge
+            && let Some(thinking) = thinking.as_str().filter(|t| !t.is_empty())
+        {
+            let text = obj.get(text_key).and_then(|t| t.as_str()).unwrap_or("");
+            let merged = format!("{}{}", thinking, text);
+            obj.insert(text_key.to_string(), json!(merged));
+        }
+    }
+
+    /// `normalize` (a proxy-side option) scales each vector to unit L2 norm.
     pub fn convert_to_ollama_embeddings(
         lm_response: &Value,
         model_ollama_name: &str,
         input: &Value,
         start_time: Instant,
+        normalize: bool,
     ) -> Value {
-        let embeddings = Self::extract_embeddings(lm_response);
+        let mut embeddings = Self::extract_embeddings(lm_response);
+        if normalize {
+            embeddings.iter_mut().for_each(l2_normalize);
+        }
 
         // Embeddings carry no `usage` in the no-usage case, so when LM Studio omits
         // `usage.prompt_tokens` (which still wins inside `from_native_stats`) fall
@@ -375,38 +477,97 @@ impl ResponseTransformer {
             .unwrap_or_default()
     }
 
-    fn extract_embeddings(lm_response: &Value) -> Vec<Value> {
-        lm_response
-            .get("data")
-            .and_then(|d| d.as_array())
-            .map(|data_array| {
-                data_array
-                    .iter()
-                    .filter_map(|item| item.get("embedding").cloned())
-                    .collect()
+    /// Vectors in input order. OpenAI-style servers tag each item with the
+    /// `index` of the input it belongs to and need not return them sorted;
+    /// items without one keep their position.
+    pub fn extract_embeddings(lm_response: &Value) -> Vec<Value> {
+        let Some(data_array) = lm_response.get("data").and_then(|d| d.as_array()) else {
+            return Vec::new();
+        };
+        let mut indexed: Vec<(u64, Value)> = data_array
+            .iter()
+            .enumerate()
+            .filter_map(|(position, item)| {
+                let index = item
+                    .get("index")
+                    .and_then(|i| i.as_u64())
+                    .unwrap_or(position as u64);
+                item.get("embedding").map(|e| (index, e.clone()))
             })
-            .unwrap_or_default()
+            .collect();
+        indexed.sort_by_key(|(index, _)| *index);
+        indexed
+            .into_iter()
+            .map(|(_, embedding)| embedding)
+            .collect()
+    }
+}
+
+/// Scale a numeric vector to unit length; a zero vector is left as is.
+fn l2_normalize(vector: &mut Value) {
+    let Some(components) = vector.as_array_mut() else {
+        return;
+    };
+    let norm = components
+        .iter()
+        .filter_map(Value::as_f64)
+        .map(|x| x * x)
+        .sum::<f64>()
+        .sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return;
+    }
+    for component in components.iter_mut() {
+        if let Some(x) = component.as_f64() {
+            *component = json!(x / norm);
+        }
     }
 }
 
 /// Insert `logprobs` into an Ollama response object when the upstream choice carries them.
-///
-/// Ollama expects `array<Logprob>`; OpenAI wraps the same items in `{content: [...]}`.
-/// Extracts `.content` from `choices[0].logprobs` so the shapes align.
 fn insert_logprobs(lm_response: &Value, response: &mut Value) {
     if let Some(logprobs) = lm_response
         .get("choices")
         .and_then(|c| c.as_array()?.first())
         .and_then(|choice| choice.get("logprobs"))
-        .filter(|lp| !lp.is_null())
-        .and_then(|lp| lp.get("content"))
-        .filter(|content| !content.is_null())
+        .and_then(to_ollama_logprobs)
         && let Some(obj) = response.as_object_mut()
     {
-        obj.insert("logprobs".to_string(), logprobs.clone());
+        obj.insert("logprobs".to_string(), logprobs);
     }
 }
 
+/// A choice's `logprobs` as Ollama's `array<Logprob>`.
+///
+/// Chat completions wrap those same items in `{content: [...]}`, so the array
+/// is taken as is. Text completions use the legacy parallel arrays
+/// (`tokens`, `token_logprobs`, `top_logprobs` as `{token: logprob}` maps),
+/// which are zipped into items. `None` when absent or empty.
+pub fn to_ollama_logprobs(logprobs: &Value) -> Option<Value> {
+    if let Some(content) = logprobs.get("content").and_then(Value::as_array) {
+        return (!content.is_empty()).then(|| Value::Array(content.clone()));
+    }
+    let tokens = logprobs.get("tokens")?.as_array()?;
+    let token_logprobs = logprobs.get("token_logprobs")?.as_array()?;
+    let top = logprobs.get("top_logprobs").and_then(Value::as_array);
+    let items: Vec<Value> = tokens
+        .iter()
+        .zip(token_logprobs)
+        .enumerate()
+        .map(|(i, (token, logprob))| {
+            let mut item = json!({"token": token, "logprob": logprob});
+            if let Some(alternatives) = top.and_then(|top| top.get(i)).and_then(Value::as_object) {
+                item["top_logprobs"] = alternatives
+                    .iter()
+                    .map(|(token, logprob)| json!({"token": token, "logprob": logprob}))
+                    .collect();
+            }
+            item
+        })
+        .collect();
+    (!items.is_empty()).then_some(Value::Array(items))
+}
+
 fn extract_chat_content(lm_response: &Value) -> String {
     lm_response
         .get("choices")
@@ -484,12 +645,36 @@ fn estimate_embedding_input_tokens(input: &Value) -> u64 {
     }
 }
 
+/// The OpenAI `finish_reason`, or failing that LM Studio's native
+/// `stats.stop_reason` translated by [`map_stop_reason`].
 pub fn extract_finish_reason(lm_response: &Value) -> Option<&str> {
     lm_response
         .get("choices")
         .and_then(|c| c.as_array()?.first())
         .and_then(|choice| choice.get("finish_reason"))
         .and_then(|reason| reason.as_str())
+        .filter(|reason| !reason.is_empty())
+        .or_else(|| extract_stop_reason(lm_response))
+}
+
+/// `stats.stop_reason` of an LM Studio response, as an OpenAI `finish_reason`.
+pub fn extract_stop_reason(lm_response: &Value) -> Option<&'static str> {
+    lm_response
+        .get("stats")
+        .and_then(|stats| stats.get("stop_reason"))
+        .and_then(|reason| reason.as_str())
+        .and_then(map_stop_reason)
+}
+
+/// Translate LM Studio's native `stop_reason` to the OpenAI `finish_reason`
+/// it corresponds to; `None` for values with no equivalent (e.g. `failed`).
+pub fn map_stop_reason(stop_reason: &str) -> Option<&'static str> {
+    match stop_reason {
+        "eosFound" | "stopStringFound" | "userStopped" => Some("stop"),
+        "maxTokensReached" | "maxPredictedTokensReached" | "contextLengthReached" => Some("length"),
+        "toolCalls" => Some("tool_calls"),
+        _ => None,
+    }
 }
 
 /// Convert an OpenAI-format `tool_calls` array to the Ollama format.
@@ -547,57 +732,63 @@ pub fn convert_tool_calls_to_ollama(tool_calls: &[Value]) -> Value {
     json!(converted)
 }
 
+/// A tool call from an earlier assistant turn that no `role:"tool"` message has
+/// answered yet.
+struct PendingToolCall {
+    id: String,
+    name: String,
+}
+
 /// Convert a single Ollama-shaped message to OpenAI shape for forwarding to LM Studio.
 ///
 /// Handles two multi-turn shapes that Ollama and OpenAI represent differently:
 ///
-/// - `role:"tool"` — Ollama uses `tool_name`; OpenAI requires `name` and `tool_call_id`.
-///   We walk prior assistant messages to find a matching `tool_calls` entry by name and
-///   copy its `id` when available. If none is found we log a debug message and omit
-///   `tool_call_id` rather than emitting a fabricated id that could confuse the model.
+/// - `role:"assistant"` with `tool_calls` — Ollama uses object `arguments` and
+///   usually omits `id`; OpenAI requires a JSON string and an `id`. Missing ids
+///   are synthesized from the message and call position (`call_<msg>_<call>`),
+///   so they are stable across repeated requests with the same history. The
+///   calls become `pending` for the tool results that follow.
 ///
-/// - `role:"assistant"` with `tool_calls` — Ollama uses object `arguments`; OpenAI
-///   requires a JSON string. We also add the `id` and `type` fields required by OpenAI
-///   if they are absent, synthesizing a stable id from the call index.
-fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
+/// - `role:"tool"` — Ollama uses `tool_name`; OpenAI requires `name` and
+///   `tool_call_id`. An explicit `tool_call_id` is kept; otherwise the result
+///   claims the first unanswered pending call with the same name (or simply the
+///   next unanswered call when the client sent no name), so parallel calls to
+///   the same function pair up in order. With nothing to claim we log a debug
+///   message and omit `tool_call_id` rather than invent one.
+fn normalize_message(msg: &Value, msg_index: usize, pending: &mut Vec<PendingToolCall>) -> Value {
     let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("");
 
     match role {
         "tool" => {
-            let tool_name = msg.get("tool_name").and_then(|v| v.as_str()).unwrap_or("");
+            let tool_name = msg
+                .get("tool_name")
+                .or_else(|| msg.get("name"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
             let content = msg
                 .get("content")
                 .cloned()
                 .unwrap_or(Value::String(String::new()));
 
-            // Walk prior messages in reverse to find the most recent assistant tool_calls
-            // entry whose function.name matches this tool result.
-            let tool_call_id = prior.iter().rev().find_map(|m| {
-                let r = m.get("role").and_then(|v| v.as_str())?;
-                if !r.eq_ignore_ascii_case("assistant") {
-                    return None;
-                }
-                let calls = m.get("tool_calls").and_then(|v| v.as_array())?;
-                calls.iter().find_map(|tc| {
-                    let name = tc
-                        .get("function")
-                        .and_then(|f| f.get("name"))
-                        .and_then(|n| n.as_str())?;
-                    if name != tool_name {
-                        return None;
-                    }
-                    // Prefer an explicit `id` field; fall back to `function.index`.
-                    tc.get("id")
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_string())
-                        .or_else(|| {
-                            tc.get("function")
-                                .and_then(|f| f.get("index"))
-                                .and_then(|i| i.as_u64())
-                                .map(|i| format!("call_{i}"))
-                        })
-                })
-            });
+            let explicit_id = msg
+                .get("tool_call_id")
+                .and_then(|v| v.as_str())
+                .filter(|id| !id.is_empty());
+            let claim = match explicit_id {
+                Some(id) => pending.iter().position(|call| call.id == id),
+                None if tool_name.is_empty() => (!pending.is_empty()).then_some(0),
+                None => pending.iter().position(|call| call.name == tool_name),
+            };
+            let claimed = claim.map(|pos| pending.remove(pos));
+
+            let tool_call_id = explicit_id
+                .map(str::to_string)
+                .or_else(|| claimed.as_ref().map(|call| call.id.clone()));
+            let name = if tool_name.is_empty() {
+                claimed.map(|call| call.name).unwrap_or_default()
+            } else {
+                tool_name.to_string()
+            };
 
             if tool_call_id.is_none() {
                 log::debug!(
@@ -608,7 +799,7 @@ fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
 
             let mut out = serde_json::Map::with_capacity(4);
             out.insert("role".into(), Value::String("tool".into()));
-            out.insert("name".into(), Value::String(tool_name.into()));
+            out.insert("name".into(), Value::String(name));
             out.insert("content".into(), content);
             if let Some(id) = tool_call_id {
                 out.insert("tool_call_id".into(), Value::String(id));
@@ -619,6 +810,9 @@ fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
         "assistant" => {
             let tool_calls = msg.get("tool_calls").and_then(|v| v.as_array());
             if let Some(calls) = tool_calls {
+                // A new assistant tool turn supersedes any calls left unanswered.
+                pending.clear();
+
                 // Convert Ollama-shaped tool_calls to OpenAI shape.
                 let converted: Vec<Value> = calls
                     .iter()
@@ -627,8 +821,9 @@ fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
                         let id = tc
                             .get("id")
                             .and_then(|v| v.as_str())
+                            .filter(|id| !id.is_empty())
                             .map(|s| s.to_string())
-                            .unwrap_or_else(|| format!("call_{idx}"));
+                            .unwrap_or_else(|| format!("call_{msg_index}_{idx}"));
 
                         let name = tc
                             .get("function")
@@ -650,6 +845,11 @@ fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
                             }
                         };
 
+                        pending.push(PendingToolCall {
+                            id: id.clone(),
+                            name: name.to_string(),
+                        });
+
                         json!({
                             "id": id,
                             "type": "function",
@@ -682,10 +882,11 @@ fn normalize_message(msg: &Value, prior: &[Value]) -> Value {
 }
 
 pub fn normalize_chat_messages(messages: &[Value], system_prompt: Option<&str>) -> Value {
+    let mut pending = Vec::new();
     let normalized: Vec<Value> = messages
         .iter()
         .enumerate()
-        .map(|(i, msg)| normalize_message(msg, &messages[..i]))
+        .map(|(i, msg)| normalize_message(msg, i, &mut pending))
         .collect();
 
     if let Some(system_text) = system_prompt {
@@ -0,0 +1,52 @@
+//! Token counts for timing stats when LM Studio returns no `usage`.
+//!
+//! The default is the `len / 4` estimate from [`estimate_token_count`]. With
+//! `--accurate-tokens` (and the `accurate-tokens` cargo feature compiled in)
+//! text is run through the `cl100k_base` BPE instead, which tracks real
+//! tokenizers far better for code and non-English text.
+
+use crate::config::get_runtime_config;
+use crate::lmstudio::response::estimate_token_count;
+
+/// Token count for `text`: the BPE count when `--accurate-tokens` is on and
+/// available, otherwise the character-ratio estimate.
+pub fn count_tokens(text: &str) -> u64 {
+    if get_runtime_config().accurate_tokens
+        && let Some(count) = bpe_token_count(text)
+    {
+        return count;
+    }
+    estimate_token_count(text)
+}
+
+/// `cl100k_base` token count, or `None` when the tokenizer is not compiled in
+/// or failed to initialise.
+#[cfg(feature = "accurate-tokens")]
+pub fn bpe_token_count(text: &str) -> Option<u64> {
+    use std::sync::OnceLock;
+
+    static BPE: OnceLock<Option<tiktoken_rs::CoreBPE>> = OnceLock::new();
+    let bpe = BPE.get_or_init(|| match tiktoken_rs::cl100k_base() {
+        Ok(bpe) => Some(bpe),
+        Err(e) => {
+            log::warn!("tokenizer unavailable, falling back to estimates: {}", e);
+            None
+        }
+    });
+    bpe.as_ref()
+        .map(|bpe| bpe.encode_ordinary(text).len() as u64)
+}
+
+#[cfg(not(feature = "accurate-tokens"))]
+pub fn bpe_token_count(_text: &str) -> Option<u64> {
+    None
+}
+
+/// Whether this build can honour `--accurate-tokens`.
+pub const fn tokenizer_compiled_in() -> bool {
+    cfg!(feature = "accurate-tokens")
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/lmstudio_tokens.rs"]
+mod tests;
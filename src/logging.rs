@@ -1,4 +1,10 @@
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 use crate::constants::{LOG_PREFIX_ERROR, LOG_PREFIX_SUCCESS, LOG_PREFIX_WARNING};
@@ -26,6 +32,72 @@ impl LogConfig {
     }
 }
 
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Longest client-supplied `X-Request-Id` the proxy adopts as its own.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The correlation id of the request being handled on this task, if any.
+/// The log formatter prefixes every line with it.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run `fut` with `id` as its correlation id.
+pub async fn with_request_id<F: Future>(id: String, fut: F) -> F::Output {
+    REQUEST_ID.scope(id, fut).await
+}
+
+/// `tokio::spawn` that carries the current correlation id (and trace span)
+/// into the task, so stream pumps and background unloads still log under
+/// their request.
+pub fn spawn_with_request_id<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    if crate::telemetry::enabled() {
+        return spawn_in_request(crate::telemetry::propagate(fut));
+    }
+    spawn_in_request(fut)
+}
+
+fn spawn_in_request<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match current_request_id() {
+        Some(id) => tokio::spawn(REQUEST_ID.scope(id, fut)),
+        None => tokio::spawn(fut),
+    }
+}
+
+/// The client's `X-Request-Id` when it is short printable ASCII, otherwise a
+/// fresh 8-hex-digit id.
+pub fn accept_request_id(incoming: Option<&str>) -> String {
+    match incoming {
+        Some(id)
+            if !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic()) =>
+        {
+            id.to_string()
+        }
+        _ => new_request_id(),
+    }
+}
+
+fn new_request_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    static SEED: OnceLock<RandomState> = OnceLock::new();
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let hash = SEED.get_or_init(RandomState::new).hash_one(n);
+    format!("{:08x}", hash as u32)
+}
+
 pub fn log_request(method: &str, path: &str, model: Option<&str>) {
     match model {
         Some(m) => log::info!(
@@ -41,6 +113,7 @@ pub fn log_request(method: &str, path: &str, model: Option<&str>) {
 pub fn log_timed(prefix: &str, operation: &str, start: Instant) {
     let duration = start.elapsed();
     let formatted_duration = format_duration(duration);
+    crate::telemetry::event(operation, &[("duration", formatted_duration.as_str())]);
 
     match prefix {
         LOG_PREFIX_SUCCESS => log::info!("{} | {}", operation, formatted_duration),
@@ -98,3 +171,181 @@ pub fn log_handler_io(
         }
     }
 }
+
+/// Level prefix of a log line; colored for the console, plain for files.
+pub fn level_label(level: log::Level, colored: bool) -> &'static str {
+    match (level, colored) {
+        (log::Level::Error, true) => "\x1b[1;31merror:\x1b[0m",
+        (log::Level::Warn, true) => "\x1b[1;33mwarn:\x1b[0m",
+        (log::Level::Info, true) => "\x1b[1;32minfo:\x1b[0m",
+        (log::Level::Debug, true) => "\x1b[1;34mdebug:\x1b[0m",
+        (log::Level::Trace, true) => "\x1b[1;35mtrace:\x1b[0m",
+        (log::Level::Error, false) => "error:",
+        (log::Level::Warn, false) => "warn:",
+        (log::Level::Info, false) => "info:",
+        (log::Level::Debug, false) => "debug:",
+        (log::Level::Trace, false) => "trace:",
+    }
+}
+
+static LOG_REOPEN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Make every open [`RotatingFile`] reopen its path before its next line.
+pub fn reopen_log_files() {
+    LOG_REOPEN_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Append-only log file that rotates by size (`--log-file`).
+///
+/// Once `max_bytes` is reached, the next line starts a fresh file: `path`
+/// becomes `path.1`, `path.1` becomes `path.2`, and so on, dropping anything
+/// past `keep`. With `keep == 0` the file is truncated instead. Rotation only
+/// happens between lines, so a line is never split across files.
+///
+/// [`reopen_log_files`] (SIGHUP) makes it reopen `path` before the next line,
+/// for logrotate and friends that move the file away themselves.
+pub struct RotatingFile {
+    path: PathBuf,
+    reopened: u64,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+    at_line_start: bool,
+}
+
+impl RotatingFile {
+    pub fn open(path: &Path, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            reopened: LOG_REOPEN_GENERATION.load(Ordering::Relaxed),
+            max_bytes,
+            keep,
+            file,
+            written,
+            at_line_start: true,
+        })
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = self.file.metadata()?.len();
+        Ok(())
+    }
+
+    fn rotated_path(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.keep));
+            for n in (1..self.keep).rev() {
+                let from = self.rotated_path(n);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.at_line_start {
+            let generation = LOG_REOPEN_GENERATION.load(Ordering::Relaxed);
+            if generation != self.reopened {
+                self.reopened = generation;
+                self.reopen()?;
+            }
+        }
+        if self.at_line_start && self.written >= self.max_bytes {
+            self.rotate()?;
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        if n > 0 {
+            self.at_line_start = buf[n - 1] == b'\n';
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// How long an [`AccessLog`] line may sit in the buffer before it is written.
+pub const ACCESS_LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `--access-log-file`: one line per request, rotated like `--log-file`.
+/// Lines are buffered and written at most every
+/// [`ACCESS_LOG_FLUSH_INTERVAL`] (or when the buffer fills), not one
+/// syscall per request.
+pub struct AccessLog {
+    writer: std::sync::Mutex<(io::BufWriter<RotatingFile>, Instant)>,
+}
+
+impl AccessLog {
+    pub fn open(path: &Path, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let file = RotatingFile::open(path, max_bytes, keep)?;
+        Ok(Self {
+            writer: std::sync::Mutex::new((io::BufWriter::new(file), Instant::now())),
+        })
+    }
+
+    /// Append `line` with a UTC timestamp in front.
+    pub fn record(&self, line: &str) {
+        let mut guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let (writer, last_flush) = &mut *guard;
+        let stamped = format!(
+            "{} {}\n",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            line
+        );
+        if let Err(e) = writer.write_all(stamped.as_bytes()) {
+            log::warn!("access log write failed: {}", e);
+            return;
+        }
+        if last_flush.elapsed() >= ACCESS_LOG_FLUSH_INTERVAL {
+            let _ = writer.flush();
+            *last_flush = Instant::now();
+        }
+    }
+
+    pub fn flush(&self) {
+        let mut guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let (writer, last_flush) = &mut *guard;
+        if let Err(e) = writer.flush() {
+            log::warn!("access log flush failed: {}", e);
+        }
+        *last_flush = Instant::now();
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/unit/logging.rs"]
+mod tests;
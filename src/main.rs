@@ -1,6 +1,6 @@
 use clap::Parser;
 
-use ollama_lmstudio_proxy::{config, logging, proxy, update};
+use ollama_lmstudio_proxy::{config, logging, proxy, telemetry, update};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -8,7 +8,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     config::validate_config(&cfg)?;
 
-    setup_logging(&cfg.log_level)?;
+    setup_logging(&cfg)?;
 
     let debug_enabled =
         cfg.log_level.eq_ignore_ascii_case("debug") || cfg.log_level.eq_ignore_ascii_case("trace");
@@ -24,32 +24,91 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         eval_batch_size: cfg.eval_batch_size,
         default_context_length: cfg.default_context_length,
         auto_evict: cfg.auto_evict,
+        accurate_tokens: cfg.accurate_tokens,
+        indefinite_ttl_seconds: cfg.indefinite_ttl_seconds,
     });
 
+    if cfg.self_test {
+        let client = proxy::server::build_client(&cfg)?;
+        let report = proxy::self_test::run(
+            &cfg,
+            &client,
+            &proxy::server::get_state_directory(),
+            !cfg.self_test_no_inference,
+        )
+        .await;
+        print!("{}", report.render());
+        std::process::exit(if report.passed() { 0 } else { 1 });
+    }
+
+    if let Some(endpoint) = &cfg.otlp_endpoint
+        && let Err(e) = telemetry::init(endpoint)
+    {
+        log::warn!("--otlp-endpoint: {}; traces are not exported", e);
+    }
+
+    #[cfg(windows)]
+    if cfg.service {
+        let runtime = tokio::runtime::Handle::current();
+        // The dispatcher blocks this thread until the service stops.
+        tokio::task::spawn_blocking(move || proxy::service::run_windows_service(cfg, runtime))
+            .await??;
+        telemetry::shutdown();
+        return Ok(());
+    }
+
     let server = proxy::ProxyServer::new(cfg)?;
-    server.run().await
+    let result = server.run().await;
+    telemetry::shutdown();
+    result
 }
 
-fn setup_logging(log_level: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let level = log_level
+fn setup_logging(cfg: &config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    let level = cfg
+        .log_level
         .to_lowercase()
         .parse::<log::LevelFilter>()
         .unwrap_or(log::LevelFilter::Info);
 
-    fern::Dispatch::new()
-        .format(|out, message, record| {
-            let level_str = match record.level() {
-                log::Level::Error => "\x1b[1;31merror:\x1b[0m",
-                log::Level::Warn => "\x1b[1;33mwarn:\x1b[0m",
-                log::Level::Info => "\x1b[1;32minfo:\x1b[0m",
-                log::Level::Debug => "\x1b[1;34mdebug:\x1b[0m",
-                log::Level::Trace => "\x1b[1;35mtrace:\x1b[0m",
-            };
-            out.finish(format_args!("{} {}", level_str, message))
-        })
-        .level(level)
-        .chain(std::io::stdout())
-        .apply()?;
+    let mut dispatch = fern::Dispatch::new().level(level);
+
+    // --service has no console to write to; --log-file is required with it.
+    if !cfg.service {
+        dispatch = dispatch.chain(
+            fern::Dispatch::new()
+                .format(|out, message, record| format_line(out, message, record, true))
+                .chain(std::io::stdout()),
+        );
+    }
 
+    if let Some(path) = &cfg.log_file {
+        let file = logging::RotatingFile::open(
+            path,
+            cfg.log_max_size_mb.saturating_mul(1024 * 1024),
+            cfg.log_max_files,
+        )
+        .map_err(|e| format!("cannot open --log-file {}: {}", path.display(), e))?;
+        let writer: Box<dyn std::io::Write + Send> = Box::new(file);
+        dispatch = dispatch.chain(
+            fern::Dispatch::new()
+                .format(|out, message, record| format_line(out, message, record, false))
+                .chain(writer),
+        );
+    }
+
+    dispatch.apply()?;
     Ok(())
 }
+
+fn format_line(
+    out: fern::FormatCallback,
+    message: &std::fmt::Arguments,
+    record: &log::Record,
+    colored: bool,
+) {
+    let level_str = logging::level_label(record.level(), colored);
+    match logging::current_request_id() {
+        Some(id) => out.finish(format_args!("{} [{}] {}", level_str, id, message)),
+        None => out.finish(format_args!("{} {}", level_str, message)),
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::response::Response;
+use futures_util::StreamExt;
+use serde_json::{Value, json};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::error::ProxyError;
+
+/// Per-model in-flight bookkeeping. `semaphore` is `None` when no limit is
+/// configured; the counters are still kept so `/health` can report load.
+struct Slot {
+    semaphore: Option<Arc<Semaphore>>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+/// Caps how many inference requests may hit LM Studio for the same model at
+/// once (`--max-concurrent-per-model`).
+///
+/// Keyed by the resolved LM Studio model id, so two aliases of one model share
+/// a budget. A request over the limit waits up to `max_wait` for a slot and is
+/// then rejected with 429; a zero `max_wait` rejects immediately.
+///
+/// Exposed behind `Arc` so handlers share one instance across requests.
+pub struct ModelConcurrency {
+    limit: Option<usize>,
+    max_wait: Duration,
+    slots: Mutex<HashMap<String, Arc<Slot>>>,
+}
+
+/// A held slot for one model. Dropping it frees the slot; for streaming
+/// responses, [`ModelPermit::attach`] keeps it alive until the body finishes.
+pub struct ModelPermit {
+    slot: Arc<Slot>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for ModelPermit {
+    fn drop(&mut self) {
+        self.slot.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ModelPermit {
+    /// Tie this permit to `response`'s body so the slot stays taken until the
+    /// client has received the whole (possibly streaming) response.
+    pub fn attach(self, response: Response) -> Response {
+        let (parts, body) = response.into_parts();
+        let stream = body.into_data_stream().map(move |chunk| {
+            let _held = &self;
+            chunk
+        });
+        Response::from_parts(parts, Body::from_stream(stream))
+    }
+}
+
+impl ModelConcurrency {
+    pub fn new(limit: Option<usize>, max_wait: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            // A limit of 0 would deadlock every request; treat it as unlimited.
+            limit: limit.filter(|n| *n > 0),
+            max_wait,
+            slots: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// No limit, no waiting — the default when the flag is unset.
+    pub fn unlimited() -> Arc<Self> {
+        Self::new(None, Duration::ZERO)
+    }
+
+    fn slot(&self, model_id: &str) -> Arc<Slot> {
+        let mut slots = self
+            .slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        slots
+            .entry(model_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(Slot {
+                    semaphore: self.limit.map(|n| Arc::new(Semaphore::new(n))),
+                    in_flight: AtomicUsize::new(0),
+                    queued: AtomicUsize::new(0),
+                })
+            })
+            .clone()
+    }
+
+    /// Take a slot for `model_id`, waiting up to the configured max wait.
+    pub async fn acquire(&self, model_id: &str) -> Result<ModelPermit, ProxyError> {
+        let slot = self.slot(model_id);
+        let Some(semaphore) = slot.semaphore.clone() else {
+            slot.in_flight.fetch_add(1, Ordering::Relaxed);
+            return Ok(ModelPermit {
+                slot,
+                _permit: None,
+            });
+        };
+
+        let permit = match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) if self.max_wait.is_zero() => return Err(self.busy_error(model_id)),
+            Err(_) => {
+                slot.queued.fetch_add(1, Ordering::Relaxed);
+                let waited = tokio::time::timeout(self.max_wait, semaphore.acquire_owned()).await;
+                slot.queued.fetch_sub(1, Ordering::Relaxed);
+                match waited {
+                    Ok(Ok(permit)) => permit,
+                    _ => return Err(self.busy_error(model_id)),
+                }
+            }
+        };
+
+        slot.in_flight.fetch_add(1, Ordering::Relaxed);
+        Ok(ModelPermit {
+            slot,
+            _permit: Some(permit),
+        })
+    }
+
+    fn busy_error(&self, model_id: &str) -> ProxyError {
+        let retry_after = self.max_wait.as_secs().max(1);
+        ProxyError::too_many_requests(&format!(
+            "model '{}' is at its concurrency limit ({} in flight); retry in {}s",
+            model_id,
+            self.limit.unwrap_or_default(),
+            retry_after
+        ))
+        .with_retry_after(retry_after)
+    }
+
+    /// Current in-flight / queued counts for every model with activity, for
+    /// the `/health` report.
+    pub fn snapshot(&self) -> Value {
+        let slots = self
+            .slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let models: BTreeMap<&str, Value> = slots
+            .iter()
+            .filter_map(|(id, slot)| {
+                let in_flight = slot.in_flight.load(Ordering::Relaxed);
+                let queued = slot.queued.load(Ordering::Relaxed);
+                (in_flight > 0 || queued > 0).then(|| {
+                    (
+                        id.as_str(),
+                        json!({ "in_flight": in_flight, "queued": queued }),
+                    )
+                })
+            })
+            .collect();
+        json!({
+            "max_concurrent_per_model": self.limit,
+            "models": models,
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/model_concurrency.rs"]
+mod tests;
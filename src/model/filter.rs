@@ -0,0 +1,81 @@
+//! `--model-allowlist` / `--model-blocklist`: hide LM Studio models from
+//! listings and resolution.
+//!
+//! Patterns are case-insensitive globs (`*` = any run, `?` = one character)
+//! matched against the LM Studio model id, with or without a `:latest` tag.
+//! A model is visible when it matches the allowlist (or the allowlist is empty)
+//! and matches nothing on the blocklist. Virtual aliases are never filtered:
+//! an alias is an explicit opt-in to its target.
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelFilter {
+    allow: Vec<String>,
+    block: Vec<String>,
+}
+
+impl ModelFilter {
+    pub fn new(allow: &[String], block: &[String]) -> Self {
+        let normalize = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| p.trim().to_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect()
+        };
+        Self {
+            allow: normalize(allow),
+            block: normalize(block),
+        }
+    }
+
+    /// Whether any pattern is configured; when false every model is visible.
+    pub fn is_active(&self) -> bool {
+        !self.allow.is_empty() || !self.block.is_empty()
+    }
+
+    pub fn is_visible(&self, model_id: &str) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let id = model_id.to_lowercase();
+        let tagged = if id.contains(':') {
+            None
+        } else {
+            Some(format!("{id}:latest"))
+        };
+        let matches_any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|p| glob_match(p, &id) || tagged.as_deref().is_some_and(|t| glob_match(p, t)))
+        };
+        (self.allow.is_empty() || matches_any(&self.allow)) && !matches_any(&self.block)
+    }
+}
+
+/// Iterative `*`/`?` glob match with single-star backtracking.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/model_filter.rs"]
+mod tests;
@@ -34,8 +34,8 @@ enum StoredTtl {
 /// its own best-effort deadline: a model the proxy loaded at `t` with `ttl` of
 /// `d` seconds expires at `t + d`. `Forever` yields a far-future timestamp that
 /// matches real Ollama's behaviour for `keep_alive: -1`. Models the proxy never
-/// loaded (e.g. loaded out-of-band) stay untracked → the caller falls back to
-/// its existing placeholder.
+/// loaded (e.g. loaded out-of-band) stay untracked → `/api/ps` omits
+/// `expires_at` for them.
 ///
 /// Exposed behind `Arc` so handlers share one instance across requests.
 #[derive(Default)]
@@ -54,7 +54,7 @@ impl LoadTracker {
     /// - `Forever` — mark as loaded indefinitely.
     /// - `Unknown` — refresh `loaded_at`; PRESERVE any existing deadline.
     ///   A fresh Unknown entry has no stored deadline → `expires_at_unix` returns
-    ///   `None`, which lets `/api/ps` fall back to its now+default placeholder.
+    ///   `None`, and `/api/ps` omits `expires_at`.
     pub fn record(&self, key: &str, intent: KeepAlive) {
         let stored = match intent {
             KeepAlive::Finite(d) if !d.is_zero() => Some(StoredTtl::Finite(d)),
@@ -92,7 +92,7 @@ impl LoadTracker {
     /// - Forever → `Some(far_future_unix)`. The exact sentinel (~100 years from
     ///   now) mirrors real Ollama's far-future `expires_at` for `keep_alive: -1`;
     ///   the spec (api-docs/ollama/api/ps.md) does not prescribe the value.
-    /// - Unknown deadline or untracked → `None` (caller uses placeholder).
+    /// - Unknown deadline or untracked → `None` (caller omits the field).
     pub fn expires_at_unix(&self, key: &str) -> Option<i64> {
         let guard = self
             .entries
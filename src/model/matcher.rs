@@ -4,81 +4,205 @@
 //! and a slice of LM Studio models, pick the single best target. The ordering
 //! is fully deterministic — the same input always produces the same output —
 //! so model resolution does not depend on LM Studio's response ordering.
+//!
+//! A quantization tag on the query (`llama3:Q4_K_M`) is split off before
+//! matching and used to prefer the candidate whose `quantization` agrees.
+
+use std::cmp::Ordering;
+
+use crate::model::naming::split_quant_hint;
+
+/// Score bonus for a scored-stage candidate whose quantization matches the
+/// query's hint — large enough to outrank any name-overlap difference between
+/// two quants of the same model.
+const QUANT_HINT_BONUS: usize = 50;
+
+/// Score every loaded model gets regardless of the query.
+pub const LOADED_SCORE_BONUS: usize = 2;
 
 #[derive(Debug, Clone)]
 pub struct ModelMatchView {
     pub id: String,
     pub arch: String,
     pub model_type: String,
+    pub quantization: String,
     pub is_loaded: bool,
 }
 
+impl ModelMatchView {
+    fn has_quant(&self, hint: Option<&str>) -> bool {
+        hint.is_some_and(|q| self.quantization.eq_ignore_ascii_case(q))
+    }
+}
+
+/// A query split the way every matching rule reads it.
+struct Query<'q> {
+    /// The whole query, lowercased.
+    full: String,
+    /// The query without its quantization hint, lowercased.
+    base: String,
+    quant_hint: Option<&'q str>,
+}
+
+impl<'q> Query<'q> {
+    fn new(query: &'q str) -> Self {
+        let (base, quant_hint) = split_quant_hint(query);
+        Self {
+            full: query.to_lowercase(),
+            base: base.to_lowercase(),
+            quant_hint,
+        }
+    }
+}
+
+/// One model's standing for a query: the precedence rule it qualifies under
+/// and its token-overlap score (quant bonus included once it passes the
+/// scored threshold). Both [`find_best_match`] and [`explain_matches`] read
+/// candidates through this, so the diagnostics cannot drift from the pick.
+fn evaluate(query: &Query<'_>, model: &ModelMatchView) -> (MatchStage, usize) {
+    let lowered = model.id.to_lowercase();
+    let raw_score = calculate_match_score(&query.base, model, &lowered);
+    let score = if raw_score >= 3 {
+        with_quant_bonus(raw_score, model, query.quant_hint)
+    } else {
+        raw_score
+    };
+    let stage =
+        if lowered == query.full || (lowered == query.base && model.has_quant(query.quant_hint)) {
+            MatchStage::Exact
+        } else if lowered.contains(&*query.base)
+            && (query.base.len() > model.id.len() / 2 || query.base.len() > 10)
+        {
+            MatchStage::Substring
+        } else if raw_score >= 3 {
+            MatchStage::Scored
+        } else {
+            MatchStage::Rejected
+        };
+    (stage, score)
+}
+
+/// The order [`find_best_match`] prefers candidates in: by stage, then
+/// within a stage by that stage's tiebreak.
+fn precedence(
+    query: &Query<'_>,
+    (a, a_stage, a_score): (&ModelMatchView, MatchStage, usize),
+    (b, b_stage, b_score): (&ModelMatchView, MatchStage, usize),
+) -> Ordering {
+    a_stage.cmp(&b_stage).then_with(|| match a_stage {
+        // the full query over the name with its quant hint removed
+        MatchStage::Exact => (b.id.to_lowercase() == query.full)
+            .cmp(&(a.id.to_lowercase() == query.full))
+            .then_with(|| a.id.cmp(&b.id)),
+        // hinted quant > loaded > shortest id length > lex(id)
+        MatchStage::Substring => b
+            .has_quant(query.quant_hint)
+            .cmp(&a.has_quant(query.quant_hint))
+            .then_with(|| b.is_loaded.cmp(&a.is_loaded))
+            .then_with(|| a.id.len().cmp(&b.id.len()))
+            .then_with(|| a.id.cmp(&b.id)),
+        // higher score > loaded > lex(id)
+        _ => b_score
+            .cmp(&a_score)
+            .then_with(|| b.is_loaded.cmp(&a.is_loaded))
+            .then_with(|| a.id.cmp(&b.id)),
+    })
+}
+
 /// Return the best match for `query` among `models`, or `None` if no candidate
 /// is plausible. Precedence:
-///   1. exact match (case-insensitive)
-///   2. substring match — pick loaded over not, then shortest id, then lex order
-///   3. scored token-overlap match (≥ 3 points)
+///   1. exact match (case-insensitive) on the full query, or on the name with
+///      its quantization hint removed when that model has the hinted quant
+///   2. substring match — pick hinted quant, then loaded over not, then
+///      shortest id, then lex order
+///   3. scored token-overlap match (≥ 3 points), hinted quant earning a bonus
 pub fn find_best_match<'a>(
     query: &str,
     models: &'a [ModelMatchView],
 ) -> Option<&'a ModelMatchView> {
-    if models.is_empty() {
-        return None;
-    }
-    let lower_query = query.to_lowercase();
-    let lowered_ids: Vec<String> = models.iter().map(|m| m.id.to_lowercase()).collect();
-
-    // 1. Exact (case-insensitive) match.
-    for (i, lowered) in lowered_ids.iter().enumerate() {
-        if *lowered == lower_query {
-            return Some(&models[i]);
-        }
-    }
-
-    // 2. Substring matches — collect all then break ties deterministically.
-    let substring_idxs: Vec<usize> = lowered_ids
+    let query = Query::new(query);
+    models
         .iter()
-        .enumerate()
-        .filter(|(i, lowered)| {
-            lowered.contains(&*lower_query)
-                && (lower_query.len() > models[*i].id.len() / 2 || lower_query.len() > 10)
+        .map(|m| {
+            let (stage, score) = evaluate(&query, m);
+            (m, stage, score)
         })
-        .map(|(i, _)| i)
-        .collect();
+        .filter(|(_, stage, _)| *stage != MatchStage::Rejected)
+        .min_by(|a, b| precedence(&query, *a, *b))
+        .map(|(m, _, _)| m)
+}
+
+/// Which precedence rule of [`find_best_match`] a candidate qualifies under,
+/// in precedence order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchStage {
+    Exact,
+    /// The target of a pin on the name, which wins over every rule but an
+    /// exact match. Set by the resolver; the matcher knows nothing of pins.
+    Pinned,
+    Substring,
+    Scored,
+    /// Below every threshold, or ruled out by `--resolution exact`; never
+    /// picked.
+    Rejected,
+}
 
-    if !substring_idxs.is_empty() {
-        // loaded > shortest id length > lex(id)
-        let best = substring_idxs
-            .iter()
-            .min_by(|&&a, &&b| {
-                let ma = &models[a];
-                let mb = &models[b];
-                ma.is_loaded
-                    .cmp(&mb.is_loaded)
-                    .reverse()
-                    .then_with(|| ma.id.len().cmp(&mb.id.len()))
-                    .then_with(|| ma.id.cmp(&mb.id))
-            })
-            .copied()
-            .unwrap();
-        return Some(&models[best]);
+impl MatchStage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MatchStage::Exact => "exact",
+            MatchStage::Pinned => "pinned",
+            MatchStage::Substring => "substring",
+            MatchStage::Scored => "scored",
+            MatchStage::Rejected => "rejected",
+        }
     }
+}
 
-    // 3. Token-overlap scored match, with deterministic tiebreak (lex id).
-    let mut scored: Vec<(usize, usize)> = models
+/// One model's standing for a query, as reported by [`explain_matches`].
+#[derive(Debug, Clone)]
+pub struct MatchCandidate {
+    pub id: String,
+    pub score: usize,
+    pub stage: MatchStage,
+    pub is_loaded: bool,
+}
+
+/// Score every model against `query` for diagnostics, highest score first
+/// (ties: loaded first, then lex id — the same tiebreak the scored stage uses).
+///
+/// `score` is the token-overlap score for every candidate, even those an
+/// exact or substring rule would pick regardless; `stage` says which rule
+/// applies, so the precedence in [`find_best_match`] can still be read off.
+pub fn explain_matches(query: &str, models: &[ModelMatchView]) -> Vec<MatchCandidate> {
+    let query = Query::new(query);
+    let mut candidates: Vec<MatchCandidate> = models
         .iter()
-        .enumerate()
-        .filter_map(|(i, m)| {
-            let score = calculate_match_score(&lower_query, m, &lowered_ids[i]);
-            (score >= 3).then_some((i, score))
+        .map(|m| {
+            let (stage, score) = evaluate(&query, m);
+            MatchCandidate {
+                id: m.id.clone(),
+                score,
+                stage,
+                is_loaded: m.is_loaded,
+            }
         })
         .collect();
-    scored.sort_by(|a, b| {
-        b.1.cmp(&a.1) // higher score first
-            .then_with(|| models[a.0].is_loaded.cmp(&models[b.0].is_loaded).reverse())
-            .then_with(|| models[a.0].id.cmp(&models[b.0].id))
+    candidates.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.is_loaded.cmp(&b.is_loaded).reverse())
+            .then_with(|| a.id.cmp(&b.id))
     });
-    scored.first().map(|(i, _)| &models[*i])
+    candidates
+}
+
+fn with_quant_bonus(score: usize, model: &ModelMatchView, quant_hint: Option<&str>) -> usize {
+    if model.has_quant(quant_hint) {
+        score + QUANT_HINT_BONUS
+    } else {
+        score
+    }
 }
 
 fn calculate_match_score(query: &str, model: &ModelMatchView, model_id_lower: &str) -> usize {
@@ -115,7 +239,7 @@ fn calculate_match_score(query: &str, model: &ModelMatchView, model_id_lower: &s
     }
 
     if model.is_loaded {
-        score += 2;
+        score += LOADED_SCORE_BONUS;
     }
 
     if model_id_lower.starts_with(query) {
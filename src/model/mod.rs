@@ -1,11 +1,17 @@
+pub mod concurrency;
+pub mod filter;
 pub mod load_tracker;
 pub mod matcher;
 pub mod naming;
 pub mod param_count;
 pub mod resolver;
+pub mod runtime_info;
 pub mod types;
 
+pub use concurrency::ModelConcurrency;
+pub use filter::ModelFilter;
 pub use load_tracker::LoadTracker;
 pub use naming::clean_model_name;
 pub use resolver::ModelResolver;
+pub use runtime_info::RuntimeInfoCache;
 pub use types::ModelInfo;
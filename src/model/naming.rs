@@ -10,6 +10,16 @@ pub fn extract_required_model_name(body: &Value) -> Result<&str, ProxyError> {
         .ok_or_else(|| ProxyError::bad_request(ERROR_MISSING_MODEL))
 }
 
+/// The name `/api/tags` lists an LM Studio key under: the key itself when it
+/// carries a tag, otherwise `<key>:latest`.
+pub fn ollama_model_name(key: &str) -> String {
+    if key.contains(':') {
+        key.to_string()
+    } else {
+        format!("{}:latest", key)
+    }
+}
+
 pub fn clean_model_name(name: &str) -> &str {
     if let Some(pos) = name.rfind(":latest") {
         &name[..pos]
@@ -18,6 +28,47 @@ pub fn clean_model_name(name: &str) -> &str {
     }
 }
 
+/// Split a quantization hint off an Ollama tag: `llama3:Q4_K_M` →
+/// (`llama3`, `Some("Q4_K_M")`), `llama3:8b-instruct-q8_0` →
+/// (`llama3:8b-instruct`, `Some("q8_0")`). Names whose tag is not a
+/// quantization label come back unchanged with `None`.
+pub fn split_quant_hint(name: &str) -> (&str, Option<&str>) {
+    let Some(colon) = name.rfind(':') else {
+        return (name, None);
+    };
+    let tag = &name[colon + 1..];
+    if is_quant_label(tag) {
+        return (&name[..colon], Some(tag));
+    }
+    if let Some(dash) = tag.rfind('-')
+        && is_quant_label(&tag[dash + 1..])
+    {
+        return (&name[..colon + 1 + dash], Some(&tag[dash + 1..]));
+    }
+    (name, None)
+}
+
+/// GGUF-style quantization labels: `q4_0`, `Q4_K_M`, `iq3_xs`, `f16`, `bf16`,
+/// `fp16`, `f32`, `fp32`, or MLX-style `4bit`/`8bit` (case-insensitive).
+fn is_quant_label(label: &str) -> bool {
+    let lower = label.to_ascii_lowercase();
+    if matches!(lower.as_str(), "f16" | "f32" | "bf16" | "fp16" | "fp32") {
+        return true;
+    }
+    if let Some(bits) = lower.strip_suffix("bit") {
+        return !bits.is_empty() && bits.bytes().all(|b| b.is_ascii_digit());
+    }
+    let rest = lower.strip_prefix("iq").or_else(|| lower.strip_prefix('q'));
+    let Some(rest) = rest else {
+        return false;
+    };
+    let mut parts = rest.split('_');
+    let bits = parts.next().unwrap_or_default();
+    !bits.is_empty()
+        && bits.bytes().all(|b| b.is_ascii_digit())
+        && parts.all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_alphanumeric()))
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/model_naming.rs"]
 mod tests;
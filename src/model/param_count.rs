@@ -27,6 +27,16 @@ pub fn parse_parameter_count(s: &str) -> Option<u64> {
         (upper.as_str(), 1_u64)
     };
 
+    // Mixture-of-experts shorthand: "8X7" is eight experts of 7B each.
+    if let Some((experts, per_expert)) = number_part.split_once('X') {
+        let experts: u64 = experts.trim().parse().ok()?;
+        let per_expert: f64 = per_expert.trim().parse().ok()?;
+        if !per_expert.is_finite() || per_expert < 0.0 {
+            return None;
+        }
+        return Some((experts as f64 * per_expert * multiplier as f64).round() as u64);
+    }
+
     let number: f64 = number_part.trim().parse().ok()?;
     if !number.is_finite() || number < 0.0 {
         return None;
@@ -34,6 +44,47 @@ pub fn parse_parameter_count(s: &str) -> Option<u64> {
     Some((number * multiplier as f64).round() as u64)
 }
 
+/// The size token in a model id, as Ollama would show it: `7b` → `7B`,
+/// `qwen2.5-0.5b` → `0.5B`, MoE `mixtral-8x7b` → `8x7B`. A million-count
+/// token such as `500m` is shown in billions from 500M up and as `135M`
+/// below. Only whole tokens between separators count, so a version
+/// (`qwen2.5`), a context size (`4k`) or a quant (`q4_k_m`) is never taken
+/// for a size. `None` when the id has no size token.
+pub fn parameter_size_from_id(id: &str) -> Option<String> {
+    id.to_ascii_lowercase()
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '.'))
+        .find_map(size_token)
+}
+
+fn size_token(token: &str) -> Option<String> {
+    let (number, unit) = token.split_at(token.len().checked_sub(1)?);
+    let is_number = |s: &str| {
+        !s.is_empty() && s.starts_with(|c: char| c.is_ascii_digit()) && s.parse::<f64>().is_ok()
+    };
+    match unit {
+        "b" => match number.split_once('x') {
+            Some((experts, per_expert))
+                if experts.chars().all(|c| c.is_ascii_digit())
+                    && !experts.is_empty()
+                    && is_number(per_expert) =>
+            {
+                Some(format!("{}x{}B", experts, per_expert))
+            }
+            None if is_number(number) => Some(format!("{}B", number)),
+            _ => None,
+        },
+        "m" if is_number(number) => {
+            let millions: f64 = number.parse().ok()?;
+            if millions >= 500.0 {
+                Some(format!("{}B", millions / 1000.0))
+            } else {
+                Some(format!("{}M", number))
+            }
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/model_param_count.rs"]
 mod tests;
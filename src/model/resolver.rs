@@ -1,20 +1,40 @@
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use moka::future::Cache;
 use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
+use crate::config::ResolutionMode;
 use crate::constants::{ERROR_LM_STUDIO_UNAVAILABLE, LM_STUDIO_NATIVE_MODELS, LOG_PREFIX_SUCCESS};
 use crate::error::ProxyError;
 use crate::http::CancellableRequest;
 use crate::logging::log_timed;
-use crate::model::matcher::{ModelMatchView, find_best_match};
+use crate::model::filter::ModelFilter;
+use crate::model::matcher::{
+    LOADED_SCORE_BONUS, MatchCandidate, MatchStage, ModelMatchView, explain_matches,
+    find_best_match,
+};
 use crate::model::naming::clean_model_name;
 use crate::model::types::{ModelInfo, NativeModelsResponse};
+use crate::storage::ModelPinStore;
+use crate::telemetry::ChildSpan;
+
+/// How many close matches a not-found error lists.
+const MAX_SUGGESTIONS: usize = 3;
 
 pub struct ModelResolver {
     lmstudio_url: String,
     cache: Cache<String, String>,
+    mode: ResolutionMode,
+    /// Short-lived copy of the LM Studio catalog shared by `/api/tags`,
+    /// `/api/ps` and `/api/show`; `None` when `--models-cache-ttl-seconds` is 0.
+    models_cache: Option<Cache<(), Arc<Vec<ModelInfo>>>>,
+    /// Models hidden by the filter cannot be resolved by name.
+    filter: Arc<ModelFilter>,
+    /// `/api/proxy/pins`: names steered to one LM Studio id ahead of the
+    /// mode's matching.
+    pins: Option<Arc<ModelPinStore>>,
 }
 
 impl ModelResolver {
@@ -22,9 +42,82 @@ impl ModelResolver {
         Self {
             lmstudio_url,
             cache,
+            mode: ResolutionMode::default(),
+            models_cache: None,
+            filter: Arc::new(ModelFilter::default()),
+            pins: None,
+        }
+    }
+
+    pub fn with_resolution_mode(mut self, mode: ResolutionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Cache the model list returned by [`Self::get_all_models`] for `ttl`.
+    /// A zero `ttl` leaves caching off.
+    pub fn with_models_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.models_cache =
+            (!ttl.is_zero()).then(|| Cache::builder().max_capacity(1).time_to_live(ttl).build());
+        self
+    }
+
+    pub fn with_model_filter(mut self, filter: Arc<ModelFilter>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_pins(mut self, pins: Arc<ModelPinStore>) -> Self {
+        self.pins = Some(pins);
+        self
+    }
+
+    /// A resolver that ignores `--model-allowlist`/`--model-blocklist`, for
+    /// creating virtual aliases: an alias is how a hidden model is exposed on
+    /// purpose. It gets its own name cache so unfiltered hits never leak into
+    /// the filtered one; the model-list cache is shared.
+    pub fn without_filter(&self) -> Self {
+        Self {
+            lmstudio_url: self.lmstudio_url.clone(),
+            cache: Cache::builder().max_capacity(64).build(),
+            mode: self.mode,
+            models_cache: self.models_cache.clone(),
+            filter: Arc::new(ModelFilter::default()),
+            pins: self.pins.clone(),
         }
     }
 
+    /// Drop the cached model list so the next listing refetches from LM Studio.
+    pub async fn invalidate_models_cache(&self) {
+        if let Some(cache) = &self.models_cache {
+            cache.invalidate(&()).await;
+        }
+    }
+
+    /// Drop cached name resolutions as well as the model list, so names that
+    /// resolved (or failed to) against an older LM Studio state are looked up
+    /// again.
+    pub async fn invalidate_all(&self) {
+        self.cache.invalidate_all();
+        self.invalidate_models_cache().await;
+    }
+
+    /// Cached Ollama-name → LM Studio id resolutions, sorted by name.
+    pub fn cached_resolutions(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<_> = self
+            .cache
+            .iter()
+            .map(|(name, id)| (name.as_ref().clone(), id))
+            .collect();
+        entries.sort();
+        entries
+    }
+
+    /// Whether some cached resolution already points at `model_id`.
+    pub fn has_cached_target(&self, model_id: &str) -> bool {
+        self.cache.iter().any(|(_, id)| id == model_id)
+    }
+
     pub async fn resolve_model_name(
         &self,
         ollama_model_name_requested: &str,
@@ -33,6 +126,7 @@ impl ModelResolver {
     ) -> Result<String, ProxyError> {
         let start_time = Instant::now();
         let cleaned_ollama_request = clean_model_name(ollama_model_name_requested).to_string();
+        let mut span = resolution_span(&cleaned_ollama_request);
 
         if let Some(cached_lm_studio_id) = self.cache.get(&cleaned_ollama_request).await {
             log::debug!(
@@ -40,60 +134,177 @@ impl ModelResolver {
                 cleaned_ollama_request,
                 cached_lm_studio_id
             );
+            span.set_bool("cache.hit", true);
+            record_resolution(&mut span, Ok(&cached_lm_studio_id));
             return Ok(cached_lm_studio_id);
         }
 
+        let result = async {
+            let available_models = self
+                .fetch_for_resolution(&cleaned_ollama_request, client, cancellation_token)
+                .await?;
+            self.match_available(&cleaned_ollama_request, available_models, start_time)
+                .await
+                .map(|(matched_model, _)| matched_model.id)
+        }
+        .await;
+        record_resolution(&mut span, result.as_deref());
+        result
+    }
+
+    /// [`Self::resolve_model_name`] plus the resolved model's catalog entry,
+    /// from a single model-list retrieval. `None` when a cached resolution
+    /// points at a model LM Studio no longer lists.
+    pub async fn resolve_and_fetch(
+        &self,
+        ollama_model_name_requested: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<(String, Option<ModelInfo>), ProxyError> {
+        let (resolved_id, available_models) = self
+            .resolve_and_list(ollama_model_name_requested, client, cancellation_token)
+            .await?;
+        let model_info = available_models.into_iter().find(|m| m.id == resolved_id);
+        Ok((resolved_id, model_info))
+    }
+
+    /// [`Self::resolve_model_name`] plus the model list it was resolved
+    /// against (unfiltered, as [`Self::get_all_models`] returns it). A name
+    /// cache hit reads the list through the models cache; a miss fetches once
+    /// and uses that list for both.
+    pub async fn resolve_and_list(
+        &self,
+        ollama_model_name_requested: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<(String, Vec<ModelInfo>), ProxyError> {
+        let start_time = Instant::now();
+        let cleaned_ollama_request = clean_model_name(ollama_model_name_requested).to_string();
+        let mut span = resolution_span(&cleaned_ollama_request);
+
+        let result = async {
+            if let Some(cached_lm_studio_id) = self.cache.get(&cleaned_ollama_request).await {
+                log::debug!(
+                    "cache hit: '{}' -> '{}'",
+                    cleaned_ollama_request,
+                    cached_lm_studio_id
+                );
+                span.set_bool("cache.hit", true);
+                let models = self.get_all_models(client, cancellation_token).await?;
+                return Ok((cached_lm_studio_id, models));
+            }
+
+            let available_models = self
+                .fetch_for_resolution(&cleaned_ollama_request, client, cancellation_token)
+                .await?;
+            let (matched_model, available_models) = self
+                .match_available(&cleaned_ollama_request, available_models, start_time)
+                .await?;
+            Ok::<_, ProxyError>((matched_model.id, available_models))
+        }
+        .await;
+        record_resolution(&mut span, result.as_ref().map(|(id, _)| id.as_str()));
+        result
+    }
+
+    /// The uncached model list a name-cache miss resolves against. A 404 from
+    /// LM Studio means the native API is missing, reported as 503.
+    async fn fetch_for_resolution(
+        &self,
+        cleaned_ollama_request: &str,
+        client: &reqwest::Client,
+        cancellation_token: CancellationToken,
+    ) -> Result<Vec<ModelInfo>, ProxyError> {
         log::debug!(
             "cache miss, fetching '{}' from LM Studio",
             cleaned_ollama_request
         );
 
-        match self.get_available_models(client, cancellation_token).await {
-            Ok(available_models) => {
-                if let Some(matched_model) =
-                    Self::resolve_match(&cleaned_ollama_request, &available_models)
-                {
-                    if !matched_model.is_loaded {
-                        log::warn!(
-                            "'{}' found but not loaded (state: {})",
-                            matched_model.id,
-                            matched_model.state
-                        );
-                    }
-
-                    self.cache
-                        .insert(cleaned_ollama_request.clone(), matched_model.id.clone())
-                        .await;
-                    log_timed(
-                        LOG_PREFIX_SUCCESS,
-                        &format!(
-                            "resolved: '{}' -> '{}' ({})",
-                            cleaned_ollama_request, matched_model.id, matched_model.state
-                        ),
-                        start_time,
-                    );
-                    Ok(matched_model.id)
-                } else {
-                    Err(ProxyError::not_found(&format!(
-                        "model '{}' not found in LM Studio. Available models can be listed via /api/tags",
-                        cleaned_ollama_request
-                    )))
-                }
-            }
-            Err(e) => {
+        self.get_available_models(client, cancellation_token)
+            .await
+            .map_err(|e| {
                 if e.message.contains("404") || e.message.contains("not found") {
-                    Err(ProxyError::new(
+                    ProxyError::new(
                         format!(
                             "LM Studio native API not available. Please update to LM Studio 0.3.6+. Original error: {}",
                             e.message
                         ),
                         503,
-                    ))
+                    )
                 } else {
-                    Err(e)
+                    e
                 }
-            }
+            })
+    }
+
+    /// Match `cleaned_ollama_request` against `available_models` (exact, then
+    /// pinned, then the mode's matching), caching the hit. Hands the list back
+    /// unfiltered alongside the match.
+    async fn match_available(
+        &self,
+        cleaned_ollama_request: &str,
+        available_models: Vec<ModelInfo>,
+        start_time: Instant,
+    ) -> Result<(ModelInfo, Vec<ModelInfo>), ProxyError> {
+        let visible_models: Vec<ModelInfo> = available_models
+            .iter()
+            .filter(|m| self.filter.is_visible(&m.id))
+            .cloned()
+            .collect();
+        let matched = match Self::resolve_exact(cleaned_ollama_request, &visible_models) {
+            Some(exact) => Some(exact),
+            None => match self
+                .resolve_pinned(cleaned_ollama_request, &visible_models)
+                .await
+            {
+                Some(pinned) => Some(pinned),
+                None => match self.mode {
+                    ResolutionMode::Fuzzy => {
+                        Self::resolve_match(cleaned_ollama_request, &visible_models)
+                    }
+                    ResolutionMode::Exact => None,
+                },
+            },
+        };
+        let Some(matched_model) = matched else {
+            let suggestions = Self::suggest_models(cleaned_ollama_request, &visible_models);
+            let did_you_mean = if suggestions.is_empty() {
+                String::new()
+            } else {
+                format!(", did you mean: {}?", suggestions.join(", "))
+            };
+            let exact_note = if self.mode == ResolutionMode::Exact {
+                " (exact resolution: the name must equal a model id)"
+            } else {
+                ""
+            };
+            return Err(ProxyError::not_found(&format!(
+                "model '{}' not found in LM Studio{}{}. Available models can be listed via /api/tags",
+                cleaned_ollama_request, exact_note, did_you_mean
+            ))
+            .with_suggestions(suggestions));
+        };
+
+        if !matched_model.is_loaded {
+            log::warn!(
+                "'{}' found but not loaded (state: {})",
+                matched_model.id,
+                matched_model.state
+            );
         }
+
+        self.cache
+            .insert(cleaned_ollama_request.to_string(), matched_model.id.clone())
+            .await;
+        log_timed(
+            LOG_PREFIX_SUCCESS,
+            &format!(
+                "resolved: '{}' -> '{}' ({})",
+                cleaned_ollama_request, matched_model.id, matched_model.state
+            ),
+            start_time,
+        );
+        Ok((matched_model, available_models))
     }
 
     async fn get_available_models(
@@ -136,16 +347,21 @@ impl ModelResolver {
         Ok(models)
     }
 
-    fn resolve_match(query: &str, available_models: &[ModelInfo]) -> Option<ModelInfo> {
-        let views: Vec<ModelMatchView> = available_models
+    fn match_views(available_models: &[ModelInfo]) -> Vec<ModelMatchView> {
+        available_models
             .iter()
             .map(|m| ModelMatchView {
                 id: m.id.clone(),
                 arch: m.arch.clone(),
                 model_type: m.model_type.clone(),
+                quantization: m.quantization.clone(),
                 is_loaded: m.is_loaded,
             })
-            .collect();
+            .collect()
+    }
+
+    fn resolve_match(query: &str, available_models: &[ModelInfo]) -> Option<ModelInfo> {
+        let views = Self::match_views(available_models);
         let matched = find_best_match(query, &views)?;
         available_models
             .iter()
@@ -153,24 +369,141 @@ impl ModelResolver {
             .cloned()
     }
 
+    /// The model `query` is pinned to, when that model is still listed (and
+    /// visible). A pin whose target has gone falls through to normal matching.
+    async fn resolve_pinned(
+        &self,
+        query: &str,
+        available_models: &[ModelInfo],
+    ) -> Option<ModelInfo> {
+        let target = self.pins.as_ref()?.target_for(query).await?;
+        let pinned = available_models.iter().find(|m| m.id == target).cloned();
+        if pinned.is_none() {
+            log::warn!(
+                "pin '{}' -> '{}' ignored: target is not available",
+                query,
+                target
+            );
+        }
+        pinned
+    }
+
+    /// `--resolution exact`: only a case-insensitive id match counts; the
+    /// substring and scored stages of [`find_best_match`] are skipped.
+    fn resolve_exact(query: &str, available_models: &[ModelInfo]) -> Option<ModelInfo> {
+        available_models
+            .iter()
+            .find(|m| m.id.eq_ignore_ascii_case(query))
+            .cloned()
+    }
+
+    /// Up to [`MAX_SUGGESTIONS`] closest model ids for a name that failed to
+    /// resolve, by the matcher's token score (ignoring its acceptance
+    /// threshold). Models sharing nothing with the name are never suggested.
+    fn suggest_models(query: &str, available_models: &[ModelInfo]) -> Vec<String> {
+        explain_matches(query, &Self::match_views(available_models))
+            .into_iter()
+            .filter(|c| c.score > usize::from(c.is_loaded) * LOADED_SCORE_BONUS)
+            .take(MAX_SUGGESTIONS)
+            .map(|c| c.id)
+            .collect()
+    }
+
+    /// Candidate scores for `ollama_model_name_requested` against
+    /// `available_models`, highest first — the diagnostics behind
+    /// `POST /api/show?debug=true`. Reads the list the way
+    /// [`Self::match_available`] does (hidden models left out, an exact id
+    /// before a pin, `--resolution exact` ruling out the other stages) but
+    /// bypasses the resolution cache.
+    pub async fn explain_match(
+        &self,
+        ollama_model_name_requested: &str,
+        available_models: &[ModelInfo],
+    ) -> Vec<MatchCandidate> {
+        let cleaned = clean_model_name(ollama_model_name_requested);
+        let visible_models: Vec<ModelInfo> = available_models
+            .iter()
+            .filter(|m| self.filter.is_visible(&m.id))
+            .cloned()
+            .collect();
+        let exact = Self::resolve_exact(cleaned, &visible_models).map(|m| m.id);
+        let pinned = match exact {
+            Some(_) => None,
+            None => self
+                .resolve_pinned(cleaned, &visible_models)
+                .await
+                .map(|m| m.id),
+        };
+        let mut candidates = explain_matches(cleaned, &Self::match_views(&visible_models));
+        for candidate in &mut candidates {
+            candidate.stage = if exact.as_ref() == Some(&candidate.id) {
+                MatchStage::Exact
+            } else if pinned.as_ref() == Some(&candidate.id) {
+                MatchStage::Pinned
+            } else if self.mode == ResolutionMode::Exact {
+                MatchStage::Rejected
+            } else if pinned.is_some() && candidate.stage == MatchStage::Exact {
+                // An id equal to the name minus its quant hint only wins the
+                // fuzzy stages, which a pin comes before.
+                MatchStage::Substring
+            } else {
+                candidate.stage
+            };
+        }
+        candidates
+    }
+
     pub async fn get_all_models(
         &self,
         client: &reqwest::Client,
         cancellation_token: CancellationToken,
     ) -> Result<Vec<ModelInfo>, ProxyError> {
-        self.get_available_models(client, cancellation_token).await
+        let Some(cache) = &self.models_cache else {
+            return self.get_available_models(client, cancellation_token).await;
+        };
+        // Concurrent misses wait on a single upstream fetch. It belongs to
+        // none of them, so it runs uncancelled; each caller gives up on its
+        // own token instead of one disconnect failing every waiter.
+        let fetch = cache.try_get_with((), async {
+            self.get_available_models(client, CancellationToken::new())
+                .await
+                .map(Arc::new)
+        });
+        let models = tokio::select! {
+            models = fetch => models.map_err(|e| (*e).clone())?,
+            _ = cancellation_token.cancelled() => return Err(ProxyError::request_cancelled()),
+        };
+        Ok(models.as_ref().clone())
     }
 
+    /// The models with a loaded instance right now. Read past the models
+    /// cache: a load or a `keep_alive: 0` unload must show up at once.
     pub async fn get_loaded_models(
         &self,
         client: &reqwest::Client,
         cancellation_token: CancellationToken,
     ) -> Result<Vec<ModelInfo>, ProxyError> {
-        let all_models = self.get_all_models(client, cancellation_token).await?;
+        let all_models = self
+            .get_available_models(client, cancellation_token)
+            .await?;
         Ok(all_models.into_iter().filter(|m| m.is_loaded).collect())
     }
 }
 
+/// The `model.resolve` trace span for a name lookup.
+fn resolution_span(cleaned_ollama_request: &str) -> ChildSpan {
+    let mut span = crate::telemetry::child_span("model.resolve");
+    span.set_str("model.requested", cleaned_ollama_request);
+    span
+}
+
+fn record_resolution(span: &mut ChildSpan, result: Result<&str, &ProxyError>) {
+    match result {
+        Ok(resolved_id) => span.set_str("model.resolved", resolved_id),
+        Err(e) => span.set_error(&e.message),
+    }
+}
+
 #[cfg(test)]
 #[path = "../../tests/unit/model_resolver.rs"]
 mod tests;
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+
+/// What LM Studio said about a model while serving it: the `model_info` and
+/// `runtime` blocks `/api/v0` chat and completion responses carry (the last
+/// chunk, when streamed). The catalog has no runtime and only the maximum
+/// context, so `/api/show` prefers these when it has seen them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuntimeInfo {
+    pub arch: Option<String>,
+    pub quant: Option<String>,
+    pub format: Option<String>,
+    /// Context length of the instance that answered.
+    pub context_length: Option<u64>,
+    pub runtime_name: Option<String>,
+    pub runtime_version: Option<String>,
+}
+
+impl RuntimeInfo {
+    /// `None` when the response carries neither block.
+    pub fn from_response(response: &Value) -> Option<Self> {
+        let model_info = response.get("model_info").filter(|v| v.is_object());
+        let runtime = response.get("runtime").filter(|v| v.is_object());
+        if model_info.is_none() && runtime.is_none() {
+            return None;
+        }
+        let text = |block: Option<&Value>, key: &str| {
+            block
+                .and_then(|b| b.get(key))
+                .and_then(Value::as_str)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        Some(Self {
+            arch: text(model_info, "arch"),
+            quant: text(model_info, "quant"),
+            format: text(model_info, "format"),
+            context_length: model_info
+                .and_then(|b| b.get("context_length"))
+                .and_then(Value::as_u64)
+                .filter(|&n| n > 0),
+            runtime_name: text(runtime, "name"),
+            runtime_version: text(runtime, "version"),
+        })
+    }
+
+    /// Overlay onto a [`crate::model::ModelInfo::to_show_response`] body.
+    ///
+    /// `general.architecture` (and the key of `<arch>.context_length`) and
+    /// `general.file_type` take the served values; `<arch>.context_length`
+    /// stays the catalog maximum unless the catalog had none. Verbose adds
+    /// the runtime engine and the served context as `lmstudio.*` keys.
+    pub fn apply_to_show(&self, show: &mut Value, verbose: bool) {
+        let Some(model_info) = show.get_mut("model_info").and_then(Value::as_object_mut) else {
+            return;
+        };
+        let previous_arch = model_info
+            .get("general.architecture")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let arch = self.arch.clone().or(previous_arch.clone());
+
+        if let Some(arch) = &arch {
+            let context_key = format!("{}.context_length", arch);
+            let catalog_context = previous_arch
+                .as_ref()
+                .and_then(|prev| model_info.remove(&format!("{}.context_length", prev)))
+                .filter(|v| v.as_u64().is_some_and(|n| n > 0));
+            if let Some(context) = catalog_context.or(self.context_length.map(|n| json!(n))) {
+                model_info.insert(context_key, context);
+            }
+            model_info.insert("general.architecture".into(), json!(arch));
+        }
+        if let Some(file_type) = self.quant.as_deref().and_then(gguf_file_type) {
+            model_info.insert("general.file_type".into(), json!(file_type));
+        }
+
+        if verbose {
+            if let Some(name) = &self.runtime_name {
+                model_info.insert("lmstudio.runtime".into(), json!(name));
+            }
+            if let Some(version) = &self.runtime_version {
+                model_info.insert("lmstudio.runtime_version".into(), json!(version));
+            }
+            if let Some(context) = self.context_length {
+                model_info.insert("lmstudio.served_context_length".into(), json!(context));
+            }
+        }
+
+        if let Some(arch) = &arch
+            && let Some(details) = show.get_mut("details").and_then(Value::as_object_mut)
+        {
+            details.insert("family".into(), json!(arch));
+            details.insert("families".into(), json!([arch]));
+        }
+    }
+}
+
+/// GGUF `general.file_type` for a quantization name (`Q4_K_M` → 15), as
+/// llama.cpp numbers them. `None` for names it doesn't know.
+pub fn gguf_file_type(quant: &str) -> Option<u32> {
+    let file_type = match quant.to_ascii_uppercase().as_str() {
+        "F32" => 0,
+        "F16" => 1,
+        "Q4_0" => 2,
+        "Q4_1" => 3,
+        "Q8_0" => 7,
+        "Q5_0" => 8,
+        "Q5_1" => 9,
+        "Q2_K" => 10,
+        "Q3_K_S" => 11,
+        "Q3_K_M" => 12,
+        "Q3_K_L" => 13,
+        "Q4_K_S" => 14,
+        "Q4_K_M" => 15,
+        "Q5_K_S" => 16,
+        "Q5_K_M" => 17,
+        "Q6_K" => 18,
+        "IQ2_XXS" => 19,
+        "IQ2_XS" => 20,
+        "Q2_K_S" => 21,
+        "IQ3_XS" => 22,
+        "IQ3_XXS" => 23,
+        "IQ1_S" => 24,
+        "IQ4_NL" => 25,
+        "IQ3_S" => 26,
+        "IQ3_M" => 27,
+        "IQ2_S" => 28,
+        "IQ2_M" => 29,
+        "IQ4_XS" => 30,
+        "IQ1_M" => 31,
+        "BF16" => 32,
+        _ => return None,
+    };
+    Some(file_type)
+}
+
+/// The last [`RuntimeInfo`] seen per LM Studio model id, shared by every
+/// request. Entries are replaced, never expired: one per served model.
+#[derive(Default)]
+pub struct RuntimeInfoCache {
+    entries: Mutex<HashMap<String, RuntimeInfo>>,
+}
+
+impl RuntimeInfoCache {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Remember the blocks of a response (or stream chunk) under the model
+    /// key it was resolved to. The response's own `model` is not used: it
+    /// names the instance when one was targeted, which `/api/show` never
+    /// looks up. Responses without the blocks are ignored.
+    pub fn observe(&self, model_id: &str, response: &Value) {
+        if let Some(info) = RuntimeInfo::from_response(response) {
+            self.entries
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(model_id.to_string(), info);
+        }
+    }
+
+    pub fn get(&self, model_id: &str) -> Option<RuntimeInfo> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(model_id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/model_runtime_info.rs"]
+mod tests;
@@ -1,8 +1,12 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use sha2::{Digest, Sha256};
 
-use crate::constants::DEFAULT_KEEP_ALIVE_MINUTES;
+use crate::model::filter::ModelFilter;
+use crate::model::naming::ollama_model_name;
 use crate::storage::VirtualModelEntry;
 use crate::storage::virtual_models::VirtualModelMetadata;
 
@@ -115,9 +119,15 @@ pub struct ModelInfo {
     pub loaded_flash_attention: Option<bool>,
     pub loaded_eval_batch_size: Option<u64>,
     pub loaded_parallel: Option<u64>,
+    /// Every loaded instance LM Studio reported, in its order. Empty when
+    /// the model is not loaded.
+    pub loaded_instances: Vec<NativeLoadedInstance>,
 }
 
 impl ModelInfo {
+    /// Base models in LM Studio's order, then every alias whose target is
+    /// among them, sorted by alias name. Aliases with a missing target are
+    /// dropped; see [`Self::merge_with_virtuals_or_orphans`] to keep them.
     pub fn merge_with_virtuals<F>(
         base_models: &[ModelInfo],
         virtual_entries: &[VirtualModelEntry],
@@ -126,15 +136,54 @@ impl ModelInfo {
     where
         F: Fn(&ModelInfo) -> Value,
     {
-        let mut result: Vec<_> = base_models.iter().map(&transform_fn).collect();
+        Self::merge_with_virtuals_or_orphans(
+            base_models,
+            virtual_entries,
+            &ModelFilter::default(),
+            transform_fn,
+            |_| None,
+        )
+    }
 
-        for entry in virtual_entries {
-            if let Some(base_model) = base_models.iter().find(|m| m.id == entry.target_model_id) {
-                let aliased = base_model.with_alias_name(&entry.name);
-                result.push(transform_fn(&aliased));
-            }
+    /// Like [`Self::merge_with_virtuals`], but base models hidden by `filter`
+    /// are left out (aliases pointing at them are still listed), and an alias
+    /// whose target is not in `base_models` is rendered by `orphan_fn` (and
+    /// skipped when it returns `None`), keeping its place in the name-sorted
+    /// alias list.
+    pub fn merge_with_virtuals_or_orphans<F, O>(
+        base_models: &[ModelInfo],
+        virtual_entries: &[VirtualModelEntry],
+        filter: &ModelFilter,
+        transform_fn: F,
+        orphan_fn: O,
+    ) -> Vec<Value>
+    where
+        F: Fn(&ModelInfo) -> Value,
+        O: Fn(&VirtualModelEntry) -> Option<Value>,
+    {
+        // First entry wins: `/api/ps` passes one view per loaded instance,
+        // and an alias mirrors the primary one.
+        let mut by_id: HashMap<&str, &ModelInfo> = HashMap::new();
+        for model in base_models {
+            by_id.entry(model.id.as_str()).or_insert(model);
         }
 
+        let mut aliases: Vec<&VirtualModelEntry> = virtual_entries.iter().collect();
+        aliases.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut result = Vec::with_capacity(base_models.len() + aliases.len());
+        result.extend(
+            base_models
+                .iter()
+                .filter(|m| filter.is_visible(&m.id))
+                .map(&transform_fn),
+        );
+        result.extend(aliases.into_iter().filter_map(|entry| {
+            match by_id.get(entry.target_model_id.as_str()) {
+                Some(base_model) => Some(transform_fn(&base_model.with_alias_name(&entry.name))),
+                None => orphan_fn(entry),
+            }
+        }));
         result
     }
 
@@ -155,11 +204,7 @@ impl ModelInfo {
         let loaded_eval_batch_size = first_config.and_then(|cfg| cfg.eval_batch_size);
         let loaded_parallel = first_config.and_then(|cfg| cfg.parallel);
 
-        let ollama_name = if native_data.key.contains(':') {
-            native_data.key.clone()
-        } else {
-            format!("{}:latest", native_data.key)
-        };
+        let ollama_name = ollama_model_name(&native_data.key);
 
         let quantization = native_data
             .quantization
@@ -219,7 +264,43 @@ impl ModelInfo {
             loaded_flash_attention,
             loaded_eval_batch_size,
             loaded_parallel,
+            loaded_instances: native_data.loaded_instances.clone(),
+        }
+    }
+
+    /// One entry per loaded instance, for `/api/ps`. A model loaded once is
+    /// returned as is. With several copies loaded, each carries its own
+    /// instance's config; the instance whose id is the key itself keeps the
+    /// model's name, the others are named as if `<key>@<instance id>` were a
+    /// key of its own (see [`ollama_model_name`]).
+    pub fn instance_views(&self) -> Vec<ModelInfo> {
+        if self.loaded_instances.len() <= 1 {
+            return vec![self.clone()];
         }
+        self.loaded_instances
+            .iter()
+            .map(|instance| {
+                let mut view = self.clone();
+                if instance.id != self.id {
+                    view.ollama_name = ollama_model_name(&format!("{}@{}", self.id, instance.id));
+                }
+                let config = instance.config.as_ref();
+                view.context_length = config
+                    .and_then(|cfg| cfg.context_length)
+                    .unwrap_or(self.max_context_length);
+                view.loaded_flash_attention = config.and_then(|cfg| cfg.flash_attention);
+                view.loaded_eval_batch_size = config.and_then(|cfg| cfg.eval_batch_size);
+                view.loaded_parallel = config.and_then(|cfg| cfg.parallel);
+                view
+            })
+            .collect()
+    }
+
+    /// Whether LM Studio has an instance of this model loaded under `instance_id`.
+    pub fn has_loaded_instance(&self, instance_id: &str) -> bool {
+        self.loaded_instances
+            .iter()
+            .any(|inst| inst.id == instance_id)
     }
 
     pub fn with_alias_name(&self, alias_name: &str) -> Self {
@@ -228,6 +309,19 @@ impl ModelInfo {
         cloned
     }
 
+    /// Stand-in for Ollama's blob digest: SHA-256 over the LM Studio key,
+    /// publisher, quantization and file size. Stable across calls, but a
+    /// re-quantized, republished or re-downloaded model gets a new one.
+    pub fn digest(&self) -> String {
+        let size = self.size_bytes.map(|b| b.to_string()).unwrap_or_default();
+        let mut hasher = Sha256::new();
+        for part in [&self.id, &self.publisher, &self.quantization, &size] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hex::encode(hasher.finalize())
+    }
+
     pub fn is_thinking_model(&self) -> bool {
         if self.supports_reasoning {
             return true;
@@ -250,6 +344,23 @@ impl ModelInfo {
             || lower.contains("reflect")
     }
 
+    /// Whether `/api/generate` should apply the model's chat template (route
+    /// through the chat endpoint) when the caller did not ask for `raw`.
+    ///
+    /// LM Studio reports no base-vs-instruct flag, so a chat-capable model is
+    /// assumed tuned unless its id carries a `base` token (`qwen2.5-7b-base`,
+    /// `mistral-7b-v0.1-base`) — those complete raw text better than they
+    /// follow a template.
+    pub fn is_chat_tuned(&self) -> bool {
+        if !self.determine_capabilities().contains(&"chat") {
+            return false;
+        }
+        let lower = self.id.to_lowercase();
+        !lower
+            .split(&['-', '_', ':', '.', '/', ' '])
+            .any(|token| token == "base")
+    }
+
     fn determine_capabilities(&self) -> Vec<&'static str> {
         let mut caps = Vec::with_capacity(5);
 
@@ -303,8 +414,11 @@ impl ModelInfo {
         caps
     }
 
+    /// LM Studio's `size_bytes` when it reports one, otherwise a guess from
+    /// the parameter count in the id and the quantization. A zero size (a
+    /// listing still being indexed) counts as unreported.
     pub(crate) fn calculate_estimated_size(&self) -> u64 {
-        if let Some(bytes) = self.size_bytes {
+        if let Some(bytes) = self.size_bytes.filter(|bytes| *bytes > 0) {
             return bytes;
         }
         let lower_id = self.id.to_lowercase();
@@ -349,27 +463,8 @@ impl ModelInfo {
                 size_string: s.clone(),
             };
         }
-        let lower_id = self.id.to_lowercase();
-
-        let size_string = if lower_id.contains("0.5b") || lower_id.contains("500m") {
-            "0.5B".to_string()
-        } else if lower_id.contains("1b") && !lower_id.contains("11b") {
-            "1B".to_string()
-        } else if lower_id.contains("2b") && !lower_id.contains("22b") {
-            "2B".to_string()
-        } else if lower_id.contains("3b") && !lower_id.contains("13b") {
-            "3B".to_string()
-        } else if lower_id.contains("7b") {
-            "7B".to_string()
-        } else if lower_id.contains("8b") {
-            "8B".to_string()
-        } else if lower_id.contains("13b") {
-            "13B".to_string()
-        } else if lower_id.contains("70b") {
-            "70B".to_string()
-        } else {
-            "unknown".to_string()
-        };
+        let size_string = crate::model::param_count::parameter_size_from_id(&self.id)
+            .unwrap_or_else(|| "unknown".to_string());
 
         ModelParameters { size_string }
     }
@@ -382,9 +477,8 @@ impl ModelInfo {
             "name": self.ollama_name,
             "model": self.ollama_name,
             "size": estimated_size,
-            // LM Studio exposes no blob hash; derive a deterministic SHA-256
-            // from the model key (stable unique identifier in LM Studio's API).
-            "digest": hex::encode(Sha256::digest(self.id.as_bytes())),
+            // LM Studio exposes no blob hash; see `digest()`.
+            "digest": self.digest(),
             // `context_length`/`max_context_length` (top-level and mirrored in
             // details) are intentional non-Ollama extensions surfacing LM Studio's
             // context window — strict validators may flag them, but they're kept
@@ -403,12 +497,17 @@ impl ModelInfo {
         })
     }
 
-    pub fn to_ollama_tags_model(&self) -> Value {
-        // LM Studio's model list exposes no per-model mtime. Real Ollama
-        // returns the model file's mtime in `modified_at`; the proxy omits
-        // the field rather than fabricate one. Ollama's tags schema allows
-        // absence — clients treat it as "unknown, refresh as needed".
-        self.base_ollama_representation()
+    pub fn to_ollama_tags_model(&self, modified_at: Option<DateTime<Utc>>) -> Value {
+        let mut base = self.base_ollama_representation();
+        // LM Studio's model list exposes no per-model mtime, so `modified_at`
+        // is when the proxy first saw this digest (see ModelTimestampStore).
+        // Without a stamp the field is omitted, which Ollama's schema allows.
+        if let Some(modified_at) = modified_at
+            && let Some(obj) = base.as_object_mut()
+        {
+            obj.insert("modified_at".to_string(), json!(modified_at.to_rfc3339()));
+        }
+        base
     }
 
     pub fn to_ollama_ps_model(&self, expires_at: Option<i64>) -> Value {
@@ -422,29 +521,15 @@ impl ModelInfo {
         }
 
         if let Some(obj) = base.as_object_mut() {
-            // `expires_at` is best-effort: when the proxy tracked this model's
-            // load/keep-alive, `expires_at` is the tracked deadline (unix
-            // seconds → RFC3339). Tracked-but-forever (None ttl) and untracked
-            // models fall back to the legacy placeholder (now + default
-            // keep-alive), since LM Studio's loaded-instance list exposes no
-            // authoritative TTL.
-            let expires_at_value = match expires_at {
-                Some(secs) => chrono::DateTime::<chrono::Utc>::from_timestamp(secs, 0)
-                    .map(|t| t.to_rfc3339())
-                    .map(Value::from)
-                    .unwrap_or_else(|| {
-                        json!(
-                            (chrono::Utc::now()
-                                + chrono::Duration::minutes(DEFAULT_KEEP_ALIVE_MINUTES))
-                            .to_rfc3339()
-                        )
-                    }),
-                None => json!(
-                    (chrono::Utc::now() + chrono::Duration::minutes(DEFAULT_KEEP_ALIVE_MINUTES))
-                        .to_rfc3339()
-                ),
-            };
-            obj.insert("expires_at".to_string(), expires_at_value);
+            // `expires_at` comes from the proxy's load tracker (unix seconds →
+            // RFC3339). LM Studio's loaded-instance list exposes no TTL, so an
+            // untracked model (loaded out-of-band, or with no keep_alive) omits
+            // the field rather than guessing a deadline.
+            if let Some(expires_at) =
+                expires_at.and_then(|secs| chrono::DateTime::<chrono::Utc>::from_timestamp(secs, 0))
+            {
+                obj.insert("expires_at".to_string(), json!(expires_at.to_rfc3339()));
+            }
             // LM Studio doesn't report the GPU/CPU memory split. A loaded model
             // is resident, so mirror its `size` into `size_vram` (assumes GPU
             // residency, the common LM Studio case) instead of reporting 0.
@@ -1,5 +1,8 @@
 pub mod auth;
 pub mod routes;
+pub mod self_test;
 pub mod server;
+pub mod service;
+pub mod tls;
 
 pub use server::ProxyServer;
@@ -1,19 +1,31 @@
+use std::convert::Infallible;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
-use axum::extract::rejection::JsonRejection;
-use axum::extract::{DefaultBodyLimit, FromRequest, Path, Query, Request, State};
+use axum::Router;
+use axum::extract::{
+    DefaultBodyLimit, FromRequest, FromRequestParts, NestedPath, Path, Query, Request, State,
+};
+use axum::handler::Handler;
 use axum::response::Response;
-use axum::routing::{delete, get, head, post};
-use axum::{Json, Router};
+use axum::routing::{MethodRouter, delete, get, head, post};
 use bytes::Bytes;
 use http::HeaderMap;
+use http::request::Parts;
 use serde_json::Value;
+use tokio_util::sync::CancellationToken;
 
 use crate::api::ollama::{EmbeddingResponseMode, handle_ollama_embeddings};
-use crate::api::{RequestContext, lmstudio, ollama, web};
-use crate::constants::MAX_JSON_BODY_SIZE_BYTES;
+use crate::api::{RequestContext, admin, lmstudio, ollama, web};
+use crate::config::Config;
+use crate::constants::ERROR_LM_STUDIO_UNAVAILABLE;
 use crate::error::ProxyError;
-use crate::http::json_response;
+use crate::http::body::{
+    body_looks_like_json, body_too_large, contains_json_content_type, parse_json_request,
+};
+use crate::http::{json_response, select_forward_headers};
+use crate::model::ModelResolver;
 use crate::proxy::ProxyServer;
 
 pub type AppState = Arc<ProxyServer>;
@@ -21,74 +33,117 @@ pub type AppState = Arc<ProxyServer>;
 /// JSON body extractor that surfaces parse errors as ProxyError::bad_request
 /// (a JSON `{"error": ..., "status": 400}` response), matching the Ollama-shaped
 /// error envelope the proxy uses everywhere else.
+///
+/// A body over `--max-body-size` gets a 413 naming the limit and the declared
+/// size; a UTF-8 BOM ahead of the JSON is ignored. With `--strict-json` a
+/// content type other than JSON is a 415; otherwise a missing one is fine and
+/// a mislabelled one (e.g. `curl -d`'s form type) is let through when the
+/// body looks like JSON.
 pub struct JsonBody<T>(pub T);
 
-impl<S, T> FromRequest<S> for JsonBody<T>
+impl<T> FromRequest<AppState> for JsonBody<T>
 where
-    S: Send + Sync,
     T: serde::de::DeserializeOwned,
-    Json<T>: FromRequest<S, Rejection = JsonRejection>,
 {
     type Rejection = ProxyError;
 
-    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
-        use axum::response::IntoResponse;
-        match Json::<T>::from_request(req, state).await {
-            Ok(Json(value)) => Ok(JsonBody(value)),
-            Err(rejection) => {
-                let status = rejection.status();
-                let message = match status.as_u16() {
-                    413 => "request body too large".to_string(),
-                    _ => format!(
-                        "invalid request body: {}",
-                        rejection.into_response().status()
-                    ),
-                };
-                Err(ProxyError::new(message, status.as_u16()))
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let json_content_type = contains_json_content_type(req.headers());
+        if state.config.strict_json && !json_content_type {
+            return Err(unsupported_content_type());
+        }
+        let content_type_present = req.headers().contains_key(http::header::CONTENT_TYPE);
+        let content_length = req
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok());
+        let bytes = Bytes::from_request(req, state).await.map_err(|rejection| {
+            match rejection.status().as_u16() {
+                413 => body_too_large(state.config.max_body_size, content_length),
+                status => ProxyError::new(
+                    format!("failed to read request body: {}", rejection.body_text()),
+                    status,
+                ),
             }
+        })?;
+        if !json_content_type && content_type_present && !body_looks_like_json(&bytes) {
+            return Err(unsupported_content_type());
         }
+        parse_json_request(&bytes).map(JsonBody)
     }
 }
 
+fn unsupported_content_type() -> ProxyError {
+    ProxyError::new(
+        "expected request with `Content-Type: application/json`".to_string(),
+        415,
+    )
+}
+
+/// LM Studio native API versions passed through under `/api/`. Routing on the
+/// literal version means any other `/api/{x}/...` 404s in the router, before
+/// a (possibly large) body is read.
+const NATIVE_API_VERSIONS: [&str; 2] = ["v0", "v1"];
+
+fn passthrough_methods<H, T>(handler: H) -> MethodRouter<AppState>
+where
+    H: Handler<T, AppState>,
+    T: 'static,
+{
+    get(handler.clone())
+        .post(handler.clone())
+        .put(handler.clone())
+        .delete(handler.clone())
+        .head(handler.clone())
+        .options(handler)
+}
+
+/// `/v1/*` and `/api/v0|v1/*`, forwarded to LM Studio as they are.
+fn passthrough_router() -> Router<AppState> {
+    let lmstudio_router = Router::new().route("/v1/{*path}", passthrough_methods(passthrough_v1));
+    let native_router = Router::new()
+        .route("/", passthrough_methods(passthrough_native_version_root))
+        .route(
+            "/{*path}",
+            passthrough_methods(passthrough_native_versioned),
+        );
+    NATIVE_API_VERSIONS
+        .iter()
+        .fold(lmstudio_router, |router, version| {
+            router.nest(&format!("/api/{}", version), native_router.clone())
+        })
+}
+
 pub fn create_router(server: AppState) -> Router {
-    let lmstudio_router = Router::new()
+    let body_limit = usize::try_from(server.config.max_body_size).unwrap_or(usize::MAX);
+    // `--disable-passthrough`: the paths fall through to the 404 fallback.
+    let passthrough = if server.config.disable_passthrough {
+        Router::new()
+    } else {
+        passthrough_router()
+    };
+
+    Router::new()
+        // Clients probe these to check they are talking to Ollama; `get`
+        // also answers HEAD.
         .route(
-            "/v1/{*path}",
-            get(passthrough_v1)
-                .post(passthrough_v1)
-                .put(passthrough_v1)
-                .delete(passthrough_v1)
-                .head(passthrough_v1)
-                .options(passthrough_v1),
+            "/",
+            get(root_handler).options(ollama::handle_ollama_root_options),
         )
         .route(
-            "/api/{version}/{*path}",
-            get(passthrough_native_versioned)
-                .post(passthrough_native_versioned)
-                .put(passthrough_native_versioned)
-                .delete(passthrough_native_versioned)
-                .head(passthrough_native_versioned)
-                .options(passthrough_native_versioned),
+            "/api",
+            get(root_handler).options(ollama::handle_ollama_root_options),
         )
-        .route(
-            "/api/{version}",
-            get(passthrough_native_version_root)
-                .post(passthrough_native_version_root)
-                .put(passthrough_native_version_root)
-                .delete(passthrough_native_version_root)
-                .head(passthrough_native_version_root)
-                .options(passthrough_native_version_root),
-        );
-
-    Router::new()
-        .route("/", get(root_handler))
         .route("/health", get(health_handler))
+        .route("/health/live", get(ollama::handle_liveness))
+        .route("/health/ready", get(readiness_handler))
         .route("/api/tags", get(tags_handler))
         .route("/api/chat", post(chat_handler))
         .route("/api/generate", post(generate_handler))
         .route("/api/embed", post(embed_handler))
         .route("/api/embeddings", post(embeddings_handler))
-        .route("/api/pull", post(pull_handler))
+        .route("/api/pull", post(pull_handler).delete(pull_cancel_handler))
         .route("/api/create", post(create_handler))
         .route("/api/copy", post(copy_handler))
         .route("/api/delete", delete(delete_handler))
@@ -98,14 +153,31 @@ pub fn create_router(server: AppState) -> Router {
         .route("/api/show", post(show_handler))
         .route("/api/ps", get(ps_handler))
         .route("/api/version", get(version_handler))
+        .route("/api/proxy/debug/transform", post(transform_debug_handler))
+        .route(
+            "/api/proxy/cache/models",
+            get(model_cache_list_handler).delete(model_cache_clear_handler),
+        )
+        .route("/api/proxy/aliases", get(alias_list_handler))
+        .route("/api/proxy/pull/cancel", post(proxy_pull_cancel_handler))
+        .route("/api/proxy/blobs", get(blob_list_handler))
+        .route(
+            "/api/proxy/pins",
+            get(pin_list_handler)
+                .post(pin_set_handler)
+                .delete(pin_delete_handler),
+        )
         .route(
             "/api/blobs/{digest}",
-            head(blob_head_handler).post(blob_upload_handler),
+            head(blob_head_handler)
+                .post(blob_upload_handler)
+                .delete(blob_delete_handler),
         )
-        .merge(lmstudio_router)
+        .route("/v2/{*path}", get(registry_handler))
+        .merge(passthrough)
         .method_not_allowed_fallback(method_not_allowed_handler)
         .fallback(not_found_handler)
-        .layer(DefaultBodyLimit::max(MAX_JSON_BODY_SIZE_BYTES as usize))
+        .layer(DefaultBodyLimit::max(body_limit))
         .with_state(server)
 }
 
@@ -117,13 +189,94 @@ async fn method_not_allowed_handler() -> ProxyError {
     ProxyError::new("method not allowed".to_string(), 405)
 }
 
-fn create_context(s: &Arc<ProxyServer>) -> RequestContext<'_> {
-    RequestContext {
-        client: &s.client,
-        lmstudio_url: &s.config.lmstudio_url,
-        virtual_models: s.virtual_models.clone(),
-        blob_store: s.blob_store.clone(),
-        load_tracker: s.load_tracker.clone(),
+/// What a handler needs from the server for one request: the shared state,
+/// the client headers `--forward-header` picks from, and a cancellation token
+/// that fires on shutdown. Handlers extract it instead of building their
+/// [`RequestContext`] and token by hand; it composes with any body extractor
+/// after it (`JsonBody`, `Bytes`, a raw `Request`).
+#[derive(Clone)]
+pub struct RequestScope {
+    pub server: AppState,
+    /// Index into the server's backends of the LM Studio this request goes to.
+    backend: usize,
+    headers: HeaderMap,
+    /// `?stats=1` (or `true`) on the request URL.
+    stats_requested: bool,
+    pub cancellation: CancellationToken,
+}
+
+impl FromRequestParts<AppState> for RequestScope {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Self {
+            server: state.clone(),
+            backend: state.backends.preferred(),
+            headers: parts.headers.clone(),
+            stats_requested: parts.uri.query().is_some_and(|query| {
+                url::form_urlencoded::parse(query.as_bytes())
+                    .any(|(k, v)| k == "stats" && matches!(v.as_ref(), "1" | "true"))
+            }),
+            cancellation: state.shutdown.child_token(),
+        })
+    }
+}
+
+impl RequestScope {
+    pub fn config(&self) -> &Config {
+        &self.server.config
+    }
+
+    pub fn model_resolver(&self) -> Arc<ModelResolver> {
+        self.server
+            .backends
+            .get(self.backend)
+            .model_resolver
+            .clone()
+    }
+
+    /// This request, sent to another backend instead.
+    fn on_backend(&self, backend: usize) -> Self {
+        Self {
+            backend,
+            ..self.clone()
+        }
+    }
+
+    /// The context for handlers that talk to LM Studio on the proxy's own
+    /// behalf; client headers are not passed on.
+    pub fn context(&self) -> RequestContext<'_> {
+        RequestContext {
+            lmstudio_url: &self.server.backends.get(self.backend).url,
+            expose_stats: self.config().expose_stats || self.stats_requested,
+            ..self.server.request_context()
+        }
+    }
+
+    /// [`Self::context`] for handlers that pass `--forward-header` client
+    /// headers on to LM Studio.
+    pub fn forwarding_context(&self) -> RequestContext<'_> {
+        RequestContext {
+            forward_headers: select_forward_headers(&self.headers, &self.config().forward_header),
+            ..self.context()
+        }
+    }
+
+    /// Fail an inference request straight away while the health monitor knows
+    /// LM Studio is unreachable, instead of waiting out the connect timeout.
+    /// The monitor only watches the primary, so with a fallback configured
+    /// the request is let through to fail over instead.
+    fn ensure_backend_reachable(&self) -> Result<(), ProxyError> {
+        if self.server.backends.has_fallback() {
+            return Ok(());
+        }
+        match &self.server.health_monitor {
+            Some(monitor) => monitor.ensure_reachable(),
+            None => Ok(()),
+        }
     }
 }
 
@@ -135,133 +288,379 @@ async fn version_handler(State(s): State<AppState>) -> Result<Response, ProxyErr
     ollama::handle_ollama_version(&s.config.ollama_version).await
 }
 
-async fn health_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    let value = ollama::handle_health_check(context, s.shutdown.child_token()).await?;
+async fn health_handler(scope: RequestScope) -> Result<Response, ProxyError> {
+    let value = deep_health(&scope).await?;
     Ok(json_response(&value))
 }
 
-async fn tags_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_tags(context, s.model_resolver.clone(), s.shutdown.child_token()).await
+async fn readiness_handler(scope: RequestScope) -> Result<Response, ProxyError> {
+    let value = deep_health(&scope).await?;
+    let mut response = json_response(&value);
+    if !ollama::is_ready(&value) {
+        *response.status_mut() = http::StatusCode::SERVICE_UNAVAILABLE;
+    }
+    Ok(response)
+}
+
+async fn deep_health(scope: &RequestScope) -> Result<Value, ProxyError> {
+    // The top-level status is always the primary's, which is what the
+    // monitor and probe cache track; fallbacks are listed under `backends`.
+    let primary = scope.on_backend(0);
+    ollama::handle_health_check(
+        primary.context(),
+        scope.server.health_monitor.as_deref(),
+        &scope.server.health_probe_cache,
+        scope.cancellation.clone(),
+    )
+    .await
+}
+
+async fn tags_handler(scope: RequestScope) -> Result<Response, ProxyError> {
+    with_failover(scope, (), |scope, ()| async move {
+        ollama::handle_ollama_tags(
+            scope.context(),
+            scope.model_resolver(),
+            scope.server.model_timestamps.clone(),
+            scope.cancellation.clone(),
+        )
+        .await
+    })
+    .await
 }
 
 async fn chat_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
+    scope.ensure_backend_reachable()?;
+    with_failover(scope, body, |scope, body| {
+        with_auto_pull(scope, body, serve_chat)
+    })
+    .await
+}
+
+async fn serve_chat(scope: RequestScope, body: Value) -> Result<Response, ProxyError> {
+    let config = scope.config();
     ollama::handle_ollama_chat(
-        context,
-        s.model_resolver.clone(),
+        scope.forwarding_context(),
+        scope.model_resolver(),
         body,
-        s.shutdown.child_token(),
+        scope.cancellation.clone(),
         ollama::ChatOptions {
-            load_timeout_seconds: s.config.load_timeout_seconds,
-            use_native_chat: s.config.use_native_chat,
-            native_chat_streaming: s.config.native_chat_streaming,
-            auto_evict: s.config.auto_evict,
+            load_timeout_seconds: config.load_timeout_seconds,
+            use_native_chat: config.use_native_chat,
+            native_chat_streaming: config.native_chat_streaming,
+            auto_evict: config.auto_evict,
+            reasoning_mode: config.reasoning_mode,
+            context_guard: config.context_guard,
         },
     )
     .await
 }
 
 async fn generate_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
+    scope.ensure_backend_reachable()?;
+    with_failover(scope, body, |scope, body| {
+        with_auto_pull(scope, body, serve_generate)
+    })
+    .await
+}
+
+async fn serve_generate(scope: RequestScope, body: Value) -> Result<Response, ProxyError> {
+    let config = scope.config();
     ollama::handle_ollama_generate(
-        context,
-        s.model_resolver.clone(),
+        scope.forwarding_context(),
+        scope.model_resolver(),
         body,
-        s.shutdown.child_token(),
-        s.config.load_timeout_seconds,
-        s.config.auto_evict,
+        scope.cancellation.clone(),
+        config.load_timeout_seconds,
+        config.auto_evict,
+        config.reasoning_mode,
     )
     .await
 }
 
-async fn embed_handler(
+/// Run `serve` on the request's backend and, when that backend can't be
+/// reached (connection refused or timed out), open its breaker and run the
+/// same request on the next one. A response that has started streaming is
+/// never retried. Without `--lmstudio-fallback-url` this is just `serve`.
+async fn with_failover<B, F, Fut>(
+    scope: RequestScope,
+    body: B,
+    serve: F,
+) -> Result<Response, ProxyError>
+where
+    B: Clone,
+    F: Fn(RequestScope, B) -> Fut,
+    Fut: Future<Output = Result<Response, ProxyError>>,
+{
+    let backends = scope.server.backends.clone();
+    if !backends.has_fallback() {
+        return serve(scope, body).await;
+    }
+    let order = backends.try_order();
+    let mut result = Err(ProxyError::unreachable(ERROR_LM_STUDIO_UNAVAILABLE));
+    for (attempt, &index) in order.iter().enumerate() {
+        result = serve(scope.on_backend(index), body.clone()).await;
+        match &result {
+            Err(e) if e.is_unreachable() => {
+                backends.trip(index);
+                if let Some(&next) = order.get(attempt + 1) {
+                    crate::telemetry::event(
+                        "upstream.failover",
+                        &[
+                            ("from", backends.get(index).url.as_str()),
+                            ("to", backends.get(next).url.as_str()),
+                        ],
+                    );
+                    log::warn!(
+                        "LM Studio at {} unreachable ({}); retrying on {}",
+                        backends.get(index).url,
+                        e.message,
+                        backends.get(next).url
+                    );
+                }
+            }
+            _ => {
+                backends.reset(index);
+                break;
+            }
+        }
+    }
+    result
+}
+
+/// `--auto-pull-missing`: download the requested model first when LM Studio
+/// lacks it, then `serve` the request.
+async fn with_auto_pull<F, Fut>(
+    scope: RequestScope,
+    body: Value,
+    serve: F,
+) -> Result<Response, ProxyError>
+where
+    F: FnOnce(RequestScope, Value) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Response, ProxyError>> + Send + 'static,
+{
+    let config = scope.config();
+    let Some(model) = body
+        .get("model")
+        .and_then(Value::as_str)
+        .filter(|_| config.auto_pull_missing)
+        .map(str::to_string)
+    else {
+        return serve(scope, body).await;
+    };
+    let Some(status) = ollama::start_missing_model_pull(
+        &scope.context(),
+        &scope.model_resolver(),
+        &model,
+        &config.auto_pull_pattern,
+        scope.cancellation.clone(),
+    )
+    .await?
+    else {
+        return serve(scope, body).await;
+    };
+
+    let pull = ollama::AutoPull {
+        client: scope.server.client.clone(),
+        base_url: scope.context().lmstudio_url.to_string(),
+        pull_registry: scope.server.pull_registry.clone(),
+        model_resolver: scope.model_resolver(),
+        model,
+        status,
+        stream: body.get("stream").and_then(Value::as_bool).unwrap_or(true),
+        wait: Duration::from_secs(config.auto_pull_wait_seconds),
+        cancellation_token: scope.cancellation.clone(),
+    };
+    ollama::serve_after_pull(pull, move || serve(scope, body)).await
+}
+
+async fn transform_debug_handler(
+    scope: RequestScope,
+    JsonBody(body): JsonBody<Value>,
+) -> Result<Response, ProxyError> {
+    ollama::handle_transform_debug(
+        scope.forwarding_context(),
+        scope.model_resolver(),
+        body,
+        scope.cancellation.clone(),
+        ollama::TransformOptions {
+            use_native_chat: scope.config().use_native_chat,
+            native_chat_streaming: scope.config().native_chat_streaming,
+        },
+    )
+    .await
+}
+
+async fn model_cache_list_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
+    admin::handle_model_cache_list(s.model_resolver.clone()).await
+}
+
+async fn model_cache_clear_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
+    let result = admin::handle_model_cache_clear(s.model_resolver.clone()).await;
+    s.backends.invalidate_resolutions().await;
+    result
+}
+
+async fn alias_list_handler(
     State(s): State<AppState>,
+    Query(query): Query<Vec<(String, String)>>,
+) -> Result<Response, ProxyError> {
+    let name = query
+        .iter()
+        .find(|(k, _)| k == "name")
+        .map(|(_, v)| v.as_str());
+    admin::handle_alias_list(s.virtual_models.clone(), name).await
+}
+
+async fn blob_list_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
+    admin::handle_blob_list(s.blob_store.clone(), s.virtual_models.clone()).await
+}
+
+async fn pin_list_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
+    admin::handle_pin_list(s.model_pins.clone()).await
+}
+
+async fn pin_set_handler(
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    embedding_handler_inner(s, body, EmbeddingResponseMode::Embed).await
+    let result = admin::handle_pin_set(
+        scope.context(),
+        scope.model_resolver(),
+        body,
+        scope.cancellation.clone(),
+    )
+    .await;
+    scope.server.backends.invalidate_resolutions().await;
+    result
 }
 
-async fn embeddings_handler(
+async fn pin_delete_handler(
     State(s): State<AppState>,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    embedding_handler_inner(s, body, EmbeddingResponseMode::LegacyEmbeddings).await
+    let result =
+        admin::handle_pin_delete(s.model_pins.clone(), s.model_resolver.clone(), body).await;
+    s.backends.invalidate_resolutions().await;
+    result
+}
+
+async fn embed_handler(
+    scope: RequestScope,
+    JsonBody(body): JsonBody<Value>,
+) -> Result<Response, ProxyError> {
+    embedding_handler_inner(scope, body, EmbeddingResponseMode::Embed).await
+}
+
+async fn embeddings_handler(
+    scope: RequestScope,
+    JsonBody(body): JsonBody<Value>,
+) -> Result<Response, ProxyError> {
+    embedding_handler_inner(scope, body, EmbeddingResponseMode::LegacyEmbeddings).await
 }
 
 async fn embedding_handler_inner(
-    s: AppState,
+    scope: RequestScope,
     body: Value,
     mode: EmbeddingResponseMode,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    handle_ollama_embeddings(
-        context,
-        s.model_resolver.clone(),
-        body,
-        mode,
-        s.shutdown.child_token(),
-        s.config.load_timeout_seconds,
-        s.config.auto_evict,
-    )
+    scope.ensure_backend_reachable()?;
+    with_failover(scope, body, |scope, body| async move {
+        handle_ollama_embeddings(
+            scope.forwarding_context(),
+            scope.model_resolver(),
+            body,
+            mode,
+            scope.cancellation.clone(),
+            scope.config().load_timeout_seconds,
+            scope.config().auto_evict,
+        )
+        .await
+    })
     .await
 }
 
 async fn pull_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_pull(
-        context,
-        s.model_resolver.clone(),
-        body,
-        s.shutdown.child_token(),
-    )
+    with_failover(scope, body, |scope, body| async move {
+        let result = ollama::handle_ollama_pull(
+            scope.context(),
+            scope.model_resolver(),
+            body,
+            scope.cancellation.clone(),
+        )
+        .await;
+        scope.model_resolver().invalidate_models_cache().await;
+        result
+    })
     .await
 }
 
+async fn pull_cancel_handler(
+    scope: RequestScope,
+    JsonBody(body): JsonBody<Value>,
+) -> Result<Response, ProxyError> {
+    ollama::handle_ollama_pull_cancel(scope.context(), body).await
+}
+
+async fn proxy_pull_cancel_handler(
+    scope: RequestScope,
+    JsonBody(body): JsonBody<Value>,
+) -> Result<Response, ProxyError> {
+    admin::handle_pull_cancel(scope.context(), body).await
+}
+
 async fn create_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_create(
-        context,
-        s.model_resolver.clone(),
+    let result = ollama::handle_ollama_create(
+        scope.context(),
+        scope.model_resolver(),
         body,
-        s.shutdown.child_token(),
+        scope.config().strict_params,
+        scope.cancellation.clone(),
     )
-    .await
+    .await;
+    scope.model_resolver().invalidate_models_cache().await;
+    result
 }
 
 async fn copy_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
     ollama::handle_ollama_copy(
-        context,
-        s.model_resolver.clone(),
+        scope.context(),
+        scope.model_resolver(),
         body,
-        s.shutdown.child_token(),
+        scope.config().strict_params,
+        scope.cancellation.clone(),
     )
     .await
 }
 
 async fn delete_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_delete(context, body).await
+    let result = ollama::handle_ollama_delete(
+        scope.context(),
+        scope.model_resolver(),
+        body,
+        scope.config().delete_unloads,
+        scope.cancellation.clone(),
+    )
+    .await;
+    scope.model_resolver().invalidate_models_cache().await;
+    result
 }
 
 async fn push_handler(JsonBody(_): JsonBody<Value>) -> Result<Response, ProxyError> {
@@ -290,44 +689,105 @@ async fn web_fetch_handler(
 }
 
 async fn show_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
+    Query(query): Query<Vec<(String, String)>>,
     JsonBody(body): JsonBody<Value>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_show(
-        context,
-        s.model_resolver.clone(),
-        body,
-        s.shutdown.child_token(),
-    )
+    // `?debug=true` (or `1`) adds the resolver's candidate scores.
+    let match_debug = query
+        .iter()
+        .any(|(k, v)| k == "debug" && matches!(v.as_str(), "1" | "true"));
+    with_failover(scope, body, |scope, body| async move {
+        ollama::handle_ollama_show(
+            scope.context(),
+            scope.model_resolver(),
+            body,
+            match_debug,
+            scope.cancellation.clone(),
+        )
+        .await
+    })
+    .await
+}
+
+async fn ps_handler(scope: RequestScope) -> Result<Response, ProxyError> {
+    with_failover(scope, (), |scope, ()| async move {
+        ollama::handle_ollama_ps(
+            scope.context(),
+            scope.model_resolver(),
+            scope.cancellation.clone(),
+        )
+        .await
+    })
     .await
 }
 
-async fn ps_handler(State(s): State<AppState>) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_ollama_ps(context, s.model_resolver.clone(), s.shutdown.child_token()).await
+async fn registry_handler(
+    scope: RequestScope,
+    Path(path): Path<String>,
+) -> Result<Response, ProxyError> {
+    match ollama::RegistryPath::parse(&path) {
+        Some(ollama::RegistryPath::Manifest { name, reference }) => {
+            ollama::handle_registry_manifest(
+                scope.context(),
+                scope.model_resolver(),
+                &name,
+                &reference,
+                scope.cancellation.clone(),
+            )
+            .await
+        }
+        Some(ollama::RegistryPath::Blob { digest, .. }) => {
+            ollama::handle_registry_blob(
+                scope.context(),
+                scope.model_resolver(),
+                &digest,
+                scope.cancellation.clone(),
+            )
+            .await
+        }
+        None => Err(ProxyError::not_found("endpoint not found")),
+    }
 }
 
 async fn blob_head_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
+    Path(digest): Path<String>,
+) -> Result<Response, ProxyError> {
+    ollama::handle_blob_head(scope.context(), digest).await
+}
+
+async fn blob_delete_handler(
+    scope: RequestScope,
     Path(digest): Path<String>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    ollama::handle_blob_head(context, digest).await
+    ollama::handle_blob_delete(scope.context(), digest).await
 }
 
 async fn blob_upload_handler(
-    State(s): State<AppState>,
+    scope: RequestScope,
     Path(digest): Path<String>,
     request: Request,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
+    let content_length = request
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    let max_size = scope.server.config.max_blob_size;
     let body_stream = request.into_body().into_data_stream();
-    ollama::handle_blob_upload(context, digest, body_stream).await
+    ollama::handle_blob_upload(
+        scope.context(),
+        digest,
+        body_stream,
+        content_length,
+        max_size,
+    )
+    .await
 }
 
 async fn passthrough_v1(
-    State(s): State<AppState>,
+    scope: RequestScope,
     Path(path): Path<String>,
     Query(query): Query<Vec<(String, String)>>,
     method: http::Method,
@@ -336,63 +796,64 @@ async fn passthrough_v1(
 ) -> Result<Response, ProxyError> {
     let full_path = format!("/v1/{}", path);
     let query_string = encode_query(&query);
-    forward_passthrough(s, method, full_path, body, headers, query_string).await
+    forward_passthrough(scope, method, full_path, body, headers, query_string).await
 }
 
 async fn passthrough_native_versioned(
-    State(s): State<AppState>,
-    Path((version, path)): Path<(String, String)>,
+    scope: RequestScope,
+    prefix: NestedPath,
+    Path(path): Path<String>,
     Query(query): Query<Vec<(String, String)>>,
     method: http::Method,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, ProxyError> {
-    if !version.starts_with('v') {
-        return Err(ProxyError::not_found("endpoint not found"));
-    }
-    let full_path = format!("/api/{}/{}", version, path);
+    let full_path = format!("{}/{}", prefix.as_str(), path);
     let query_string = encode_query(&query);
-    forward_passthrough(s, method, full_path, body, headers, query_string).await
+    forward_passthrough(scope, method, full_path, body, headers, query_string).await
 }
 
 async fn passthrough_native_version_root(
-    State(s): State<AppState>,
-    Path(version): Path<String>,
+    scope: RequestScope,
+    prefix: NestedPath,
     Query(query): Query<Vec<(String, String)>>,
     method: http::Method,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, ProxyError> {
-    if !version.starts_with('v') {
-        return Err(ProxyError::not_found("endpoint not found"));
-    }
-    let full_path = format!("/api/{}", version);
+    let full_path = prefix.as_str().to_string();
     let query_string = encode_query(&query);
-    forward_passthrough(s, method, full_path, body, headers, query_string).await
+    forward_passthrough(scope, method, full_path, body, headers, query_string).await
 }
 
 async fn forward_passthrough(
-    s: AppState,
+    scope: RequestScope,
     method: http::Method,
     full_path: String,
     body: Bytes,
     headers: HeaderMap,
     query: Option<String>,
 ) -> Result<Response, ProxyError> {
-    let context = create_context(&s);
-    lmstudio::handle_lmstudio_passthrough(
-        context,
-        s.model_resolver.clone(),
-        lmstudio::LmStudioPassthroughRequest {
-            method,
-            endpoint: full_path,
-            body,
-            headers,
-            query,
-        },
-        s.shutdown.child_token(),
-        s.config.load_timeout_seconds,
-    )
+    if !lmstudio::is_answered_locally(&method, &full_path) {
+        scope.ensure_backend_reachable()?;
+    }
+    let request = lmstudio::LmStudioPassthroughRequest {
+        method,
+        endpoint: full_path,
+        body,
+        headers,
+        query,
+    };
+    with_failover(scope, request, |scope, request| async move {
+        lmstudio::handle_lmstudio_passthrough(
+            scope.context(),
+            scope.model_resolver(),
+            request,
+            scope.cancellation.clone(),
+            scope.config().load_timeout_seconds,
+        )
+        .await
+    })
     .await
 }
 
@@ -0,0 +1,330 @@
+use std::fmt;
+use std::path::Path;
+use std::time::Instant;
+
+use serde_json::json;
+
+use crate::config::{Config, ListenAddr, parse_listen_addrs};
+use crate::constants::{LM_STUDIO_NATIVE_CHAT, LM_STUDIO_NATIVE_MODELS};
+use crate::model::types::{NativeModelData, NativeModelsResponse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    Skip,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Pass => "PASS",
+            Self::Fail => "FAIL",
+            Self::Skip => "SKIP",
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    /// A failed mandatory check fails the whole self-test; others only
+    /// inform.
+    pub mandatory: bool,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &str, status: CheckStatus, mandatory: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            mandatory,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// What `--self-test` prints before exiting.
+#[derive(Debug, Clone, Default)]
+pub struct SelfTestReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    /// No mandatory check failed.
+    pub fn passed(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|c| c.mandatory && c.status == CheckStatus::Fail)
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for check in &self.checks {
+            let optional = if check.mandatory { "" } else { " (optional)" };
+            out.push_str(&format!(
+                "[{}] {}{}: {}\n",
+                check.status, check.name, optional, check.detail
+            ));
+        }
+        let failed = self
+            .checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Fail)
+            .count();
+        out.push_str(&format!(
+            "self-test {}: {} checks, {} failed\n",
+            if self.passed() { "passed" } else { "failed" },
+            self.checks.len(),
+            failed
+        ));
+        out
+    }
+}
+
+/// Run every startup check against `config` without starting the server.
+/// `state_dir` is where virtual models and blobs live; `inference` sends a
+/// one-token completion to the first loaded model.
+pub async fn run(
+    config: &Config,
+    client: &reqwest::Client,
+    state_dir: &Path,
+    inference: bool,
+) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    report.checks.push(check_data_dir(state_dir));
+    report.checks.extend(check_listen(&config.listen));
+
+    let models = check_lmstudio(client, &config.lmstudio_url, &mut report).await;
+    report
+        .checks
+        .push(check_inference(client, &config.lmstudio_url, models.as_deref(), inference).await);
+    report
+}
+
+fn check_data_dir(state_dir: &Path) -> CheckResult {
+    const NAME: &str = "data directory writable";
+    let probe = state_dir.join("blobs").join(".self-test");
+    let result = std::fs::create_dir_all(state_dir.join("blobs"))
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            true,
+            state_dir.display().to_string(),
+        ),
+        Err(e) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            true,
+            format!("{}: {}", state_dir.display(), e),
+        ),
+    }
+}
+
+fn check_listen(listen: &[String]) -> Vec<CheckResult> {
+    const NAME: &str = "listen address bindable";
+    let addrs = match parse_listen_addrs(listen) {
+        Ok(addrs) => addrs,
+        Err(e) => return vec![CheckResult::new(NAME, CheckStatus::Fail, true, e)],
+    };
+    addrs
+        .iter()
+        .map(|addr| match addr {
+            // Dropped straight away, so the port is free again for the real run.
+            ListenAddr::Tcp(socket_addr) => match std::net::TcpListener::bind(socket_addr) {
+                Ok(_) => CheckResult::new(NAME, CheckStatus::Pass, true, addr.to_string()),
+                Err(e) => {
+                    CheckResult::new(NAME, CheckStatus::Fail, true, format!("{}: {}", addr, e))
+                }
+            },
+            ListenAddr::Unix(_) => CheckResult::new(
+                NAME,
+                CheckStatus::Skip,
+                true,
+                format!("{}: unix sockets are only bound at startup", addr),
+            ),
+        })
+        .collect()
+}
+
+/// Reachability, native API and model count. Returns the model list when
+/// the native API answered.
+async fn check_lmstudio(
+    client: &reqwest::Client,
+    lmstudio_url: &str,
+    report: &mut SelfTestReport,
+) -> Option<Vec<NativeModelData>> {
+    const REACHABLE: &str = "LM Studio reachable";
+    const NATIVE: &str = "LM Studio native API";
+    const MODELS: &str = "models available";
+    let url = format!("{}{}", lmstudio_url, LM_STUDIO_NATIVE_MODELS);
+
+    let response = match client.get(&url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            report.checks.push(CheckResult::new(
+                REACHABLE,
+                CheckStatus::Fail,
+                true,
+                format!("{}: {}", lmstudio_url, e),
+            ));
+            report.checks.push(CheckResult::new(
+                NATIVE,
+                CheckStatus::Skip,
+                true,
+                "LM Studio unreachable",
+            ));
+            report.checks.push(CheckResult::new(
+                MODELS,
+                CheckStatus::Skip,
+                false,
+                "LM Studio unreachable",
+            ));
+            return None;
+        }
+    };
+    let status = response.status();
+    report.checks.push(CheckResult::new(
+        REACHABLE,
+        CheckStatus::Pass,
+        true,
+        format!("{} (HTTP {})", lmstudio_url, status.as_u16()),
+    ));
+
+    let models = if status.is_success() {
+        match response.json::<NativeModelsResponse>().await {
+            Ok(parsed) => {
+                // The native API has no version field; /api/v1/models itself
+                // is the 0.3.6+ marker.
+                report.checks.push(CheckResult::new(
+                    NATIVE,
+                    CheckStatus::Pass,
+                    true,
+                    format!("{} answers (LM Studio 0.3.6+)", LM_STUDIO_NATIVE_MODELS),
+                ));
+                Some(parsed.models)
+            }
+            Err(e) => {
+                report.checks.push(CheckResult::new(
+                    NATIVE,
+                    CheckStatus::Fail,
+                    true,
+                    format!("unexpected {} body: {}", LM_STUDIO_NATIVE_MODELS, e),
+                ));
+                None
+            }
+        }
+    } else {
+        let hint = match status.as_u16() {
+            401 | 403 => " (check --lmstudio-token)",
+            404 => " (LM Studio 0.3.6+ required)",
+            _ => "",
+        };
+        report.checks.push(CheckResult::new(
+            NATIVE,
+            CheckStatus::Fail,
+            true,
+            format!(
+                "{} returned HTTP {}{}",
+                LM_STUDIO_NATIVE_MODELS,
+                status.as_u16(),
+                hint
+            ),
+        ));
+        None
+    };
+
+    report.checks.push(match &models {
+        Some(models) => {
+            let loaded = models
+                .iter()
+                .filter(|m| !m.loaded_instances.is_empty())
+                .count();
+            CheckResult::new(
+                MODELS,
+                if models.is_empty() {
+                    CheckStatus::Fail
+                } else {
+                    CheckStatus::Pass
+                },
+                false,
+                format!("{} models, {} loaded", models.len(), loaded),
+            )
+        }
+        None => CheckResult::new(MODELS, CheckStatus::Skip, false, "no model list"),
+    });
+    models
+}
+
+async fn check_inference(
+    client: &reqwest::Client,
+    lmstudio_url: &str,
+    models: Option<&[NativeModelData]>,
+    inference: bool,
+) -> CheckResult {
+    const NAME: &str = "test completion";
+    if !inference {
+        return CheckResult::new(NAME, CheckStatus::Skip, true, "--self-test-no-inference");
+    }
+    let Some(models) = models else {
+        return CheckResult::new(NAME, CheckStatus::Skip, true, "no model list");
+    };
+    // Only an already loaded chat model: the self-test must not start a load.
+    let Some(instance) = models
+        .iter()
+        .filter(|m| m.model_type == "llm" || m.model_type == "vlm")
+        .find_map(|m| m.loaded_instances.first())
+    else {
+        return CheckResult::new(NAME, CheckStatus::Skip, true, "no loaded chat model");
+    };
+
+    let started = Instant::now();
+    let result = client
+        .post(format!("{}{}", lmstudio_url, LM_STUDIO_NATIVE_CHAT))
+        .json(&json!({
+            "model": instance.id,
+            "messages": [{"role": "user", "content": "Hi"}],
+            "max_tokens": 1,
+            "stream": false,
+        }))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => CheckResult::new(
+            NAME,
+            CheckStatus::Pass,
+            true,
+            format!(
+                "{} answered in {} ms",
+                instance.id,
+                started.elapsed().as_millis()
+            ),
+        ),
+        Ok(response) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            true,
+            format!(
+                "{} returned HTTP {}",
+                instance.id,
+                response.status().as_u16()
+            ),
+        ),
+        Err(e) => CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            true,
+            format!("{}: {}", instance.id, e),
+        ),
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/proxy_self_test.rs"]
+mod tests;
@@ -1,25 +1,65 @@
-use std::net::SocketAddr;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
+use futures_util::FutureExt;
 use moka::future::Cache;
+use tokio_rustls::TlsAcceptor;
 use tokio_util::sync::CancellationToken;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
-use crate::config::Config;
-use crate::logging::LogConfig;
-use crate::model::{LoadTracker, ModelResolver};
+use crate::api::backends::BACKEND_COOLDOWN;
+use crate::api::ollama::HealthProbeCache;
+use crate::api::ollama::resolution::resolve_model_target_and_fetch;
+use crate::api::retry::trigger_model_loading_for_ollama;
+use crate::api::{
+    Backend, BackendSelector, HealthMonitor, LoadCoordinator, PullRegistry, RequestContext,
+};
+use crate::config::{
+    Config, ListenAddr, UpstreamBind, parse_listen_addrs, parse_lmstudio_headers,
+    parse_model_name_map, parse_upstream_bind,
+};
+use crate::constants::HEADER_REQUEST_ID;
+use crate::error::ProxyError;
+use crate::http::ClientSettings;
+use crate::logging::{ACCESS_LOG_FLUSH_INTERVAL, AccessLog, LogConfig};
+use crate::model::{LoadTracker, ModelConcurrency, ModelFilter, ModelResolver, RuntimeInfoCache};
 use crate::proxy::routes::create_router;
-use crate::storage::{BlobStore, VirtualModelStore};
+use crate::proxy::service::{PidFile, spawn_log_reopen_on_sighup};
+use crate::proxy::tls::{TlsListener, load_tls_acceptor};
+use crate::storage::blob::BLOB_INDEX_FLUSH_INTERVAL;
+use crate::storage::{
+    BlobStore, EmbeddingCache, GenerateContextStore, ModelPinStore, ModelTimestampStore,
+    VirtualModelStore,
+};
+use crate::streaming::StreamTimeouts;
 
 pub struct ProxyServer {
     pub client: reqwest::Client,
     pub config: Config,
+    /// The primary backend's resolver.
     pub model_resolver: Arc<ModelResolver>,
+    pub backends: Arc<BackendSelector>,
     pub virtual_models: Arc<VirtualModelStore>,
+    pub model_pins: Arc<ModelPinStore>,
+    pub model_name_map: Arc<HashMap<String, String>>,
     pub blob_store: Arc<BlobStore>,
+    pub model_timestamps: Arc<ModelTimestampStore>,
     pub load_tracker: Arc<LoadTracker>,
+    pub runtime_info: Arc<RuntimeInfoCache>,
+    pub model_concurrency: Arc<ModelConcurrency>,
+    pub load_coordinator: Arc<LoadCoordinator>,
+    pub pull_registry: Arc<PullRegistry>,
+    pub model_filter: Arc<ModelFilter>,
+    pub generate_context: Option<Arc<GenerateContextStore>>,
+    pub embedding_cache: Option<Arc<EmbeddingCache>>,
+    pub health_monitor: Option<Arc<HealthMonitor>>,
+    pub health_probe_cache: HealthProbeCache,
+    /// `--access-log-file`, when set.
+    pub access_log: Option<Arc<AccessLog>>,
     pub shutdown: CancellationToken,
 }
 
@@ -32,67 +72,262 @@ impl ProxyServer {
         config: Config,
         state_dir: PathBuf,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut client_builder = reqwest::Client::builder()
-            .timeout(Duration::from_secs(300))
-            .pool_max_idle_per_host(32)
-            .pool_idle_timeout(Duration::from_secs(90))
-            .tcp_keepalive(Duration::from_secs(60));
-
-        if let Some(ref token) = config.lmstudio_token {
-            let mut default_headers = reqwest::header::HeaderMap::new();
-            let header_value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
-                .map_err(|e| format!("invalid lmstudio-token: {}", e))?;
-            default_headers.insert(reqwest::header::AUTHORIZATION, header_value);
-            client_builder = client_builder.default_headers(default_headers);
-        }
-
-        let client = client_builder.build()?;
+        let client = build_client(&config)?;
 
-        let cache: Cache<String, String> = Cache::builder()
-            .max_capacity(1000)
-            .time_to_live(Duration::from_secs(
-                config.model_resolution_cache_ttl_seconds,
-            ))
-            .build();
-
-        let model_resolver = Arc::new(ModelResolver::new(config.lmstudio_url.clone(), cache));
+        let model_filter = Arc::new(ModelFilter::new(
+            &config.model_allowlist,
+            &config.model_blocklist,
+        ));
 
         let virtual_models_path = state_dir.join("virtual_models.json");
         let blob_dir = state_dir.join("blobs");
 
         let virtual_models = Arc::new(VirtualModelStore::load(virtual_models_path)?);
-        let blob_store = Arc::new(BlobStore::new(blob_dir)?);
+        let model_pins = Arc::new(ModelPinStore::load(state_dir.join("model_pins.json"))?);
+        let model_name_map = Arc::new(parse_model_name_map(&config.model_name_map)?);
+
+        // One resolver per backend: ids and cached resolutions from one
+        // LM Studio server mean nothing on another.
+        let backend = |url: &String| {
+            let cache: Cache<String, String> = Cache::builder()
+                .max_capacity(1000)
+                .time_to_live(Duration::from_secs(
+                    config.model_resolution_cache_ttl_seconds,
+                ))
+                .build();
+            let resolver = ModelResolver::new(url.clone(), cache)
+                .with_resolution_mode(config.resolution)
+                .with_models_cache_ttl(Duration::from_secs(config.models_cache_ttl_seconds))
+                .with_model_filter(model_filter.clone())
+                .with_pins(model_pins.clone());
+            Backend::new(url.clone(), Arc::new(resolver))
+        };
+        let backends = BackendSelector::new(
+            std::iter::once(&config.lmstudio_url)
+                .chain(config.lmstudio_fallback_url.as_ref())
+                .map(backend)
+                .collect(),
+            BACKEND_COOLDOWN,
+        );
+        let model_resolver = backends.primary().model_resolver.clone();
+
+        let blob_store = Arc::new(
+            BlobStore::new(blob_dir)?
+                .with_max_total_bytes(config.blob_max_total_mb.saturating_mul(1024 * 1024)),
+        );
+        let model_timestamps = Arc::new(ModelTimestampStore::load(
+            state_dir.join("model_timestamps.json"),
+        )?);
         let load_tracker = LoadTracker::new();
+        let model_concurrency = ModelConcurrency::new(
+            config.max_concurrent_per_model,
+            Duration::from_secs(config.concurrency_wait_seconds),
+        );
+        let load_coordinator =
+            LoadCoordinator::new(Duration::from_secs(config.load_timeout_seconds));
+        let generate_context = config.emulate_generate_context.then(|| {
+            GenerateContextStore::new(Duration::from_secs(config.generate_context_ttl_seconds))
+        });
+        let embedding_cache = (config.embedding_cache_size > 0).then(|| {
+            EmbeddingCache::new(
+                config.embedding_cache_size,
+                Duration::from_secs(config.embedding_cache_ttl_seconds),
+            )
+        });
+        let health_monitor = (config.health_check_interval_seconds > 0)
+            .then(|| HealthMonitor::new(Duration::from_secs(config.health_check_interval_seconds)));
+        let access_log = match &config.access_log_file {
+            Some(path) => Some(Arc::new(
+                AccessLog::open(
+                    path,
+                    config.log_max_size_mb.saturating_mul(1024 * 1024),
+                    config.log_max_files,
+                )
+                .map_err(|e| format!("cannot open --access-log-file {}: {}", path.display(), e))?,
+            )),
+            None => None,
+        };
 
         Ok(Self {
             client,
             config,
             model_resolver,
+            backends,
             virtual_models,
+            model_pins,
+            model_name_map,
             blob_store,
+            model_timestamps,
             load_tracker,
+            runtime_info: RuntimeInfoCache::new(),
+            model_concurrency,
+            load_coordinator,
+            pull_registry: PullRegistry::new(),
+            model_filter,
+            generate_context,
+            embedding_cache,
+            health_monitor,
+            health_probe_cache: HealthProbeCache::new(Duration::from_secs(
+                config.health_cache_seconds,
+            )),
+            access_log,
             shutdown: CancellationToken::new(),
         })
     }
 
+    /// A [`RequestContext`] for work done on the proxy's own behalf; no
+    /// client headers are forwarded.
+    pub fn request_context(&self) -> RequestContext<'_> {
+        RequestContext {
+            client: &self.client,
+            lmstudio_url: &self.config.lmstudio_url,
+            backends: self.backends.clone(),
+            virtual_models: self.virtual_models.clone(),
+            model_pins: self.model_pins.clone(),
+            model_name_map: self.model_name_map.clone(),
+            blob_store: self.blob_store.clone(),
+            load_tracker: self.load_tracker.clone(),
+            runtime_info: self.runtime_info.clone(),
+            model_concurrency: self.model_concurrency.clone(),
+            load_coordinator: self.load_coordinator.clone(),
+            pull_registry: self.pull_registry.clone(),
+            model_filter: self.model_filter.clone(),
+            stream_timeouts: StreamTimeouts::from_secs(
+                self.config.first_token_timeout_seconds,
+                self.config.stream_idle_timeout_seconds,
+            )
+            .with_sse_keepalive(self.config.sse_keepalive_seconds),
+            forward_headers: reqwest::header::HeaderMap::new(),
+            generate_context: self.generate_context.clone(),
+            embedding_cache: self.embedding_cache.clone(),
+            default_system_prompt: self.config.default_system_prompt.as_deref(),
+            expose_stats: self.config.expose_stats,
+            ollama_model_ids: self.config.ollama_model_ids,
+        }
+    }
+
+    /// `--preload`: resolve each configured model and send it the load
+    /// trigger a cold request would, one at a time. A model that fails to
+    /// resolve or load is logged and skipped.
+    pub async fn preload_models(&self) {
+        let context = self.request_context();
+        for name in &self.config.preload {
+            let token = self.shutdown.child_token();
+            let result = async {
+                let (model_id, _, model_info) = resolve_model_target_and_fetch(
+                    &context,
+                    &self.model_resolver,
+                    name,
+                    token.clone(),
+                )
+                .await?;
+                trigger_model_loading_for_ollama(&context, &model_id, model_info.as_ref(), token)
+                    .await?;
+                Ok::<_, ProxyError>(model_id)
+            }
+            .await;
+            match result {
+                Ok(model_id) => log::info!("preload: '{}' warmed up as {}", name, model_id),
+                Err(e) if e.is_cancelled() => return,
+                Err(e) => log::warn!("preload: '{}' not loaded: {}", name, e.message),
+            }
+        }
+    }
+
+    /// Start the `--health-check-interval-seconds` probe task; a no-op when
+    /// the monitor is off. It stops with the server's shutdown token.
+    pub fn spawn_health_monitor(&self) {
+        if let Some(monitor) = &self.health_monitor {
+            tokio::spawn(monitor.clone().run(
+                self.client.clone(),
+                self.config.lmstudio_url.clone(),
+                self.model_resolver.clone(),
+                self.shutdown.clone(),
+            ));
+        }
+    }
+
+    /// Write buffered `--access-log-file` lines every
+    /// [`ACCESS_LOG_FLUSH_INTERVAL`] so a quiet server doesn't hold them back;
+    /// a no-op without an access log. Flushes once more on shutdown.
+    pub fn spawn_access_log_flusher(&self) {
+        let Some(access_log) = self.access_log.clone() else {
+            return;
+        };
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ACCESS_LOG_FLUSH_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => access_log.flush(),
+                }
+            }
+            access_log.flush();
+        });
+    }
+
+    /// Write blob last-use times every [`BLOB_INDEX_FLUSH_INTERVAL`], and
+    /// once more on shutdown.
+    pub fn spawn_blob_index_flusher(&self) {
+        let blob_store = self.blob_store.clone();
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(BLOB_INDEX_FLUSH_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => break,
+                    _ = interval.tick() => blob_store.flush().await,
+                }
+            }
+            blob_store.flush().await;
+        });
+    }
+
     pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
-        let addr: SocketAddr = self.config.listen.parse()?;
+        let addrs = parse_listen_addrs(&self.config.listen)?;
         let server = Arc::new(self);
 
-        let api_key = Arc::new(server.config.api_key.clone());
+        let app = build_app(server.clone());
 
-        let app = create_router(server.clone())
-            .layer(axum::middleware::from_fn(access_log))
-            .layer(axum::middleware::from_fn_with_state(
-                api_key,
-                crate::proxy::auth::api_key_gate,
-            ))
-            .layer(cors_layer());
+        let tls_acceptor = match (&server.config.tls_cert, &server.config.tls_key) {
+            (Some(cert), Some(key)) => Some(load_tls_acceptor(cert, key)?),
+            _ => None,
+        };
 
+        // Bind everything before serving anything: a single failed bind aborts
+        // startup instead of leaving the proxy half-reachable.
+        let mut listeners = Vec::with_capacity(addrs.len());
+        for addr in &addrs {
+            listeners.push(BoundListener::bind(addr, tls_acceptor.as_ref()).await?);
+        }
+        // Written only once the ports are ours, so its presence means "up".
+        let _pid_file = match &server.config.pid_file {
+            Some(path) => Some(
+                PidFile::create(path)
+                    .map_err(|e| format!("cannot write --pid-file {}: {}", path.display(), e))?,
+            ),
+            None => None,
+        };
+
+        let bound = addrs
+            .iter()
+            .map(ListenAddr::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let scheme = if tls_acceptor.is_some() {
+            "https"
+        } else {
+            "http"
+        };
         if LogConfig::get().debug_enabled {
-            log::info!("starting proxy server on {} (debug mode)", addr);
+            log::info!(
+                "starting proxy server ({}) on {} (debug mode)",
+                scheme,
+                bound
+            );
         } else {
-            log::info!("starting proxy server on {}", addr);
+            log::info!("starting proxy server ({}) on {}", scheme, bound);
         }
         log::info!("LM Studio backend: {}", server.config.lmstudio_url);
 
@@ -105,7 +340,20 @@ impl ProxyServer {
             );
         }
 
-        let listener = tokio::net::TcpListener::bind(addr).await?;
+        if server.config.accurate_tokens && !crate::lmstudio::tokens::tokenizer_compiled_in() {
+            log::warn!(
+                "--accurate-tokens requested but this build lacks the `accurate-tokens` \
+                 feature; falling back to length-based token estimates"
+            );
+        }
+
+        server.spawn_health_monitor();
+        server.spawn_access_log_flusher();
+        server.spawn_blob_index_flusher();
+        if !server.config.preload.is_empty() {
+            let preloader = server.clone();
+            tokio::spawn(async move { preloader.preload_models().await });
+        }
 
         let shutdown = server.shutdown.clone();
         tokio::spawn(async move {
@@ -113,16 +361,137 @@ impl ProxyServer {
             log::info!("shutdown signal received, draining in-flight requests");
             shutdown.cancel();
         });
+        // Only a proxy with files to reopen, or one run as a daemon, takes
+        // SIGHUP over; in a terminal a hangup still stops it.
+        let config = &server.config;
+        if config.log_file.is_some()
+            || config.access_log_file.is_some()
+            || config.service
+            || config.pid_file.is_some()
+        {
+            spawn_log_reopen_on_sighup(server.shutdown.clone());
+        }
 
-        axum::serve(listener, app)
-            .with_graceful_shutdown(server.shutdown.clone().cancelled_owned())
-            .await?;
+        let servers = listeners.into_iter().map(|listener| {
+            let shutdown = server.shutdown.clone().cancelled_owned();
+            match listener {
+                BoundListener::Tcp(listener) => axum::serve(listener, app.clone())
+                    .with_graceful_shutdown(shutdown)
+                    .into_future()
+                    .boxed(),
+                BoundListener::Tls(listener) => axum::serve(listener, app.clone())
+                    .with_graceful_shutdown(shutdown)
+                    .into_future()
+                    .boxed(),
+                #[cfg(unix)]
+                BoundListener::Unix(listener) => axum::serve(listener, app.clone())
+                    .with_graceful_shutdown(shutdown)
+                    .into_future()
+                    .boxed(),
+            }
+        });
+        let served = futures_util::future::try_join_all(servers).await;
+
+        #[cfg(unix)]
+        for addr in &addrs {
+            if let ListenAddr::Unix(path) = addr {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        served?;
 
+        if let Some(access_log) = &server.access_log {
+            access_log.flush();
+        }
+        server.blob_store.flush().await;
         log::info!("server stopped");
         Ok(())
     }
 }
 
+enum BoundListener {
+    Tcp(tokio::net::TcpListener),
+    Tls(TlsListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl BoundListener {
+    /// TCP addresses serve HTTPS when `tls` is set; Unix sockets stay plain.
+    async fn bind(addr: &ListenAddr, tls: Option<&TlsAcceptor>) -> Result<Self, String> {
+        match addr {
+            ListenAddr::Tcp(socket_addr) => {
+                let listener = bind_tcp(*socket_addr)
+                    .map_err(|e| format!("failed to bind {}: {}", addr, e))?;
+                match tls {
+                    Some(acceptor) => TlsListener::new(listener, acceptor.clone())
+                        .map(Self::Tls)
+                        .map_err(|e| format!("failed to bind {}: {}", addr, e)),
+                    None => Ok(Self::Tcp(listener)),
+                }
+            }
+            #[cfg(unix)]
+            ListenAddr::Unix(path) => {
+                remove_stale_socket(path)?;
+                tokio::net::UnixListener::bind(path)
+                    .map(Self::Unix)
+                    .map_err(|e| format!("failed to bind {}: {}", addr, e))
+            }
+            #[cfg(not(unix))]
+            ListenAddr::Unix(_) => Err(format!(
+                "unix socket listen addresses are not supported on this platform: {}",
+                addr
+            )),
+        }
+    }
+}
+
+/// A listening TCP socket on `addr`. IPv6 sockets are made v6-only: on a
+/// dual-stack Linux host `[::]` otherwise claims the IPv4 port as well, and
+/// `--listen 0.0.0.0:11434 --listen [::]:11434` fails with "address in use".
+fn bind_tcp(addr: std::net::SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
+    // What `TcpListener::bind` does: a restart can rebind a port whose old
+    // connections are still in TIME_WAIT.
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+    tokio::net::TcpListener::from_std(socket.into())
+}
+
+/// Clear a socket file left behind by a previous run that didn't shut down
+/// cleanly. A socket something still accepts on, or a path that isn't a
+/// socket at all, is left alone and reported instead.
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> Result<(), String> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(format!(
+            "refusing to replace {}: it exists and is not a socket",
+            path.display()
+        ));
+    }
+    if std::os::unix::net::UnixStream::connect(path).is_ok() {
+        return Err(format!(
+            "{} is already in use by another process",
+            path.display()
+        ));
+    }
+    std::fs::remove_file(path)
+        .map_err(|e| format!("failed to remove stale socket {}: {}", path.display(), e))
+}
+
 async fn wait_for_shutdown_signal() {
     let ctrl_c = async {
         if let Err(e) = tokio::signal::ctrl_c().await {
@@ -150,7 +519,86 @@ async fn wait_for_shutdown_signal() {
     }
 }
 
+/// Tag the request with a correlation id (the client's `X-Request-Id`, or a
+/// fresh one) for every log line and LM Studio call made while handling it,
+/// and echo it back in the `X-Request-Id` response header.
+pub async fn request_id(
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let id = crate::logging::accept_request_id(
+        req.headers()
+            .get(HEADER_REQUEST_ID)
+            .and_then(|v| v.to_str().ok()),
+    );
+    let mut response = crate::logging::with_request_id(id.clone(), next.run(req)).await;
+    if let Ok(value) = http::HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HEADER_REQUEST_ID, value);
+    }
+    response
+}
+
+/// The routes wrapped in every cross-cutting layer, outermost last: access
+/// log → `--api-key` gate → request span (with `--otlp-endpoint`) → request
+/// id → CORS, plus compression when enabled. Middleware that applies to all
+/// requests is registered here, so `run` and the test harness serve the same
+/// stack.
+pub fn build_app(server: Arc<ProxyServer>) -> axum::Router {
+    let api_key = Arc::new(server.config.api_key.clone());
+    let enable_compression = server.config.enable_compression;
+    let cors = cors_layer(&server.config.cors_origin);
+    let access_log_sinks = AccessLogSinks {
+        file: server.access_log.clone(),
+        console: !server.config.no_console_access_log,
+    };
+
+    let mut app = create_router(server);
+    if enable_compression {
+        app = app.layer(compression_layer());
+    }
+    let app = app
+        .layer(axum::middleware::from_fn_with_state(
+            access_log_sinks,
+            access_log,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            api_key,
+            crate::proxy::auth::api_key_gate,
+        ));
+    let app = if crate::telemetry::enabled() {
+        app.layer(axum::middleware::from_fn(trace_request))
+    } else {
+        app
+    };
+    let app = app.layer(axum::middleware::from_fn(request_id));
+    match cors {
+        Some(cors) => app.layer(cors),
+        None => app,
+    }
+}
+
+/// The request's server span (see [`crate::telemetry`]): parent of the
+/// resolution and LM Studio spans recorded while handling it.
+async fn trace_request(
+    req: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let span = crate::telemetry::RequestSpan::start(req.method().as_str(), req.uri().path());
+    let response = span.scope(next.run(req)).await;
+    span.finish(response)
+}
+
+/// Where [`access_log`] sends its per-request line.
+#[derive(Clone)]
+struct AccessLogSinks {
+    file: Option<Arc<AccessLog>>,
+    /// The regular logger (console and `--log-file`); off with
+    /// `--no-console-access-log`.
+    console: bool,
+}
+
 async fn access_log(
+    axum::extract::State(sinks): axum::extract::State<AccessLogSinks>,
     req: axum::extract::Request,
     next: axum::middleware::Next,
 ) -> axum::response::Response {
@@ -160,6 +608,16 @@ async fn access_log(
     let response = next.run(req).await;
     let status = response.status().as_u16();
     let elapsed = crate::logging::format_duration(start.elapsed());
+    if let Some(file) = &sinks.file {
+        let request_id = crate::logging::current_request_id().unwrap_or_else(|| "-".to_string());
+        file.record(&format!(
+            "{} {} {} {} {}",
+            request_id, method, path, status, elapsed
+        ));
+    }
+    if !sinks.console {
+        return response;
+    }
     if status >= 500 {
         log::error!("{} {} -> {} | {}", method, path, status, elapsed);
     } else if status >= 400 {
@@ -170,26 +628,120 @@ async fn access_log(
     response
 }
 
-pub fn cors_layer() -> CorsLayer {
-    use http::Method;
-    CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-            Method::HEAD,
-        ])
-        .allow_headers(Any)
+/// `--cors-origin`: CORS for the listed browser origins (`*` for any), or no
+/// CORS headers at all when none are configured. `Authorization` is listed
+/// explicitly because a wildcard `Access-Control-Allow-Headers` never covers it.
+pub fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    use http::{Method, header};
+    if origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|o| http::HeaderValue::from_str(o.trim_end_matches('/')).ok()),
+        )
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::OPTIONS,
+                Method::HEAD,
+            ])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::ACCEPT,
+                header::ACCEPT_ENCODING,
+            ]),
+    )
+}
+
+/// Smallest response body worth compressing.
+const COMPRESSION_MIN_BYTES: u16 = 1024;
+
+/// `--enable-compression`: gzip/deflate per `Accept-Encoding` for buffered
+/// responses. NDJSON and SSE streams are left alone so each chunk reaches the
+/// client as soon as it is produced instead of waiting on the encoder.
+pub fn compression_layer() -> CompressionLayer<impl Predicate + Clone> {
+    let predicate = SizeAbove::new(COMPRESSION_MIN_BYTES)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new("application/x-ndjson"));
+    CompressionLayer::new()
+        .gzip(true)
+        .deflate(true)
+        .compress_when(predicate)
 }
 
 #[cfg(test)]
 #[path = "../../tests/unit/auth_token.rs"]
 mod tests;
 
-fn get_state_directory() -> PathBuf {
+/// The shared LM Studio client: `--connect-timeout-seconds` and friends,
+/// `--lmstudio-token` and `--lmstudio-header` as default headers on every
+/// request (listing, inference, streaming, downloads), and
+/// `--upstream-bind-interface`. Default headers only go upstream; nothing
+/// copies them onto client responses.
+pub fn build_client(config: &Config) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+    let mut client_builder = ClientSettings::from_config(config).apply(
+        reqwest::Client::builder()
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60)),
+    );
+
+    let mut default_headers = reqwest::header::HeaderMap::new();
+    if let Some(ref token) = config.lmstudio_token {
+        let mut header_value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|e| format!("invalid lmstudio-token: {}", e))?;
+        header_value.set_sensitive(true);
+        default_headers.insert(reqwest::header::AUTHORIZATION, header_value);
+    }
+    for (name, value) in parse_lmstudio_headers(&config.lmstudio_header)?.iter() {
+        default_headers.insert(name.clone(), value.clone());
+    }
+    if !default_headers.is_empty() {
+        client_builder = client_builder.default_headers(default_headers);
+    }
+
+    match config
+        .upstream_bind_interface
+        .as_deref()
+        .map(parse_upstream_bind)
+        .transpose()?
+    {
+        Some(UpstreamBind::Address(addr)) => {
+            client_builder = client_builder.local_address(addr);
+        }
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        Some(UpstreamBind::Interface(name)) => {
+            client_builder = client_builder.interface(&name);
+        }
+        #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+        Some(UpstreamBind::Interface(name)) => {
+            return Err(format!(
+                "--upstream-bind-interface {}: interface names need Linux",
+                name
+            )
+            .into());
+        }
+        None => {}
+    }
+
+    Ok(client_builder.build()?)
+}
+
+/// Where virtual models, pins and blobs are kept.
+pub fn get_state_directory() -> PathBuf {
     if let Ok(xdg_cache) = std::env::var("XDG_CACHE_HOME") {
         return PathBuf::from(xdg_cache).join("ollama-lmstudio-proxy");
     }
@@ -0,0 +1,169 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio_util::sync::CancellationToken;
+
+/// `--pid-file`: holds this process's id for as long as the value lives, so
+/// service managers can find and signal the proxy. Removed on drop.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Reopen `--log-file` on every SIGHUP until `shutdown` fires, the contract
+/// logrotate's `postrotate` and most init scripts expect. Installing the
+/// handler also keeps SIGHUP from terminating the proxy.
+#[cfg(unix)]
+pub fn spawn_log_reopen_on_sighup(shutdown: CancellationToken) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            log::error!("failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                received = hangup.recv() => {
+                    if received.is_none() {
+                        break;
+                    }
+                    crate::logging::reopen_log_files();
+                    log::info!("SIGHUP received, reopened log file");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn spawn_log_reopen_on_sighup(_shutdown: CancellationToken) {}
+
+#[cfg(windows)]
+pub use windows::{SERVICE_NAME, run_windows_service};
+
+/// `--service` on Windows: hand the process to the Service Control Manager
+/// and map its stop and shutdown controls onto the graceful shutdown path.
+#[cfg(windows)]
+mod windows {
+    use std::ffi::OsString;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use windows_service::service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+        ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    use crate::config::Config;
+    use crate::proxy::ProxyServer;
+
+    /// Name the service is registered under (`sc create ollama-lmstudio-proxy ...`).
+    pub const SERVICE_NAME: &str = "ollama-lmstudio-proxy";
+
+    /// What `service_main` needs; the dispatcher calls it without arguments
+    /// of ours, on a thread of its own.
+    static STARTUP: Mutex<Option<(Config, tokio::runtime::Handle)>> = Mutex::new(None);
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// Blocks until the service stops. Fails straight away when the process
+    /// wasn't started by the Service Control Manager.
+    pub fn run_windows_service(
+        config: Config,
+        runtime: tokio::runtime::Handle,
+    ) -> Result<(), String> {
+        *STARTUP.lock().unwrap_or_else(|e| e.into_inner()) = Some((config, runtime));
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(|e| {
+            format!(
+                "--service: cannot connect to the Service Control Manager ({}); install the proxy with `sc create {} binPath= \"...\"` and start it with `sc start {}`",
+                e, SERVICE_NAME, SERVICE_NAME
+            )
+        })
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_service() {
+            log::error!("windows service stopped with an error: {}", e);
+        }
+    }
+
+    fn status(state: ServiceState, exit_code: u32) -> ServiceStatus {
+        let controls_accepted = if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        };
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code: ServiceExitCode::Win32(exit_code),
+            checkpoint: 0,
+            wait_hint: Duration::from_secs(30),
+            process_id: None,
+        }
+    }
+
+    fn run_service() -> Result<(), Box<dyn std::error::Error>> {
+        let Some((config, runtime)) = STARTUP.lock().unwrap_or_else(|e| e.into_inner()).take()
+        else {
+            return Err("service started twice".into());
+        };
+
+        runtime.block_on(async move {
+            let server = ProxyServer::new(config)?;
+            let shutdown = server.shutdown.clone();
+            let status_handle =
+                service_control_handler::register(SERVICE_NAME, move |control| match control {
+                    ServiceControl::Stop | ServiceControl::Shutdown => {
+                        log::info!("service stop requested, draining in-flight requests");
+                        shutdown.cancel();
+                        ServiceControlHandlerResult::NoError
+                    }
+                    ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+                    _ => ServiceControlHandlerResult::NotImplemented,
+                })?;
+            status_handle.set_service_status(status(ServiceState::Running, 0))?;
+
+            let result = server.run().await;
+            let exit_code = if result.is_ok() { 0 } else { 1 };
+            status_handle.set_service_status(status(ServiceState::Stopped, exit_code))?;
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/proxy_service.rs"]
+mod tests;
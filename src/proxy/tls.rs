@@ -0,0 +1,117 @@
+//! HTTPS termination for `--tls-cert` / `--tls-key`.
+//!
+//! Handshakes run on their own tasks and finished streams are handed to
+//! `axum::serve` through a channel, so one slow or stalled client cannot hold
+//! up the accept loop for everyone else.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::crypto::aws_lc_rs;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::server::TlsStream;
+
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const TLS_ACCEPT_BACKLOG: usize = 64;
+
+/// Build a TLS acceptor from a PEM certificate chain and PEM private key.
+pub fn load_tls_acceptor(cert_path: &Path, key_path: &Path) -> Result<TlsAcceptor, String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| {
+            format!(
+                "failed to read TLS certificate {}: {}",
+                cert_path.display(),
+                e
+            )
+        })?;
+    if certs.is_empty() {
+        return Err(format!("no certificates found in {}", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("failed to read TLS key {}: {}", key_path.display(), e))?;
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("failed to configure TLS: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("invalid TLS certificate/key pair: {}", e))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// A TCP listener that yields only connections whose TLS handshake succeeded.
+pub struct TlsListener {
+    handshaken: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, acceptor: TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (tx, handshaken) = mpsc::channel(TLS_ACCEPT_BACKLOG);
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = tx.closed() => break,
+                    accepted = listener.accept() => match accepted {
+                        Ok(conn) => conn,
+                        Err(e) => {
+                            log::warn!("tls: accept failed: {}", e);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            continue;
+                        }
+                    },
+                };
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                    {
+                        Ok(Ok(tls_stream)) => {
+                            let _ = tx.send((tls_stream, peer)).await;
+                        }
+                        Ok(Err(e)) => log::debug!("tls: handshake with {} failed: {}", peer, e),
+                        Err(_) => log::debug!("tls: handshake with {} timed out", peer),
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            handshaken,
+            local_addr,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.handshaken.recv().await {
+            Some(conn) => conn,
+            // The accept task only exits once this receiver is gone.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/proxy_tls.rs"]
+mod tests;
@@ -1,14 +1,49 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
 
 use crate::error::ProxyError;
 
+/// Size and last use of one stored blob, as kept in `index.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobRecord {
+    pub size: u64,
+    /// Last upload, `HEAD` or read of the blob; eviction drops the oldest.
+    pub last_access: DateTime<Utc>,
+}
+
+/// How often last-use times recorded by reads are written to `index.json`.
+/// Uploads, deletes and evictions write it straight away.
+pub const BLOB_INDEX_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// One stored blob as `GET /api/proxy/blobs` lists it.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobInfo {
+    pub digest: String,
+    pub size: u64,
+    pub last_access: DateTime<Utc>,
+}
+
+/// Content-addressed uploads from `/api/blobs`, with a persisted index of
+/// their sizes and last use. With a total limit set, uploads that push the
+/// store over it evict the least recently used blobs no alias refers to.
 pub struct BlobStore {
     base_dir: PathBuf,
+    index: RwLock<HashMap<String, BlobRecord>>,
+    /// Set by reads that moved a `last_access` since `index.json` was last
+    /// written.
+    dirty: AtomicBool,
+    /// `--blob-max-total-mb` in bytes; 0 = unlimited.
+    max_total_bytes: u64,
 }
 
 impl BlobStore {
@@ -20,10 +55,28 @@ impl BlobStore {
                 e
             ))
         })?;
-        Ok(Self { base_dir: dir })
+        let index = load_index(&dir);
+        Ok(Self {
+            base_dir: dir,
+            index: RwLock::new(index),
+            dirty: AtomicBool::new(false),
+            max_total_bytes: 0,
+        })
     }
 
-    fn validated_blob_path(&self, digest: &str) -> Result<PathBuf, ProxyError> {
+    /// Evict unreferenced blobs once the store holds more than `bytes`
+    /// (0 = unlimited).
+    pub fn with_max_total_bytes(mut self, bytes: u64) -> Self {
+        self.max_total_bytes = bytes;
+        self
+    }
+
+    pub fn max_total_bytes(&self) -> u64 {
+        self.max_total_bytes
+    }
+
+    /// `digest` checked and normalised to its index key and file path.
+    fn validated_blob(&self, digest: &str) -> Result<(String, PathBuf), ProxyError> {
         let Some((algo, hex)) = digest.split_once(':') else {
             return Err(ProxyError::bad_request(
                 "invalid digest format. Expected algo:hex (e.g. sha256:abc)",
@@ -35,24 +88,111 @@ impl BlobStore {
         if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
             return Err(ProxyError::bad_request("invalid sha256 digest"));
         }
-        Ok(self.base_dir.join(algo).join(hex))
+        // Hex is case-insensitive; store under the lowercase form so
+        // `sha256:ABC…` and `sha256:abc…` name the same blob.
+        let hex = hex.to_ascii_lowercase();
+        let path = self.base_dir.join(algo).join(&hex);
+        Ok((format!("{}:{}", algo, hex), path))
+    }
+
+    fn validated_blob_path(&self, digest: &str) -> Result<PathBuf, ProxyError> {
+        self.validated_blob(digest).map(|(_, path)| path)
+    }
+
+    /// Every indexed blob, sorted by digest.
+    pub async fn list(&self) -> Vec<BlobInfo> {
+        let guard = self.index.read().await;
+        let mut blobs: Vec<_> = guard
+            .iter()
+            .map(|(digest, record)| BlobInfo {
+                digest: digest.clone(),
+                size: record.size,
+                last_access: record.last_access,
+            })
+            .collect();
+        blobs.sort_by(|a, b| a.digest.cmp(&b.digest));
+        blobs
+    }
+
+    /// Bytes held by every indexed blob.
+    pub async fn total_bytes(&self) -> u64 {
+        total_size(&*self.index.read().await)
+    }
+
+    /// Mark a blob as used now, so eviction takes it last. Unknown digests
+    /// are ignored. Only the in-memory index changes; [`Self::flush`] or the
+    /// next upload, delete or eviction writes it out.
+    pub async fn touch(&self, digest: &str) {
+        let Ok((key, _)) = self.validated_blob(digest) else {
+            return;
+        };
+        let mut guard = self.index.write().await;
+        let Some(record) = guard.get_mut(&key) else {
+            return;
+        };
+        record.last_access = Utc::now();
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Write `index.json` if reads have changed it since the last write.
+    pub async fn flush(&self) {
+        if !self.dirty.load(Ordering::Relaxed) {
+            return;
+        }
+        let guard = self.index.read().await;
+        self.persist_locked(&guard).await;
     }
 
     pub async fn exists(&self, digest: &str) -> Result<bool, ProxyError> {
+        Ok(self.size(digest).await?.is_some())
+    }
+
+    /// Size in bytes of a stored blob, or `None` when it does not exist.
+    pub async fn size(&self, digest: &str) -> Result<Option<u64>, ProxyError> {
         let path = self.validated_blob_path(digest)?;
-        tokio::fs::try_exists(path).await.map_err(|e| {
-            ProxyError::internal_server_error(&format!(
+        match fs::metadata(&path).await {
+            Ok(meta) => Ok(Some(meta.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ProxyError::internal_server_error(&format!(
                 "failed to verify blob existence for {}: {}",
                 digest, e
-            ))
-        })
+            ))),
+        }
+    }
+
+    /// A stored blob opened for reading, with its size; `None` when it does
+    /// not exist.
+    pub async fn open(&self, digest: &str) -> Result<Option<(fs::File, u64)>, ProxyError> {
+        let path = self.validated_blob_path(digest)?;
+        let file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(ProxyError::internal_server_error(&format!(
+                    "failed to open blob {}: {}",
+                    digest, e
+                )));
+            }
+        };
+        let size = file
+            .metadata()
+            .await
+            .map_err(|e| {
+                ProxyError::internal_server_error(&format!(
+                    "failed to read blob metadata for {}: {}",
+                    digest, e
+                ))
+            })?
+            .len();
+        self.touch(digest).await;
+        Ok(Some((file, size)))
     }
 
     pub async fn save_stream<S>(&self, digest: &str, mut stream: S) -> Result<(), ProxyError>
     where
         S: Stream<Item = Result<bytes::Bytes, axum::Error>> + Unpin,
     {
-        let final_path = self.validated_blob_path(digest)?;
+        let (key, final_path) = self.validated_blob(digest)?;
         let expected_hex = digest.split_once(':').map(|(_, h)| h).unwrap();
 
         if let Some(parent) = final_path.parent() {
@@ -74,23 +214,32 @@ impl BlobStore {
         let mut hasher = Sha256::new();
         let mut total_bytes = 0u64;
 
-        while let Some(chunk_result) = stream.next().await {
-            let chunk = chunk_result.map_err(|e| {
-                ProxyError::internal_server_error(&format!("blob upload error: {}", e))
-            })?;
-            hasher.update(&chunk);
-            file.write_all(&chunk).await.map_err(|e| {
-                ProxyError::internal_server_error(&format!("failed writing blob chunk: {}", e))
-            })?;
-            total_bytes = total_bytes.saturating_add(chunk.len() as u64);
+        let written: Result<(), ProxyError> = async {
+            while let Some(chunk_result) = stream.next().await {
+                let chunk = chunk_result.map_err(|e| {
+                    ProxyError::internal_server_error(&format!("blob upload error: {}", e))
+                })?;
+                hasher.update(&chunk);
+                file.write_all(&chunk).await.map_err(|e| {
+                    ProxyError::internal_server_error(&format!("failed writing blob chunk: {}", e))
+                })?;
+                total_bytes = total_bytes.saturating_add(chunk.len() as u64);
+            }
+            file.flush().await.map_err(|e| {
+                ProxyError::internal_server_error(&format!("failed to flush blob data: {}", e))
+            })
         }
-
-        file.flush().await.map_err(|e| {
-            ProxyError::internal_server_error(&format!("failed to flush blob data: {}", e))
-        })?;
+        .await;
+        // A broken upload must not leave a partial temp file behind.
+        if let Err(e) = written {
+            drop(file);
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e);
+        }
+        drop(file);
 
         let actual_hex = hex::encode(hasher.finalize());
-        if actual_hex != expected_hex {
+        if !actual_hex.eq_ignore_ascii_case(expected_hex) {
             let _ = fs::remove_file(&tmp_path).await;
             return Err(ProxyError::bad_request(&format!(
                 "digest mismatch. Expected {}, computed {}",
@@ -103,8 +252,198 @@ impl BlobStore {
         })?;
 
         log::info!("stored blob {} ({} bytes)", digest, total_bytes);
+        let mut guard = self.index.write().await;
+        guard.insert(
+            key,
+            BlobRecord {
+                size: total_bytes,
+                last_access: Utc::now(),
+            },
+        );
+        self.persist_locked(&guard).await;
         Ok(())
     }
+
+    /// Delete one blob, returning its size; 404 when it is not stored.
+    pub async fn remove(&self, digest: &str) -> Result<u64, ProxyError> {
+        let (key, path) = self.validated_blob(digest)?;
+        let mut guard = self.index.write().await;
+        let size = match fs::remove_file(&path).await {
+            Ok(()) => guard.get(&key).map(|record| record.size).unwrap_or(0),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if guard.remove(&key).is_some() {
+                    self.persist_locked(&guard).await;
+                }
+                return Err(ProxyError::not_found(&format!("blob {} not found", digest)));
+            }
+            Err(e) => {
+                return Err(ProxyError::internal_server_error(&format!(
+                    "failed to delete blob {}: {}",
+                    digest, e
+                )));
+            }
+        };
+        guard.remove(&key);
+        self.persist_locked(&guard).await;
+        Ok(size)
+    }
+
+    /// Bring the store back under its limit by deleting the least recently
+    /// used blobs, skipping `keep` (the upload that triggered this) and every
+    /// digest in `referenced`. Returns the evicted digests. Failures are
+    /// logged and the blob is left for the next pass. Nothing is evicted when
+    /// `keep` alone is over the limit: the store couldn't get under it anyway.
+    pub async fn evict_over_limit(&self, keep: &str, referenced: &HashSet<String>) -> Vec<String> {
+        if self.max_total_bytes == 0 {
+            return Vec::new();
+        }
+        let keep = self
+            .validated_blob(keep)
+            .map(|(key, _)| key)
+            .unwrap_or_default();
+        let mut guard = self.index.write().await;
+        let mut total = total_size(&guard);
+        if total <= self.max_total_bytes {
+            return Vec::new();
+        }
+        if let Some(record) = guard.get(&keep)
+            && record.size > self.max_total_bytes
+        {
+            log::warn!(
+                "blob gc: {} alone ({} bytes) is over the {} byte limit; evicting nothing",
+                keep,
+                record.size,
+                self.max_total_bytes
+            );
+            return Vec::new();
+        }
+
+        let mut candidates: Vec<_> = guard
+            .iter()
+            .filter(|(digest, _)| **digest != keep && !referenced.contains(*digest))
+            .map(|(digest, record)| (digest.clone(), record.clone()))
+            .collect();
+        candidates.sort_by_key(|(_, record)| record.last_access);
+
+        let mut evicted = Vec::new();
+        for (digest, record) in candidates {
+            if total <= self.max_total_bytes {
+                break;
+            }
+            let Ok((_, path)) = self.validated_blob(&digest) else {
+                continue;
+            };
+            match fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    log::warn!("blob gc: failed to evict {}: {}", digest, e);
+                    continue;
+                }
+            }
+            log::info!(
+                "blob gc: evicted {} ({} bytes, last used {})",
+                digest,
+                record.size,
+                record.last_access.to_rfc3339()
+            );
+            guard.remove(&digest);
+            total = total.saturating_sub(record.size);
+            evicted.push(digest);
+        }
+
+        if total > self.max_total_bytes {
+            log::warn!(
+                "blob gc: {} bytes stored, over the {} byte limit; the rest are referenced or just uploaded",
+                total,
+                self.max_total_bytes
+            );
+        }
+        if !evicted.is_empty() {
+            self.persist_locked(&guard).await;
+        }
+        evicted
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.base_dir.join(INDEX_FILE)
+    }
+
+    /// Rewrite `index.json`. A failed write is logged, not surfaced: the
+    /// index is rebuilt from the files on the next start.
+    async fn persist_locked(&self, index: &HashMap<String, BlobRecord>) {
+        self.dirty.store(false, Ordering::Relaxed);
+        let path = self.index_path();
+        let tmp_path = path.with_extension("tmp");
+        let written = async {
+            let data = serde_json::to_vec_pretty(index).map_err(std::io::Error::from)?;
+            fs::write(&tmp_path, data).await?;
+            fs::rename(&tmp_path, &path).await
+        }
+        .await;
+        if let Err(e) = written {
+            log::warn!("blob index: failed to write {}: {}", path.display(), e);
+        }
+    }
+}
+
+const INDEX_FILE: &str = "index.json";
+
+fn total_size(index: &HashMap<String, BlobRecord>) -> u64 {
+    index
+        .values()
+        .fold(0u64, |total, record| total.saturating_add(record.size))
+}
+
+/// The stored index reconciled with the blob files on disk: entries whose
+/// file is gone are dropped, files the index misses (written by an older
+/// build, or before a crash) are added with their mtime as last access, and
+/// sizes are taken from the files.
+fn load_index(base_dir: &Path) -> HashMap<String, BlobRecord> {
+    let path = base_dir.join(INDEX_FILE);
+    let mut stored: HashMap<String, BlobRecord> = match std::fs::read(&path) {
+        Ok(bytes) if !bytes.is_empty() => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            log::warn!(
+                "blob index {} is unreadable ({}); rebuilding it from the stored blobs",
+                path.display(),
+                e
+            );
+            HashMap::new()
+        }),
+        _ => HashMap::new(),
+    };
+
+    let mut index = HashMap::new();
+    let Ok(entries) = std::fs::read_dir(base_dir.join("sha256")) else {
+        return index;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(hex) = name.to_str() else {
+            continue;
+        };
+        // Upload temp files are `<hex>.<pid>.tmp`; only finished blobs count.
+        if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        let digest = format!("sha256:{}", hex.to_ascii_lowercase());
+        let last_access = stored
+            .remove(&digest)
+            .map(|record| record.last_access)
+            .or_else(|| meta.modified().ok().map(DateTime::<Utc>::from))
+            .unwrap_or_else(Utc::now);
+        index.insert(
+            digest,
+            BlobRecord {
+                size: meta.len(),
+                last_access,
+            },
+        );
+    }
+    index
 }
 
 #[cfg(test)]
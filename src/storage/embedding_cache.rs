@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::future::Cache;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::error::ProxyError;
+use crate::lmstudio::response::ResponseTransformer;
+
+/// One input's vector is only reused for the same model and output size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EmbeddingKey {
+    model: String,
+    dimensions: Option<u64>,
+    digest: [u8; 32],
+}
+
+/// Embedding vectors already returned by LM Studio (`--embedding-cache-size`).
+///
+/// Keyed per input string, so a batch that repeats most of an earlier one
+/// sends only the new strings upstream. Vectors are stored as LM Studio
+/// returned them; `normalize` is applied afterwards, per request.
+pub struct EmbeddingCache {
+    vectors: Cache<EmbeddingKey, Arc<Value>>,
+}
+
+/// The cached vectors for one request's inputs, and the keys to store the
+/// missing ones under once LM Studio has embedded them.
+pub struct EmbeddingLookup {
+    keys: Vec<EmbeddingKey>,
+    texts: Vec<String>,
+    cached: Vec<Option<Arc<Value>>>,
+    single: bool,
+}
+
+impl EmbeddingLookup {
+    /// The input to send LM Studio: the uncached strings only, or `None`
+    /// when every vector is cached. A single-string request stays a string.
+    pub fn missing_input(&self) -> Option<Value> {
+        let missing: Vec<&String> = self
+            .texts
+            .iter()
+            .zip(&self.cached)
+            .filter(|(_, cached)| cached.is_none())
+            .map(|(text, _)| text)
+            .collect();
+        match missing.as_slice() {
+            [] => None,
+            [text] if self.single => Some(json!(text)),
+            _ => Some(json!(missing)),
+        }
+    }
+
+    fn hits(&self) -> usize {
+        self.cached.iter().filter(|c| c.is_some()).count()
+    }
+}
+
+impl EmbeddingCache {
+    pub fn new(max_entries: u64, ttl: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            vectors: Cache::builder()
+                .max_capacity(max_entries)
+                .time_to_live(ttl)
+                .build(),
+        })
+    }
+
+    /// Cached vectors for `input` (a string or an array of strings).
+    /// `None` for any other input, which bypasses the cache.
+    pub async fn lookup(
+        &self,
+        model: &str,
+        dimensions: Option<u64>,
+        input: &Value,
+    ) -> Option<EmbeddingLookup> {
+        let (texts, single) = match input {
+            Value::String(text) => (vec![text.clone()], true),
+            Value::Array(items) => (
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()?,
+                false,
+            ),
+            _ => return None,
+        };
+        let keys: Vec<EmbeddingKey> = texts
+            .iter()
+            .map(|text| EmbeddingKey {
+                model: model.to_string(),
+                dimensions,
+                digest: Sha256::digest(text.as_bytes()).into(),
+            })
+            .collect();
+        let mut cached = Vec::with_capacity(keys.len());
+        for key in &keys {
+            cached.push(self.vectors.get(key).await);
+        }
+        Some(EmbeddingLookup {
+            keys,
+            texts,
+            cached,
+            single,
+        })
+    }
+
+    /// Merge LM Studio's response for [`EmbeddingLookup::missing_input`] with
+    /// the cached vectors into one OpenAI-style response covering every
+    /// input in order, and cache the new vectors. `usage` is LM Studio's
+    /// (tokens actually processed), zero when nothing was sent.
+    pub async fn complete(
+        &self,
+        lookup: EmbeddingLookup,
+        upstream: Option<Value>,
+    ) -> Result<Value, ProxyError> {
+        let hits = lookup.hits();
+        let total = lookup.keys.len();
+        let model = lookup.keys.first().map(|key| key.model.clone());
+        let mut fresh = upstream
+            .as_ref()
+            .map(ResponseTransformer::extract_embeddings)
+            .unwrap_or_default()
+            .into_iter();
+        if fresh.len() != total - hits {
+            return Err(ProxyError::bad_gateway(&format!(
+                "LM Studio returned {} embeddings for {} inputs",
+                fresh.len(),
+                total - hits
+            )));
+        }
+
+        let mut data = Vec::with_capacity(total);
+        for (index, (key, cached)) in lookup.keys.into_iter().zip(lookup.cached).enumerate() {
+            let vector = match cached {
+                Some(vector) => vector,
+                None => {
+                    let vector = Arc::new(fresh.next().unwrap_or_default());
+                    self.vectors.insert(key, vector.clone()).await;
+                    vector
+                }
+            };
+            data.push(json!({"object": "embedding", "index": index, "embedding": vector.as_ref()}));
+        }
+        if hits > 0 {
+            log::info!(
+                "embeddings cache: {}/{} inputs served from cache",
+                hits,
+                total
+            );
+        }
+
+        let mut response = upstream.unwrap_or_else(|| {
+            json!({
+                "object": "list",
+                "model": model,
+                "usage": {"prompt_tokens": 0, "total_tokens": 0},
+            })
+        });
+        if let Some(obj) = response.as_object_mut() {
+            obj.insert("data".to_string(), Value::Array(data));
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/storage_embedding_cache.rs"]
+mod tests;
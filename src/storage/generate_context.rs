@@ -0,0 +1,149 @@
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use moka::future::Cache;
+use serde_json::{Value, json};
+
+/// First element of every `context` array this proxy issues ("OLPX"), so a
+/// real Ollama token array sent by a client is never mistaken for a key.
+const CONTEXT_MARKER: u32 = 0x4F4C_5058;
+
+/// Oldest exchanges are dropped beyond this, bounding prompt growth.
+const MAX_EXCHANGES: usize = 16;
+
+const MAX_CONVERSATIONS: u64 = 10_000;
+
+/// One `/api/generate` prompt and the text the model produced for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateExchange {
+    pub prompt: String,
+    pub response: String,
+}
+
+/// Proxy-managed stand-in for Ollama's generate `context` token array.
+///
+/// LM Studio exposes no token ids, so with `--emulate-generate-context` the
+/// proxy returns `[marker, key_hi, key_lo]` instead and keeps the exchanges
+/// behind that key in memory. A request carrying the array gets them
+/// prepended to its prompt. Entries expire after
+/// `--generate-context-ttl-seconds`; an expired or unknown key is treated as
+/// a fresh conversation.
+pub struct GenerateContextStore {
+    conversations: Cache<u64, Arc<Vec<GenerateExchange>>>,
+    counter: AtomicU64,
+    hasher: RandomState,
+}
+
+impl GenerateContextStore {
+    pub fn new(ttl: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            conversations: Cache::builder()
+                .max_capacity(MAX_CONVERSATIONS)
+                .time_to_live(ttl)
+                .build(),
+            counter: AtomicU64::new(0),
+            hasher: RandomState::new(),
+        })
+    }
+
+    /// Exchanges behind a request's `context`; empty when it is absent, not
+    /// one of ours, or expired.
+    pub async fn history(&self, context: Option<&Value>) -> Arc<Vec<GenerateExchange>> {
+        let Some(key) = context.and_then(decode_context) else {
+            return Arc::default();
+        };
+        match self.conversations.get(&key).await {
+            Some(history) => history,
+            None => {
+                log::debug!("generate context: unknown or expired key, starting fresh");
+                Arc::default()
+            }
+        }
+    }
+
+    /// Store `history` plus the new exchange under a fresh key and return
+    /// the `context` array that refers to it.
+    pub async fn remember(
+        &self,
+        history: &[GenerateExchange],
+        prompt: &str,
+        response: &str,
+    ) -> Value {
+        let keep = history.len().min(MAX_EXCHANGES - 1);
+        let mut exchanges = history[history.len() - keep..].to_vec();
+        exchanges.push(GenerateExchange {
+            prompt: prompt.to_string(),
+            response: response.to_string(),
+        });
+
+        let key = self
+            .hasher
+            .hash_one(self.counter.fetch_add(1, Ordering::Relaxed));
+        self.conversations.insert(key, Arc::new(exchanges)).await;
+        encode_context(key)
+    }
+}
+
+/// The pending exchange of one generate request, completed once the
+/// response text is known.
+pub struct GenerateContextTurn {
+    pub store: Arc<GenerateContextStore>,
+    pub history: Arc<Vec<GenerateExchange>>,
+    pub prompt: String,
+}
+
+impl GenerateContextTurn {
+    /// Record the exchange and return the `context` for the response.
+    pub async fn finish(self, response: &str) -> Value {
+        self.store
+            .remember(&self.history, &self.prompt, response)
+            .await
+    }
+}
+
+/// Raw-completion prompt: earlier prompts and responses verbatim, then
+/// `prompt`, as Ollama's token context would replay them.
+pub fn prompt_with_history(history: &[GenerateExchange], prompt: &str) -> String {
+    let mut full = String::new();
+    for exchange in history {
+        full.push_str(&exchange.prompt);
+        full.push_str(&exchange.response);
+    }
+    full.push_str(prompt);
+    full
+}
+
+/// Earlier exchanges as alternating user/assistant chat turns.
+pub fn history_messages(history: &[GenerateExchange]) -> Vec<Value> {
+    history
+        .iter()
+        .flat_map(|exchange| {
+            [
+                json!({"role": "user", "content": exchange.prompt}),
+                json!({"role": "assistant", "content": exchange.response}),
+            ]
+        })
+        .collect()
+}
+
+fn encode_context(key: u64) -> Value {
+    json!([CONTEXT_MARKER, (key >> 32) as u32, key as u32])
+}
+
+fn decode_context(context: &Value) -> Option<u64> {
+    let [marker, hi, lo] = context.as_array()?.as_slice() else {
+        return None;
+    };
+    if marker.as_u64()? != u64::from(CONTEXT_MARKER) {
+        return None;
+    }
+    let hi = u32::try_from(hi.as_u64()?).ok()?;
+    let lo = u32::try_from(lo.as_u64()?).ok()?;
+    Some((u64::from(hi) << 32) | u64::from(lo))
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/storage_generate_context.rs"]
+mod tests;
@@ -1,5 +1,13 @@
 pub mod blob;
+pub mod embedding_cache;
+pub mod generate_context;
+pub mod model_pins;
+pub mod model_timestamps;
 pub mod virtual_models;
 
-pub use blob::BlobStore;
+pub use blob::{BlobInfo, BlobStore};
+pub use embedding_cache::EmbeddingCache;
+pub use generate_context::{GenerateContextStore, GenerateContextTurn};
+pub use model_pins::{ModelPin, ModelPinStore};
+pub use model_timestamps::ModelTimestampStore;
 pub use virtual_models::{VirtualModelEntry, VirtualModelStore};
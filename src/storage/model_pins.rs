@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::error::ProxyError;
+use crate::model::clean_model_name;
+
+/// A name steered to one LM Studio model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelPin {
+    pub name: String,
+    pub target: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Persisted pins keyed by lowercased, `:latest`-stripped name.
+///
+/// A pin is not a model: it never appears in `/api/tags`, it only decides
+/// which LM Studio id an existing name resolves to when several variants
+/// (say a Q4 and a Q8 of the same model) would otherwise compete on score.
+pub struct ModelPinStore {
+    path: PathBuf,
+    entries: RwLock<HashMap<String, ModelPin>>,
+}
+
+impl ModelPinStore {
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self, ProxyError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ProxyError::internal_server_error(&format!(
+                    "failed to create state directory: {}",
+                    e
+                ))
+            })?;
+        }
+
+        let map = match std::fs::read(&path) {
+            Ok(bytes) if !bytes.is_empty() => match serde_json::from_slice(&bytes) {
+                Ok(map) => map,
+                Err(e) => {
+                    log::warn!(
+                        "{} is corrupt ({}); starting with no pins",
+                        path.display(),
+                        e
+                    );
+                    HashMap::new()
+                }
+            },
+            Ok(_) => HashMap::new(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(ProxyError::internal_server_error(&format!(
+                    "failed to read {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+
+        Ok(Self {
+            path,
+            entries: RwLock::new(map),
+        })
+    }
+
+    fn key(name: &str) -> String {
+        clean_model_name(name).to_lowercase()
+    }
+
+    /// The LM Studio id `name` is pinned to, if any.
+    pub async fn target_for(&self, name: &str) -> Option<String> {
+        let guard = self.entries.read().await;
+        guard.get(&Self::key(name)).map(|pin| pin.target.clone())
+    }
+
+    /// Pin `name` to `target`, replacing any earlier pin for the name.
+    pub async fn set(&self, name: &str, target: &str) -> Result<ModelPin, ProxyError> {
+        let pin = ModelPin {
+            name: clean_model_name(name).to_string(),
+            target: target.to_string(),
+            created_at: Utc::now(),
+        };
+        let mut guard = self.entries.write().await;
+        guard.insert(Self::key(name), pin.clone());
+        self.persist_locked(&guard).await?;
+        Ok(pin)
+    }
+
+    pub async fn remove(&self, name: &str) -> Result<ModelPin, ProxyError> {
+        let mut guard = self.entries.write().await;
+        let removed = guard
+            .remove(&Self::key(name))
+            .ok_or_else(|| ProxyError::not_found(&format!("no pin for '{}'", name)))?;
+        self.persist_locked(&guard).await?;
+        Ok(removed)
+    }
+
+    /// Every pin, sorted by name.
+    pub async fn list(&self) -> Vec<ModelPin> {
+        let guard = self.entries.read().await;
+        let mut pins: Vec<ModelPin> = guard.values().cloned().collect();
+        pins.sort_by(|a, b| a.name.cmp(&b.name));
+        pins
+    }
+
+    async fn persist_locked(&self, entries: &HashMap<String, ModelPin>) -> Result<(), ProxyError> {
+        let tmp_path = self.path.with_extension("tmp");
+        let data = serde_json::to_vec_pretty(entries).map_err(|e| {
+            ProxyError::internal_server_error(&format!("failed to serialize pins: {}", e))
+        })?;
+        fs::write(&tmp_path, data).await.map_err(|e| {
+            ProxyError::internal_server_error(&format!(
+                "failed to write {}: {}",
+                tmp_path.display(),
+                e
+            ))
+        })?;
+        fs::rename(&tmp_path, &self.path).await.map_err(|e| {
+            ProxyError::internal_server_error(&format!(
+                "failed to atomic write {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/storage_model_pins.rs"]
+mod tests;
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::error::ProxyError;
+use crate::model::types::ModelInfo;
+
+/// When the proxy first saw a model in its current form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeenModel {
+    pub digest: String,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// Persisted first-seen times keyed by LM Studio model id.
+///
+/// LM Studio reports no per-model mtime, so `/api/tags` uses the moment the
+/// proxy first listed a model as its `modified_at`. The stamp is kept until the
+/// model's digest changes (re-downloaded, re-quantized or republished) or the
+/// model is deleted, so clients that poll the list don't see every model as
+/// freshly modified and the file doesn't grow with every model ever listed.
+pub struct ModelTimestampStore {
+    path: PathBuf,
+    entries: RwLock<HashMap<String, SeenModel>>,
+}
+
+impl ModelTimestampStore {
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self, ProxyError> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ProxyError::internal_server_error(&format!(
+                    "failed to create state directory: {}",
+                    e
+                ))
+            })?;
+        }
+
+        let map = match std::fs::read(&path) {
+            Ok(bytes) if !bytes.is_empty() => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Ok(_) => HashMap::new(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                return Err(ProxyError::internal_server_error(&format!(
+                    "failed to read {}: {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+
+        Ok(Self {
+            path,
+            entries: RwLock::new(map),
+        })
+    }
+
+    /// `modified_at` for each of `models`, keyed by id. `models` is LM Studio's
+    /// whole list: models not seen before, or whose digest differs from the
+    /// recorded one, are stamped now, models no longer listed are forgotten,
+    /// and the file is rewritten. A failed write is logged, not surfaced: the
+    /// listing is still correct for this process, only the next restart
+    /// re-stamps.
+    pub async fn observe(&self, models: &[ModelInfo]) -> HashMap<String, DateTime<Utc>> {
+        {
+            let guard = self.entries.read().await;
+            if guard.len() == models.len()
+                && let Some(stamps) = Self::lookup_all(&guard, models)
+            {
+                return stamps;
+            }
+        }
+
+        let mut guard = self.entries.write().await;
+        let now = Utc::now();
+        let before = guard.len();
+        guard.retain(|id, _| models.iter().any(|m| &m.id == id));
+        let mut changed = guard.len() != before;
+        for model in models {
+            let digest = model.digest();
+            let current = guard
+                .get(&model.id)
+                .is_some_and(|seen| seen.digest == digest);
+            if !current {
+                guard.insert(
+                    model.id.clone(),
+                    SeenModel {
+                        digest,
+                        first_seen: now,
+                    },
+                );
+                changed = true;
+            }
+        }
+        if changed && let Err(e) = self.persist_locked(&guard).await {
+            log::warn!("model timestamps: {}", e.message);
+        }
+
+        models
+            .iter()
+            .filter_map(|m| guard.get(&m.id).map(|seen| (m.id.clone(), seen.first_seen)))
+            .collect()
+    }
+
+    /// Every stamp, or `None` as soon as one model is unknown or changed.
+    fn lookup_all(
+        entries: &HashMap<String, SeenModel>,
+        models: &[ModelInfo],
+    ) -> Option<HashMap<String, DateTime<Utc>>> {
+        models
+            .iter()
+            .map(|m| {
+                entries
+                    .get(&m.id)
+                    .filter(|seen| seen.digest == m.digest())
+                    .map(|seen| (m.id.clone(), seen.first_seen))
+            })
+            .collect()
+    }
+
+    async fn persist_locked(&self, entries: &HashMap<String, SeenModel>) -> Result<(), ProxyError> {
+        let tmp_path = self.path.with_extension("tmp");
+        let data = serde_json::to_vec_pretty(entries).map_err(|e| {
+            ProxyError::internal_server_error(&format!("failed to serialize timestamps: {}", e))
+        })?;
+        fs::write(&tmp_path, data).await.map_err(|e| {
+            ProxyError::internal_server_error(&format!(
+                "failed to write {}: {}",
+                tmp_path.display(),
+                e
+            ))
+        })?;
+        fs::rename(&tmp_path, &self.path).await.map_err(|e| {
+            ProxyError::internal_server_error(&format!(
+                "failed to atomic write {}: {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "../../tests/unit/storage_model_timestamps.rs"]
+mod tests;
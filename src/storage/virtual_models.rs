@@ -1,6 +1,6 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -25,12 +25,164 @@ pub struct VirtualModelMetadata {
 pub struct VirtualModelEntry {
     pub name: String,
     pub source_model: String,
+    /// LM Studio id captured when the alias was written. For a child alias
+    /// this is only a fallback; the live target comes from `parent_alias`.
     pub target_model_id: String,
+    /// The alias this one was created from, re-resolved on every request so
+    /// repointing the parent carries through to its children.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_alias: Option<String>,
+    /// Digests of `/api/blobs` uploads this alias is built from. The blob
+    /// store never evicts them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blobs: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub metadata: VirtualModelMetadata,
 }
 
+/// Layer request `parameters` over inherited ones key by key, the way a
+/// Modelfile `PARAMETER` line overrides only that parameter of its `FROM`
+/// model. Non-object values on either side replace wholesale.
+fn merge_parameters(base: Option<Value>, overrides: &Value) -> Value {
+    match (base, overrides) {
+        (Some(Value::Object(mut merged)), Value::Object(override_obj)) => {
+            for (key, value) in override_obj {
+                merged.insert(key.clone(), value.clone());
+            }
+            Value::Object(merged)
+        }
+        _ => overrides.clone(),
+    }
+}
+
+/// Longest parent chain followed before resolution gives up.
+const MAX_ALIAS_CHAIN_DEPTH: usize = 8;
+
+/// Follow `parent_alias` links from `start` to the LM Studio id at the root of
+/// the chain. A parent deleted since the child was created ends the walk at
+/// the last snapshot; a loop or an over-long chain is an error.
+fn resolve_chain(
+    entries: &HashMap<String, VirtualModelEntry>,
+    start: &VirtualModelEntry,
+) -> Result<String, ProxyError> {
+    let mut visited = vec![VirtualModelStore::canonical(&start.name).into_owned()];
+    let mut current = start;
+    while let Some(parent_name) = &current.parent_alias {
+        let parent_key = VirtualModelStore::canonical(parent_name).into_owned();
+        if visited.contains(&parent_key) {
+            return Err(ProxyError::bad_request(&format!(
+                "alias '{}' has a cyclic parent chain through '{}'",
+                start.name, parent_name
+            )));
+        }
+        if visited.len() > MAX_ALIAS_CHAIN_DEPTH {
+            return Err(ProxyError::bad_request(&format!(
+                "alias '{}' nests more than {} aliases deep",
+                start.name, MAX_ALIAS_CHAIN_DEPTH
+            )));
+        }
+        let Some(parent) = entries.get(&parent_key) else {
+            break;
+        };
+        visited.push(parent_key);
+        current = parent;
+    }
+    Ok(current.target_model_id.clone())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Entries stored at `path`: empty when the file is missing or empty, `None`
+/// when it exists but does not parse.
+fn read_entries(path: &Path) -> Result<Option<HashMap<String, VirtualModelEntry>>, ProxyError> {
+    if !path.exists() {
+        return Ok(Some(HashMap::new()));
+    }
+    match std::fs::read(path) {
+        Ok(bytes) if bytes.is_empty() => Ok(Some(HashMap::new())),
+        Ok(bytes) => Ok(serde_json::from_slice(&bytes).ok().map(migrate_store)),
+        Err(e) => Err(ProxyError::internal_server_error(&format!(
+            "failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Layout of `virtual_models.json` written by this build. Version 0 is the
+/// original bare `{alias: entry}` map; version 1 wraps it as
+/// `{"version": 1, "models": {...}}`.
+const STORE_VERSION: u64 = 1;
+
+#[derive(Serialize)]
+struct StoreFile<'a> {
+    version: u64,
+    models: &'a HashMap<String, VirtualModelEntry>,
+}
+
+/// Upgrade a parsed store of any known version to the current entries.
+/// A newer version is read as best it can be, with a warning; entries that
+/// still fail to deserialize are skipped rather than failing the load.
+fn migrate_store(file: Value) -> HashMap<String, VirtualModelEntry> {
+    let (version, models) = match file {
+        Value::Object(mut obj) if obj.get("version").is_some_and(Value::is_u64) => {
+            let version = obj.get("version").and_then(Value::as_u64).unwrap_or(0);
+            (version, obj.remove("models").unwrap_or_default())
+        }
+        other => (0, other),
+    };
+    if version > STORE_VERSION {
+        log::warn!(
+            "virtual model store version {} is newer than this build understands ({}); loading what it can",
+            version,
+            STORE_VERSION
+        );
+    }
+    let Value::Object(models) = models else {
+        return HashMap::new();
+    };
+    models
+        .into_iter()
+        .filter_map(|(key, mut entry)| {
+            if version == 0 {
+                upgrade_v0_entry(&key, &mut entry);
+            }
+            match serde_json::from_value(entry) {
+                Ok(entry) => Some((key, entry)),
+                Err(e) => {
+                    log::warn!("skipping unreadable virtual model '{}': {}", key, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Fill in what early stores may lack: `metadata`, `updated_at`, and the
+/// identity fields recoverable from the key. Metadata fields added later
+/// (`adapters`, `messages`, ...) are optional and read as absent.
+fn upgrade_v0_entry(key: &str, entry: &mut Value) {
+    let Some(obj) = entry.as_object_mut() else {
+        return;
+    };
+    obj.entry("name").or_insert_with(|| Value::from(key));
+    if !obj.contains_key("source_model")
+        && let Some(target) = obj.get("target_model_id").cloned()
+    {
+        obj.insert("source_model".to_string(), target);
+    }
+    let now = Value::from(Utc::now().to_rfc3339());
+    let created_at = obj.entry("created_at").or_insert(now).clone();
+    obj.entry("updated_at").or_insert(created_at);
+    obj.entry("metadata")
+        .or_insert_with(|| Value::Object(Default::default()));
+}
+
 pub struct VirtualModelStore {
     path: PathBuf,
     entries: RwLock<HashMap<String, VirtualModelEntry>>,
@@ -52,7 +204,7 @@ impl VirtualModelStore {
         }
 
         if let Some(parameters) = body.get("parameters") {
-            metadata.parameters = Some(parameters.clone());
+            metadata.parameters = Some(merge_parameters(metadata.parameters.take(), parameters));
         }
 
         if let Some(license) = body.get("license") {
@@ -81,22 +233,34 @@ impl VirtualModelStore {
             })?;
         }
 
-        let map = if path.exists() {
-            match std::fs::read(&path) {
-                Ok(bytes) if !bytes.is_empty() => {
-                    serde_json::from_slice(&bytes).unwrap_or_default()
-                }
-                Ok(_) => HashMap::new(),
-                Err(e) => {
-                    return Err(ProxyError::internal_server_error(&format!(
-                        "failed to read {}: {}",
-                        path.display(),
-                        e
-                    )));
+        let map = match read_entries(&path)? {
+            Some(map) => map,
+            None => {
+                let backup = backup_path(&path);
+                match read_entries(&backup) {
+                    Ok(Some(map)) => {
+                        log::warn!(
+                            "{} is corrupt; recovered {} virtual models from {}",
+                            path.display(),
+                            map.len(),
+                            backup.display()
+                        );
+                        // Restore it now, so the next save does not back up
+                        // the corrupt file over the good copy.
+                        if let Err(e) = std::fs::copy(&backup, &path) {
+                            log::warn!("failed to restore {}: {}", path.display(), e);
+                        }
+                        map
+                    }
+                    _ => {
+                        log::warn!(
+                            "{} is corrupt and no usable backup exists; starting with no virtual models",
+                            path.display()
+                        );
+                        HashMap::new()
+                    }
                 }
             }
-        } else {
-            HashMap::new()
         };
 
         Ok(Self {
@@ -120,6 +284,21 @@ impl VirtualModelStore {
         guard.get(key.as_ref()).cloned()
     }
 
+    /// Look up an alias and resolve its parent chain to the LM Studio id it
+    /// currently targets.
+    pub async fn resolve(
+        &self,
+        model_name: &str,
+    ) -> Result<Option<(VirtualModelEntry, String)>, ProxyError> {
+        let key = Self::canonical(model_name);
+        let guard = self.entries.read().await;
+        let Some(entry) = guard.get(key.as_ref()) else {
+            return Ok(None);
+        };
+        let target = resolve_chain(&guard, entry)?;
+        Ok(Some((entry.clone(), target)))
+    }
+
     pub async fn create_alias(
         &self,
         alias: &str,
@@ -141,6 +320,8 @@ impl VirtualModelStore {
             name: alias.to_string(),
             source_model,
             target_model_id,
+            parent_alias: None,
+            blobs: Vec::new(),
             created_at: now,
             updated_at: now,
             metadata,
@@ -158,15 +339,52 @@ impl VirtualModelStore {
         source_model: String,
         target_model_id: String,
         metadata: VirtualModelMetadata,
+    ) -> Result<VirtualModelEntry, ProxyError> {
+        self.upsert_entry(alias, source_model, target_model_id, None, metadata)
+            .await
+    }
+
+    /// Create or overwrite an alias derived from another alias. The parent is
+    /// followed at request time; `target_model_id` is the parent's current
+    /// target, kept as a fallback should the parent be deleted.
+    pub async fn upsert_child_alias(
+        &self,
+        alias: &str,
+        parent_alias: String,
+        target_model_id: String,
+        metadata: VirtualModelMetadata,
+    ) -> Result<VirtualModelEntry, ProxyError> {
+        self.upsert_entry(
+            alias,
+            parent_alias.clone(),
+            target_model_id,
+            Some(parent_alias),
+            metadata,
+        )
+        .await
+    }
+
+    async fn upsert_entry(
+        &self,
+        alias: &str,
+        source_model: String,
+        target_model_id: String,
+        parent_alias: Option<String>,
+        metadata: VirtualModelMetadata,
     ) -> Result<VirtualModelEntry, ProxyError> {
         let alias_key = Self::canonical(alias).into_owned();
         let mut guard = self.entries.write().await;
+        if let Some(parent) = &parent_alias {
+            Self::reject_cycle_locked(&guard, &alias_key, parent)?;
+        }
         let now = Utc::now();
         let created_at = guard.get(&alias_key).map(|e| e.created_at).unwrap_or(now);
         let entry = VirtualModelEntry {
             name: alias.to_string(),
             source_model,
             target_model_id,
+            parent_alias,
+            blobs: Vec::new(),
             created_at,
             updated_at: now,
             metadata,
@@ -176,6 +394,50 @@ impl VirtualModelStore {
         Ok(entry)
     }
 
+    /// Refuse a parent link that would lead back to `alias_key` itself.
+    fn reject_cycle_locked(
+        entries: &HashMap<String, VirtualModelEntry>,
+        alias_key: &str,
+        parent_alias: &str,
+    ) -> Result<(), ProxyError> {
+        let mut next = Some(parent_alias.to_string());
+        for _ in 0..=MAX_ALIAS_CHAIN_DEPTH {
+            let Some(name) = next else {
+                return Ok(());
+            };
+            let key = Self::canonical(&name);
+            if key.as_ref() == alias_key {
+                return Err(ProxyError::bad_request(&format!(
+                    "'{}' cannot be derived from '{}': the alias chain would loop",
+                    alias_key, parent_alias
+                )));
+            }
+            next = entries
+                .get(key.as_ref())
+                .and_then(|entry| entry.parent_alias.clone());
+        }
+        Err(ProxyError::bad_request(&format!(
+            "'{}' would nest more than {} aliases deep",
+            alias_key, MAX_ALIAS_CHAIN_DEPTH
+        )))
+    }
+
+    /// Every alias with `target_model_id` replaced by its chain's current
+    /// target; unresolvable chains keep their stored snapshot.
+    pub async fn list_resolved(&self) -> Vec<VirtualModelEntry> {
+        let guard = self.entries.read().await;
+        guard
+            .values()
+            .map(|entry| {
+                let mut entry = entry.clone();
+                if let Ok(target) = resolve_chain(&guard, &entry) {
+                    entry.target_model_id = target;
+                }
+                entry
+            })
+            .collect()
+    }
+
     pub async fn delete(&self, alias: &str) -> Result<VirtualModelEntry, ProxyError> {
         let alias_key = Self::canonical(alias).into_owned();
         let mut guard = self.entries.write().await;
@@ -191,12 +453,25 @@ impl VirtualModelStore {
         guard.values().cloned().collect()
     }
 
+    /// Every blob digest some alias is built from, lowercased.
+    pub async fn referenced_blobs(&self) -> HashSet<String> {
+        let guard = self.entries.read().await;
+        guard
+            .values()
+            .flat_map(|entry| entry.blobs.iter().map(|d| d.to_ascii_lowercase()))
+            .collect()
+    }
+
     async fn persist_locked(
         &self,
         entries: &HashMap<String, VirtualModelEntry>,
     ) -> Result<(), ProxyError> {
         let tmp_path = self.path.with_extension("tmp");
-        let data = serde_json::to_vec_pretty(entries).map_err(|e| {
+        let file = StoreFile {
+            version: STORE_VERSION,
+            models: entries,
+        };
+        let data = serde_json::to_vec_pretty(&file).map_err(|e| {
             ProxyError::internal_server_error(&format!("failed to serialize store: {}", e))
         })?;
         fs::write(&tmp_path, data).await.map_err(|e| {
@@ -206,6 +481,13 @@ impl VirtualModelStore {
                 e
             ))
         })?;
+        // Keep the previous good file; `load` falls back to it if the primary
+        // is ever found corrupt.
+        if fs::try_exists(&self.path).await.unwrap_or(false)
+            && let Err(e) = fs::copy(&self.path, backup_path(&self.path)).await
+        {
+            log::warn!("failed to back up {}: {}", self.path.display(), e);
+        }
         fs::rename(&tmp_path, &self.path).await.map_err(|e| {
             ProxyError::internal_server_error(&format!(
                 "failed to atomic write {}: {}",
@@ -3,7 +3,11 @@ use std::{collections::BTreeMap, time::Duration};
 use serde_json::{Map, Value, json};
 use tokio::sync::mpsc;
 
-use crate::lmstudio::response::{TimingInfo, convert_tool_calls_to_ollama};
+use crate::config::ReasoningMode;
+use crate::constants::TOKEN_TO_CHAR_RATIO;
+use crate::lmstudio::response::{
+    TimingInfo, convert_tool_calls_to_ollama, extract_stop_reason, to_ollama_logprobs,
+};
 
 #[derive(Default)]
 pub struct ChunkProcessingState {
@@ -28,6 +32,25 @@ impl ChunkProcessingState {
         }
     }
 
+    /// Take the last non-null `finish_reason` across all of a chunk's choices.
+    ///
+    /// Content is read from the first choice only, but completion-style
+    /// streams may report the reason on a later choice of the final chunk; a
+    /// `length` stop must survive into the final chunk's `done_reason`. When
+    /// no choice has carried one yet, LM Studio's `stats.stop_reason` is used.
+    pub fn update_finish_reason_from_chunk(&mut self, chunk: &Value) {
+        if let Some(choices) = chunk.get("choices").and_then(|c| c.as_array()) {
+            choices
+                .iter()
+                .for_each(|choice| self.update_finish_reason(choice));
+        }
+        if self.last_finish_reason.is_none()
+            && let Some(reason) = extract_stop_reason(chunk)
+        {
+            self.last_finish_reason = Some(reason.to_string());
+        }
+    }
+
     pub fn accumulate_tool_calls(&mut self, tool_calls: &[Value]) {
         for (position, tool_call) in tool_calls.iter().enumerate() {
             let index = tool_call_index(tool_call, position);
@@ -60,6 +83,33 @@ pub struct ChoiceDeltaPayload {
     /// `ChunkProcessingState` independently merges fragments for the final
     /// `done:true` chunk — this field does not consume that state.
     pub tool_calls_delta: Option<Value>,
+    /// Token logprobs of this delta in Ollama shape, forwarded on the
+    /// intermediate chunk when the request asked for them.
+    pub logprobs: Option<Value>,
+}
+
+impl ChoiceDeltaPayload {
+    /// Reshape this delta's reasoning text for the selected [`ReasoningMode`].
+    ///
+    /// `Merge` moves it in front of the visible content, `Strip` drops it, and
+    /// `Separate` leaves it in `thinking`. A stripped reasoning-only delta ends
+    /// up empty; callers already skip empty payloads.
+    pub fn apply_reasoning_mode(&mut self, mode: ReasoningMode) {
+        match mode {
+            ReasoningMode::Separate => {}
+            ReasoningMode::Merge => {
+                if !self.thinking.is_empty() {
+                    let thinking = std::mem::take(&mut self.thinking);
+                    self.content.insert_str(0, &thinking);
+                }
+            }
+            ReasoningMode::Strip => self.thinking.clear(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty() && self.thinking.is_empty() && self.tool_calls_delta.is_none()
+    }
 }
 
 fn tool_call_index(tool_call: &Value, position: usize) -> u64 {
@@ -197,10 +247,24 @@ pub fn process_choice_delta(
             content,
             thinking,
             tool_calls_delta,
+            logprobs: delta_logprobs(choice),
         })
     }
 }
 
+fn delta_logprobs(choice: &Value) -> Option<Value> {
+    choice.get("logprobs").and_then(to_ollama_logprobs)
+}
+
+/// Add a delta's logprobs to its Ollama chunk.
+pub fn attach_logprobs(chunk: &mut Value, logprobs: Option<Value>) {
+    if let Some(logprobs) = logprobs
+        && let Some(obj) = chunk.as_object_mut()
+    {
+        obj.insert("logprobs".to_string(), logprobs);
+    }
+}
+
 fn append_stream_content(content_value: &Value, buffer: &mut String) {
     match content_value {
         Value::String(text) => buffer.push_str(text),
@@ -313,6 +377,10 @@ pub fn create_error_chunk(error_message: &str) -> Value {
     json!({ "error": error_message })
 }
 
+/// Stand-in prompt size for a cancelled stream's timings, which only need
+/// to be plausible.
+const CANCELLED_PROMPT_TOKENS_ESTIMATE: u64 = 10;
+
 pub fn create_cancellation_chunk(
     model_ollama_name: &str,
     duration: Duration,
@@ -322,9 +390,11 @@ pub fn create_cancellation_chunk(
 ) -> Value {
     // Ollama's spec only documents `done_reason: stop | length`; "cancelled" is not a value
     // real clients expect. Leave content empty and omit `done_reason` rather than fabricating one.
-    let timing = TimingInfo::from_stream_chunks(
+    let timing = TimingInfo::from_stream(
         duration,
+        CANCELLED_PROMPT_TOKENS_ESTIMATE,
         tokens_generated_estimate,
+        None,
         Some(tokens_generated_estimate),
     );
 
@@ -361,10 +431,62 @@ pub fn create_cancellation_chunk(
     chunk
 }
 
+/// Token counts behind a stream's final chunk: LM Studio's `usage` when a
+/// chunk carried it, otherwise estimates from the prompt and the length of
+/// the streamed text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamTokens {
+    pub prompt_estimate: u64,
+    /// Bytes of content and thinking sent so far.
+    pub output_bytes: usize,
+    pub usage_prompt: Option<u64>,
+    pub usage_completion: Option<u64>,
+}
+
+impl StreamTokens {
+    pub fn new(prompt_estimate: u64) -> Self {
+        Self {
+            prompt_estimate,
+            ..Self::default()
+        }
+    }
+
+    pub fn record_output(&mut self, content: &str, thinking: &str) {
+        self.output_bytes += content.len() + thinking.len();
+    }
+
+    /// Keep a chunk's `usage` block; the last one seen wins.
+    pub fn record_usage(&mut self, chunk: &Value) {
+        let Some(usage) = chunk.get("usage").filter(|u| u.is_object()) else {
+            return;
+        };
+        if let Some(prompt) = usage.get("prompt_tokens").and_then(Value::as_u64) {
+            self.usage_prompt = Some(prompt);
+        }
+        if let Some(completion) = usage.get("completion_tokens").and_then(Value::as_u64) {
+            self.usage_completion = Some(completion);
+        }
+    }
+
+    pub fn output_estimate(&self) -> u64 {
+        (self.output_bytes as f64 * TOKEN_TO_CHAR_RATIO).ceil() as u64
+    }
+
+    pub fn timing(&self, duration: Duration) -> TimingInfo {
+        TimingInfo::from_stream(
+            duration,
+            self.prompt_estimate,
+            self.output_estimate(),
+            self.usage_prompt,
+            self.usage_completion,
+        )
+    }
+}
+
 pub struct FinalChunkParams<'a> {
     pub model_name: &'a str,
     pub duration: Duration,
-    pub chunk_count: u64,
+    pub tokens: StreamTokens,
     pub is_chat: bool,
     pub done_reason: Option<&'a str>,
     /// Accumulated tool_calls to emit in this final chunk.
@@ -373,10 +495,9 @@ pub struct FinalChunkParams<'a> {
 }
 
 pub fn create_final_chunk(params: FinalChunkParams<'_>) -> Value {
-    // LM Studio's SSE chunks carry no `usage` and `stream_options.include_usage`
-    // is not in its supported parameter list, so real per-token timings are unavailable
-    // on the streaming path. Wall-clock heuristics are the only option until upstream adds it.
-    let timing = TimingInfo::from_stream_chunks(params.duration, params.chunk_count, None);
+    // LM Studio only sends `usage` on some builds (on the last chunk); per-token
+    // timings never come, so durations are a wall-clock split by token counts.
+    let timing = params.tokens.timing(params.duration);
 
     let mut chunk = create_ollama_streaming_chunk(
         params.model_name,
@@ -6,6 +6,6 @@ pub mod sse;
 
 pub use response::{create_ndjson_stream_response, is_streaming_request};
 pub use sse::{
-    handle_native_streaming_response, handle_passthrough_streaming_response,
-    handle_streaming_response,
+    NativeStreamingParams, StreamTimeouts, StreamingParams, handle_native_streaming_response,
+    handle_passthrough_streaming_response, handle_streaming_response,
 };
@@ -13,7 +13,7 @@
 
 use serde_json::Value;
 
-use crate::lmstudio::native_chat::native_tool_call_to_openai;
+use crate::lmstudio::native_chat::{native_done_reason_from, native_tool_call_to_openai};
 use crate::streaming::chunks::{ChoiceDeltaPayload, ChunkProcessingState};
 
 /// Outcome of mapping a single native SSE event.
@@ -86,6 +86,7 @@ pub fn map_native_event(
                 content: String::new(),
                 thinking,
                 tool_calls_delta: None,
+                logprobs: None,
             })
         }
         "message.delta" => {
@@ -98,6 +99,7 @@ pub fn map_native_event(
                 content,
                 thinking: String::new(),
                 tool_calls_delta: None,
+                logprobs: None,
             })
         }
         "tool_call.arguments" | "tool_call.success" => {
@@ -114,6 +116,7 @@ pub fn map_native_event(
                 content: String::new(),
                 thinking: String::new(),
                 tool_calls_delta,
+                logprobs: None,
             })
         }
         "error" => NativeEvent::Error(parse_native_error(data)),
@@ -152,18 +155,18 @@ fn parse_native_error(data: &Value) -> NativeStreamError {
 /// Extract `result.stats` from a `chat.end` event.
 ///
 /// The `result` object is cloned out so a caller can run it through the
-/// non-streaming converter if it wants the full aggregated body. `done_reason`
-/// is hardcoded to `"stop"`: the native API exposes no finish-reason (neither
-/// `chat.end` nor the stats block carry one), so `"stop"` is the only honest
-/// value.
+/// non-streaming converter if it wants the full aggregated body. The native
+/// API documents no finish-reason, so `done_reason` is `"stop"` unless the
+/// stats block carries a `stop_reason`.
 pub fn parse_chat_end(data: &Value) -> NativeChatEnd {
     let result = data.get("result").cloned().unwrap_or(Value::Null);
     let stats = result.get("stats").cloned();
+    let done_reason = native_done_reason_from(&result);
 
     NativeChatEnd {
         result,
         stats,
-        done_reason: "stop",
+        done_reason,
     }
 }
 
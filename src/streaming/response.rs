@@ -1,13 +1,15 @@
 use axum::body::Body;
 use axum::response::Response;
+use bytes::Bytes;
+use futures_util::StreamExt;
 use http::StatusCode;
 use serde_json::Value;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
 use crate::constants::{
-    CONTENT_TYPE_SSE, HEADER_ACCESS_CONTROL_ALLOW_HEADERS, HEADER_ACCESS_CONTROL_ALLOW_METHODS,
-    HEADER_ACCESS_CONTROL_ALLOW_ORIGIN, HEADER_CACHE_CONTROL, HEADER_CONNECTION,
+    CONTENT_TYPE_NDJSON, CONTENT_TYPE_SSE, HEADER_ACCEL_BUFFERING, HEADER_CACHE_CONTROL,
+    HEADER_CONNECTION,
 };
 use crate::error::ProxyError;
 
@@ -23,46 +25,64 @@ pub fn is_streaming_request(body: &Value) -> bool {
 }
 
 fn create_generic_streaming_response(
-    rx: mpsc::UnboundedReceiver<Result<bytes::Bytes, std::io::Error>>,
+    body: Body,
     content_type: &str,
     error_message_on_build_fail: &str,
 ) -> Result<Response, ProxyError> {
-    let body = Body::from_stream(UnboundedReceiverStream::new(rx));
-
     Response::builder()
         .status(StatusCode::OK)
         .header("content-type", content_type)
         .header("cache-control", HEADER_CACHE_CONTROL)
         .header("connection", HEADER_CONNECTION)
-        .header(
-            "access-control-allow-origin",
-            HEADER_ACCESS_CONTROL_ALLOW_ORIGIN,
-        )
-        .header(
-            "access-control-allow-methods",
-            HEADER_ACCESS_CONTROL_ALLOW_METHODS,
-        )
-        .header(
-            "access-control-allow-headers",
-            HEADER_ACCESS_CONTROL_ALLOW_HEADERS,
-        )
+        .header("x-accel-buffering", HEADER_ACCEL_BUFFERING)
         .body(body)
         .map_err(|_| ProxyError::internal_server_error(error_message_on_build_fail))
 }
 
+/// Split a chunk holding several NDJSON lines into one chunk per line (each
+/// keeping its `\n`), so every line is its own body frame. A trailing
+/// partial line is passed on as is.
+pub fn split_ndjson_lines(chunk: Bytes) -> Vec<Bytes> {
+    let mut lines = Vec::with_capacity(1);
+    let mut start = 0;
+    for (index, byte) in chunk.iter().enumerate() {
+        if *byte == b'\n' {
+            lines.push(chunk.slice(start..=index));
+            start = index + 1;
+        }
+    }
+    if start < chunk.len() {
+        lines.push(chunk.slice(start..));
+    }
+    lines
+}
+
 pub fn create_streaming_response(
-    rx: mpsc::UnboundedReceiver<Result<bytes::Bytes, std::io::Error>>,
+    rx: mpsc::UnboundedReceiver<Result<Bytes, std::io::Error>>,
     content_type: StreamContentType,
 ) -> Result<Response, ProxyError> {
-    let (content_type_str, error_message) = match content_type {
-        StreamContentType::Ndjson => (
-            "application/x-ndjson; charset=utf-8",
-            "failed to create NDJSON streaming response",
+    let chunks = UnboundedReceiverStream::new(rx);
+    match content_type {
+        StreamContentType::Ndjson => {
+            let lines = chunks.flat_map(|chunk| {
+                let frames: Vec<Result<Bytes, std::io::Error>> = match chunk {
+                    Ok(bytes) => split_ndjson_lines(bytes).into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                futures_util::stream::iter(frames)
+            });
+            create_generic_streaming_response(
+                Body::from_stream(lines),
+                CONTENT_TYPE_NDJSON,
+                "failed to create NDJSON streaming response",
+            )
+        }
+        StreamContentType::Sse => create_generic_streaming_response(
+            Body::from_stream(chunks),
+            CONTENT_TYPE_SSE,
+            "failed to create SSE streaming response",
         ),
-        StreamContentType::Sse => (CONTENT_TYPE_SSE, "failed to create SSE streaming response"),
-    };
-
-    create_generic_streaming_response(rx, content_type_str, error_message)
+    }
 }
 
 pub fn create_ndjson_stream_response(
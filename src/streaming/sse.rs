@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
@@ -7,18 +8,22 @@ use tokio::sync::mpsc;
 use tokio::time::timeout;
 use tokio_util::sync::CancellationToken;
 
-use crate::config::get_runtime_config;
+use crate::config::{ReasoningMode, get_runtime_config};
 use crate::constants::{
-    ERROR_CANCELLED, ERROR_TIMEOUT, LOG_PREFIX_CONN, LOG_PREFIX_SUCCESS, SSE_DATA_PREFIX,
-    SSE_DONE_MESSAGE, SSE_MESSAGE_BOUNDARY,
+    DEFAULT_SSE_KEEPALIVE_SECONDS, DEFAULT_STREAM_TIMEOUT_SECONDS, ERROR_CANCELLED, ERROR_TIMEOUT,
+    LOG_PREFIX_CONN, LOG_PREFIX_SUCCESS, SSE_DATA_PREFIX, SSE_DONE_MESSAGE, SSE_MESSAGE_BOUNDARY,
 };
 use crate::error::ProxyError;
-use crate::lmstudio::response::TimingInfo;
+use crate::http::error::upstream_error;
+use crate::lmstudio::response::{TimingInfo, apply_measured_load};
 use crate::logging::log_timed;
+use crate::model::RuntimeInfoCache;
+use crate::storage::GenerateContextTurn;
 use crate::streaming::chunks::{
-    ChunkProcessingState, FinalChunkParams, create_cancellation_chunk, create_final_chunk,
-    create_ollama_streaming_chunk, extract_first_choice, process_choice_delta, send_chunk,
-    send_chunk_and_close_channel, send_error_and_close,
+    ChunkProcessingState, FinalChunkParams, StreamTokens, attach_logprobs,
+    create_cancellation_chunk, create_final_chunk, create_ollama_streaming_chunk,
+    extract_first_choice, process_choice_delta, send_chunk, send_chunk_and_close_channel,
+    send_error_and_close,
 };
 use crate::streaming::native::{
     NativeChatEnd, NativeEvent, map_native_event, parse_native_sse_message,
@@ -30,16 +35,155 @@ static STREAM_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 const STREAM_START_LOADING_THRESHOLD_MS: u128 = 500;
 
+/// The load a stream's final chunk reports: the one the proxy waited for
+/// before retrying, else a first chunk slower than
+/// [`STREAM_START_LOADING_THRESHOLD_MS`], taken as LM Studio loading the model
+/// on demand. Zero (keeping the near-zero placeholder) for a warm model.
+pub fn stream_load_duration(waited: Duration, first_chunk_after: Duration) -> Duration {
+    if !waited.is_zero() {
+        waited
+    } else if first_chunk_after.as_millis() > STREAM_START_LOADING_THRESHOLD_MS {
+        first_chunk_after
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// How long a stream may go without a chunk before it is failed.
+///
+/// The first chunk gets its own budget because prompt evaluation on a long
+/// context (or a slow reasoning model) can take far longer than the gaps
+/// between tokens once generation has started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTimeouts {
+    /// `--first-token-timeout-seconds`: wait for the first chunk.
+    pub first_token: Duration,
+    /// `--stream-idle-timeout-seconds`: max gap between later chunks.
+    pub idle: Duration,
+    /// `--sse-keepalive-seconds`: quiet period after which an SSE
+    /// passthrough stream gets a `: keep-alive` comment; `None` = never.
+    pub sse_keepalive: Option<Duration>,
+}
+
+impl Default for StreamTimeouts {
+    fn default() -> Self {
+        Self::from_secs(
+            DEFAULT_STREAM_TIMEOUT_SECONDS,
+            DEFAULT_STREAM_TIMEOUT_SECONDS,
+        )
+        .with_sse_keepalive(DEFAULT_SSE_KEEPALIVE_SECONDS)
+    }
+}
+
+impl StreamTimeouts {
+    pub fn from_secs(first_token: u64, idle: u64) -> Self {
+        Self {
+            first_token: Duration::from_secs(first_token),
+            idle: Duration::from_secs(idle),
+            sse_keepalive: None,
+        }
+    }
+
+    /// Send SSE keep-alive comments after `secs` of silence; 0 disables them.
+    pub fn with_sse_keepalive(mut self, secs: u64) -> Self {
+        self.sse_keepalive = (secs > 0).then(|| Duration::from_secs(secs));
+        self
+    }
+
+    /// Budget for the next chunk, given whether one has already arrived.
+    pub fn next_chunk(&self, first_chunk_received: bool) -> Duration {
+        if first_chunk_received {
+            self.idle
+        } else {
+            self.first_token
+        }
+    }
+
+    /// Error text naming the timeout that fired.
+    pub fn expired_message(&self, first_chunk_received: bool) -> String {
+        if first_chunk_received {
+            format!(
+                "{}: no data for {}s (--stream-idle-timeout-seconds)",
+                ERROR_TIMEOUT,
+                self.idle.as_secs()
+            )
+        } else {
+            format!(
+                "{}: no first token within {}s (--first-token-timeout-seconds)",
+                ERROR_TIMEOUT,
+                self.first_token.as_secs()
+            )
+        }
+    }
+
+    /// The longest a healthy stream can stall, used to delay post-stream work.
+    pub fn longest(&self) -> Duration {
+        self.first_token.max(self.idle)
+    }
+}
+
+/// Everything [`handle_streaming_response`] needs besides the upstream body.
+pub struct StreamingParams<'a> {
+    pub is_chat: bool,
+    pub model_name: &'a str,
+    pub start_time: Instant,
+    /// Time spent loading the model before this attempt (zero when warm).
+    pub load_duration: Duration,
+    pub cancellation_token: CancellationToken,
+    pub timeouts: StreamTimeouts,
+    pub reasoning_mode: ReasoningMode,
+    /// Generate only: records the exchange and adds `context` to the final
+    /// chunk (`--emulate-generate-context`).
+    pub generate_context: Option<GenerateContextTurn>,
+    /// `prompt_eval_count` for the final chunk when LM Studio sends no
+    /// `usage`.
+    pub prompt_tokens_estimate: u64,
+    /// Keeps the last chunk's `model_info`/`runtime` blocks for `/api/show`.
+    pub runtime_info: Arc<RuntimeInfoCache>,
+    /// The resolved LM Studio model key the blocks are filed under.
+    pub lm_studio_model_id: &'a str,
+}
+
+/// Everything [`handle_native_streaming_response`] needs besides the upstream
+/// body.
+pub struct NativeStreamingParams<'a> {
+    pub model_name: &'a str,
+    pub start_time: Instant,
+    /// Time spent loading the model before this attempt (zero when warm).
+    pub load_duration: Duration,
+    pub cancellation_token: CancellationToken,
+    pub timeouts: StreamTimeouts,
+    pub reasoning_mode: ReasoningMode,
+    /// `prompt_eval_count` for the final chunk when LM Studio reports no
+    /// input tokens (or the stream ends before `chat.end`).
+    pub prompt_tokens_estimate: u64,
+}
+
 pub async fn handle_streaming_response(
     lm_studio_response: reqwest::Response,
-    is_chat_endpoint: bool,
-    ollama_model_name: &str,
-    start_time: Instant,
-    cancellation_token: CancellationToken,
-    stream_timeout_seconds: u64,
+    params: StreamingParams<'_>,
 ) -> Result<axum::response::Response, ProxyError> {
+    let StreamingParams {
+        is_chat: is_chat_endpoint,
+        model_name: ollama_model_name,
+        start_time,
+        load_duration,
+        cancellation_token,
+        timeouts,
+        reasoning_mode,
+        generate_context,
+        prompt_tokens_estimate,
+        runtime_info,
+        lm_studio_model_id,
+    } = params;
+    // An error body is not an SSE stream: fail before any chunk is sent, as
+    // the non-streaming path does, so the client gets Ollama's flat error.
+    if !lm_studio_response.status().is_success() {
+        return Err(upstream_error(lm_studio_response).await);
+    }
     let runtime_config = get_runtime_config();
     let ollama_model_name = ollama_model_name.to_string();
+    let lm_studio_model_id = lm_studio_model_id.to_string();
     let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
 
     let stream_id = STREAM_COUNTER.fetch_add(1, Ordering::Relaxed) % 1_000_000;
@@ -48,14 +192,18 @@ pub async fn handle_streaming_response(
     let model_clone_for_task = ollama_model_name.clone();
     let token_clone = cancellation_token.clone();
 
-    tokio::spawn(async move {
+    crate::logging::spawn_with_request_id(async move {
         let mut stream = lm_studio_response.bytes_stream();
         let mut sse_buffer = String::with_capacity(runtime_config.max_buffer_size.min(1024 * 1024));
         let mut chunk_count = 0u64;
+        let mut tokens = StreamTokens::new(prompt_tokens_estimate);
         let mut chunk_state = ChunkProcessingState::default();
         let mut first_chunk_received = false;
+        let mut time_to_first_chunk = Duration::ZERO;
         let mut recovery_buffer = String::new();
         let enable_chunk_recovery = runtime_config.enable_chunk_recovery;
+        // Full response text, kept only when it is needed for `context`.
+        let mut generated_text = generate_context.as_ref().map(|_| String::new());
 
         let stream_result = 'stream_loop: loop {
             tokio::select! {
@@ -64,7 +212,7 @@ pub async fn handle_streaming_response(
                     let cancellation_chunk = create_cancellation_chunk(
                         &model_clone_for_task,
                         start_time.elapsed(),
-                        chunk_count,
+                        tokens.output_estimate(),
                         chunk_state.take_tool_calls(),
                         is_chat_endpoint,
                     );
@@ -72,12 +220,12 @@ pub async fn handle_streaming_response(
                     break 'stream_loop Err(ERROR_CANCELLED.to_string());
                 }
 
-                chunk_result = timeout(Duration::from_secs(stream_timeout_seconds), stream.next()) => {
+                chunk_result = timeout(timeouts.next_chunk(first_chunk_received), stream.next()) => {
                     match chunk_result {
                         Ok(Some(Ok(bytes_chunk))) => {
                             if !first_chunk_received {
                                 first_chunk_received = true;
-                                let time_to_first_chunk = start_time.elapsed();
+                                time_to_first_chunk = start_time.elapsed();
 
                                 if time_to_first_chunk.as_millis() > STREAM_START_LOADING_THRESHOLD_MS {
                                     log_timed(LOG_PREFIX_SUCCESS, &format!("{} loaded", model_clone_for_task), model_loading_start);
@@ -105,16 +253,22 @@ pub async fn handle_streaming_response(
                                                 let mut content_to_send = String::new();
                                                 let mut thinking_to_send = String::new();
                                                 let mut tool_calls_to_send: Option<Value> = None;
+                                                let mut logprobs_to_send: Option<Value> = None;
 
                                                 if let Some(choice) = extract_first_choice(&lm_studio_json_chunk)
-                                                    && let Some(delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                                    && let Some(mut delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                                        delta_payload.apply_reasoning_mode(reasoning_mode);
                                                         content_to_send = delta_payload.content;
                                                         thinking_to_send = delta_payload.thinking;
                                                         tool_calls_to_send = delta_payload.tool_calls_delta;
+                                                        logprobs_to_send = delta_payload.logprobs;
                                                     }
+                                                chunk_state.update_finish_reason_from_chunk(&lm_studio_json_chunk);
+                                                tokens.record_usage(&lm_studio_json_chunk);
+                                                runtime_info.observe(&lm_studio_model_id, &lm_studio_json_chunk);
 
                                                 if !content_to_send.is_empty() || !thinking_to_send.is_empty() || tool_calls_to_send.is_some() {
-                                                    let ollama_chunk = create_ollama_streaming_chunk(
+                                                    let mut ollama_chunk = create_ollama_streaming_chunk(
                                                         &model_clone_for_task,
                                                         &content_to_send,
                                                         is_chat_endpoint,
@@ -122,6 +276,11 @@ pub async fn handle_streaming_response(
                                                         tool_calls_to_send.as_ref(),
                                                         &thinking_to_send,
                                                     );
+                                                    attach_logprobs(&mut ollama_chunk, logprobs_to_send);
+                                                    if let Some(text) = generated_text.as_mut() {
+                                                        text.push_str(&content_to_send);
+                                                    }
+                                                    tokens.record_output(&content_to_send, &thinking_to_send);
                                                     chunk_count += 1;
                                                     if !send_chunk(&tx, &ollama_chunk).await {
                                                         break 'stream_loop Ok(());
@@ -136,16 +295,22 @@ pub async fn handle_streaming_response(
                                                         let mut content_to_send = String::new();
                                                         let mut thinking_to_send = String::new();
                                                         let mut tool_calls_to_send: Option<Value> = None;
+                                                        let mut logprobs_to_send: Option<Value> = None;
 
                                                         if let Some(choice) = extract_first_choice(&recovered_json)
-                                                            && let Some(delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                                            && let Some(mut delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                                                delta_payload.apply_reasoning_mode(reasoning_mode);
                                                                 content_to_send = delta_payload.content;
                                                                 thinking_to_send = delta_payload.thinking;
                                                                 tool_calls_to_send = delta_payload.tool_calls_delta;
+                                                                logprobs_to_send = delta_payload.logprobs;
                                                             }
+                                                        chunk_state.update_finish_reason_from_chunk(&recovered_json);
+                                                        tokens.record_usage(&recovered_json);
+                                                        runtime_info.observe(&lm_studio_model_id, &recovered_json);
 
                                                         if !content_to_send.is_empty() || !thinking_to_send.is_empty() || tool_calls_to_send.is_some() {
-                                                            let ollama_chunk = create_ollama_streaming_chunk(
+                                                            let mut ollama_chunk = create_ollama_streaming_chunk(
                                                                 &model_clone_for_task,
                                                                 &content_to_send,
                                                                 is_chat_endpoint,
@@ -153,6 +318,11 @@ pub async fn handle_streaming_response(
                                                                 tool_calls_to_send.as_ref(),
                                                                 &thinking_to_send,
                                                             );
+                                                            attach_logprobs(&mut ollama_chunk, logprobs_to_send);
+                                                            if let Some(text) = generated_text.as_mut() {
+                                                                text.push_str(&content_to_send);
+                                                            }
+                                                            tokens.record_output(&content_to_send, &thinking_to_send);
                                                             chunk_count += 1;
                                                             if !send_chunk(&tx, &ollama_chunk).await {
                                                                 break 'stream_loop Ok(());
@@ -199,16 +369,22 @@ pub async fn handle_streaming_response(
                                     let mut content_to_send = String::new();
                                     let mut thinking_to_send = String::new();
                                     let mut tool_calls_to_send: Option<Value> = None;
+                                    let mut logprobs_to_send: Option<Value> = None;
 
                                     if let Some(choice) = extract_first_choice(&recovered_json)
-                                        && let Some(delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                        && let Some(mut delta_payload) = process_choice_delta(choice, &mut chunk_state) {
+                                            delta_payload.apply_reasoning_mode(reasoning_mode);
                                             content_to_send = delta_payload.content;
                                             thinking_to_send = delta_payload.thinking;
                                             tool_calls_to_send = delta_payload.tool_calls_delta;
+                                            logprobs_to_send = delta_payload.logprobs;
                                         }
+                                    chunk_state.update_finish_reason_from_chunk(&recovered_json);
+                                    tokens.record_usage(&recovered_json);
+                                    runtime_info.observe(&lm_studio_model_id, &recovered_json);
 
                                     if !content_to_send.is_empty() || !thinking_to_send.is_empty() || tool_calls_to_send.is_some() {
-                                        let ollama_chunk = create_ollama_streaming_chunk(
+                                        let mut ollama_chunk = create_ollama_streaming_chunk(
                                             &model_clone_for_task,
                                             &content_to_send,
                                             is_chat_endpoint,
@@ -216,6 +392,11 @@ pub async fn handle_streaming_response(
                                             tool_calls_to_send.as_ref(),
                                             &thinking_to_send,
                                         );
+                                        attach_logprobs(&mut ollama_chunk, logprobs_to_send);
+                                        if let Some(text) = generated_text.as_mut() {
+                                            text.push_str(&content_to_send);
+                                        }
+                                        tokens.record_output(&content_to_send, &thinking_to_send);
                                         chunk_count += 1;
                                         if !send_chunk(&tx, &ollama_chunk).await {
                                             break 'stream_loop Ok(());
@@ -226,8 +407,9 @@ pub async fn handle_streaming_response(
                             break 'stream_loop Ok(());
                         }
                         Err(_) => {
-                            send_error_and_close(&tx, ERROR_TIMEOUT).await;
-                            break 'stream_loop Err(ERROR_TIMEOUT.to_string());
+                            let message = timeouts.expired_message(first_chunk_received);
+                            send_error_and_close(&tx, &message).await;
+                            break 'stream_loop Err(message);
                         }
                     }
                 }
@@ -236,14 +418,25 @@ pub async fn handle_streaming_response(
 
         if stream_result.is_ok() && !token_clone.is_cancelled() {
             let accumulated_tool_calls = chunk_state.take_tool_calls();
-            let final_chunk = create_final_chunk(FinalChunkParams {
+            let mut final_chunk = create_final_chunk(FinalChunkParams {
                 model_name: &model_clone_for_task,
                 duration: start_time.elapsed(),
-                chunk_count,
+                tokens,
                 is_chat: is_chat_endpoint,
                 done_reason: chunk_state.finish_reason(),
                 tool_calls: accumulated_tool_calls,
             });
+            apply_measured_load(
+                &mut final_chunk,
+                stream_load_duration(load_duration, time_to_first_chunk),
+                start_time,
+            );
+            if let (Some(turn), Some(text)) = (generate_context, generated_text.as_deref()) {
+                let context = turn.finish(text).await;
+                if let Some(obj) = final_chunk.as_object_mut() {
+                    obj.insert("context".to_string(), context);
+                }
+            }
             send_chunk_and_close_channel(&tx, final_chunk).await;
         }
 
@@ -269,26 +462,19 @@ pub async fn handle_streaming_response(
 /// recovery (OpenAI-specific) is intentionally skipped.
 pub async fn handle_native_streaming_response(
     lm_studio_response: reqwest::Response,
-    ollama_model_name: &str,
-    start_time: Instant,
-    cancellation_token: CancellationToken,
-    stream_timeout_seconds: u64,
+    params: NativeStreamingParams<'_>,
 ) -> Result<axum::response::Response, ProxyError> {
-    let status = lm_studio_response.status();
-    if !status.is_success() {
-        let body_text = lm_studio_response.text().await.unwrap_or_default();
-        let message = serde_json::from_str::<serde_json::Value>(&body_text)
-            .ok()
-            .and_then(|v| match v.get("error") {
-                Some(serde_json::Value::Object(obj)) => obj
-                    .get("message")
-                    .and_then(|m| m.as_str())
-                    .map(|s| s.to_string()),
-                Some(serde_json::Value::String(s)) => Some(s.clone()),
-                _ => None,
-            })
-            .unwrap_or_else(|| format!("LM Studio error: {}", status));
-        return Err(ProxyError::new(message, status.as_u16()));
+    let NativeStreamingParams {
+        model_name: ollama_model_name,
+        start_time,
+        load_duration,
+        cancellation_token,
+        timeouts,
+        reasoning_mode,
+        prompt_tokens_estimate,
+    } = params;
+    if !lm_studio_response.status().is_success() {
+        return Err(upstream_error(lm_studio_response).await);
     }
 
     let runtime_config = get_runtime_config();
@@ -301,12 +487,16 @@ pub async fn handle_native_streaming_response(
     let model_clone_for_task = ollama_model_name.clone();
     let token_clone = cancellation_token.clone();
 
-    tokio::spawn(async move {
+    crate::logging::spawn_with_request_id(async move {
         let mut stream = lm_studio_response.bytes_stream();
         let mut sse_buffer = String::with_capacity(runtime_config.max_buffer_size.min(1024 * 1024));
         let mut chunk_count = 0u64;
+        // `chat.end` reports the real input tokens; the estimate covers a
+        // stream that ends without one.
+        let mut tokens = StreamTokens::new(prompt_tokens_estimate);
         let mut chunk_state = ChunkProcessingState::default();
         let mut first_chunk_received = false;
+        let mut time_to_first_chunk = Duration::ZERO;
         // Captured from `chat.end` so the final done chunk can carry native stats.
         let mut chat_end: Option<NativeChatEnd> = None;
 
@@ -317,7 +507,7 @@ pub async fn handle_native_streaming_response(
                     let cancellation_chunk = create_cancellation_chunk(
                         &model_clone_for_task,
                         start_time.elapsed(),
-                        chunk_count,
+                        tokens.output_estimate(),
                         chunk_state.take_tool_calls(),
                         true,
                     );
@@ -325,12 +515,12 @@ pub async fn handle_native_streaming_response(
                     break 'stream_loop Err(ERROR_CANCELLED.to_string());
                 }
 
-                chunk_result = timeout(Duration::from_secs(stream_timeout_seconds), stream.next()) => {
+                chunk_result = timeout(timeouts.next_chunk(first_chunk_received), stream.next()) => {
                     match chunk_result {
                         Ok(Some(Ok(bytes_chunk))) => {
                             if !first_chunk_received {
                                 first_chunk_received = true;
-                                let time_to_first_chunk = start_time.elapsed();
+                                time_to_first_chunk = start_time.elapsed();
 
                                 if time_to_first_chunk.as_millis() > STREAM_START_LOADING_THRESHOLD_MS {
                                     log_timed(LOG_PREFIX_SUCCESS, &format!("{} loaded", model_clone_for_task), model_loading_start);
@@ -354,11 +544,9 @@ pub async fn handle_native_streaming_response(
                                     };
 
                                     match map_native_event(&event_type, &data, &mut chunk_state) {
-                                        NativeEvent::Delta(payload) => {
-                                            if payload.content.is_empty()
-                                                && payload.thinking.is_empty()
-                                                && payload.tool_calls_delta.is_none()
-                                            {
+                                        NativeEvent::Delta(mut payload) => {
+                                            payload.apply_reasoning_mode(reasoning_mode);
+                                            if payload.is_empty() {
                                                 continue;
                                             }
                                             let ollama_chunk = create_ollama_streaming_chunk(
@@ -369,6 +557,7 @@ pub async fn handle_native_streaming_response(
                                                 payload.tool_calls_delta.as_ref(),
                                                 &payload.thinking,
                                             );
+                                            tokens.record_output(&payload.content, &payload.thinking);
                                             chunk_count += 1;
                                             if !send_chunk(&tx, &ollama_chunk).await {
                                                 break 'stream_loop Ok(());
@@ -404,8 +593,9 @@ pub async fn handle_native_streaming_response(
                             break 'stream_loop Ok(());
                         }
                         Err(_) => {
-                            send_error_and_close(&tx, ERROR_TIMEOUT).await;
-                            break 'stream_loop Err(ERROR_TIMEOUT.to_string());
+                            let message = timeouts.expired_message(first_chunk_received);
+                            send_error_and_close(&tx, &message).await;
+                            break 'stream_loop Err(message);
                         }
                     }
                 }
@@ -414,13 +604,24 @@ pub async fn handle_native_streaming_response(
 
         if stream_result.is_ok() && !token_clone.is_cancelled() {
             let accumulated_tool_calls = chunk_state.take_tool_calls();
-            let final_chunk = build_native_final_chunk(
+            let mut final_chunk = build_native_final_chunk(
                 &model_clone_for_task,
                 chat_end.as_ref(),
                 start_time,
-                chunk_count,
+                tokens,
                 accumulated_tool_calls,
             );
+            // LM Studio's own `model_load_time_seconds` beats the estimate.
+            let first_chunk_after = if chat_end.as_ref().is_some_and(reports_model_load) {
+                Duration::ZERO
+            } else {
+                time_to_first_chunk
+            };
+            apply_measured_load(
+                &mut final_chunk,
+                stream_load_duration(load_duration, first_chunk_after),
+                start_time,
+            );
             send_chunk_and_close_channel(&tx, final_chunk).await;
         }
 
@@ -437,6 +638,14 @@ pub async fn handle_native_streaming_response(
     create_streaming_response(rx, StreamContentType::Ndjson)
 }
 
+/// Whether a native `chat.end` timed the model load itself.
+fn reports_model_load(end: &NativeChatEnd) -> bool {
+    end.result
+        .get("stats")
+        .and_then(|stats| stats.get("model_load_time_seconds"))
+        .is_some_and(Value::is_number)
+}
+
 /// Build the final `done:true` chunk for the native streaming path.
 ///
 /// When a `chat.end` was seen, timing comes from its native `stats` block via
@@ -447,21 +656,26 @@ fn build_native_final_chunk(
     model_name: &str,
     chat_end: Option<&NativeChatEnd>,
     start_time: Instant,
-    chunk_count: u64,
+    tokens: StreamTokens,
     tool_calls: Option<Value>,
 ) -> Value {
     let Some(end) = chat_end else {
         return create_final_chunk(FinalChunkParams {
             model_name,
             duration: start_time.elapsed(),
-            chunk_count,
+            tokens,
             is_chat: true,
             done_reason: None,
             tool_calls,
         });
     };
 
-    let timing = TimingInfo::from_native_stats(&end.result, start_time, 10, chunk_count.max(1));
+    let timing = TimingInfo::from_native_stats(
+        &end.result,
+        start_time,
+        tokens.prompt_estimate.max(1),
+        tokens.output_estimate().max(1),
+    );
 
     let mut chunk =
         create_ollama_streaming_chunk(model_name, "", true, true, tool_calls.as_ref(), "");
@@ -485,20 +699,64 @@ fn build_native_final_chunk(
     chunk
 }
 
+/// SSE comment frame sent on quiet passthrough streams; clients ignore it.
+const SSE_KEEPALIVE_FRAME: &[u8] = b": keep-alive\n\n";
+
+/// Whether the bytes forwarded so far end on an SSE event boundary (a blank
+/// line). Only there can a keep-alive comment go without landing inside an
+/// event LM Studio is still sending in pieces.
+#[derive(Default)]
+struct EventBoundary {
+    tail: Vec<u8>,
+}
+
+impl EventBoundary {
+    fn observe(&mut self, chunk: &[u8]) {
+        self.tail.extend_from_slice(chunk);
+        let excess = self.tail.len().saturating_sub(4);
+        self.tail.drain(..excess);
+    }
+
+    fn at_boundary(&self) -> bool {
+        self.tail.is_empty() || self.tail.ends_with(b"\n\n") || self.tail.ends_with(b"\r\n\r\n")
+    }
+}
+
+/// Wait for the next keep-alive tick, or forever when they are off.
+async fn next_keepalive(keepalive: &mut Option<tokio::time::Interval>) {
+    match keepalive {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
 pub async fn handle_passthrough_streaming_response(
     response: reqwest::Response,
     cancellation_token: CancellationToken,
-    stream_timeout_seconds: u64,
+    timeouts: StreamTimeouts,
 ) -> Result<axum::response::Response, ProxyError> {
     let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
     let stream_id = STREAM_COUNTER.fetch_add(1, Ordering::Relaxed) % 1_000_000;
     let start_time = Instant::now();
 
-    tokio::spawn(async move {
+    crate::logging::spawn_with_request_id(async move {
         let mut stream = response.bytes_stream();
         let mut chunk_count = 0u64;
+        // Keep-alive frames do not count as data: the idle deadline only
+        // moves when LM Studio sends something.
+        let mut deadline = tokio::time::Instant::now() + timeouts.next_chunk(false);
+        let mut keepalive = timeouts.sse_keepalive.map(|period| {
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            interval
+        });
+        let mut boundary = EventBoundary::default();
 
         loop {
+            let first_chunk_received = chunk_count > 0;
             tokio::select! {
                 biased;
                 _ = cancellation_token.cancelled() => {
@@ -506,13 +764,18 @@ pub async fn handle_passthrough_streaming_response(
                     let _ = tx.send(Ok(bytes::Bytes::from(cancel_data)));
                     break;
                 }
-                chunk_result = timeout(Duration::from_secs(stream_timeout_seconds), stream.next()) => {
+                chunk_result = tokio::time::timeout_at(deadline, stream.next()) => {
                     match chunk_result {
                         Ok(Some(Ok(chunk))) => {
                             chunk_count += 1;
+                            boundary.observe(&chunk);
                             if tx.send(Ok(chunk)).is_err() {
                                 break;
                             }
+                            deadline = tokio::time::Instant::now() + timeouts.next_chunk(true);
+                            if let Some(interval) = keepalive.as_mut() {
+                                interval.reset();
+                            }
                         }
                         Ok(Some(Err(e))) => {
                             let error_data = format!("data: {{\"error\": \"streaming error: {}\"}}\n\n", e);
@@ -521,12 +784,24 @@ pub async fn handle_passthrough_streaming_response(
                         }
                         Ok(None) => break,
                         Err(_) => {
-                            let timeout_data = format!("data: {{\"error\": \"{}\"}}\n\n", ERROR_TIMEOUT);
+                            let timeout_data = format!(
+                                "data: {}\n\n",
+                                json!({ "error": timeouts.expired_message(first_chunk_received) })
+                            );
                             let _ = tx.send(Ok(bytes::Bytes::from(timeout_data)));
                             break;
                         }
                     }
                 }
+                _ = next_keepalive(&mut keepalive) => {
+                    // Mid-event, wait for the next tick rather than split it.
+                    if !boundary.at_boundary() {
+                        continue;
+                    }
+                    if tx.send(Ok(bytes::Bytes::from_static(SSE_KEEPALIVE_FRAME))).is_err() {
+                        break;
+                    }
+                }
             }
         }
 
@@ -0,0 +1,428 @@
+//! OpenTelemetry traces: `--otlp-endpoint`, with the `otel` cargo feature
+//! compiled in.
+//!
+//! Every request gets a server span (method, endpoint, model, whether the
+//! reply streamed, status) with child spans for model resolution and each
+//! LM Studio call, and events for load triggers, retries and the
+//! [`crate::logging::log_timed`] milestones. A streamed reply keeps its span
+//! open until the body ends, under a `response.stream` child.
+//!
+//! With no endpoint configured every hook here is a single [`enabled`]
+//! branch; without the feature the hooks compile to nothing.
+
+use std::future::Future;
+
+#[cfg(not(feature = "otel"))]
+use noop as imp;
+#[cfg(feature = "otel")]
+use otel as imp;
+
+/// Path an OTLP/HTTP collector takes traces on.
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+/// Whether spans are being exported. False until [`init`] succeeds.
+#[inline]
+pub fn enabled() -> bool {
+    imp::enabled()
+}
+
+/// The OTLP/HTTP traces URL for `--otlp-endpoint`: the collector's base URL
+/// (`http://otel-collector:4318`) gets `/v1/traces` appended; a URL already
+/// ending in it is kept as given.
+pub fn traces_url(endpoint: &str) -> Result<String, String> {
+    let parsed = url::Url::parse(endpoint)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some())
+        .ok_or_else(|| {
+            format!(
+                "invalid --otlp-endpoint (expected http[s]://host[:port]): {}",
+                endpoint
+            )
+        })?;
+    let trimmed = parsed.as_str().trim_end_matches('/');
+    if trimmed.ends_with(OTLP_TRACES_PATH) {
+        Ok(trimmed.to_string())
+    } else {
+        Ok(format!("{}{}", trimmed, OTLP_TRACES_PATH))
+    }
+}
+
+/// Start exporting to `endpoint` (see [`traces_url`]) in batches.
+pub fn init(endpoint: &str) -> Result<(), String> {
+    imp::init(&traces_url(endpoint)?)
+}
+
+/// Flush buffered spans; called once the server has stopped.
+pub fn shutdown() {
+    imp::shutdown()
+}
+
+/// The server span of one incoming request. Its context is task-local while
+/// the handler runs ([`RequestSpan::scope`]) and is carried into spawned
+/// stream pumps by [`propagate`].
+pub struct RequestSpan(imp::RequestSpan);
+
+impl RequestSpan {
+    pub fn start(method: &str, endpoint: &str) -> Self {
+        Self(imp::RequestSpan::start(method, endpoint))
+    }
+
+    /// Run the handler with this span as the parent of everything it records.
+    pub async fn scope<F: Future>(&self, fut: F) -> F::Output {
+        self.0.scope(fut).await
+    }
+
+    /// Record the status and end the span, or, for an NDJSON/SSE reply,
+    /// hand it to the body so it ends when the stream does.
+    pub fn finish(self, response: axum::response::Response) -> axum::response::Response {
+        self.0.finish(response)
+    }
+}
+
+/// A child of the current request's span, ended on drop. Inert outside a
+/// traced request.
+pub struct ChildSpan(imp::ChildSpan);
+
+impl ChildSpan {
+    pub fn set_str(&mut self, key: &'static str, value: &str) {
+        self.0.set_str(key, value)
+    }
+
+    pub fn set_i64(&mut self, key: &'static str, value: i64) {
+        self.0.set_i64(key, value)
+    }
+
+    pub fn set_bool(&mut self, key: &'static str, value: bool) {
+        self.0.set_bool(key, value)
+    }
+
+    /// Mark the span failed with `message`.
+    pub fn set_error(&mut self, message: &str) {
+        self.0.set_error(message)
+    }
+}
+
+pub fn child_span(name: &'static str) -> ChildSpan {
+    ChildSpan(imp::child_span(name))
+}
+
+/// Add an event to the current request's span.
+pub fn event(name: &str, attributes: &[(&'static str, &str)]) {
+    imp::event(name, attributes)
+}
+
+/// Set the request span's `model` attribute (the name the client asked for).
+pub fn record_model(model: &str) {
+    imp::record_model(model)
+}
+
+/// `fut` with the current request's span context, for `tokio::spawn`.
+pub fn propagate<F>(fut: F) -> impl Future<Output = F::Output> + Send + 'static
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    imp::propagate(fut)
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use std::future::Future;
+    use std::sync::OnceLock;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use axum::body::Body;
+    use axum::response::Response;
+    use futures_util::StreamExt;
+    use opentelemetry::global::{BoxedSpan, BoxedTracer};
+    use opentelemetry::trace::{Span, SpanKind, Status, TraceContextExt, Tracer};
+    use opentelemetry::{Context, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+    static TRACER: OnceLock<BoxedTracer> = OnceLock::new();
+
+    tokio::task_local! {
+        static REQUEST_CONTEXT: Option<Context>;
+    }
+
+    #[inline]
+    pub fn enabled() -> bool {
+        ENABLED.load(Ordering::Relaxed)
+    }
+
+    pub fn init(traces_url: &str) -> Result<(), String> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(traces_url)
+            .build()
+            .map_err(|e| format!("OTLP exporter for {}: {}", traces_url, e))?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(env!("CARGO_PKG_NAME"))
+                    .build(),
+            )
+            .build();
+        if PROVIDER.set(provider.clone()).is_err() {
+            return Err("tracing already initialised".to_string());
+        }
+        opentelemetry::global::set_tracer_provider(provider);
+        let _ = TRACER.set(opentelemetry::global::tracer(env!("CARGO_PKG_NAME")));
+        ENABLED.store(true, Ordering::Relaxed);
+        log::info!("exporting traces to {}", traces_url);
+        Ok(())
+    }
+
+    pub fn shutdown() {
+        if let Some(provider) = PROVIDER.get()
+            && let Err(e) = provider.shutdown()
+        {
+            log::warn!("trace export shutdown: {}", e);
+        }
+    }
+
+    fn tracer() -> Option<&'static BoxedTracer> {
+        TRACER.get().filter(|_| enabled())
+    }
+
+    fn current_context() -> Option<Context> {
+        REQUEST_CONTEXT.try_with(Clone::clone).ok().flatten()
+    }
+
+    pub struct RequestSpan(Option<Context>);
+
+    impl RequestSpan {
+        pub fn start(method: &str, endpoint: &str) -> Self {
+            Self(tracer().map(|tracer| {
+                let mut attributes = vec![
+                    KeyValue::new("http.request.method", method.to_string()),
+                    KeyValue::new("endpoint", endpoint.to_string()),
+                ];
+                if let Some(id) = crate::logging::current_request_id() {
+                    attributes.push(KeyValue::new("request.id", id));
+                }
+                let span = tracer
+                    .span_builder(format!("{} {}", method, endpoint))
+                    .with_kind(SpanKind::Server)
+                    .with_attributes(attributes)
+                    .start(tracer);
+                Context::new().with_span(span)
+            }))
+        }
+
+        pub async fn scope<F: Future>(&self, fut: F) -> F::Output {
+            REQUEST_CONTEXT.scope(self.0.clone(), fut).await
+        }
+
+        pub fn finish(self, response: Response) -> Response {
+            let Some(cx) = self.0 else {
+                return response;
+            };
+            let span = cx.span();
+            let status = response.status();
+            span.set_attribute(KeyValue::new(
+                "http.response.status_code",
+                i64::from(status.as_u16()),
+            ));
+            if status.is_server_error() {
+                span.set_status(Status::error(
+                    status.canonical_reason().unwrap_or("server error"),
+                ));
+            }
+            let streamed = is_streamed(&response);
+            span.set_attribute(KeyValue::new("stream", streamed));
+            let Some(tracer) = tracer().filter(|_| streamed) else {
+                span.end();
+                return response;
+            };
+
+            let guard = StreamGuard {
+                stream: tracer.start_with_context("response.stream", &cx),
+                request: cx,
+            };
+            let (parts, body) = response.into_parts();
+            let body = Body::from_stream(body.into_data_stream().map(move |chunk| {
+                let _ = &guard;
+                chunk
+            }));
+            Response::from_parts(parts, body)
+        }
+    }
+
+    /// NDJSON and SSE replies, whose span lasts as long as the body.
+    fn is_streamed(response: &Response) -> bool {
+        response
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| {
+                ct.starts_with("application/x-ndjson") || ct.starts_with("text/event-stream")
+            })
+    }
+
+    /// Ends a streamed reply's spans when its body is dropped: finished, or
+    /// abandoned by the client.
+    struct StreamGuard {
+        request: Context,
+        stream: BoxedSpan,
+    }
+
+    impl Drop for StreamGuard {
+        fn drop(&mut self) {
+            self.stream.end();
+            self.request.span().end();
+        }
+    }
+
+    pub struct ChildSpan(Option<BoxedSpan>);
+
+    impl ChildSpan {
+        pub fn set_str(&mut self, key: &'static str, value: &str) {
+            if let Some(span) = &mut self.0 {
+                span.set_attribute(KeyValue::new(key, value.to_string()));
+            }
+        }
+
+        pub fn set_i64(&mut self, key: &'static str, value: i64) {
+            if let Some(span) = &mut self.0 {
+                span.set_attribute(KeyValue::new(key, value));
+            }
+        }
+
+        pub fn set_bool(&mut self, key: &'static str, value: bool) {
+            if let Some(span) = &mut self.0 {
+                span.set_attribute(KeyValue::new(key, value));
+            }
+        }
+
+        pub fn set_error(&mut self, message: &str) {
+            if let Some(span) = &mut self.0 {
+                span.set_status(Status::error(message.to_string()));
+            }
+        }
+    }
+
+    impl Drop for ChildSpan {
+        fn drop(&mut self) {
+            if let Some(span) = &mut self.0 {
+                span.end();
+            }
+        }
+    }
+
+    pub fn child_span(name: &'static str) -> ChildSpan {
+        if !enabled() {
+            return ChildSpan(None);
+        }
+        ChildSpan(
+            tracer()
+                .zip(current_context())
+                .map(|(tracer, cx)| tracer.start_with_context(name, &cx)),
+        )
+    }
+
+    pub fn event(name: &str, attributes: &[(&'static str, &str)]) {
+        if !enabled() {
+            return;
+        }
+        if let Some(cx) = current_context() {
+            cx.span().add_event(
+                name.to_string(),
+                attributes
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(*key, value.to_string()))
+                    .collect(),
+            );
+        }
+    }
+
+    pub fn record_model(model: &str) {
+        if !enabled() {
+            return;
+        }
+        if let Some(cx) = current_context() {
+            cx.span()
+                .set_attribute(KeyValue::new("model", model.to_string()));
+        }
+    }
+
+    pub fn propagate<F>(fut: F) -> impl Future<Output = F::Output> + Send + 'static
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let cx = if enabled() { current_context() } else { None };
+        REQUEST_CONTEXT.scope(cx, fut)
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+mod noop {
+    use std::future::Future;
+
+    use axum::response::Response;
+
+    #[inline]
+    pub const fn enabled() -> bool {
+        false
+    }
+
+    pub fn init(_traces_url: &str) -> Result<(), String> {
+        Err("this build lacks the `otel` feature".to_string())
+    }
+
+    pub fn shutdown() {}
+
+    pub struct RequestSpan;
+
+    impl RequestSpan {
+        pub fn start(_method: &str, _endpoint: &str) -> Self {
+            Self
+        }
+
+        pub async fn scope<F: Future>(&self, fut: F) -> F::Output {
+            fut.await
+        }
+
+        pub fn finish(self, response: Response) -> Response {
+            response
+        }
+    }
+
+    pub struct ChildSpan;
+
+    impl ChildSpan {
+        pub fn set_str(&mut self, _key: &'static str, _value: &str) {}
+
+        pub fn set_i64(&mut self, _key: &'static str, _value: i64) {}
+
+        pub fn set_bool(&mut self, _key: &'static str, _value: bool) {}
+
+        pub fn set_error(&mut self, _message: &str) {}
+    }
+
+    pub fn child_span(_name: &'static str) -> ChildSpan {
+        ChildSpan
+    }
+
+    pub fn event(_name: &str, _attributes: &[(&'static str, &str)]) {}
+
+    pub fn record_model(_model: &str) {}
+
+    pub fn propagate<F>(fut: F) -> impl Future<Output = F::Output> + Send + 'static
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        fut
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/unit/telemetry.rs"]
+mod tests;
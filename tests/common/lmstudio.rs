@@ -0,0 +1,126 @@
+// Canned LM Studio for end-to-end tests, served from `tests/fixtures/lmstudio/`.
+//
+// `mount_lmstudio(&proxy)` registers the model catalog, chat completions
+// (JSON, or SSE when the request streams) and embeddings on the proxy's
+// MockServer; `mount_download` adds a download that walks through a list of
+// status steps. Mocks a test mounts afterwards take precedence only when
+// given a higher priority (lower number) than `FIXTURE_PRIORITY`.
+
+use serde_json::{Value, json};
+use wiremock::matchers::{body_partial_json, method, path, path_regex};
+use wiremock::{Mock, ResponseTemplate};
+
+use super::TestProxy;
+
+/// Priority of the fixture mocks; wiremock's default is 5, so anything a test
+/// mounts with the default wins over them.
+pub const FIXTURE_PRIORITY: u8 = 10;
+
+/// Keys of the models in `models.json`.
+pub const CHAT_MODEL: &str = "llama-3.2-3b-instruct";
+pub const VISION_MODEL: &str = "qwen2-vl-2b-instruct";
+pub const EMBEDDING_MODEL: &str = "text-embedding-nomic-embed-text-v1.5";
+
+/// The reply text of both `chat_completion.json` and `chat_completion.sse`.
+pub const FIXTURE_REPLY: &str = "Hello from the fixture.";
+
+/// The `runtime.name` both chat fixtures report.
+pub const FIXTURE_RUNTIME: &str = "llama.cpp-linux-x86_64-avx2";
+
+pub fn fixture(name: &str) -> String {
+    let path = format!(
+        "{}/tests/fixtures/lmstudio/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read fixture {path}: {e}"))
+}
+
+pub fn fixture_json(name: &str) -> Value {
+    serde_json::from_str(&fixture(name)).unwrap_or_else(|e| panic!("parse fixture {name}: {e}"))
+}
+
+/// Catalog, chat completions and embeddings.
+pub async fn mount_lmstudio(proxy: &TestProxy) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture_json("models.json")))
+        .with_priority(FIXTURE_PRIORITY)
+        .mount(&proxy.mock)
+        .await;
+
+    // Streaming requests first: the JSON mock below would match them too.
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .and(body_partial_json(json!({ "stream": true })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(fixture("chat_completion.sse")),
+        )
+        .with_priority(FIXTURE_PRIORITY - 1)
+        .mount(&proxy.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(fixture_json("chat_completion.json")),
+        )
+        .with_priority(FIXTURE_PRIORITY)
+        .mount(&proxy.mock)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(fixture_json("embeddings.json")))
+        .with_priority(FIXTURE_PRIORITY)
+        .mount(&proxy.mock)
+        .await;
+}
+
+/// A download that answers the start request with `steps[0]` and each
+/// status poll with the next step, repeating the last one from then on.
+/// `download_steps.json` is a three-step 0 % → 50 % → completed download.
+pub async fn mount_download(proxy: &TestProxy, steps: &[Value]) {
+    let (first, polls) = steps.split_first().expect("at least one download step");
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(first))
+        .with_priority(FIXTURE_PRIORITY)
+        .mount(&proxy.mock)
+        .await;
+
+    let Some((last, sequence)) = polls.split_last() else {
+        return;
+    };
+    // Equal priorities match in mount order; exhausted mocks are skipped.
+    for step in sequence {
+        Mock::given(method("GET"))
+            .and(path_regex(r"^/api/v1/models/download/status/.*"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(step))
+            .up_to_n_times(1)
+            .with_priority(FIXTURE_PRIORITY)
+            .mount(&proxy.mock)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/api/v1/models/download/status/.*"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(last))
+        .with_priority(FIXTURE_PRIORITY)
+        .mount(&proxy.mock)
+        .await;
+}
+
+/// `mount_download` with `download_steps.json`.
+pub async fn mount_fixture_download(proxy: &TestProxy) {
+    let steps = fixture_json("download_steps.json");
+    mount_download(proxy, steps.as_array().expect("download steps array")).await;
+}
+
+/// Split an NDJSON body into its JSON lines.
+pub fn ndjson(text: &str) -> Vec<Value> {
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).unwrap_or_else(|e| panic!("invalid NDJSON line {l}: {e}")))
+        .collect()
+}
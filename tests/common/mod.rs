@@ -4,9 +4,14 @@
 // to a wiremock MockServer that stands in for LM Studio. Tests register mocks
 // on the returned MockServer, then hit the proxy at the returned base URL with
 // reqwest just like a real client would.
+//
+// `lmstudio` mounts a canned LM Studio from `tests/fixtures/lmstudio/` for
+// tests that want realistic upstream traffic without writing their own mocks.
 
 #![allow(dead_code)]
 
+pub mod lmstudio;
+
 use std::sync::Arc;
 use std::sync::Once;
 
@@ -14,11 +19,12 @@ use tempfile::TempDir;
 use tokio::task::JoinHandle;
 use wiremock::MockServer;
 
-use ollama_lmstudio_proxy::config::{Config, RuntimeConfig, init_runtime_config};
+use ollama_lmstudio_proxy::config::{
+    Config, ReasoningMode, ResolutionMode, RuntimeConfig, init_runtime_config,
+};
 use ollama_lmstudio_proxy::logging::LogConfig;
 use ollama_lmstudio_proxy::proxy::ProxyServer;
-use ollama_lmstudio_proxy::proxy::routes::create_router;
-use ollama_lmstudio_proxy::proxy::server::cors_layer;
+use ollama_lmstudio_proxy::proxy::server::build_app;
 
 static INIT_RUNTIME: Once = Once::new();
 
@@ -32,6 +38,8 @@ fn ensure_runtime_initialized(enable_chunk_recovery: bool) {
             eval_batch_size: None,
             default_context_length: None,
             auto_evict: false,
+            accurate_tokens: false,
+            indefinite_ttl_seconds: 365 * 24 * 60 * 60,
         });
         LogConfig::init(false);
     });
@@ -49,6 +57,11 @@ impl TestProxy {
     pub fn url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
+
+    /// The proxy's state directory (virtual models, blobs, timestamps).
+    pub fn state_dir(&self) -> &std::path::Path {
+        self._state_dir.path()
+    }
 }
 
 pub async fn spawn_proxy() -> TestProxy {
@@ -65,6 +78,7 @@ pub async fn spawn_proxy_with_recovery(enable_chunk_recovery: bool) -> TestProxy
         false,
         false,
         15,
+        |_| {},
     )
     .await
 }
@@ -73,20 +87,20 @@ pub async fn spawn_proxy_with_recovery(enable_chunk_recovery: bool) -> TestProxy
 /// `/api/chat` routes through LM Studio's native endpoint instead of the
 /// OpenAI-compat `/api/v0/chat/completions`.
 pub async fn spawn_proxy_with_native() -> TestProxy {
-    spawn_proxy_inner(true, true, false, true, None, false, false, 15).await
+    spawn_proxy_inner(true, true, false, true, None, false, false, 15, |_| {}).await
 }
 
 /// Boot a proxy with the `/api/web_search` configured to forward to the mock
 /// server's `/search` endpoint (with a bearer key). Mount a POST `/search`
 /// mock to drive it.
 pub async fn spawn_proxy_with_search() -> TestProxy {
-    spawn_proxy_inner(true, false, true, true, None, false, false, 15).await
+    spawn_proxy_inner(true, false, true, true, None, false, false, 15, |_| {}).await
 }
 
 /// Boot a proxy with the web_fetch SSRF guard ENABLED (private/loopback targets
 /// rejected) — i.e. `--allow-private-fetch` off.
 pub async fn spawn_proxy_strict_ssrf() -> TestProxy {
-    spawn_proxy_inner(true, false, false, false, None, false, false, 15).await
+    spawn_proxy_inner(true, false, false, false, None, false, false, 15, |_| {}).await
 }
 
 /// Boot a proxy requiring an inbound `Authorization: Bearer <api_key>` on every
@@ -102,6 +116,7 @@ pub async fn spawn_proxy_with_api_key(api_key: &str) -> TestProxy {
         false,
         false,
         15,
+        |_| {},
     )
     .await
 }
@@ -110,13 +125,13 @@ pub async fn spawn_proxy_with_api_key(api_key: &str) -> TestProxy {
 /// (`stream:true`) routes through native `/api/v1/chat`, non-streaming stays on
 /// the OpenAI-compat `/api/v0/chat/completions` path.
 pub async fn spawn_proxy_with_native_streaming() -> TestProxy {
-    spawn_proxy_inner(true, false, false, true, None, true, false, 15).await
+    spawn_proxy_inner(true, false, false, true, None, true, false, 15, |_| {}).await
 }
 
 /// Boot a proxy with `--auto-evict` on: proactively evicts other loaded models
 /// before inference when the target model is not yet loaded.
 pub async fn spawn_proxy_with_auto_evict() -> TestProxy {
-    spawn_proxy_inner(true, false, false, true, None, false, true, 15).await
+    spawn_proxy_inner(true, false, false, true, None, false, true, 15, |_| {}).await
 }
 
 /// Boot a proxy with a custom `load_timeout_seconds` — useful for tests that
@@ -132,10 +147,17 @@ pub async fn spawn_proxy_with_load_timeout(load_timeout_seconds: u64) -> TestPro
         false,
         false,
         load_timeout_seconds,
+        |_| {},
     )
     .await
 }
 
+/// Boot a proxy with arbitrary `Config` tweaks applied on top of the defaults
+/// used by `spawn_proxy()` — for flags that don't warrant a dedicated helper.
+pub async fn spawn_proxy_with_config(configure: impl FnOnce(&mut Config)) -> TestProxy {
+    spawn_proxy_inner(true, false, false, true, None, false, false, 15, configure).await
+}
+
 /// Bearer key the search-configured test proxy sends to its provider.
 pub const TEST_SEARCH_API_KEY: &str = "test-search-key";
 
@@ -149,6 +171,7 @@ async fn spawn_proxy_inner(
     native_chat_streaming: bool,
     auto_evict: bool,
     load_timeout_seconds: u64,
+    configure: impl FnOnce(&mut Config),
 ) -> TestProxy {
     ensure_runtime_initialized(enable_chunk_recovery);
 
@@ -158,15 +181,22 @@ async fn spawn_proxy_inner(
     let search_url = configure_search.then(|| format!("{}/search", mock.uri()));
     let search_api_key = configure_search.then(|| TEST_SEARCH_API_KEY.to_string());
 
-    let config = Config {
-        listen: "127.0.0.1:0".to_string(),
+    let mut config = Config {
+        listen: vec!["127.0.0.1:0".to_string()],
         lmstudio_url: mock.uri(),
+        lmstudio_fallback_url: None,
         log_level: "off".to_string(),
         load_timeout_seconds,
+        preload: Vec::new(),
+        auto_pull_missing: false,
+        auto_pull_pattern: Vec::new(),
+        auto_pull_wait_seconds: 300,
         max_buffer_size: 262_144,
         enable_chunk_recovery,
         model_resolution_cache_ttl_seconds: 1,
         lmstudio_token: None,
+        lmstudio_header: Vec::new(),
+        upstream_bind_interface: None,
         api_key,
         use_native_chat,
         native_chat_streaming,
@@ -179,23 +209,64 @@ async fn spawn_proxy_inner(
         search_api_key,
         ollama_version: "0.30.0".to_string(),
         default_context_length: None,
+        delete_unloads: false,
+        reasoning_mode: ReasoningMode::default(),
+        resolution: ResolutionMode::default(),
+        max_concurrent_per_model: None,
+        concurrency_wait_seconds: 30,
+        accurate_tokens: false,
+        models_cache_ttl_seconds: 0,
+        model_allowlist: Vec::new(),
+        model_blocklist: Vec::new(),
+        model_name_map: Vec::new(),
+        context_guard: false,
+        self_test: false,
+        self_test_no_inference: false,
+        enable_compression: false,
+        first_token_timeout_seconds: 60,
+        sse_keepalive_seconds: 15,
+        stream_idle_timeout_seconds: 60,
+        connect_timeout_seconds: 10,
+        request_timeout_seconds: 300,
+        pool_max_idle_per_host: 32,
+        tls_cert: None,
+        tls_key: None,
+        cors_origin: Vec::new(),
+        forward_header: Vec::new(),
+        disable_passthrough: false,
+        ollama_model_ids: false,
+        emulate_generate_context: false,
+        generate_context_ttl_seconds: 1800,
+        embedding_cache_size: 0,
+        embedding_cache_ttl_seconds: 3600,
+        max_body_size: 16 * 1024 * 1024,
+        max_blob_size: 0,
+        blob_max_total_mb: 0,
+        default_system_prompt: None,
+        health_check_interval_seconds: 0,
+        health_cache_seconds: 2,
+        indefinite_ttl_seconds: 365 * 24 * 60 * 60,
+        strict_json: false,
+        strict_params: false,
+        expose_stats: false,
+        log_file: None,
+        log_max_size_mb: 10,
+        log_max_files: 5,
+        service: false,
+        access_log_file: None,
+        no_console_access_log: false,
+        otlp_endpoint: None,
+        pid_file: None,
     };
+    configure(&mut config);
 
     let server = ProxyServer::new_with_state_dir(config, state_dir.path().to_path_buf())
         .expect("ProxyServer::new_with_state_dir");
     let server = Arc::new(server);
+    server.spawn_health_monitor();
+    server.spawn_access_log_flusher();
 
-    // Replicate the production layer stack from `ProxyServer::run` so the test
-    // harness exercises the same middleware: access_log → api_key_gate → cors.
-    // The api_key gate is a no-op when `api_key` is None, so existing tests are
-    // unaffected.
-    let api_key = Arc::new(server.config.api_key.clone());
-    let app = create_router(server)
-        .layer(axum::middleware::from_fn_with_state(
-            api_key,
-            ollama_lmstudio_proxy::proxy::auth::api_key_gate,
-        ))
-        .layer(cors_layer());
+    let app = build_app(server);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
         .await
@@ -0,0 +1,211 @@
+// Integration tests for --lmstudio-fallback-url: a request the primary LM
+// Studio can't take (connection refused) is retried on the fallback, and
+// /health reports both.
+
+use serde_json::{Value, json};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::common::{TestProxy, spawn_proxy_with_config};
+
+/// A URL nothing listens on: bind an ephemeral port, then free it.
+fn refused_url() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().expect("local_addr").port();
+    format!("http://127.0.0.1:{}", port)
+}
+
+/// A proxy whose primary is down and whose fallback is the wiremock server.
+async fn spawn_with_dead_primary() -> TestProxy {
+    let dead = refused_url();
+    spawn_proxy_with_config(move |c| {
+        c.lmstudio_fallback_url = Some(c.lmstudio_url.clone());
+        c.lmstudio_url = dead;
+    })
+    .await
+}
+
+async fn mount_fallback_chat(p: &TestProxy) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{
+                "key": "llama3.1-8b-instruct",
+                "type": "llm",
+                "publisher": "meta",
+                "architecture": "llama",
+                "format": "gguf",
+                "quantization": { "name": "Q4_K_M" },
+                "max_context_length": 8192,
+                "loaded_instances": [{ "id": "llama3.1-8b-instruct" }]
+            }]
+        })))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-test",
+            "object": "chat.completion",
+            "created": 1_700_000_000u64,
+            "model": "llama3.1-8b-instruct",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "from the fallback" },
+                "finish_reason": "stop"
+            }]
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
+async fn chat(p: &TestProxy) -> Value {
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+    resp.json().await.expect("JSON body")
+}
+
+#[tokio::test]
+async fn unreachable_primary_fails_over_to_the_fallback() {
+    let p = spawn_with_dead_primary().await;
+    mount_fallback_chat(&p).await;
+
+    let body = chat(&p).await;
+    assert_eq!(body["message"]["content"], "from the fallback");
+
+    // The primary's breaker is open now, so a route without its own retry
+    // (the model list) goes straight to the fallback too.
+    let tags = p
+        .client
+        .get(p.url("/api/tags"))
+        .send()
+        .await
+        .expect("GET /api/tags");
+    assert_eq!(tags.status(), 200);
+}
+
+#[tokio::test]
+async fn model_list_routes_fail_over_on_their_own() {
+    // No chat first: /api/tags, /api/ps and /api/show trip the primary's
+    // breaker and retry on the fallback themselves.
+    let p = spawn_with_dead_primary().await;
+    mount_fallback_chat(&p).await;
+
+    for route in ["/api/tags", "/api/ps"] {
+        let resp = p
+            .client
+            .get(p.url(route))
+            .send()
+            .await
+            .unwrap_or_else(|e| panic!("GET {route}: {e}"));
+        assert_eq!(resp.status(), 200, "{route}");
+    }
+    let resp = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({ "model": "llama3.1:8b" }))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn health_reports_each_backend() {
+    let p = spawn_with_dead_primary().await;
+    mount_fallback_chat(&p).await;
+    chat(&p).await;
+
+    let resp = p
+        .client
+        .get(p.url("/health/ready"))
+        .send()
+        .await
+        .expect("GET /health/ready");
+    assert_eq!(resp.status(), 200, "the fallback is healthy");
+    let body: Value = resp.json().await.expect("JSON body");
+    assert_eq!(body["status"], "unreachable");
+    let backends = body["backends"].as_array().expect("backends array");
+    assert_eq!(backends.len(), 2, "{body}");
+    assert_eq!(backends[0]["status"], "unreachable");
+    assert_eq!(backends[0]["circuit_open"], true);
+    assert_eq!(backends[1]["url"], json!(p.mock.uri()));
+    assert_eq!(backends[1]["status"], "healthy");
+    assert_eq!(backends[1]["circuit_open"], false);
+}
+
+#[tokio::test]
+async fn upstream_503_does_not_fail_over() {
+    // The primary answers, with a 503 of its own: that is an error to report,
+    // not an outage, so the fallback must never see the request.
+    let fallback = MockServer::start().await;
+    let fallback_url = fallback.uri();
+    let p = spawn_proxy_with_config(move |c| {
+        c.lmstudio_fallback_url = Some(fallback_url);
+    })
+    .await;
+    mount_fallback_chat(&p).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(503).set_body_json(json!({ "error": "model is busy" })))
+        .with_priority(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 503);
+    let requests = fallback.received_requests().await.unwrap_or_default();
+    assert!(requests.is_empty(), "fallback was tried: {requests:?}");
+}
+
+#[tokio::test]
+async fn health_reuses_cached_fallback_probes() {
+    let dead = refused_url();
+    let p = spawn_proxy_with_config(move |c| {
+        c.lmstudio_fallback_url = Some(c.lmstudio_url.clone());
+        c.lmstudio_url = dead;
+        c.health_cache_seconds = 60;
+    })
+    .await;
+    mount_fallback_chat(&p).await;
+
+    for _ in 0..3 {
+        let resp = p
+            .client
+            .get(p.url("/health"))
+            .send()
+            .await
+            .expect("GET /health");
+        let body: Value = resp.json().await.expect("JSON body");
+        assert_eq!(body["backends"][1]["status"], "healthy", "{body}");
+    }
+    let probes = p
+        .mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == "/api/v1/models")
+        .count();
+    assert_eq!(probes, 1, "the fallback is probed once per cache window");
+}
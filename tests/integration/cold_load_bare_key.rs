@@ -24,7 +24,7 @@ use serde_json::{Value, json};
 use wiremock::matchers::{body_partial_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy_with_load_timeout;
+use crate::common::{spawn_proxy_with_config, spawn_proxy_with_load_timeout};
 
 // The LM Studio bare key has no tag — confirming the resolver strips ":latest".
 const MODEL_KEY: &str = "some-model";
@@ -149,3 +149,130 @@ async fn cold_load_uses_bare_key_not_latest_tag() {
     // Verifies that load mock's .expect(1) was satisfied with the bare key body.
     p.mock.verify().await;
 }
+
+// `load_duration` reports the time the proxy spent bringing the model up, and
+// `total_duration` includes it, rather than the 1ms placeholder.
+#[tokio::test]
+async fn cold_load_time_is_reported_in_load_duration() {
+    // No fixed wait after the trigger: the load itself takes the 2s.
+    let p = spawn_proxy_with_load_timeout(0).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [unloaded_model_entry()]
+        })))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": { "message": "No models loaded. Please load a model first." }
+        })))
+        .up_to_n_times(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "status": "loaded", "instance_id": MODEL_KEY }))
+                .set_delay(std::time::Duration::from_secs(2)),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_ok()))
+        .mount(&p.mock)
+        .await;
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .expect("reqwest client");
+    let resp = client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": OLLAMA_MODEL,
+            "messages": [{ "role": "user", "content": "hello" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat cold load");
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("JSON body");
+    let load = body["load_duration"].as_u64().expect("load_duration");
+    let total = body["total_duration"].as_u64().expect("total_duration");
+    assert!(
+        load >= 2_000_000_000,
+        "load_duration {load} misses the 2s load"
+    );
+    assert!(total >= load, "total {total} must include load {load}");
+}
+
+// A `--model-name-map` name is loaded as the key it maps to, even when the
+// resolver alone would match the name to another model.
+#[tokio::test]
+async fn cold_load_of_a_mapped_name_loads_the_mapped_key() {
+    let p = spawn_proxy_with_config(|c| {
+        c.load_timeout_seconds = 0;
+        c.model_name_map = vec![format!("decoy={}", MODEL_KEY)];
+    })
+    .await;
+
+    let mut decoy = unloaded_model_entry();
+    decoy["key"] = json!("decoy-model");
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [unloaded_model_entry(), decoy]
+        })))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": { "message": "No models loaded. Please load a model first." }
+        })))
+        .up_to_n_times(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "status": "loaded", "instance_id": MODEL_KEY })),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_ok()))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "decoy",
+            "messages": [{ "role": "user", "content": "hello" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat cold load");
+    assert_eq!(resp.status(), 200);
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let loaded: Vec<Value> = received
+        .iter()
+        .filter(|r| r.url.path() == "/api/v1/models/load")
+        .map(|r| serde_json::from_slice(&r.body).expect("JSON load body"))
+        .collect();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0]["model"], MODEL_KEY);
+}
@@ -0,0 +1,146 @@
+// Integration tests for `--max-concurrent-per-model`.
+//
+// LM Studio is stubbed with a delayed chat response so the first request holds
+// the model's only slot while the second arrives.
+
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::common::{TestProxy, spawn_proxy_with_config};
+
+async fn mount_slow_chat(p: &TestProxy, delay: Duration) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "llama3-8b-instruct", "type": "llm", "publisher": "meta",
+                        "architecture": "llama", "format": "gguf",
+                        "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
+                        "max_context_length": 8192,
+                        "loaded_instances": [{"id": "inst-0", "config": {"context_length": 4096}}],
+                        "capabilities": {"vision": false, "trained_for_tool_use": false}}]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "done"},
+                        "finish_reason": "stop"
+                    }]
+                }))
+                .set_delay(delay),
+        )
+        .mount(&p.mock)
+        .await;
+}
+
+fn chat_body() -> Value {
+    json!({
+        "model": "llama3-8b-instruct",
+        "messages": [{"role": "user", "content": "hi"}],
+        "stream": false
+    })
+}
+
+/// Poll `/health` until the model reports one in-flight request.
+async fn wait_for_in_flight(p: &TestProxy) -> Value {
+    let mut health = Value::Null;
+    for _ in 0..100 {
+        health = p
+            .client
+            .get(p.url("/health"))
+            .send()
+            .await
+            .expect("GET /health")
+            .json()
+            .await
+            .expect("health JSON");
+        if health["concurrency"]["models"]["llama3-8b-instruct"]["in_flight"] == json!(1) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    health
+}
+
+#[tokio::test]
+async fn second_request_over_limit_is_rejected_with_retry_after() {
+    let p = spawn_proxy_with_config(|c| {
+        c.max_concurrent_per_model = Some(1);
+        c.concurrency_wait_seconds = 0;
+    })
+    .await;
+    mount_slow_chat(&p, Duration::from_millis(800)).await;
+
+    let first = {
+        let client = p.client.clone();
+        let url = p.url("/api/chat");
+        tokio::spawn(async move { client.post(url).json(&chat_body()).send().await })
+    };
+
+    let health = wait_for_in_flight(&p).await;
+    assert_eq!(
+        health["concurrency"]["max_concurrent_per_model"],
+        json!(1),
+        "{health}"
+    );
+    assert_eq!(
+        health["concurrency"]["models"]["llama3-8b-instruct"]["in_flight"],
+        json!(1),
+        "{health}"
+    );
+
+    let second = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&chat_body())
+        .send()
+        .await
+        .expect("POST /api/chat second");
+    assert_eq!(second.status(), 429);
+    assert_eq!(
+        second
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok()),
+        Some("1")
+    );
+
+    let first = first.await.unwrap().expect("first request");
+    assert_eq!(first.status(), 200);
+}
+
+#[tokio::test]
+async fn queued_request_runs_once_slot_frees() {
+    let p = spawn_proxy_with_config(|c| {
+        c.max_concurrent_per_model = Some(1);
+        c.concurrency_wait_seconds = 10;
+    })
+    .await;
+    mount_slow_chat(&p, Duration::from_millis(300)).await;
+
+    let first = {
+        let client = p.client.clone();
+        let url = p.url("/api/chat");
+        tokio::spawn(async move { client.post(url).json(&chat_body()).send().await })
+    };
+    wait_for_in_flight(&p).await;
+
+    let second = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&chat_body())
+        .send()
+        .await
+        .expect("POST /api/chat second");
+    assert_eq!(second.status(), 200);
+    assert_eq!(first.await.unwrap().expect("first").status(), 200);
+}
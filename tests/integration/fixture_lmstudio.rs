@@ -0,0 +1,262 @@
+// End-to-end tests against the fixture-backed LM Studio in
+// `common::lmstudio`: each request runs the full handler path (resolution,
+// request building, upstream call, response or stream translation) with no
+// per-test mocks.
+
+use serde_json::{Value, json};
+
+use crate::common::lmstudio::{
+    CHAT_MODEL, EMBEDDING_MODEL, FIXTURE_REPLY, FIXTURE_RUNTIME, VISION_MODEL,
+    mount_fixture_download, mount_lmstudio, ndjson,
+};
+use crate::common::{TestProxy, spawn_proxy};
+
+const PNG_1X1: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+async fn fixture_proxy() -> TestProxy {
+    let p = spawn_proxy().await;
+    mount_lmstudio(&p).await;
+    p
+}
+
+async fn upstream_requests(p: &TestProxy, upstream_path: &str) -> Vec<Value> {
+    p.mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == upstream_path)
+        .map(|r| serde_json::from_slice(&r.body).expect("JSON upstream body"))
+        .collect()
+}
+
+#[tokio::test]
+async fn chat_without_streaming_answers_from_the_fixture() {
+    let p = fixture_proxy().await;
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": CHAT_MODEL,
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON body");
+    assert_eq!(body["model"], CHAT_MODEL);
+    assert_eq!(body["message"]["content"], FIXTURE_REPLY);
+    assert_eq!(body["done"], true);
+    assert_eq!(body["done_reason"], "stop");
+
+    let upstream = upstream_requests(&p, "/api/v0/chat/completions").await;
+    assert_eq!(upstream.len(), 1);
+    assert_eq!(upstream[0]["model"], CHAT_MODEL);
+    assert_eq!(upstream[0]["messages"][0]["content"], "Hi");
+}
+
+#[tokio::test]
+async fn chat_stream_reassembles_the_fixture_reply() {
+    let p = fixture_proxy().await;
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": CHAT_MODEL,
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat stream");
+    assert_eq!(resp.status(), 200);
+    let chunks = ndjson(&resp.text().await.expect("body text"));
+    assert!(chunks.len() >= 2, "{chunks:?}");
+
+    let text: String = chunks
+        .iter()
+        .filter_map(|c| c["message"]["content"].as_str())
+        .collect();
+    assert_eq!(text, FIXTURE_REPLY);
+    let last = chunks.last().unwrap();
+    assert_eq!(last["done"], true);
+    assert!(
+        chunks[..chunks.len() - 1]
+            .iter()
+            .all(|c| c["done"] == false)
+    );
+}
+
+async fn show_verbose(p: &TestProxy) -> Value {
+    let resp = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({ "model": CHAT_MODEL, "verbose": true }))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(resp.status(), 200);
+    resp.json().await.expect("JSON body")
+}
+
+#[tokio::test]
+async fn show_reports_the_runtime_of_the_last_streamed_reply() {
+    let p = fixture_proxy().await;
+    assert!(
+        show_verbose(&p).await["model_info"]
+            .get("lmstudio.runtime")
+            .is_none()
+    );
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": CHAT_MODEL,
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat stream");
+    resp.text().await.expect("drain the stream");
+
+    let body = show_verbose(&p).await;
+    let info = &body["model_info"];
+    assert_eq!(info["lmstudio.runtime"], FIXTURE_RUNTIME);
+    assert_eq!(info["lmstudio.runtime_version"], "1.21.0");
+    assert_eq!(info["lmstudio.served_context_length"], 4096);
+    assert_eq!(info["general.architecture"], "llama");
+    assert_eq!(info["general.file_type"], 15);
+}
+
+#[tokio::test]
+async fn generate_with_an_image_sends_a_vision_chat_request() {
+    let p = fixture_proxy().await;
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": VISION_MODEL,
+            "prompt": "What is in this picture?",
+            "images": [PNG_1X1],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON body");
+    assert_eq!(body["response"], FIXTURE_REPLY);
+    assert_eq!(body["done"], true);
+
+    let upstream = upstream_requests(&p, "/api/v0/chat/completions").await;
+    assert_eq!(upstream.len(), 1);
+    let content = upstream[0]["messages"]
+        .as_array()
+        .and_then(|m| m.last())
+        .map(|m| m["content"].clone())
+        .expect("user message");
+    assert_eq!(
+        content[1]["image_url"]["url"],
+        format!("data:image/png;base64,{PNG_1X1}")
+    );
+}
+
+#[tokio::test]
+async fn embed_returns_the_fixture_vector() {
+    let p = fixture_proxy().await;
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": EMBEDDING_MODEL, "input": "hello" }))
+        .send()
+        .await
+        .expect("POST /api/embed");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON body");
+    let vector: Vec<f64> = body["embeddings"][0]
+        .as_array()
+        .expect("one vector")
+        .iter()
+        .map(|v| v.as_f64().expect("number"))
+        .collect();
+    let expected = [0.1, -0.2, 0.3, -0.4];
+    assert_eq!(vector.len(), expected.len());
+    for (got, want) in vector.iter().zip(expected) {
+        assert!((got - want).abs() < 1e-6, "{vector:?}");
+    }
+}
+
+#[tokio::test]
+async fn pull_stream_reports_each_download_step() {
+    let p = fixture_proxy().await;
+    mount_fixture_download(&p).await;
+    let resp = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({ "model": "phi-4-mini", "stream": true }))
+        .send()
+        .await
+        .expect("POST /api/pull");
+    assert_eq!(resp.status(), 200);
+    let chunks = ndjson(&resp.text().await.expect("body text"));
+
+    assert_eq!(chunks.last(), Some(&json!({ "status": "success" })));
+    let progress: Vec<u64> = chunks
+        .iter()
+        .filter_map(|c| c["completed"].as_u64())
+        .collect();
+    assert!(progress.contains(&1_500_000_000), "{chunks:?}");
+    assert!(
+        progress.windows(2).all(|w| w[0] <= w[1]),
+        "progress must not go backwards: {progress:?}"
+    );
+    assert!(
+        chunks
+            .iter()
+            .filter(|c| c.get("total").is_some())
+            .all(|c| c["total"] == 3_000_000_000u64),
+        "{chunks:?}"
+    );
+}
+
+#[tokio::test]
+async fn unknown_model_is_a_404_and_never_reaches_inference() {
+    let p = fixture_proxy().await;
+    for (endpoint, body) in [
+        (
+            "/api/chat",
+            json!({ "model": "no-such-model", "messages": [{ "role": "user", "content": "Hi" }], "stream": false }),
+        ),
+        (
+            "/api/generate",
+            json!({ "model": "no-such-model", "prompt": "Hi", "stream": false }),
+        ),
+        (
+            "/api/embed",
+            json!({ "model": "no-such-model", "input": "hello" }),
+        ),
+    ] {
+        let resp = p
+            .client
+            .post(p.url(endpoint))
+            .json(&body)
+            .send()
+            .await
+            .expect("POST unknown model");
+        assert_eq!(resp.status(), 404, "{endpoint}");
+        let err: Value = resp.json().await.expect("JSON error");
+        assert!(err["error"].is_string(), "{endpoint}: {err}");
+    }
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        received
+            .iter()
+            .all(|r| r.method.as_str() == "GET" || r.url.path().starts_with("/api/v1/models/load")),
+        "inference must not be attempted for an unknown model"
+    );
+}
@@ -1,18 +1,18 @@
 // Integration tests for /api/v0/* LM Studio native REST passthrough.
 //
-// The proxy forwards every /api/v0/* (and any /api/vN/*) request verbatim to
+// The proxy forwards every /api/v0/* and /api/v1/* request verbatim to
 // the LM Studio backend. These tests assert transparent forwarding: method,
 // path, body, and response status/headers all round-trip unchanged.
 //
-// Note: the native route guard requires the path segment after /api/ to start
-// with 'v', so /api/v0, /api/v1, etc. are forwarded; /api/tags and other
-// Ollama paths are handled by dedicated Ollama routes.
+// Note: only the v0 and v1 native versions are routed; any other /api/{x}/...
+// 404s in the router. /api/tags and other Ollama paths are handled by
+// dedicated Ollama routes.
 
 use serde_json::json;
 use wiremock::matchers::{body_partial_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 /// Mount a GET /api/v1/models stub returning a single native model for resolution.
 async fn mount_native_models(p: &crate::common::TestProxy, model_key: &str) {
@@ -467,3 +467,81 @@ async fn native_model_unload_backend_error_propagated() {
 
     assert_eq!(resp.status(), 404);
 }
+
+// ── Unsupported native versions ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn unsupported_native_version_404s_without_forwarding() {
+    let p = spawn_proxy().await;
+
+    for url in ["/api/v2/models", "/api/vfoo/chat/completions", "/api/v2"] {
+        let resp = p
+            .client
+            .post(p.url(url))
+            .json(&json!({ "model": "x" }))
+            .send()
+            .await
+            .expect("POST unsupported native version");
+        assert_eq!(resp.status(), 404, "{url}");
+    }
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        received.is_empty(),
+        "nothing may reach LM Studio: {received:?}"
+    );
+}
+
+#[tokio::test]
+async fn unsupported_native_version_404s_before_reading_the_body() {
+    let p = spawn_proxy_with_config(|c| c.max_body_size = 1024).await;
+    let big = vec![b'x'; 4096];
+
+    let rejected = p
+        .client
+        .post(p.url("/api/v2/chat/completions"))
+        .body(big.clone())
+        .send()
+        .await
+        .expect("POST /api/v2");
+    assert_eq!(
+        rejected.status(),
+        404,
+        "routing must fail before the body limit"
+    );
+
+    // A supported version reads the body and so hits the limit.
+    let read = p
+        .client
+        .post(p.url("/api/v1/chat/completions"))
+        .body(big)
+        .send()
+        .await
+        .expect("POST /api/v1");
+    assert_eq!(read.status(), 413);
+}
+
+#[tokio::test]
+async fn native_version_roots_still_forward() {
+    let p = spawn_proxy().await;
+    for version in ["v0", "v1"] {
+        Mock::given(method("GET"))
+            .and(path(format!("/api/{version}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": version })))
+            .expect(1)
+            .mount(&p.mock)
+            .await;
+    }
+
+    for version in ["v0", "v1"] {
+        let resp = p
+            .client
+            .get(p.url(&format!("/api/{version}")))
+            .send()
+            .await
+            .expect("GET native version root");
+        assert_eq!(resp.status(), 200, "{version}");
+        let body: serde_json::Value = resp.json().await.expect("json body");
+        assert_eq!(body["ok"], version);
+    }
+    p.mock.verify().await;
+}
@@ -9,7 +9,7 @@ use serde_json::json;
 use wiremock::matchers::{body_partial_json, header, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 /// Mount a GET /api/v1/models stub returning a single model whose key contains `model_key`.
 async fn mount_native_models(p: &crate::common::TestProxy, model_key: &str) {
@@ -86,6 +86,88 @@ async fn openai_models_list_backend_error_propagated() {
     assert_eq!(resp.status(), 503);
 }
 
+/// Mount a GET /v1/models stub listing one LM Studio id.
+async fn mount_openai_models(p: &crate::common::TestProxy, model_id: &str) {
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [
+                { "id": model_id, "object": "model", "owned_by": "organization_owner" }
+            ]
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
+async fn openai_model_ids(p: &crate::common::TestProxy) -> Vec<String> {
+    let body: serde_json::Value = p
+        .client
+        .get(p.url("/v1/models"))
+        .send()
+        .await
+        .expect("GET /v1/models")
+        .json()
+        .await
+        .expect("json body");
+    body["data"]
+        .as_array()
+        .expect("data array")
+        .iter()
+        .map(|entry| entry["id"].as_str().expect("id").to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn openai_models_list_appends_virtual_aliases() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "meta-llama-3.1-8b").await;
+    mount_openai_models(&p, "meta-llama-3.1-8b").await;
+
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({ "source": "meta-llama-3.1-8b", "destination": "my-llama" }))
+        .send()
+        .await
+        .expect("POST /api/copy");
+    assert_eq!(copy.status(), 200);
+
+    let body: serde_json::Value = p
+        .client
+        .get(p.url("/v1/models"))
+        .send()
+        .await
+        .expect("GET /v1/models")
+        .json()
+        .await
+        .expect("json body");
+    assert_eq!(body["object"], "list");
+    assert_eq!(
+        body["data"],
+        json!([
+            { "id": "meta-llama-3.1-8b", "object": "model", "owned_by": "organization_owner" },
+            { "id": "my-llama", "object": "model", "owned_by": "organization_owner" }
+        ])
+    );
+}
+
+#[tokio::test]
+async fn openai_models_list_uses_ollama_names_with_flag() {
+    let p = spawn_proxy_with_config(|c| c.ollama_model_ids = true).await;
+    mount_openai_models(&p, "meta-llama-3.1-8b").await;
+
+    assert_eq!(openai_model_ids(&p).await, ["meta-llama-3.1-8b:latest"]);
+}
+
+#[tokio::test]
+async fn openai_models_list_is_untouched_without_aliases() {
+    let p = spawn_proxy().await;
+    mount_openai_models(&p, "meta-llama-3.1-8b").await;
+
+    assert_eq!(openai_model_ids(&p).await, ["meta-llama-3.1-8b"]);
+}
+
 // ── POST /v1/chat/completions (non-streaming) ─────────────────────────────────
 
 #[tokio::test]
@@ -133,6 +215,159 @@ async fn openai_chat_completions_non_streaming_forwarded() {
     assert_eq!(body["object"], "chat.completion");
 }
 
+#[tokio::test]
+async fn openai_chat_completions_stop_string_is_sent_as_an_array() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "lmstudio-community/meta-llama-3.1-8b").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({ "stop": ["\n\n"] })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{ "message": { "role": "assistant", "content": "Hi!" } }]
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/chat/completions"))
+        .json(&json!({
+            "model": "lmstudio-community/meta-llama-3.1-8b",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "stop": "\n\n"
+        }))
+        .send()
+        .await
+        .expect("POST /v1/chat/completions");
+
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn openai_chat_completions_ollama_options_are_lifted() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "lmstudio-community/meta-llama-3.1-8b").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(
+            json!({ "temperature": 0.2, "max_tokens": 32 }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{ "message": { "role": "assistant", "content": "Hi!" } }]
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/chat/completions"))
+        .json(&json!({
+            "model": "lmstudio-community/meta-llama-3.1-8b",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "options": { "temperature": 0.2, "num_predict": 32, "mirostat": 1 }
+        }))
+        .send()
+        .await
+        .expect("POST /v1/chat/completions");
+    assert_eq!(resp.status(), 200);
+
+    let requests = p.mock.received_requests().await.expect("recorded requests");
+    let sent: serde_json::Value = requests
+        .iter()
+        .find(|r| r.url.path() == "/v1/chat/completions")
+        .expect("chat request")
+        .body_json()
+        .expect("JSON body");
+    assert!(sent.get("options").is_none(), "{sent}");
+    assert!(sent.get("mirostat").is_none(), "{sent}");
+}
+
+#[tokio::test]
+async fn openai_chat_completions_n_fans_out_and_merges_choices() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "lmstudio-community/meta-llama-3.1-8b").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": "Hi!" } }],
+            "usage": { "prompt_tokens": 4, "completion_tokens": 2, "total_tokens": 6 }
+        })))
+        .expect(3)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/chat/completions"))
+        .json(&json!({
+            "model": "lmstudio-community/meta-llama-3.1-8b",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "n": 3,
+            "best_of": 5,
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /v1/chat/completions");
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    let indexes: Vec<_> = body["choices"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["index"].as_u64().unwrap())
+        .collect();
+    assert_eq!(indexes, vec![0, 1, 2]);
+    assert_eq!(body["usage"]["total_tokens"], 18);
+
+    for request in p.mock.received_requests().await.unwrap() {
+        if request.url.path() == "/v1/chat/completions" {
+            let sent: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            assert!(sent.get("n").is_none() && sent.get("best_of").is_none());
+        }
+    }
+}
+
+#[tokio::test]
+async fn openai_chat_completions_streaming_with_n_is_rejected() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "lmstudio-community/meta-llama-3.1-8b").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/chat/completions"))
+        .json(&json!({
+            "model": "lmstudio-community/meta-llama-3.1-8b",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "n": 2,
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /v1/chat/completions");
+
+    assert_eq!(resp.status(), 400);
+}
+
 #[tokio::test]
 async fn openai_chat_completions_backend_error_propagated() {
     let p = spawn_proxy().await;
@@ -342,6 +577,108 @@ async fn openai_embeddings_backend_error_propagated() {
     assert_eq!(resp.status(), 422);
 }
 
+#[tokio::test]
+async fn openai_embeddings_ollama_style_name_resolved() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "text-embedding-nomic-embed-text-v1.5").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({
+            "model": "text-embedding-nomic-embed-text-v1.5",
+            "input": "hello"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [{ "object": "embedding", "index": 0, "embedding": [0.1] }]
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/embeddings"))
+        .json(&json!({ "model": "nomic-embed-text:latest", "input": "hello" }))
+        .send()
+        .await
+        .expect("POST /v1/embeddings ollama-style name");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn openai_embeddings_virtual_alias_resolved() {
+    let p = spawn_proxy().await;
+    mount_native_models(&p, "text-embedding-nomic-embed-text-v1.5").await;
+
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({
+            "source": "text-embedding-nomic-embed-text-v1.5",
+            "destination": "my-embedder"
+        }))
+        .send()
+        .await
+        .expect("POST /api/copy");
+    assert_eq!(copy.status(), 200);
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({
+            "model": "text-embedding-nomic-embed-text-v1.5"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": []
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/embeddings"))
+        .json(&json!({ "model": "my-embedder", "input": "hello" }))
+        .send()
+        .await
+        .expect("POST /v1/embeddings virtual alias");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn openai_completions_invalid_json_forwarded_raw() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": { "message": "bad body" }
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/v1/completions"))
+        .header("content-type", "application/json")
+        .body("{not json")
+        .send()
+        .await
+        .expect("POST /v1/completions invalid json");
+
+    // LM Studio's verdict is relayed; the proxy no longer rejects on its own.
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(body["error"]["message"], "bad body");
+    p.mock.verify().await;
+}
+
 // ── POST /v1/messages (Anthropic-compat passthrough) ─────────────────────────
 
 #[tokio::test]
@@ -696,3 +1033,87 @@ async fn openai_request_authorization_header_forwarded() {
 
     assert_eq!(resp.status(), 200);
 }
+
+// ── Local probe shims ─────────────────────────────────────────────────────────
+
+async fn assert_no_upstream_calls(p: &crate::common::TestProxy) {
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        received.is_empty(),
+        "shimmed probe must not reach LM Studio; got {:?}",
+        received.iter().map(|r| r.url.path()).collect::<Vec<_>>()
+    );
+}
+
+#[tokio::test]
+async fn v1_api_version_probe_answered_locally() {
+    let p = spawn_proxy().await;
+
+    let resp = p
+        .client
+        .get(p.url("/v1/api/version"))
+        .send()
+        .await
+        .expect("GET /v1/api/version");
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert!(body["version"].is_string(), "{body}");
+    assert_no_upstream_calls(&p).await;
+}
+
+#[tokio::test]
+async fn v1_health_probe_answered_locally() {
+    let p = spawn_proxy().await;
+
+    let resp = p
+        .client
+        .get(p.url("/v1/health"))
+        .send()
+        .await
+        .expect("GET /v1/health");
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(body["status"], "ok");
+    assert_no_upstream_calls(&p).await;
+}
+
+#[tokio::test]
+async fn v1_options_probe_answered_locally() {
+    let p = spawn_proxy().await;
+
+    let resp = p
+        .client
+        .request(reqwest::Method::OPTIONS, p.url("/v1/chat/completions"))
+        .send()
+        .await
+        .expect("OPTIONS /v1/chat/completions");
+
+    assert_eq!(resp.status(), 204);
+    assert!(resp.headers().get("allow").is_some());
+    assert_no_upstream_calls(&p).await;
+}
+
+#[tokio::test]
+async fn unlisted_v1_path_still_passes_through() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/v1/api/other"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "from": "lmstudio" })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .get(p.url("/v1/api/other"))
+        .send()
+        .await
+        .expect("GET /v1/api/other");
+
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.expect("json body");
+    assert_eq!(body["from"], "lmstudio");
+}
@@ -0,0 +1,176 @@
+// Integration tests for `--model-allowlist` / `--model-blocklist`.
+//
+// The catalog has two chat models and an embedder; the blocklist hides the
+// embedder everywhere except through a virtual alias.
+
+use serde_json::{Value, json};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::common::{TestProxy, spawn_proxy_with_config};
+
+fn native_model(key: &str, model_type: &str) -> Value {
+    json!({
+        "key": key,
+        "type": model_type,
+        "publisher": "test",
+        "architecture": "llama",
+        "format": "gguf",
+        "quantization": {"name": "Q4_K_M"},
+        "max_context_length": 8192,
+        "loaded_instances": [{"id": key, "config": {"context_length": 4096}}]
+    })
+}
+
+async fn spawn_filtered() -> TestProxy {
+    let p = spawn_proxy_with_config(|c| {
+        c.model_blocklist = vec!["*embed*".to_string()];
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [
+                native_model("qwen2.5-7b-instruct", "llm"),
+                native_model("llama3.1-8b-instruct", "llm"),
+                native_model("nomic-embed-text-v1.5", "embeddings"),
+            ]
+        })))
+        .mount(&p.mock)
+        .await;
+    p
+}
+
+async fn get_json(p: &TestProxy, endpoint: &str) -> Value {
+    let resp = p
+        .client
+        .get(p.url(endpoint))
+        .send()
+        .await
+        .expect("GET listing");
+    assert_eq!(resp.status(), 200, "{endpoint}");
+    resp.json().await.expect("json body")
+}
+
+async fn listed_names(p: &TestProxy, endpoint: &str) -> Vec<String> {
+    get_json(p, endpoint).await["models"]
+        .as_array()
+        .expect("models array")
+        .iter()
+        .map(|m| m["name"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn blocklisted_model_is_hidden_from_tags_and_ps() {
+    let p = spawn_filtered().await;
+    let expected = ["qwen2.5-7b-instruct:latest", "llama3.1-8b-instruct:latest"];
+    assert_eq!(listed_names(&p, "/api/tags").await, expected);
+    assert_eq!(listed_names(&p, "/api/ps").await, expected);
+}
+
+#[tokio::test]
+async fn allowlist_limits_tags_to_matching_models() {
+    let p = spawn_proxy_with_config(|c| {
+        c.model_allowlist = vec!["qwen*".to_string()];
+    })
+    .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [
+                native_model("qwen2.5-7b-instruct", "llm"),
+                native_model("llama3.1-8b-instruct", "llm"),
+            ]
+        })))
+        .mount(&p.mock)
+        .await;
+    assert_eq!(
+        listed_names(&p, "/api/tags").await,
+        ["qwen2.5-7b-instruct:latest"]
+    );
+}
+
+#[tokio::test]
+async fn blocklisted_model_cannot_be_resolved_by_name() {
+    let p = spawn_filtered().await;
+    let resp = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({"model": "nomic-embed-text-v1.5"}))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn alias_to_blocklisted_model_is_listed_and_resolvable() {
+    let p = spawn_filtered().await;
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({"source": "nomic-embed-text-v1.5", "destination": "embedder:latest"}))
+        .send()
+        .await
+        .expect("POST /api/copy");
+    assert_eq!(copy.status(), 200);
+
+    let names = listed_names(&p, "/api/tags").await;
+    assert!(names.contains(&"embedder:latest".to_string()), "{names:?}");
+    assert!(!names.iter().any(|n| n.starts_with("nomic")), "{names:?}");
+
+    let show = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({"model": "embedder:latest"}))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(show.status(), 200);
+
+    // The alias does not unhide the model under its own name.
+    let hidden = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({"model": "nomic-embed-text-v1.5"}))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(hidden.status(), 404);
+}
+
+#[tokio::test]
+async fn openai_and_native_model_lists_are_filtered() {
+    let p = spawn_filtered().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [
+                {"id": "qwen2.5-7b-instruct", "object": "model"},
+                {"id": "nomic-embed-text-v1.5", "object": "model"}
+            ]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let openai = get_json(&p, "/v1/models").await;
+    let ids: Vec<&str> = openai["data"]
+        .as_array()
+        .expect("data array")
+        .iter()
+        .filter_map(|m| m["id"].as_str())
+        .collect();
+    assert_eq!(ids, ["qwen2.5-7b-instruct"]);
+    assert_eq!(openai["object"], "list");
+
+    let native = get_json(&p, "/api/v1/models").await;
+    let keys: Vec<&str> = native["models"]
+        .as_array()
+        .expect("models array")
+        .iter()
+        .filter_map(|m| m["key"].as_str())
+        .collect();
+    assert_eq!(keys, ["qwen2.5-7b-instruct", "llama3.1-8b-instruct"]);
+}
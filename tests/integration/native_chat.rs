@@ -118,6 +118,40 @@ async fn non_streaming_routes_to_native_and_converts_response() {
     assert!(body["created_at"].is_string());
 }
 
+#[tokio::test]
+async fn non_streaming_without_input_tokens_estimates_from_native_input() {
+    let p = spawn_proxy_with_native().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/chat"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "output": [{ "type": "message", "content": "Sure." }]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let long_prompt = "please summarise this paragraph for me ".repeat(20);
+    let body: Value = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": long_prompt }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat (native)")
+        .json()
+        .await
+        .expect("JSON body");
+
+    // Counted on the `input` sent, not a fixed placeholder.
+    let prompt_eval_count = body["prompt_eval_count"].as_u64().expect("a count");
+    assert!(prompt_eval_count > 100, "{body}");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Integrations passthrough
 // ═══════════════════════════════════════════════════════════════════════════
@@ -6,11 +6,12 @@
 // a single loaded model whose key contains the substring the Ollama name
 // resolves to.
 
+use ollama_lmstudio_proxy::config::{ReasoningMode, ResolutionMode};
 use serde_json::{Value, json};
-use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::matchers::{body_partial_json, method, path, path_regex};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ── model-catalog helpers ───────────────────────────────────────────────────
 
@@ -873,6 +874,109 @@ async fn tools_forwarded_and_tool_calls_translated_to_ollama() {
     assert_eq!(body["done_reason"], "stop");
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// 9b. two-turn tool conversation: Ollama-shaped history is paired for LM Studio
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn two_turn_tool_conversation_pairs_results_with_call_ids() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    // Turn 1: the model asks for the same tool twice in parallel.
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [
+                        {"index": 0, "id": "call_lon", "type": "function",
+                         "function": {"name": "get_weather", "arguments": "{\"location\":\"London\"}"}},
+                        {"index": 1, "id": "call_par", "type": "function",
+                         "function": {"name": "get_weather", "arguments": "{\"location\":\"Paris\"}"}}
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })))
+        .up_to_n_times(1)
+        .mount(&p.mock)
+        .await;
+    // Turn 2: the final answer.
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lm_chat_response("London 15°C, Paris 18°C.", "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let user = json!({ "role": "user", "content": "Weather in London and Paris?" });
+    let first: Value = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [user.clone()],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat turn 1")
+        .json()
+        .await
+        .expect("JSON");
+    let assistant = first["message"].clone();
+    assert_eq!(assistant["tool_calls"].as_array().map(Vec::len), Some(2));
+
+    // Turn 2: echo the assistant message back as Ollama clients do, followed by
+    // one tool result per call, identified only by tool_name.
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [
+                user,
+                assistant,
+                { "role": "tool", "tool_name": "get_weather", "content": "15°C" },
+                { "role": "tool", "tool_name": "get_weather", "content": "18°C" }
+            ],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat turn 2");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON");
+    assert_eq!(body["message"]["content"], "London 15°C, Paris 18°C.");
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let second: Value = received
+        .iter()
+        .filter(|r| r.url.path() == "/api/v0/chat/completions")
+        .nth(1)
+        .map(|r| serde_json::from_slice(&r.body).expect("forwarded JSON"))
+        .expect("second chat request forwarded");
+    let messages = second["messages"].as_array().expect("messages");
+    let calls = messages[1]["tool_calls"].as_array().expect("tool_calls");
+    assert_eq!(calls.len(), 2);
+    for call in calls {
+        assert_eq!(call["type"], "function");
+        assert!(call["function"]["arguments"].is_string(), "{call}");
+    }
+    let call_ids: Vec<&Value> = calls.iter().map(|c| &c["id"]).collect();
+    assert_ne!(call_ids[0], call_ids[1], "synthesized ids must be distinct");
+    assert_eq!(&messages[2]["tool_call_id"], call_ids[0]);
+    assert_eq!(&messages[3]["tool_call_id"], call_ids[1]);
+    assert_eq!(messages[2]["name"], "get_weather");
+    assert!(messages[2].get("tool_name").is_none());
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 10. format JSON schema (object) → structured output forwarded
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1012,6 +1116,110 @@ async fn think_flag_forwarded_and_reasoning_in_message() {
     );
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// 12b. reasoning_mode reshapes message.thinking on the non-streaming path
+// ═══════════════════════════════════════════════════════════════════════════
+
+async fn mount_reasoning_reply(proxy: &crate::common::TestProxy) {
+    mount_model_catalog(proxy, "llama3.1-8b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": "42.",
+                    "reasoning_content": "6*7 is 42. "
+                },
+                "finish_reason": "stop"
+            }]
+        })))
+        .mount(&proxy.mock)
+        .await;
+}
+
+async fn post_reasoning_chat(proxy: &crate::common::TestProxy, options: Value) -> Value {
+    let resp = proxy
+        .client
+        .post(proxy.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "What is 6*7?" }],
+            "stream": false,
+            "options": options
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat reasoning_mode");
+    assert_eq!(resp.status(), 200);
+    resp.json().await.expect("JSON")
+}
+
+#[tokio::test]
+async fn reasoning_mode_strip_drops_thinking() {
+    let p = spawn_proxy().await;
+    mount_reasoning_reply(&p).await;
+
+    let body = post_reasoning_chat(&p, json!({ "reasoning_mode": "strip" })).await;
+    assert_eq!(body["message"]["content"], "42.");
+    assert!(body["message"].get("thinking").is_none(), "{body}");
+}
+
+#[tokio::test]
+async fn server_reasoning_mode_merge_prepends_thinking_to_content() {
+    let p = spawn_proxy_with_config(|c| c.reasoning_mode = ReasoningMode::Merge).await;
+    mount_reasoning_reply(&p).await;
+
+    let body = post_reasoning_chat(&p, json!({})).await;
+    assert_eq!(body["message"]["content"], "6*7 is 42. 42.");
+    assert!(body["message"].get("thinking").is_none(), "{body}");
+}
+
+#[tokio::test]
+async fn reasoning_mode_is_not_forwarded_upstream() {
+    let p = spawn_proxy().await;
+    mount_reasoning_reply(&p).await;
+
+    post_reasoning_chat(&p, json!({ "reasoning_mode": "separate" })).await;
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let upstream = received
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/chat/completions")
+        .expect("chat/completions request captured");
+    let upstream_body: Value = serde_json::from_slice(&upstream.body).expect("JSON");
+    assert!(upstream_body.get("reasoning_mode").is_none());
+}
+
+#[tokio::test]
+async fn invalid_reasoning_mode_is_rejected() {
+    let p = spawn_proxy().await;
+    mount_reasoning_reply(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "hi" }],
+            "stream": false,
+            "options": { "reasoning_mode": "verbose" }
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat bad reasoning_mode");
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("JSON");
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap_or("")
+            .contains("reasoning_mode"),
+        "{body}"
+    );
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 13. per-message images converted to multimodal content parts
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1053,6 +1261,65 @@ async fn per_message_images_converted_to_multimodal_content() {
     assert_eq!(body["message"]["content"], "I see a cat.");
 }
 
+#[tokio::test]
+async fn per_message_images_reach_lm_studio_as_content_parts() {
+    let p = spawn_proxy().await;
+    mount_vision_catalog(&p, "llava-7b-v1.6").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("ok", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+    let jpeg = "/9j/4AAQSkZJRgABAQAAAQABAAD";
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llava-7b:latest",
+            "messages": [
+                { "role": "user", "content": "Compare these", "images": [png, jpeg] },
+                { "role": "assistant", "content": "They differ." },
+                { "role": "user", "content": "And this one?", "images": [png] },
+                { "role": "user", "content": "Thanks" }
+            ],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat images");
+    assert_eq!(resp.status(), 200);
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let chat = received
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/chat/completions")
+        .expect("chat request reached LM Studio");
+    let upstream: Value = serde_json::from_slice(&chat.body).unwrap();
+    let messages = upstream["messages"].as_array().unwrap();
+
+    let image = |mime: &str, b64: &str| json!({"type": "image_url", "image_url": {"url": format!("data:{mime};base64,{b64}")}});
+    assert_eq!(
+        messages[0]["content"],
+        json!([
+            {"type": "text", "text": "Compare these"},
+            image("image/png", png),
+            image("image/jpeg", jpeg),
+        ])
+    );
+    assert!(messages[0].get("images").is_none());
+    assert_eq!(messages[1]["content"], "They differ.");
+    assert_eq!(
+        messages[2]["content"],
+        json!([{"type": "text", "text": "And this one?"}, image("image/png", png)])
+    );
+    // text-only messages keep their plain string content
+    assert_eq!(messages[3]["content"], "Thanks");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 14. keep_alive as duration string accepted
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1165,6 +1432,52 @@ async fn missing_messages_returns_400() {
     assert_eq!(resp.status(), 400);
 }
 
+#[tokio::test]
+async fn multiple_candidates_are_rejected() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    for body in [
+        json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false,
+            "n": 2
+        }),
+        json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false,
+            "options": { "n": 3 }
+        }),
+    ] {
+        let resp = p
+            .client
+            .post(p.url("/api/chat"))
+            .json(&body)
+            .send()
+            .await
+            .expect("POST /api/chat n > 1");
+        assert_eq!(resp.status(), 400);
+        let error: Value = resp.json().await.expect("JSON");
+        assert!(
+            error["error"]
+                .as_str()
+                .unwrap_or("")
+                .contains("/v1/chat/completions"),
+            "{error}"
+        );
+    }
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        !received
+            .iter()
+            .any(|r| r.url.path().ends_with("/chat/completions")),
+        "a rejected request must not reach LM Studio"
+    );
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 17. missing model field → 400
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1215,6 +1528,95 @@ async fn unknown_model_returns_404() {
     assert_eq!(resp.status(), 404);
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// 18b. --resolution exact: only a case-insensitive id match resolves
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn exact_resolution_rejects_close_but_not_equal_name() {
+    let p = spawn_proxy_with_config(|c| c.resolution = ResolutionMode::Exact).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat exact resolution");
+
+    assert_eq!(resp.status(), 404);
+    let body: Value = resp.json().await.expect("JSON");
+    assert!(
+        body["error"].as_str().unwrap_or("").contains("exact"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn unresolved_model_error_suggests_close_names() {
+    let p = spawn_proxy_with_config(|c| c.resolution = ResolutionMode::Exact).await;
+    mount_model_catalog(&p, "meta-llama-3.1-8b-instruct").await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat unresolved model");
+
+    assert_eq!(resp.status(), 404);
+    let body: Value = resp.json().await.expect("JSON");
+    assert_eq!(body["suggestions"], json!(["meta-llama-3.1-8b-instruct"]));
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap_or("")
+            .contains("did you mean: meta-llama-3.1-8b-instruct"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn exact_resolution_accepts_matching_id() {
+    let p = spawn_proxy_with_config(|c| c.resolution = ResolutionMode::Exact).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .and(body_partial_json(
+            json!({ "model": "llama3.1-8b-instruct" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Hello!", "stop")))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "LLaMA3.1-8B-Instruct:latest",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat exact resolution");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 19. finish_reason "length" maps into done_reason:"length"
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1915,3 +2317,874 @@ async fn chat_keep_alive_zero_no_messages_streaming_returns_ndjson_done_chunk()
     assert_no_chat_inference_calls(&p).await;
     assert!(wait_for_unload_call(&p).await);
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --forward-header: allowlisted client headers reach LM Studio
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn allowlisted_client_headers_are_forwarded_to_lm_studio() {
+    let p = spawn_proxy_with_config(|c| {
+        c.forward_header = vec!["X-Request-Id".to_string(), "x-tenant".to_string()];
+    })
+    .await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("ok", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .header("X-Request-Id", "req-123")
+        .header("X-Tenant", "acme")
+        .header("X-Internal-Secret", "do-not-forward")
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let chat = received
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/chat/completions")
+        .expect("chat request reached LM Studio");
+    assert_eq!(chat.headers.get("x-request-id").unwrap(), "req-123");
+    assert_eq!(chat.headers.get("x-tenant").unwrap(), "acme");
+    assert!(chat.headers.get("x-internal-secret").is_none());
+}
+
+#[tokio::test]
+async fn client_headers_are_not_forwarded_by_default() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("ok", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    p.client
+        .post(p.url("/api/chat"))
+        .header("X-Request-Id", "req-123")
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        received
+            .iter()
+            .all(|r| r.headers.get("x-request-id").is_none())
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --default-system-prompt: request > virtual model > global default
+// ═══════════════════════════════════════════════════════════════════════════
+
+async fn upstream_chat_messages(p: &crate::common::TestProxy) -> Vec<Value> {
+    p.mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == "/api/v0/chat/completions")
+        .map(|r| {
+            let body: Value = serde_json::from_slice(&r.body).expect("JSON upstream body");
+            body["messages"].clone()
+        })
+        .collect()
+}
+
+async fn spawn_with_default_system_prompt() -> crate::common::TestProxy {
+    let p = spawn_proxy_with_config(|c| {
+        c.default_system_prompt = Some("Answer concisely.".to_string())
+    })
+    .await;
+    mount_model_catalog(&p, "llama3.2-3b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Ok.", "stop")))
+        .mount(&p.mock)
+        .await;
+    p
+}
+
+async fn post_chat(p: &crate::common::TestProxy, model: &str, body: Value) {
+    let mut body = body;
+    body["model"] = json!(model);
+    body["stream"] = json!(false);
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&body)
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn default_system_prompt_is_added_when_request_has_none() {
+    let p = spawn_with_default_system_prompt().await;
+    post_chat(
+        &p,
+        "llama3.2:3b",
+        json!({"messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+
+    let messages = &upstream_chat_messages(&p).await[0];
+    assert_eq!(
+        messages[0],
+        json!({"role": "system", "content": "Answer concisely."})
+    );
+    assert_eq!(messages[1]["content"], "Hi");
+}
+
+#[tokio::test]
+async fn request_system_message_overrides_default_system_prompt() {
+    let p = spawn_with_default_system_prompt().await;
+    post_chat(
+        &p,
+        "llama3.2:3b",
+        json!({"messages": [
+            {"role": "system", "content": "Be verbose."},
+            {"role": "user", "content": "Hi"}
+        ]}),
+    )
+    .await;
+
+    let messages = upstream_chat_messages(&p).await[0].clone();
+    assert_eq!(messages.as_array().map(Vec::len), Some(2));
+    assert!(
+        !messages.to_string().contains("Answer concisely."),
+        "{messages}"
+    );
+}
+
+#[tokio::test]
+async fn virtual_model_system_prompt_overrides_default_system_prompt() {
+    let p = spawn_with_default_system_prompt().await;
+    let create = p
+        .client
+        .post(p.url("/api/create"))
+        .json(&json!({
+            "model": "pirate:latest",
+            "from": "llama3.2:3b",
+            "system": "You are a pirate.",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/create");
+    assert_eq!(create.status(), 200);
+
+    post_chat(
+        &p,
+        "pirate:latest",
+        json!({"messages": [{"role": "user", "content": "Hi"}]}),
+    )
+    .await;
+
+    let messages = &upstream_chat_messages(&p).await[0];
+    assert_eq!(
+        messages[0],
+        json!({"role": "system", "content": "You are a pirate."})
+    );
+    assert!(!messages.to_string().contains("Answer concisely."));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// logprobs: options forwarded, response mapped (non-streaming and streaming)
+// ═══════════════════════════════════════════════════════════════════════════
+
+fn canned_logprobs() -> Value {
+    json!({ "content": [{
+        "token": "OK",
+        "logprob": -0.25,
+        "bytes": [79, 75],
+        "top_logprobs": [{ "token": "OK", "logprob": -0.25, "bytes": [79, 75] }]
+    }] })
+}
+
+#[tokio::test]
+async fn options_logprobs_forwarded_and_mapped_into_response() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    let mut lm_response = lm_chat_response("OK", "stop");
+    lm_response["choices"][0]["logprobs"] = canned_logprobs();
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .and(body_partial_json(
+            json!({ "logprobs": true, "top_logprobs": 1 }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_response))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "options": { "logprobs": true, "top_logprobs": 1 },
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["logprobs"], canned_logprobs()["content"]);
+}
+
+#[tokio::test]
+async fn streaming_chunks_carry_their_logprobs() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    let chunk = json!({
+        "id": "chatcmpl-stream",
+        "object": "chat.completion.chunk",
+        "choices": [{
+            "index": 0,
+            "delta": { "content": "OK" },
+            "logprobs": canned_logprobs(),
+            "finish_reason": "stop"
+        }]
+    });
+    let sse = format!("data: {}\n\ndata: [DONE]\n\n", chunk);
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(sse.into_bytes(), "text/event-stream"),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let text = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "logprobs": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat")
+        .text()
+        .await
+        .expect("body text");
+
+    let chunks = parse_ndjson(&text);
+    let content_chunk = chunks
+        .iter()
+        .find(|c| c["message"]["content"] == "OK")
+        .expect("content chunk");
+    assert_eq!(content_chunk["logprobs"], canned_logprobs()["content"]);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --auto-pull-missing: download a catalog model, then answer
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// LM Studio without `model_key` until a download of it completes; the
+/// status endpoint reports `final_status` for the download.
+async fn mount_missing_model(p: &crate::common::TestProxy, model_key: &str, final_status: &str) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": [] })))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&p.mock)
+        .await;
+    mount_model_catalog(p, model_key).await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .and(body_partial_json(json!({ "model": model_key })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "job_id": "job-auto",
+            "status": "downloading",
+            "total_size_bytes": 1000,
+            "downloaded_bytes": 0
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/api/v1/models/download/status/job-auto$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "job_id": "job-auto",
+            "status": final_status,
+            "total_size_bytes": 1000,
+            "downloaded_bytes": if final_status == "completed" { 1000 } else { 10 }
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
+#[tokio::test]
+async fn auto_pull_downloads_missing_model_then_answers() {
+    let p = spawn_proxy_with_config(|c| c.auto_pull_missing = true).await;
+    mount_missing_model(&p, "qwen/qwen3-8b", "completed").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("hi", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "qwen/qwen3-8b",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["message"]["content"], "hi");
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn auto_pull_streams_progress_before_the_reply() {
+    let p = spawn_proxy_with_config(|c| c.auto_pull_missing = true).await;
+    mount_missing_model(&p, "qwen/qwen3-8b", "completed").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            sse_chat_body(&["Hello"], "stop").into_bytes(),
+            "text/event-stream",
+        ))
+        .mount(&p.mock)
+        .await;
+
+    let text = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "qwen/qwen3-8b",
+            "messages": [{ "role": "user", "content": "Hello" }]
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat")
+        .text()
+        .await
+        .expect("body text");
+    let chunks = parse_ndjson(&text);
+    assert_eq!(chunks[0]["status"], "downloading", "{text}");
+    assert_eq!(chunks[0]["total"], 1000);
+    assert!(
+        chunks.iter().any(|c| c == &json!({"status": "success"})),
+        "{text}"
+    );
+    assert_eq!(chunks.last().unwrap()["done"], true, "{text}");
+}
+
+#[tokio::test]
+async fn auto_pull_non_streaming_times_out_with_job_id() {
+    let p = spawn_proxy_with_config(|c| {
+        c.auto_pull_missing = true;
+        c.auto_pull_wait_seconds = 1;
+    })
+    .await;
+    mount_missing_model(&p, "qwen/qwen3-8b", "downloading").await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({ "model": "qwen/qwen3-8b", "prompt": "Hi", "stream": false }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 503);
+    let body: Value = resp.json().await.expect("json body");
+    let error = body["error"].as_str().unwrap_or_default();
+    assert!(
+        error.contains("still downloading") && error.contains("job-auto"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn auto_pull_leaves_bare_missing_names_as_404() {
+    let p = spawn_proxy_with_config(|c| c.auto_pull_missing = true).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "mistral-nemo",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 404);
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(
+        !received
+            .iter()
+            .any(|r| r.url.path() == "/api/v1/models/download")
+    );
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --expose-stats / ?stats=1: LM Studio's raw stats as `proxy_stats`
+// ═══════════════════════════════════════════════════════════════════════════
+
+async fn chat_with_stats(p: &crate::common::TestProxy, endpoint: &str) -> Value {
+    mount_model_catalog(p, "llama3.1-8b-instruct").await;
+    let mut lm_body = lm_chat_response("Hello there!", "stop");
+    lm_body.as_object_mut().unwrap().insert(
+        "stats".to_string(),
+        json!({
+            "tokens_per_second": 50.0,
+            "time_to_first_token": 0.2,
+            "generation_time": 0.4,
+            "accepted_draft_tokens_count": 7
+        }),
+    );
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_body))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url(endpoint))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    assert_eq!(resp.status(), 200);
+    resp.json().await.expect("JSON body")
+}
+
+#[tokio::test]
+async fn stats_are_not_exposed_by_default() {
+    let p = spawn_proxy().await;
+    let body = chat_with_stats(&p, "/api/chat").await;
+    assert!(body.get("proxy_stats").is_none(), "{body}");
+}
+
+#[tokio::test]
+async fn stats_query_exposes_the_raw_stats_block() {
+    let p = spawn_proxy().await;
+    let body = chat_with_stats(&p, "/api/chat?stats=1").await;
+    assert_eq!(body["proxy_stats"]["tokens_per_second"], 50.0);
+    assert_eq!(body["proxy_stats"]["accepted_draft_tokens_count"], 7);
+}
+
+#[tokio::test]
+async fn expose_stats_flag_exposes_the_raw_stats_block() {
+    let p = spawn_proxy_with_config(|c| c.expose_stats = true).await;
+    let body = chat_with_stats(&p, "/api/chat").await;
+    assert_eq!(body["proxy_stats"]["time_to_first_token"], 0.2);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Several loaded instances of one model: `model@instance` / options.instance_id
+// ═══════════════════════════════════════════════════════════════════════════
+
+async fn mount_two_instances(p: &crate::common::TestProxy, upstream_model: &str) {
+    let mut entry = loaded_model_entry("llama3.1-8b-instruct");
+    entry["loaded_instances"] = json!([
+        { "id": "llama3.1-8b-instruct", "config": { "context_length": 4096 } },
+        { "id": "llama3.1-8b-instruct:2", "config": { "context_length": 16384 } }
+    ]);
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": [entry] })))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .and(body_partial_json(json!({ "model": upstream_model })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Hi", "stop")))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+}
+
+async fn chat_status(p: &crate::common::TestProxy, body: Value) -> u16 {
+    p.client
+        .post(p.url("/api/chat"))
+        .json(&body)
+        .send()
+        .await
+        .expect("POST /api/chat")
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn instance_suffix_targets_that_instance() {
+    let p = spawn_proxy().await;
+    mount_two_instances(&p, "llama3.1-8b-instruct:2").await;
+
+    let status = chat_status(
+        &p,
+        json!({
+            "model": "llama3.1-8b-instruct@llama3.1-8b-instruct:2",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn instance_id_option_targets_that_instance() {
+    let p = spawn_proxy().await;
+    mount_two_instances(&p, "llama3.1-8b-instruct:2").await;
+
+    let status = chat_status(
+        &p,
+        json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "options": { "instance_id": "llama3.1-8b-instruct:2" },
+            "stream": false
+        }),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn no_instance_sends_the_model_key() {
+    let p = spawn_proxy().await;
+    mount_two_instances(&p, "llama3.1-8b-instruct").await;
+
+    let status = chat_status(
+        &p,
+        json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn unknown_instance_id_is_not_found() {
+    let p = spawn_proxy().await;
+    let mut entry = loaded_model_entry("llama3.1-8b-instruct");
+    entry["loaded_instances"] = json!([{ "id": "llama3.1-8b-instruct" }]);
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "models": [entry] })))
+        .mount(&p.mock)
+        .await;
+
+    let status = chat_status(
+        &p,
+        json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "options": { "instance_id": "nope" },
+            "stream": false
+        }),
+    )
+    .await;
+    assert_eq!(status, 404);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --context-guard
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// A message estimated at the loaded model's whole 4096-token context.
+fn context_filling_text() -> String {
+    "abcd".repeat(4096)
+}
+
+async fn upstream_chat_messages(proxy: &crate::common::TestProxy) -> Vec<Value> {
+    let requests = proxy.mock.received_requests().await.unwrap_or_default();
+    let chat = requests
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/chat/completions")
+        .expect("chat request sent upstream");
+    let body: Value = serde_json::from_slice(&chat.body).unwrap();
+    body["messages"].as_array().cloned().unwrap_or_default()
+}
+
+#[tokio::test]
+async fn context_guard_rejects_an_overflowing_history() {
+    let p = spawn_proxy_with_config(|c| c.context_guard = true).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("x", "stop")))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": context_filling_text() }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    let error = body["error"].as_str().unwrap();
+    assert!(error.contains("> 4096 tokens"), "{error}");
+}
+
+#[tokio::test]
+async fn context_guard_off_forwards_an_overflowing_history() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("x", "stop")))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": context_filling_text() }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert!(body.get("truncated").is_none());
+}
+
+#[tokio::test]
+async fn context_guard_truncates_history_when_asked() {
+    let p = spawn_proxy_with_config(|c| c.context_guard = true).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("x", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "user", "content": context_filling_text() },
+                { "role": "assistant", "content": "Done." },
+                { "role": "user", "content": "Hi" }
+            ],
+            "options": { "truncate_history": true },
+            "stream": false
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["truncated"], json!(true));
+
+    let messages = upstream_chat_messages(&p).await;
+    let contents: Vec<&str> = messages
+        .iter()
+        .map(|m| m["content"].as_str().unwrap_or_default())
+        .collect();
+    assert_eq!(contents, ["Be brief.", "Done.", "Hi"]);
+}
+
+#[tokio::test]
+async fn context_guard_marks_the_final_stream_chunk_truncated() {
+    let p = spawn_proxy_with_config(|c| c.context_guard = true).await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_chat_body(&["Hel", "lo"], "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [
+                { "role": "user", "content": context_filling_text() },
+                { "role": "user", "content": "Hi" }
+            ],
+            "options": { "truncate_history": true }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let chunks = parse_ndjson(&resp.text().await.unwrap());
+    let (last, rest) = chunks.split_last().unwrap();
+    assert_eq!(last["done"], json!(true));
+    assert_eq!(last["truncated"], json!(true));
+    assert!(rest.iter().all(|c| c.get("truncated").is_none()));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// OpenAI-style upstream errors become Ollama's flat `error` string
+// ═══════════════════════════════════════════════════════════════════════════
+
+async fn mount_openai_error(proxy: &crate::common::TestProxy) {
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": {
+                "message": "context length exceeded",
+                "type": "invalid_request_error",
+                "code": "context_length_exceeded"
+            }
+        })))
+        .mount(&proxy.mock)
+        .await;
+}
+
+#[tokio::test]
+async fn openai_error_becomes_flat_ollama_error() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    mount_openai_error(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }],
+            "stream": false
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], json!("context length exceeded"));
+}
+
+#[tokio::test]
+async fn streaming_openai_error_becomes_flat_ollama_error() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+    mount_openai_error(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [{ "role": "user", "content": "Hi" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["error"], json!("context length exceeded"));
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// Streamed chat without `usage` estimates counts from the text itself
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn streaming_chat_without_usage_estimates_counts_from_text() {
+    let p = spawn_proxy().await;
+    mount_model_catalog(&p, "llama3.1-8b-instruct").await;
+
+    // Two chunks carrying 400 bytes of reply between them.
+    let half = "x".repeat(200);
+    let sse = sse_chat_body(&[&half, &half], "stop");
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(sse.into_bytes(), "text/event-stream"),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3.1:8b",
+            "messages": [
+                { "role": "system", "content": "word ".repeat(200) },
+                { "role": "user", "content": "word ".repeat(200) }
+            ],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat stream:true");
+    assert_eq!(resp.status(), 200);
+    let chunks = parse_ndjson(&resp.text().await.expect("body text"));
+    let last = chunks.last().expect("final chunk");
+    assert_eq!(last["done"], true);
+
+    // 2000 bytes of message content is ~500 tokens, not a per-message count.
+    let prompt = last["prompt_eval_count"]
+        .as_u64()
+        .expect("prompt_eval_count");
+    assert!(
+        (400..=700).contains(&prompt),
+        "prompt_eval_count = {prompt}"
+    );
+    // 400 bytes of reply is ~100 tokens, not the 2 chunks it arrived in.
+    assert_eq!(last["eval_count"], 100);
+}
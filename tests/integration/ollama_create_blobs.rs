@@ -11,7 +11,7 @@ use sha2::{Digest, Sha256};
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -109,6 +109,64 @@ async fn blob_head_present_after_upload_returns_200() {
     );
 }
 
+#[tokio::test]
+async fn blob_head_reports_size_in_content_length() {
+    let p = spawn_proxy().await;
+
+    let data = b"sized fake model blob";
+    let digest = sha256_digest(data);
+    let url = p.url(&format!("/api/blobs/{digest}"));
+
+    p.client
+        .post(&url)
+        .body(data.to_vec())
+        .send()
+        .await
+        .expect("POST /api/blobs upload");
+
+    let head = p
+        .client
+        .head(&url)
+        .send()
+        .await
+        .expect("HEAD /api/blobs/:digest");
+
+    assert_eq!(head.status(), 200);
+    let content_length = head
+        .headers()
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    assert_eq!(content_length, Some(data.len()));
+}
+
+#[tokio::test]
+async fn blob_upload_accepts_uppercase_hex_digest() {
+    let p = spawn_proxy().await;
+
+    let data = b"uppercase digest blob";
+    let digest = sha256_digest(data);
+    let upper = format!("sha256:{}", digest["sha256:".len()..].to_ascii_uppercase());
+
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{upper}")))
+        .body(data.to_vec())
+        .send()
+        .await
+        .expect("POST /api/blobs uppercase digest");
+    assert_eq!(resp.status(), 201);
+
+    // Stored under the canonical lowercase name.
+    let head = p
+        .client
+        .head(p.url(&format!("/api/blobs/{digest}")))
+        .send()
+        .await
+        .expect("HEAD /api/blobs lowercase digest");
+    assert_eq!(head.status(), 200);
+}
+
 #[tokio::test]
 async fn blob_head_absent_returns_404() {
     let p = spawn_proxy().await;
@@ -236,6 +294,130 @@ async fn create_from_existing_model_registers_virtual() {
     );
 }
 
+#[tokio::test]
+async fn create_stream_reports_steps_in_order() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let text = p
+        .client
+        .post(p.url("/api/create"))
+        .json(&json!({"model": "my-custom:v1", "from": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("POST /api/create")
+        .text()
+        .await
+        .expect("stream body");
+
+    let statuses: Vec<String> = text
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("ndjson line"))
+        .map(|chunk| chunk["status"].as_str().unwrap_or_default().to_string())
+        .collect();
+    assert_eq!(
+        statuses,
+        vec!["reading model metadata", "creating alias", "success"]
+    );
+}
+
+#[tokio::test]
+async fn create_stream_reports_persistence_failure() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    // A directory where the store file belongs makes the final rename fail.
+    std::fs::create_dir_all(p.state_dir().join("virtual_models.json")).unwrap();
+
+    let text = p
+        .client
+        .post(p.url("/api/create"))
+        .json(&json!({"model": "my-custom:v1", "from": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("POST /api/create")
+        .text()
+        .await
+        .expect("stream body");
+
+    let chunks: Vec<Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("ndjson line"))
+        .collect();
+    let last = chunks.last().expect("at least one chunk");
+    assert_eq!(last["status"], "failed", "{text}");
+    assert!(last["error"].as_str().is_some_and(|e| !e.is_empty()));
+    assert!(
+        chunks.iter().all(|c| c["status"] != "success"),
+        "a failed create must not report success: {text}"
+    );
+}
+
+async fn create_with_parameters(p: &crate::common::TestProxy) -> reqwest::Response {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    p.client
+        .post(p.url("/api/create"))
+        .json(&json!({
+            "model": "my-custom:v1",
+            "from": "llama3.2:3b",
+            "parameters": {"temperatur": 0.2, "top_k": 20},
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/create")
+}
+
+#[tokio::test]
+async fn create_warns_about_unknown_parameters() {
+    let p = spawn_proxy().await;
+    let resp = create_with_parameters(&p).await;
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["status"], "success");
+    let warnings = body["warnings"].as_array().expect("warnings array");
+    assert_eq!(warnings.len(), 1, "{body}");
+    assert!(warnings[0].as_str().unwrap().contains("temperatur"));
+}
+
+#[tokio::test]
+async fn strict_params_rejects_unknown_parameters() {
+    let p = spawn_proxy_with_config(|c| c.strict_params = true).await;
+    let resp = create_with_parameters(&p).await;
+
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("json body");
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("temperatur"),
+        "{body}"
+    );
+}
+
 #[tokio::test]
 async fn create_result_appears_in_api_tags() {
     let p = spawn_proxy().await;
@@ -461,6 +643,91 @@ async fn create_from_missing_source_returns_error() {
     );
 }
 
+// ---------------------------------------------------------------------------
+// POST /api/blobs/:digest — --max-blob-size
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn blob_at_the_limit_is_stored_and_one_byte_over_gets_413() {
+    let p = spawn_proxy_with_config(|c| c.max_blob_size = 64).await;
+
+    let fits = vec![b'a'; 64];
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{}", sha256_digest(&fits))))
+        .body(fits)
+        .send()
+        .await
+        .expect("POST /api/blobs at the limit");
+    assert_eq!(resp.status(), 201);
+
+    let over = vec![b'b'; 65];
+    let digest = sha256_digest(&over);
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{digest}")))
+        .body(over)
+        .send()
+        .await
+        .expect("POST /api/blobs over the limit");
+    assert_eq!(resp.status(), 413);
+    let err: Value = resp.json().await.expect("413 body must be JSON");
+    let message = err["error"].as_str().unwrap_or_default();
+    assert!(message.contains("65 bytes"), "{err}");
+    assert!(message.contains("--max-blob-size"), "{err}");
+
+    let head = p
+        .client
+        .head(p.url(&format!("/api/blobs/{digest}")))
+        .send()
+        .await
+        .expect("HEAD /api/blobs");
+    assert_eq!(head.status(), 404, "a refused blob must not be stored");
+}
+
+#[tokio::test]
+async fn chunked_blob_over_the_limit_is_cut_off_with_413() {
+    let p = spawn_proxy_with_config(|c| c.max_blob_size = 64).await;
+
+    let data = vec![b'c'; 96];
+    let digest = sha256_digest(&data);
+    // No Content-Length: the limit can only be enforced while streaming.
+    let chunks: Vec<Result<Vec<u8>, std::io::Error>> =
+        data.chunks(32).map(|c| Ok(c.to_vec())).collect();
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{digest}")))
+        .body(reqwest::Body::wrap_stream(futures_util::stream::iter(
+            chunks,
+        )))
+        .send()
+        .await
+        .expect("POST /api/blobs chunked");
+    assert_eq!(resp.status(), 413);
+
+    let head = p
+        .client
+        .head(p.url(&format!("/api/blobs/{digest}")))
+        .send()
+        .await
+        .expect("HEAD /api/blobs");
+    assert_eq!(head.status(), 404);
+}
+
+#[tokio::test]
+async fn blobs_are_not_bound_by_max_body_size() {
+    let p = spawn_proxy_with_config(|c| c.max_body_size = 16).await;
+    let data = vec![b'd'; 1024];
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{}", sha256_digest(&data))))
+        .body(data)
+        .send()
+        .await
+        .expect("POST /api/blobs");
+    assert_eq!(resp.status(), 201);
+}
+
 // ---------------------------------------------------------------------------
 // POST /api/create — "files" field with blob digest reference
 // ---------------------------------------------------------------------------
@@ -634,3 +901,396 @@ async fn create_stream_chunks_have_no_internal_fields() {
         );
     }
 }
+
+// ---------------------------------------------------------------------------
+// POST /api/create — alias derived from an alias follows its parent
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn child_alias_follows_repointed_parent() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![
+            native_model("base-model:7b"),
+            native_model("other-model:7b"),
+        ])))
+        .mount(&p.mock)
+        .await;
+
+    let create = |model: &'static str, from: &'static str| {
+        p.client
+            .post(p.url("/api/create"))
+            .json(&json!({"model": model, "from": from, "stream": false}))
+            .send()
+    };
+
+    assert_eq!(
+        create("parent:v1", "base-model:7b").await.unwrap().status(),
+        200
+    );
+    assert_eq!(create("child:v1", "parent:v1").await.unwrap().status(), 200);
+    // Repoint the parent; the child must pick it up without being recreated.
+    assert_eq!(
+        create("parent:v1", "other-model:7b")
+            .await
+            .unwrap()
+            .status(),
+        200
+    );
+
+    let show: Value = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({"model": "child:v1"}))
+        .send()
+        .await
+        .expect("POST /api/show child")
+        .json()
+        .await
+        .expect("show body");
+    assert_eq!(show["parent_alias"], "parent:v1", "{show}");
+    assert_eq!(show["target_model_id"], "other-model:7b", "{show}");
+}
+
+#[tokio::test]
+async fn create_rejects_alias_cycle() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lms_models(vec![native_model("base-model:7b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let create = |model: &'static str, from: &'static str| {
+        p.client
+            .post(p.url("/api/create"))
+            .json(&json!({"model": model, "from": from, "stream": false}))
+            .send()
+    };
+
+    assert_eq!(
+        create("loop-a:v1", "base-model:7b").await.unwrap().status(),
+        200
+    );
+    assert_eq!(
+        create("loop-b:v1", "loop-a:v1").await.unwrap().status(),
+        200
+    );
+
+    let resp = create("loop-a:v1", "loop-b:v1").await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("json body");
+    assert!(
+        body["error"].as_str().unwrap_or("").contains("would loop"),
+        "{body}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/proxy/aliases
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn alias_list_returns_stored_entries() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    for (model, system) in [
+        ("writer:v1", "Write prose."),
+        ("coder:latest", "Write code."),
+    ] {
+        let resp = p
+            .client
+            .post(p.url("/api/create"))
+            .json(
+                &json!({"model": model, "from": "llama3.2:3b", "system": system, "stream": false}),
+            )
+            .send()
+            .await
+            .expect("POST /api/create");
+        assert_eq!(resp.status(), 200);
+    }
+
+    let list = |query: &'static str| {
+        p.client
+            .get(p.url(&format!("/api/proxy/aliases{query}")))
+            .send()
+    };
+    let resp = list("").await.expect("GET /api/proxy/aliases");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["count"], 2, "{body}");
+
+    let stored: Value = serde_json::from_slice(
+        &std::fs::read(p.state_dir().join("virtual_models.json")).expect("store file"),
+    )
+    .expect("store JSON");
+    for alias in body["aliases"].as_array().expect("aliases array") {
+        let key = alias["name"].as_str().unwrap().trim_end_matches(":latest");
+        assert_eq!(alias, &stored["models"][key], "{body}");
+    }
+    assert_eq!(body["aliases"][0]["name"], "coder:latest");
+    assert_eq!(body["aliases"][1]["target_model_id"], "llama3.2:3b");
+    assert_eq!(
+        body["aliases"][1]["metadata"]["system_prompt"],
+        "Write prose."
+    );
+
+    let filtered: Value = list("?name=coder")
+        .await
+        .expect("GET /api/proxy/aliases?name=")
+        .json()
+        .await
+        .expect("json body");
+    assert_eq!(filtered["count"], 1, "{filtered}");
+    assert_eq!(filtered["aliases"][0]["name"], "coder:latest");
+
+    let missing: Value = list("?name=nope")
+        .await
+        .expect("GET /api/proxy/aliases?name=")
+        .json()
+        .await
+        .expect("json body");
+    assert_eq!(missing["count"], 0);
+}
+
+// ---------------------------------------------------------------------------
+// GET /v2/{name}/manifests/{tag} and /v2/{name}/blobs/{digest}
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn registry_manifest_describes_the_model_like_api_tags() {
+    let p = spawn_proxy().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .get(p.url("/v2/library/llama3.2/manifests/3b"))
+        .send()
+        .await
+        .expect("GET /v2/.../manifests/...");
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers()["content-type"],
+        "application/vnd.docker.distribution.manifest.v2+json"
+    );
+    let digest_header = resp.headers()["docker-content-digest"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let bytes = resp.bytes().await.expect("manifest bytes");
+    assert_eq!(digest_header, sha256_digest(&bytes));
+    let manifest: Value = serde_json::from_slice(&bytes).expect("manifest JSON");
+
+    let tags: Value = p
+        .client
+        .get(p.url("/api/tags"))
+        .send()
+        .await
+        .expect("GET /api/tags")
+        .json()
+        .await
+        .expect("tags body");
+    let listed = &tags["models"][0];
+    assert_eq!(manifest["schemaVersion"], 2);
+    assert_eq!(manifest["layers"], json!([]));
+
+    // Every digest the manifest names can be fetched.
+    let config_digest = manifest["config"]["digest"].as_str().unwrap();
+    let resp = p
+        .client
+        .get(p.url(&format!("/v2/library/llama3.2/blobs/{config_digest}")))
+        .send()
+        .await
+        .expect("GET config blob");
+    assert_eq!(resp.status(), 200);
+    let config_bytes = resp.bytes().await.expect("config bytes");
+    assert_eq!(sha256_digest(&config_bytes), config_digest);
+    assert_eq!(manifest["config"]["size"], config_bytes.len());
+    let config: Value = serde_json::from_slice(&config_bytes).expect("config JSON");
+    assert_eq!(
+        config["model_digest"],
+        format!("sha256:{}", listed["digest"].as_str().unwrap())
+    );
+    assert_eq!(config["model_size"], listed["size"]);
+
+    let missing = p
+        .client
+        .get(p.url("/v2/library/nope/manifests/latest"))
+        .send()
+        .await
+        .expect("GET unknown manifest");
+    assert_eq!(missing.status(), 404);
+}
+
+#[tokio::test]
+async fn registry_blob_serves_uploaded_blobs_and_configs_only() {
+    let p = spawn_proxy().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+    let data = b"registry blob content";
+    let digest = sha256_digest(data);
+    p.client
+        .post(p.url(&format!("/api/blobs/{digest}")))
+        .body(data.to_vec())
+        .send()
+        .await
+        .expect("POST /api/blobs upload");
+
+    let resp = p
+        .client
+        .get(p.url(&format!("/v2/library/any/blobs/{digest}")))
+        .send()
+        .await
+        .expect("GET /v2/.../blobs/...");
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["docker-content-digest"], digest.as_str());
+    assert_eq!(resp.bytes().await.expect("blob bytes").as_ref(), data);
+
+    let absent = sha256_digest(b"never uploaded");
+    let resp = p
+        .client
+        .get(p.url(&format!("/v2/library/any/blobs/{absent}")))
+        .send()
+        .await
+        .expect("GET absent blob");
+    assert_eq!(resp.status(), 404);
+}
+
+// ---------------------------------------------------------------------------
+// GET /api/proxy/blobs, DELETE /api/blobs/:digest, --blob-max-total-mb
+// ---------------------------------------------------------------------------
+
+async fn upload_blob(p: &TestProxy, data: &[u8]) -> String {
+    let digest = sha256_digest(data);
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{digest}")))
+        .body(data.to_vec())
+        .send()
+        .await
+        .expect("POST /api/blobs upload");
+    assert_eq!(resp.status(), 201);
+    digest
+}
+
+async fn listed_blobs(p: &crate::common::TestProxy) -> Value {
+    p.client
+        .get(p.url("/api/proxy/blobs"))
+        .send()
+        .await
+        .expect("GET /api/proxy/blobs")
+        .json()
+        .await
+        .expect("json body")
+}
+
+#[tokio::test]
+async fn blob_list_reports_sizes_and_total() {
+    let p = spawn_proxy().await;
+    let first = upload_blob(&p, b"first blob").await;
+    let second = upload_blob(&p, b"second blob!").await;
+
+    let body = listed_blobs(&p).await;
+    assert_eq!(body["count"], 2);
+    assert_eq!(body["total_bytes"], 22);
+    assert_eq!(body["max_total_bytes"], 0);
+    let mut expected = vec![(first, 10), (second, 12)];
+    expected.sort();
+    let listed: Vec<(String, u64)> = body["blobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            assert_eq!(b["referenced"], false);
+            assert!(b["last_access"].is_string());
+            (
+                b["digest"].as_str().unwrap().to_string(),
+                b["size"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(listed, expected);
+}
+
+#[tokio::test]
+async fn blob_delete_removes_it_and_404s_after() {
+    let p = spawn_proxy().await;
+    let digest = upload_blob(&p, b"delete me").await;
+    let url = p.url(&format!("/api/blobs/{digest}"));
+
+    let resp = p.client.delete(&url).send().await.expect("DELETE blob");
+    assert_eq!(resp.status(), 200);
+    let head = p.client.head(&url).send().await.expect("HEAD blob");
+    assert_eq!(head.status(), 404);
+    assert_eq!(listed_blobs(&p).await["count"], 0);
+
+    let again = p.client.delete(&url).send().await.expect("DELETE blob");
+    assert_eq!(again.status(), 404);
+}
+
+#[tokio::test]
+async fn blob_upload_over_total_limit_evicts_the_oldest_blob() {
+    let p = spawn_proxy_with_config(|c| c.blob_max_total_mb = 1).await;
+    let older = upload_blob(&p, &vec![b'a'; 600 * 1024]).await;
+    let newer = upload_blob(&p, &vec![b'b'; 600 * 1024]).await;
+
+    let old_head = p
+        .client
+        .head(p.url(&format!("/api/blobs/{older}")))
+        .send()
+        .await
+        .expect("HEAD older blob");
+    assert_eq!(old_head.status(), 404, "older blob should be evicted");
+    let body = listed_blobs(&p).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["blobs"][0]["digest"], newer);
+    assert_eq!(body["max_total_bytes"], 1024 * 1024);
+}
+
+#[tokio::test]
+async fn blob_upload_over_the_whole_store_limit_gets_413_and_evicts_nothing() {
+    let p = spawn_proxy_with_config(|c| c.blob_max_total_mb = 1).await;
+    let kept = upload_blob(&p, &vec![b'a'; 600 * 1024]).await;
+
+    let data = vec![b'b'; 1024 * 1024 + 1];
+    let resp = p
+        .client
+        .post(p.url(&format!("/api/blobs/{}", sha256_digest(&data))))
+        .body(data)
+        .send()
+        .await
+        .expect("POST /api/blobs upload");
+    assert_eq!(resp.status(), 413);
+    let err: Value = resp.json().await.expect("413 body must be JSON");
+    let message = err["error"].as_str().expect("error string");
+    assert!(message.contains("--blob-max-total-mb"), "{message}");
+
+    let body = listed_blobs(&p).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["blobs"][0]["digest"], kept);
+}
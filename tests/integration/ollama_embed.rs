@@ -8,7 +8,7 @@ use serde_json::{Value, json};
 use wiremock::matchers::{body_partial_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ---------------------------------------------------------------------------
 // Shared helpers
@@ -29,6 +29,23 @@ async fn mount_models(p: &crate::common::TestProxy, model_id: &str) {
         .await;
 }
 
+async fn mount_embedding_model(p: &crate::common::TestProxy, model_id: &str, loaded: bool) {
+    let instances = if loaded {
+        json!([{ "id": model_id }])
+    } else {
+        json!([])
+    };
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": model_id, "type": "embeddings", "publisher": "nomic-ai",
+                        "architecture": "nomic-bert", "format": "gguf",
+                        "max_context_length": 2048, "loaded_instances": instances}]
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
 /// A single-vector LM Studio embeddings response.
 fn lm_response_single(model_id: &str, vec: Vec<f32>) -> Value {
     json!({
@@ -298,29 +315,43 @@ async fn embed_truncate_option_forwarded() {
 }
 
 // ---------------------------------------------------------------------------
-// 7. Empty input → error
+// 7. Empty input → preload
 // ---------------------------------------------------------------------------
 
 #[tokio::test]
-async fn embed_empty_input_returns_error() {
+async fn embed_empty_input_loads_the_model() {
     let p = spawn_proxy().await;
-    mount_models(&p, "all-minilm").await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", false).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .and(body_partial_json(
+            json!({ "model": "nomic-embed-text-v1.5" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "loaded" })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
 
     let resp = p
         .client
         .post(p.url("/api/embed"))
-        .json(&json!({ "model": "all-minilm", "input": "" }))
+        .json(&json!({ "model": "nomic-embed-text-v1.5", "input": "" }))
         .send()
         .await
         .expect("POST /api/embed empty input");
 
-    // Per `fix(embed): reject empty input string before forwarding`, the proxy
-    // must short-circuit empty strings with a 4xx rather than forward them.
-    assert!(
-        resp.status().is_client_error(),
-        "empty input must be rejected with 4xx; got {}",
-        resp.status()
-    );
+    // Ollama treats an empty `input` as a preload: load, return no vectors.
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["model"], "nomic-embed-text-v1.5");
+    assert_eq!(body["embeddings"], json!([]));
+    assert!(body["load_duration"].is_u64());
 }
 
 // ---------------------------------------------------------------------------
@@ -347,27 +378,130 @@ async fn embed_missing_model_returns_error() {
 }
 
 // ---------------------------------------------------------------------------
-// 9. Missing input/prompt → 4xx error
+// 9. Missing input → preload
 // ---------------------------------------------------------------------------
 
 #[tokio::test]
-async fn embed_missing_input_field_returns_error() {
+async fn embed_missing_input_field_is_a_preload() {
     let p = spawn_proxy().await;
-    mount_models(&p, "all-minilm").await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", false).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "loaded" })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
 
     let resp = p
         .client
         .post(p.url("/api/embed"))
-        .json(&json!({ "model": "all-minilm" }))
+        .json(&json!({ "model": "nomic-embed-text-v1.5" }))
         .send()
         .await
         .expect("POST /api/embed no input");
 
-    assert!(
-        resp.status().as_u16() >= 400 && resp.status().as_u16() < 500,
-        "missing input must return 4xx, got {}",
-        resp.status()
-    );
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["embeddings"], json!([]));
+}
+
+#[tokio::test]
+async fn embed_empty_input_with_keep_alive_zero_unloads_the_model() {
+    let p = spawn_proxy().await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", true).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/unload"))
+        .and(body_partial_json(
+            json!({ "instance_id": "nomic-embed-text-v1.5" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "ok": true })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "nomic-embed-text-v1.5", "input": [], "keep_alive": 0 }))
+        .send()
+        .await
+        .expect("POST /api/embed keep_alive 0");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["model"], "nomic-embed-text-v1.5");
+    assert_eq!(body["embeddings"], json!([]));
+    assert_eq!(body["load_duration"], 0);
+
+    // The unload runs in the background after the response.
+    for _ in 0..50 {
+        let received = p.mock.received_requests().await.unwrap_or_default();
+        if received
+            .iter()
+            .any(|r| r.url.path() == "/api/v1/models/unload")
+        {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn embed_preload_resolves_mapped_names() {
+    let p = spawn_proxy_with_config(|c| {
+        c.model_name_map = vec!["embedder=nomic-embed-text-v1.5".to_string()];
+    })
+    .await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", false).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .and(body_partial_json(
+            json!({ "model": "nomic-embed-text-v1.5" }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "loaded" })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "embedder" }))
+        .send()
+        .await
+        .expect("POST /api/embed mapped name");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn embed_preload_of_unknown_model_is_not_found() {
+    let p = spawn_proxy().await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", false).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "does-not-exist:latest" }))
+        .send()
+        .await
+        .expect("POST /api/embed unknown model");
+
+    assert_eq!(resp.status(), 404);
 }
 
 // ---------------------------------------------------------------------------
@@ -946,43 +1080,58 @@ async fn embed_rejects_legacy_prompt_field_with_400() {
 }
 
 // ---------------------------------------------------------------------------
-// 27. /api/embed rejects empty input array
+// 27. /api/embed empty input array → preload
 // ---------------------------------------------------------------------------
 
 #[tokio::test]
-async fn embed_rejects_empty_input_array_with_400() {
+async fn embed_empty_input_array_is_a_preload() {
     let p = spawn_proxy().await;
-    mount_models(&p, "all-minilm").await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", false).await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "loaded" })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
 
     let resp = p
         .client
         .post(p.url("/api/embed"))
-        .json(&json!({ "model": "all-minilm", "input": [] }))
+        .json(&json!({ "model": "nomic-embed-text-v1.5", "input": [] }))
         .send()
         .await
         .expect("POST /api/embed empty array");
 
-    assert_eq!(resp.status(), 400, "empty array must be rejected with 400");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["embeddings"], json!([]));
 }
 
 // ---------------------------------------------------------------------------
-// 28. /api/embed rejects empty string input (regression)
+// 28. preload of an already loaded embedder sends no load
 // ---------------------------------------------------------------------------
 
 #[tokio::test]
-async fn embed_rejects_empty_string_input_with_400() {
+async fn embed_preload_of_a_loaded_embedder_sends_no_load() {
     let p = spawn_proxy().await;
-    mount_models(&p, "all-minilm").await;
+    mount_embedding_model(&p, "nomic-embed-text-v1.5", true).await;
+    // A second `/api/v1/models/load` would stack a duplicate instance.
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/load"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
 
     let resp = p
         .client
         .post(p.url("/api/embed"))
-        .json(&json!({ "model": "all-minilm", "input": "" }))
+        .json(&json!({ "model": "nomic-embed-text-v1.5", "input": "" }))
         .send()
         .await
         .expect("POST /api/embed empty string");
 
-    assert_eq!(resp.status(), 400, "empty string must be rejected with 400");
+    assert_eq!(resp.status(), 200);
 }
 
 // ---------------------------------------------------------------------------
@@ -1423,3 +1572,315 @@ async fn embed_truncate_and_dimensions_reach_lm_studio_body() {
         "dimensions must reach LM Studio embeddings body: {body}"
     );
 }
+
+// ---------------------------------------------------------------------------
+// 36. Batch vectors are realigned to their inputs by `index`
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn embed_three_inputs_return_three_vectors_in_input_order() {
+    let p = spawn_proxy().await;
+    mount_models(&p, "all-minilm").await;
+
+    // Upstream lists the items out of order; `index` says which input each is.
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "object": "list",
+            "data": [
+                { "object": "embedding", "index": 2, "embedding": [3.0] },
+                { "object": "embedding", "index": 0, "embedding": [1.0] },
+                { "object": "embedding", "index": 1, "embedding": [2.0] }
+            ],
+            "model": "all-minilm",
+            "usage": { "prompt_tokens": 6, "total_tokens": 6 }
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "all-minilm", "input": ["one", "two", "three"] }))
+        .send()
+        .await
+        .expect("POST /api/embed batch");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["embeddings"], json!([[1.0], [2.0], [3.0]]));
+}
+
+// ---------------------------------------------------------------------------
+// 37. A vector count that does not match the inputs is an upstream error
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn embed_vector_count_mismatch_returns_502() {
+    let p = spawn_proxy().await;
+    mount_models(&p, "all-minilm").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lm_response_multi("all-minilm", vec![vec![0.1], vec![0.2]])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "all-minilm", "input": ["a", "b", "c"] }))
+        .send()
+        .await
+        .expect("POST /api/embed batch");
+
+    assert_eq!(resp.status(), 502);
+    let body: Value = resp.json().await.expect("json body");
+    assert!(
+        body["error"]
+            .as_str()
+            .is_some_and(|e| e.contains("2 embeddings for 3 inputs")),
+        "{body}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 38. truncate:false rejects inputs longer than the model's context
+// ---------------------------------------------------------------------------
+
+async fn mount_small_context_model(p: &crate::common::TestProxy) {
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "tiny-embed", "type": "embedding", "publisher": "test",
+                        "architecture": "bert", "format": "gguf",
+                        "quantization": {"name": "F16", "bits_per_weight": 16},
+                        "max_context_length": 16, "loaded_instances": []}]
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
+#[tokio::test]
+async fn embed_truncate_false_rejects_input_over_context_length() {
+    let p = spawn_proxy().await;
+    mount_small_context_model(&p).await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_response_single("tiny-embed", vec![0.1])),
+        )
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({
+            "model": "tiny-embed",
+            "input": "word ".repeat(100),
+            "truncate": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/embed truncate:false");
+
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("json body");
+    assert!(
+        body["error"]
+            .as_str()
+            .is_some_and(|e| e.contains("context length")),
+        "{body}"
+    );
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn embed_truncate_default_forwards_long_input() {
+    let p = spawn_proxy().await;
+    mount_small_context_model(&p).await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({ "truncate": true })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_response_single("tiny-embed", vec![0.1])),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "tiny-embed", "input": "word ".repeat(100) }))
+        .send()
+        .await
+        .expect("POST /api/embed long input");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn embed_truncate_false_forwards_input_that_fits() {
+    let p = spawn_proxy().await;
+    mount_small_context_model(&p).await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({ "truncate": false })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_response_single("tiny-embed", vec![0.1])),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "tiny-embed", "input": "short", "truncate": false }))
+        .send()
+        .await
+        .expect("POST /api/embed short input");
+
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
+
+// ---------------------------------------------------------------------------
+// 39. normalize:true returns unit-length vectors and is not forwarded
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn embed_normalize_returns_unit_norm_vectors() {
+    let p = spawn_proxy().await;
+    mount_models(&p, "all-minilm").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_response_multi(
+            "all-minilm",
+            vec![vec![3.0, 4.0], vec![0.5, -2.0]],
+        )))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/embed"))
+        .json(&json!({ "model": "all-minilm", "input": ["a", "b"], "normalize": true }))
+        .send()
+        .await
+        .expect("POST /api/embed normalize");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    let vectors = body["embeddings"].as_array().expect("embeddings array");
+    assert_eq!(vectors.len(), 2);
+    for vector in vectors {
+        let norm = vector
+            .as_array()
+            .expect("vector")
+            .iter()
+            .map(|x| x.as_f64().unwrap().powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!((norm - 1.0).abs() < 1e-6, "norm {norm} for {vector}");
+    }
+    assert!((vectors[0][0].as_f64().unwrap() - 0.6).abs() < 1e-6);
+
+    let requests = p.mock.received_requests().await.unwrap_or_default();
+    let upstream = requests
+        .iter()
+        .find(|r| r.url.path() == "/v1/embeddings")
+        .expect("embeddings request forwarded");
+    let upstream: Value = serde_json::from_slice(&upstream.body).expect("upstream json");
+    assert!(upstream.get("normalize").is_none(), "{upstream}");
+}
+
+// ---------------------------------------------------------------------------
+// --embedding-cache-size: repeated inputs skip LM Studio
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn embed_cache_sends_only_novel_inputs_and_keeps_order() {
+    let p = spawn_proxy_with_config(|c| c.embedding_cache_size = 100).await;
+    mount_models(&p, "all-minilm").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({ "input": ["alpha", "beta"] })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_response_multi(
+            "all-minilm",
+            vec![vec![1.0, 0.0], vec![0.0, 1.0]],
+        )))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(body_partial_json(json!({ "input": ["gamma"] })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lm_response_multi("all-minilm", vec![vec![0.5, 0.5]])),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let embed = |input: Value| {
+        p.client
+            .post(p.url("/api/embed"))
+            .json(&json!({ "model": "all-minilm", "input": input }))
+            .send()
+    };
+    let first = embed(json!(["alpha", "beta"])).await.expect("first embed");
+    assert_eq!(first.status(), 200);
+
+    let second = embed(json!(["beta", "gamma", "alpha"]))
+        .await
+        .expect("second embed");
+    assert_eq!(second.status(), 200);
+    let body: Value = second.json().await.expect("json body");
+    assert_eq!(
+        body["embeddings"],
+        json!([[0.0, 1.0], [0.5, 0.5], [1.0, 0.0]])
+    );
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn openai_embeddings_served_from_cache_on_repeat() {
+    let p = spawn_proxy_with_config(|c| c.embedding_cache_size = 100).await;
+    mount_models(&p, "all-minilm").await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lm_response_single("all-minilm", vec![0.1, 0.2])),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    for _ in 0..2 {
+        let resp = p
+            .client
+            .post(p.url("/v1/embeddings"))
+            .json(&json!({ "model": "all-minilm", "input": "same text" }))
+            .send()
+            .await
+            .expect("POST /v1/embeddings");
+        assert_eq!(resp.status(), 200);
+        let body: Value = resp.json().await.expect("json body");
+        assert_eq!(body["data"][0]["index"], 0);
+        assert_eq!(body["data"][0]["embedding"].as_array().unwrap().len(), 2);
+    }
+    p.mock.verify().await;
+}
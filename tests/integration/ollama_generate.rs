@@ -1,18 +1,19 @@
 // Integration tests for POST /api/generate — Ollama generate surface.
 //
-// The generate handler routes to `/api/v0/completions` for plain text prompts
-// and to `/api/v0/chat/completions` for vision requests (images present). Both
-// paths are covered here.
+// The generate handler routes to `/api/v0/completions` for raw, fill-in-the-
+// middle, and base-model prompts, and to `/api/v0/chat/completions` when a chat
+// template applies (instruct models, system prompts) or images are present.
+// Both paths are covered here.
 //
-// Model resolution uses /api/v1/models (LM Studio native). The mock key
-// "llama3.2-3b-instruct" substring-matches the Ollama name "llama3.2:3b",
+// Model resolution uses /api/v1/models (LM Studio native). The mock keys
+// "llama3.2-3b-instruct" / "llama3.2-3b-base" match the Ollama name "llama3.2:3b",
 // and "llava-7b-v1.6" matches "llava-7b:latest".
 
 use serde_json::{Value, json};
 use wiremock::matchers::{body_partial_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ── model-catalog helpers ───────────────────────────────────────────────────
 
@@ -150,7 +151,7 @@ fn parse_ndjson(text: &str) -> Vec<Value> {
 #[tokio::test]
 async fn non_streaming_generate_returns_ollama_shape() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -196,7 +197,7 @@ async fn non_streaming_generate_returns_ollama_shape() {
 #[tokio::test]
 async fn stream_absent_defaults_to_streaming() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     let sse = sse_completion_body(&["OK"], "stop");
     Mock::given(method("POST"))
@@ -239,7 +240,7 @@ async fn stream_absent_defaults_to_streaming() {
 #[tokio::test]
 async fn streaming_generate_emits_ndjson_with_final_done_chunk() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     let sse = sse_completion_body(&["The", " sky", " is", " blue."], "stop");
     Mock::given(method("POST"))
@@ -301,7 +302,7 @@ async fn streaming_generate_emits_ndjson_with_final_done_chunk() {
 #[tokio::test]
 async fn stream_explicit_false_returns_single_object() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -336,7 +337,7 @@ async fn stream_explicit_false_returns_single_object() {
 #[tokio::test]
 async fn options_temperature_and_num_predict_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -377,7 +378,7 @@ async fn options_temperature_and_num_predict_forwarded() {
 #[tokio::test]
 async fn options_num_ctx_reloads_model_at_context_length() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v1/models/unload"))
@@ -391,7 +392,7 @@ async fn options_num_ctx_reloads_model_at_context_length() {
         .and(path("/api/v1/models/load"))
         .and(body_partial_json(json!({ "context_length": 1024 })))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "status": "loaded", "instance_id": "llama3.2-3b-instruct", "load_time_seconds": 0.1
+            "status": "loaded", "instance_id": "llama3.2-3b-base", "load_time_seconds": 0.1
         })))
         .expect(1)
         .mount(&p.mock)
@@ -426,7 +427,7 @@ async fn options_num_ctx_reloads_model_at_context_length() {
 #[tokio::test]
 async fn options_num_ctx_matching_loaded_context_skips_reload() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v1/models/unload"))
@@ -471,7 +472,7 @@ async fn options_num_ctx_matching_loaded_context_skips_reload() {
 #[tokio::test]
 async fn options_num_ctx_absent_does_not_touch_model() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v1/models/unload"))
@@ -519,7 +520,7 @@ async fn options_num_ctx_absent_does_not_touch_model() {
 #[tokio::test]
 async fn options_stop_array_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -664,12 +665,12 @@ async fn generate_system_surfaces_thinking_from_chat_message() {
     );
 }
 
-// A generate request WITHOUT a system prompt keeps the plain /api/v0/completions
-// path — chat routing is gated strictly on a non-empty, non-raw system prompt.
+// A base model WITHOUT a system prompt keeps the plain /api/v0/completions path
+// — there is no chat template worth applying to a raw-text model.
 #[tokio::test]
-async fn generate_without_system_stays_on_completions() {
+async fn base_model_without_system_stays_on_completions() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -703,7 +704,7 @@ async fn generate_without_system_stays_on_completions() {
         .count();
     assert_eq!(
         chat_hits, 0,
-        "a no-system request must NOT route to /api/v0/chat/completions"
+        "a base-model request must NOT route to /api/v0/chat/completions"
     );
     let completion_hits = received
         .iter()
@@ -711,8 +712,56 @@ async fn generate_without_system_stays_on_completions() {
         .count();
     assert_eq!(
         completion_hits, 1,
-        "no-system request must hit /completions"
+        "base-model request must hit /completions"
+    );
+}
+
+// An instruct/chat-tuned model without `raw` gets its chat template applied:
+// the prompt goes out as a single user turn on /api/v0/chat/completions, and
+// the reply still comes back in the Ollama generate shape.
+#[tokio::test]
+async fn instruct_model_without_raw_applies_chat_template() {
+    let p = spawn_proxy().await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .and(body_partial_json(json!({
+            "model": "llama3.2-3b-instruct",
+            "messages": [{ "role": "user", "content": "Hello" }]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Hi!", "stop")))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_completion_response("x", "stop")))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "Hello",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate instruct model");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON");
+    assert_eq!(body["response"], "Hi!");
+    assert_eq!(body["done"], true);
+    assert!(
+        body.get("message").is_none(),
+        "generate shape must not leak a chat message: {body}"
     );
+    p.mock.verify().await;
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -819,6 +868,72 @@ async fn raw_true_with_system_stays_on_completions_no_template() {
     );
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// 9c. raw:true on a virtual model with a stored system prompt — the alias's
+// SYSTEM must not be prepended or templated; the prompt goes out verbatim.
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn raw_true_skips_virtual_model_system_prompt() {
+    let p = spawn_proxy().await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+
+    let create = p
+        .client
+        .post(p.url("/api/create"))
+        .json(&json!({
+            "model": "pirate:latest",
+            "from": "llama3.2:3b",
+            "system": "You are a pirate.",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/create");
+    assert_eq!(create.status(), 200);
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response("Raw reply.", "stop")),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let raw_prompt = "<|user|>Hello<|assistant|>";
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "pirate:latest",
+            "prompt": raw_prompt,
+            "raw": true,
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate raw on virtual model");
+    assert_eq!(resp.status(), 200);
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let upstream = received
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/completions")
+        .expect("LM Studio completions request captured");
+    let body: Value = serde_json::from_slice(&upstream.body).expect("upstream body is JSON");
+
+    assert_eq!(body["model"], "llama3.2-3b-instruct");
+    assert_eq!(
+        body["prompt"], raw_prompt,
+        "virtual-model system prompt must not be prepended in raw mode"
+    );
+    assert!(
+        !body.to_string().contains("You are a pirate."),
+        "system text leaked into raw request: {body}"
+    );
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 10. suffix forwarded for completion fill-in-the-middle
 // ═══════════════════════════════════════════════════════════════════════════
@@ -830,9 +945,14 @@ async fn suffix_forwarded_to_completions_endpoint() {
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
+        .and(body_partial_json(json!({
+            "prompt": "def hello(",
+            "suffix": "):\n    pass"
+        })))
         .respond_with(
             ResponseTemplate::new(200).set_body_json(lm_completion_response("middle text", "stop")),
         )
+        .expect(1)
         .mount(&p.mock)
         .await;
 
@@ -853,6 +973,84 @@ async fn suffix_forwarded_to_completions_endpoint() {
     p.mock.verify().await;
 }
 
+#[tokio::test]
+async fn suffix_rejected_by_model_falls_back_to_plain_completion() {
+    let p = spawn_proxy().await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+
+    // The model has no FIM support: any body carrying `suffix` is rejected.
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .and(body_partial_json(json!({ "suffix": "):\n    pass" })))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": { "message": "suffix is not supported by this model" }
+        })))
+        .with_priority(1)
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response("world\")", "stop")),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "def hello(",
+            "suffix": "):\n    pass",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate suffix fallback");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json");
+    assert_eq!(body["response"], "world\")");
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn suffix_request_rejected_for_another_reason_is_not_retried() {
+    let p = spawn_proxy().await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": { "message": "prompt exceeds the context length" }
+        })))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "def hello(",
+            "suffix": "):\n    pass",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate suffix, unrelated 400");
+
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("json");
+    assert_eq!(body["error"], "prompt exceeds the context length");
+    p.mock.verify().await;
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 11. format:"json" forwarded
 // ═══════════════════════════════════════════════════════════════════════════
@@ -860,7 +1058,7 @@ async fn suffix_forwarded_to_completions_endpoint() {
 #[tokio::test]
 async fn format_json_string_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -894,7 +1092,7 @@ async fn format_json_string_forwarded() {
 #[tokio::test]
 async fn format_json_schema_object_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -931,14 +1129,14 @@ async fn format_json_schema_object_forwarded() {
 #[tokio::test]
 async fn think_flag_forwarded_and_reasoning_in_response() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     // The completions endpoint may return reasoning at the choice level.
     let lm_resp = json!({
         "id": "cmpl-think",
         "object": "text_completion",
         "created": 1_700_000_000u64,
-        "model": "llama3.2-3b-instruct",
+        "model": "llama3.2-3b-base",
         "choices": [{
             "index": 0,
             "text": "42",
@@ -1016,6 +1214,104 @@ async fn generate_with_images_routes_to_chat_completions() {
     assert_eq!(body["done"], true);
 }
 
+async fn upstream_chat_content(p: &crate::common::TestProxy) -> Vec<Value> {
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    let chat = received
+        .iter()
+        .find(|r| r.url.path() == "/api/v0/chat/completions")
+        .expect("vision request must reach /api/v0/chat/completions");
+    let body: Value = serde_json::from_slice(&chat.body).expect("JSON upstream body");
+    let messages = body["messages"].as_array().expect("messages array");
+    let user = messages.last().expect("user message");
+    assert_eq!(user["role"], "user");
+    user["content"]
+        .as_array()
+        .expect("vision user content must be a parts array")
+        .clone()
+}
+
+#[tokio::test]
+async fn generate_single_image_becomes_image_url_part() {
+    let p = spawn_proxy().await;
+    mount_vlm_catalog(&p, "llava-7b-v1.6").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("ok", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let png = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llava-7b:latest",
+            "prompt": "Describe the image",
+            "images": [png],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 200);
+
+    let content = upstream_chat_content(&p).await;
+    assert_eq!(
+        content,
+        vec![
+            json!({"type": "text", "text": "Describe the image"}),
+            json!({"type": "image_url", "image_url": {"url": format!("data:image/png;base64,{png}")}}),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn generate_multiple_images_become_ordered_image_url_parts() {
+    let p = spawn_proxy().await;
+    mount_vlm_catalog(&p, "llava-7b-v1.6").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("ok", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llava-7b:latest",
+            "prompt": "Compare",
+            "images": [
+                "/9j/4AAQSkZJRgABAQAAAQABAAD",
+                "UklGRiIAAABXRUJQVlA4IBYAAAAw",
+                "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg"
+            ],
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 200);
+
+    let content = upstream_chat_content(&p).await;
+    let urls: Vec<&str> = content[1..]
+        .iter()
+        .map(|part| {
+            assert_eq!(part["type"], "image_url");
+            part["image_url"]["url"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(content[0]["text"], "Compare");
+    assert_eq!(
+        urls,
+        vec![
+            "data:image/jpeg;base64,/9j/4AAQSkZJRgABAQAAAQABAAD",
+            "data:image/webp;base64,UklGRiIAAABXRUJQVlA4IBYAAAAw",
+            "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg",
+        ]
+    );
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 15. vision + streaming — NDJSON chunks, done:true at end
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1201,7 +1497,7 @@ async fn unknown_model_returns_404() {
 #[tokio::test]
 async fn finish_reason_length_maps_to_done_reason_length() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1235,7 +1531,7 @@ async fn finish_reason_length_maps_to_done_reason_length() {
 #[tokio::test]
 async fn keep_alive_duration_string_accepted() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1261,6 +1557,45 @@ async fn keep_alive_duration_string_accepted() {
     assert_eq!(resp.status(), 200);
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+// 20b. keep_alive:-1 is sent as --indefinite-ttl-seconds, not dropped
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn negative_keep_alive_sends_indefinite_ttl() {
+    let p = spawn_proxy_with_config(|c| c.indefinite_ttl_seconds = 7200).await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response("OK", "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    for keep_alive in [json!(-1), json!("-1")] {
+        let resp = p
+            .client
+            .post(p.url("/api/generate"))
+            .json(&json!({
+                "model": "llama3.2:3b",
+                "prompt": "Hi",
+                "stream": false,
+                "keep_alive": keep_alive
+            }))
+            .send()
+            .await
+            .expect("POST /api/generate keep_alive -1");
+        assert_eq!(resp.status(), 200);
+    }
+
+    let bodies = upstream_bodies(&p, "/api/v0/completions").await;
+    assert_eq!(bodies.len(), 2);
+    for body in bodies {
+        assert_eq!(body["ttl"], json!(7200), "{body}");
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // 21. LM Studio 500 propagates as error
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1268,7 +1603,7 @@ async fn keep_alive_duration_string_accepted() {
 #[tokio::test]
 async fn lm_studio_500_propagates_as_error() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1298,7 +1633,7 @@ async fn lm_studio_500_propagates_as_error() {
 #[tokio::test]
 async fn repeat_penalty_option_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1430,7 +1765,7 @@ async fn generate_raw_true_with_empty_images_array_does_not_reject() {
 #[tokio::test]
 async fn logprobs_forwarded() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1467,7 +1802,7 @@ fn lm_completion_response_with_logprobs(text: &str, finish_reason: &str) -> Valu
         "id": "cmpl-test",
         "object": "text_completion",
         "created": 1_700_000_000u64,
-        "model": "llama3.2-3b-instruct",
+        "model": "llama3.2-3b-base",
         "choices": [{
             "index": 0,
             "text": text,
@@ -1490,7 +1825,7 @@ fn lm_completion_response_with_logprobs(text: &str, finish_reason: &str) -> Valu
 #[tokio::test]
 async fn logprobs_data_present_in_generate_response() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1536,7 +1871,7 @@ async fn logprobs_data_present_in_generate_response() {
 #[tokio::test]
 async fn context_array_in_request_accepted() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     Mock::given(method("POST"))
         .and(path("/api/v0/completions"))
@@ -1563,8 +1898,164 @@ async fn context_array_in_request_accepted() {
     assert_eq!(resp.status(), 200);
     let body: Value = resp.json().await.expect("JSON");
     assert_eq!(body["done"], true);
-    // Ollama omits the context field in the response (deprecated)
-    // — we only assert done:true and no crash.
+    assert!(
+        body.get("context").is_none(),
+        "no context without --emulate-generate-context: {body}"
+    );
+}
+
+async fn upstream_bodies(p: &crate::common::TestProxy, upstream_path: &str) -> Vec<Value> {
+    p.mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == upstream_path)
+        .map(|r| serde_json::from_slice(&r.body).expect("JSON upstream body"))
+        .collect()
+}
+
+#[tokio::test]
+async fn emulated_context_replays_exchange_as_chat_turns() {
+    let p = spawn_proxy_with_config(|c| c.emulate_generate_context = true).await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Hi!", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let generate = |body: Value| async {
+        let resp = p
+            .client
+            .post(p.url("/api/generate"))
+            .json(&body)
+            .send()
+            .await
+            .expect("POST /api/generate");
+        assert_eq!(resp.status(), 200);
+        resp.json::<Value>().await.expect("JSON")
+    };
+
+    let first = generate(json!({"model": "llama3.2:3b", "prompt": "Hello", "stream": false})).await;
+    let context = first["context"].clone();
+    assert!(context.as_array().is_some_and(|c| !c.is_empty()), "{first}");
+
+    let second = generate(json!({
+        "model": "llama3.2:3b",
+        "prompt": "And now?",
+        "stream": false,
+        "context": context
+    }))
+    .await;
+    assert_ne!(
+        second["context"], context,
+        "each response issues a new context"
+    );
+
+    let bodies = upstream_bodies(&p, "/api/v0/chat/completions").await;
+    assert_eq!(
+        bodies[1]["messages"],
+        json!([
+            {"role": "user", "content": "Hello"},
+            {"role": "assistant", "content": "Hi!"},
+            {"role": "user", "content": "And now?"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn emulated_context_from_stream_prefixes_raw_prompt() {
+    let p = spawn_proxy_with_config(|c| c.emulate_generate_context = true).await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .and(body_partial_json(json!({"stream": true})))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_string(sse_completion_body(&["one", " two"], "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .and(body_partial_json(json!({"stream": false})))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response(" three", "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let text = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({"model": "llama3.2:3b", "prompt": "Count:", "raw": true}))
+        .send()
+        .await
+        .expect("POST /api/generate stream")
+        .text()
+        .await
+        .expect("NDJSON body");
+    let chunks = parse_ndjson(&text);
+    let last = chunks.last().expect("final chunk");
+    assert_eq!(last["done"], true);
+    assert!(
+        chunks[..chunks.len() - 1]
+            .iter()
+            .all(|c| c.get("context").is_none()),
+        "only the final chunk carries context"
+    );
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": ",",
+            "raw": true,
+            "stream": false,
+            "context": last["context"]
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate with context");
+    assert_eq!(resp.status(), 200);
+
+    let bodies = upstream_bodies(&p, "/api/v0/completions").await;
+    assert_eq!(bodies[1]["prompt"], "Count:one two,");
+}
+
+#[tokio::test]
+async fn unknown_emulated_context_starts_fresh() {
+    let p = spawn_proxy_with_config(|c| c.emulate_generate_context = true).await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response("Continued.", "stop")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "Continue from here",
+            "stream": false,
+            "context": [1, 2, 3, 4, 5]
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("JSON");
+    assert_eq!(body["context"].as_array().map(Vec::len), Some(3), "{body}");
+
+    let bodies = upstream_bodies(&p, "/api/v0/completions").await;
+    assert_eq!(bodies[0]["prompt"], "Continue from here");
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1578,7 +2069,7 @@ async fn context_array_in_request_accepted() {
 #[tokio::test]
 async fn empty_stats_block_falls_back_to_wall_clock_timings() {
     let p = spawn_proxy().await;
-    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
 
     let mut lm_body = lm_completion_response("Reply.", "stop");
     lm_body
@@ -1832,3 +2323,72 @@ async fn generate_keep_alive_zero_no_prompt_streaming_returns_ndjson_done_chunk(
     assert_no_inference_calls(&p).await;
     assert!(wait_for_unload_call(&p).await);
 }
+
+// ═══════════════════════════════════════════════════════════════════════════
+// --default-system-prompt on /api/generate
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn default_system_prompt_frames_generate_as_system_turn() {
+    let p = spawn_proxy_with_config(|c| {
+        c.default_system_prompt = Some("Answer concisely.".to_string())
+    })
+    .await;
+    mount_llm_catalog(&p, "llama3.2-3b-instruct").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lm_chat_response("Ok.", "stop")))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({"model": "llama3.2:3b", "prompt": "Hi", "stream": false}))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(resp.status(), 200);
+
+    let bodies = upstream_bodies(&p, "/api/v0/chat/completions").await;
+    assert_eq!(
+        bodies[0]["messages"],
+        json!([
+            {"role": "system", "content": "Answer concisely."},
+            {"role": "user", "content": "Hi"}
+        ])
+    );
+}
+
+#[tokio::test]
+async fn default_system_prompt_leaves_fill_in_the_middle_on_completions() {
+    let p = spawn_proxy_with_config(|c| {
+        c.default_system_prompt = Some("Answer concisely.".to_string())
+    })
+    .await;
+    mount_llm_catalog(&p, "llama3.2-3b-base").await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .and(body_partial_json(json!({"suffix": "}"})))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lm_completion_response("x + 1", "stop")),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "fn f(x: u32) -> u32 {",
+            "suffix": "}",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate FIM");
+    assert_eq!(resp.status(), 200);
+    p.mock.verify().await;
+}
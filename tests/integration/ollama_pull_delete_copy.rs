@@ -6,10 +6,10 @@
 // Delete and copy operate on the in-process VirtualModelStore.
 
 use serde_json::{Value, json};
-use wiremock::matchers::{method, path, path_regex};
+use wiremock::matchers::{body_json, method, path, path_regex};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -509,6 +509,165 @@ async fn pull_model_name_forwarded_to_lmstudio() {
     // wiremock asserts the expectation on drop.
 }
 
+#[tokio::test]
+async fn pull_hf_web_url_posts_the_exact_repo_link() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .and(body_json(json!({
+            "model": "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_download_completed("job-hf")))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({
+            "model": "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF/blob/main/gpt-oss-20b-MXFP4.gguf",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/pull HF URL");
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn pull_rejected_by_lmstudio_names_the_identifier() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({"error": "model not found"})))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({"model": "hf.co/owner/missing-GGUF", "stream": false}))
+        .send()
+        .await
+        .expect("POST /api/pull rejected");
+    assert_eq!(resp.status(), 400);
+    let body: Value = resp.json().await.expect("json body");
+    let error = body["error"].as_str().unwrap_or_default();
+    assert!(
+        error.contains("https://huggingface.co/owner/missing-GGUF")
+            && error.contains("model not found"),
+        "{body}"
+    );
+}
+
+#[tokio::test]
+async fn pull_hf_url_without_repo_is_rejected_before_lmstudio() {
+    let p = spawn_proxy().await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({"model": "https://huggingface.co/datasets/owner/data", "stream": false}))
+        .send()
+        .await
+        .expect("POST /api/pull dataset URL");
+    assert_eq!(resp.status(), 400);
+    assert!(
+        p.mock
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .is_empty()
+    );
+}
+
+// ---------------------------------------------------------------------------
+// POST /api/pull — concurrent pulls of one model share a download
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn concurrent_pulls_of_one_model_start_a_single_download() {
+    let p = spawn_proxy().await;
+
+    // The slow answer keeps the first pull in flight while the second arrives.
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(lms_download_downloading("job-shared", 0, 4_000))
+                .set_delay(std::time::Duration::from_millis(300)),
+        )
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models/download/status/job-shared"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_download_completed("job-shared")),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let model = "https://huggingface.co/owner/repo";
+    let pull = |stream: bool| {
+        p.client
+            .post(p.url("/api/pull"))
+            .json(&json!({"model": model, "stream": stream}))
+            .send()
+    };
+    let first = pull(false);
+    let second = async {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        pull(true).await
+    };
+    let (first, second) = tokio::join!(first, second);
+
+    let first: Value = first.expect("first pull").json().await.expect("json body");
+    assert_eq!(first, json!({"status": "success"}));
+    let second = second
+        .expect("second pull")
+        .text()
+        .await
+        .expect("stream body");
+    let last: Value = serde_json::from_str(second.lines().last().expect("a chunk")).unwrap();
+    assert_eq!(last, json!({"status": "success"}), "{second}");
+
+    let downloads = p
+        .mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.method.as_str() == "POST" && r.url.path() == "/api/v1/models/download")
+        .count();
+    assert_eq!(downloads, 1);
+}
+
+#[tokio::test]
+async fn a_finished_pull_does_not_capture_the_next_one() {
+    let p = spawn_proxy().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_download_already("job-done")))
+        .expect(2)
+        .mount(&p.mock)
+        .await;
+
+    for _ in 0..2 {
+        let resp = p
+            .client
+            .post(p.url("/api/pull"))
+            .json(&json!({"model": "https://huggingface.co/owner/repo", "stream": false}))
+            .send()
+            .await
+            .expect("POST /api/pull");
+        assert_eq!(resp.status(), 200);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // POST /api/pull — insecure flag is accepted and ignored (no TLS-skip surface)
 // ---------------------------------------------------------------------------
@@ -692,6 +851,132 @@ async fn delete_unknown_model_returns_404() {
     );
 }
 
+#[tokio::test]
+async fn delete_real_model_without_flag_returns_404_and_does_not_unload() {
+    let p = spawn_proxy().await;
+
+    let mut loaded = native_model("llama3.2:3b");
+    loaded["loaded_instances"] = json!([{ "id": "llama3.2:3b-inst" }]);
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![loaded])))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/unload"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .delete(p.url("/api/delete"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("DELETE /api/delete real model");
+
+    assert_eq!(resp.status(), 404);
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn delete_real_model_with_delete_unloads_unloads_instances() {
+    let p = spawn_proxy_with_config(|c| c.delete_unloads = true).await;
+
+    let mut loaded = native_model("llama3.2:3b");
+    loaded["loaded_instances"] = json!([{ "id": "llama3.2:3b-inst" }]);
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![loaded])))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/unload"))
+        .and(body_json(json!({ "instance_id": "llama3.2:3b-inst" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .delete(p.url("/api/delete"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("DELETE /api/delete with --delete-unloads");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["status"], "success");
+    p.mock.verify().await;
+}
+
+#[tokio::test]
+async fn delete_unknown_model_with_delete_unloads_returns_404() {
+    let p = spawn_proxy_with_config(|c| c.delete_unloads = true).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![])))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .delete(p.url("/api/delete"))
+        .json(&json!({"model": "ghost:latest"}))
+        .send()
+        .await
+        .expect("DELETE /api/delete unknown with --delete-unloads");
+
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn delete_virtual_alias_with_delete_unloads_still_removes_alias() {
+    let p = spawn_proxy_with_config(|c| c.delete_unloads = true).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/unload"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(0)
+        .mount(&p.mock)
+        .await;
+
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({"source": "llama3.2:3b", "destination": "alias:v1"}))
+        .send()
+        .await
+        .expect("POST /api/copy");
+    assert_eq!(copy.status(), 200);
+
+    let del = p
+        .client
+        .delete(p.url("/api/delete"))
+        .json(&json!({"model": "alias:v1"}))
+        .send()
+        .await
+        .expect("DELETE /api/delete alias");
+    assert_eq!(del.status(), 200);
+    assert!(
+        del.bytes().await.expect("bytes").is_empty(),
+        "alias delete keeps the empty-body response"
+    );
+    p.mock.verify().await;
+}
+
 #[tokio::test]
 async fn delete_virtual_model_removed_from_tags() {
     let p = spawn_proxy().await;
@@ -1036,6 +1321,90 @@ async fn copy_creates_virtual_model_showable_via_show() {
     );
 }
 
+#[tokio::test]
+async fn copy_with_system_override_inherits_remaining_metadata() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("mistral:7b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let create = p
+        .client
+        .post(p.url("/api/create"))
+        .json(&json!({
+            "model": "base-alias:v1",
+            "from": "mistral:7b",
+            "system": "You are terse.",
+            "template": "{{ .Prompt }}",
+            "parameters": {"temperature": 0.2, "top_k": 10},
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/create");
+    assert_eq!(create.status(), 200);
+
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({
+            "source": "base-alias:v1",
+            "destination": "verbose-alias:v1",
+            "system": "You are verbose.",
+            "parameters": {"temperature": 0.9}
+        }))
+        .send()
+        .await
+        .expect("POST /api/copy with overrides");
+    assert_eq!(copy.status(), 200);
+
+    let show = |model: &'static str| {
+        p.client
+            .post(p.url("/api/show"))
+            .json(&json!({ "model": model }))
+            .send()
+    };
+
+    let copied: Value = show("verbose-alias:v1")
+        .await
+        .expect("POST /api/show copy")
+        .json()
+        .await
+        .expect("show body");
+    assert_eq!(
+        copied["system"], "You are verbose.",
+        "override applied: {copied}"
+    );
+    assert_eq!(
+        copied["template"], "{{ .Prompt }}",
+        "template inherited: {copied}"
+    );
+    assert_eq!(
+        copied["parameters"],
+        json!({"temperature": 0.9, "top_k": 10}),
+        "parameters deep-merged: {copied}"
+    );
+    assert_eq!(copied["target_model_id"], "mistral:7b");
+
+    // The source alias is untouched by the copy's overrides.
+    let source: Value = show("base-alias:v1")
+        .await
+        .expect("POST /api/show source")
+        .json()
+        .await
+        .expect("show body");
+    assert_eq!(source["system"], "You are terse.");
+    assert_eq!(
+        source["parameters"],
+        json!({"temperature": 0.2, "top_k": 10})
+    );
+}
+
 // ---------------------------------------------------------------------------
 // POST /api/create — unsupported backends explained clearly
 // ---------------------------------------------------------------------------
@@ -1102,3 +1471,252 @@ async fn create_quantize_returns_400_explaining_backend() {
         "quantize rejection must explain the backend limit; got {error}"
     );
 }
+
+// ---------------------------------------------------------------------------
+// Pull cancellation: DELETE /api/pull and abandoned streams
+// ---------------------------------------------------------------------------
+
+/// Start a streaming pull of a download that never finishes, and mount the
+/// LM Studio cancel endpoint.
+async fn start_endless_pull(p: &crate::common::TestProxy) -> reqwest::Response {
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_download_downloading(
+                "job-endless",
+                0,
+                4_000_000_000,
+            )),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/api/v1/models/download/status/.*"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_download_downloading(
+                "job-endless",
+                1_000_000_000,
+                4_000_000_000,
+            )),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download/cancel"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "cancelled"})))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model("llama3.2:3b")])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let mut resp = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({"model": "llama3.2:3b", "stream": true}))
+        .send()
+        .await
+        .expect("POST /api/pull stream");
+    assert_eq!(resp.status(), 200);
+    resp.chunk()
+        .await
+        .expect("first chunk")
+        .expect("stream open");
+    resp
+}
+
+/// Wait for the proxy to ask LM Studio to cancel `job_id`.
+async fn wait_for_cancel_call(p: &crate::common::TestProxy, job_id: &str) -> bool {
+    for _ in 0..50 {
+        let received = p.mock.received_requests().await.unwrap_or_default();
+        if received.iter().any(|r| {
+            r.url.path() == "/api/v1/models/download/cancel"
+                && serde_json::from_slice::<Value>(&r.body).ok() == Some(json!({"job_id": job_id}))
+        }) {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn delete_pull_cancels_in_flight_download() {
+    let p = spawn_proxy().await;
+    let stream = start_endless_pull(&p).await;
+
+    let resp = p
+        .client
+        .delete(p.url("/api/pull"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("DELETE /api/pull");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["status"], "cancelled");
+    assert_eq!(body["job_id"], "job-endless");
+    assert_eq!(body["last_status"]["total"], 4_000_000_000u64);
+
+    let text = stream.text().await.expect("stream ends");
+    let last: Value = serde_json::from_str(text.lines().last().expect("chunks")).unwrap();
+    assert_eq!(last, json!({"error": "download cancelled"}));
+    assert!(wait_for_cancel_call(&p, "job-endless").await);
+}
+
+#[tokio::test]
+async fn abandoned_pull_stream_cancels_download() {
+    let p = spawn_proxy().await;
+    let stream = start_endless_pull(&p).await;
+    drop(stream);
+
+    assert!(
+        wait_for_cancel_call(&p, "job-endless").await,
+        "dropping the pull stream must cancel the LM Studio download"
+    );
+}
+
+#[tokio::test]
+async fn shared_pull_outlives_its_leader_until_the_last_client_leaves() {
+    let p = spawn_proxy().await;
+    let leader = start_endless_pull(&p).await;
+    let mut follower = p
+        .client
+        .post(p.url("/api/pull"))
+        .json(&json!({"model": "llama3.2:3b", "stream": true}))
+        .send()
+        .await
+        .expect("second POST /api/pull");
+    follower
+        .chunk()
+        .await
+        .expect("follower chunk")
+        .expect("follower stream open");
+
+    drop(leader);
+    for _ in 0..3 {
+        let chunk = follower.chunk().await.expect("follower keeps streaming");
+        assert!(chunk.is_some(), "the download must survive its leader");
+    }
+    let cancels = p
+        .mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.url.path() == "/api/v1/models/download/cancel")
+        .count();
+    assert_eq!(cancels, 0, "a followed download must not be cancelled");
+
+    drop(follower);
+    assert!(
+        wait_for_cancel_call(&p, "job-endless").await,
+        "the last client leaving must cancel the LM Studio download"
+    );
+}
+
+#[tokio::test]
+async fn delete_pull_without_download_returns_404() {
+    let p = spawn_proxy().await;
+    let resp = p
+        .client
+        .delete(p.url("/api/pull"))
+        .json(&json!({"job_id": "job-unknown"}))
+        .send()
+        .await
+        .expect("DELETE /api/pull");
+    assert_eq!(resp.status(), 404);
+}
+
+// ---------------------------------------------------------------------------
+// POST /api/proxy/pull/cancel
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn proxy_pull_cancel_stops_a_tracked_job() {
+    let p = spawn_proxy().await;
+    let stream = start_endless_pull(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/proxy/pull/cancel"))
+        .json(&json!({"job_id": "job-endless"}))
+        .send()
+        .await
+        .expect("POST /api/proxy/pull/cancel");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["status"], "cancelled");
+    assert_eq!(body["model"], "llama3.2:3b");
+
+    let text = stream.text().await.expect("stream ends");
+    assert!(
+        text.ends_with("{\"error\":\"download cancelled\"}\n"),
+        "{text}"
+    );
+    assert!(wait_for_cancel_call(&p, "job-endless").await);
+}
+
+#[tokio::test]
+async fn proxy_pull_cancel_forwards_untracked_jobs_to_lmstudio() {
+    let p = spawn_proxy().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download/cancel"))
+        .and(body_json(json!({"job_id": "job-elsewhere"})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": "cancelled"})))
+        .expect(1)
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models/download/status/job-elsewhere"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_download_paused("job-elsewhere", 10, 100)),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/proxy/pull/cancel"))
+        .json(&json!({"job_id": "job-elsewhere"}))
+        .send()
+        .await
+        .expect("POST /api/proxy/pull/cancel");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert_eq!(body["job_id"], "job-elsewhere");
+    assert_eq!(body["last_status"]["status"], "paused");
+    assert_eq!(body["last_status"]["completed"], 10);
+}
+
+#[tokio::test]
+async fn proxy_pull_cancel_needs_a_job_lmstudio_knows() {
+    let p = spawn_proxy().await;
+    Mock::given(method("POST"))
+        .and(path("/api/v1/models/download/cancel"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&p.mock)
+        .await;
+
+    let missing = p
+        .client
+        .post(p.url("/api/proxy/pull/cancel"))
+        .json(&json!({"job_id": "job-unknown"}))
+        .send()
+        .await
+        .expect("POST /api/proxy/pull/cancel");
+    assert_eq!(missing.status(), 404);
+
+    let no_job = p
+        .client
+        .post(p.url("/api/proxy/pull/cancel"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("POST /api/proxy/pull/cancel");
+    assert_eq!(no_job.status(), 400);
+}
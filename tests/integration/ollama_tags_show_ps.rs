@@ -9,7 +9,7 @@ use serde_json::{Value, json};
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{TestProxy, spawn_proxy, spawn_proxy_with_config};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -99,11 +99,10 @@ async fn tags_multiple_models_ollama_shape() {
     for m in models {
         assert!(m["name"].is_string(), "missing name in {m}");
         assert!(m["model"].is_string(), "missing model in {m}");
-        // LM Studio surfaces no per-model mtime; the proxy must omit
-        // modified_at rather than fabricate one.
+        let modified_at = m["modified_at"].as_str().expect("modified_at string");
         assert!(
-            m.get("modified_at").is_none(),
-            "tags entry must omit modified_at when no real mtime is available; got {m}"
+            chrono::DateTime::parse_from_rfc3339(modified_at).is_ok(),
+            "modified_at must be RFC3339; got {m}"
         );
         assert!(m["size"].is_number(), "missing size in {m}");
         assert_eq!(
@@ -199,8 +198,21 @@ async fn tags_model_details_omit_parent_model() {
     );
 }
 
+async fn tags_entry(p: &TestProxy) -> Value {
+    let body: Value = p
+        .client
+        .get(p.url("/api/tags"))
+        .send()
+        .await
+        .expect("GET /api/tags")
+        .json()
+        .await
+        .expect("json body");
+    body["models"][0].clone()
+}
+
 #[tokio::test]
-async fn tags_response_omits_modified_at_when_no_mtime_available() {
+async fn tags_modified_at_and_digest_are_stable_until_the_model_changes() {
     let p = spawn_proxy().await;
 
     Mock::given(method("GET"))
@@ -215,23 +227,29 @@ async fn tags_response_omits_modified_at_when_no_mtime_available() {
         .mount(&p.mock)
         .await;
 
-    let resp = p
-        .client
-        .get(p.url("/api/tags"))
-        .send()
-        .await
-        .expect("GET /api/tags");
-    assert_eq!(resp.status(), 200);
+    // LM Studio exposes no mtime; the proxy reports when it first saw the
+    // model and keeps that across polls instead of stamping Utc::now().
+    let first = tags_entry(&p).await;
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    let second = tags_entry(&p).await;
+    assert!(first["modified_at"].is_string(), "got {first}");
+    assert_eq!(first["modified_at"], second["modified_at"]);
+    assert_eq!(first["digest"], second["digest"]);
+
+    // a different quantization is a different model file
+    let mut requantized = native_model("llama3.2:3b", "llama", false);
+    requantized["quantization"] = json!({"name": "Q8_0"});
+    p.mock.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![requantized])))
+        .mount(&p.mock)
+        .await;
 
-    let body: Value = resp.json().await.expect("json body");
-    // LM Studio's model list exposes no per-model mtime. The proxy must omit
-    // `modified_at` rather than fabricate Utc::now() or the epoch.
-    for m in body["models"].as_array().expect("models array") {
-        assert!(
-            m.get("modified_at").is_none(),
-            "tags entry must omit modified_at when no real mtime is available; got {m}"
-        );
-    }
+    let third = tags_entry(&p).await;
+    assert_ne!(third["digest"], first["digest"]);
+    assert_ne!(third["modified_at"], first["modified_at"]);
+    assert_eq!(tags_entry(&p).await["modified_at"], third["modified_at"]);
 }
 
 #[tokio::test]
@@ -284,6 +302,66 @@ async fn tags_virtual_model_created_via_copy_appears_in_list() {
     );
 }
 
+#[tokio::test]
+async fn tags_order_is_base_models_then_aliases_by_name() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![
+            native_model("llama3.2:3b", "llama", false),
+            native_model("qwen2.5:7b", "qwen2", false),
+        ])))
+        .mount(&p.mock)
+        .await;
+
+    for (source, alias) in [
+        ("qwen2.5:7b", "zeta:latest"),
+        ("llama3.2:3b", "alpha:latest"),
+        ("qwen2.5:7b", "mid:latest"),
+    ] {
+        let copy = p
+            .client
+            .post(p.url("/api/copy"))
+            .json(&json!({"source": source, "destination": alias}))
+            .send()
+            .await
+            .expect("POST /api/copy");
+        assert_eq!(copy.status(), 200);
+    }
+
+    let mut listings = Vec::new();
+    for _ in 0..3 {
+        let body: Value = p
+            .client
+            .get(p.url("/api/tags"))
+            .send()
+            .await
+            .expect("GET /api/tags")
+            .json()
+            .await
+            .expect("json body");
+        let names: Vec<String> = body["models"]
+            .as_array()
+            .expect("models array")
+            .iter()
+            .map(|m| m["name"].as_str().unwrap_or_default().to_string())
+            .collect();
+        listings.push(names);
+    }
+    assert_eq!(
+        listings[0],
+        [
+            "llama3.2:3b",
+            "qwen2.5:7b",
+            "alpha:latest",
+            "mid:latest",
+            "zeta:latest"
+        ]
+    );
+    assert!(listings.windows(2).all(|w| w[0] == w[1]), "{listings:?}");
+}
+
 #[tokio::test]
 async fn tags_backend_5xx_returns_error() {
     let p = spawn_proxy().await;
@@ -307,6 +385,113 @@ async fn tags_backend_5xx_returns_error() {
     );
 }
 
+async fn models_fetch_count(p: &TestProxy) -> usize {
+    p.mock
+        .received_requests()
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter(|r| r.method.as_str() == "GET" && r.url.path() == "/api/v1/models")
+        .count()
+}
+
+#[tokio::test]
+async fn tags_share_cached_model_list_and_ps_reads_past_it() {
+    let p = spawn_proxy_with_config(|c| c.models_cache_ttl_seconds = 60).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model(
+                "llama3.2:3b",
+                "llama",
+                true,
+            )])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    for endpoint in ["/api/tags", "/api/tags"] {
+        let resp = p
+            .client
+            .get(p.url(endpoint))
+            .send()
+            .await
+            .expect("GET listing");
+        assert_eq!(resp.status(), 200);
+    }
+    assert_eq!(models_fetch_count(&p).await, 1);
+
+    // Loaded state changes with every load and unload, so /api/ps always
+    // asks LM Studio.
+    for _ in 0..2 {
+        let resp = p.client.get(p.url("/api/ps")).send().await.expect("GET ps");
+        assert_eq!(resp.status(), 200);
+    }
+    assert_eq!(models_fetch_count(&p).await, 3);
+}
+
+#[tokio::test]
+async fn delete_invalidates_cached_model_list() {
+    let p = spawn_proxy_with_config(|c| c.models_cache_ttl_seconds = 60).await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model(
+                "llama3.2:3b",
+                "llama",
+                false,
+            )])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let copy = p
+        .client
+        .post(p.url("/api/copy"))
+        .json(&json!({"source": "llama3.2:3b", "destination": "my-alias:latest"}))
+        .send()
+        .await
+        .expect("POST /api/copy");
+    assert_eq!(copy.status(), 200);
+
+    let tags = |p: &TestProxy| p.client.get(p.url("/api/tags")).send();
+    assert_eq!(tags(&p).await.expect("GET /api/tags").status(), 200);
+    let before_delete = models_fetch_count(&p).await;
+    assert_eq!(tags(&p).await.expect("GET /api/tags").status(), 200);
+    assert_eq!(
+        models_fetch_count(&p).await,
+        before_delete,
+        "served from cache"
+    );
+
+    let delete = p
+        .client
+        .delete(p.url("/api/delete"))
+        .json(&json!({"model": "my-alias:latest"}))
+        .send()
+        .await
+        .expect("DELETE /api/delete");
+    assert_eq!(delete.status(), 200);
+    let after_delete = models_fetch_count(&p).await;
+
+    let body: Value = tags(&p)
+        .await
+        .expect("GET /api/tags")
+        .json()
+        .await
+        .expect("json body");
+    assert_eq!(models_fetch_count(&p).await, after_delete + 1);
+    let names: Vec<&str> = body["models"]
+        .as_array()
+        .expect("models array")
+        .iter()
+        .filter_map(|m| m["name"].as_str())
+        .collect();
+    assert!(!names.iter().any(|n| n.contains("my-alias")), "{body}");
+}
+
 // ---------------------------------------------------------------------------
 // POST /api/show
 // ---------------------------------------------------------------------------
@@ -364,6 +549,110 @@ async fn show_present_model_returns_full_shape() {
     );
 }
 
+#[tokio::test]
+async fn show_resolves_and_looks_up_from_one_model_list_fetch() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model(
+                "llama3.2:3b",
+                "llama",
+                false,
+            )])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/show?debug=true"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("POST /api/show");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+    assert!(body["proxy_match_debug"].is_object(), "{body}");
+
+    // Resolution, the load warm-up, the lookup and the match debug all
+    // share one list.
+    assert_eq!(models_fetch_count(&p).await, 1);
+}
+
+#[tokio::test]
+async fn show_debug_reports_match_scores_in_descending_order() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![
+            native_model("llama3.2-3b-instruct", "llama", true),
+            native_model("granite-embedding-278m", "bert", false),
+            native_model("granite-3.1-8b-instruct", "granite", true),
+        ])))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/show?debug=true"))
+        .json(&json!({"model": "granite"}))
+        .send()
+        .await
+        .expect("POST /api/show?debug=true");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("json body");
+
+    let debug = &body["proxy_match_debug"];
+    assert_eq!(debug["query"], "granite");
+    assert_eq!(debug["resolved_via"], "matcher");
+    let candidates = debug["candidates"].as_array().expect("candidates array");
+    assert_eq!(candidates.len(), 3, "{debug}");
+    let scores: Vec<u64> = candidates
+        .iter()
+        .map(|c| c["score"].as_u64().expect("numeric score"))
+        .collect();
+    assert!(
+        scores.windows(2).all(|w| w[0] >= w[1]),
+        "scores must be descending: {scores:?}"
+    );
+    let selected: Vec<&Value> = candidates
+        .iter()
+        .filter(|c| c["selected"] == json!(true))
+        .collect();
+    assert_eq!(selected.len(), 1, "{debug}");
+    assert_eq!(selected[0]["id"], debug["resolved_id"]);
+}
+
+#[tokio::test]
+async fn show_omits_match_debug_without_flag() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model(
+                "llama3.2:3b",
+                "llama",
+                true,
+            )])),
+        )
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/show"))
+        .json(&json!({"model": "llama3.2:3b"}))
+        .send()
+        .await
+        .expect("POST /api/show");
+    let body: Value = resp.json().await.expect("json body");
+    assert!(body.get("proxy_match_debug").is_none(), "{body}");
+}
+
 #[tokio::test]
 async fn show_loaded_model_uses_configured_context() {
     let p = spawn_proxy().await;
@@ -719,7 +1008,7 @@ async fn ps_no_loaded_models_returns_empty() {
 }
 
 #[tokio::test]
-async fn ps_loaded_model_has_expires_at_and_size_vram_mirrors_size() {
+async fn ps_untracked_model_omits_expires_at_and_size_vram_mirrors_size() {
     let p = spawn_proxy().await;
 
     Mock::given(method("GET"))
@@ -751,7 +1040,11 @@ async fn ps_loaded_model_has_expires_at_and_size_vram_mirrors_size() {
     // Per docs/lmstudio_vs_ollama.md §"Running models", /api/ps entries
     // expose both `name` (display) and `model` (canonical identifier).
     assert!(m["model"].is_string(), "missing model; {m}");
-    assert!(m["expires_at"].is_string(), "missing expires_at; {m}");
+    // Loaded out-of-band: the proxy has no TTL for it, so no guessed deadline.
+    assert!(
+        m.get("expires_at").is_none(),
+        "untracked model must omit expires_at; {m}"
+    );
     // size_vram mirrors the loaded size (LM Studio gives no GPU/CPU split).
     assert_eq!(
         m["size_vram"], m["size"],
@@ -766,6 +1059,69 @@ async fn ps_loaded_model_has_expires_at_and_size_vram_mirrors_size() {
     );
 }
 
+#[tokio::test]
+async fn ps_expires_at_reflects_tracked_keep_alive() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(lms_models(vec![native_model(
+                "llama3.2:3b",
+                "llama",
+                true,
+            )])),
+        )
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "ok" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2 }
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let gen_resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3.2:3b",
+            "prompt": "hi",
+            "keep_alive": "10m",
+            "stream": false
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+    assert_eq!(gen_resp.status(), 200);
+
+    let resp = p
+        .client
+        .get(p.url("/api/ps"))
+        .send()
+        .await
+        .expect("GET /api/ps");
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("json body");
+    let m = &body["models"][0];
+    let expires_at = m["expires_at"]
+        .as_str()
+        .unwrap_or_else(|| panic!("tracked model must report expires_at; {m}"));
+    let ts = chrono::DateTime::parse_from_rfc3339(expires_at).expect("RFC3339 expires_at");
+    let remaining = ts.timestamp() - chrono::Utc::now().timestamp();
+    assert!(
+        (590..=600).contains(&remaining),
+        "expires_at should be ~10m out, got {remaining}s; {m}"
+    );
+}
+
 #[tokio::test]
 async fn ps_loaded_model_size_vram_mirrors_size() {
     let p = spawn_proxy().await;
@@ -910,6 +1266,38 @@ async fn ps_loaded_model_details_have_empty_parent_model() {
     );
 }
 
+#[tokio::test]
+async fn ps_lists_each_loaded_instance_separately() {
+    let p = spawn_proxy().await;
+
+    let mut model = native_model("llama3.2:3b", "llama", true);
+    model["loaded_instances"] = json!([
+        {"id": "llama3.2:3b", "config": {"context_length": 4096}},
+        {"id": "llama3.2:3b:2", "config": {"context_length": 8192}}
+    ]);
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(lms_models(vec![model])))
+        .mount(&p.mock)
+        .await;
+
+    let body: Value = p
+        .client
+        .get(p.url("/api/ps"))
+        .send()
+        .await
+        .expect("GET /api/ps")
+        .json()
+        .await
+        .expect("json body");
+    let models = body["models"].as_array().expect("models array");
+    assert_eq!(models.len(), 2, "one entry per instance; got {body}");
+    assert_eq!(models[0]["name"], json!("llama3.2:3b"));
+    assert_eq!(models[0]["context_length"], json!(4096));
+    assert_eq!(models[1]["name"], json!("llama3.2:3b@llama3.2:3b:2"));
+    assert_eq!(models[1]["context_length"], json!(8192));
+}
+
 #[tokio::test]
 async fn show_verbose_surfaces_loaded_tuning_from_instance_config() {
     let p = spawn_proxy().await;
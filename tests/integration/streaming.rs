@@ -5,11 +5,12 @@
 // on shape and content.
 
 use futures_util::StreamExt;
+use ollama_lmstudio_proxy::config::ReasoningMode;
 use serde_json::{Value, json};
 use wiremock::matchers::{method, path, path_regex};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::common::spawn_proxy;
+use crate::common::{spawn_proxy, spawn_proxy_with_config};
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -154,7 +155,7 @@ async fn generate_stream_emits_ndjson_chunks_with_done() {
     Mock::given(method("GET"))
         .and(path("/api/v1/models"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+            "models": [{"key": "llama3-base", "type": "llm", "publisher": "meta",
                         "architecture": "llama", "format": "gguf",
                         "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
                         "max_context_length": 8192, "loaded_instances": [],
@@ -183,6 +184,67 @@ async fn generate_stream_emits_ndjson_chunks_with_done() {
     assert_eq!(last.get("done"), Some(&json!(true)));
 }
 
+// ---------------------------------------------------------------------------
+// 2b. /api/generate stream that hit the token limit ends with done_reason length
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn generate_stream_truncated_generation_reports_length() {
+    let p = spawn_proxy().await;
+
+    // Transcript of a completion cut off by max_tokens: the reason arrives on
+    // the last choice of the last data chunk, followed by a usage-only chunk.
+    let body = sse_body(&[
+        r#"{"id":"cmpl-1","object":"text_completion","choices":[{"index":0,"text":"Once upon","finish_reason":null}]}"#,
+        r#"{"id":"cmpl-1","object":"text_completion","choices":[{"index":0,"text":" a time","finish_reason":null}]}"#,
+        r#"{"id":"cmpl-1","object":"text_completion","choices":[{"index":0,"text":"","finish_reason":null},{"index":1,"text":"","finish_reason":"length"}]}"#,
+        r#"{"id":"cmpl-1","object":"text_completion","choices":[],"usage":{"prompt_tokens":3,"completion_tokens":4,"total_tokens":7}}"#,
+    ]);
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/completions"))
+        .respond_with(sse_response(body))
+        .mount(&p.mock)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "llama3-base", "type": "llm", "publisher": "meta",
+                        "architecture": "llama", "format": "gguf",
+                        "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
+                        "max_context_length": 8192, "loaded_instances": [],
+                        "capabilities": {"vision": false, "trained_for_tool_use": false}}]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/generate"))
+        .json(&json!({
+            "model": "llama3",
+            "prompt": "Tell me a story",
+            "stream": true,
+            "options": { "num_predict": 4 }
+        }))
+        .send()
+        .await
+        .expect("POST /api/generate");
+
+    assert_eq!(resp.status(), 200);
+    let chunks = collect_ndjson(resp).await;
+    let text: String = chunks
+        .iter()
+        .filter_map(|c| c.get("response").and_then(|r| r.as_str()))
+        .collect();
+    assert_eq!(text, "Once upon a time");
+
+    let last = chunks.last().expect("last chunk");
+    assert_eq!(last["done"], true);
+    assert_eq!(last["done_reason"], "length", "{last}");
+}
+
 // ---------------------------------------------------------------------------
 // 3. /api/embed — no streaming, returns a single JSON body
 // ---------------------------------------------------------------------------
@@ -955,7 +1017,7 @@ async fn generate_stream_reasoning_goes_to_thinking_not_response() {
     Mock::given(method("GET"))
         .and(path("/api/v1/models"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+            "models": [{"key": "llama3-base", "type": "llm", "publisher": "meta",
                         "architecture": "llama", "format": "gguf",
                         "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
                         "max_context_length": 8192, "loaded_instances": [],
@@ -1126,7 +1188,7 @@ async fn generate_non_stream_returns_single_object() {
     Mock::given(method("GET"))
         .and(path("/api/v1/models"))
         .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+            "models": [{"key": "llama3-base", "type": "llm", "publisher": "meta",
                         "architecture": "llama", "format": "gguf",
                         "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
                         "max_context_length": 8192, "loaded_instances": [],
@@ -1353,3 +1415,243 @@ async fn chat_stream_tool_calls_emit_intermediate_chunks() {
         "expected at least one intermediate (done:false) chunk with tool_calls; got {chunks:#?}"
     );
 }
+
+// ---------------------------------------------------------------------------
+// Reasoning modes — options.reasoning_mode / --reasoning-mode
+// ---------------------------------------------------------------------------
+
+async fn mount_reasoning_stream(p: &crate::common::TestProxy) {
+    let body = sse_body(&[
+        r#"{"choices":[{"delta":{"reasoning":"let me think. "},"finish_reason":null}]}"#,
+        r#"{"choices":[{"delta":{"content":"Answer"},"finish_reason":"stop"}]}"#,
+    ]);
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(sse_response(body))
+        .mount(&p.mock)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+                        "architecture": "llama", "format": "gguf",
+                        "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
+                        "max_context_length": 8192, "loaded_instances": [],
+                        "capabilities": {"vision": false, "trained_for_tool_use": false}}]
+        })))
+        .mount(&p.mock)
+        .await;
+}
+
+fn joined_message_field(chunks: &[Value], field: &str) -> String {
+    chunks
+        .iter()
+        .filter_map(|c| c["message"][field].as_str())
+        .collect()
+}
+
+#[tokio::test]
+async fn chat_stream_reasoning_mode_strip_drops_reasoning() {
+    let p = spawn_proxy().await;
+    mount_reasoning_stream(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "think"}],
+            "stream": true,
+            "options": {"reasoning_mode": "strip"}
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    let chunks = collect_ndjson(resp).await;
+    assert_eq!(joined_message_field(&chunks, "thinking"), "");
+    assert_eq!(joined_message_field(&chunks, "content"), "Answer");
+    assert_eq!(
+        chunks.iter().filter(|c| c["done"] == json!(false)).count(),
+        1,
+        "the reasoning-only delta must not produce an empty chunk: {chunks:?}"
+    );
+}
+
+#[tokio::test]
+async fn chat_stream_server_reasoning_mode_merge_prepends_to_content() {
+    let p = spawn_proxy_with_config(|c| c.reasoning_mode = ReasoningMode::Merge).await;
+    mount_reasoning_stream(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "think"}],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    let chunks = collect_ndjson(resp).await;
+    assert_eq!(joined_message_field(&chunks, "thinking"), "");
+    assert_eq!(
+        joined_message_field(&chunks, "content"),
+        "let me think. Answer"
+    );
+}
+
+#[tokio::test]
+async fn chat_stream_request_reasoning_mode_overrides_server_default() {
+    let p = spawn_proxy_with_config(|c| c.reasoning_mode = ReasoningMode::Strip).await;
+    mount_reasoning_stream(&p).await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "think"}],
+            "stream": true,
+            "options": {"reasoning_mode": "separate"}
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    let chunks = collect_ndjson(resp).await;
+    assert_eq!(joined_message_field(&chunks, "thinking"), "let me think. ");
+    assert_eq!(joined_message_field(&chunks, "content"), "Answer");
+}
+
+// ---------------------------------------------------------------------------
+// X-Request-Id: echoed on streaming responses and forwarded to LM Studio
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn streaming_response_carries_request_id_end_to_end() {
+    let p = spawn_proxy().await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(sse_response(sse_body(&[
+            r#"{"choices":[{"delta":{"content":"hi"},"finish_reason":"stop"}]}"#,
+        ])))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+                        "architecture": "llama", "format": "gguf",
+                        "quantization": {"name": "Q4_K_M", "bits_per_weight": 4.5},
+                        "max_context_length": 8192, "loaded_instances": [],
+                        "capabilities": {"vision": false, "trained_for_tool_use": false}}]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .header("X-Request-Id", "client-trace-1")
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "hi"}],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+
+    assert_eq!(
+        resp.headers().get("x-request-id").unwrap(),
+        "client-trace-1"
+    );
+    collect_ndjson(resp).await;
+
+    let received = p.mock.received_requests().await.unwrap_or_default();
+    assert!(!received.is_empty());
+    for request in &received {
+        assert_eq!(
+            request
+                .headers
+                .get("x-request-id")
+                .map(|v| v.to_str().unwrap()),
+            Some("client-trace-1"),
+            "every LM Studio call for the request carries its id: {}",
+            request.url
+        );
+    }
+}
+
+#[tokio::test]
+async fn request_id_is_generated_when_absent() {
+    let p = spawn_proxy().await;
+
+    let first = p.client.get(p.url("/api/version")).send().await.unwrap();
+    let second = p.client.get(p.url("/no/such/route")).send().await.unwrap();
+
+    let first_id = first.headers().get("x-request-id").expect("generated id");
+    let second_id = second
+        .headers()
+        .get("x-request-id")
+        .expect("error responses carry an id too");
+    assert_eq!(first_id.len(), 8);
+    assert_ne!(first_id, second_id);
+}
+
+// ---------------------------------------------------------------------------
+// load_duration: a slow first chunk is reported as the model load
+// ---------------------------------------------------------------------------
+
+async fn streamed_load_duration(delay: std::time::Duration) -> u64 {
+    let p = spawn_proxy().await;
+    let body = sse_body(&[r#"{"choices":[{"delta":{"content":"hi"},"finish_reason":"stop"}]}"#]);
+    Mock::given(method("POST"))
+        .and(path("/api/v0/chat/completions"))
+        .respond_with(sse_response(body).set_delay(delay))
+        .mount(&p.mock)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"key": "llama3", "type": "llm", "publisher": "meta",
+                        "architecture": "llama", "format": "gguf",
+                        "max_context_length": 8192, "loaded_instances": []}]
+        })))
+        .mount(&p.mock)
+        .await;
+
+    let resp = p
+        .client
+        .post(p.url("/api/chat"))
+        .json(&json!({
+            "model": "llama3",
+            "messages": [{"role": "user", "content": "hi"}],
+            "stream": true
+        }))
+        .send()
+        .await
+        .expect("POST /api/chat");
+    let chunks = collect_ndjson(resp).await;
+    let last = chunks.last().expect("final chunk");
+    assert_eq!(last["done"], true);
+    last["load_duration"].as_u64().expect("load_duration")
+}
+
+#[tokio::test]
+async fn slow_first_chunk_is_reported_as_load_duration() {
+    let load = streamed_load_duration(std::time::Duration::from_millis(900)).await;
+    assert!(load >= 900_000_000, "load_duration {load} misses the delay");
+}
+
+#[tokio::test]
+async fn warm_stream_reports_near_zero_load_duration() {
+    let load = streamed_load_duration(std::time::Duration::ZERO).await;
+    assert!(load < 500_000_000, "load_duration {load} for a warm model");
+}
@@ -61,3 +61,15 @@ mod auto_evict;
 
 #[path = "integration/cold_load_bare_key.rs"]
 mod cold_load_bare_key;
+
+#[path = "integration/concurrency_limit.rs"]
+mod concurrency_limit;
+
+#[path = "integration/model_filter.rs"]
+mod model_filter;
+
+#[path = "integration/backend_failover.rs"]
+mod backend_failover;
+
+#[path = "integration/fixture_lmstudio.rs"]
+mod fixture_lmstudio;
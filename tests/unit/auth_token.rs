@@ -5,6 +5,8 @@
 // They do not exercise live HTTP; the header is inspected via a loopback
 // mock server that echoes request headers back as JSON.
 
+use super::{Config, build_client};
+
 /// Build a reqwest Client with a default Authorization header, mirroring
 /// the logic in ProxyServer::new_with_state_dir.
 fn build_client_with_token(token: Option<&str>) -> reqwest::Client {
@@ -163,3 +165,59 @@ async fn caller_auth_overrides_client_default_header() {
         "caller-supplied Authorization must override the proxy default token"
     );
 }
+
+/// Send one GET with `client` and return the request head it put on the wire.
+async fn captured_request_head(client: &reqwest::Client) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0u8; 4096];
+        let n = stream.read(&mut buf).await.unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\n{}")
+            .await
+            .unwrap();
+        String::from_utf8_lossy(&buf[..n]).to_ascii_lowercase()
+    });
+    let _ = client.get(format!("http://{}/", addr)).send().await;
+    server.await.unwrap()
+}
+
+#[tokio::test]
+async fn build_client_sends_lmstudio_headers_and_token() {
+    use clap::Parser;
+
+    let config = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--lmstudio-api-key",
+        "lm-token",
+        "--lmstudio-header",
+        "X-Gateway-Key: gw-secret",
+        "--upstream-bind-interface",
+        "127.0.0.1",
+    ])
+    .unwrap();
+    let head = captured_request_head(&build_client(&config).unwrap()).await;
+    assert!(head.contains("authorization: bearer lm-token"), "{head}");
+    assert!(head.contains("x-gateway-key: gw-secret"), "{head}");
+}
+
+#[tokio::test]
+async fn lmstudio_header_authorization_replaces_the_token() {
+    use clap::Parser;
+
+    let config = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--lmstudio-token",
+        "lm-token",
+        "--lmstudio-header",
+        "Authorization: Basic Z3c6cHc=",
+    ])
+    .unwrap();
+    let head = captured_request_head(&build_client(&config).unwrap()).await;
+    assert!(head.contains("authorization: basic z3c6chc="), "{head}");
+    assert!(!head.contains("lm-token"), "{head}");
+}
@@ -0,0 +1,546 @@
+use super::*;
+
+#[test]
+fn listen_defaults_to_single_ipv4_address() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.listen, vec!["0.0.0.0:11434".to_string()]);
+}
+
+#[test]
+fn listen_accepts_repeated_flags() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--listen",
+        "0.0.0.0:11434",
+        "--listen",
+        "[::]:11434",
+    ])
+    .unwrap();
+    assert_eq!(cfg.listen.len(), 2);
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn parse_listen_addrs_accepts_bracketed_ipv6() {
+    let addrs = parse_listen_addrs(&["[::]:11434".to_string(), "[::1]:8080".to_string()]).unwrap();
+    let ListenAddr::Tcp(first) = addrs[0] else {
+        panic!("expected a TCP address: {:?}", addrs[0]);
+    };
+    assert!(first.is_ipv6());
+    assert_eq!(first.port(), 11434);
+    assert_eq!(addrs[1].to_string(), "[::1]:8080");
+}
+
+#[test]
+fn parse_listen_addrs_rejects_unbracketed_ipv6() {
+    let err = parse_listen_addrs(&["::1:11434".to_string()]).unwrap_err();
+    assert!(
+        err.contains("::1:11434"),
+        "error should name the bad value: {err}"
+    );
+}
+
+#[test]
+fn parse_listen_addrs_drops_duplicates() {
+    let addrs = parse_listen_addrs(&["127.0.0.1:11434".to_string(), "127.0.0.1:11434".to_string()])
+        .unwrap();
+    assert_eq!(addrs.len(), 1);
+}
+
+#[test]
+fn parse_listen_addrs_requires_at_least_one() {
+    assert!(parse_listen_addrs(&[]).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn parse_listen_addrs_accepts_unix_socket_path() {
+    let addrs = parse_listen_addrs(&[
+        "unix:/run/ollama-proxy.sock".to_string(),
+        "127.0.0.1:11434".to_string(),
+    ])
+    .unwrap();
+    assert_eq!(
+        addrs[0],
+        ListenAddr::Unix(std::path::PathBuf::from("/run/ollama-proxy.sock"))
+    );
+    assert_eq!(addrs[0].to_string(), "unix:/run/ollama-proxy.sock");
+    assert_eq!(
+        addrs[1],
+        ListenAddr::Tcp("127.0.0.1:11434".parse().unwrap())
+    );
+}
+
+#[test]
+fn parse_listen_addrs_rejects_empty_unix_path() {
+    assert!(parse_listen_addrs(&["unix:".to_string()]).is_err());
+}
+
+#[test]
+fn parse_listen_addrs_host_port_without_scheme_stays_tcp() {
+    let addrs = parse_listen_addrs(&["0.0.0.0:11434".to_string()]).unwrap();
+    assert!(matches!(addrs[0], ListenAddr::Tcp(_)));
+}
+
+#[test]
+fn reasoning_mode_defaults_to_separate() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.reasoning_mode, ReasoningMode::Separate);
+}
+
+#[test]
+fn reasoning_mode_flag_accepts_each_mode() {
+    for (raw, expected) in [
+        ("merge", ReasoningMode::Merge),
+        ("separate", ReasoningMode::Separate),
+        ("strip", ReasoningMode::Strip),
+    ] {
+        let cfg =
+            Config::try_parse_from(["ollama-lmstudio-proxy", "--reasoning-mode", raw]).unwrap();
+        assert_eq!(cfg.reasoning_mode, expected);
+    }
+    assert!(Config::try_parse_from(["ollama-lmstudio-proxy", "--reasoning-mode", "hide"]).is_err());
+}
+
+#[test]
+fn reasoning_mode_parse_is_case_insensitive() {
+    assert_eq!(ReasoningMode::parse(" STRIP "), Some(ReasoningMode::Strip));
+    assert_eq!(ReasoningMode::parse("Merge"), Some(ReasoningMode::Merge));
+    assert_eq!(ReasoningMode::parse("verbose"), None);
+}
+
+#[test]
+fn resolution_defaults_to_fuzzy() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.resolution, ResolutionMode::Fuzzy);
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--resolution", "exact"]).unwrap();
+    assert_eq!(cfg.resolution, ResolutionMode::Exact);
+}
+
+#[test]
+fn tls_cert_without_key_is_rejected() {
+    assert!(
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--tls-cert", "cert.pem"]).is_err(),
+        "clap must require --tls-key alongside --tls-cert"
+    );
+
+    let mut cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    cfg.tls_cert = Some("cert.pem".into());
+    let err = validate_config(&cfg).unwrap_err();
+    assert!(err.contains("--tls-key"), "{err}");
+
+    cfg.tls_cert = None;
+    cfg.tls_key = Some("key.pem".into());
+    assert!(validate_config(&cfg).is_err());
+}
+
+#[test]
+fn tls_cert_and_key_together_validate() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--tls-cert",
+        "cert.pem",
+        "--tls-key",
+        "key.pem",
+    ])
+    .unwrap();
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn cors_origin_defaults_to_none_and_accepts_lists() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(cfg.cors_origin.is_empty());
+
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--cors-origin",
+        "http://localhost:3000,https://app.example.com",
+        "--cors-origin",
+        "*",
+    ])
+    .unwrap();
+    assert_eq!(
+        cfg.cors_origin,
+        vec!["http://localhost:3000", "https://app.example.com", "*"]
+    );
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn cors_origin_without_host_is_rejected() {
+    let mut cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    for bad in ["localhost:3000", "not a url", ""] {
+        cfg.cors_origin = vec![bad.to_string()];
+        let err = validate_config(&cfg).unwrap_err();
+        assert!(err.contains("--cors-origin"), "{bad}: {err}");
+    }
+}
+
+#[test]
+fn forward_header_rejects_invalid_and_hop_by_hop_names() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--forward-header",
+        "X-Request-Id,Authorization",
+    ])
+    .unwrap();
+    assert_eq!(cfg.forward_header, vec!["X-Request-Id", "Authorization"]);
+    assert!(validate_config(&cfg).is_ok());
+
+    let mut cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    for bad in ["Host", "content-length", "bad header"] {
+        cfg.forward_header = vec![bad.to_string()];
+        let err = validate_config(&cfg).unwrap_err();
+        assert!(err.contains("--forward-header"), "{bad}: {err}");
+    }
+}
+
+#[test]
+fn preload_is_repeatable_and_comma_separated() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--preload",
+        "llama3.2:3b,qwen3",
+        "--preload",
+        "nomic-embed-text",
+    ])
+    .unwrap();
+    assert_eq!(
+        cfg.preload,
+        vec!["llama3.2:3b", "qwen3", "nomic-embed-text"]
+    );
+
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(cfg.preload.is_empty());
+}
+
+#[test]
+fn auto_pull_is_off_by_default() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(!cfg.auto_pull_missing);
+    assert!(cfg.auto_pull_pattern.is_empty());
+    assert_eq!(cfg.auto_pull_wait_seconds, 300);
+
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--auto-pull-missing",
+        "--auto-pull-pattern",
+        "qwen*,gemma*",
+    ])
+    .unwrap();
+    assert!(cfg.auto_pull_missing);
+    assert_eq!(cfg.auto_pull_pattern, vec!["qwen*", "gemma*"]);
+}
+
+#[test]
+fn generate_context_emulation_needs_a_positive_ttl() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(!cfg.emulate_generate_context);
+    assert_eq!(cfg.generate_context_ttl_seconds, 1800);
+
+    let mut cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--emulate-generate-context",
+        "--generate-context-ttl-seconds",
+        "0",
+    ])
+    .unwrap();
+    let err = validate_config(&cfg).unwrap_err();
+    assert!(err.contains("--generate-context-ttl-seconds"), "{err}");
+    cfg.emulate_generate_context = false;
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn max_body_size_defaults_to_16_mib_and_rejects_zero() {
+    let mut cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.max_body_size, 16 * 1024 * 1024);
+    cfg.max_body_size = 0;
+    let err = validate_config(&cfg).unwrap_err();
+    assert!(err.contains("--max-body-size"), "{err}");
+}
+
+#[test]
+fn max_blob_size_defaults_to_unlimited() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.max_blob_size, 0);
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--max-blob-size", "4096"]).unwrap();
+    assert_eq!(cfg.max_blob_size, 4096);
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn blob_max_total_mb_defaults_to_unlimited() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.blob_max_total_mb, 0);
+    let cfg =
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--blob-max-total-mb", "512"]).unwrap();
+    assert_eq!(cfg.blob_max_total_mb, 512);
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn strict_json_is_off_by_default() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(!cfg.strict_json);
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--strict-json"]).unwrap();
+    assert!(cfg.strict_json);
+}
+
+#[test]
+fn health_monitor_is_off_by_default() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.health_check_interval_seconds, 0);
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--health-check-interval-seconds",
+        "15",
+    ])
+    .unwrap();
+    assert_eq!(cfg.health_check_interval_seconds, 15);
+    assert!(validate_config(&cfg).is_ok());
+}
+
+#[test]
+fn health_cache_defaults_to_two_seconds() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.health_cache_seconds, 2);
+    let cfg =
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--health-cache-seconds", "0"]).unwrap();
+    assert_eq!(cfg.health_cache_seconds, 0);
+}
+
+#[test]
+fn indefinite_ttl_defaults_to_a_year() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.indefinite_ttl_seconds, 365 * 24 * 60 * 60);
+    let cfg =
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--indefinite-ttl-seconds", "0"]).unwrap();
+    assert_eq!(cfg.indefinite_ttl_seconds, 0);
+}
+
+#[test]
+fn default_system_prompt_reads_inline_text_or_file() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--default-system-prompt",
+        "Answer concisely.",
+    ])
+    .unwrap();
+    assert_eq!(
+        cfg.default_system_prompt.as_deref(),
+        Some("Answer concisely.")
+    );
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("system.txt");
+    std::fs::write(&path, "House style.\n").unwrap();
+    let arg = format!("@{}", path.display());
+    let cfg =
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--default-system-prompt", &arg]).unwrap();
+    assert_eq!(cfg.default_system_prompt.as_deref(), Some("House style."));
+
+    let missing = format!("@{}", dir.path().join("nope.txt").display());
+    assert!(
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--default-system-prompt", &missing])
+            .is_err()
+    );
+    assert!(
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--default-system-prompt", "  "]).is_err()
+    );
+}
+
+#[test]
+fn strict_params_is_off_by_default() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(!cfg.strict_params);
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--strict-params"]).unwrap();
+    assert!(cfg.strict_params);
+}
+
+#[test]
+fn log_file_is_off_by_default_and_rotation_needs_a_size() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(cfg.log_file.is_none());
+    assert_eq!(cfg.log_max_size_mb, 10);
+    assert_eq!(cfg.log_max_files, 5);
+
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--log-file",
+        "proxy.log",
+        "--log-max-size-mb",
+        "0",
+    ])
+    .unwrap();
+    assert!(validate_config(&cfg).is_err());
+}
+
+#[test]
+fn embedding_cache_is_off_by_default_and_needs_a_ttl() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.embedding_cache_size, 0);
+    assert!(validate_config(&cfg).is_ok());
+
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--embedding-cache-size",
+        "1000",
+        "--embedding-cache-ttl-seconds",
+        "0",
+    ])
+    .unwrap();
+    assert!(validate_config(&cfg).is_err());
+}
+
+#[test]
+fn sse_keepalive_defaults_to_fifteen_seconds() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert_eq!(cfg.sse_keepalive_seconds, 15);
+    let cfg =
+        Config::try_parse_from(["ollama-lmstudio-proxy", "--sse-keepalive-seconds", "0"]).unwrap();
+    assert_eq!(cfg.sse_keepalive_seconds, 0);
+}
+
+#[test]
+fn expose_stats_is_off_by_default() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(!cfg.expose_stats);
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--expose-stats"]).unwrap();
+    assert!(cfg.expose_stats);
+}
+
+#[test]
+fn lmstudio_fallback_url_is_validated_like_the_primary() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy"]).unwrap();
+    assert!(cfg.lmstudio_fallback_url.is_none());
+
+    let mut cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--lmstudio-fallback-url",
+        "http://10.0.0.2:1234",
+    ])
+    .unwrap();
+    assert!(validate_config(&cfg).is_ok());
+
+    cfg.lmstudio_fallback_url = Some("10.0.0.2:1234".to_string());
+    let err = validate_config(&cfg).unwrap_err();
+    assert!(err.contains("10.0.0.2:1234"), "{err}");
+}
+
+#[test]
+fn model_name_map_parses_name_id_pairs() {
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--model-name-map",
+        "Llama3:latest=lmstudio-community/Meta-Llama-3.1-8B-Instruct-GGUF",
+        "--model-name-map",
+        "coder = qwen2.5-coder-7b",
+    ])
+    .unwrap();
+    let map = parse_model_name_map(&cfg.model_name_map).unwrap();
+    assert_eq!(
+        map.get("llama3").map(String::as_str),
+        Some("lmstudio-community/Meta-Llama-3.1-8B-Instruct-GGUF")
+    );
+    assert_eq!(
+        map.get("coder").map(String::as_str),
+        Some("qwen2.5-coder-7b")
+    );
+
+    let mut cfg = cfg;
+    cfg.model_name_map = vec!["llama3".to_string()];
+    let err = validate_config(&cfg).unwrap_err();
+    assert!(err.contains("name=id"), "{err}");
+}
+
+#[test]
+fn self_test_no_inference_requires_self_test() {
+    assert!(Config::try_parse_from(["ollama-lmstudio-proxy", "--self-test-no-inference"]).is_err());
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--self-test",
+        "--self-test-no-inference",
+    ])
+    .unwrap();
+    assert!(cfg.self_test && cfg.self_test_no_inference);
+}
+
+#[test]
+fn service_mode_requires_a_log_file() {
+    assert!(Config::try_parse_from(["ollama-lmstudio-proxy", "--service"]).is_err());
+    let cfg = Config::try_parse_from([
+        "ollama-lmstudio-proxy",
+        "--service",
+        "--log-file",
+        "proxy.log",
+        "--pid-file",
+        "proxy.pid",
+    ])
+    .unwrap();
+    assert!(cfg.service);
+    assert_eq!(
+        cfg.pid_file.as_deref(),
+        Some(std::path::Path::new("proxy.pid"))
+    );
+}
+
+#[test]
+fn lmstudio_api_key_is_an_alias_of_lmstudio_token() {
+    let cfg = Config::try_parse_from(["ollama-lmstudio-proxy", "--lmstudio-api-key", "gw-secret"])
+        .unwrap();
+    assert_eq!(cfg.lmstudio_token.as_deref(), Some("gw-secret"));
+}
+
+#[test]
+fn lmstudio_headers_parse_name_value_pairs() {
+    let headers = parse_lmstudio_headers(&[
+        "X-Gateway-Key: abc: def".to_string(),
+        "cf-access-client-id:client".to_string(),
+    ])
+    .unwrap();
+    assert_eq!(headers["x-gateway-key"], "abc: def");
+    assert_eq!(headers["cf-access-client-id"], "client");
+    assert!(headers["x-gateway-key"].is_sensitive());
+
+    assert!(parse_lmstudio_headers(&["no-colon".to_string()]).is_err());
+    assert!(parse_lmstudio_headers(&[": value".to_string()]).is_err());
+    assert!(parse_lmstudio_headers(&["bad header: x".to_string()]).is_err());
+    assert!(parse_lmstudio_headers(&["Host: lmstudio".to_string()]).is_err());
+}
+
+#[test]
+fn upstream_bind_accepts_addresses_and_linux_interface_names() {
+    assert_eq!(
+        parse_upstream_bind("192.168.1.20"),
+        Ok(UpstreamBind::Address("192.168.1.20".parse().unwrap()))
+    );
+    assert_eq!(
+        parse_upstream_bind("fe80::1"),
+        Ok(UpstreamBind::Address("fe80::1".parse().unwrap()))
+    );
+    assert!(parse_upstream_bind("").is_err());
+    assert_eq!(
+        parse_upstream_bind("eth1").is_ok(),
+        cfg!(any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux"
+        ))
+    );
+}
+
+#[test]
+fn otlp_endpoint_must_be_an_http_url() {
+    let cfg =
+        Config::try_parse_from(["proxy", "--otlp-endpoint", "http://otel-collector:4318"]).unwrap();
+    assert_eq!(
+        cfg.otlp_endpoint.as_deref(),
+        Some("http://otel-collector:4318")
+    );
+    assert!(validate_config(&cfg).is_ok());
+
+    let cfg = Config::try_parse_from(["proxy", "--otlp-endpoint", "otel-collector:4317"]).unwrap();
+    assert!(validate_config(&cfg).is_err());
+}
@@ -0,0 +1,47 @@
+use super::*;
+use moka::future::Cache;
+
+fn backend(url: &str) -> Backend {
+    let resolver = ModelResolver::new(url.to_string(), Cache::builder().max_capacity(16).build());
+    Backend::new(url.to_string(), Arc::new(resolver))
+}
+
+#[test]
+fn tripped_primary_moves_behind_the_fallback_until_reset() {
+    let selector = BackendSelector::new(
+        vec![backend("http://primary"), backend("http://fallback")],
+        Duration::from_secs(60),
+    );
+    assert!(selector.has_fallback());
+    assert_eq!(selector.try_order(), vec![0, 1]);
+
+    selector.trip(0);
+    assert!(selector.primary().is_tripped());
+    assert_eq!(selector.try_order(), vec![1, 0]);
+    assert_eq!(selector.preferred(), 1);
+
+    selector.reset(0);
+    assert_eq!(selector.preferred(), 0);
+}
+
+#[test]
+fn breaker_closes_after_the_cooldown() {
+    let selector = BackendSelector::new(
+        vec![backend("http://primary"), backend("http://fallback")],
+        Duration::ZERO,
+    );
+    selector.trip(0);
+    assert!(!selector.primary().is_tripped());
+    assert_eq!(selector.preferred(), 0);
+}
+
+#[test]
+fn every_backend_tripped_still_yields_all_in_order() {
+    let selector = BackendSelector::new(
+        vec![backend("http://primary"), backend("http://fallback")],
+        Duration::from_secs(60),
+    );
+    selector.trip(0);
+    selector.trip(1);
+    assert_eq!(selector.try_order(), vec![0, 1]);
+}
@@ -10,6 +10,10 @@ fn fresh_vm_store(dir: &TempDir) -> std::sync::Arc<crate::storage::VirtualModelS
     )
 }
 
+fn fresh_pin_store(dir: &TempDir) -> std::sync::Arc<crate::storage::ModelPinStore> {
+    std::sync::Arc::new(crate::storage::ModelPinStore::load(dir.path().join("pins.json")).unwrap())
+}
+
 fn fresh_blob_store(dir: &TempDir) -> std::sync::Arc<crate::storage::BlobStore> {
     std::sync::Arc::new(crate::storage::BlobStore::new(dir.path()).unwrap())
 }
@@ -24,12 +28,37 @@ macro_rules! with_ctx {
         let bs_dir = fresh_blob_dir();
         let vms = fresh_vm_store(&vm_dir);
         let bs = fresh_blob_store(&bs_dir);
+        let pins = fresh_pin_store(&vm_dir);
         let $ctx = crate::api::RequestContext {
             client: &client,
             lmstudio_url: $url,
+            backends: crate::api::BackendSelector::new(
+                vec![crate::api::Backend::new(
+                    $url.to_string(),
+                    std::sync::Arc::new(crate::model::ModelResolver::new(
+                        $url.to_string(),
+                        moka::future::Cache::builder().max_capacity(1).build(),
+                    )),
+                )],
+                std::time::Duration::ZERO,
+            ),
             virtual_models: vms,
+            model_pins: pins,
+            model_name_map: std::sync::Arc::new(std::collections::HashMap::new()),
             blob_store: bs,
             load_tracker: crate::model::LoadTracker::new(),
+            runtime_info: crate::model::RuntimeInfoCache::new(),
+            model_concurrency: crate::model::ModelConcurrency::unlimited(),
+            load_coordinator: crate::api::LoadCoordinator::new(std::time::Duration::ZERO),
+            pull_registry: crate::api::PullRegistry::new(),
+            model_filter: std::sync::Arc::new(crate::model::ModelFilter::default()),
+            stream_timeouts: crate::streaming::StreamTimeouts::default(),
+            forward_headers: reqwest::header::HeaderMap::new(),
+            generate_context: None,
+            embedding_cache: None,
+            default_system_prompt: None,
+            expose_stats: false,
+            ollama_model_ids: false,
         };
         $body
     }};
@@ -0,0 +1,81 @@
+use super::*;
+use serde_json::json;
+
+fn probed(status: BackendStatus) -> (BackendStatus, ProbeResult) {
+    (
+        status,
+        ProbeResult {
+            checked_at: Some(Utc::now()),
+            ..ProbeResult::default()
+        },
+    )
+}
+
+#[test]
+fn nothing_is_known_before_the_first_probe() {
+    let monitor = HealthMonitor::new(Duration::from_secs(5));
+    assert_eq!(monitor.status(), BackendStatus::Unknown);
+    assert!(monitor.snapshot().is_none());
+    assert!(monitor.ensure_reachable().is_ok(), "unknown must not block");
+}
+
+#[test]
+fn unreachable_fails_fast_with_last_healthy_time() {
+    let monitor = HealthMonitor::new(Duration::from_secs(5));
+    let (status, result) = probed(BackendStatus::Healthy);
+    let healthy_at = result.checked_at.unwrap();
+    monitor.record(status, result);
+
+    let (status, result) = probed(BackendStatus::Unreachable);
+    monitor.record(status, result);
+
+    let err = monitor.ensure_reachable().unwrap_err();
+    assert_eq!(err.status_code, 503);
+    assert!(
+        err.message.contains(&healthy_at.to_rfc3339()),
+        "{}",
+        err.message
+    );
+    let (_, snapshot) = monitor.snapshot().unwrap();
+    assert_eq!(snapshot.last_healthy_at, Some(healthy_at));
+}
+
+#[test]
+fn never_healthy_says_so() {
+    let monitor = HealthMonitor::new(Duration::from_secs(5));
+    let (status, result) = probed(BackendStatus::Unreachable);
+    monitor.record(status, result);
+    let err = monitor.ensure_reachable().unwrap_err();
+    assert!(err.message.contains("not seen healthy"), "{}", err.message);
+}
+
+#[test]
+fn unhealthy_status_does_not_block_requests() {
+    let monitor = HealthMonitor::new(Duration::from_secs(5));
+    let (status, result) = probed(BackendStatus::Unhealthy);
+    monitor.record(status, result);
+    assert!(monitor.ensure_reachable().is_ok());
+}
+
+#[test]
+fn only_a_return_from_down_reports_recovery() {
+    let monitor = HealthMonitor::new(Duration::from_secs(5));
+    let (status, result) = probed(BackendStatus::Healthy);
+    assert!(
+        !monitor.record(status, result),
+        "first probe is not a recovery"
+    );
+    let (status, result) = probed(BackendStatus::Healthy);
+    assert!(!monitor.record(status, result));
+    let (status, result) = probed(BackendStatus::Unreachable);
+    assert!(!monitor.record(status, result));
+    let (status, result) = probed(BackendStatus::Healthy);
+    assert!(monitor.record(status, result), "back after being down");
+}
+
+#[test]
+fn counts_native_and_openai_model_lists() {
+    assert_eq!(count_listed_models(&json!({"models": [{}, {}]})), 2);
+    assert_eq!(count_listed_models(&json!({"data": [{}]})), 1);
+    assert_eq!(count_listed_models(&json!({})), 0);
+}
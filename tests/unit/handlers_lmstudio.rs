@@ -40,3 +40,134 @@ fn passthrough_request_with_query() {
     assert_eq!(req.query.as_deref(), Some("stream=true"));
     assert!(!req.body.is_empty());
 }
+
+#[test]
+fn requested_candidates_only_fans_out_above_one() {
+    assert_eq!(requested_candidates(&json!({})).unwrap(), None);
+    assert_eq!(requested_candidates(&json!({"n": 1})).unwrap(), None);
+    assert_eq!(requested_candidates(&json!({"n": "3"})).unwrap(), None);
+    assert_eq!(requested_candidates(&json!({"n": 3})).unwrap(), Some(3));
+
+    let err = requested_candidates(&json!({"n": MAX_FAN_OUT_CANDIDATES + 1})).unwrap_err();
+    assert_eq!(err.status_code, 400);
+}
+
+#[test]
+fn choices_endpoints_cover_chat_and_completions() {
+    assert!(is_choices_endpoint("/v1/chat/completions"));
+    assert!(is_choices_endpoint("/api/v0/completions"));
+    assert!(!is_choices_endpoint("/v1/embeddings"));
+    assert!(!is_choices_endpoint("/v1/responses"));
+}
+
+#[test]
+fn merged_candidates_are_reindexed_and_usage_summed() {
+    let candidate = |text: &str| {
+        json!({
+            "id": format!("chatcmpl-{text}"),
+            "object": "chat.completion",
+            "choices": [{"index": 0, "message": {"role": "assistant", "content": text}}],
+            "usage": {
+                "prompt_tokens": 5,
+                "completion_tokens": 2,
+                "total_tokens": 7,
+                "completion_tokens_details": {"reasoning_tokens": 1}
+            }
+        })
+    };
+
+    let merged = merge_candidates(vec![candidate("a"), candidate("b"), candidate("c")]);
+
+    assert_eq!(merged["id"], "chatcmpl-a");
+    let choices = merged["choices"].as_array().unwrap();
+    assert_eq!(choices.len(), 3);
+    for (i, choice) in choices.iter().enumerate() {
+        assert_eq!(choice["index"], i);
+    }
+    assert_eq!(choices[2]["message"]["content"], "c");
+    assert_eq!(merged["usage"]["prompt_tokens"], 15);
+    assert_eq!(merged["usage"]["total_tokens"], 21);
+    assert_eq!(
+        merged["usage"]["completion_tokens_details"]["reasoning_tokens"],
+        3
+    );
+}
+
+fn alias(name: &str, target_id: &str) -> VirtualModelEntry {
+    let now = chrono::Utc::now();
+    VirtualModelEntry {
+        name: name.to_string(),
+        source_model: target_id.to_string(),
+        target_model_id: target_id.to_string(),
+        parent_alias: None,
+        blobs: Vec::new(),
+        created_at: now,
+        updated_at: now,
+        metadata: Default::default(),
+    }
+}
+
+fn listing_ids(listing: &Value) -> Vec<&str> {
+    listing["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|entry| entry["id"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn ollama_catalog_appends_aliases_sorted_after_the_models() {
+    let mut listing = json!({
+        "object": "list",
+        "data": [
+            { "id": "qwen3-8b", "object": "model", "owned_by": "organization_owner" },
+            { "id": "text-embedding-nomic", "object": "model", "owned_by": "organization_owner" }
+        ]
+    });
+    add_ollama_catalog(
+        &mut listing,
+        &[
+            alias("work-qwen", "qwen3-8b"),
+            alias("gone:latest", "deleted-model"),
+            alias("qwen3-8b", "qwen3-8b"),
+        ],
+        false,
+    );
+
+    assert_eq!(
+        listing_ids(&listing),
+        [
+            "qwen3-8b",
+            "text-embedding-nomic",
+            "gone:latest",
+            "work-qwen"
+        ]
+    );
+    // An alias copies its target's entry; an orphan gets a bare one.
+    assert_eq!(listing["data"][3]["owned_by"], "organization_owner");
+    assert_eq!(
+        listing["data"][2],
+        json!({ "id": "gone:latest", "object": "model", "owned_by": "organization_owner" })
+    );
+}
+
+#[test]
+fn ollama_catalog_renames_ids_when_asked() {
+    let mut listing = json!({
+        "data": [
+            { "id": "qwen3-8b", "object": "model" },
+            { "id": "llama3:8b", "object": "model" }
+        ]
+    });
+    add_ollama_catalog(&mut listing, &[alias("qwen3-8b:latest", "qwen3-8b")], true);
+    // The alias now collides with the renamed model and is not repeated.
+    assert_eq!(listing_ids(&listing), ["qwen3-8b:latest", "llama3:8b"]);
+}
+
+#[test]
+fn ollama_catalog_ignores_listings_without_data() {
+    let mut listing = json!({ "models": [{ "key": "qwen3-8b" }] });
+    add_ollama_catalog(&mut listing, &[alias("work-qwen", "qwen3-8b")], true);
+    assert_eq!(listing, json!({ "models": [{ "key": "qwen3-8b" }] }));
+}
@@ -0,0 +1,165 @@
+use super::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures_util::future::join_all;
+
+async fn slow_load(
+    loads: &AtomicUsize,
+    result: Result<bool, ProxyError>,
+) -> Result<bool, ProxyError> {
+    loads.fetch_add(1, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    result
+}
+
+#[tokio::test]
+async fn concurrent_callers_share_one_load() {
+    let coordinator = LoadCoordinator::new(Duration::from_secs(5));
+    let loads = AtomicUsize::new(0);
+    let token = CancellationToken::new();
+
+    let results = join_all(
+        (0..5).map(|_| coordinator.run("model-a#load", &token, || slow_load(&loads, Ok(true)))),
+    )
+    .await;
+
+    assert_eq!(
+        loads.load(Ordering::SeqCst),
+        1,
+        "only the first caller loads"
+    );
+    assert!(results.iter().all(|r| matches!(r, Ok(true))), "{results:?}");
+    assert_eq!(coordinator.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn waiters_receive_the_leaders_error() {
+    let coordinator = LoadCoordinator::new(Duration::from_secs(5));
+    let loads = AtomicUsize::new(0);
+    let token = CancellationToken::new();
+
+    let results = join_all((0..3).map(|_| {
+        coordinator.run("model-a#load", &token, || {
+            slow_load(&loads, Err(ProxyError::lm_studio_unavailable("down")))
+        })
+    }))
+    .await;
+
+    assert_eq!(loads.load(Ordering::SeqCst), 1);
+    for result in results {
+        let err = result.unwrap_err();
+        assert_eq!(err.status_code, 503);
+        assert_eq!(err.message, "down");
+    }
+}
+
+#[tokio::test]
+async fn different_models_load_independently() {
+    let coordinator = LoadCoordinator::new(Duration::from_secs(5));
+    let loads = AtomicUsize::new(0);
+    let token = CancellationToken::new();
+
+    let (a, b) = tokio::join!(
+        coordinator.run("model-a#load", &token, || slow_load(&loads, Ok(true))),
+        coordinator.run("model-b#load", &token, || slow_load(&loads, Ok(true))),
+    );
+
+    assert!(a.unwrap() && b.unwrap());
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn finished_load_is_not_reused_by_later_callers() {
+    let coordinator = LoadCoordinator::new(Duration::from_secs(5));
+    let loads = AtomicUsize::new(0);
+    let token = CancellationToken::new();
+
+    for _ in 0..2 {
+        coordinator
+            .run("model-a#load", &token, || slow_load(&loads, Ok(true)))
+            .await
+            .unwrap();
+    }
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn waiter_takes_over_when_the_leader_is_cancelled() {
+    let coordinator = LoadCoordinator::new(Duration::from_secs(5));
+    let loads = AtomicUsize::new(0);
+    let token = CancellationToken::new();
+
+    let (leader, follower) = tokio::join!(
+        coordinator.run("model-a#load", &token, || async {
+            loads.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(ProxyError::request_cancelled())
+        }),
+        async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            coordinator
+                .run("model-a#load", &token, || slow_load(&loads, Ok(true)))
+                .await
+        },
+    );
+
+    assert!(leader.unwrap_err().is_cancelled());
+    assert!(follower.unwrap(), "the waiter must load the model itself");
+    assert_eq!(loads.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn waiting_is_bounded_by_max_wait() {
+    let coordinator = LoadCoordinator::new(Duration::from_millis(20));
+    let token = CancellationToken::new();
+
+    let (leader, follower) = tokio::join!(
+        coordinator.run("model-a#load", &token, || async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(true)
+        }),
+        async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            coordinator
+                .run("model-a#load", &token, || async { Ok(true) })
+                .await
+        },
+    );
+
+    assert!(leader.unwrap());
+    let err = follower.unwrap_err();
+    assert_eq!(err.status_code, 504);
+    assert!(
+        err.message.contains("--load-timeout-seconds"),
+        "{}",
+        err.message
+    );
+}
+
+#[tokio::test]
+async fn cancelled_waiter_stops_waiting() {
+    let coordinator = LoadCoordinator::new(Duration::ZERO);
+    let leader_token = CancellationToken::new();
+    let waiter_token = CancellationToken::new();
+
+    let (leader, follower) = tokio::join!(
+        coordinator.run("model-a#load", &leader_token, || async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(true)
+        }),
+        async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            let cancel = waiter_token.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                cancel.cancel();
+            });
+            coordinator
+                .run("model-a#load", &waiter_token, || async { Ok(true) })
+                .await
+        },
+    );
+
+    assert!(leader.unwrap());
+    assert!(follower.unwrap_err().is_cancelled());
+}
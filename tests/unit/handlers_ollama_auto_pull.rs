@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn catalog_ids_are_auto_pull_candidates() {
+    assert!(is_auto_pull_candidate(
+        "lmstudio-community/Qwen3-8B-GGUF",
+        &[]
+    ));
+    assert!(is_auto_pull_candidate(
+        "https://huggingface.co/owner/repo",
+        &[]
+    ));
+}
+
+#[test]
+fn bare_names_need_a_matching_pattern() {
+    assert!(!is_auto_pull_candidate("llama3.2:3b", &[]));
+    let patterns = vec!["Qwen*".to_string(), "gemma-?b".to_string()];
+    assert!(is_auto_pull_candidate("qwen3-8b", &patterns));
+    assert!(is_auto_pull_candidate("gemma-2b", &patterns));
+    assert!(!is_auto_pull_candidate("gemma-27b", &patterns));
+    assert!(!is_auto_pull_candidate("llama3.2:3b", &patterns));
+}
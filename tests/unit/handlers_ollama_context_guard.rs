@@ -0,0 +1,154 @@
+use serde_json::json;
+
+use super::*;
+
+/// 40 characters: 10 estimated tokens, 14 with the per-message overhead.
+const TEXT_40: &str = "abcdabcdabcdabcdabcdabcdabcdabcdabcdabcd";
+
+fn msg(role: &str) -> Value {
+    json!({ "role": role, "content": TEXT_40 })
+}
+
+fn roles(messages: &[Value]) -> Vec<&str> {
+    messages
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn message_estimate_counts_text_parts_and_tool_calls() {
+    assert_eq!(estimate_message_tokens(&msg("user")), 14);
+    let parts = json!({
+        "role": "user",
+        "content": [
+            { "type": "text", "text": TEXT_40 },
+            { "type": "image_url", "image_url": { "url": "data:..." } },
+            { "type": "text", "text": "abcd" },
+        ]
+    });
+    assert_eq!(
+        estimate_message_tokens(&parts),
+        10 + 1 + MESSAGE_OVERHEAD_TOKENS
+    );
+    let call = json!({
+        "role": "assistant",
+        "content": "",
+        "tool_calls": [{ "function": { "name": "f", "arguments": {} } }]
+    });
+    assert!(estimate_message_tokens(&call) > MESSAGE_OVERHEAD_TOKENS);
+}
+
+#[test]
+fn history_at_the_limit_fits_and_one_token_over_fails() {
+    let mut messages = vec![msg("user")];
+    assert_eq!(fit_messages(&mut messages, None, 14, 0, false).unwrap(), 0);
+
+    let err = fit_messages(&mut messages, None, 13, 0, false).unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("~14 prompt tokens"), "{}", err.message);
+    assert!(err.message.contains("> 13 tokens"), "{}", err.message);
+    assert_eq!(
+        messages.len(),
+        1,
+        "nothing dropped without truncate_history"
+    );
+}
+
+#[test]
+fn reply_reservation_counts_against_the_limit() {
+    let mut messages = vec![msg("user")];
+    assert!(fit_messages(&mut messages, None, 20, 6, false).is_ok());
+    let err = fit_messages(&mut messages, None, 20, 7, false).unwrap_err();
+    assert!(err.message.contains("7 reserved"), "{}", err.message);
+}
+
+#[test]
+fn injected_system_prompt_counts_only_when_history_has_none() {
+    let mut messages = vec![msg("user")];
+    assert!(fit_messages(&mut messages, Some(TEXT_40), 27, 0, false).is_err());
+    assert!(fit_messages(&mut messages, Some(TEXT_40), 28, 0, false).is_ok());
+
+    let mut with_system = vec![msg("system"), msg("user")];
+    assert!(fit_messages(&mut with_system, Some(TEXT_40), 28, 0, false).is_ok());
+}
+
+#[test]
+fn truncation_drops_oldest_non_system_messages_until_it_fits() {
+    let mut messages = vec![msg("system"), msg("user"), msg("assistant"), msg("user")];
+    assert_eq!(fit_messages(&mut messages, None, 42, 0, true).unwrap(), 1);
+    assert_eq!(roles(&messages), ["system", "assistant", "user"]);
+
+    let mut messages = vec![msg("system"), msg("user"), msg("assistant"), msg("user")];
+    assert_eq!(fit_messages(&mut messages, None, 41, 0, true).unwrap(), 2);
+    assert_eq!(roles(&messages), ["system", "user"]);
+}
+
+#[test]
+fn truncation_drops_tool_results_with_their_call() {
+    let mut messages = vec![msg("assistant"), msg("tool"), msg("tool"), msg("user")];
+    assert_eq!(fit_messages(&mut messages, None, 30, 0, true).unwrap(), 3);
+    assert_eq!(roles(&messages), ["user"]);
+}
+
+#[test]
+fn truncation_keeps_the_last_message_and_fails_when_it_alone_overflows() {
+    let mut messages = vec![msg("system"), msg("user"), msg("user")];
+    let err = fit_messages(&mut messages, None, 27, 0, true).unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("even with older messages dropped"));
+    assert_eq!(roles(&messages), ["system", "user"]);
+}
+
+#[test]
+fn unknown_context_skips_the_guard() {
+    let mut messages = vec![msg("user")];
+    assert_eq!(fit_messages(&mut messages, None, 0, 100, false).unwrap(), 0);
+}
+
+#[test]
+fn limit_prefers_num_ctx_then_loaded_context() {
+    let native: crate::model::types::NativeModelData = serde_json::from_value(json!({
+        "key": "m",
+        "type": "llm",
+        "publisher": "p",
+        "max_context_length": 8192,
+        "loaded_instances": [{ "id": "m", "config": { "context_length": 4096 } }]
+    }))
+    .unwrap();
+    let info = ModelInfo::from_native_data(&native);
+    assert_eq!(context_limit(&info, None), 4096);
+    assert_eq!(
+        context_limit(&info, Some(&json!({ "num_ctx": 2048 }))),
+        2048
+    );
+    assert_eq!(context_limit(&info, Some(&json!({ "num_ctx": 0 }))), 4096);
+}
+
+#[test]
+fn reservation_reads_max_tokens_or_num_predict() {
+    assert_eq!(reserved_output_tokens(None), 0);
+    assert_eq!(
+        reserved_output_tokens(Some(&json!({ "num_predict": 128 }))),
+        128
+    );
+    assert_eq!(
+        reserved_output_tokens(Some(&json!({ "num_predict": -1 }))),
+        0
+    );
+    assert_eq!(
+        reserved_output_tokens(Some(&json!({ "max_tokens": 64, "num_predict": 128 }))),
+        64
+    );
+}
+
+#[test]
+fn only_the_final_line_is_marked() {
+    let line = Bytes::from_static(b"{\"done\":false,\"message\":{}}\n");
+    assert_eq!(mark_final_line(line.clone()), line);
+
+    let marked = mark_final_line(Bytes::from_static(b"{\"done\":true}\n"));
+    assert!(marked.ends_with(b"\n"));
+    let value: Value = serde_json::from_slice(&marked).unwrap();
+    assert_eq!(value["truncated"], json!(true));
+}
@@ -70,3 +70,48 @@ fn lifts_both_truncate_and_dimensions_creating_options() {
     assert_eq!(body.pointer("/options/truncate"), Some(&json!(true)));
     assert_eq!(body.pointer("/options/dimensions"), Some(&json!(1024)));
 }
+
+#[test]
+fn truncation_defaults_to_allowed() {
+    assert!(truncation_allowed(None));
+    assert!(truncation_allowed(Some(&json!({"truncate": true}))));
+    assert!(!truncation_allowed(Some(&json!({"truncate": false}))));
+}
+
+#[test]
+fn inputs_within_context_pass() {
+    assert!(check_inputs_fit_context(&json!("short"), 16).is_ok());
+    assert!(check_inputs_fit_context(&json!(["a", "b"]), 16).is_ok());
+    // Unknown context length never rejects.
+    assert!(check_inputs_fit_context(&json!("x".repeat(10_000)), 0).is_ok());
+}
+
+#[test]
+fn overlong_input_is_rejected_with_its_index() {
+    let long = "word ".repeat(100);
+    let err = check_inputs_fit_context(&json!(["ok", long]), 16).unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("input 1"), "{}", err.message);
+    assert!(err.message.contains("16 tokens"), "{}", err.message);
+
+    let err = check_inputs_fit_context(&json!(long), 16).unwrap_err();
+    assert!(err.message.starts_with("input exceeds"), "{}", err.message);
+}
+
+#[test]
+fn lifts_top_level_normalize_into_options() {
+    let mut body = json!({
+        "model": "all-minilm",
+        "input": "hello",
+        "normalize": true
+    });
+    lift_embed_top_level_params(&mut body);
+    assert_eq!(body.pointer("/options/normalize"), Some(&json!(true)));
+    assert!(normalize_requested(body.get("options")));
+}
+
+#[test]
+fn normalize_defaults_to_off() {
+    assert!(!normalize_requested(None));
+    assert!(!normalize_requested(Some(&json!({ "normalize": "yes" }))));
+}
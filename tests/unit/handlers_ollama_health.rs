@@ -18,3 +18,13 @@ async fn root_returns_ollama_is_running() {
     let body = response.into_body().collect().await.unwrap().to_bytes();
     assert_eq!(body.as_ref(), b"Ollama is running");
 }
+
+#[tokio::test]
+async fn root_options_lists_allowed_methods() {
+    let response = handle_ollama_root_options().await;
+    assert_eq!(response.status(), 204);
+    assert_eq!(
+        response.headers().get(http::header::ALLOW).unwrap(),
+        "GET, HEAD, OPTIONS"
+    );
+}
@@ -0,0 +1,93 @@
+use super::*;
+use crate::model::types::{NativeModelData, NativeQuantization};
+
+fn model(key: &str) -> ModelInfo {
+    ModelInfo::from_native_data(&NativeModelData {
+        key: key.to_string(),
+        model_type: "llm".to_string(),
+        publisher: "meta".to_string(),
+        architecture: Some("llama".to_string()),
+        format: Some("gguf".to_string()),
+        quantization: Some(NativeQuantization {
+            name: Some("Q4_K_M".to_string()),
+            bits_per_weight: Some(4.5),
+        }),
+        max_context_length: 8192,
+        loaded_instances: vec![],
+        capabilities: None,
+        size_bytes: Some(4_000_000_000),
+        params_string: Some("8B".to_string()),
+        display_name: None,
+        description: None,
+    })
+}
+
+#[test]
+fn parses_manifest_and_blob_paths_with_namespaced_names() {
+    assert_eq!(
+        RegistryPath::parse("library/llama3/manifests/latest"),
+        Some(RegistryPath::Manifest {
+            name: "library/llama3".to_string(),
+            reference: "latest".to_string(),
+        })
+    );
+    assert_eq!(
+        RegistryPath::parse("llama3/blobs/sha256:abc"),
+        Some(RegistryPath::Blob {
+            name: "llama3".to_string(),
+            digest: "sha256:abc".to_string(),
+        })
+    );
+}
+
+#[test]
+fn rejects_paths_that_are_neither_manifests_nor_blobs() {
+    for path in [
+        "",
+        "llama3",
+        "llama3/tags/list",
+        "/manifests/latest",
+        "llama3/manifests/",
+        "llama3/blobs/",
+    ] {
+        assert_eq!(RegistryPath::parse(path), None, "{path}");
+    }
+}
+
+#[test]
+fn reference_maps_to_an_ollama_model_name() {
+    assert_eq!(
+        model_name_for_reference("library/llama3", "latest"),
+        "llama3"
+    );
+    assert_eq!(model_name_for_reference("llama3", "8b"), "llama3:8b");
+    assert_eq!(
+        model_name_for_reference("bartowski/qwen", "Q4_K_M"),
+        "bartowski/qwen:Q4_K_M"
+    );
+}
+
+#[test]
+fn manifest_config_carries_the_tags_digest_and_size() {
+    let model = model("meta-llama-3-8b");
+    let manifest = build_manifest(&model);
+    assert_eq!(manifest["schemaVersion"], 2);
+    assert_eq!(manifest["mediaType"], MANIFEST_MEDIA_TYPE);
+    // The weights cannot be fetched through the proxy, so no layer is named.
+    assert_eq!(manifest["layers"], json!([]));
+
+    let (config, config_digest) = manifest_config(&model);
+    assert_eq!(manifest["config"]["digest"], config_digest.as_str());
+    assert_eq!(manifest["config"]["size"], config.len());
+    assert_eq!(config_digest.len(), "sha256:".len() + 64);
+
+    let tags = model.to_ollama_tags_model(None);
+    let config: Value = serde_json::from_slice(&config).unwrap();
+    assert_eq!(
+        config["model_digest"],
+        format!("sha256:{}", tags["digest"].as_str().unwrap())
+    );
+    assert_eq!(config["model_size"], tags["size"]);
+    // Fabricated, but stable for the same model.
+    assert_eq!(build_manifest(&model), manifest);
+}
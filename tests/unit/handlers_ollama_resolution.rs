@@ -36,8 +36,6 @@ fn suffix_inserted_into_lm_request() {
     use crate::lmstudio::request::{LMStudioRequestType, TopLevelParams, build_lm_studio_request};
     use std::borrow::Cow;
 
-    let body = json!({ "suffix": "world", "model": "test", "prompt": "hello" });
-    let suffix_val = body.get("suffix");
     let top_level = TopLevelParams {
         think: None,
         logprobs: None,
@@ -45,11 +43,12 @@ fn suffix_inserted_into_lm_request() {
         model_is_thinking: false,
     };
 
-    let mut lm_request = build_lm_studio_request(
+    let lm_request = build_lm_studio_request(
         "test",
         LMStudioRequestType::Completion {
             prompt: Cow::Borrowed("hello"),
             stream: false,
+            suffix: Some("world"),
         },
         None,
         None,
@@ -57,15 +56,31 @@ fn suffix_inserted_into_lm_request() {
         Some(&top_level),
     );
 
-    if let Some(s) = suffix_val
-        && let Some(obj) = lm_request.as_object_mut()
-    {
-        obj.insert("suffix".to_string(), s.clone());
-    }
-
+    assert_eq!(lm_request.get("prompt"), Some(&json!("hello")));
     assert_eq!(lm_request.get("suffix"), Some(&json!("world")));
 }
 
+#[test]
+fn empty_suffix_not_inserted_into_lm_request() {
+    use crate::lmstudio::request::{LMStudioRequestType, build_lm_studio_request};
+    use std::borrow::Cow;
+
+    let lm_request = build_lm_studio_request(
+        "test",
+        LMStudioRequestType::Completion {
+            prompt: Cow::Borrowed("hello"),
+            stream: false,
+            suffix: Some(""),
+        },
+        None,
+        None,
+        None,
+        None,
+    );
+
+    assert!(lm_request.get("suffix").is_none());
+}
+
 #[test]
 fn suffix_not_inserted_on_vision_path() {
     let body = json!({ "suffix": "world", "model": "test", "prompt": "hello",
@@ -221,6 +236,7 @@ fn model_info(id: &str, supports_reasoning: bool) -> ModelInfo {
         loaded_flash_attention: None,
         loaded_eval_batch_size: None,
         loaded_parallel: None,
+        loaded_instances: Vec::new(),
     }
 }
 
@@ -300,3 +316,32 @@ fn merge_non_object_base_falls_back_to_overrides() {
         "non-object base must defer to the override"
     );
 }
+
+#[test]
+fn reasoning_mode_falls_back_to_server_default() {
+    assert_eq!(
+        resolve_reasoning_mode(None, ReasoningMode::Strip).unwrap(),
+        ReasoningMode::Strip
+    );
+    let opts = json!({ "temperature": 0.2 });
+    assert_eq!(
+        resolve_reasoning_mode(Some(&opts), ReasoningMode::Merge).unwrap(),
+        ReasoningMode::Merge
+    );
+}
+
+#[test]
+fn reasoning_mode_option_overrides_server_default() {
+    let opts = json!({ "reasoning_mode": "strip" });
+    assert_eq!(
+        resolve_reasoning_mode(Some(&opts), ReasoningMode::Separate).unwrap(),
+        ReasoningMode::Strip
+    );
+}
+
+#[test]
+fn reasoning_mode_rejects_unknown_value() {
+    let opts = json!({ "reasoning_mode": 1 });
+    let err = resolve_reasoning_mode(Some(&opts), ReasoningMode::Separate).unwrap_err();
+    assert_eq!(err.status_code, 400);
+}
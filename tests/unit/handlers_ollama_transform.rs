@@ -0,0 +1,37 @@
+use super::*;
+
+#[test]
+fn explicit_kind_wins_over_body_shape() {
+    let body = json!({"kind": "generate", "messages": [], "prompt": "hi"});
+    assert_eq!(
+        TransformKind::from_body(&body).unwrap(),
+        TransformKind::Generate
+    );
+    let body = json!({"kind": "chat", "prompt": "hi"});
+    assert_eq!(
+        TransformKind::from_body(&body).unwrap(),
+        TransformKind::Chat
+    );
+}
+
+#[test]
+fn kind_is_inferred_from_messages_or_prompt() {
+    assert_eq!(
+        TransformKind::from_body(&json!({"messages": []})).unwrap(),
+        TransformKind::Chat
+    );
+    assert_eq!(
+        TransformKind::from_body(&json!({"prompt": "hi"})).unwrap(),
+        TransformKind::Generate
+    );
+}
+
+#[test]
+fn unknown_or_missing_kind_is_a_bad_request() {
+    let err = TransformKind::from_body(&json!({"kind": "embed"})).unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("\"embed\""), "{}", err.message);
+
+    let err = TransformKind::from_body(&json!({"model": "m"})).unwrap_err();
+    assert_eq!(err.status_code, 400);
+}
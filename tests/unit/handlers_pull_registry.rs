@@ -0,0 +1,160 @@
+use super::*;
+
+fn track(registry: &Arc<PullRegistry>, job_id: Option<&str>, model: &str) -> PullJob {
+    registry.track(
+        job_id,
+        model,
+        CancellationToken::new(),
+        reqwest::Client::new(),
+        "http://127.0.0.1:9".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn cancel_by_model_fires_token_and_reports_last_status() {
+    let registry = PullRegistry::new();
+    let mut job = track(&registry, Some("job_1"), "llama3.2:latest");
+    job.record(&json!({"status": "downloading", "total": 100, "completed": 40}));
+
+    let cancelled = registry.cancel("llama3.2").expect("download is tracked");
+    assert!(job.token().is_cancelled());
+    assert_eq!(cancelled["status"], "cancelled");
+    assert_eq!(cancelled["job_id"], "job_1");
+    assert_eq!(cancelled["last_status"]["completed"], 40);
+
+    job.finish();
+    drop(job);
+    assert_eq!(registry.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn cancel_by_job_id_leaves_other_downloads_running() {
+    let registry = PullRegistry::new();
+    let mut first = track(&registry, Some("job_1"), "qwen3");
+    let mut second = track(&registry, Some("job_2"), "qwen3");
+
+    assert!(registry.cancel("job_2").is_some());
+    assert!(second.token().is_cancelled());
+    assert!(!first.token().is_cancelled());
+
+    first.finish();
+    second.finish();
+}
+
+#[tokio::test]
+async fn cancel_job_matches_job_ids_only() {
+    let registry = PullRegistry::new();
+    let mut job = track(&registry, Some("job_1"), "qwen3");
+
+    assert!(registry.cancel_job("qwen3").is_none());
+    assert!(!job.token().is_cancelled());
+    let cancelled = registry.cancel_job("job_1").expect("job is tracked");
+    assert_eq!(cancelled["model"], "qwen3");
+    assert!(job.token().is_cancelled());
+
+    job.finish();
+}
+
+#[tokio::test]
+async fn unknown_target_is_not_found() {
+    let registry = PullRegistry::new();
+    let mut job = track(&registry, Some("job_1"), "qwen3");
+    assert!(registry.cancel("llama3").is_none());
+    assert!(registry.cancel("job_9").is_none());
+    job.finish();
+}
+
+#[tokio::test]
+async fn job_without_id_is_not_tracked() {
+    let registry = PullRegistry::new();
+    let job = track(&registry, None, "qwen3");
+    assert_eq!(registry.in_flight(), 0);
+    assert!(registry.cancel("qwen3").is_none());
+    drop(job);
+}
+
+#[tokio::test]
+async fn dropping_a_job_untracks_it() {
+    let registry = PullRegistry::new();
+    let job = track(&registry, Some("job_1"), "qwen3");
+    assert_eq!(registry.in_flight(), 1);
+    drop(job);
+    assert_eq!(registry.in_flight(), 0);
+}
+
+fn downloading(job_id: &str) -> LmStudioDownloadStatus {
+    serde_json::from_value(json!({"job_id": job_id, "status": "downloading"})).unwrap()
+}
+
+#[tokio::test]
+async fn second_claim_follows_the_lead_until_it_is_dropped() {
+    let registry = PullRegistry::new();
+    let PullSlot::Lead(lead) = registry.claim("owner/repo#") else {
+        panic!("first claim leads");
+    };
+    let PullSlot::Follow(mut follower) = registry.claim("owner/repo#") else {
+        panic!("second claim follows");
+    };
+    assert!(matches!(
+        registry.claim("owner/repo#Q8_0"),
+        PullSlot::Lead(_)
+    ));
+
+    lead.publish(Ok(downloading("job_1")));
+    follower.status.changed().await.unwrap();
+    let seen = follower.status.borrow_and_update().clone();
+    assert_eq!(seen.unwrap().unwrap().job_id.as_deref(), Some("job_1"));
+
+    drop(lead);
+    assert!(follower.status.changed().await.is_err());
+    assert!(matches!(registry.claim("owner/repo#"), PullSlot::Lead(_)));
+}
+
+#[tokio::test]
+async fn job_shares_polled_statuses_with_followers() {
+    let registry = PullRegistry::new();
+    let PullSlot::Lead(lead) = registry.claim("qwen3#") else {
+        panic!("first claim leads");
+    };
+    let PullSlot::Follow(mut follower) = registry.claim("qwen3#") else {
+        panic!("second claim follows");
+    };
+    let mut job = track(&registry, Some("job_1"), "qwen3").share_with(lead);
+
+    job.publish(&downloading("job_1"));
+    follower.status.changed().await.unwrap();
+    assert!(follower.status.borrow_and_update().is_some());
+
+    job.finish();
+    drop(job);
+    assert!(matches!(registry.claim("qwen3#"), PullSlot::Lead(_)));
+}
+
+#[tokio::test]
+async fn shared_job_is_abandoned_only_once_every_client_detaches() {
+    let registry = PullRegistry::new();
+    let PullSlot::Lead(lead) = registry.claim("qwen3#") else {
+        panic!("first claim leads");
+    };
+    let own = lead.attach();
+    let PullSlot::Follow(follower) = registry.claim("qwen3#") else {
+        panic!("second claim follows");
+    };
+    let mut job = track(&registry, Some("job_1"), "qwen3").share_with(lead);
+    assert!(!job.is_abandoned());
+
+    drop(own);
+    assert!(!job.is_abandoned());
+    drop(follower);
+    assert!(job.is_abandoned());
+
+    job.finish();
+}
+
+#[tokio::test]
+async fn unshared_job_is_never_abandoned() {
+    let registry = PullRegistry::new();
+    let mut job = track(&registry, Some("job_1"), "qwen3");
+    assert!(!job.is_abandoned());
+    job.finish();
+}
@@ -22,8 +22,9 @@ fn response_context_chat_variant() {
 fn response_context_generate_variant() {
     let ctx = ResponseContext::Generate {
         prompt: "hello world".to_string(),
+        generate_context: None,
     };
-    let ResponseContext::Generate { prompt } = ctx else {
+    let ResponseContext::Generate { prompt, .. } = ctx else {
         panic!("expected Generate variant");
     };
     assert_eq!(prompt, "hello world");
@@ -213,3 +213,59 @@ fn prepare_request_body_with_none_and_empty_bytes_returns_none() {
     );
     assert!(!prepared.is_json);
 }
+
+// ── parse_json_request ───────────────────────────────────────────────────────
+
+#[test]
+fn parse_json_request_accepts_utf8_bom() {
+    let value: Value = parse_json_request(b"\xEF\xBB\xBF{\"model\":\"x\"}").unwrap();
+    assert_eq!(value, json!({"model": "x"}));
+}
+
+#[test]
+fn parse_json_request_error_names_position_and_quotes_context() {
+    let body = b"{\n  \"model\": \"x\",\n  \"stream\": tru\n}";
+    let err = parse_json_request::<Value>(body).unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("line 3"), "{}", err.message);
+    assert!(err.message.contains("column"), "{}", err.message);
+    assert!(err.message.contains("\"stream\": tru"), "{}", err.message);
+    assert!(!err.message.contains('\n'), "snippet must stay on one line");
+}
+
+#[test]
+fn parse_json_request_snippet_is_bounded_in_large_payloads() {
+    let mut body = format!("{{\"pad\":\"{}\",", "a".repeat(10_000)).into_bytes();
+    body.extend_from_slice(b" oops }");
+    let err = parse_json_request::<Value>(&body).unwrap_err();
+    assert!(err.message.contains("oops"), "{}", err.message);
+    assert!(err.message.len() < 300, "{}", err.message);
+}
+
+#[test]
+fn bom_prefixed_json_looks_like_json() {
+    assert!(body_looks_like_json(b"\xEF\xBB\xBF {\"a\":1}"));
+}
+
+// ── body_too_large ───────────────────────────────────────────────────────────
+
+#[test]
+fn body_too_large_reports_limit_and_declared_size() {
+    let err = body_too_large(1024, Some(4096));
+    assert_eq!(err.status_code, 413);
+    assert!(err.message.contains("4096 bytes"), "{}", err.message);
+    assert!(err.message.contains("1024-byte limit"), "{}", err.message);
+    assert!(err.message.contains("--max-body-size"), "{}", err.message);
+
+    let err = body_too_large(1024, None);
+    assert!(err.message.contains("1024-byte limit"), "{}", err.message);
+}
+
+#[test]
+fn blob_too_large_names_the_blob_flag() {
+    let err = blob_too_large(1024, Some(1025));
+    assert_eq!(err.status_code, 413);
+    assert!(err.message.starts_with("blob too large"), "{}", err.message);
+    assert!(err.message.contains("1025 bytes"), "{}", err.message);
+    assert!(err.message.contains("--max-blob-size"), "{}", err.message);
+}
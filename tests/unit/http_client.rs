@@ -27,3 +27,75 @@ async fn new_with_fresh_token_is_not_cancelled() {
 // reqwest::Response objects.  The live request-execution paths
 // (make_request, make_raw_request) require a running HTTP server and are
 // therefore covered by integration tests only.
+
+// ── ClientSettings ──────────────────────────────────────────────────────────
+
+fn parse_config(args: &[&str]) -> Config {
+    use clap::Parser;
+    Config::parse_from(std::iter::once("ollama-lmstudio-proxy").chain(args.iter().copied()))
+}
+
+#[test]
+fn client_settings_defaults_bound_the_connect_phase() {
+    let settings = ClientSettings::from_config(&parse_config(&[]));
+    assert_eq!(
+        settings.connect_timeout,
+        Some(Duration::from_secs(10)),
+        "default connect timeout must be 10s"
+    );
+    assert_eq!(settings.request_timeout, Some(Duration::from_secs(300)));
+    assert_eq!(settings.pool_max_idle_per_host, 32);
+}
+
+#[test]
+fn client_settings_take_configured_values() {
+    let settings = ClientSettings::from_config(&parse_config(&[
+        "--connect-timeout-seconds",
+        "3",
+        "--request-timeout-seconds",
+        "900",
+        "--pool-max-idle-per-host",
+        "128",
+    ]));
+    assert_eq!(
+        settings,
+        ClientSettings {
+            connect_timeout: Some(Duration::from_secs(3)),
+            request_timeout: Some(Duration::from_secs(900)),
+            pool_max_idle_per_host: 128,
+        }
+    );
+}
+
+#[test]
+fn client_settings_zero_timeouts_are_unlimited() {
+    let settings = ClientSettings::from_config(&parse_config(&[
+        "--connect-timeout-seconds",
+        "0",
+        "--request-timeout-seconds",
+        "0",
+    ]));
+    assert_eq!(settings.connect_timeout, None);
+    assert_eq!(settings.request_timeout, None);
+}
+
+#[tokio::test]
+async fn client_settings_request_timeout_is_applied_to_the_builder() {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let mock = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&mock)
+        .await;
+
+    let settings = ClientSettings {
+        connect_timeout: None,
+        request_timeout: Some(Duration::from_millis(200)),
+        pool_max_idle_per_host: 1,
+    };
+    let client = settings.apply(reqwest::Client::builder()).build().unwrap();
+    let err = client.get(mock.uri()).send().await.unwrap_err();
+    assert!(err.is_timeout(), "{err}");
+}
@@ -62,6 +62,20 @@ fn lm_studio_unavailable_is_503() {
     assert!(e.is_lm_studio_unavailable());
 }
 
+#[test]
+fn unreachable_is_503_of_its_own_kind() {
+    let e = ProxyError::unreachable("refused");
+    assert_eq!(e.status_code, 503);
+    assert!(e.is_lm_studio_unavailable());
+    assert!(e.is_unreachable());
+}
+
+#[test]
+fn plain_503_is_not_unreachable() {
+    let e = ProxyError::lm_studio_unavailable("still downloading");
+    assert!(!e.is_unreachable());
+}
+
 #[test]
 fn too_many_requests_is_429() {
     let e = ProxyError::too_many_requests("slow down");
@@ -169,3 +183,99 @@ fn detection_is_case_insensitive() {
     assert!(is_model_loading_error("Loading Model"));
     assert!(is_model_loading_error("FAILED TO LOAD the model"));
 }
+
+#[test]
+fn retry_after_is_sent_as_header() {
+    use axum::response::IntoResponse;
+    let response = ProxyError::too_many_requests("busy")
+        .with_retry_after(7)
+        .into_response();
+    assert_eq!(response.status(), 429);
+    assert_eq!(
+        response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok()),
+        Some("7")
+    );
+}
+
+#[test]
+fn no_retry_after_header_by_default() {
+    use axum::response::IntoResponse;
+    let response = ProxyError::too_many_requests("busy").into_response();
+    assert!(response.headers().get("retry-after").is_none());
+}
+
+#[tokio::test]
+async fn suggestions_are_included_in_the_body() {
+    use axum::response::IntoResponse;
+    let response = ProxyError::not_found("model 'llama3' not found")
+        .with_suggestions(vec!["meta-llama-3.1-8b-instruct".to_string()])
+        .into_response();
+    assert_eq!(response.status(), 404);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "model 'llama3' not found");
+    assert_eq!(body["suggestions"][0], "meta-llama-3.1-8b-instruct");
+}
+
+#[tokio::test]
+async fn suggestions_are_omitted_when_empty() {
+    use axum::response::IntoResponse;
+    let response = ProxyError::not_found("gone").into_response();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(body.get("suggestions").is_none(), "{body}");
+}
+
+// ── upstream_error_message ───────────────────────────────────────────────────
+
+use super::upstream_error_message;
+use reqwest::StatusCode;
+
+#[test]
+fn openai_nested_error_message_is_flattened() {
+    let body = r#"{"error":{"message":"model not found","type":"invalid_request_error"}}"#;
+    assert_eq!(
+        upstream_error_message(StatusCode::BAD_REQUEST, body),
+        "model not found"
+    );
+}
+
+#[test]
+fn flat_error_string_and_bare_message_are_kept() {
+    assert_eq!(
+        upstream_error_message(StatusCode::BAD_REQUEST, r#"{"error":"no models loaded"}"#),
+        "no models loaded"
+    );
+    assert_eq!(
+        upstream_error_message(StatusCode::NOT_FOUND, r#"{"message":"unknown route"}"#),
+        "unknown route"
+    );
+}
+
+#[test]
+fn plain_text_body_is_used_and_empty_or_shapeless_bodies_name_the_status() {
+    assert_eq!(
+        upstream_error_message(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error\n"),
+        "Internal Server Error"
+    );
+    assert_eq!(
+        upstream_error_message(StatusCode::BAD_GATEWAY, ""),
+        "LM Studio error: 502 Bad Gateway"
+    );
+    assert_eq!(
+        upstream_error_message(StatusCode::BAD_REQUEST, r#"{"error":{"code":1}}"#),
+        "LM Studio error: 400 Bad Request"
+    );
+    let page = "<html>".repeat(200);
+    assert_eq!(
+        upstream_error_message(StatusCode::BAD_GATEWAY, &page),
+        "LM Studio error: 502 Bad Gateway"
+    );
+}
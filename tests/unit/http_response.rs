@@ -47,21 +47,13 @@ async fn json_response_body_round_trips() {
 }
 
 #[test]
-fn json_response_cors_headers_present() {
+fn json_response_leaves_cors_to_the_layer() {
+    // CORS headers come only from `--cors-origin`'s layer, never per response.
     let resp = json_response(&json!({}));
     let headers = resp.headers();
-    assert!(
-        headers.contains_key("access-control-allow-origin"),
-        "CORS origin header must be present"
-    );
-    assert!(
-        headers.contains_key("access-control-allow-methods"),
-        "CORS methods header must be present"
-    );
-    assert!(
-        headers.contains_key("access-control-allow-headers"),
-        "CORS headers header must be present"
-    );
+    assert!(!headers.contains_key("access-control-allow-origin"));
+    assert!(!headers.contains_key("access-control-allow-methods"));
+    assert!(!headers.contains_key("access-control-allow-headers"));
 }
 
 #[test]
@@ -201,3 +193,26 @@ fn no_authorization_from_caller_produces_no_auth_header() {
         "must not inject an authorization header when the caller provided none"
     );
 }
+
+// ── select_forward_headers ───────────────────────────────────────────────────
+
+#[test]
+fn select_forward_headers_keeps_only_allowlisted_names() {
+    let headers = make_warp_headers(&[
+        ("x-request-id", "abc"),
+        ("authorization", "Bearer client"),
+        ("cookie", "session=1"),
+    ]);
+    let allow = vec!["X-Request-Id".to_string(), "Authorization".to_string()];
+    let out = select_forward_headers(&headers, &allow);
+    assert_eq!(out.len(), 2);
+    assert_eq!(out.get("x-request-id").unwrap(), "abc");
+    assert_eq!(out.get("authorization").unwrap(), "Bearer client");
+    assert!(out.get("cookie").is_none());
+}
+
+#[test]
+fn select_forward_headers_empty_allowlist_forwards_nothing() {
+    let headers = make_warp_headers(&[("x-request-id", "abc")]);
+    assert!(select_forward_headers(&headers, &[]).is_empty());
+}
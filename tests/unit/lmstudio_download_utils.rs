@@ -7,6 +7,8 @@ fn make_virtual_entry(parameters: Option<serde_json::Value>) -> VirtualModelEntr
         name: "alias".to_string(),
         source_model: "llama3".to_string(),
         target_model_id: "llama-3-8b".to_string(),
+        parent_alias: None,
+        blobs: Vec::new(),
         created_at: Utc::now(),
         updated_at: Utc::now(),
         metadata: crate::storage::virtual_models::VirtualModelMetadata {
@@ -47,6 +49,12 @@ fn gs_protocol_is_remote() {
     assert!(looks_like_remote_identifier("gs://bucket/model.gguf"));
 }
 
+#[test]
+fn scheme_less_hf_url_is_remote() {
+    assert!(looks_like_remote_identifier("huggingface.co/org/model"));
+    assert!(looks_like_remote_identifier("hf.co/org/model"));
+}
+
 #[test]
 fn plain_model_name_is_not_remote() {
     assert!(!looks_like_remote_identifier("llama3"));
@@ -183,3 +191,68 @@ fn catalog_id_trims_leading_slash_from_model() {
     let id = build_catalog_identifier("org", "/model");
     assert_eq!(id, Some("org/model".to_string()));
 }
+
+// --- hf_repo_from_url / normalize_download_identifier ---
+
+#[test]
+fn hf_web_urls_map_to_owner_and_repo() {
+    for url in [
+        "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF",
+        "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF/",
+        "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF/tree/main",
+        "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF/blob/main/gpt-oss-20b-Q4_K_M.gguf",
+        "https://huggingface.co/lmstudio-community/gpt-oss-20b-GGUF?library=lmstudio",
+        "HTTPS://HuggingFace.co/lmstudio-community/gpt-oss-20b-GGUF",
+        "http://www.huggingface.co/lmstudio-community/gpt-oss-20b-GGUF",
+        "https://hf.co/lmstudio-community/gpt-oss-20b-GGUF",
+        "hf://lmstudio-community/gpt-oss-20b-GGUF",
+        "huggingface.co/lmstudio-community/gpt-oss-20b-GGUF",
+    ] {
+        assert_eq!(
+            hf_repo_from_url(url).unwrap().as_deref(),
+            Some("lmstudio-community/gpt-oss-20b-GGUF"),
+            "{url}"
+        );
+    }
+}
+
+#[test]
+fn non_hf_identifiers_are_not_hf_urls() {
+    for identifier in [
+        "openai/gpt-oss-20b",
+        "llama3:latest",
+        "https://example.com/models/model.gguf",
+        "s3://bucket/model.gguf",
+        "https://huggingface.com.evil.test/owner/repo",
+    ] {
+        assert_eq!(hf_repo_from_url(identifier).unwrap(), None, "{identifier}");
+    }
+}
+
+#[test]
+fn hf_urls_without_a_model_repo_are_rejected() {
+    for url in [
+        "https://huggingface.co/",
+        "https://huggingface.co/lmstudio-community",
+        "https://huggingface.co/datasets/owner/data",
+        "https://huggingface.co/spaces/owner/app",
+        "https://huggingface.co/owner/re po",
+        "hf://owner/..",
+    ] {
+        let err = hf_repo_from_url(url).unwrap_err();
+        assert_eq!(err.status_code, 400, "{url}");
+        assert!(err.message.contains(url), "{url}: {}", err.message);
+    }
+}
+
+#[test]
+fn download_identifier_normalizes_hf_urls_to_exact_repo_links() {
+    assert_eq!(
+        normalize_download_identifier("https://hf.co/owner/repo/blob/main/f.gguf").unwrap(),
+        "https://huggingface.co/owner/repo"
+    );
+    assert_eq!(
+        normalize_download_identifier("openai/gpt-oss-20b").unwrap(),
+        "openai/gpt-oss-20b"
+    );
+}
@@ -243,3 +243,55 @@ fn inject_empty_images_returns_messages_unchanged() {
     let out = inject_images_into_messages(messages.clone(), &images_val);
     assert_eq!(out, messages);
 }
+
+// --- data URL normalisation ---
+
+fn image_urls(messages: &Value) -> Vec<String> {
+    messages[messages.as_array().unwrap().len() - 1]["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|part| part["type"] == "image_url")
+        .map(|part| part["image_url"]["url"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn existing_data_url_is_not_wrapped_twice() {
+    let url = "data:image/webp;base64,UklGRiIAAABXRUJQ";
+    let messages = build_vision_chat_messages(None, "look", Some(&json!([url])));
+    assert_eq!(image_urls(&messages), vec![url.to_string()]);
+    assert_eq!(native_image_data_url(url), url);
+}
+
+#[test]
+fn line_wrapped_base64_is_joined() {
+    let wrapped = "iVBORw0KGgoAAAANSUhE\nUgAAAAEAAAAB\r\nCAYAAAAf ";
+    let messages = build_vision_chat_messages(None, "look", Some(&json!([wrapped])));
+    assert_eq!(
+        image_urls(&messages),
+        vec!["data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAf".to_string()]
+    );
+}
+
+#[test]
+fn multiple_images_keep_order_and_their_own_mime() {
+    let images = json!([
+        "iVBORw0KGgoAAAANSUhEUg",
+        "/9j/4AAQSkZJRgABAQ",
+        "R0lGODlhAQABAIAAAP",
+        "data:image/bmp;base64,Qk0eAAAAAAAAABoAAAAM"
+    ]);
+    let messages = build_vision_chat_messages(None, "compare", Some(&images));
+    let content = messages[0]["content"].as_array().unwrap();
+    assert_eq!(content[0], json!({"type": "text", "text": "compare"}));
+    assert_eq!(
+        image_urls(&messages),
+        vec![
+            "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg".to_string(),
+            "data:image/jpeg;base64,/9j/4AAQSkZJRgABAQ".to_string(),
+            "data:image/gif;base64,R0lGODlhAQABAIAAAP".to_string(),
+            "data:image/bmp;base64,Qk0eAAAAAAAAABoAAAAM".to_string(),
+        ]
+    );
+}
@@ -38,6 +38,32 @@ fn sub_second_duration_rounds_up_to_one() {
     assert_eq!(parse(json!("500ms")), Some(1));
 }
 
+#[test]
+fn float_seconds_truncate_to_whole_seconds() {
+    assert_eq!(parse(json!(5.0)), Some(5));
+    assert_eq!(parse(json!(5.5)), Some(5));
+    assert_eq!(parse(json!(0.0)), Some(0));
+}
+
+#[test]
+fn sub_second_float_rounds_up_to_one() {
+    assert_eq!(parse(json!(0.25)), Some(1));
+}
+
+#[test]
+fn negative_float_normalizes_to_minus_one() {
+    assert_eq!(parse(json!(-1.5)), Some(-1));
+}
+
+#[test]
+fn non_finite_or_huge_float_is_rejected() {
+    // serde_json cannot carry NaN or infinity inside a `Value`, so these
+    // are checked on the float path itself.
+    assert_eq!(parse_float(f64::NAN).unwrap_err().status_code, 400);
+    assert_eq!(parse_float(f64::INFINITY).unwrap_err().status_code, 400);
+    assert!(parse_keep_alive_seconds(Some(&json!(1e30))).is_err());
+}
+
 #[test]
 fn negative_string_normalizes_to_minus_one() {
     // bare "-1" parsed as integer
@@ -56,18 +82,56 @@ fn missing_returns_none() {
 }
 
 #[test]
-fn apply_ttl_negative_omits_field() {
+fn apply_ttl_negative_sends_indefinite_ttl() {
     // Negative keep_alive means "forever" — must NOT be forwarded as a negative
-    // ttl (LM Studio would reject or misinterpret).
+    // ttl (LM Studio would reject or misinterpret), nor omitted (a JIT-loaded
+    // model would fall back to LM Studio's idle TTL).
     let mut target = json!({"model": "x"});
     apply_keep_alive_ttl(&mut target, Some(-1));
-    assert!(
-        target.get("ttl").is_none(),
-        "ttl must be omitted when keep_alive is negative, got {}",
-        target
+    assert_eq!(
+        target.get("ttl"),
+        Some(&json!(get_runtime_config().indefinite_ttl_seconds))
     );
 }
 
+const YEAR: u64 = 365 * 24 * 60 * 60;
+
+fn ttl_for(v: Option<Value>, indefinite: u64) -> Option<u64> {
+    let parsed = parse_keep_alive_seconds(v.as_ref()).expect("keep_alive parses");
+    keep_alive_ttl(parsed, indefinite)
+}
+
+#[test]
+fn ttl_for_minus_one_is_the_indefinite_ttl() {
+    assert_eq!(ttl_for(Some(json!(-1)), YEAR), Some(YEAR));
+    assert_eq!(ttl_for(Some(json!("-1")), YEAR), Some(YEAR));
+    assert_eq!(ttl_for(Some(json!("-5m")), 60), Some(60));
+}
+
+#[test]
+fn ttl_for_negative_is_omitted_when_indefinite_ttl_is_zero() {
+    assert_eq!(ttl_for(Some(json!(-1)), 0), None);
+    assert_eq!(ttl_for(Some(json!("-1")), 0), None);
+}
+
+#[test]
+fn ttl_for_zero_string_keeps_unload_meaning() {
+    assert_eq!(ttl_for(Some(json!("0")), YEAR), Some(0));
+    assert!(keep_alive_requests_unload(
+        parse_keep_alive_seconds(Some(&json!("0"))).unwrap()
+    ));
+}
+
+#[test]
+fn ttl_for_duration_string_passes_through() {
+    assert_eq!(ttl_for(Some(json!("10m")), YEAR), Some(600));
+}
+
+#[test]
+fn ttl_for_absent_keep_alive_is_none() {
+    assert_eq!(ttl_for(None, YEAR), None);
+}
+
 #[test]
 fn apply_ttl_positive_sets_field() {
     let mut target = json!({"model": "x"});
@@ -13,13 +13,6 @@ use super::*;
 // When keep_alive == 0 it spawns a task that tries to reach LM Studio;
 // that path is an integration concern and is skipped here.
 
-fn make_resolver() -> std::sync::Arc<crate::model::ModelResolver> {
-    use moka::future::Cache;
-    let cache: Cache<String, String> = Cache::builder().max_capacity(128).build();
-    let resolver = crate::model::ModelResolver::new("http://127.0.0.1:0".to_string(), cache);
-    std::sync::Arc::new(resolver)
-}
-
 #[test]
 fn spawn_model_unload_noop_when_keep_alive_none() {
     let rt = tokio::runtime::Builder::new_current_thread()
@@ -31,7 +24,6 @@ fn spawn_model_unload_noop_when_keep_alive_none() {
         spawn_model_unload_if_needed(
             reqwest::Client::new(),
             "http://localhost:1234".to_string(),
-            make_resolver(),
             "llama3".to_string(),
             None,
             0,
@@ -50,7 +42,6 @@ fn spawn_model_unload_noop_when_keep_alive_positive() {
         spawn_model_unload_if_needed(
             reqwest::Client::new(),
             "http://localhost:1234".to_string(),
-            make_resolver(),
             "llama3".to_string(),
             Some(300),
             0,
@@ -69,7 +60,6 @@ fn spawn_model_unload_noop_when_keep_alive_negative() {
         spawn_model_unload_if_needed(
             reqwest::Client::new(),
             "http://localhost:1234".to_string(),
-            make_resolver(),
             "llama3".to_string(),
             Some(-1),
             0,
@@ -4,7 +4,7 @@ use serde_json::{Value, json};
 
 use crate::lmstudio::native_chat::{
     NativeChatRequestParams, build_native_chat_request, convert_native_to_ollama_chat,
-    native_done_reason, native_tool_call_to_openai,
+    native_done_reason, native_done_reason_from, native_tool_call_to_openai,
 };
 
 fn build(messages: &Value, options: Option<&Value>, think: Option<&Value>) -> Value {
@@ -184,7 +184,7 @@ fn convert_maps_message_and_reasoning_output() {
         "response_id": "resp_02b2017dbc06c12bfc353a2ed6c2b802f8cc682884bb5716"
     });
 
-    let out = convert_native_to_ollama_chat(&native, "ollama-model", Instant::now());
+    let out = convert_native_to_ollama_chat(&native, "ollama-model", 10, Instant::now());
 
     assert_eq!(out["model"], json!("ollama-model"));
     assert_eq!(out["done"], json!(true));
@@ -226,7 +226,7 @@ fn convert_maps_tool_call_output() {
         }
     });
 
-    let out = convert_native_to_ollama_chat(&native, "m", Instant::now());
+    let out = convert_native_to_ollama_chat(&native, "m", 10, Instant::now());
 
     assert_eq!(out["message"]["content"], json!("done"));
     let tool_calls = out["message"]["tool_calls"]
@@ -247,7 +247,7 @@ fn convert_omits_empty_thinking_and_tool_calls() {
         "output": [{ "type": "message", "content": "hi" }],
         "stats": {}
     });
-    let out = convert_native_to_ollama_chat(&native, "m", Instant::now());
+    let out = convert_native_to_ollama_chat(&native, "m", 10, Instant::now());
     assert!(out["message"].get("thinking").is_none());
     assert!(out["message"].get("tool_calls").is_none());
     // No response_id when absent.
@@ -274,6 +274,16 @@ fn done_reason_is_stop() {
     assert_eq!(native_done_reason(), "stop");
 }
 
+#[test]
+fn done_reason_follows_stats_stop_reason() {
+    let body = |reason: &str| json!({ "stats": { "stop_reason": reason } });
+    assert_eq!(native_done_reason_from(&body("maxTokensReached")), "length");
+    assert_eq!(native_done_reason_from(&body("eosFound")), "stop");
+    assert_eq!(native_done_reason_from(&body("toolCalls")), "stop");
+    assert_eq!(native_done_reason_from(&body("failed")), "stop");
+    assert_eq!(native_done_reason_from(&json!({})), "stop");
+}
+
 #[test]
 fn request_drops_tools_format_tool_choice() {
     // The native request schema has no tools/tool_choice/response_format field,
@@ -81,6 +81,7 @@ fn top_level_params_think_true_emits_reasoning_on() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -104,6 +105,7 @@ fn top_level_params_think_false_emits_reasoning_off() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -127,6 +129,7 @@ fn top_level_params_think_string_passes_through() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -152,6 +155,7 @@ fn top_level_params_think_max_clamps_to_high() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -175,6 +179,7 @@ fn top_level_params_absent_think_emits_no_reasoning() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -199,6 +204,7 @@ fn top_level_params_absent_think_thinking_model_defaults_reasoning_on() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -224,6 +230,7 @@ fn top_level_params_explicit_think_false_wins_over_thinking_model_default() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -247,6 +254,7 @@ fn top_level_params_logprobs_forwarded() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hello"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -322,6 +330,36 @@ fn forwards_stop_array() {
     );
 }
 
+#[test]
+fn wraps_a_single_stop_string_in_an_array() {
+    let options = json!({ "stop": "\nUser:" });
+    let params = map_ollama_to_lmstudio_params(Some(&options), None);
+    assert_eq!(params.get("stop"), Some(&json!(["\nUser:"])));
+}
+
+#[test]
+fn drops_non_string_and_empty_stop_entries() {
+    let options = json!({ "stop": ["END", 7, "", null, {"x": 1}, "###"] });
+    let params = map_ollama_to_lmstudio_params(Some(&options), None);
+    assert_eq!(params.get("stop"), Some(&json!(["END", "###"])));
+}
+
+#[test]
+fn caps_stop_sequences_at_four() {
+    let options = json!({ "stop": ["a", "b", "c", "d", "e", "f"] });
+    let params = map_ollama_to_lmstudio_params(Some(&options), None);
+    assert_eq!(params.get("stop"), Some(&json!(["a", "b", "c", "d"])));
+}
+
+#[test]
+fn omits_stop_when_nothing_usable_is_left() {
+    for stop in [json!(""), json!([]), json!(["", 3]), json!(42), json!(null)] {
+        let options = json!({ "stop": stop });
+        let params = map_ollama_to_lmstudio_params(Some(&options), None);
+        assert!(params.get("stop").is_none(), "stop = {stop}");
+    }
+}
+
 #[test]
 fn forwards_min_p() {
     let options = json!({ "min_p": 0.05 });
@@ -396,6 +434,7 @@ fn truncate_dropped_on_chat_build_kept_on_embeddings_build() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("hi"),
             stream: false,
+            suffix: None,
         },
         Some(&opts),
         None,
@@ -807,6 +846,7 @@ fn build_completion_request_contains_model_prompt_stream() {
         LMStudioRequestType::Completion {
             prompt: std::borrow::Cow::Borrowed("once upon a time"),
             stream: false,
+            suffix: None,
         },
         None,
         None,
@@ -872,6 +912,7 @@ fn think_none_string_normalised_to_off() {
             LMStudioRequestType::Completion {
                 prompt: std::borrow::Cow::Borrowed("hi"),
                 stream: false,
+                suffix: None,
             },
             None,
             None,
@@ -902,6 +943,7 @@ fn think_known_levels_pass_through_unchanged() {
             LMStudioRequestType::Completion {
                 prompt: std::borrow::Cow::Borrowed("hi"),
                 stream: false,
+                suffix: None,
             },
             None,
             None,
@@ -941,3 +983,96 @@ fn top_level_all_none_fields_inserts_nothing() {
     assert!(request.get("logprobs").is_none());
     assert!(request.get("top_logprobs").is_none());
 }
+
+#[test]
+fn unknown_option_keys_exclude_mapped_and_unsupported_ones() {
+    let options = json!({
+        "temperatur": 0.2,
+        "temperature": 0.2,
+        "num_ctx": 4096,
+        "mirostat": 1,
+        "truncate": true,
+        "reasoning_mode": "strip",
+        "topk": 5
+    });
+    assert_eq!(
+        collect_unknown_option_keys(&options),
+        vec!["temperatur".to_string(), "topk".to_string()]
+    );
+    assert!(collect_unknown_option_keys(&json!("not an object")).is_empty());
+}
+
+#[test]
+fn options_logprobs_forwarded_and_top_level_wins() {
+    let options = json!({ "logprobs": true, "top_logprobs": 2 });
+    let request = build_lm_studio_request(
+        "mymodel",
+        LMStudioRequestType::Completion {
+            prompt: std::borrow::Cow::Borrowed("hello"),
+            stream: false,
+            suffix: None,
+        },
+        Some(&options),
+        None,
+        None,
+        None,
+    );
+    assert_eq!(request["logprobs"], json!(true));
+    assert_eq!(request["top_logprobs"], json!(2));
+
+    let top_logprobs = json!(5);
+    let top = TopLevelParams {
+        think: None,
+        logprobs: None,
+        top_logprobs: Some(&top_logprobs),
+        model_is_thinking: false,
+    };
+    let request = build_lm_studio_request(
+        "mymodel",
+        LMStudioRequestType::Completion {
+            prompt: std::borrow::Cow::Borrowed("hello"),
+            stream: false,
+            suffix: None,
+        },
+        Some(&options),
+        None,
+        None,
+        Some(&top),
+    );
+    assert_eq!(request["top_logprobs"], json!(5));
+}
+
+#[test]
+fn lift_ollama_options_maps_known_keys_and_drops_the_rest() {
+    let mut body = json!({
+        "model": "m",
+        "messages": [],
+        "temperature": 0.1,
+        "options": {
+            "temperature": 0.9,
+            "num_predict": 64,
+            "stop": "END",
+            "mirostat": 2,
+            "num_ctx": 8192
+        }
+    });
+    lift_ollama_options(body.as_object_mut().unwrap());
+    assert_eq!(
+        body,
+        json!({
+            "model": "m",
+            "messages": [],
+            "temperature": 0.1,
+            "max_tokens": 64,
+            "stop": ["END"]
+        })
+    );
+}
+
+#[test]
+fn lift_ollama_options_leaves_bodies_without_an_options_object() {
+    let mut body = json!({ "model": "m", "options": "opaque" });
+    let before = body.clone();
+    lift_ollama_options(body.as_object_mut().unwrap());
+    assert_eq!(body, before);
+}
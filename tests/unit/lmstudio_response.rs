@@ -816,6 +816,7 @@ fn embeddings_response_shape_matches_ollama_embed_spec() {
         "all-minilm",
         &json!("hi"),
         Instant::now(),
+        false,
     );
     assert_eq!(
         result.get("model").and_then(|v| v.as_str()),
@@ -842,11 +843,62 @@ fn embeddings_response_shape_matches_ollama_embed_spec() {
     }
 }
 
+#[test]
+fn embeddings_are_ordered_by_index() {
+    let lm = json!({
+        "data": [
+            {"index": 1, "embedding": [0.2]},
+            {"index": 0, "embedding": [0.1]}
+        ]
+    });
+    let result = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm,
+        "m",
+        &json!(["a", "b"]),
+        Instant::now(),
+        false,
+    );
+    assert_eq!(result["embeddings"], json!([[0.1], [0.2]]));
+}
+
+#[test]
+fn normalized_embeddings_have_unit_norm() {
+    let lm = json!({"data": [
+        {"index": 0, "embedding": [3.0, 4.0]},
+        {"index": 1, "embedding": [1.0, 2.0, 2.0]},
+        {"index": 2, "embedding": [0.0, 0.0]}
+    ]});
+    let result = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm,
+        "m",
+        &json!(["a", "b", "c"]),
+        Instant::now(),
+        true,
+    );
+    let embeds = result["embeddings"].as_array().unwrap();
+    for vector in &embeds[..2] {
+        let norm: f64 = vector
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_f64().unwrap().powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!((norm - 1.0).abs() < 1e-9, "{vector}");
+    }
+    assert_eq!(embeds[2], json!([0.0, 0.0]), "zero vector is left alone");
+}
+
 #[test]
 fn embeddings_response_empty_data_yields_empty_embeddings() {
     let lm = json!({"data": [], "usage": {"prompt_tokens": 0}});
-    let result =
-        ResponseTransformer::convert_to_ollama_embeddings(&lm, "m", &json!("x"), Instant::now());
+    let result = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm,
+        "m",
+        &json!("x"),
+        Instant::now(),
+        false,
+    );
     let embeds = result
         .get("embeddings")
         .and_then(|v| v.as_array())
@@ -865,6 +917,7 @@ fn embeddings_prompt_eval_count_scales_with_input_when_no_usage() {
         "m",
         &json!("hi"),
         Instant::now(),
+        false,
     );
     let long_text = "word ".repeat(200);
     let long = ResponseTransformer::convert_to_ollama_embeddings(
@@ -872,6 +925,7 @@ fn embeddings_prompt_eval_count_scales_with_input_when_no_usage() {
         "m",
         &json!(long_text),
         Instant::now(),
+        false,
     );
 
     let short_count = short.get("prompt_eval_count").and_then(|v| v.as_u64());
@@ -888,6 +942,7 @@ fn embeddings_prompt_eval_count_scales_with_input_when_no_usage() {
         "m",
         &json!([long_text.clone(), long_text]),
         Instant::now(),
+        false,
     );
     assert!(
         batch.get("prompt_eval_count").and_then(|v| v.as_u64()) > long_count,
@@ -903,8 +958,13 @@ fn embeddings_prompt_eval_count_uses_usage_when_present() {
         "data": [{"embedding": [0.1, 0.2]}],
         "usage": {"prompt_tokens": 42, "total_tokens": 42}
     });
-    let result =
-        ResponseTransformer::convert_to_ollama_embeddings(&lm, "m", &json!("x"), Instant::now());
+    let result = ResponseTransformer::convert_to_ollama_embeddings(
+        &lm,
+        "m",
+        &json!("x"),
+        Instant::now(),
+        false,
+    );
     assert_eq!(
         result.get("prompt_eval_count").and_then(|v| v.as_u64()),
         Some(42),
@@ -1005,6 +1065,40 @@ fn extract_finish_reason_present() {
     assert_eq!(extract_finish_reason(&v), Some("length"));
 }
 
+#[test]
+fn stop_reason_maps_to_finish_reason() {
+    assert_eq!(map_stop_reason("eosFound"), Some("stop"));
+    assert_eq!(map_stop_reason("stopStringFound"), Some("stop"));
+    assert_eq!(map_stop_reason("userStopped"), Some("stop"));
+    assert_eq!(map_stop_reason("maxTokensReached"), Some("length"));
+    assert_eq!(map_stop_reason("maxPredictedTokensReached"), Some("length"));
+    assert_eq!(map_stop_reason("contextLengthReached"), Some("length"));
+    assert_eq!(map_stop_reason("toolCalls"), Some("tool_calls"));
+    assert_eq!(map_stop_reason("failed"), None);
+    assert_eq!(map_stop_reason(""), None);
+}
+
+#[test]
+fn extract_finish_reason_falls_back_to_stop_reason() {
+    let v =
+        json!({"choices": [{"text": "x"}], "stats": {"stop_reason": "maxPredictedTokensReached"}});
+    assert_eq!(extract_finish_reason(&v), Some("length"));
+    let v = json!({"choices": [{"finish_reason": ""}], "stats": {"stop_reason": "eosFound"}});
+    assert_eq!(extract_finish_reason(&v), Some("stop"));
+}
+
+#[test]
+fn extract_finish_reason_prefers_openai_finish_reason() {
+    let v = json!({"choices": [{"finish_reason": "stop"}], "stats": {"stop_reason": "maxTokensReached"}});
+    assert_eq!(extract_finish_reason(&v), Some("stop"));
+}
+
+#[test]
+fn unknown_stop_reason_leaves_finish_reason_absent() {
+    let v = json!({"choices": [{}], "stats": {"stop_reason": "failed"}});
+    assert!(extract_finish_reason(&v).is_none());
+}
+
 #[test]
 fn timing_legacy_estimation_no_zero_fields() {
     let timing = TimingInfo::from_legacy_estimation(Instant::now(), 5, 5, None, None);
@@ -1037,8 +1131,52 @@ fn timing_legacy_estimation_falls_back_to_estimates() {
 }
 
 #[test]
-fn timing_stream_chunks_no_zero_fields() {
-    let timing = TimingInfo::from_stream_chunks(Duration::from_millis(50), 12, None);
+fn measured_load_is_added_on_top_of_stats_timings() {
+    // Stats time only prompt processing and generation (0.3s here).
+    let mut response = json!({
+        "total_duration": 300_000_000u64,
+        "load_duration": 1_000_000u64,
+        "prompt_eval_duration": 100_000_000u64,
+        "eval_duration": 200_000_000u64,
+    });
+    let started = Instant::now() - Duration::from_millis(2_400);
+    apply_measured_load(&mut response, Duration::from_secs(2), started);
+
+    assert_eq!(response["load_duration"], 2_000_000_000u64);
+    let total = response["total_duration"].as_u64().unwrap();
+    assert!(total >= 2_400_000_000, "total covers the load: {total}");
+    assert_eq!(response["prompt_eval_duration"], 100_000_000u64);
+    assert_eq!(response["eval_duration"], 200_000_000u64);
+}
+
+#[test]
+fn measured_load_is_carved_out_of_wall_clock_phases() {
+    // Wall-clock estimates split the whole elapsed time, load included.
+    let mut response = json!({
+        "total_duration": 3_000_000_000u64,
+        "prompt_eval_duration": 1_000_000_000u64,
+        "eval_duration": 2_000_000_000u64,
+    });
+    apply_measured_load(&mut response, Duration::from_secs(2), Instant::now());
+
+    assert_eq!(response["total_duration"], 3_000_000_000u64);
+    let prompt_eval = response["prompt_eval_duration"].as_u64().unwrap();
+    let eval = response["eval_duration"].as_u64().unwrap();
+    assert!(prompt_eval + eval <= 1_000_000_000, "{response}");
+    assert!(eval > prompt_eval, "proportions kept: {response}");
+}
+
+#[test]
+fn zero_load_leaves_timings_alone() {
+    let mut response = json!({"total_duration": 5u64, "load_duration": 1_000_000u64});
+    let before = response.clone();
+    apply_measured_load(&mut response, Duration::ZERO, Instant::now());
+    assert_eq!(response, before);
+}
+
+#[test]
+fn timing_stream_no_zero_fields() {
+    let timing = TimingInfo::from_stream(Duration::from_millis(50), 10, 12, None, None);
     assert!(timing.total_duration >= 1);
     assert!(timing.load_duration >= 1);
     assert!(timing.prompt_eval_count >= 1);
@@ -1048,8 +1186,8 @@ fn timing_stream_chunks_no_zero_fields() {
 }
 
 #[test]
-fn timing_stream_chunks_uses_actual_completion_tokens_when_provided() {
-    let timing = TimingInfo::from_stream_chunks(Duration::from_millis(50), 12, Some(77));
+fn timing_stream_uses_actual_completion_tokens_when_provided() {
+    let timing = TimingInfo::from_stream(Duration::from_millis(50), 10, 12, None, Some(77));
     assert_eq!(
         timing.eval_count, 77,
         "actual completion tokens must override chunk-count estimate"
@@ -1057,14 +1195,22 @@ fn timing_stream_chunks_uses_actual_completion_tokens_when_provided() {
 }
 
 #[test]
-fn timing_stream_chunks_uses_chunk_count_estimate_as_fallback() {
-    let timing = TimingInfo::from_stream_chunks(Duration::from_millis(50), 9, None);
+fn timing_stream_uses_output_estimate_as_fallback() {
+    let timing = TimingInfo::from_stream(Duration::from_millis(50), 10, 9, None, None);
     assert_eq!(
         timing.eval_count, 9,
-        "chunk-count estimate must be used as fallback"
+        "output-text estimate must be used as fallback"
     );
 }
 
+#[test]
+fn timing_stream_prefers_actual_prompt_tokens_over_estimate() {
+    let estimated = TimingInfo::from_stream(Duration::from_millis(50), 340, 9, None, None);
+    assert_eq!(estimated.prompt_eval_count, 340);
+    let actual = TimingInfo::from_stream(Duration::from_millis(50), 340, 9, Some(312), None);
+    assert_eq!(actual.prompt_eval_count, 312);
+}
+
 // =========================================================================
 // GAP A: logprobs forwarding
 // =========================================================================
@@ -1097,6 +1243,31 @@ fn chat_response_logprobs_forwarded_when_upstream_provides_them() {
     );
 }
 
+#[test]
+fn chat_response_logprobs_round_trip_with_alternatives() {
+    // Ollama puts `logprobs` at the top level of the chat response, not under
+    // `message`; each entry (alternatives and bytes included) is kept as sent.
+    let entries = json!([
+        {"token": "Hel", "logprob": -0.01, "bytes": [72, 101, 108],
+         "top_logprobs": [
+            {"token": "Hel", "logprob": -0.01, "bytes": [72, 101, 108]},
+            {"token": "Hi", "logprob": -4.6, "bytes": [72, 105]}
+         ]},
+        {"token": "lo", "logprob": -0.2, "bytes": [108, 111],
+         "top_logprobs": [{"token": "lo", "logprob": -0.2, "bytes": [108, 111]}]}
+    ]);
+    let lm = json!({
+        "choices": [{
+            "message": {"role": "assistant", "content": "Hello"},
+            "finish_reason": "stop",
+            "logprobs": {"content": entries}
+        }]
+    });
+    let result = ResponseTransformer::convert_to_ollama_chat(&lm, "m", 1, Instant::now());
+    assert_eq!(result["logprobs"], entries);
+    assert!(result["message"].get("logprobs").is_none());
+}
+
 #[test]
 fn chat_response_logprobs_absent_when_upstream_omits_them() {
     let lm = json!({
@@ -1201,14 +1372,14 @@ fn chat_response_message_images_absent_when_upstream_returns_empty_array() {
 // =========================================================================
 
 #[test]
-fn timing_stream_chunks_is_wall_clock_heuristic_not_real_stats() {
+fn timing_stream_is_wall_clock_heuristic_not_real_stats() {
     // LM Studio's OpenAI-compat streaming does not expose per-token timing data
-    // in SSE chunks. This test documents that from_stream_chunks produces non-zero
-    // heuristic timings based on wall-clock duration and chunk count.
+    // in SSE chunks. This test documents that from_stream produces non-zero
+    // heuristic timings based on wall-clock duration and token estimates.
     // When LM Studio adds streaming usage support, this test should be updated
     // to assert against real per-token stats from upstream.
     let duration = Duration::from_millis(200);
-    let timing = TimingInfo::from_stream_chunks(duration, 20, None);
+    let timing = TimingInfo::from_stream(duration, 10, 20, None, None);
     assert_eq!(
         timing.total_duration,
         duration.as_nanos() as u64,
@@ -1346,6 +1517,96 @@ fn normalize_tool_role_parallel_tool_calls_match_by_name() {
     );
 }
 
+#[test]
+fn normalize_tool_role_pairs_with_synthesized_id_for_ollama_history() {
+    // Ollama clients echo tool_calls without `id` or `function.index`.
+    let msgs = vec![
+        json!({"role": "user", "content": "temp?"}),
+        json!({
+            "role": "assistant",
+            "content": "",
+            "tool_calls": [{"function": {"name": "get_temperature", "arguments": {"city": "Oslo"}}}]
+        }),
+        json!({"role": "tool", "tool_name": "get_temperature", "content": "3°C"}),
+    ];
+    let out = normalize_chat_messages(&msgs, None);
+    let arr = out.as_array().unwrap();
+    let call_id = arr[1]["tool_calls"][0]["id"]
+        .as_str()
+        .expect("synthesized id");
+    assert_eq!(call_id, "call_1_0");
+    assert_eq!(arr[2]["tool_call_id"].as_str(), Some(call_id));
+}
+
+#[test]
+fn normalize_tool_role_same_name_parallel_calls_pair_in_order() {
+    let msgs = vec![
+        json!({
+            "role": "assistant",
+            "tool_calls": [
+                {"function": {"name": "get_weather", "arguments": {"city": "London"}}},
+                {"function": {"name": "get_weather", "arguments": {"city": "Paris"}}}
+            ]
+        }),
+        json!({"role": "tool", "tool_name": "get_weather", "content": "15°C"}),
+        json!({"role": "tool", "tool_name": "get_weather", "content": "18°C"}),
+    ];
+    let out = normalize_chat_messages(&msgs, None);
+    let arr = out.as_array().unwrap();
+    assert_eq!(arr[1]["tool_call_id"], "call_0_0");
+    assert_eq!(arr[2]["tool_call_id"], "call_0_1");
+}
+
+#[test]
+fn normalize_tool_role_ids_stay_distinct_across_turns() {
+    let call = json!({"function": {"name": "lookup", "arguments": {}}});
+    let msgs = vec![
+        json!({"role": "assistant", "tool_calls": [call.clone()]}),
+        json!({"role": "tool", "tool_name": "lookup", "content": "a"}),
+        json!({"role": "assistant", "tool_calls": [call]}),
+        json!({"role": "tool", "tool_name": "lookup", "content": "b"}),
+    ];
+    let out = normalize_chat_messages(&msgs, None);
+    let arr = out.as_array().unwrap();
+    assert_eq!(arr[1]["tool_call_id"], arr[0]["tool_calls"][0]["id"]);
+    assert_eq!(arr[3]["tool_call_id"], arr[2]["tool_calls"][0]["id"]);
+    assert_ne!(arr[1]["tool_call_id"], arr[3]["tool_call_id"]);
+}
+
+#[test]
+fn normalize_tool_role_without_name_takes_next_call() {
+    let msgs = vec![
+        json!({
+            "role": "assistant",
+            "tool_calls": [{"id": "call_a", "function": {"name": "search", "arguments": {}}}]
+        }),
+        json!({"role": "tool", "content": "results"}),
+    ];
+    let out = normalize_chat_messages(&msgs, None);
+    let tool_msg = &out.as_array().unwrap()[1];
+    assert_eq!(tool_msg["tool_call_id"], "call_a");
+    assert_eq!(tool_msg["name"], "search");
+}
+
+#[test]
+fn normalize_tool_role_keeps_explicit_tool_call_id() {
+    let msgs = vec![
+        json!({
+            "role": "assistant",
+            "tool_calls": [
+                {"id": "call_a", "function": {"name": "f", "arguments": {}}},
+                {"id": "call_b", "function": {"name": "f", "arguments": {}}}
+            ]
+        }),
+        json!({"role": "tool", "tool_name": "f", "tool_call_id": "call_b", "content": "b"}),
+        json!({"role": "tool", "tool_name": "f", "content": "a"}),
+    ];
+    let out = normalize_chat_messages(&msgs, None);
+    let arr = out.as_array().unwrap();
+    assert_eq!(arr[1]["tool_call_id"], "call_b");
+    assert_eq!(arr[2]["tool_call_id"], "call_a");
+}
+
 // =========================================================================
 // GAP B — inbound assistant tool_calls with object arguments
 // =========================================================================
@@ -1446,3 +1707,84 @@ fn normalize_non_tool_messages_pass_through_unmodified() {
     assert_eq!(arr[1], json!({"role": "assistant", "content": "hi there"}));
     assert_eq!(arr[2], json!({"role": "system", "content": "be helpful"}));
 }
+
+#[test]
+fn apply_reasoning_mode_strip_removes_chat_thinking() {
+    let mut resp = ResponseTransformer::convert_to_ollama_chat(
+        &lm_chat_response("answer", Some("hmm")),
+        "m",
+        1,
+        Instant::now(),
+    );
+    ResponseTransformer::apply_reasoning_mode(&mut resp, ReasoningMode::Strip);
+    assert_eq!(resp["message"]["content"], "answer");
+    assert!(resp["message"].get("thinking").is_none());
+}
+
+#[test]
+fn apply_reasoning_mode_merge_prepends_chat_thinking() {
+    let mut resp = ResponseTransformer::convert_to_ollama_chat(
+        &lm_chat_response("answer", Some("hmm ")),
+        "m",
+        1,
+        Instant::now(),
+    );
+    ResponseTransformer::apply_reasoning_mode(&mut resp, ReasoningMode::Merge);
+    assert_eq!(resp["message"]["content"], "hmm answer");
+    assert!(resp["message"].get("thinking").is_none());
+}
+
+#[test]
+fn apply_reasoning_mode_merge_prepends_generate_thinking() {
+    let mut resp = json!({ "response": "answer", "thinking": "hmm ", "done": true });
+    ResponseTransformer::apply_reasoning_mode(&mut resp, ReasoningMode::Merge);
+    assert_eq!(resp["response"], "hmm answer");
+    assert!(resp.get("thinking").is_none());
+}
+
+#[test]
+fn apply_reasoning_mode_separate_is_noop() {
+    let mut resp = json!({ "response": "answer", "thinking": "hmm", "done": true });
+    let before = resp.clone();
+    ResponseTransformer::apply_reasoning_mode(&mut resp, ReasoningMode::Separate);
+    assert_eq!(resp, before);
+}
+
+#[test]
+fn chat_logprobs_are_unwrapped_from_content() {
+    let logprobs = json!({ "content": [{ "token": "a", "logprob": -0.5, "top_logprobs": [] }] });
+    assert_eq!(
+        to_ollama_logprobs(&logprobs),
+        Some(json!([{ "token": "a", "logprob": -0.5, "top_logprobs": [] }]))
+    );
+    assert_eq!(to_ollama_logprobs(&json!({ "content": [] })), None);
+    assert_eq!(to_ollama_logprobs(&json!({ "content": null })), None);
+    assert_eq!(to_ollama_logprobs(&Value::Null), None);
+}
+
+#[test]
+fn legacy_completion_logprobs_are_zipped_into_items() {
+    let logprobs = json!({
+        "tokens": ["Hi", "!"],
+        "token_logprobs": [-0.1, -0.9],
+        "top_logprobs": [{ "Hi": -0.1, "Hey": -2.5 }, { "!": -0.9 }]
+    });
+    let items = to_ollama_logprobs(&logprobs).unwrap();
+    assert_eq!(items[0]["token"], "Hi");
+    assert_eq!(items[1]["logprob"], -0.9);
+    let alternatives = items[0]["top_logprobs"].as_array().unwrap();
+    assert_eq!(alternatives.len(), 2);
+    assert!(alternatives.contains(&json!({ "token": "Hey", "logprob": -2.5 })));
+}
+
+#[test]
+fn attach_proxy_stats_copies_the_stats_block_verbatim() {
+    let lm = json!({"stats": {"tokens_per_second": 42.5, "draft_model": "tiny"}});
+    let mut resp = json!({"done": true});
+    ResponseTransformer::attach_proxy_stats(&mut resp, &lm);
+    assert_eq!(resp["proxy_stats"], lm["stats"]);
+
+    let mut resp = json!({"done": true});
+    ResponseTransformer::attach_proxy_stats(&mut resp, &json!({"choices": []}));
+    assert!(resp.get("proxy_stats").is_none());
+}
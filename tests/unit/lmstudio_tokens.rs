@@ -0,0 +1,32 @@
+use super::*;
+
+#[test]
+fn count_tokens_falls_back_to_estimate_when_disabled() {
+    // The unit-test runtime config leaves `accurate_tokens` off.
+    let text = "fn main() { println!(\"hello\"); }";
+    assert_eq!(count_tokens(text), estimate_token_count(text));
+    assert_eq!(count_tokens(""), 0);
+}
+
+#[cfg(not(feature = "accurate-tokens"))]
+#[test]
+fn bpe_token_count_is_none_without_feature() {
+    assert!(!tokenizer_compiled_in());
+    assert_eq!(bpe_token_count("hello world"), None);
+}
+
+#[cfg(feature = "accurate-tokens")]
+#[test]
+fn bpe_token_count_differs_from_estimate_for_known_strings() {
+    assert!(tokenizer_compiled_in());
+    // cl100k_base: "hello world" is two tokens; the estimate rounds 11/4 up to 3.
+    assert_eq!(bpe_token_count("hello world"), Some(2));
+    assert_eq!(estimate_token_count("hello world"), 3);
+
+    // Non-Latin text is several bytes per char, so the byte-length estimate
+    // overshoots badly while the BPE count stays near one token per char.
+    let japanese = "こんにちは世界";
+    let bpe = bpe_token_count(japanese).unwrap();
+    let estimate = estimate_token_count(japanese);
+    assert!(bpe < estimate, "bpe={bpe} estimate={estimate}");
+}
@@ -0,0 +1,146 @@
+use super::*;
+
+// --- accept_request_id ---
+
+#[test]
+fn client_request_id_is_honored() {
+    assert_eq!(accept_request_id(Some("trace-42")), "trace-42");
+}
+
+#[test]
+fn missing_or_unusable_request_id_is_replaced() {
+    let too_long = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+    for incoming in [None, Some(""), Some("has space"), Some(too_long.as_str())] {
+        let id = accept_request_id(incoming);
+        assert_eq!(id.len(), 8, "{incoming:?} -> {id}");
+        assert!(id.bytes().all(|b| b.is_ascii_hexdigit()), "{id}");
+    }
+}
+
+#[test]
+fn generated_request_ids_differ() {
+    assert_ne!(accept_request_id(None), accept_request_id(None));
+}
+
+// --- task-local scope ---
+
+#[tokio::test]
+async fn request_id_is_visible_inside_scope_only() {
+    assert_eq!(current_request_id(), None);
+    let seen = with_request_id("abc".to_string(), async { current_request_id() }).await;
+    assert_eq!(seen.as_deref(), Some("abc"));
+    assert_eq!(current_request_id(), None);
+}
+
+#[tokio::test]
+async fn spawned_tasks_inherit_the_request_id() {
+    let seen = with_request_id("abc".to_string(), async {
+        spawn_with_request_id(async { current_request_id() })
+            .await
+            .unwrap()
+    })
+    .await;
+    assert_eq!(seen.as_deref(), Some("abc"));
+}
+
+// --- level_label ---
+
+#[test]
+fn plain_labels_carry_no_escape_codes() {
+    for level in [log::Level::Error, log::Level::Info, log::Level::Trace] {
+        assert!(!level_label(level, false).contains('\x1b'));
+        assert!(level_label(level, true).contains('\x1b'));
+    }
+}
+
+// --- RotatingFile ---
+
+#[test]
+fn log_file_rotates_between_lines_and_keeps_n_copies() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("proxy.log");
+    let mut file = RotatingFile::open(&path, 10, 2).unwrap();
+
+    for line in ["first line\n", "second\n", "third line\n", "fourth line\n"] {
+        // Written in two pieces, as fern does, to prove lines stay whole.
+        let (head, tail) = line.split_at(3);
+        file.write_all(head.as_bytes()).unwrap();
+        file.write_all(tail.as_bytes()).unwrap();
+    }
+    file.flush().unwrap();
+
+    let read = |p: &Path| std::fs::read_to_string(p).unwrap();
+    assert_eq!(read(&path), "fourth line\n");
+    assert_eq!(
+        read(&dir.path().join("proxy.log.1")),
+        "second\nthird line\n"
+    );
+    assert_eq!(read(&dir.path().join("proxy.log.2")), "first line\n");
+    assert!(!dir.path().join("proxy.log.3").exists());
+}
+
+#[test]
+fn log_file_without_copies_is_truncated() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("proxy.log");
+    let mut file = RotatingFile::open(&path, 4, 0).unwrap();
+    file.write_all(b"one\ntwo\n").unwrap();
+    file.write_all(b"three\n").unwrap();
+    file.flush().unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "three\n");
+    assert!(!dir.path().join("proxy.log.1").exists());
+}
+
+#[test]
+fn log_file_appends_and_counts_existing_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("proxy.log");
+    std::fs::write(&path, "earlier run\n").unwrap();
+
+    let mut file = RotatingFile::open(&path, 1024, 1).unwrap();
+    file.write_all(b"this run\n").unwrap();
+    file.flush().unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "earlier run\nthis run\n"
+    );
+}
+
+#[test]
+fn log_file_reopens_its_path_after_reopen_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("proxy.log");
+    let moved = dir.path().join("proxy.log.moved");
+    let mut file = RotatingFile::open(&path, 1024, 1).unwrap();
+    file.write_all(b"before\n").unwrap();
+
+    // What logrotate does before sending SIGHUP.
+    std::fs::rename(&path, &moved).unwrap();
+    reopen_log_files();
+    file.write_all(b"after\n").unwrap();
+    file.flush().unwrap();
+
+    assert_eq!(std::fs::read_to_string(&moved).unwrap(), "before\n");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "after\n");
+}
+
+// --- AccessLog ---
+
+#[test]
+fn access_log_buffers_until_flushed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let log = AccessLog::open(&path, 1024 * 1024, 1).unwrap();
+    log.record("abc123 GET /api/tags 200 3ms");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+
+    log.flush();
+    let text = std::fs::read_to_string(&path).unwrap();
+    let (stamp, rest) = text.split_once(' ').unwrap();
+    assert!(
+        chrono::DateTime::parse_from_rfc3339(stamp).is_ok(),
+        "{text}"
+    );
+    assert_eq!(rest, "abc123 GET /api/tags 200 3ms\n");
+}
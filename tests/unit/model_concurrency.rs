@@ -0,0 +1,72 @@
+use super::*;
+
+#[tokio::test]
+async fn unlimited_never_rejects_and_counts_in_flight() {
+    let limiter = ModelConcurrency::unlimited();
+    let a = limiter.acquire("m").await.unwrap();
+    let b = limiter.acquire("m").await.unwrap();
+    assert_eq!(limiter.snapshot()["models"]["m"]["in_flight"], json!(2));
+    drop((a, b));
+    assert_eq!(limiter.snapshot()["models"], json!({}));
+}
+
+#[tokio::test]
+async fn over_limit_without_wait_is_429_with_retry_hint() {
+    let limiter = ModelConcurrency::new(Some(1), Duration::ZERO);
+    let _held = limiter.acquire("m").await.unwrap();
+    let err = limiter
+        .acquire("m")
+        .await
+        .err()
+        .expect("second request rejected");
+    assert_eq!(err.status_code, 429);
+    assert_eq!(err.retry_after_seconds, Some(1));
+}
+
+#[tokio::test]
+async fn limits_are_per_model() {
+    let limiter = ModelConcurrency::new(Some(1), Duration::ZERO);
+    let _a = limiter.acquire("a").await.unwrap();
+    assert!(limiter.acquire("b").await.is_ok());
+}
+
+#[tokio::test]
+async fn queued_request_gets_slot_when_released() {
+    let limiter = ModelConcurrency::new(Some(1), Duration::from_secs(5));
+    let held = limiter.acquire("m").await.unwrap();
+
+    let waiter = {
+        let limiter = limiter.clone();
+        tokio::spawn(async move { limiter.acquire("m").await.map(|_| ()) })
+    };
+    // Let the waiter register before releasing.
+    for _ in 0..50 {
+        if limiter.snapshot()["models"]["m"]["queued"] == json!(1) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(limiter.snapshot()["models"]["m"]["queued"], json!(1));
+
+    drop(held);
+    assert!(waiter.await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn queued_request_times_out_with_429() {
+    let limiter = ModelConcurrency::new(Some(1), Duration::from_millis(50));
+    let _held = limiter.acquire("m").await.unwrap();
+    let err = limiter.acquire("m").await.err().expect("times out");
+    assert_eq!(err.status_code, 429);
+    assert_eq!(limiter.snapshot()["models"]["m"]["queued"], json!(0));
+}
+
+#[tokio::test]
+async fn attached_permit_is_released_when_body_is_dropped() {
+    let limiter = ModelConcurrency::new(Some(1), Duration::ZERO);
+    let permit = limiter.acquire("m").await.unwrap();
+    let response = permit.attach(Response::new(Body::from("hello")));
+    assert!(limiter.acquire("m").await.is_err(), "slot held by body");
+    drop(response);
+    assert!(limiter.acquire("m").await.is_ok());
+}
@@ -0,0 +1,53 @@
+use super::*;
+
+fn filter(allow: &[&str], block: &[&str]) -> ModelFilter {
+    let owned = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    ModelFilter::new(&owned(allow), &owned(block))
+}
+
+#[test]
+fn glob_match_wildcards() {
+    assert!(glob_match("qwen*", "qwen2.5-7b-instruct"));
+    assert!(glob_match("*embed*", "text-embedding-nomic"));
+    assert!(glob_match("llama3.?-8b", "llama3.1-8b"));
+    assert!(glob_match("*", ""));
+    assert!(!glob_match("qwen*", "llama3"));
+    assert!(!glob_match("llama3.?-8b", "llama3.10-8b"));
+    assert!(glob_match("a*b*c", "axxbyyc"));
+    assert!(!glob_match("a*b*c", "axxbyy"));
+}
+
+#[test]
+fn empty_filter_shows_everything() {
+    let f = ModelFilter::default();
+    assert!(!f.is_active());
+    assert!(f.is_visible("anything"));
+}
+
+#[test]
+fn allowlist_hides_non_matching_models() {
+    let f = filter(&["qwen*", "llama3*"], &[]);
+    assert!(f.is_visible("qwen2.5-7b"));
+    assert!(f.is_visible("LLaMA3-8B"));
+    assert!(!f.is_visible("phi-3-mini"));
+}
+
+#[test]
+fn blocklist_wins_over_allowlist() {
+    let f = filter(&["qwen*"], &["*embed*"]);
+    assert!(f.is_visible("qwen2.5-7b"));
+    assert!(!f.is_visible("qwen3-embedding-0.6b"));
+}
+
+#[test]
+fn patterns_may_name_the_latest_tag() {
+    let f = filter(&[], &["phi-3-mini:latest"]);
+    assert!(!f.is_visible("phi-3-mini"));
+    assert!(f.is_visible("phi-3-mini-4k"));
+}
+
+#[test]
+fn blank_patterns_are_ignored() {
+    let f = filter(&["  "], &[""]);
+    assert!(!f.is_active());
+}
@@ -5,10 +5,18 @@ fn mv(id: &str, loaded: bool) -> ModelMatchView {
         id: id.to_string(),
         arch: String::new(),
         model_type: "llm".to_string(),
+        quantization: "Q4_K_M".to_string(),
         is_loaded: loaded,
     }
 }
 
+fn mvq(id: &str, quant: &str, loaded: bool) -> ModelMatchView {
+    ModelMatchView {
+        quantization: quant.to_string(),
+        ..mv(id, loaded)
+    }
+}
+
 #[test]
 fn exact_match_wins_over_substring() {
     let models = vec![
@@ -70,3 +78,93 @@ fn results_are_stable_across_input_order_permutations() {
         "result must be deterministic regardless of input order"
     );
 }
+
+#[test]
+fn explain_matches_orders_scores_descending() {
+    let models = vec![
+        mv("llama3.2-3b-instruct", true),
+        mv("granite-embedding-278m", false),
+        mv("granite-3.1-8b-instruct", true),
+    ];
+    let candidates = explain_matches("granite", &models);
+    assert_eq!(candidates.len(), 3);
+    assert!(
+        candidates.windows(2).all(|w| w[0].score >= w[1].score),
+        "{candidates:?}"
+    );
+    assert_eq!(candidates[0].id, "granite-3.1-8b-instruct");
+    assert_eq!(candidates[2].stage, MatchStage::Rejected);
+}
+
+#[test]
+fn explain_matches_top_candidate_agrees_with_scored_pick() {
+    let models = vec![mv("phi-3-mini", false), mv("qwen2.5-coder-7b", true)];
+    let picked = find_best_match("qwen coder", &models).expect("should match");
+    let candidates = explain_matches("qwen coder", &models);
+    assert_eq!(candidates[0].id, picked.id);
+    assert_eq!(candidates[0].stage, MatchStage::Scored);
+}
+
+#[test]
+fn explain_matches_labels_exact_and_substring_stages() {
+    let models = vec![mv("qwen2-7b", false), mv("qwen2-7b-chat", false)];
+    let candidates = explain_matches("qwen2-7b", &models);
+    let stage_of = |id: &str| candidates.iter().find(|c| c.id == id).unwrap().stage;
+    assert_eq!(stage_of("qwen2-7b"), MatchStage::Exact);
+    assert_eq!(stage_of("qwen2-7b-chat"), MatchStage::Substring);
+}
+
+#[test]
+fn quant_hint_picks_matching_quant_among_scored_candidates() {
+    let models = vec![
+        mvq("meta-llama-3-8b-instruct", "Q8_0", true),
+        mvq("meta-llama-3-8b-instruct@q4_k_m", "Q4_K_M", true),
+    ];
+    let picked = find_best_match("llama:Q4_K_M", &models).expect("should match");
+    assert_eq!(picked.id, "meta-llama-3-8b-instruct@q4_k_m");
+    let picked = find_best_match("llama:q8_0", &models).expect("should match");
+    assert_eq!(picked.id, "meta-llama-3-8b-instruct");
+}
+
+#[test]
+fn quant_hint_breaks_substring_ties_before_loaded_state() {
+    let models = vec![
+        mvq("qwen2-7b-instruct", "Q8_0", true),
+        mvq("qwen2-7b-instruct-gguf", "Q4_K_M", false),
+    ];
+    let picked = find_best_match("qwen2-7b-instruct:q4_k_m", &models).expect("should match");
+    assert_eq!(picked.id, "qwen2-7b-instruct-gguf");
+    // Without a hint the loaded, shorter id still wins.
+    let picked = find_best_match("qwen2-7b-instruct", &models).expect("should match");
+    assert_eq!(picked.id, "qwen2-7b-instruct");
+}
+
+#[test]
+fn quant_hint_does_not_rescue_an_unrelated_model() {
+    let models = vec![mvq("phi-3-mini", "Q4_K_M", true)];
+    assert!(find_best_match("llama3:Q4_K_M", &models).is_none());
+}
+
+#[test]
+fn full_query_exact_match_wins_over_quant_hint() {
+    let models = vec![
+        mvq("llama3:q4_0", "Q8_0", false),
+        mvq("llama3", "Q4_0", true),
+    ];
+    let picked = find_best_match("llama3:q4_0", &models).expect("should match");
+    assert_eq!(picked.id, "llama3:q4_0");
+}
+
+#[test]
+fn explain_matches_reports_quant_bonus() {
+    let models = vec![
+        mvq("meta-llama-3-8b-instruct", "Q8_0", true),
+        mvq("meta-llama-3-8b-instruct@q4_k_m", "Q4_K_M", true),
+    ];
+    let candidates = explain_matches("llama:Q4_K_M", &models);
+    assert_eq!(candidates[0].id, "meta-llama-3-8b-instruct@q4_k_m");
+    assert!(
+        candidates[0].score > candidates[1].score + 40,
+        "{candidates:?}"
+    );
+}
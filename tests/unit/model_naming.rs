@@ -182,3 +182,44 @@ fn extract_required_model_name_error_message_contains_missing_model() {
         err.message
     );
 }
+
+// ─── split_quant_hint ────────────────────────────────────────────────────────
+
+#[test]
+fn split_quant_hint_whole_tag() {
+    assert_eq!(
+        split_quant_hint("llama3:Q4_K_M"),
+        ("llama3", Some("Q4_K_M"))
+    );
+    assert_eq!(
+        split_quant_hint("llama3:iq3_xs"),
+        ("llama3", Some("iq3_xs"))
+    );
+    assert_eq!(split_quant_hint("llama3:f16"), ("llama3", Some("f16")));
+    assert_eq!(
+        split_quant_hint("mlx/qwen:4bit"),
+        ("mlx/qwen", Some("4bit"))
+    );
+}
+
+#[test]
+fn split_quant_hint_trailing_tag_segment() {
+    assert_eq!(
+        split_quant_hint("llama3:8b-instruct-q8_0"),
+        ("llama3:8b-instruct", Some("q8_0"))
+    );
+}
+
+#[test]
+fn split_quant_hint_ignores_non_quant_tags() {
+    assert_eq!(split_quant_hint("llama3.1:8b"), ("llama3.1:8b", None));
+    assert_eq!(
+        split_quant_hint("llama3:instruct"),
+        ("llama3:instruct", None)
+    );
+    assert_eq!(split_quant_hint("qwen:q"), ("qwen:q", None));
+    assert_eq!(
+        split_quant_hint("meta-llama-3-8b"),
+        ("meta-llama-3-8b", None)
+    );
+}
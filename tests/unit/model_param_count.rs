@@ -43,3 +43,36 @@ fn bare_number_treated_as_count() {
     // LM Studio sometimes already gives the raw count.
     assert_eq!(parse_parameter_count("1234567"), Some(1_234_567));
 }
+
+#[test]
+fn moe_shorthand_multiplies_experts() {
+    assert_eq!(parse_parameter_count("8x7B"), Some(56_000_000_000));
+    assert_eq!(parse_parameter_count("8x"), None);
+}
+
+#[test]
+fn size_from_id_reads_whole_tokens() {
+    assert_eq!(
+        parameter_size_from_id("mixtral-8x7b").as_deref(),
+        Some("8x7B")
+    );
+    assert_eq!(
+        parameter_size_from_id("Meta-Llama-3.1-8B-Instruct").as_deref(),
+        Some("8B")
+    );
+    assert_eq!(
+        parameter_size_from_id("qwen2.5-1.5b").as_deref(),
+        Some("1.5B")
+    );
+    assert_eq!(parameter_size_from_id("llama3:70b").as_deref(), Some("70B"));
+    assert_eq!(
+        parameter_size_from_id("smollm-135m").as_deref(),
+        Some("135M")
+    );
+    assert_eq!(
+        parameter_size_from_id("tinyllama-500m").as_deref(),
+        Some("0.5B")
+    );
+    assert_eq!(parameter_size_from_id("phi-3-mini-4k"), None);
+    assert_eq!(parameter_size_from_id("nomic-embed-text"), None);
+}
@@ -25,6 +25,7 @@ fn mi(id: &str, loaded: bool) -> ModelInfo {
         loaded_flash_attention: None,
         loaded_eval_batch_size: None,
         loaded_parallel: None,
+        loaded_instances: Vec::new(),
     }
 }
 
@@ -56,6 +57,25 @@ fn resolve_match_exact_match_case_insensitive() {
     assert_eq!(result.id, "Meta-Llama-3-8B-Instruct");
 }
 
+// ─── resolve_exact: --resolution exact ────────────────────────────────────────
+
+#[test]
+fn resolve_exact_accepts_case_insensitive_id() {
+    let models = vec![mi("Meta-Llama-3-8B-Instruct", false)];
+    let result =
+        ModelResolver::resolve_exact("meta-llama-3-8b-instruct", &models).expect("should match");
+    assert_eq!(result.id, "Meta-Llama-3-8B-Instruct");
+}
+
+#[test]
+fn resolve_exact_rejects_close_but_not_equal_name() {
+    let models = vec![mi("llama3-8b-instruct", true), mi("llama3-8b-q8", false)];
+    assert!(ModelResolver::resolve_exact("llama3", &models).is_none());
+    assert!(ModelResolver::resolve_exact("llama3-8b", &models).is_none());
+    // The fuzzy matcher would have picked something for the same query.
+    assert!(ModelResolver::resolve_match("llama3-8b", &models).is_some());
+}
+
 // ─── resolve_match: ollama shorthand → lmstudio id ───────────────────────────
 
 #[test]
@@ -150,3 +170,222 @@ fn resolve_match_result_is_stable_across_input_order() {
         "result must be deterministic regardless of input ordering"
     );
 }
+
+// ─── suggest_models ─────────────────────────────────────────────────────────
+
+#[test]
+fn suggest_models_ranks_closest_first_and_caps_at_three() {
+    let models = vec![
+        mi("meta-llama-3.1-8b-instruct", false),
+        mi("llama-guard-3-8b", false),
+        mi("codellama-7b", false),
+        mi("llama-3.2-1b", false),
+        mi("qwen2.5-7b", false),
+    ];
+    let suggestions = ModelResolver::suggest_models("llama3", &models);
+    assert_eq!(suggestions.len(), 3, "{suggestions:?}");
+    assert!(!suggestions.contains(&"qwen2.5-7b".to_string()));
+}
+
+#[test]
+fn suggest_models_skips_models_sharing_nothing_with_the_query() {
+    let models = vec![mi("qwen2.5-7b", true), mi("phi-4", false)];
+    assert!(ModelResolver::suggest_models("mixtral", &models).is_empty());
+}
+
+// ─── explain_match: the same rules as resolution ─────────────────────────────
+
+#[tokio::test]
+async fn explain_match_marks_the_pinned_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let pins = Arc::new(ModelPinStore::load(dir.path().join("pins.json")).unwrap());
+    pins.set("qwen3", "qwen3-8b-mlx").await.unwrap();
+    let resolver = ModelResolver::new(
+        "http://127.0.0.1:1".to_string(),
+        Cache::builder().max_capacity(16).build(),
+    )
+    .with_pins(pins);
+    let models = vec![mi("qwen3-8b", true), mi("qwen3-8b-mlx", false)];
+
+    let candidates = resolver.explain_match("qwen3", &models).await;
+    let stage_of = |id: &str| candidates.iter().find(|c| c.id == id).unwrap().stage;
+    assert_eq!(stage_of("qwen3-8b-mlx"), MatchStage::Pinned);
+    assert_eq!(stage_of("qwen3-8b"), MatchStage::Substring);
+}
+
+#[tokio::test]
+async fn explain_match_rejects_fuzzy_candidates_in_exact_mode() {
+    let resolver = ModelResolver::new(
+        "http://127.0.0.1:1".to_string(),
+        Cache::builder().max_capacity(16).build(),
+    )
+    .with_resolution_mode(ResolutionMode::Exact);
+    let models = vec![mi("qwen3-8b", true), mi("qwen3", false)];
+
+    let candidates = resolver.explain_match("qwen3", &models).await;
+    let stage_of = |id: &str| candidates.iter().find(|c| c.id == id).unwrap().stage;
+    assert_eq!(stage_of("qwen3"), MatchStage::Exact);
+    assert_eq!(stage_of("qwen3-8b"), MatchStage::Rejected);
+}
+
+// ─── cached resolutions ───────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn cached_resolutions_are_listed_and_cleared() {
+    let resolver = ModelResolver::new(
+        "http://127.0.0.1:1".to_string(),
+        Cache::builder().max_capacity(16).build(),
+    );
+    resolver
+        .cache
+        .insert("qwen3".to_string(), "qwen3-8b".to_string())
+        .await;
+    resolver
+        .cache
+        .insert("llama3".to_string(), "meta-llama-3-8b".to_string())
+        .await;
+
+    assert_eq!(
+        resolver.cached_resolutions(),
+        vec![
+            ("llama3".to_string(), "meta-llama-3-8b".to_string()),
+            ("qwen3".to_string(), "qwen3-8b".to_string()),
+        ]
+    );
+    assert!(resolver.has_cached_target("qwen3-8b"));
+    assert!(!resolver.has_cached_target("qwen3"));
+
+    resolver.invalidate_all().await;
+    assert!(resolver.cached_resolutions().is_empty());
+}
+
+// ─── resolve_and_fetch: one model-list retrieval ─────────────────────────────
+
+async fn mock_native_models(keys: &[&str]) -> wiremock::MockServer {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    let models: Vec<Value> = keys
+        .iter()
+        .map(|key| {
+            serde_json::json!({
+                "key": key,
+                "type": "llm",
+                "publisher": "test",
+                "max_context_length": 4096,
+                "loaded_instances": []
+            })
+        })
+        .collect();
+    Mock::given(method("GET"))
+        .and(path(LM_STUDIO_NATIVE_MODELS))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "models": models
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn models_fetch_count(server: &wiremock::MockServer) -> usize {
+    server.received_requests().await.unwrap_or_default().len()
+}
+
+#[tokio::test]
+async fn resolve_and_fetch_cold_fetches_the_model_list_once() {
+    let server = mock_native_models(&["qwen3-8b", "meta-llama-3-8b"]).await;
+    let resolver = ModelResolver::new(server.uri(), Cache::builder().max_capacity(16).build());
+    let client = reqwest::Client::new();
+
+    let (id, info) = resolver
+        .resolve_and_fetch("qwen3-8b:latest", &client, CancellationToken::new())
+        .await
+        .expect("resolves");
+    assert_eq!(id, "qwen3-8b");
+    assert_eq!(info.expect("catalog entry").id, "qwen3-8b");
+    assert_eq!(models_fetch_count(&server).await, 1);
+    assert_eq!(
+        resolver.cached_resolutions(),
+        vec![("qwen3-8b".to_string(), "qwen3-8b".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn resolve_and_fetch_cached_name_reads_the_models_cache() {
+    let server = mock_native_models(&["qwen3-8b"]).await;
+    let resolver = ModelResolver::new(server.uri(), Cache::builder().max_capacity(16).build())
+        .with_models_cache_ttl(Duration::from_secs(60));
+    let client = reqwest::Client::new();
+
+    for _ in 0..2 {
+        let (id, info) = resolver
+            .resolve_and_fetch("qwen3-8b", &client, CancellationToken::new())
+            .await
+            .expect("resolves");
+        assert_eq!(id, "qwen3-8b");
+        assert!(info.is_some());
+    }
+    // A name-cache miss resolves against a fresh list; the hit after it reads
+    // the list through the models cache, which fetches it once.
+    assert_eq!(models_fetch_count(&server).await, 2);
+    resolver
+        .resolve_and_fetch("qwen3-8b", &client, CancellationToken::new())
+        .await
+        .expect("resolves");
+    assert_eq!(models_fetch_count(&server).await, 2);
+}
+
+#[tokio::test]
+async fn resolve_and_fetch_reports_unknown_names_like_resolve_model_name() {
+    let server = mock_native_models(&["qwen3-8b"]).await;
+    let resolver = ModelResolver::new(server.uri(), Cache::builder().max_capacity(16).build())
+        .with_resolution_mode(ResolutionMode::Exact);
+    let client = reqwest::Client::new();
+
+    let fetched = resolver
+        .resolve_and_fetch("qwen3", &client, CancellationToken::new())
+        .await
+        .expect_err("exact mode rejects a partial name");
+    let resolved = resolver
+        .resolve_model_name("qwen3", &client, CancellationToken::new())
+        .await
+        .expect_err("exact mode rejects a partial name");
+    assert_eq!(fetched.status_code, 404);
+    assert_eq!(fetched.message, resolved.message);
+}
+
+#[tokio::test]
+async fn one_cancelled_caller_does_not_fail_the_shared_models_fetch() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(LM_STUDIO_NATIVE_MODELS))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(serde_json::json!({ "models": [] }))
+                .set_delay(Duration::from_millis(200)),
+        )
+        .mount(&server)
+        .await;
+    let resolver = ModelResolver::new(server.uri(), Cache::builder().max_capacity(16).build())
+        .with_models_cache_ttl(Duration::from_secs(60));
+    let client = reqwest::Client::new();
+
+    let leaving = CancellationToken::new();
+    let (first, second) = tokio::join!(resolver.get_all_models(&client, leaving.clone()), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        leaving.cancel();
+        resolver
+            .get_all_models(&client, CancellationToken::new())
+            .await
+    });
+    assert!(first.expect_err("its own token fired").is_cancelled());
+    assert!(
+        second
+            .expect("the other waiter still gets the list")
+            .is_empty()
+    );
+}
@@ -0,0 +1,136 @@
+use super::*;
+use crate::model::ModelInfo;
+use crate::model::types::{NativeModelData, NativeQuantization};
+
+/// The tail of an `/api/v0/chat/completions` response.
+fn served_response() -> Value {
+    json!({
+        "model": "qwen2.5-7b-instruct",
+        "choices": [],
+        "model_info": {
+            "arch": "qwen2",
+            "quant": "Q4_K_M",
+            "format": "gguf",
+            "context_length": 32768
+        },
+        "runtime": {
+            "name": "llama.cpp-linux-x86_64-nvidia-cuda-avx2",
+            "version": "1.21.0",
+            "supported_formats": ["gguf"]
+        }
+    })
+}
+
+fn catalog_model(arch: Option<&str>, max_context_length: u64) -> ModelInfo {
+    ModelInfo::from_native_data(&NativeModelData {
+        key: "qwen2.5-7b-instruct".to_string(),
+        model_type: "llm".to_string(),
+        publisher: "qwen".to_string(),
+        architecture: arch.map(str::to_string),
+        format: Some("gguf".to_string()),
+        quantization: Some(NativeQuantization {
+            name: Some("Q4_K_M".to_string()),
+            bits_per_weight: Some(4.5),
+        }),
+        max_context_length,
+        loaded_instances: vec![],
+        capabilities: None,
+        size_bytes: None,
+        params_string: None,
+        display_name: None,
+        description: None,
+    })
+}
+
+#[test]
+fn reads_model_info_and_runtime_blocks() {
+    let info = RuntimeInfo::from_response(&served_response()).expect("blocks present");
+    assert_eq!(
+        info,
+        RuntimeInfo {
+            arch: Some("qwen2".to_string()),
+            quant: Some("Q4_K_M".to_string()),
+            format: Some("gguf".to_string()),
+            context_length: Some(32768),
+            runtime_name: Some("llama.cpp-linux-x86_64-nvidia-cuda-avx2".to_string()),
+            runtime_version: Some("1.21.0".to_string()),
+        }
+    );
+    assert!(RuntimeInfo::from_response(&json!({ "model": "m", "choices": [] })).is_none());
+}
+
+#[test]
+fn show_takes_served_architecture_and_file_type() {
+    let info = RuntimeInfo::from_response(&served_response()).unwrap();
+    let mut show = catalog_model(None, 131072).to_show_response(None, false);
+    assert_eq!(show["model_info"]["general.architecture"], "unknown");
+
+    info.apply_to_show(&mut show, false);
+    let mi = &show["model_info"];
+    assert_eq!(mi["general.architecture"], "qwen2");
+    assert_eq!(mi["general.file_type"], 15);
+    // The catalog maximum is kept, re-keyed under the real architecture.
+    assert_eq!(mi["qwen2.context_length"], 131072);
+    assert!(mi.get("unknown.context_length").is_none());
+    assert!(mi.get("lmstudio.runtime").is_none());
+    assert_eq!(show["details"]["family"], "qwen2");
+    assert_eq!(show["details"]["families"], json!(["qwen2"]));
+}
+
+#[test]
+fn show_falls_back_to_served_context_when_catalog_has_none() {
+    let info = RuntimeInfo::from_response(&served_response()).unwrap();
+    let mut show = catalog_model(Some("qwen2"), 0).to_show_response(None, false);
+    info.apply_to_show(&mut show, false);
+    assert_eq!(show["model_info"]["qwen2.context_length"], 32768);
+}
+
+#[test]
+fn verbose_show_adds_runtime_keys() {
+    let info = RuntimeInfo::from_response(&served_response()).unwrap();
+    let mut show = catalog_model(Some("qwen2"), 131072).to_show_response(None, true);
+    info.apply_to_show(&mut show, true);
+    let mi = &show["model_info"];
+    assert_eq!(
+        mi["lmstudio.runtime"],
+        "llama.cpp-linux-x86_64-nvidia-cuda-avx2"
+    );
+    assert_eq!(mi["lmstudio.runtime_version"], "1.21.0");
+    assert_eq!(mi["lmstudio.served_context_length"], 32768);
+}
+
+#[test]
+fn gguf_file_type_maps_known_quantizations() {
+    assert_eq!(gguf_file_type("Q4_K_M"), Some(15));
+    assert_eq!(gguf_file_type("q8_0"), Some(7));
+    assert_eq!(gguf_file_type("F16"), Some(1));
+    assert_eq!(gguf_file_type("4bit"), None);
+}
+
+#[test]
+fn cache_keeps_the_last_blocks_per_model() {
+    let cache = RuntimeInfoCache::new();
+    cache.observe(
+        "qwen2.5-7b-instruct",
+        &json!({ "model": "qwen2.5-7b-instruct", "choices": [] }),
+    );
+    assert!(cache.get("qwen2.5-7b-instruct").is_none());
+
+    cache.observe("qwen2.5-7b-instruct", &served_response());
+    let mut newer = served_response();
+    newer["runtime"]["version"] = json!("1.22.0");
+    cache.observe("qwen2.5-7b-instruct", &newer);
+    let info = cache.get("qwen2.5-7b-instruct").expect("cached");
+    assert_eq!(info.runtime_version.as_deref(), Some("1.22.0"));
+    assert!(cache.get("other-model").is_none());
+}
+
+#[test]
+fn cache_files_instance_responses_under_the_model_key() {
+    let cache = RuntimeInfoCache::new();
+    let mut response = served_response();
+    response["model"] = json!("qwen2.5-7b-instruct@2");
+    cache.observe("qwen2.5-7b-instruct", &response);
+    assert!(cache.get("qwen2.5-7b-instruct").is_some());
+    assert!(cache.get("qwen2.5-7b-instruct@2").is_none());
+}
@@ -63,6 +63,8 @@ fn virt(name: &str, target_id: &str) -> VirtualModelEntry {
         name: name.to_string(),
         source_model: "src".to_string(),
         target_model_id: target_id.to_string(),
+        parent_alias: None,
+        blobs: Vec::new(),
         created_at: now,
         updated_at: now,
         metadata: Default::default(),
@@ -360,7 +362,7 @@ fn from_native_context_length_falls_back_to_max_when_instance_has_no_config() {
 #[test]
 fn tags_model_has_all_spec_keys() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     for key in [
         "name",
         "model",
@@ -402,7 +404,7 @@ fn tags_model_has_all_spec_keys() {
 #[test]
 fn tags_model_digest_is_sha256_shaped() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     let digest = v["digest"].as_str().expect("digest must be a string");
     assert_eq!(
         digest.len(),
@@ -438,8 +440,8 @@ fn ps_model_digest_is_sha256_shaped() {
 #[test]
 fn digest_is_deterministic_for_same_model() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v1 = info.to_ollama_tags_model();
-    let v2 = info.to_ollama_tags_model();
+    let v1 = info.to_ollama_tags_model(None);
+    let v2 = info.to_ollama_tags_model(None);
     assert_eq!(
         v1["digest"], v2["digest"],
         "digest must be deterministic across calls"
@@ -450,15 +452,15 @@ fn digest_is_deterministic_for_same_model() {
 fn digest_differs_for_distinct_models() {
     let a = ModelInfo::from_native_data(&native("publisher/model-a"));
     let b = ModelInfo::from_native_data(&native("publisher/model-b"));
-    let da = a.to_ollama_tags_model()["digest"].clone();
-    let db = b.to_ollama_tags_model()["digest"].clone();
+    let da = a.to_ollama_tags_model(None)["digest"].clone();
+    let db = b.to_ollama_tags_model(None)["digest"].clone();
     assert_ne!(da, db, "distinct models must produce distinct digests");
 }
 
 #[test]
 fn tags_model_details_format_mirrors_compatibility_type() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     assert_eq!(v["details"]["format"], json!(info.compatibility_type));
     assert_eq!(v["details"]["format"], json!("gguf"));
 }
@@ -466,23 +468,57 @@ fn tags_model_details_format_mirrors_compatibility_type() {
 #[test]
 fn tags_model_family_and_families_both_use_arch() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     assert_eq!(v["details"]["family"], json!("llama"));
     assert_eq!(v["details"]["families"], json!(["llama"]));
 }
 
 #[test]
 fn tags_entry_omits_modified_at_when_unknown() {
-    // LM Studio's model list has no mtime. The proxy must omit `modified_at`
-    // rather than fabricate one (real Ollama returns the model file mtime).
+    // Without a first-seen stamp the proxy omits `modified_at` rather than
+    // fabricating Utc::now() on every call.
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     assert!(
         v.get("modified_at").is_none(),
-        "tags entry must omit modified_at when no real mtime is available; got {v}"
+        "tags entry must omit modified_at without a stamp; got {v}"
     );
 }
 
+#[test]
+fn tags_entry_reports_given_modified_at() {
+    let info = ModelInfo::from_native_data(&native("publisher/model"));
+    let seen = chrono::DateTime::parse_from_rfc3339("2025-03-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+    let v = info.to_ollama_tags_model(Some(seen));
+    assert_eq!(v["modified_at"], json!(seen.to_rfc3339()));
+}
+
+#[test]
+fn digest_changes_with_quantization_publisher_and_size() {
+    let base = ModelInfo::from_native_data(&native("publisher/model"));
+    let digest = base.digest();
+
+    let mut requantized = base.clone();
+    requantized.quantization = "Q8_0".to_string();
+    assert_ne!(requantized.digest(), digest);
+
+    let mut republished = base.clone();
+    republished.publisher = "someone-else".to_string();
+    assert_ne!(republished.digest(), digest);
+
+    let mut redownloaded = base.clone();
+    redownloaded.size_bytes = Some(1234);
+    assert_ne!(redownloaded.digest(), digest);
+
+    // loading state and display metadata don't make it a different model
+    let mut loaded = base.clone();
+    loaded.is_loaded = true;
+    loaded.display_name = Some("Model".to_string());
+    assert_eq!(loaded.digest(), digest);
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // ModelInfo::to_ollama_ps_model
 // ════════════════════════════════════════════════════════════════════════════
@@ -490,7 +526,7 @@ fn tags_entry_omits_modified_at_when_unknown() {
 #[test]
 fn ps_model_includes_tags_fields_plus_expires_and_vram() {
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_ps_model(None);
+    let v = info.to_ollama_ps_model(Some(Utc::now().timestamp() + 300));
     for key in [
         "name",
         "model",
@@ -509,13 +545,24 @@ fn ps_model_includes_tags_fields_plus_expires_and_vram() {
 }
 
 #[test]
-fn ps_model_expires_at_is_rfc3339_in_future() {
+fn ps_model_expires_at_reflects_tracked_deadline() {
     use chrono::DateTime;
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_ps_model(None);
+    let deadline = Utc::now().timestamp() + 600;
+    let v = info.to_ollama_ps_model(Some(deadline));
     let s = v["expires_at"].as_str().expect("expires_at must be string");
     let ts = DateTime::parse_from_rfc3339(s).expect("must parse as RFC3339");
-    assert!(ts > Utc::now(), "expires_at must be in the future, got {s}");
+    assert_eq!(ts.timestamp(), deadline);
+}
+
+#[test]
+fn ps_model_expires_at_omitted_when_unknown() {
+    let info = ModelInfo::from_native_data(&native("publisher/model"));
+    let v = info.to_ollama_ps_model(None);
+    assert!(
+        v.get("expires_at").is_none(),
+        "untracked model must not fabricate expires_at: {v}"
+    );
 }
 
 // ════════════════════════════════════════════════════════════════════════════
@@ -695,6 +742,22 @@ fn parse_parameters_id_heuristic_common_sizes() {
     );
 }
 
+#[test]
+fn parse_parameters_id_heuristic_moe_and_eleven_b() {
+    let info = ModelInfo::from_native_data(&native("mistralai/mixtral-8x7b-instruct-v0.1"));
+    assert_eq!(info.parse_parameters().size_string, "8x7B");
+
+    let info = ModelInfo::from_native_data(&native("solar-11b-instruct"));
+    assert_eq!(info.parse_parameters().size_string, "11B");
+}
+
+#[test]
+fn parse_parameters_id_without_size_token_is_unknown() {
+    // A version (2.5), a context size (32k) and a quant (q4_k_m) are not sizes.
+    let info = ModelInfo::from_native_data(&native("qwen2.5-coder-32k@q4_k_m"));
+    assert_eq!(info.parse_parameters().size_string, "unknown");
+}
+
 #[test]
 fn parse_parameters_unknown_yields_unknown_literal() {
     let info = ModelInfo::from_native_data(&native("mystery/shape"));
@@ -713,6 +776,14 @@ fn calculate_size_uses_size_bytes_when_present() {
     assert_eq!(info.calculate_estimated_size(), 123_456_789);
 }
 
+#[test]
+fn calculate_size_treats_zero_size_bytes_as_unreported() {
+    let mut n = native("llama-7b");
+    n.size_bytes = Some(0);
+    let info = ModelInfo::from_native_data(&n);
+    assert_eq!(info.calculate_estimated_size(), 3_850_000_000);
+}
+
 #[test]
 fn calculate_size_scales_with_quant_for_7b() {
     let n = native("llama-7b");
@@ -861,6 +932,67 @@ fn merge_with_virtuals_skips_unknown_targets() {
     assert_eq!(out[0]["n"], "a/one:latest");
 }
 
+#[test]
+fn merge_with_virtuals_lists_base_models_then_aliases_by_name() {
+    let base = vec![
+        ModelInfo::from_native_data(&native("a/one")),
+        ModelInfo::from_native_data(&native("a/two")),
+    ];
+    let virtuals = vec![
+        virt("zeta:latest", "a/one"),
+        virt("alpha:latest", "a/two"),
+        virt("mid:latest", "a/one"),
+    ];
+    let out = ModelInfo::merge_with_virtuals(&base, &virtuals, |m| json!({ "n": m.ollama_name }));
+    let names: Vec<&str> = out.iter().map(|v| v["n"].as_str().unwrap()).collect();
+    assert_eq!(
+        names,
+        [
+            "a/one:latest",
+            "a/two:latest",
+            "alpha:latest",
+            "mid:latest",
+            "zeta:latest"
+        ]
+    );
+}
+
+#[test]
+fn merge_with_virtuals_or_orphans_keeps_orphans_in_name_order() {
+    let base = vec![ModelInfo::from_native_data(&native("a/one"))];
+    let virtuals = vec![virt("zeta:latest", "a/one"), virt("beta:latest", "ghost")];
+    let out = ModelInfo::merge_with_virtuals_or_orphans(
+        &base,
+        &virtuals,
+        &ModelFilter::default(),
+        |m| json!({ "n": m.ollama_name }),
+        |entry| Some(json!({ "n": entry.name, "orphan": true })),
+    );
+    let names: Vec<&str> = out.iter().map(|v| v["n"].as_str().unwrap()).collect();
+    assert_eq!(names, ["a/one:latest", "beta:latest", "zeta:latest"]);
+    assert_eq!(out[1]["orphan"], true);
+}
+
+#[test]
+fn merge_with_virtuals_or_orphans_hides_filtered_base_but_keeps_its_alias() {
+    let base = vec![
+        ModelInfo::from_native_data(&native("a/one")),
+        ModelInfo::from_native_data(&native("a/embed")),
+    ];
+    let virtuals = vec![virt("embedder:latest", "a/embed")];
+    let filter = ModelFilter::new(&[], &["*embed*".to_string()]);
+    let out = ModelInfo::merge_with_virtuals_or_orphans(
+        &base,
+        &virtuals,
+        &filter,
+        |m| json!({ "n": m.ollama_name, "id": m.id }),
+        |_| None,
+    );
+    let names: Vec<&str> = out.iter().map(|v| v["n"].as_str().unwrap()).collect();
+    assert_eq!(names, ["a/one:latest", "embedder:latest"]);
+    assert_eq!(out[1]["id"], "a/embed");
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // T5 — /api/show no longer fabricates parameters/template; verbose contract;
 //       details.context_length is stable across load states.
@@ -1027,6 +1159,34 @@ fn is_thinking_model_uses_keyword_when_no_backend_capabilities() {
     assert!(info.is_thinking_model());
 }
 
+#[test]
+fn is_chat_tuned_for_instruct_llm() {
+    let info = ModelInfo::from_native_data(&native("llama3.2-3b-instruct"));
+    assert!(info.is_chat_tuned());
+}
+
+#[test]
+fn is_chat_tuned_false_for_base_model_token() {
+    for key in ["qwen2.5-7b-base", "mistral/base_7b", "llama3:base"] {
+        let info = ModelInfo::from_native_data(&native(key));
+        assert!(
+            !info.is_chat_tuned(),
+            "{key} must be treated as a base model"
+        );
+    }
+    // `base` only counts as a whole token — not a substring of another word.
+    let info = ModelInfo::from_native_data(&native("database-coder-7b"));
+    assert!(info.is_chat_tuned());
+}
+
+#[test]
+fn is_chat_tuned_false_for_embedding_model() {
+    let mut data = native("nomic-embed-text-v1.5");
+    data.model_type = "embeddings".to_string();
+    let info = ModelInfo::from_native_data(&data);
+    assert!(!info.is_chat_tuned());
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // Item 12 — null description in native JSON deserializes to None and is omitted
 //           (no fabrication).
@@ -1148,9 +1308,52 @@ fn ps_model_details_include_empty_parent_model() {
 fn tags_model_still_omits_parent_model_after_ps_change() {
     // The ps-only parent_model injection must not leak into /api/tags.
     let info = ModelInfo::from_native_data(&native("publisher/model"));
-    let v = info.to_ollama_tags_model();
+    let v = info.to_ollama_tags_model(None);
     assert!(
         v["details"].get("parent_model").is_none(),
         "tags details must not include parent_model; got {v}"
     );
 }
+
+#[test]
+fn single_instance_keeps_its_name_in_instance_views() {
+    let mut n = native("publisher/model");
+    n.loaded_instances.push(loaded_instance(Some(8192)));
+    let views = ModelInfo::from_native_data(&n).instance_views();
+    assert_eq!(views.len(), 1);
+    assert_eq!(views[0].ollama_name, "publisher/model:latest");
+}
+
+#[test]
+fn each_loaded_instance_gets_its_own_view() {
+    let mut n = native("publisher/model");
+    n.loaded_instances.push(NativeLoadedInstance {
+        id: "publisher/model".to_string(),
+        ..loaded_instance(Some(4096))
+    });
+    n.loaded_instances.push(NativeLoadedInstance {
+        id: "publisher/model:2".to_string(),
+        ..loaded_instance(Some(32768))
+    });
+    n.loaded_instances.push(NativeLoadedInstance {
+        id: "fast".to_string(),
+        ..loaded_instance(Some(2048))
+    });
+    let info = ModelInfo::from_native_data(&n);
+    assert!(info.has_loaded_instance("publisher/model:2"));
+
+    let views = info.instance_views();
+    let names: Vec<&str> = views.iter().map(|v| v.ollama_name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "publisher/model:latest",
+            "publisher/model@publisher/model:2",
+            "publisher/model@fast:latest",
+        ]
+    );
+    assert_eq!(views[0].context_length, 4096);
+    assert_eq!(views[1].context_length, 32768);
+    assert_eq!(views[2].context_length, 2048);
+    assert!(views.iter().all(|v| v.id == "publisher/model"));
+}
@@ -0,0 +1,176 @@
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+
+fn check(status: CheckStatus, mandatory: bool) -> CheckResult {
+    CheckResult::new("c", status, mandatory, "")
+}
+
+fn config_for(lmstudio_url: &str) -> Config {
+    use clap::Parser;
+    Config::parse_from([
+        "ollama-lmstudio-proxy",
+        "--lmstudio-url",
+        lmstudio_url,
+        "--listen",
+        "127.0.0.1:0",
+    ])
+}
+
+fn native_model(key: &str, model_type: &str, loaded: bool) -> serde_json::Value {
+    let instances = if loaded {
+        json!([{ "id": key }])
+    } else {
+        json!([])
+    };
+    json!({
+        "key": key,
+        "type": model_type,
+        "publisher": "test",
+        "max_context_length": 4096,
+        "loaded_instances": instances,
+    })
+}
+
+fn status_of(report: &SelfTestReport, name: &str) -> CheckStatus {
+    report
+        .checks
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap_or_else(|| panic!("no check named {name}"))
+        .status
+}
+
+#[test]
+fn only_mandatory_failures_fail_the_report() {
+    let report = SelfTestReport {
+        checks: vec![
+            check(CheckStatus::Pass, true),
+            check(CheckStatus::Skip, true),
+            check(CheckStatus::Fail, false),
+        ],
+    };
+    assert!(report.passed());
+    assert!(
+        report
+            .render()
+            .ends_with("self-test passed: 3 checks, 1 failed\n")
+    );
+
+    let report = SelfTestReport {
+        checks: vec![check(CheckStatus::Fail, true)],
+    };
+    assert!(!report.passed());
+    assert!(report.render().starts_with("[FAIL] c: "));
+}
+
+#[test]
+fn data_dir_check_creates_blobs_and_leaves_no_probe() {
+    let dir = tempfile::tempdir().unwrap();
+    let state_dir = dir.path().join("state");
+    let result = check_data_dir(&state_dir);
+    assert_eq!(result.status, CheckStatus::Pass, "{}", result.detail);
+    let blobs = state_dir.join("blobs");
+    assert!(blobs.is_dir());
+    assert_eq!(std::fs::read_dir(blobs).unwrap().count(), 0);
+}
+
+#[test]
+fn listen_check_fails_on_a_taken_port() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = taken.local_addr().unwrap().to_string();
+    let results = check_listen(&[addr.clone(), "127.0.0.1:0".to_string()]);
+    assert_eq!(results[0].status, CheckStatus::Fail);
+    assert!(results[0].detail.contains(&addr), "{}", results[0].detail);
+    assert_eq!(results[1].status, CheckStatus::Pass);
+}
+
+#[tokio::test]
+async fn healthy_backend_passes_with_a_test_completion() {
+    let mock = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(LM_STUDIO_NATIVE_MODELS))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [
+                native_model("nomic-embed", "embeddings", true),
+                native_model("qwen3-8b", "llm", true),
+            ]
+        })))
+        .mount(&mock)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(LM_STUDIO_NATIVE_CHAT))
+        .and(wiremock::matchers::body_partial_json(
+            json!({ "model": "qwen3-8b", "max_tokens": 1 }),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "choices": [] })))
+        .expect(1)
+        .mount(&mock)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let report = run(
+        &config_for(&mock.uri()),
+        &reqwest::Client::new(),
+        dir.path(),
+        true,
+    )
+    .await;
+    assert!(report.passed(), "{}", report.render());
+    assert_eq!(status_of(&report, "test completion"), CheckStatus::Pass);
+    assert!(report.render().contains("2 models, 2 loaded"));
+}
+
+#[tokio::test]
+async fn unreachable_backend_fails_and_skips_dependent_checks() {
+    // Bind then drop: nothing listens on the port afterwards.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let dir = tempfile::tempdir().unwrap();
+    let report = run(
+        &config_for(&format!("http://127.0.0.1:{port}")),
+        &reqwest::Client::new(),
+        dir.path(),
+        true,
+    )
+    .await;
+    assert!(!report.passed());
+    assert_eq!(status_of(&report, "LM Studio reachable"), CheckStatus::Fail);
+    assert_eq!(
+        status_of(&report, "LM Studio native API"),
+        CheckStatus::Skip
+    );
+    assert_eq!(status_of(&report, "test completion"), CheckStatus::Skip);
+}
+
+#[tokio::test]
+async fn missing_native_api_fails_and_no_inference_skips_completion() {
+    let mock = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path(LM_STUDIO_NATIVE_MODELS))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&mock)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let report = run(
+        &config_for(&mock.uri()),
+        &reqwest::Client::new(),
+        dir.path(),
+        false,
+    )
+    .await;
+    assert!(!report.passed());
+    assert_eq!(status_of(&report, "LM Studio reachable"), CheckStatus::Pass);
+    assert_eq!(
+        status_of(&report, "LM Studio native API"),
+        CheckStatus::Fail
+    );
+    assert!(report.render().contains("0.3.6+ required"));
+    assert_eq!(status_of(&report, "test completion"), CheckStatus::Skip);
+}
@@ -0,0 +1,15 @@
+use super::*;
+
+#[test]
+fn pid_file_holds_this_process_and_is_removed_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("run").join("proxy.pid");
+    let pid_file = PidFile::create(&path).unwrap();
+    assert_eq!(pid_file.path(), path);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        format!("{}\n", std::process::id())
+    );
+    drop(pid_file);
+    assert!(!path.exists());
+}
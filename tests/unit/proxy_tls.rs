@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use super::*;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/tls")
+        .join(name)
+}
+
+#[test]
+fn loads_pem_certificate_and_key() {
+    assert!(load_tls_acceptor(&fixture("cert.pem"), &fixture("key.pem")).is_ok());
+}
+
+#[test]
+fn missing_certificate_is_reported_with_its_path() {
+    let err = load_tls_acceptor(&fixture("missing.pem"), &fixture("key.pem"))
+        .err()
+        .expect("missing cert must fail");
+    assert!(err.contains("missing.pem"), "{err}");
+}
+
+#[test]
+fn key_file_without_a_key_is_rejected() {
+    let err = load_tls_acceptor(&fixture("cert.pem"), &fixture("cert.pem"))
+        .err()
+        .expect("a certificate is not a private key");
+    assert!(err.contains("TLS key"), "{err}");
+}
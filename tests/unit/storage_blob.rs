@@ -98,3 +98,175 @@ fn blob_digest_path_traversal_is_rejected() {
     let err = check_digest(&digest).expect_err("path traversal must be rejected");
     assert_eq!(err.status_code, 400);
 }
+
+fn sha256_of(data: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(data)))
+}
+
+/// A stored blob reports its byte length; an absent one reports `None`.
+#[tokio::test]
+async fn blob_size_reflects_saved_bytes() {
+    let store = fresh_blob_store();
+    let data = b"blob payload";
+    let digest = sha256_of(data);
+    assert_eq!(store.size(&digest).await.unwrap(), None);
+
+    let stream =
+        futures_util::stream::iter(vec![Ok::<_, axum::Error>(bytes::Bytes::from_static(data))]);
+    store.save_stream(&digest, stream).await.unwrap();
+    assert_eq!(store.size(&digest).await.unwrap(), Some(data.len() as u64));
+}
+
+/// A stream that fails mid-upload leaves neither the blob nor its temp file.
+#[tokio::test]
+async fn blob_stream_error_removes_temp_file() {
+    let store = fresh_blob_store();
+    let digest = sha256_of(b"never completes");
+    let stream = futures_util::stream::iter(vec![
+        Ok(bytes::Bytes::from_static(b"never ")),
+        Err(axum::Error::new(std::io::Error::other("client went away"))),
+    ]);
+
+    let err = store.save_stream(&digest, stream).await.unwrap_err();
+    assert_eq!(err.status_code, 500);
+    assert_eq!(store.size(&digest).await.unwrap(), None);
+    let leftovers = std::fs::read_dir(store.base_dir.join("sha256"))
+        .map(|entries| entries.count())
+        .unwrap_or(0);
+    assert_eq!(leftovers, 0, "temp file must be removed");
+}
+
+/// A body whose hash differs from the declared digest is rejected with 400.
+#[tokio::test]
+async fn blob_digest_mismatch_is_rejected() {
+    let store = fresh_blob_store();
+    let digest = sha256_of(b"declared");
+    let stream = futures_util::stream::iter(vec![Ok::<_, axum::Error>(bytes::Bytes::from_static(
+        b"actual",
+    ))]);
+    let err = store.save_stream(&digest, stream).await.unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert_eq!(store.size(&digest).await.unwrap(), None);
+}
+
+async fn save(store: &BlobStore, data: &'static [u8]) -> String {
+    let digest = sha256_of(data);
+    let stream =
+        futures_util::stream::iter(vec![Ok::<_, axum::Error>(bytes::Bytes::from_static(data))]);
+    store.save_stream(&digest, stream).await.unwrap();
+    digest
+}
+
+/// Sizes and last use survive a restart through `index.json`, and a blob
+/// file the index does not know about is picked up from disk.
+#[tokio::test]
+async fn blob_index_persists_and_adopts_unindexed_files() {
+    let store = fresh_blob_store();
+    let kept = save(&store, b"indexed").await;
+    let stray = sha256_of(b"stray");
+    std::fs::write(
+        store
+            .base_dir
+            .join("sha256")
+            .join(&stray["sha256:".len()..]),
+        b"stray",
+    )
+    .unwrap();
+
+    let reopened = BlobStore::new(&store.base_dir).unwrap();
+    let listed: Vec<_> = reopened
+        .list()
+        .await
+        .into_iter()
+        .map(|blob| (blob.digest, blob.size))
+        .collect();
+    let mut expected = vec![(kept, 7), (stray, 5)];
+    expected.sort();
+    assert_eq!(listed, expected);
+    assert_eq!(reopened.total_bytes().await, 12);
+}
+
+/// A read only moves `last_access` in memory; `flush` writes it out.
+#[tokio::test]
+async fn blob_touch_is_persisted_on_flush_only() {
+    let store = fresh_blob_store();
+    let digest = save(&store, b"read-me").await;
+    let stored = std::fs::read(store.index_path()).unwrap();
+
+    store.touch(&digest).await;
+    assert_eq!(std::fs::read(store.index_path()).unwrap(), stored);
+
+    store.flush().await;
+    let reopened = BlobStore::new(&store.base_dir).unwrap();
+    let touched_at = store.list().await[0].last_access;
+    assert_eq!(reopened.list().await[0].last_access, touched_at);
+}
+
+/// Over the limit, the least recently used blobs go first; the upload that
+/// triggered eviction and referenced blobs are never evicted.
+#[tokio::test]
+async fn blob_eviction_drops_least_recently_used_unreferenced_blobs() {
+    let store = fresh_blob_store().with_max_total_bytes(15);
+    let referenced = save(&store, b"ref-1").await;
+    let oldest = save(&store, b"old-1").await;
+    let touched = save(&store, b"tch-1").await;
+    let newest = save(&store, b"new-1").await;
+    store.touch(&touched).await;
+    let protected = HashSet::from([referenced.clone()]);
+
+    let evicted = store.evict_over_limit(&newest, &protected).await;
+    assert_eq!(evicted, vec![oldest.clone()]);
+    assert_eq!(store.size(&oldest).await.unwrap(), None);
+    assert_eq!(store.total_bytes().await, 15);
+
+    // `touched` was used after `newest` was stored, so `newest` goes next.
+    let extra = save(&store, b"ext-1").await;
+    let evicted = store.evict_over_limit(&extra, &protected).await;
+    assert_eq!(evicted, vec![newest]);
+    for digest in [&referenced, &touched, &extra] {
+        assert!(store.exists(digest).await.unwrap(), "{digest} must be kept");
+    }
+}
+
+/// Without a limit nothing is evicted.
+#[tokio::test]
+async fn blob_eviction_is_off_without_a_limit() {
+    let store = fresh_blob_store();
+    let first = save(&store, b"first").await;
+    let second = save(&store, b"second").await;
+    assert!(
+        store
+            .evict_over_limit(&second, &HashSet::new())
+            .await
+            .is_empty()
+    );
+    assert!(store.exists(&first).await.unwrap());
+}
+
+/// An upload bigger than the whole limit evicts nothing: the store stays
+/// over the limit either way.
+#[tokio::test]
+async fn blob_eviction_skips_an_upload_over_the_whole_limit() {
+    let store = fresh_blob_store().with_max_total_bytes(8);
+    let small = save(&store, b"tiny").await;
+    let huge = save(&store, b"far too large").await;
+    assert!(
+        store
+            .evict_over_limit(&huge, &HashSet::new())
+            .await
+            .is_empty()
+    );
+    assert!(store.exists(&small).await.unwrap());
+}
+
+/// Removing a blob deletes the file and its index entry; a second removal
+/// is a 404.
+#[tokio::test]
+async fn blob_remove_deletes_file_and_index_entry() {
+    let store = fresh_blob_store();
+    let digest = save(&store, b"doomed").await;
+    assert_eq!(store.remove(&digest).await.unwrap(), 6);
+    assert_eq!(store.size(&digest).await.unwrap(), None);
+    assert!(store.list().await.is_empty());
+    assert_eq!(store.remove(&digest).await.unwrap_err().status_code, 404);
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+fn upstream(vectors: &[[f64; 2]]) -> Value {
+    json!({
+        "object": "list",
+        "model": "embed-model",
+        "data": vectors
+            .iter()
+            .enumerate()
+            .map(|(i, v)| json!({"object": "embedding", "index": i, "embedding": v}))
+            .collect::<Vec<_>>(),
+        "usage": {"prompt_tokens": 4, "total_tokens": 4}
+    })
+}
+
+fn vectors(response: &Value) -> Vec<Value> {
+    response["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["embedding"].clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn only_novel_inputs_go_upstream_and_order_is_kept() {
+    let cache = EmbeddingCache::new(100, Duration::from_secs(60));
+
+    let first = cache
+        .lookup("embed-model", None, &json!(["a", "b"]))
+        .await
+        .unwrap();
+    assert_eq!(first.missing_input(), Some(json!(["a", "b"])));
+    cache
+        .complete(first, Some(upstream(&[[1.0, 0.0], [0.0, 1.0]])))
+        .await
+        .unwrap();
+
+    let second = cache
+        .lookup("embed-model", None, &json!(["b", "c", "a"]))
+        .await
+        .unwrap();
+    assert_eq!(second.missing_input(), Some(json!(["c"])));
+    let merged = cache
+        .complete(second, Some(upstream(&[[0.5, 0.5]])))
+        .await
+        .unwrap();
+    assert_eq!(
+        vectors(&merged),
+        vec![json!([0.0, 1.0]), json!([0.5, 0.5]), json!([1.0, 0.0])]
+    );
+    let indexes: Vec<_> = merged["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["index"].as_u64().unwrap())
+        .collect();
+    assert_eq!(indexes, vec![0, 1, 2]);
+}
+
+#[tokio::test]
+async fn fully_cached_request_needs_no_upstream_call() {
+    let cache = EmbeddingCache::new(100, Duration::from_secs(60));
+    let lookup = cache.lookup("m", None, &json!("hello")).await.unwrap();
+    assert_eq!(lookup.missing_input(), Some(json!("hello")));
+    cache
+        .complete(lookup, Some(upstream(&[[1.0, 2.0]])))
+        .await
+        .unwrap();
+
+    let lookup = cache.lookup("m", None, &json!("hello")).await.unwrap();
+    assert_eq!(lookup.missing_input(), None);
+    let merged = cache.complete(lookup, None).await.unwrap();
+    assert_eq!(vectors(&merged), vec![json!([1.0, 2.0])]);
+    assert_eq!(merged["model"], "m");
+    assert_eq!(merged["usage"]["prompt_tokens"], 0);
+}
+
+#[tokio::test]
+async fn model_and_dimensions_separate_entries() {
+    let cache = EmbeddingCache::new(100, Duration::from_secs(60));
+    let lookup = cache.lookup("m", None, &json!("x")).await.unwrap();
+    cache
+        .complete(lookup, Some(upstream(&[[1.0, 2.0]])))
+        .await
+        .unwrap();
+
+    for (model, dimensions) in [("other", None), ("m", Some(256))] {
+        let lookup = cache.lookup(model, dimensions, &json!("x")).await.unwrap();
+        assert!(lookup.missing_input().is_some(), "{model} {dimensions:?}");
+    }
+}
+
+#[tokio::test]
+async fn non_string_inputs_bypass_the_cache() {
+    let cache = EmbeddingCache::new(100, Duration::from_secs(60));
+    assert!(cache.lookup("m", None, &json!([1, 2])).await.is_none());
+    assert!(cache.lookup("m", None, &json!({"a": 1})).await.is_none());
+}
+
+#[tokio::test]
+async fn short_upstream_response_is_a_bad_gateway() {
+    let cache = EmbeddingCache::new(100, Duration::from_secs(60));
+    let lookup = cache.lookup("m", None, &json!(["a", "b"])).await.unwrap();
+    let err = cache
+        .complete(lookup, Some(upstream(&[[1.0, 0.0]])))
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code, 502);
+}
@@ -0,0 +1,95 @@
+use super::*;
+
+fn exchange(prompt: &str, response: &str) -> GenerateExchange {
+    GenerateExchange {
+        prompt: prompt.to_string(),
+        response: response.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn remembered_context_round_trips() {
+    let store = GenerateContextStore::new(Duration::from_secs(60));
+
+    let first = store.remember(&[], "Hi", "Hello!").await;
+    let history = store.history(Some(&first)).await;
+    assert_eq!(*history, vec![exchange("Hi", "Hello!")]);
+
+    let second = store.remember(&history, "And you?", "Fine.").await;
+    assert_ne!(first, second, "each turn gets its own key");
+    let history = store.history(Some(&second)).await;
+    assert_eq!(
+        *history,
+        vec![exchange("Hi", "Hello!"), exchange("And you?", "Fine.")]
+    );
+}
+
+#[tokio::test]
+async fn foreign_or_missing_context_starts_fresh() {
+    let store = GenerateContextStore::new(Duration::from_secs(60));
+    store.remember(&[], "Hi", "Hello!").await;
+
+    assert!(store.history(None).await.is_empty());
+    assert!(store.history(Some(&json!([1, 2, 3]))).await.is_empty());
+    assert!(
+        store
+            .history(Some(&json!([1, 2, 3, 4, 5])))
+            .await
+            .is_empty()
+    );
+    assert!(store.history(Some(&json!("nope"))).await.is_empty());
+    assert!(
+        store
+            .history(Some(&json!([CONTEXT_MARKER, 0, 0])))
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn context_expires_after_ttl() {
+    let store = GenerateContextStore::new(Duration::from_millis(50));
+    let context = store.remember(&[], "Hi", "Hello!").await;
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(store.history(Some(&context)).await.is_empty());
+}
+
+#[tokio::test]
+async fn history_is_capped_to_the_newest_exchanges() {
+    let store = GenerateContextStore::new(Duration::from_secs(60));
+    let long: Vec<_> = (0..MAX_EXCHANGES + 5)
+        .map(|i| exchange(&format!("p{i}"), "r"))
+        .collect();
+
+    let context = store.remember(&long, "last", "r").await;
+    let history = store.history(Some(&context)).await;
+    assert_eq!(history.len(), MAX_EXCHANGES);
+    assert_eq!(history.last().unwrap().prompt, "last");
+    assert_eq!(history[0].prompt, format!("p{}", 6));
+}
+
+#[test]
+fn context_key_encoding_round_trips() {
+    for key in [0, 1, u64::from(u32::MAX) + 1, u64::MAX] {
+        assert_eq!(decode_context(&encode_context(key)), Some(key));
+    }
+}
+
+#[test]
+fn prompt_with_history_replays_exchanges_verbatim() {
+    let history = [exchange("Q1 ", "A1\n"), exchange("Q2 ", "A2\n")];
+    assert_eq!(prompt_with_history(&history, "Q3 "), "Q1 A1\nQ2 A2\nQ3 ");
+    assert_eq!(prompt_with_history(&[], "Q"), "Q");
+}
+
+#[test]
+fn history_messages_alternate_user_and_assistant() {
+    let messages = history_messages(&[exchange("Hi", "Hello!")]);
+    assert_eq!(
+        messages,
+        vec![
+            json!({"role": "user", "content": "Hi"}),
+            json!({"role": "assistant", "content": "Hello!"}),
+        ]
+    );
+}
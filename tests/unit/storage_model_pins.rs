@@ -0,0 +1,51 @@
+use super::*;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn pins_survive_a_reload_and_ignore_case_and_latest() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("model_pins.json");
+
+    let store = ModelPinStore::load(&path).unwrap();
+    store
+        .set("Llama3.1:latest", "llama-3.1-8b@q8_0")
+        .await
+        .unwrap();
+    assert_eq!(
+        store.target_for("llama3.1").await.as_deref(),
+        Some("llama-3.1-8b@q8_0")
+    );
+
+    let reloaded = ModelPinStore::load(&path).unwrap();
+    assert_eq!(
+        reloaded.target_for("LLAMA3.1:latest").await.as_deref(),
+        Some("llama-3.1-8b@q8_0")
+    );
+    let pins = reloaded.list().await;
+    assert_eq!(pins.len(), 1);
+    assert_eq!(pins[0].name, "Llama3.1");
+}
+
+#[tokio::test]
+async fn setting_again_replaces_and_remove_reports_missing() {
+    let dir = TempDir::new().unwrap();
+    let store = ModelPinStore::load(dir.path().join("model_pins.json")).unwrap();
+
+    store.set("llama3.1", "a").await.unwrap();
+    store.set("llama3.1", "b").await.unwrap();
+    assert_eq!(store.target_for("llama3.1").await.as_deref(), Some("b"));
+
+    assert_eq!(store.remove("llama3.1").await.unwrap().target, "b");
+    assert!(store.target_for("llama3.1").await.is_none());
+    assert_eq!(store.remove("llama3.1").await.unwrap_err().status_code, 404);
+}
+
+#[tokio::test]
+async fn corrupt_file_starts_empty() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("model_pins.json");
+    std::fs::write(&path, b"{not json").unwrap();
+
+    let store = ModelPinStore::load(&path).unwrap();
+    assert!(store.list().await.is_empty());
+}
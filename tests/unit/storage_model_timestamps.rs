@@ -0,0 +1,93 @@
+use super::*;
+use crate::model::types::{NativeModelData, NativeQuantization};
+use tempfile::TempDir;
+
+fn model(key: &str, quantization: &str) -> ModelInfo {
+    ModelInfo::from_native_data(&NativeModelData {
+        key: key.to_string(),
+        model_type: "llm".to_string(),
+        publisher: "publisher".to_string(),
+        architecture: Some("llama".to_string()),
+        format: Some("gguf".to_string()),
+        quantization: Some(NativeQuantization {
+            name: Some(quantization.to_string()),
+            bits_per_weight: None,
+        }),
+        max_context_length: 4096,
+        loaded_instances: vec![],
+        capabilities: None,
+        size_bytes: Some(1_000),
+        params_string: None,
+        display_name: None,
+        description: None,
+    })
+}
+
+#[tokio::test]
+async fn first_seen_is_stable_across_calls_and_restarts() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("model_timestamps.json");
+    let models = vec![model("a", "Q4_K_M"), model("b", "Q4_K_M")];
+
+    let store = ModelTimestampStore::load(&path).unwrap();
+    let first = store.observe(&models).await;
+    assert_eq!(first.len(), 2);
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    assert_eq!(store.observe(&models).await, first);
+
+    let reloaded = ModelTimestampStore::load(&path).unwrap();
+    assert_eq!(reloaded.observe(&models).await, first);
+}
+
+#[tokio::test]
+async fn changed_digest_restamps_only_that_model() {
+    let dir = TempDir::new().unwrap();
+    let store = ModelTimestampStore::load(dir.path().join("ts.json")).unwrap();
+
+    let before = store
+        .observe(&[model("a", "Q4_K_M"), model("b", "Q4_K_M")])
+        .await;
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    let after = store
+        .observe(&[model("a", "Q8_0"), model("b", "Q4_K_M")])
+        .await;
+
+    assert!(
+        after["a"] > before["a"],
+        "re-quantized model must be restamped"
+    );
+    assert_eq!(after["b"], before["b"]);
+}
+
+#[tokio::test]
+async fn models_no_longer_listed_are_forgotten() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ts.json");
+    let store = ModelTimestampStore::load(&path).unwrap();
+
+    let first = store
+        .observe(&[model("a", "Q4_K_M"), model("b", "Q4_K_M")])
+        .await;
+    let stamps = store.observe(&[model("a", "Q4_K_M")]).await;
+    assert_eq!(stamps.len(), 1);
+    assert_eq!(stamps["a"], first["a"]);
+
+    let saved: HashMap<String, SeenModel> =
+        serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert!(saved.contains_key("a"));
+    assert!(!saved.contains_key("b"), "removed model must be pruned");
+}
+
+#[tokio::test]
+async fn corrupt_file_starts_fresh() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("ts.json");
+    std::fs::write(&path, b"not json").unwrap();
+
+    let store = ModelTimestampStore::load(&path).unwrap();
+    let stamps = store.observe(&[model("a", "Q4_K_M")]).await;
+    assert!(stamps.contains_key("a"));
+    let saved: HashMap<String, SeenModel> =
+        serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(saved["a"].first_seen, stamps["a"]);
+}
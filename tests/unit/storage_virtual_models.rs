@@ -71,6 +71,31 @@ fn build_metadata_messages_non_array_not_set() {
     assert!(meta.messages.is_none());
 }
 
+#[test]
+fn build_metadata_parameters_merge_over_base() {
+    let base = VirtualModelMetadata {
+        parameters: Some(json!({"temperature": 0.2, "top_k": 10})),
+        ..VirtualModelMetadata::default()
+    };
+    let body = json!({"parameters": {"temperature": 0.9}});
+    let meta = VirtualModelStore::build_metadata_from_request(&body, Some(base));
+    assert_eq!(
+        meta.parameters,
+        Some(json!({"temperature": 0.9, "top_k": 10}))
+    );
+}
+
+#[test]
+fn build_metadata_non_object_parameters_replace_base() {
+    let base = VirtualModelMetadata {
+        parameters: Some(json!({"temperature": 0.2})),
+        ..VirtualModelMetadata::default()
+    };
+    let body = json!({"parameters": "temperature 0.5"});
+    let meta = VirtualModelStore::build_metadata_from_request(&body, Some(base));
+    assert_eq!(meta.parameters, Some(json!("temperature 0.5")));
+}
+
 // --- VirtualModelEntry fields ---
 
 #[test]
@@ -80,6 +105,8 @@ fn entry_fields_are_set_correctly() {
         name: "mymodel".to_string(),
         source_model: "llama3".to_string(),
         target_model_id: "llama-3-8b".to_string(),
+        parent_alias: None,
+        blobs: Vec::new(),
         created_at: now,
         updated_at: now,
         metadata: default_metadata(),
@@ -346,6 +373,48 @@ async fn persistence_round_trip() {
     assert_eq!(entry.target_model_id, "tgt-id");
 }
 
+#[tokio::test]
+async fn corrupt_primary_recovers_from_backup() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("vm.json");
+
+    {
+        let store = VirtualModelStore::load(path.clone()).unwrap();
+        for alias in ["first", "second"] {
+            store
+                .create_alias(
+                    alias,
+                    "src".to_string(),
+                    "tgt-id".to_string(),
+                    default_metadata(),
+                )
+                .await
+                .unwrap();
+        }
+    }
+    assert!(dir.path().join("vm.json.bak").exists());
+
+    // A write cut short by a crash.
+    std::fs::write(&path, b"{\"first\": {\"name\": ").unwrap();
+
+    let store = VirtualModelStore::load(path.clone()).unwrap();
+    assert!(store.get("first").await.is_some(), "backup holds `first`");
+    assert!(
+        serde_json::from_slice::<serde_json::Value>(&std::fs::read(&path).unwrap()).is_ok(),
+        "the recovered entries are written back to the primary"
+    );
+}
+
+#[test]
+fn corrupt_primary_without_backup_starts_empty() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("vm.json");
+    std::fs::write(&path, b"not json").unwrap();
+
+    let store = VirtualModelStore::load(path).unwrap();
+    assert!(store.entries.try_read().unwrap().is_empty());
+}
+
 // --- canonical name normalization ---
 
 #[tokio::test]
@@ -369,3 +438,198 @@ async fn get_strips_latest_tag_to_match_stored_alias() {
         .expect("':latest' must canonicalize to bare name");
     assert_eq!(entry.name, "llama3");
 }
+
+// --- parent alias chains ---
+
+#[tokio::test]
+async fn child_alias_resolves_through_repointed_parent() {
+    let dir = TempDir::new().unwrap();
+    let store = make_store(&dir);
+
+    store
+        .upsert_alias("parent", "m1".into(), "model-1".into(), default_metadata())
+        .await
+        .unwrap();
+    store
+        .upsert_child_alias(
+            "child",
+            "parent".into(),
+            "model-1".into(),
+            default_metadata(),
+        )
+        .await
+        .unwrap();
+    store
+        .upsert_alias("parent", "m2".into(), "model-2".into(), default_metadata())
+        .await
+        .unwrap();
+
+    let (entry, target) = store.resolve("child").await.unwrap().unwrap();
+    assert_eq!(entry.parent_alias.as_deref(), Some("parent"));
+    assert_eq!(entry.target_model_id, "model-1", "snapshot is kept as-is");
+    assert_eq!(target, "model-2");
+
+    let listed = store.list_resolved().await;
+    let child = listed.iter().find(|e| e.name == "child").unwrap();
+    assert_eq!(child.target_model_id, "model-2");
+}
+
+#[tokio::test]
+async fn child_alias_falls_back_to_snapshot_when_parent_deleted() {
+    let dir = TempDir::new().unwrap();
+    let store = make_store(&dir);
+
+    store
+        .upsert_alias("parent", "m1".into(), "model-1".into(), default_metadata())
+        .await
+        .unwrap();
+    store
+        .upsert_child_alias(
+            "child",
+            "parent".into(),
+            "model-1".into(),
+            default_metadata(),
+        )
+        .await
+        .unwrap();
+    store.delete("parent").await.unwrap();
+
+    let (_, target) = store.resolve("child").await.unwrap().unwrap();
+    assert_eq!(target, "model-1");
+}
+
+#[tokio::test]
+async fn child_alias_cannot_close_a_cycle() {
+    let dir = TempDir::new().unwrap();
+    let store = make_store(&dir);
+
+    store
+        .upsert_alias("a", "m1".into(), "model-1".into(), default_metadata())
+        .await
+        .unwrap();
+    store
+        .upsert_child_alias("b", "a".into(), "model-1".into(), default_metadata())
+        .await
+        .unwrap();
+    let err = store
+        .upsert_child_alias("a", "b".into(), "model-1".into(), default_metadata())
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code, 400);
+    assert!(err.message.contains("would loop"), "{}", err.message);
+}
+
+#[tokio::test]
+async fn resolve_rejects_a_cyclic_chain_on_disk() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("virtual_models.json");
+    let entry = |name: &str, parent: &str| {
+        json!({
+            "name": name,
+            "source_model": parent,
+            "target_model_id": "model-1",
+            "parent_alias": parent,
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "metadata": {}
+        })
+    };
+    let on_disk = json!({ "a": entry("a", "b"), "b": entry("b", "a") });
+    std::fs::write(&path, serde_json::to_vec(&on_disk).unwrap()).unwrap();
+
+    let store = VirtualModelStore::load(path).unwrap();
+    let err = store.resolve("a").await.unwrap_err();
+    assert!(err.message.contains("cyclic"), "{}", err.message);
+}
+
+#[tokio::test]
+async fn entries_without_parent_field_load_as_roots() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("virtual_models.json");
+    let on_disk = json!({
+        "legacy": {
+            "name": "legacy",
+            "source_model": "llama3",
+            "target_model_id": "llama-3-8b",
+            "created_at": "2024-01-01T00:00:00Z",
+            "updated_at": "2024-01-01T00:00:00Z",
+            "metadata": {}
+        }
+    });
+    std::fs::write(&path, serde_json::to_vec(&on_disk).unwrap()).unwrap();
+
+    let store = VirtualModelStore::load(path).unwrap();
+    let (entry, target) = store.resolve("legacy").await.unwrap().unwrap();
+    assert!(entry.parent_alias.is_none());
+    assert_eq!(target, "llama-3-8b");
+}
+
+// --- schema versioning ---
+
+#[tokio::test]
+async fn v0_store_upgrades_and_is_rewritten_as_current_version() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("vm.json");
+    // Bare alias map from before versioning: no `updated_at`, no
+    // `adapters`/`messages`, and one entry with no metadata at all.
+    let v0 = json!({
+        "old-alias": {
+            "name": "old-alias",
+            "source_model": "llama",
+            "target_model_id": "llama-3-8b",
+            "created_at": "2024-01-01T00:00:00Z",
+            "metadata": { "system_prompt": "be brief", "template": null,
+                          "parameters": null, "license": null }
+        },
+        "bare": { "target_model_id": "qwen-7b" }
+    });
+    std::fs::write(&path, serde_json::to_vec(&v0).unwrap()).unwrap();
+
+    let store = VirtualModelStore::load(path.clone()).unwrap();
+    let old = store.get("old-alias").await.expect("upgraded entry");
+    assert_eq!(old.metadata.system_prompt.as_deref(), Some("be brief"));
+    assert!(old.metadata.adapters.is_none() && old.metadata.messages.is_none());
+    assert_eq!(old.updated_at, old.created_at);
+    let bare = store.get("bare").await.expect("entry without metadata");
+    assert_eq!(bare.name, "bare");
+    assert_eq!(bare.source_model, "qwen-7b");
+
+    store
+        .create_alias(
+            "new-alias",
+            "src".to_string(),
+            "tgt-id".to_string(),
+            default_metadata(),
+        )
+        .await
+        .unwrap();
+    let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(saved["version"], STORE_VERSION);
+    assert_eq!(saved["models"].as_object().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn newer_store_version_loads_what_it_can() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("vm.json");
+    let future = json!({
+        "version": STORE_VERSION + 1,
+        "models": {
+            "alias": {
+                "name": "alias",
+                "source_model": "src",
+                "target_model_id": "tgt",
+                "created_at": "2024-01-01T00:00:00Z",
+                "updated_at": "2024-01-01T00:00:00Z",
+                "metadata": {},
+                "field_from_the_future": true
+            },
+            "garbled": 42
+        }
+    });
+    std::fs::write(&path, serde_json::to_vec(&future).unwrap()).unwrap();
+
+    let store = VirtualModelStore::load(path).unwrap();
+    assert!(store.get("alias").await.is_some());
+    assert!(store.get("garbled").await.is_none());
+}
@@ -185,6 +185,37 @@ fn delta_tool_calls_accumulated_into_state() {
     assert_eq!(args.get("tz").and_then(|v| v.as_str()), Some("UTC"));
 }
 
+#[test]
+fn split_tool_call_deltas_reassemble_into_complete_calls() {
+    // Name in the first delta, arguments spread over later ones, with a second
+    // call interleaved by index.
+    let deltas = [
+        json!({"index": 0, "id": "call_a", "type": "function", "function": {"name": "get_weather"}}),
+        json!({"index": 0, "function": {"arguments": "{\"city\""}}),
+        json!({"index": 1, "id": "call_b", "type": "function", "function": {"name": "get_time", "arguments": ""}}),
+        json!({"index": 0, "function": {"arguments": ":\"Oslo\"}"}}),
+        json!({"index": 1, "function": {"arguments": "{\"tz\":\"CET\"}"}}),
+    ];
+    let mut state = ChunkProcessingState::default();
+    for delta in deltas {
+        let choice = json!({"delta": {"tool_calls": [delta]}});
+        process_choice_delta(&choice, &mut state).expect("tool_calls delta is emitted");
+    }
+
+    let calls = state.take_tool_calls().expect("accumulated tool_calls");
+    assert_eq!(
+        calls,
+        json!([
+            {"function": {"index": 0, "name": "get_weather", "arguments": {"city": "Oslo"}}},
+            {"function": {"index": 1, "name": "get_time", "arguments": {"tz": "CET"}}}
+        ])
+    );
+    assert!(
+        state.take_tool_calls().is_none(),
+        "taking consumes the state"
+    );
+}
+
 #[test]
 fn completion_choice_text_fallback_used_when_delta_empty() {
     let choice = json!({
@@ -219,6 +250,40 @@ fn finish_reason_keeps_last_seen_non_null() {
     assert_eq!(state.finish_reason(), Some("tool_calls"));
 }
 
+#[test]
+fn completion_finish_reason_on_a_later_choice_is_captured() {
+    let mut state = ChunkProcessingState::default();
+    let text = json!({"choices": [{"text": "Hel", "finish_reason": null}]});
+    process_choice_delta(extract_first_choice(&text).unwrap(), &mut state);
+    state.update_finish_reason_from_chunk(&text);
+    assert_eq!(state.finish_reason(), None);
+
+    let last = json!({"choices": [
+        {"text": "lo", "finish_reason": null},
+        {"text": "", "finish_reason": "length"}
+    ]});
+    process_choice_delta(extract_first_choice(&last).unwrap(), &mut state);
+    state.update_finish_reason_from_chunk(&last);
+    assert_eq!(state.finish_reason(), Some("length"));
+}
+
+#[test]
+fn stats_stop_reason_fills_in_a_missing_finish_reason() {
+    let mut state = ChunkProcessingState::default();
+    state.update_finish_reason_from_chunk(&json!({
+        "choices": [{"delta": {"content": "x"}, "finish_reason": null}],
+        "stats": {"stop_reason": "maxPredictedTokensReached"}
+    }));
+    assert_eq!(state.finish_reason(), Some("length"));
+
+    let mut state = ChunkProcessingState::default();
+    state.update_finish_reason_from_chunk(&json!({
+        "choices": [{"finish_reason": "stop"}],
+        "stats": {"stop_reason": "maxPredictedTokensReached"}
+    }));
+    assert_eq!(state.finish_reason(), Some("stop"), "finish_reason wins");
+}
+
 #[test]
 fn extract_first_choice_handles_missing_or_empty() {
     assert!(extract_first_choice(&json!({})).is_none());
@@ -478,7 +543,7 @@ fn final_chunk_chat_no_done_reason_omits_field_with_timings() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(120),
-        chunk_count: 4,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: None,
         tool_calls: None,
@@ -498,7 +563,7 @@ fn final_chunk_chat_propagates_done_reason_length() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(10),
-        chunk_count: 1,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: Some("length"),
         tool_calls: None,
@@ -514,7 +579,7 @@ fn final_chunk_generate_omits_context_and_emits_timings() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(80),
-        chunk_count: 6,
+        tokens: StreamTokens::default(),
         is_chat: false,
         done_reason: None,
         tool_calls: None,
@@ -537,7 +602,7 @@ fn final_chunk_generate_propagates_done_reason_length() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(10),
-        chunk_count: 1,
+        tokens: StreamTokens::default(),
         is_chat: false,
         done_reason: Some("length"),
         tool_calls: None,
@@ -677,7 +742,7 @@ fn tool_calls_in_final_chunk_when_provided() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(50),
-        chunk_count: 3,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: Some("tool_calls"),
         tool_calls: Some(tc),
@@ -702,7 +767,7 @@ fn final_chunk_without_tool_calls_has_no_tool_calls_field() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(50),
-        chunk_count: 2,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: None,
         tool_calls: None,
@@ -824,7 +889,7 @@ fn final_chunk_translates_tool_calls_to_stop() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(10),
-        chunk_count: 1,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: Some("tool_calls"),
         tool_calls: None,
@@ -841,7 +906,7 @@ fn final_chunk_omits_done_reason_for_unknown_value() {
     let c = create_final_chunk(FinalChunkParams {
         model_name: "m",
         duration: Duration::from_millis(10),
-        chunk_count: 1,
+        tokens: StreamTokens::default(),
         is_chat: true,
         done_reason: Some("weird_value"),
         tool_calls: None,
@@ -851,3 +916,101 @@ fn final_chunk_omits_done_reason_for_unknown_value() {
         "unknown done_reason must be omitted, not lied about"
     );
 }
+
+fn reasoning_payload(content: &str, thinking: &str) -> ChoiceDeltaPayload {
+    ChoiceDeltaPayload {
+        content: content.to_string(),
+        thinking: thinking.to_string(),
+        tool_calls_delta: None,
+        logprobs: None,
+    }
+}
+
+#[test]
+fn reasoning_mode_separate_keeps_thinking_field() {
+    let mut payload = reasoning_payload("answer", "hmm");
+    payload.apply_reasoning_mode(ReasoningMode::Separate);
+    assert_eq!(payload.content, "answer");
+    assert_eq!(payload.thinking, "hmm");
+}
+
+#[test]
+fn reasoning_mode_merge_prepends_thinking_to_content() {
+    let mut payload = reasoning_payload("answer", "hmm ");
+    payload.apply_reasoning_mode(ReasoningMode::Merge);
+    assert_eq!(payload.content, "hmm answer");
+    assert!(payload.thinking.is_empty());
+}
+
+#[test]
+fn reasoning_mode_strip_empties_reasoning_only_delta() {
+    let mut payload = reasoning_payload("", "hmm");
+    payload.apply_reasoning_mode(ReasoningMode::Strip);
+    assert!(payload.is_empty());
+}
+
+#[test]
+fn delta_logprobs_are_carried_in_ollama_shape() {
+    let choice = json!({
+        "delta": { "content": "Hi" },
+        "logprobs": { "content": [{ "token": "Hi", "logprob": -0.1, "top_logprobs": [] }] }
+    });
+    let mut state = ChunkProcessingState::default();
+    let payload = process_choice_delta(&choice, &mut state).unwrap();
+    assert_eq!(
+        payload.logprobs,
+        Some(json!([{ "token": "Hi", "logprob": -0.1, "top_logprobs": [] }]))
+    );
+
+    let mut chunk = json!({ "done": false });
+    attach_logprobs(&mut chunk, payload.logprobs);
+    assert_eq!(chunk["logprobs"][0]["token"], "Hi");
+
+    let plain = choice_with_delta(Some("x"), None);
+    assert!(
+        process_choice_delta(&plain, &mut state)
+            .unwrap()
+            .logprobs
+            .is_none()
+    );
+}
+
+#[test]
+fn stream_tokens_estimate_output_from_streamed_text() {
+    let mut tokens = StreamTokens::new(250);
+    tokens.record_output("abcdefgh", "");
+    tokens.record_output("ab", "cd");
+    assert_eq!(tokens.output_estimate(), 3);
+
+    let timing = tokens.timing(Duration::from_millis(100));
+    assert_eq!(timing.prompt_eval_count, 250);
+    assert_eq!(timing.eval_count, 3);
+}
+
+#[test]
+fn stream_tokens_prefer_upstream_usage() {
+    let mut tokens = StreamTokens::new(250);
+    tokens.record_output(&"x".repeat(400), "");
+    tokens.record_usage(&json!({ "choices": [] }));
+    tokens.record_usage(&json!({ "usage": { "prompt_tokens": 231, "completion_tokens": 97 } }));
+
+    let timing = tokens.timing(Duration::from_millis(100));
+    assert_eq!(timing.prompt_eval_count, 231);
+    assert_eq!(timing.eval_count, 97);
+}
+
+#[test]
+fn final_chunk_reports_stream_token_counts() {
+    let mut tokens = StreamTokens::new(42);
+    tokens.record_output(&"x".repeat(40), "");
+    let chunk = create_final_chunk(FinalChunkParams {
+        model_name: "m",
+        duration: Duration::from_millis(100),
+        tokens,
+        is_chat: true,
+        done_reason: Some("stop"),
+        tool_calls: None,
+    });
+    assert_eq!(chunk["prompt_eval_count"], 42);
+    assert_eq!(chunk["eval_count"], 10);
+}
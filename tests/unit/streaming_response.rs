@@ -66,10 +66,7 @@ async fn ndjson_response_has_correct_content_type() {
         .get("content-type")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
-    assert!(
-        ct.contains("ndjson"),
-        "content-type must contain ndjson; got {ct:?}"
-    );
+    assert_eq!(ct, "application/x-ndjson; charset=utf-8");
 }
 
 #[tokio::test]
@@ -82,23 +79,21 @@ async fn sse_response_has_event_stream_content_type() {
         .get("content-type")
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
-    assert!(
-        ct.contains("event-stream"),
-        "content-type must be text/event-stream; got {ct:?}"
-    );
+    assert_eq!(ct, "text/event-stream; charset=utf-8");
 }
 
 #[tokio::test]
-async fn streaming_response_has_cors_header() {
+async fn streaming_response_leaves_cors_to_the_layer() {
     let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
     drop(tx);
     let response = create_streaming_response(rx, StreamContentType::Ndjson).unwrap();
-    let origin = response
-        .headers()
-        .get("access-control-allow-origin")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    assert_eq!(origin, "*", "CORS origin header must be *");
+    assert!(
+        response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none(),
+        "CORS headers come only from the --cors-origin layer"
+    );
 }
 
 #[tokio::test]
@@ -142,3 +137,50 @@ async fn ndjson_stream_response_convenience_returns_ok() {
     let result = create_ndjson_stream_response(rx, "test error message");
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn streaming_responses_disable_proxy_buffering() {
+    for content_type in [StreamContentType::Ndjson, StreamContentType::Sse] {
+        let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
+        drop(tx);
+        let response = create_streaming_response(rx, content_type).unwrap();
+        assert_eq!(response.headers()["x-accel-buffering"], "no");
+    }
+}
+
+#[test]
+fn split_ndjson_lines_keeps_each_newline_with_its_line() {
+    let lines = split_ndjson_lines(bytes::Bytes::from("{\"a\":1}\n{\"b\":2}\n{\"c\""));
+    assert_eq!(
+        lines,
+        vec![
+            bytes::Bytes::from("{\"a\":1}\n"),
+            bytes::Bytes::from("{\"b\":2}\n"),
+            bytes::Bytes::from("{\"c\""),
+        ]
+    );
+    assert_eq!(
+        split_ndjson_lines(bytes::Bytes::from("{}\n")),
+        vec![bytes::Bytes::from("{}\n")]
+    );
+    assert!(split_ndjson_lines(bytes::Bytes::new()).is_empty());
+}
+
+#[tokio::test]
+async fn ndjson_response_sends_one_frame_per_line() {
+    use http_body_util::BodyExt;
+
+    let (tx, rx) = mpsc::unbounded_channel::<Result<bytes::Bytes, std::io::Error>>();
+    tx.send(Ok(bytes::Bytes::from("{\"n\":1}\n{\"n\":2}\n")))
+        .unwrap();
+    tx.send(Ok(bytes::Bytes::from("{\"n\":3}\n"))).unwrap();
+    drop(tx);
+    let mut body = create_streaming_response(rx, StreamContentType::Ndjson)
+        .unwrap()
+        .into_body();
+    let mut frames = Vec::new();
+    while let Some(frame) = body.frame().await {
+        frames.push(frame.unwrap().into_data().unwrap());
+    }
+    assert_eq!(frames, vec!["{\"n\":1}\n", "{\"n\":2}\n", "{\"n\":3}\n"]);
+}
@@ -1,6 +1,9 @@
+use std::time::Duration;
+
 use serde_json::json;
 
-use crate::constants::{SSE_DATA_PREFIX, SSE_DONE_MESSAGE, SSE_MESSAGE_BOUNDARY};
+use super::{EventBoundary, StreamTimeouts, stream_load_duration};
+use crate::constants::{ERROR_TIMEOUT, SSE_DATA_PREFIX, SSE_DONE_MESSAGE, SSE_MESSAGE_BOUNDARY};
 use crate::streaming::chunks::{ChunkProcessingState, extract_first_choice, process_choice_delta};
 use crate::streaming::recovery::recover_json_from_chunk;
 
@@ -318,3 +321,120 @@ fn pipeline_stream_split_across_two_chunks_reassembled() {
     }
     // If the split happened after the \n\n then p1 would have one entry — also fine
 }
+
+// ─── StreamTimeouts ──────────────────────────────────────────────────────────
+
+#[test]
+fn stream_timeouts_default_preserves_sixty_second_budget() {
+    let timeouts = StreamTimeouts::default();
+    assert_eq!(timeouts.first_token, Duration::from_secs(60));
+    assert_eq!(timeouts.idle, Duration::from_secs(60));
+}
+
+#[test]
+fn stream_timeouts_use_first_token_budget_until_a_chunk_arrives() {
+    let timeouts = StreamTimeouts::from_secs(300, 20);
+    assert_eq!(timeouts.next_chunk(false), Duration::from_secs(300));
+    assert_eq!(timeouts.next_chunk(true), Duration::from_secs(20));
+    assert_eq!(timeouts.longest(), Duration::from_secs(300));
+}
+
+#[test]
+fn stream_timeouts_message_names_the_timeout_that_fired() {
+    let timeouts = StreamTimeouts::from_secs(300, 20);
+    let first = timeouts.expired_message(false);
+    assert!(first.starts_with(ERROR_TIMEOUT), "{first}");
+    assert!(first.contains("--first-token-timeout-seconds"), "{first}");
+    assert!(first.contains("300s"), "{first}");
+
+    let idle = timeouts.expired_message(true);
+    assert!(idle.contains("--stream-idle-timeout-seconds"), "{idle}");
+    assert!(idle.contains("20s"), "{idle}");
+}
+
+#[test]
+fn sse_keepalive_defaults_to_fifteen_seconds_and_zero_disables_it() {
+    assert_eq!(
+        StreamTimeouts::default().sse_keepalive,
+        Some(Duration::from_secs(15))
+    );
+    assert_eq!(StreamTimeouts::from_secs(60, 60).sse_keepalive, None);
+    assert_eq!(
+        StreamTimeouts::from_secs(60, 60)
+            .with_sse_keepalive(0)
+            .sse_keepalive,
+        None
+    );
+}
+
+#[tokio::test]
+async fn passthrough_sends_keepalive_comments_while_upstream_is_quiet() {
+    use http_body_util::BodyExt;
+    use tokio_util::sync::CancellationToken;
+
+    let upstream = futures_util::stream::unfold(0u8, |step| async move {
+        match step {
+            0 => Some((
+                Ok::<_, std::io::Error>(bytes::Bytes::from("data: 1\n\n")),
+                1,
+            )),
+            1 => {
+                tokio::time::sleep(Duration::from_millis(1500)).await;
+                Some((Ok(bytes::Bytes::from("data: 2\n\n")), 2))
+            }
+            _ => None,
+        }
+    });
+    let response = reqwest::Response::from(
+        http::Response::builder()
+            .body(reqwest::Body::wrap_stream(upstream))
+            .unwrap(),
+    );
+    let timeouts = StreamTimeouts::from_secs(5, 5).with_sse_keepalive(1);
+    let proxied =
+        super::handle_passthrough_streaming_response(response, CancellationToken::new(), timeouts)
+            .await
+            .unwrap();
+    let body = proxied.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(
+        std::str::from_utf8(&body).unwrap(),
+        "data: 1\n\n: keep-alive\n\ndata: 2\n\n"
+    );
+}
+
+#[test]
+fn stream_load_prefers_the_load_the_proxy_waited_for() {
+    let waited = Duration::from_secs(2);
+    assert_eq!(stream_load_duration(waited, Duration::from_secs(3)), waited);
+}
+
+#[test]
+fn slow_first_chunk_counts_as_an_on_demand_load() {
+    let first_chunk_after = Duration::from_millis(1_200);
+    assert_eq!(
+        stream_load_duration(Duration::ZERO, first_chunk_after),
+        first_chunk_after
+    );
+    assert_eq!(
+        stream_load_duration(Duration::ZERO, Duration::from_millis(40)),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn keepalive_waits_for_an_event_boundary() {
+    let mut boundary = EventBoundary::default();
+    assert!(boundary.at_boundary(), "nothing forwarded yet");
+
+    boundary.observe(b"data: {\"choices\":");
+    assert!(!boundary.at_boundary());
+    boundary.observe(b"[]}\n");
+    assert!(!boundary.at_boundary());
+    boundary.observe(b"\n");
+    assert!(boundary.at_boundary(), "blank line split across chunks");
+
+    boundary.observe(b"data: x\r\n\r\n");
+    assert!(boundary.at_boundary());
+    boundary.observe(b"data: y\n");
+    assert!(!boundary.at_boundary());
+}
@@ -0,0 +1,48 @@
+use super::*;
+
+#[test]
+fn traces_url_appends_the_otlp_traces_path() {
+    assert_eq!(
+        traces_url("http://otel-collector:4318").unwrap(),
+        "http://otel-collector:4318/v1/traces"
+    );
+    assert_eq!(
+        traces_url("https://collector.example/otlp/").unwrap(),
+        "https://collector.example/otlp/v1/traces"
+    );
+}
+
+#[test]
+fn traces_url_keeps_an_explicit_traces_path() {
+    assert_eq!(
+        traces_url("http://localhost:4318/v1/traces").unwrap(),
+        "http://localhost:4318/v1/traces"
+    );
+}
+
+#[test]
+fn traces_url_rejects_non_http_endpoints() {
+    assert!(traces_url("otel-collector:4317").is_err());
+    assert!(traces_url("grpc://otel-collector:4317").is_err());
+    assert!(traces_url("not a url").is_err());
+}
+
+#[tokio::test]
+async fn hooks_are_inert_until_initialised() {
+    assert!(!enabled());
+    let span = RequestSpan::start("POST", "/api/chat");
+    let output = span
+        .scope(async {
+            record_model("llama3");
+            event("model.load_trigger", &[("model", "llama3")]);
+            let mut child = child_span("model.resolve");
+            child.set_str("model.requested", "llama3");
+            child.set_error("not found");
+            propagate(async { 7 }).await
+        })
+        .await;
+    assert_eq!(output, 7);
+
+    let response = axum::response::IntoResponse::into_response("ok");
+    assert_eq!(span.finish(response).status(), 200);
+}